@@ -1,10 +1,15 @@
 import { NextRequest, NextResponse } from 'next/server';
 import { ExtendedHealthData } from '@/lib/health-models';
+import { PageParamError, paginate, parsePageParams } from '@/lib/pagination';
 
-// Mock database
-let diagnosticStore: ExtendedHealthData[] = [];
+// Mock database. Each entry keeps its write order, which breaks ties between
+// diagnostics with the same timestamp when paging.
+let diagnosticStore: { sequence: string; diagnostic: ExtendedHealthData }[] = [];
 
 // GET /api/diagnostics - List diagnostics
+// Paged oldest first (`order=desc` for newest first); pass `cursor` from the
+// previous page to continue.
+// Filters: from/to (diagnostic date).
 export async function GET ( request: NextRequest )
 {
     try
@@ -21,12 +26,20 @@ export async function GET ( request: NextRequest )
         }
 
         // Filter diagnostics for the patient
-        const diagnostics = diagnosticStore.filter( d => d.patientDID === patientDID );
+        const entries = diagnosticStore.filter( e => e.diagnostic.patientDID === patientDID );
+        const page = paginate( entries, e => [ e.diagnostic.timestamp, e.sequence ], parsePageParams( searchParams ) );
 
-        return NextResponse.json( diagnostics );
+        return NextResponse.json( {
+            items: page.items.map( e => e.diagnostic ),
+            nextCursor: page.nextCursor,
+        } );
 
     } catch ( error )
     {
+        if ( error instanceof PageParamError )
+        {
+            return NextResponse.json( { error: error.message }, { status: 400 } );
+        }
         console.error( 'Error fetching diagnostics:', error );
         return NextResponse.json(
             { error: 'Failed to fetch diagnostics' },
@@ -44,7 +57,9 @@ export async function POST ( request: NextRequest )
         // Validate body...
 
         // Store in memory for demo
-        diagnosticStore.push( body );
+        // Zero-padded so sequences compare in write order as strings
+        const sequence = String( diagnosticStore.length ).padStart( 12, '0' );
+        diagnosticStore.push( { sequence, diagnostic: body } );
 
         return NextResponse.json( {
             success: true,
@@ -1,5 +1,7 @@
 import { NextRequest, NextResponse } from 'next/server';
-import { InsuranceClaim, validateInsuranceClaim } from '@/lib/health-models';
+import { InsuranceClaim, InsuranceClaimSchema, validateInsuranceClaim } from '@/lib/health-models';
+import { withIdempotency } from '@/lib/idempotency';
+import { PageParamError, paginate, parsePageParams } from '@/lib/pagination';
 import { HealthDataStorageService } from '@/lib/solana-storage';
 import { InsuranceProviderDIDManager } from '@/lib/did';
 import { PublicKey } from '@solana/web3.js';
@@ -8,62 +10,19 @@ import { PublicKey } from '@solana/web3.js';
 let claimsStore: InsuranceClaim[] = [];
 
 // POST /api/insurance/claim - Submit insurance claim
+// Send an Idempotency-Key header so a retried request is not recorded twice.
 export async function POST ( request: NextRequest )
 {
   try
   {
     const body = await request.json();
-
-    // Prepare claim data with server-set fields
-    const claimData = {
-      ...body,
-      timestamp: Date.now(),
-      status: body.status || 'pending',
-    };
-
-    // Validate claim data
-    const claim: InsuranceClaim = validateInsuranceClaim( claimData );
-
-    // Verify patient DID format
-    if ( !claim.patientDID.startsWith( 'did:solana:' ) )
-    {
-      return NextResponse.json(
-        { error: 'Invalid patient DID format' },
-        { status: 400 }
-      );
-    }
-
-    // Create insurance provider DID
-    const insuranceManager = new InsuranceProviderDIDManager(
-      undefined,
-      'Primal Health Insurance',
-      'primal-health-insurance'
+    return await withIdempotency(
+      request.headers,
+      'insurance/claim',
+      body,
+      () => submitClaim( body )
     );
 
-    // Verify insurance provider DID matches
-    if ( claim.insuranceProviderDID !== insuranceManager.getDID() )
-    {
-      return NextResponse.json(
-        { error: 'Invalid insurance provider DID' },
-        { status: 400 }
-      );
-    }
-
-    // In a real app, we would verify the transaction signature on-chain here
-    // const storageService = new HealthDataStorageService();
-    // const isValid = await storageService.verifyClaimExists(claim.claimId, new PublicKey(claim.patientDID.split(':')[3]));
-
-    // Store claim in memory
-    claimsStore.push( claim );
-
-    return NextResponse.json( {
-      success: true,
-      claimId: claim.claimId,
-      transactionSignature: body.transactionSignature,
-      status: 'submitted',
-      message: 'Insurance claim submitted successfully. Awaiting verification.',
-    } );
-
   } catch ( error )
   {
     console.error( 'Error submitting insurance claim:', error );
@@ -77,7 +36,71 @@ export async function POST ( request: NextRequest )
   }
 }
 
+async function submitClaim ( body: any ): Promise<NextResponse>
+{
+  // Prepare claim data with server-set fields
+  const claimData = {
+    ...body,
+    timestamp: Date.now(),
+    status: body.status || 'pending',
+  };
+
+  // Validate claim data
+  const claim: InsuranceClaim = validateInsuranceClaim( claimData );
+
+  // Verify patient DID format
+  if ( !claim.patientDID.startsWith( 'did:solana:' ) )
+  {
+    return NextResponse.json(
+      { error: 'Invalid patient DID format' },
+      { status: 400 }
+    );
+  }
+
+  // Create insurance provider DID
+  const insuranceManager = new InsuranceProviderDIDManager(
+    undefined,
+    'Primal Health Insurance',
+    'primal-health-insurance'
+  );
+
+  // Verify insurance provider DID matches
+  if ( claim.insuranceProviderDID !== insuranceManager.getDID() )
+  {
+    return NextResponse.json(
+      { error: 'Invalid insurance provider DID' },
+      { status: 400 }
+    );
+  }
+
+  // In a real app, we would verify the transaction signature on-chain here
+  // const storageService = new HealthDataStorageService();
+  // const isValid = await storageService.verifyClaimExists(claim.claimId, new PublicKey(claim.patientDID.split(':')[3]));
+
+  if ( claimsStore.some( c => c.claimId === claim.claimId ) )
+  {
+    return NextResponse.json(
+      { error: 'Claim was already submitted', claimId: claim.claimId },
+      { status: 409 }
+    );
+  }
+
+  // Store claim in memory
+  claimsStore.push( claim );
+
+  return NextResponse.json( {
+    success: true,
+    claimId: claim.claimId,
+    transactionSignature: body.transactionSignature,
+    status: 'submitted',
+    message: 'Insurance claim submitted successfully. Awaiting verification.',
+  } );
+}
+
 // GET /api/insurance/claim - Get claim status or list claims
+// Lists are paged oldest first (`order=desc` for newest first); pass `cursor`
+// from the previous page to continue.
+// Filters: status, provider (insurance provider DID), from/to (submission date).
 export async function GET ( request: NextRequest )
 {
   try
@@ -88,9 +111,21 @@ export async function GET ( request: NextRequest )
 
     if ( patientDID )
     {
+      const status = searchParams.get( 'status' );
+      const provider = searchParams.get( 'provider' );
+      if ( status !== null && !InsuranceClaimSchema.shape.status.safeParse( status ).success )
+      {
+        return NextResponse.json( { error: 'Invalid status filter' }, { status: 400 } );
+      }
+
       // List claims for patient
-      const claims = claimsStore.filter( c => c.patientDID === patientDID );
-      return NextResponse.json( claims );
+      const claims = claimsStore.filter( c =>
+        c.patientDID === patientDID &&
+        ( status === null || c.status === status ) &&
+        ( provider === null || c.insuranceProviderDID === provider )
+      );
+      const page = paginate( claims, c => [ c.timestamp, c.claimId ], parsePageParams( searchParams ) );
+      return NextResponse.json( page );
     }
 
     if ( !claimId )
@@ -133,6 +168,10 @@ export async function GET ( request: NextRequest )
 
   } catch ( error )
   {
+    if ( error instanceof PageParamError )
+    {
+      return NextResponse.json( { error: error.message }, { status: 400 } );
+    }
     console.error( 'Error getting claim status:', error );
     return NextResponse.json(
       { error: 'Failed to get claim status' },
@@ -1,135 +1,204 @@
-import { NextRequest, NextResponse } from 'next/server';
+import { NextRequest, NextResponse, after } from 'next/server';
 import { MinimalHealthData, validateMinimalHealthData } from '@/lib/health-models';
 import { decryptHealthDataFromBlockchain, verifyDataIntegrity } from '@/lib/encryption';
-import { retrieveHealthDataFromChain, processClaimPayment, HealthDataStorageService } from '@/lib/solana-storage';
+import { retrieveHealthDataFromChain, processClaimPayment, claimAddress, HealthDataStorageService } from '@/lib/solana-storage';
 import { extractPublicKeyFromDID } from '@/lib/encryption';
 import { PublicKey, Keypair } from '@solana/web3.js';
+import { globalIdempotencyStore, withIdempotency } from '@/lib/idempotency';
+import { CallbackUrlError, deliverReceipt, parseCallbackUrl, signReceipt } from '@/lib/webhooks';
+import { rpcErrors } from '@/lib/metrics';
 
 // POST /api/insurance/verification - Verify health data and process claim
+// Send an Idempotency-Key header so a retried request cannot pay the claim twice.
+// Once the claim is paid, a signed settlement receipt is POSTed to `callbackUrl`,
+// or to SETTLEMENT_WEBHOOK_URL when the request names none. A `callbackUrl` must be
+// on a host listed in SETTLEMENT_WEBHOOK_HOSTS (or SETTLEMENT_WEBHOOK_URL's host).
 export async function POST ( request: NextRequest )
 {
   try
   {
     const body = await request.json();
-    const { claimId, patientDID, healthDataHash, encryptionKey, insuranceProviderDID } = body;
-
-    if ( !claimId || !patientDID || !healthDataHash || !encryptionKey || !insuranceProviderDID )
-    {
-      return NextResponse.json(
-        { error: 'Missing required fields' },
-        { status: 400 }
-      );
-    }
-
-    // Extract patient public key from DID
-    const patientPublicKey = extractPublicKeyFromDID( patientDID );
+    return await withIdempotency(
+      request.headers,
+      'insurance/verification',
+      body,
+      () => verifyAndPay( body )
+    );
 
-    // Retrieve encrypted health data from blockchain
-    const decryptedData = await retrieveHealthDataFromChain(
-      healthDataHash,
-      encryptionKey,
-      patientPublicKey
+  } catch ( error )
+  {
+    console.error( 'Error verifying health data:', error );
+    return NextResponse.json(
+      {
+        error: 'Failed to verify health data',
+        details: error instanceof Error ? error.message : 'Unknown error'
+      },
+      { status: 500 }
     );
+  }
+}
 
-    if ( !decryptedData )
-    {
-      return NextResponse.json(
-        { error: 'Health data not found or invalid encryption key' },
-        { status: 404 }
-      );
-    }
+async function verifyAndPay ( body: any ): Promise<NextResponse>
+{
+  const { claimId, patientDID, healthDataHash, encryptionKey, insuranceProviderDID } = body;
 
-    // Validate the decrypted data
-    const healthData: MinimalHealthData = validateMinimalHealthData( decryptedData );
+  if ( !claimId || !patientDID || !healthDataHash || !encryptionKey || !insuranceProviderDID )
+  {
+    return NextResponse.json(
+      { error: 'Missing required fields' },
+      { status: 400 }
+    );
+  }
 
-    // Verify data integrity
-    const isValid = verifyDataIntegrity( JSON.stringify( healthData ), healthDataHash );
-    if ( !isValid )
+  let callbackUrl: string | null = null;
+  try
+  {
+    const requested = body.callbackUrl ?? process.env.SETTLEMENT_WEBHOOK_URL;
+    callbackUrl = requested ? parseCallbackUrl( requested ) : null;
+  } catch ( error )
+  {
+    if ( error instanceof CallbackUrlError )
     {
-      return NextResponse.json(
-        { error: 'Data integrity verification failed' },
-        { status: 400 }
-      );
+      return NextResponse.json( { error: error.message }, { status: 400 } );
     }
+    throw error;
+  }
 
-    // Perform insurance-specific verification logic
-    const verificationResult = await performInsuranceVerification( healthData );
+  // Extract patient public key from DID
+  const patientPublicKey = extractPublicKeyFromDID( patientDID );
 
-    if ( !verificationResult.eligible )
-    {
-      return NextResponse.json( {
-        success: false,
-        claimId,
-        status: 'rejected',
-        reason: verificationResult.reason,
-        message: 'Claim does not meet insurance criteria',
-      } );
-    }
+  // Retrieve encrypted health data from blockchain
+  const decryptedData = await retrieveHealthDataFromChain(
+    healthDataHash,
+    encryptionKey,
+    patientPublicKey
+  );
 
-    // Calculate payment amount based on health data and policy
-    if ( !verificationResult.coverage )
-    {
-      return NextResponse.json( {
-        success: false,
-        claimId,
-        status: 'rejected',
-        reason: 'No coverage available for this claim',
-        message: 'Insurance claim was rejected due to coverage limitations',
-      } );
-    }
-
-    const paymentAmount = calculatePaymentAmount( healthData, { coverage: verificationResult.coverage } );
+  if ( !decryptedData )
+  {
+    return NextResponse.json(
+      { error: 'Health data not found or invalid encryption key' },
+      { status: 404 }
+    );
+  }
 
-    // In production, this would trigger the X402 payment from insurance to patient
-    // For now, we use the Solana program to process the payment
+  // Validate the decrypted data
+  const healthData: MinimalHealthData = validateMinimalHealthData( decryptedData );
 
-    // NOTE: In a real production environment, the insurance provider's private key
-    // would be securely managed (e.g., via AWS KMS, Vault, or a secure wallet service).
-    // For this demo, we generate a keypair and request an airdrop to simulate a funded wallet.
-    const providerKeypair = Keypair.generate();
+  // Verify data integrity
+  const isValid = verifyDataIntegrity( JSON.stringify( healthData ), healthDataHash );
+  if ( !isValid )
+  {
+    return NextResponse.json(
+      { error: 'Data integrity verification failed' },
+      { status: 400 }
+    );
+  }
 
-    // Fund the provider wallet for the demo
-    const storageService = new HealthDataStorageService();
-    try
-    {
-      await storageService.requestAirdrop( providerKeypair.publicKey, 2 ); // Request 2 SOL
-    } catch ( e )
-    {
-      console.warn( "Airdrop failed, payment might fail if wallet is empty", e );
-    }
+  // Perform insurance-specific verification logic
+  const verificationResult = await performInsuranceVerification( healthData );
 
-    const txSignature = await processClaimPayment(
+  if ( !verificationResult.eligible )
+  {
+    return NextResponse.json( {
+      success: false,
       claimId,
-      providerKeypair,
-      patientPublicKey
-    );
+      status: 'rejected',
+      reason: verificationResult.reason,
+      message: 'Claim does not meet insurance criteria',
+    } );
+  }
 
+  // Calculate payment amount based on health data and policy
+  if ( !verificationResult.coverage )
+  {
     return NextResponse.json( {
-      success: true,
+      success: false,
       claimId,
-      status: 'verified',
-      paymentAmount,
-      paymentTransactionId: txSignature,
-      verificationTimestamp: Date.now(),
-      message: 'Health data verified and payment processed',
-      healthDataSummary: {
-        disease: healthData.disease,
-        result: healthData.result,
-        timestamp: healthData.timestamp,
-      },
+      status: 'rejected',
+      reason: 'No coverage available for this claim',
+      message: 'Insurance claim was rejected due to coverage limitations',
     } );
+  }
 
-  } catch ( error )
+  const paymentAmount = calculatePaymentAmount( healthData, { coverage: verificationResult.coverage } );
+
+  // A claim is paid at most once, whatever idempotency key a retry carries
+  if ( !globalIdempotencyStore.beginSettlement( claimId ) )
   {
-    console.error( 'Error verifying health data:', error );
     return NextResponse.json(
       {
-        error: 'Failed to verify health data',
-        details: error instanceof Error ? error.message : 'Unknown error'
+        error: 'Claim payment was already submitted',
+        claimId,
+        paymentTransactionId: globalIdempotencyStore.settlementOf( claimId ),
       },
-      { status: 500 }
+      { status: 409 }
     );
   }
+
+  // In production, this would trigger the X402 payment from insurance to patient
+  // For now, we use the Solana program to process the payment
+
+  // NOTE: In a real production environment, the insurance provider's private key
+  // would be securely managed (e.g., via AWS KMS, Vault, or a secure wallet service).
+  // For this demo, we generate a keypair and request an airdrop to simulate a funded wallet.
+  const providerKeypair = Keypair.generate();
+
+  // Fund the provider wallet for the demo
+  const storageService = new HealthDataStorageService();
+  try
+  {
+    await storageService.requestAirdrop( providerKeypair.publicKey, 2 ); // Request 2 SOL
+  } catch ( e )
+  {
+    console.warn( "Airdrop failed, payment might fail if wallet is empty", e );
+    rpcErrors.inc( { operation: 'airdrop' } );
+  }
+
+  let txSignature: string;
+  try
+  {
+    txSignature = await processClaimPayment(
+      claimId,
+      providerKeypair,
+      patientPublicKey
+    );
+  } catch ( error )
+  {
+    globalIdempotencyStore.abandonSettlement( claimId );
+    throw error;
+  }
+  globalIdempotencyStore.recordSignature( claimId, txSignature );
+
+  const settlementReceipt = signReceipt( {
+    kind: 'claim',
+    reference: claimId,
+    transactionSignature: txSignature,
+    receiptAddress: claimAddress( claimId ).toBase58(),
+    settledAt: Date.now(),
+  } );
+  if ( callbackUrl )
+  {
+    // Delivered after the response is sent, so a slow server does not hold it up
+    const url = callbackUrl;
+    after( () => deliverReceipt( url, settlementReceipt ) );
+  }
+
+  return NextResponse.json( {
+    success: true,
+    claimId,
+    status: 'verified',
+    paymentAmount,
+    paymentTransactionId: txSignature,
+    settlementReceipt,
+    verificationTimestamp: Date.now(),
+    message: 'Health data verified and payment processed',
+    healthDataSummary: {
+      disease: healthData.disease,
+      result: healthData.result,
+      timestamp: healthData.timestamp,
+    },
+  } );
 }
 
 // Helper functions for insurance verification logic
@@ -0,0 +1,15 @@
+import { NextRequest } from 'next/server';
+import { renderMetrics } from '@/lib/metrics';
+
+// Prometheus metrics for this instance: settlement latency and outcomes, RPC
+// errors, and webhook deliveries and retries. When METRICS_TOKEN is set,
+// scrapers must send it as a bearer token.
+export async function GET(request: NextRequest) {
+  const token = process.env.METRICS_TOKEN;
+  if (token && request.headers.get('authorization') !== `Bearer ${token}`) {
+    return new Response('Unauthorized\n', { status: 401 });
+  }
+  return new Response(renderMetrics(), {
+    headers: { 'Content-Type': 'text/plain; version=0.0.4; charset=utf-8' },
+  });
+}
@@ -0,0 +1,10 @@
+import { NextResponse } from 'next/server';
+import { facilitatorKey } from '@/lib/webhooks';
+
+// The public key settlement receipts are signed with, for resource servers
+// to pin before trusting webhook deliveries
+export async function GET() {
+  return NextResponse.json({
+    publicKey: facilitatorKey().publicKey.toBase58(),
+  });
+}
@@ -49,11 +49,11 @@ export default function ClaimsPage ()
   {
     try
     {
-      const response = await fetch( `/api/diagnostics?patientDID=${ did }` );
+      const response = await fetch( `/api/diagnostics?patientDID=${ did }&order=desc` );
       if ( response.ok )
       {
         const data = await response.json();
-        setDiagnosticData( data );
+        setDiagnosticData( data.items );
       }
     } catch ( error )
     {
@@ -154,6 +154,7 @@ export default function ClaimsPage ()
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
+          'Idempotency-Key': claimId,
         },
         body: JSON.stringify( { ...claim, transactionSignature: txSignature } ),
       } );
@@ -195,6 +196,7 @@ export default function ClaimsPage ()
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
+          'Idempotency-Key': claimId,
         },
         body: JSON.stringify( {
           claimId,
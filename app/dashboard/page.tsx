@@ -28,6 +28,7 @@ export default function DashboardPage ()
 {
   const { selectedAccount, isConnected } = useSolana();
   const [ claims, setClaims ] = useState<InsuranceClaim[]>( [] );
+  const [ nextCursor, setNextCursor ] = useState<string | null>( null );
   const [ patientDID, setPatientDID ] = useState<string>( '' );
   const [ isLoading, setIsLoading ] = useState( true );
   const [ activeTab, setActiveTab ] = useState<'claims' | 'privacy'>( 'claims' );
@@ -47,15 +48,19 @@ export default function DashboardPage ()
     }
   }, [ isConnected, selectedAccount ] );
 
-  const fetchClaims = async ( did: string ) =>
+  // Newest claims first; `cursor` continues from the last page loaded
+  const fetchClaims = async ( did: string, cursor?: string ) =>
   {
     try
     {
-      const response = await fetch( `/api/insurance/claim?patientDID=${ did }` );
+      const params = new URLSearchParams( { patientDID: did, order: 'desc' } );
+      if ( cursor ) params.set( 'cursor', cursor );
+      const response = await fetch( `/api/insurance/claim?${ params }` );
       if ( response.ok )
       {
         const data = await response.json();
-        setClaims( data );
+        setClaims( loaded => cursor ? [ ...loaded, ...data.items ] : data.items );
+        setNextCursor( data.nextCursor );
       }
     } catch ( error )
     {
@@ -284,6 +289,17 @@ export default function DashboardPage ()
                     </table>
                   </div>
 
+                  { nextCursor && (
+                    <div className="text-center py-4 border-t border-gray-200">
+                      <button
+                        onClick={ () => fetchClaims( patientDID, nextCursor ) }
+                        className="px-4 py-2 text-sm font-medium text-blue-600 hover:text-blue-800"
+                      >
+                        Load more claims
+                      </button>
+                    </div>
+                  ) }
+
                   { claims.length === 0 && (
                     <div className="text-center py-12">
                       <div className="text-4xl mb-4">📋</div>
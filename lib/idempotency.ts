@@ -0,0 +1,158 @@
+import { createHash } from 'crypto';
+import { NextResponse } from 'next/server';
+
+// Idempotency keys for the write APIs that submit transactions
+
+export const IDEMPOTENCY_HEADER = 'Idempotency-Key';
+export const MAX_KEY_LENGTH = 255;
+// Keys are forgotten a day after their first use
+export const KEY_TTL_MS = 24 * 60 * 60 * 1000;
+
+interface StoredResponse {
+  status: number;
+  body: unknown;
+}
+
+interface Entry {
+  // Hash of the request body, so a key cannot be reused for another request
+  fingerprint: string;
+  createdAt: number;
+  // Unset while the first request with the key is still running
+  response?: StoredResponse;
+}
+
+export class IdempotencyStore {
+  private entries = new Map<string, Entry>();
+  // Settlement signatures by claim, kept after their keys expire
+  private settlements = new Map<string, string | null>();
+
+  constructor(private ttlMs: number = KEY_TTL_MS) {}
+
+  private prune(now: number) {
+    for (const [key, entry] of this.entries) {
+      if (now - entry.createdAt > this.ttlMs) {
+        this.entries.delete(key);
+      }
+    }
+  }
+
+  // Claims `key` for a request. Returns the stored response of an earlier
+  // request with the same key and body, or a 409/422 response if that request
+  // is still running or had a different body. Returns null when the caller
+  // should go ahead.
+  begin(key: string, fingerprint: string, now: number = Date.now()): NextResponse | null {
+    this.prune(now);
+    const entry = this.entries.get(key);
+    if (!entry) {
+      this.entries.set(key, { fingerprint, createdAt: now });
+      return null;
+    }
+    if (entry.fingerprint !== fingerprint) {
+      return NextResponse.json(
+        { error: 'Idempotency key was already used with a different request' },
+        { status: 422 }
+      );
+    }
+    if (!entry.response) {
+      return NextResponse.json(
+        { error: 'A request with this idempotency key is still in progress' },
+        { status: 409 }
+      );
+    }
+    return NextResponse.json(entry.response.body, {
+      status: entry.response.status,
+      headers: { 'Idempotent-Replayed': 'true' },
+    });
+  }
+
+  complete(key: string, response: StoredResponse) {
+    const entry = this.entries.get(key);
+    if (entry) {
+      entry.response = response;
+    }
+  }
+
+  // Releases a key whose request failed without submitting anything, so
+  // a retry runs it again
+  release(key: string) {
+    this.entries.delete(key);
+  }
+
+  // Marks a claim's settlement as started. Returns false if it already was,
+  // so concurrent requests for one claim submit a single transaction.
+  beginSettlement(claimId: string): boolean {
+    if (this.settlements.has(claimId)) return false;
+    this.settlements.set(claimId, null);
+    return true;
+  }
+
+  recordSignature(claimId: string, signature: string) {
+    this.settlements.set(claimId, signature);
+  }
+
+  // Forgets a settlement whose submission failed, so it can be tried again
+  abandonSettlement(claimId: string) {
+    if (this.settlements.get(claimId) === null) {
+      this.settlements.delete(claimId);
+    }
+  }
+
+  // The claim's settlement signature, null while it is being submitted, or
+  // undefined if it was never started
+  settlementOf(claimId: string): string | null | undefined {
+    return this.settlements.get(claimId);
+  }
+}
+
+export function fingerprint(body: unknown): string {
+  return createHash('sha256').update(JSON.stringify(body)).digest('hex');
+}
+
+// Reads the idempotency key of a request. Returns a 400 response for a key
+// that is present but empty or too long.
+export function idempotencyKey(headers: Headers): string | null | NextResponse {
+  const key = headers.get(IDEMPOTENCY_HEADER);
+  if (key === null) return null;
+  if (key.length === 0 || key.length > MAX_KEY_LENGTH) {
+    return NextResponse.json(
+      { error: `${IDEMPOTENCY_HEADER} must be 1 to ${MAX_KEY_LENGTH} characters` },
+      { status: 400 }
+    );
+  }
+  return key;
+}
+
+// Shared by the API routes
+export const globalIdempotencyStore = new IdempotencyStore();
+
+// Runs `handler` once per idempotency key. Keys are scoped to `scope`, so the
+// same key sent to two routes is two requests. Responses below 500 are kept and
+// replayed to retries; a 500 releases the key so the request can be retried.
+// Requests without a key run every time.
+export async function withIdempotency(
+  headers: Headers,
+  scope: string,
+  body: unknown,
+  handler: () => Promise<NextResponse>,
+  store: IdempotencyStore = globalIdempotencyStore
+): Promise<NextResponse> {
+  const key = idempotencyKey(headers);
+  if (key instanceof NextResponse) return key;
+  if (key === null) return handler();
+
+  const scoped = `${scope}:${key}`;
+  const earlier = store.begin(scoped, fingerprint(body));
+  if (earlier) return earlier;
+  try {
+    const response = await handler();
+    if (response.status >= 500) {
+      store.release(scoped);
+    } else {
+      store.complete(scoped, { status: response.status, body: await response.clone().json() });
+    }
+    return response;
+  } catch (error) {
+    store.release(scoped);
+    throw error;
+  }
+}
@@ -0,0 +1,122 @@
+// Prometheus metrics for the webhook and settlement paths, served in the text
+// exposition format by /api/metrics. Kept per process, like the idempotency
+// store, so each instance is scraped separately.
+
+export type Labels = Record<string, string>;
+
+// Upper bounds of the latency histograms' buckets, in seconds
+export const LATENCY_BUCKETS = [0.1, 0.25, 0.5, 1, 2.5, 5, 10, 30, 60];
+
+function labelKey(labels: Labels): string {
+  const pairs = Object.keys(labels)
+    .sort()
+    .map((name) => `${name}="${labels[name].replace(/\\/g, '\\\\').replace(/"/g, '\\"').replace(/\n/g, '\\n')}"`);
+  return pairs.join(',');
+}
+
+function series(name: string, key: string, extra?: string): string {
+  const labels = [key, extra].filter(Boolean).join(',');
+  return labels ? `${name}{${labels}}` : name;
+}
+
+interface Metric {
+  render(): string[];
+}
+
+export class Counter implements Metric {
+  private values = new Map<string, number>();
+
+  constructor(readonly name: string, readonly help: string) {}
+
+  inc(labels: Labels = {}, by: number = 1) {
+    const key = labelKey(labels);
+    this.values.set(key, (this.values.get(key) ?? 0) + by);
+  }
+
+  render(): string[] {
+    const lines = [`# HELP ${this.name} ${this.help}`, `# TYPE ${this.name} counter`];
+    for (const [key, value] of this.values) {
+      lines.push(`${series(this.name, key)} ${value}`);
+    }
+    return lines;
+  }
+}
+
+interface Observations {
+  // Cumulative, one per bucket
+  buckets: number[];
+  sum: number;
+  count: number;
+}
+
+export class Histogram implements Metric {
+  private values = new Map<string, Observations>();
+
+  constructor(
+    readonly name: string,
+    readonly help: string,
+    readonly buckets: number[] = LATENCY_BUCKETS
+  ) {}
+
+  observe(value: number, labels: Labels = {}) {
+    const key = labelKey(labels);
+    const observations = this.values.get(key) ?? { buckets: this.buckets.map(() => 0), sum: 0, count: 0 };
+    this.values.set(key, observations);
+    this.buckets.forEach((bound, i) => {
+      if (value <= bound) observations.buckets[i]++;
+    });
+    observations.sum += value;
+    observations.count++;
+  }
+
+  // Runs `operation` and records how long it took in seconds, whether or not it succeeded
+  async time<T>(labels: Labels, operation: () => Promise<T>): Promise<T> {
+    const start = performance.now();
+    try {
+      return await operation();
+    } finally {
+      this.observe((performance.now() - start) / 1000, labels);
+    }
+  }
+
+  render(): string[] {
+    const lines = [`# HELP ${this.name} ${this.help}`, `# TYPE ${this.name} histogram`];
+    for (const [key, { buckets, sum, count }] of this.values) {
+      this.buckets.forEach((bound, i) => {
+        lines.push(`${series(`${this.name}_bucket`, key, `le="${bound}"`)} ${buckets[i]}`);
+      });
+      lines.push(`${series(`${this.name}_bucket`, key, 'le="+Inf"')} ${count}`);
+      lines.push(`${series(`${this.name}_sum`, key)} ${sum}`);
+      lines.push(`${series(`${this.name}_count`, key)} ${count}`);
+    }
+    return lines;
+  }
+}
+
+export const webhookDeliveries = new Counter(
+  'primal_health_webhook_deliveries_total',
+  'Settlement receipt deliveries by outcome (delivered, refused, failed, blocked, unresolved)'
+);
+export const webhookRetries = new Counter(
+  'primal_health_webhook_retries_total',
+  'Settlement receipt delivery attempts after the first'
+);
+export const settlementLatency = new Histogram(
+  'primal_health_settlement_duration_seconds',
+  'Time to submit and confirm a settlement transaction'
+);
+export const settlements = new Counter(
+  'primal_health_settlements_total',
+  'Settlements by kind and outcome (settled, failed)'
+);
+export const rpcErrors = new Counter(
+  'primal_health_rpc_errors_total',
+  'Solana RPC calls that failed, by operation'
+);
+
+const REGISTRY: Metric[] = [webhookDeliveries, webhookRetries, settlementLatency, settlements, rpcErrors];
+
+// Every metric in the Prometheus text exposition format
+export function renderMetrics(): string {
+  return REGISTRY.flatMap((metric) => metric.render()).join('\n') + '\n';
+}
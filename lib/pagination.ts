@@ -0,0 +1,114 @@
+// Cursor pagination for the list APIs
+
+export const DEFAULT_PAGE_SIZE = 50;
+export const MAX_PAGE_SIZE = 200;
+
+// Orders items by timestamp, with a unique tiebreaker so items written in
+// the same millisecond keep their order between requests
+export type OrderKey = [timestamp: number, tiebreaker: string];
+
+export interface Page<T> {
+  items: T[];
+  // Pass as `cursor` to get the next page; null on the last page
+  nextCursor: string | null;
+}
+
+export interface PageParams {
+  cursor: OrderKey | null;
+  limit: number;
+  newestFirst: boolean;
+  from: number | null;
+  to: number | null;
+}
+
+export class PageParamError extends Error {}
+
+export function encodeCursor(key: OrderKey): string {
+  return Buffer.from(JSON.stringify(key)).toString('base64url');
+}
+
+export function decodeCursor(cursor: string): OrderKey {
+  try {
+    const key = JSON.parse(Buffer.from(cursor, 'base64url').toString());
+    if (
+      Array.isArray(key) &&
+      key.length === 2 &&
+      Number.isInteger(key[0]) &&
+      typeof key[1] === 'string'
+    ) {
+      return [key[0], key[1]];
+    }
+  } catch {
+    // Falls through to the error below
+  }
+  throw new PageParamError('Invalid cursor');
+}
+
+function parseTimestamp(value: string | null, name: string): number | null {
+  if (value === null) return null;
+  const timestamp = /^\d+$/.test(value) ? Number(value) : Date.parse(value);
+  if (!Number.isFinite(timestamp)) {
+    throw new PageParamError(`Invalid ${name} date`);
+  }
+  return timestamp;
+}
+
+// Reads `cursor`, `limit`, `order` (`asc`, the default, or `desc`) and the
+// `from`/`to` date range (inclusive, as milliseconds or ISO dates) from a
+// request's query string
+export function parsePageParams(searchParams: URLSearchParams): PageParams {
+  const cursor = searchParams.get('cursor');
+  const limitParam = searchParams.get('limit');
+  let limit = DEFAULT_PAGE_SIZE;
+  if (limitParam !== null) {
+    limit = Number(limitParam);
+    if (!Number.isInteger(limit) || limit < 1 || limit > MAX_PAGE_SIZE) {
+      throw new PageParamError(`limit must be between 1 and ${MAX_PAGE_SIZE}`);
+    }
+  }
+  const order = searchParams.get('order') ?? 'asc';
+  if (order !== 'asc' && order !== 'desc') {
+    throw new PageParamError('order must be asc or desc');
+  }
+  const from = parseTimestamp(searchParams.get('from'), 'from');
+  const to = parseTimestamp(searchParams.get('to'), 'to');
+  if (from !== null && to !== null && from > to) {
+    throw new PageParamError('from must not be after to');
+  }
+  return {
+    cursor: cursor ? decodeCursor(cursor) : null,
+    limit,
+    newestFirst: order === 'desc',
+    from,
+    to,
+  };
+}
+
+function compareKeys(a: OrderKey, b: OrderKey): number {
+  if (a[0] !== b[0]) return a[0] - b[0];
+  return a[1] < b[1] ? -1 : a[1] > b[1] ? 1 : 0;
+}
+
+// Returns the page of `items` after the cursor. Items written after a client
+// started paging never shift the ones it has already seen: oldest first they
+// show up on a later page, newest first they are skipped until it starts over.
+export function paginate<T>(
+  items: T[],
+  keyOf: (item: T) => OrderKey,
+  params: PageParams
+): Page<T> {
+  const { cursor, limit, newestFirst, from, to } = params;
+  const direction = newestFirst ? -1 : 1;
+  const keyed = items
+    .map((item) => ({ item, key: keyOf(item) }))
+    .filter(({ key }) => from === null || key[0] >= from)
+    .filter(({ key }) => to === null || key[0] <= to)
+    .filter(({ key }) => cursor === null || direction * compareKeys(key, cursor) > 0)
+    .sort((a, b) => direction * compareKeys(a.key, b.key));
+  const page = keyed.slice(0, limit);
+  const more = keyed.length > limit;
+  return {
+    items: page.map(({ item }) => item),
+    nextCursor: more ? encodeCursor(page[page.length - 1].key) : null,
+  };
+}
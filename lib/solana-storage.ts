@@ -11,6 +11,7 @@ import
 import { Program, AnchorProvider, Idl, BN, Wallet } from '@coral-xyz/anchor';
 import { encryptHealthDataForBlockchain, decryptHealthDataFromBlockchain, hashHealthData } from './encryption';
 import { MinimalHealthData, InsuranceClaim } from './health-models';
+import { rpcErrors, settlementLatency, settlements } from './metrics';
 
 // Solana configuration
 const SOLANA_RPC_URL = process.env.NEXT_PUBLIC_SOLANA_RPC_URL || 'https://api.devnet.solana.com';
@@ -875,6 +876,16 @@ export async function submitClaimToChain (
   return service.submitInsuranceClaim( claim, wallet, providerPubkey );
 }
 
+// Address of the claim account for `claimId`, which records its payment
+export function claimAddress ( claimId: string ): PublicKey
+{
+  const [ claimPDA ] = PublicKey.findProgramAddressSync(
+    [ Buffer.from( "claim" ), Buffer.from( claimId ) ],
+    PROGRAM_ID
+  );
+  return claimPDA;
+}
+
 export async function processClaimPayment (
   claimId: string,
   wallet: Wallet,
@@ -882,5 +893,17 @@ export async function processClaimPayment (
 ): Promise<string>
 {
   const service = await createHealthDataStorageService();
-  return service.processPayment( claimId, wallet, patientPubkey );
+  try
+  {
+    const signature = await settlementLatency.time( { kind: 'claim' }, () =>
+      service.processPayment( claimId, wallet, patientPubkey )
+    );
+    settlements.inc( { kind: 'claim', outcome: 'settled' } );
+    return signature;
+  } catch ( error )
+  {
+    settlements.inc( { kind: 'claim', outcome: 'failed' } );
+    rpcErrors.inc( { operation: 'process_payment' } );
+    throw error;
+  }
 }
@@ -0,0 +1,227 @@
+import { lookup } from 'node:dns/promises';
+import { isIP } from 'node:net';
+import bs58 from 'bs58';
+import nacl from 'tweetnacl';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { webhookDeliveries, webhookRetries } from './metrics';
+
+// Signed settlement receipts, POSTed to a resource server's callback URL once
+// a claim or x402 payment settles so it can grant access without polling
+
+export const MAX_ATTEMPTS = 3;
+// Doubled after each failed attempt
+export const RETRY_DELAY_MS = 1000;
+
+export type SettlementKind = 'claim' | 'x402';
+
+export interface SettlementReceipt {
+  kind: SettlementKind;
+  // The claim ID, or the path of the x402 resource paid for
+  reference: string;
+  transactionSignature: string;
+  // The program account the settlement is recorded in, such as the claim PDA
+  receiptAddress: string;
+  // The facilitator key the receipt is signed with
+  facilitator: string;
+  settledAt: number;
+}
+
+export interface SignedReceipt {
+  receipt: SettlementReceipt;
+  // Base58 ed25519 signature over receiptMessage(receipt)
+  signature: string;
+}
+
+let facilitatorKeypair: Keypair | null = null;
+
+// The key receipts are signed with, from FACILITATOR_SECRET_KEY (a base58
+// secret key). Without it a key is generated per process, which servers
+// cannot pin, so set it in any real deployment.
+export function facilitatorKey(): Keypair {
+  if (!facilitatorKeypair) {
+    const secret = process.env.FACILITATOR_SECRET_KEY;
+    if (secret) {
+      facilitatorKeypair = Keypair.fromSecretKey(bs58.decode(secret));
+    } else {
+      console.warn('FACILITATOR_SECRET_KEY is not set; signing receipts with a throwaway key');
+      facilitatorKeypair = Keypair.generate();
+    }
+  }
+  return facilitatorKeypair;
+}
+
+// The signed bytes: the receipt's fields as JSON in a fixed order, so a server
+// that re-serializes the parsed receipt gets the same bytes
+export function receiptMessage(receipt: SettlementReceipt): Uint8Array {
+  const { kind, reference, transactionSignature, receiptAddress, facilitator, settledAt } = receipt;
+  return new TextEncoder().encode(
+    JSON.stringify({ kind, reference, transactionSignature, receiptAddress, facilitator, settledAt })
+  );
+}
+
+export function signReceipt(
+  fields: Omit<SettlementReceipt, 'facilitator'>,
+  key: Keypair = facilitatorKey()
+): SignedReceipt {
+  const receipt = { ...fields, facilitator: key.publicKey.toBase58() };
+  const signature = nacl.sign.detached(receiptMessage(receipt), key.secretKey);
+  return { receipt, signature: bs58.encode(signature) };
+}
+
+// Checks a delivered receipt against the facilitator key the server trusts
+export function verifyReceipt(signed: SignedReceipt, facilitator: PublicKey): boolean {
+  if (signed.receipt.facilitator !== facilitator.toBase58()) return false;
+  try {
+    return nacl.sign.detached.verify(
+      receiptMessage(signed.receipt),
+      bs58.decode(signed.signature),
+      facilitator.toBytes()
+    );
+  } catch {
+    return false;
+  }
+}
+
+export class CallbackUrlError extends Error {}
+
+// Hosts receipts may be delivered to: SETTLEMENT_WEBHOOK_HOSTS (comma
+// separated) plus the host of SETTLEMENT_WEBHOOK_URL
+export function callbackHosts(): string[] {
+  const hosts = (process.env.SETTLEMENT_WEBHOOK_HOSTS ?? '')
+    .split(',')
+    .map((host) => host.trim().toLowerCase())
+    .filter(Boolean);
+  const fallback = process.env.SETTLEMENT_WEBHOOK_URL;
+  if (fallback) {
+    try {
+      hosts.push(new URL(fallback).hostname.toLowerCase());
+    } catch {
+      // An unparseable fallback is rejected when it is used
+    }
+  }
+  return hosts;
+}
+
+function ipv4Internal(address: string): boolean {
+  const [a, b] = address.split('.').map(Number);
+  return (
+    a === 0 ||
+    a === 10 ||
+    a === 127 ||
+    (a === 100 && b >= 64 && b < 128) ||
+    (a === 169 && b === 254) ||
+    (a === 172 && b >= 16 && b < 32) ||
+    (a === 192 && b === 168) ||
+    a >= 224
+  );
+}
+
+// Whether an IP address is loopback, private, link-local or otherwise not a
+// public unicast address. Names that are not IP literals return false.
+export function isInternalAddress(address: string): boolean {
+  const ip = address.replace(/^\[|\]$/g, '').toLowerCase();
+  switch (isIP(ip)) {
+    case 4:
+      return ipv4Internal(ip);
+    case 6: {
+      const mapped = ip.match(/^::ffff:(\d+\.\d+\.\d+\.\d+)$/);
+      if (mapped) return ipv4Internal(mapped[1]);
+      return (
+        ip === '::' ||
+        ip === '::1' ||
+        ip.startsWith('fc') ||
+        ip.startsWith('fd') ||
+        /^fe[89ab]/.test(ip) ||
+        ip.startsWith('ff')
+      );
+    }
+    default:
+      return false;
+  }
+}
+
+function internalHost(hostname: string): boolean {
+  return hostname === 'localhost' || hostname.endsWith('.localhost') || isInternalAddress(hostname);
+}
+
+// Reads a callback URL, which must be absolute http or https, on one of
+// `allowedHosts` and not on a loopback, private or link-local address
+export function parseCallbackUrl(value: unknown, allowedHosts: string[] = callbackHosts()): string {
+  let url: URL | null = null;
+  if (typeof value === 'string') {
+    try {
+      url = new URL(value);
+    } catch {
+      // Falls through to the error below
+    }
+  }
+  if (!url || (url.protocol !== 'https:' && url.protocol !== 'http:')) {
+    throw new CallbackUrlError('callbackUrl must be an absolute http or https URL');
+  }
+  const hostname = url.hostname.toLowerCase();
+  if (internalHost(hostname)) {
+    throw new CallbackUrlError('callbackUrl must not point at a loopback, private or link-local address');
+  }
+  if (!allowedHosts.includes(hostname)) {
+    throw new CallbackUrlError(`callbackUrl host ${hostname} is not in SETTLEMENT_WEBHOOK_HOSTS`);
+  }
+  return url.toString();
+}
+
+export type ResolveHost = (hostname: string) => Promise<string[]>;
+
+const resolveHost: ResolveHost = async (hostname) =>
+  (await lookup(hostname, { all: true })).map((entry) => entry.address);
+
+// POSTs the receipt to `url`, retrying network errors and 5xx responses.
+// Returns whether the server accepted it. The host is resolved first and
+// delivery refused if any address is internal, so a public name cannot be
+// pointed at the local network; redirects are not followed for the same reason.
+export async function deliverReceipt(
+  url: string,
+  signed: SignedReceipt,
+  fetchImpl: typeof fetch = fetch,
+  resolveImpl: ResolveHost = resolveHost
+): Promise<boolean> {
+  const { hostname } = new URL(url);
+  try {
+    const addresses = isIP(hostname.replace(/^\[|\]$/g, '')) ? [hostname] : await resolveImpl(hostname);
+    if (addresses.length === 0 || addresses.some(isInternalAddress)) {
+      console.error(`Settlement webhook ${url} resolves to an internal address; not delivering`);
+      webhookDeliveries.inc({ outcome: 'blocked' });
+      return false;
+    }
+  } catch (error) {
+    console.error(`Settlement webhook ${url} could not be resolved:`, error);
+    webhookDeliveries.inc({ outcome: 'unresolved' });
+    return false;
+  }
+  for (let attempt = 0; attempt < MAX_ATTEMPTS; attempt++) {
+    if (attempt > 0) {
+      webhookRetries.inc();
+      await new Promise((resolve) => setTimeout(resolve, RETRY_DELAY_MS * 2 ** (attempt - 1)));
+    }
+    try {
+      const response = await fetchImpl(url, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(signed),
+        redirect: 'manual',
+      });
+      if (response.ok) {
+        webhookDeliveries.inc({ outcome: 'delivered' });
+        return true;
+      }
+      if (response.status < 500) {
+        console.warn(`Settlement webhook ${url} refused the receipt with ${response.status}`);
+        webhookDeliveries.inc({ outcome: 'refused' });
+        return false;
+      }
+    } catch (error) {
+      console.warn(`Settlement webhook ${url} failed:`, error);
+    }
+  }
+  console.error(`Gave up delivering the settlement receipt to ${url}`);
+  webhookDeliveries.inc({ outcome: 'failed' });
+  return false;
+}
@@ -0,0 +1,165 @@
+//! Encrypted, signed exports of a patient's accounts for data portability.
+//!
+//! An [`Export`] lists raw account data by address. [`seal_archive`] encrypts
+//! its JSON under a fresh [`RecordKey`] with ChaCha20-Poly1305 and wraps that
+//! key to the recipient's X25519 key the same way grants wrap record keys.
+//! The exporter then signs [`SealedArchive::signing_message`] with their
+//! wallet, so anyone can check who produced an archive without decrypting it.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand_core::CryptoRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{unwrap_record_key, wrap_record_key, CryptoError, RecordKey};
+
+pub const ARCHIVE_VERSION: u8 = 1;
+
+const ARCHIVE_AAD: &[u8] = b"primal-health archive v1";
+const SIGNING_DOMAIN: &[u8] = b"primal-health archive signature v1";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("unsupported archive version {0}")]
+    UnsupportedVersion(u8),
+    #[error("archive field is not valid base64")]
+    InvalidEncoding,
+    #[error("archive is labeled for a different patient than it contains")]
+    PatientMismatch,
+    #[error("archive contents are malformed: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+}
+
+/// One account as it was on chain when exported.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedAccount {
+    /// The account type, such as `ClaimAccount`.
+    pub kind: String,
+    /// Base58 address.
+    pub address: String,
+    /// Base64 of the full account data, discriminator included.
+    pub data: String,
+}
+
+/// Everything exported for one patient.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Export {
+    /// Base58 wallet address of the patient.
+    pub patient: String,
+    /// Slot the accounts were read at.
+    pub slot: u64,
+    pub accounts: Vec<ExportedAccount>,
+}
+
+/// An encrypted export as written to disk. Every binary field is base64.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedArchive {
+    pub version: u8,
+    pub patient: String,
+    /// The archive key wrapped to the recipient.
+    pub key_envelope: String,
+    /// `nonce || ciphertext` of the export's JSON.
+    pub ciphertext: String,
+    /// Base58 address of the signing wallet; empty until signed.
+    pub signer: String,
+    /// Base58 ed25519 signature over `signing_message`; empty until signed.
+    pub signature: String,
+}
+
+impl SealedArchive {
+    /// The bytes the exporter signs: a hash over everything but the
+    /// signature fields, so re-encoding cannot change what was signed.
+    pub fn signing_message(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for part in [
+            SIGNING_DOMAIN,
+            &[self.version],
+            self.patient.as_bytes(),
+            self.key_envelope.as_bytes(),
+            self.ciphertext.as_bytes(),
+        ] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Encrypts `export` to `recipient`'s X25519 public key. The result still
+/// needs the exporter's signature.
+pub fn seal_archive(
+    export: &Export,
+    recipient: &[u8; 32],
+    rng: &mut impl CryptoRng,
+) -> Result<SealedArchive, ArchiveError> {
+    let key = RecordKey::generate(rng);
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let plaintext = serde_json::to_vec(export)?;
+    let ciphertext = ChaCha20Poly1305::new(&key.0.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: ARCHIVE_AAD,
+            },
+        )
+        .expect("ChaCha20-Poly1305 only fails on inputs of 256 GiB or more");
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(SealedArchive {
+        version: ARCHIVE_VERSION,
+        patient: export.patient.clone(),
+        key_envelope: STANDARD.encode(wrap_record_key(&key, recipient, rng)?),
+        ciphertext: STANDARD.encode(sealed),
+        signer: String::new(),
+        signature: String::new(),
+    })
+}
+
+/// Decrypts an archive with the recipient's X25519 secret key. The signature
+/// is not checked here.
+pub fn open_archive(
+    archive: &SealedArchive,
+    recipient_secret: &[u8; 32],
+) -> Result<Export, ArchiveError> {
+    if archive.version != ARCHIVE_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(archive.version));
+    }
+    let envelope = decode(&archive.key_envelope)?;
+    let sealed = decode(&archive.ciphertext)?;
+    if sealed.len() < NONCE_LEN {
+        return Err(ArchiveError::InvalidEncoding);
+    }
+
+    let key = unwrap_record_key(&envelope, recipient_secret)?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let plaintext = ChaCha20Poly1305::new(&key.0.into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: ARCHIVE_AAD,
+            },
+        )
+        .map_err(|_| CryptoError::Decryption)?;
+    let export: Export = serde_json::from_slice(&plaintext)?;
+    // The outer patient is signed but not encrypted; it must not disagree
+    if export.patient != archive.patient {
+        return Err(ArchiveError::PatientMismatch);
+    }
+    Ok(export)
+}
+
+fn decode(field: &str) -> Result<Vec<u8>, ArchiveError> {
+    STANDARD
+        .decode(field)
+        .map_err(|_| ArchiveError::InvalidEncoding)
+}
@@ -0,0 +1,227 @@
+//! Encryption at rest for columns an indexer decodes from program accounts.
+//!
+//! The chain only holds hashes and ciphertext, but the values an indexer
+//! decodes, such as DID strings, note hashes and field commitments, still tie
+//! rows to people. Downstream databases are often less tightly controlled
+//! than the chain, so an indexer can store those columns sealed instead.
+//! [`seal_column`] encrypts a value with ChaCha20-Poly1305 under a data key
+//! from a [`KeyProvider`], which in production wraps a KMS. The column name
+//! and row are bound in as associated data, so a value copied into another
+//! row or column does not open. [`blind_index`] gives a keyed HMAC-SHA256 of a
+//! value for equality lookups, such as finding a patient's rows by DID,
+//! without storing the DID.
+//!
+//! A sealed column is the standard base64 of
+//! `version || key_id_len || key_id || nonce || ciphertext`. Carrying the key
+//! id lets old rows open after the provider moves new writes to another key.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use hmac::{Hmac, Mac};
+use rand_core::CryptoRng;
+use sha2::Sha256;
+
+const COLUMN_VERSION: u8 = 1;
+const COLUMN_AAD: &[u8] = b"primal-health column v1";
+const NONCE_LEN: usize = 12;
+/// Longest key id a sealed column can carry.
+pub const MAX_KEY_ID_LEN: usize = u8::MAX as usize;
+
+/// Columns [`seal_column`] is meant for. Indexers may seal others too.
+pub const SENSITIVE_COLUMNS: &[&str] = &["did", "note_hash", "field_commitment"];
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum AtRestError {
+    #[error("no data key with id {0:?}")]
+    UnknownKey(String),
+    #[error("key id is {0} bytes, over the limit of {MAX_KEY_ID_LEN}")]
+    KeyIdTooLong(usize),
+    #[error("sealed column is not valid base64 or is truncated")]
+    InvalidEncoding,
+    #[error("unsupported column version {0}")]
+    UnsupportedVersion(u8),
+    #[error("decryption failed")]
+    Decryption,
+}
+
+/// A 256-bit data key, as a KMS returns it after unwrapping.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DataKey(pub [u8; 32]);
+
+/// Source of data keys, normally a KMS client holding the unwrapped keys.
+pub trait KeyProvider {
+    /// The id of the key new values are sealed under.
+    fn current_key_id(&self) -> &str;
+    /// The data key with `key_id`, if the provider still has it.
+    fn data_key(&self, key_id: &str) -> Option<DataKey>;
+}
+
+/// Keys held in memory, for tests and for deployments that load their keys
+/// from a KMS at startup.
+pub struct StaticKeys {
+    current: String,
+    keys: Vec<(String, DataKey)>,
+}
+
+impl StaticKeys {
+    pub fn new(key_id: impl Into<String>, key: DataKey) -> Self {
+        let current = key_id.into();
+        StaticKeys {
+            keys: vec![(current.clone(), key)],
+            current,
+        }
+    }
+
+    /// Adds `key` and seals new values under it. Earlier keys still open the
+    /// values sealed under them.
+    pub fn rotate(&mut self, key_id: impl Into<String>, key: DataKey) {
+        self.current = key_id.into();
+        self.keys.retain(|(id, _)| *id != self.current);
+        self.keys.push((self.current.clone(), key));
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn current_key_id(&self) -> &str {
+        &self.current
+    }
+
+    fn data_key(&self, key_id: &str) -> Option<DataKey> {
+        self.keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, key)| key.clone())
+    }
+}
+
+fn aad(column: &str, row: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(COLUMN_AAD.len() + 8 + column.len() + row.len());
+    aad.extend_from_slice(COLUMN_AAD);
+    aad.extend_from_slice(&(column.len() as u32).to_le_bytes());
+    aad.extend_from_slice(column.as_bytes());
+    aad.extend_from_slice(&(row.len() as u32).to_le_bytes());
+    aad.extend_from_slice(row);
+    aad
+}
+
+/// Encrypts `value` for storage in `column` of the row keyed by `row`,
+/// usually the account address, under the provider's current key.
+pub fn seal_column(
+    keys: &impl KeyProvider,
+    column: &str,
+    row: &[u8],
+    value: &[u8],
+    rng: &mut impl CryptoRng,
+) -> Result<String, AtRestError> {
+    let key_id = keys.current_key_id();
+    if key_id.len() > MAX_KEY_ID_LEN {
+        return Err(AtRestError::KeyIdTooLong(key_id.len()));
+    }
+    let key = keys
+        .data_key(key_id)
+        .ok_or_else(|| AtRestError::UnknownKey(key_id.to_string()))?;
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(&key.0.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: value,
+                aad: &aad(column, row),
+            },
+        )
+        .expect("ChaCha20-Poly1305 only fails on inputs of 256 GiB or more");
+
+    let mut bytes = Vec::with_capacity(2 + key_id.len() + NONCE_LEN + ciphertext.len());
+    bytes.push(COLUMN_VERSION);
+    bytes.push(key_id.len() as u8);
+    bytes.extend_from_slice(key_id.as_bytes());
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Decrypts a value [`seal_column`] sealed for the same column and row.
+pub fn open_column(
+    keys: &impl KeyProvider,
+    column: &str,
+    row: &[u8],
+    sealed: &str,
+) -> Result<Vec<u8>, AtRestError> {
+    let bytes = STANDARD
+        .decode(sealed)
+        .map_err(|_| AtRestError::InvalidEncoding)?;
+    let [version, key_id_len, rest @ ..] = bytes.as_slice() else {
+        return Err(AtRestError::InvalidEncoding);
+    };
+    if *version != COLUMN_VERSION {
+        return Err(AtRestError::UnsupportedVersion(*version));
+    }
+    let key_id_len = usize::from(*key_id_len);
+    if rest.len() < key_id_len + NONCE_LEN {
+        return Err(AtRestError::InvalidEncoding);
+    }
+    let (key_id, rest) = rest.split_at(key_id_len);
+    let key_id = std::str::from_utf8(key_id).map_err(|_| AtRestError::InvalidEncoding)?;
+    let key = keys
+        .data_key(key_id)
+        .ok_or_else(|| AtRestError::UnknownKey(key_id.to_string()))?;
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(&key.0.into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &aad(column, row),
+            },
+        )
+        .map_err(|_| AtRestError::Decryption)
+}
+
+/// Whether an indexer stores sensitive columns sealed or as decoded. Values
+/// are strings either way, so the choice does not change the schema.
+pub enum ColumnStorage<K> {
+    Plaintext,
+    Sealed(K),
+}
+
+impl<K: KeyProvider> ColumnStorage<K> {
+    /// The value to store in `column` of `row`.
+    pub fn encode(
+        &self,
+        column: &str,
+        row: &[u8],
+        value: &str,
+        rng: &mut impl CryptoRng,
+    ) -> Result<String, AtRestError> {
+        match self {
+            ColumnStorage::Plaintext => Ok(value.to_string()),
+            ColumnStorage::Sealed(keys) => seal_column(keys, column, row, value.as_bytes(), rng),
+        }
+    }
+
+    /// The decoded value of a stored `column` of `row`.
+    pub fn decode(&self, column: &str, row: &[u8], stored: &str) -> Result<String, AtRestError> {
+        match self {
+            ColumnStorage::Plaintext => Ok(stored.to_string()),
+            ColumnStorage::Sealed(keys) => {
+                String::from_utf8(open_column(keys, column, row, stored)?)
+                    .map_err(|_| AtRestError::InvalidEncoding)
+            }
+        }
+    }
+}
+
+/// A keyed hash of `value` in `column`, equal for equal values, to look rows
+/// up by a sealed column. Use a separate key from the sealing keys; rotating
+/// it means recomputing the index.
+pub fn blind_index(key: &DataKey, column: &str, value: &[u8]) -> [u8; 32] {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(&key.0).expect("HMAC accepts keys of any length");
+    mac.update(&(column.len() as u32).to_le_bytes());
+    mac.update(column.as_bytes());
+    mac.update(value);
+    mac.finalize().into_bytes().into()
+}
@@ -0,0 +1,228 @@
+//! Envelope encryption for health records.
+//!
+//! Each record is encrypted with a fresh random [`RecordKey`] using
+//! ChaCha20-Poly1305. The record key is then wrapped to a recipient's
+//! registered X25519 key with HPKE (RFC 9180, base mode,
+//! DHKEM(X25519, HKDF-SHA256) / HKDF-SHA256 / ChaCha20-Poly1305), which is the
+//! key envelope a grant stores. [`seal_record`] produces everything
+//! `submit_health_data` and `grant_access` take; [`open_record`] reverses it.
+//!
+//! `encrypted_data` is the standard base64 of `version || nonce || ciphertext`
+//! and `data_hash` is its SHA-256, which also seeds the record PDA. The
+//! payload is uploaded off-chain as is, so `data_hash` is also the
+//! `commitment` of the record's pointer.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use hpke::aead::ChaCha20Poly1305 as HpkeChaCha20Poly1305;
+use hpke::kdf::HkdfSha256;
+use hpke::kem::X25519HkdfSha256;
+use hpke::{Deserializable, Kem as _, OpModeR, OpModeS, Serializable};
+use primal_health_solana_program::{MAX_ENCRYPTED_DATA_LEN, MAX_KEY_ENVELOPE_LEN};
+use rand_core::CryptoRng;
+use sha2::{Digest, Sha256};
+
+type Kem = X25519HkdfSha256;
+
+const RECORD_VERSION: u8 = 1;
+const RECORD_AAD: &[u8] = b"primal-health record v1";
+const ENVELOPE_INFO: &[u8] = b"primal-health key envelope v1";
+const NONCE_LEN: usize = 12;
+/// Encapsulated key followed by the wrapped 32-byte key and its tag.
+pub const KEY_ENVELOPE_LEN: usize = 32 + 32 + 16;
+
+const _: () = assert!(KEY_ENVELOPE_LEN <= MAX_KEY_ENVELOPE_LEN);
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CryptoError {
+    #[error("encrypted record is {0} bytes, over the limit of {MAX_ENCRYPTED_DATA_LEN}")]
+    RecordTooLarge(usize),
+    #[error("encrypted data is not valid base64")]
+    InvalidEncoding,
+    #[error("unsupported record version {0}")]
+    UnsupportedVersion(u8),
+    #[error("encrypted data does not match the data hash")]
+    HashMismatch,
+    #[error("key envelope is malformed")]
+    InvalidEnvelope,
+    #[error("decryption failed")]
+    Decryption,
+}
+
+/// Symmetric key a single record is encrypted under.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecordKey(pub [u8; 32]);
+
+impl RecordKey {
+    pub fn generate(rng: &mut impl CryptoRng) -> Self {
+        let mut key = [0; 32];
+        rng.fill_bytes(&mut key);
+        RecordKey(key)
+    }
+}
+
+/// An X25519 key pair for receiving key envelopes. `public` is what
+/// `register_encryption_key` stores.
+pub struct EncryptionKeypair {
+    pub secret: [u8; 32],
+    pub public: [u8; 32],
+}
+
+impl EncryptionKeypair {
+    pub fn generate(rng: &mut impl CryptoRng) -> Self {
+        let (secret, public) = Kem::gen_keypair(rng);
+        EncryptionKeypair {
+            secret: secret.to_bytes().into(),
+            public: public.to_bytes().into(),
+        }
+    }
+}
+
+/// A record ready to submit, with its key wrapped to one recipient.
+pub struct SealedRecord {
+    pub data_hash: [u8; 32],
+    pub encrypted_data: String,
+    pub key_envelope: Vec<u8>,
+}
+
+/// Encrypts `plaintext` under a fresh record key wrapped to `recipient`.
+pub fn seal_record(
+    plaintext: &[u8],
+    recipient: &[u8; 32],
+    rng: &mut impl CryptoRng,
+) -> Result<SealedRecord, CryptoError> {
+    let key = RecordKey::generate(rng);
+    let (data_hash, encrypted_data) = encrypt_record(&key, plaintext, rng)?;
+    Ok(SealedRecord {
+        data_hash,
+        encrypted_data,
+        key_envelope: wrap_record_key(&key, recipient, rng)?,
+    })
+}
+
+/// Checks `encrypted_data` against `data_hash`, unwraps the record key from
+/// `key_envelope` and decrypts.
+pub fn open_record(
+    data_hash: &[u8; 32],
+    encrypted_data: &str,
+    key_envelope: &[u8],
+    recipient_secret: &[u8; 32],
+) -> Result<Vec<u8>, CryptoError> {
+    if record_hash(encrypted_data) != *data_hash {
+        return Err(CryptoError::HashMismatch);
+    }
+    let key = unwrap_record_key(key_envelope, recipient_secret)?;
+    decrypt_record(&key, encrypted_data)
+}
+
+/// Encrypts `plaintext` under `key`, returning the data hash and the encoded
+/// ciphertext.
+pub fn encrypt_record(
+    key: &RecordKey,
+    plaintext: &[u8],
+    rng: &mut impl CryptoRng,
+) -> Result<([u8; 32], String), CryptoError> {
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(&key.0.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: RECORD_AAD,
+            },
+        )
+        .expect("ChaCha20-Poly1305 only fails on inputs of 256 GiB or more");
+
+    let mut bytes = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+    bytes.push(RECORD_VERSION);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    let encrypted_data = STANDARD.encode(bytes);
+    if encrypted_data.len() > MAX_ENCRYPTED_DATA_LEN {
+        return Err(CryptoError::RecordTooLarge(encrypted_data.len()));
+    }
+    Ok((record_hash(&encrypted_data), encrypted_data))
+}
+
+pub fn decrypt_record(key: &RecordKey, encrypted_data: &str) -> Result<Vec<u8>, CryptoError> {
+    let bytes = STANDARD
+        .decode(encrypted_data)
+        .map_err(|_| CryptoError::InvalidEncoding)?;
+    let Some((&version, rest)) = bytes.split_first() else {
+        return Err(CryptoError::InvalidEncoding);
+    };
+    if version != RECORD_VERSION {
+        return Err(CryptoError::UnsupportedVersion(version));
+    }
+    if rest.len() < NONCE_LEN {
+        return Err(CryptoError::InvalidEncoding);
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(&key.0.into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: RECORD_AAD,
+            },
+        )
+        .map_err(|_| CryptoError::Decryption)
+}
+
+/// Wraps `key` to `recipient`'s X25519 public key, giving the bytes
+/// `grant_access` stores as the grant's key envelope.
+pub fn wrap_record_key(
+    key: &RecordKey,
+    recipient: &[u8; 32],
+    rng: &mut impl CryptoRng,
+) -> Result<Vec<u8>, CryptoError> {
+    let recipient = <Kem as hpke::Kem>::PublicKey::from_bytes(recipient)
+        .map_err(|_| CryptoError::InvalidEnvelope)?;
+    let (encapped, wrapped) = hpke::single_shot_seal::<HpkeChaCha20Poly1305, HkdfSha256, Kem, _>(
+        &OpModeS::Base,
+        &recipient,
+        ENVELOPE_INFO,
+        &key.0,
+        &[],
+        rng,
+    )
+    .map_err(|_| CryptoError::InvalidEnvelope)?;
+
+    let mut envelope = encapped.to_bytes().to_vec();
+    envelope.extend_from_slice(&wrapped);
+    Ok(envelope)
+}
+
+pub fn unwrap_record_key(
+    key_envelope: &[u8],
+    recipient_secret: &[u8; 32],
+) -> Result<RecordKey, CryptoError> {
+    if key_envelope.len() != KEY_ENVELOPE_LEN {
+        return Err(CryptoError::InvalidEnvelope);
+    }
+    let (encapped, wrapped) = key_envelope.split_at(32);
+    let encapped = <Kem as hpke::Kem>::EncappedKey::from_bytes(encapped)
+        .map_err(|_| CryptoError::InvalidEnvelope)?;
+    let secret = <Kem as hpke::Kem>::PrivateKey::from_bytes(recipient_secret)
+        .map_err(|_| CryptoError::InvalidEnvelope)?;
+    let key = hpke::single_shot_open::<HpkeChaCha20Poly1305, HkdfSha256, Kem>(
+        &OpModeR::Base,
+        &secret,
+        &encapped,
+        ENVELOPE_INFO,
+        wrapped,
+        &[],
+    )
+    .map_err(|_| CryptoError::Decryption)?;
+    key.try_into()
+        .map(RecordKey)
+        .map_err(|_| CryptoError::InvalidEnvelope)
+}
+
+/// The `data_hash` the program stores for `encrypted_data`.
+pub fn record_hash(encrypted_data: &str) -> [u8; 32] {
+    Sha256::digest(encrypted_data.as_bytes()).into()
+}
@@ -0,0 +1,464 @@
+//! Typed, localized errors decoded from failed transactions.
+//!
+//! A failed instruction reports only a custom error number. Its logs say more:
+//! which account an Anchor constraint blamed, the two sides of a failed
+//! comparison, and the program's own `msg!` explanation just before it. Pass
+//! them to [`ProgramError::from_logs`], or a bare number to
+//! [`ProgramError::from_number`], then show [`ProgramError::message`] in the
+//! user's [`Locale`]. Messages come from the templates in `locales/`, which
+//! may use `{account}`, `{left}`, `{right}` and `{number}`.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use primal_health_solana_program::ErrorCode;
+
+/// Every program error, in declaration order, so index `n` has number
+/// `6000 + n`.
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode::Unauthorized,
+    ErrorCode::InvalidPatient,
+    ErrorCode::ClaimNotVerified,
+    ErrorCode::EmptyBatch,
+    ErrorCode::BatchTooLarge,
+    ErrorCode::NoLineItems,
+    ErrorCode::TooManyLineItems,
+    ErrorCode::CodeTooLong,
+    ErrorCode::ModifierTooLong,
+    ErrorCode::InvalidLineItem,
+    ErrorCode::ClaimNotPending,
+    ErrorCode::InvalidDownCode,
+    ErrorCode::LineItemsNotAdjudicated,
+    ErrorCode::HashTooLong,
+    ErrorCode::TooManyAttachments,
+    ErrorCode::DuplicateAttachment,
+    ErrorCode::AttachmentNotFound,
+    ErrorCode::InvalidNotePage,
+    ErrorCode::ClaimNotOpen,
+    ErrorCode::InvalidStatusTransition,
+    ErrorCode::ClaimNotRejected,
+    ErrorCode::ClaimAlreadyReopened,
+    ErrorCode::ReopenWindowElapsed,
+    ErrorCode::NoNewEvidence,
+    ErrorCode::NoApprovedLineItems,
+    ErrorCode::AmountOverflow,
+    ErrorCode::DidEmpty,
+    ErrorCode::DidTooLong,
+    ErrorCode::NameEmpty,
+    ErrorCode::NameTooLong,
+    ErrorCode::HashEmpty,
+    ErrorCode::EncryptedDataTooLong,
+    ErrorCode::BatchIdEmpty,
+    ErrorCode::BatchIdTooLong,
+    ErrorCode::PointerEmpty,
+    ErrorCode::PointerTooLong,
+    ErrorCode::ClaimIdEmpty,
+    ErrorCode::ClaimIdTooLong,
+    ErrorCode::CodeEmpty,
+    ErrorCode::ZeroUnits,
+    ErrorCode::UnknownAccountLayout,
+    ErrorCode::AlreadyMigrated,
+    ErrorCode::InsufficientVaultBalance,
+    ErrorCode::InvalidGrantScope,
+    ErrorCode::InvalidGrantExpiry,
+    ErrorCode::GrantRequired,
+    ErrorCode::GrantInactive,
+    ErrorCode::InsufficientGrantScope,
+    ErrorCode::RecordAlreadyLinked,
+    ErrorCode::InvalidProvenanceLink,
+    ErrorCode::InvalidFieldCount,
+    ErrorCode::FieldPathTooLong,
+    ErrorCode::FieldValueTooLong,
+    ErrorCode::InvalidFieldIndex,
+    ErrorCode::InvalidMerkleProof,
+    ErrorCode::TooManyPolicyRules,
+    ErrorCode::InvalidPolicyRule,
+    ErrorCode::InvalidGuardianPermissions,
+    ErrorCode::InvalidDependentExpiry,
+    ErrorCode::GuardianLinkRequired,
+    ErrorCode::GuardianLinkExpired,
+    ErrorCode::InsufficientGuardianPermissions,
+    ErrorCode::InvalidEncryptionKey,
+    ErrorCode::KeyEnvelopeTooLong,
+    ErrorCode::EncryptionKeyRequired,
+    ErrorCode::KeyEnvelopeMismatch,
+    ErrorCode::ValidatorInactive,
+    ErrorCode::InvalidQualityScore,
+    ErrorCode::InvalidSubscriptionPrice,
+    ErrorCode::NothingToWithdraw,
+    ErrorCode::SubscriptionMismatch,
+    ErrorCode::InvalidSettlementPeriod,
+    ErrorCode::SettlementPeriodOpen,
+    ErrorCode::SettlementPeriodClosed,
+    ErrorCode::InvalidProvider,
+    ErrorCode::DecimalsMismatch,
+    ErrorCode::InvalidDecimals,
+    ErrorCode::InvalidServiceDate,
+    ErrorCode::DuplicateClaim,
+    ErrorCode::ClaimFingerprintMismatch,
+    ErrorCode::InsufficientSponsorFunds,
+    ErrorCode::TenantIdEmpty,
+    ErrorCode::TenantIdTooLong,
+    ErrorCode::TenantFeeTooHigh,
+    ErrorCode::NotTenantMember,
+    ErrorCode::TenantMismatch,
+    ErrorCode::FeatureDisabled,
+    ErrorCode::InvalidPaymentAmount,
+    ErrorCode::ClaimNotOnHold,
+    ErrorCode::ClaimAlreadyAdjudicated,
+    ErrorCode::InvalidAutoApprovalWindow,
+    ErrorCode::AutoApprovalNotDue,
+    ErrorCode::ClaimMintMismatch,
+    ErrorCode::InvalidTokenAccount,
+    ErrorCode::UnsupportedTokenProgram,
+    ErrorCode::OfferIdEmpty,
+    ErrorCode::OfferIdTooLong,
+    ErrorCode::InvalidOfferTerms,
+    ErrorCode::OfferPriceMismatch,
+    ErrorCode::AccountNotUpgraded,
+    ErrorCode::ExternalRefTooLong,
+    ErrorCode::TooManyTags,
+    ErrorCode::TagEmpty,
+    ErrorCode::TagTooLong,
+    ErrorCode::DuplicateTag,
+    ErrorCode::InvalidConsentScope,
+    ErrorCode::InvalidConsentExpiry,
+    ErrorCode::InvalidServicePeriod,
+    ErrorCode::ServiceBeforeEnrollment,
+    ErrorCode::ClaimNotEscrowable,
+    ErrorCode::InsufficientEscrow,
+    ErrorCode::PolicyIdEmpty,
+    ErrorCode::PolicyIdTooLong,
+    ErrorCode::InvalidApprovers,
+    ErrorCode::NotAnApprover,
+    ErrorCode::AlreadyApproved,
+    ErrorCode::ApprovalsPending,
+    ErrorCode::PaymentStarted,
+    ErrorCode::RecordIndexMismatch,
+    ErrorCode::ClaimNotClosable,
+    ErrorCode::ClaimEscrowed,
+    ErrorCode::DuplicateAccount,
+    ErrorCode::ProviderNotVerified,
+    ErrorCode::InvalidPriceTiers,
+    ErrorCode::OfferRecordMissing,
+    ErrorCode::OfferRecordMismatch,
+    ErrorCode::GrantNotDelegable,
+    ErrorCode::DelegationTooDeep,
+    ErrorCode::InvalidDelegationScope,
+    ErrorCode::GrantAlreadyHeld,
+    ErrorCode::GrantChainMismatch,
+    ErrorCode::DelegationsNotRevoked,
+    ErrorCode::ClaimGrantLapsed,
+    ErrorCode::InsurerApprovalPending,
+    ErrorCode::InvalidInsurer,
+    ErrorCode::InvalidDevice,
+    ErrorCode::InvalidDeviceRateLimit,
+    ErrorCode::DeviceRateLimited,
+    ErrorCode::RollupDayOpen,
+    ErrorCode::InvalidRollup,
+    ErrorCode::ReadingOutsideRollup,
+    ErrorCode::InvalidPaymentSchedule,
+    ErrorCode::InstallmentTooSmall,
+    ErrorCode::InvalidDelegate,
+    ErrorCode::InvalidDelegatePermissions,
+    ErrorCode::DelegateRequired,
+    ErrorCode::InsufficientDelegatePermissions,
+    ErrorCode::InvalidSecondOpinion,
+    ErrorCode::InvalidConsultant,
+    ErrorCode::RecordNotShared,
+    ErrorCode::SecondOpinionClosed,
+    ErrorCode::SecondOpinionPending,
+    ErrorCode::InvalidCareTeam,
+    ErrorCode::CareTeamRecordNotShared,
+    ErrorCode::InvalidEmergencyAccess,
+    ErrorCode::EmergencyAccessVetoed,
+    ErrorCode::EmergencyAccessPending,
+    ErrorCode::EmergencyAccessExpired,
+    ErrorCode::EmergencyVetoWindowClosed,
+    ErrorCode::EmergencyAccessActive,
+    ErrorCode::AuditEntryMismatch,
+    ErrorCode::InvalidFeeSchedule,
+    ErrorCode::CodeNotInFeeSchedule,
+    ErrorCode::InvalidAppointment,
+    ErrorCode::AppointmentStatusMismatch,
+    ErrorCode::InlineStorage,
+    ErrorCode::CommitmentMismatch,
+    ErrorCode::InvalidDisputeConfig,
+    ErrorCode::ClaimNotDisputed,
+    ErrorCode::DisputeWindowElapsed,
+    ErrorCode::InvalidArbiter,
+    ErrorCode::EscrowReleaseUnauthorized,
+    ErrorCode::ProgramPaused,
+    ErrorCode::ProtocolFeeTooHigh,
+    ErrorCode::InsufficientTreasuryFunds,
+    ErrorCode::PlanIdEmpty,
+    ErrorCode::PlanIdTooLong,
+    ErrorCode::InvalidPlanTerms,
+    ErrorCode::BatchAccountsMismatch,
+    ErrorCode::MintTreasuryRequired,
+    ErrorCode::InvalidRecordLength,
+    ErrorCode::ChunkOutOfOrder,
+    ErrorCode::InvalidChunkLength,
+    ErrorCode::RecordFinalized,
+    ErrorCode::InvalidClawbackWindow,
+    ErrorCode::ClawbackDisabled,
+    ErrorCode::PaymentStillHeld,
+    ErrorCode::ClawbackWindowElapsed,
+    ErrorCode::ClawbackUnauthorized,
+    ErrorCode::EncryptionKeyUnchanged,
+    ErrorCode::InvalidFacilitatorKey,
+    ErrorCode::InvalidFeeShare,
+    ErrorCode::FacilitatorRevoked,
+    ErrorCode::FacilitatorKeyMismatch,
+    ErrorCode::InsufficientFacilitatorFees,
+    ErrorCode::InsufficientConsent,
+    ErrorCode::ReencryptionAlreadyFulfilled,
+    ErrorCode::ReencryptionKeyStale,
+    ErrorCode::InvalidWrappedKey,
+    ErrorCode::InvalidCrankReward,
+    ErrorCode::InvalidClaimTtl,
+    ErrorCode::ClaimNotExpirable,
+    ErrorCode::ClaimNotExpired,
+    ErrorCode::InvalidFeeRebates,
+    ErrorCode::ProviderVolumeRequired,
+    ErrorCode::InvalidPlanBenefits,
+    ErrorCode::PlanBenefitsRequired,
+    ErrorCode::OutOfPocketRequired,
+    ErrorCode::InsufficientStake,
+    ErrorCode::StakeStillUnbonding,
+    ErrorCode::DisputeNotUpheld,
+];
+
+const FRAMEWORK: &str = "@framework";
+const FRAMEWORK_ACCOUNT: &str = "@framework_account";
+const UNKNOWN: &str = "@unknown";
+
+/// A language patient-facing messages are available in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Locale {
+    En,
+    Es,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::Es, Locale::Fr];
+
+    /// The locale for a BCP 47 tag such as `es-MX`, matching on the language
+    /// only. Unsupported languages get `None`.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            "fr" => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::En => include_str!("../locales/en.json"),
+            Locale::Es => include_str!("../locales/es.json"),
+            Locale::Fr => include_str!("../locales/fr.json"),
+        }
+    }
+
+    /// Message templates keyed by error name.
+    pub fn templates(self) -> &'static BTreeMap<String, String> {
+        static CATALOGS: [OnceLock<BTreeMap<String, String>>; 3] =
+            [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+        CATALOGS[self as usize].get_or_init(|| {
+            serde_json::from_str(self.source()).expect("locale catalogs are valid JSON")
+        })
+    }
+}
+
+/// What the logs said about a failure besides its number.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The account a failed constraint was declared on.
+    pub account: Option<String>,
+    /// Left and right sides of a failed `require_*` comparison.
+    pub compared: Option<(String, String)>,
+    /// The last line the program logged before failing, in English.
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum ProgramError {
+    /// An error raised by the Primal Health program.
+    #[error("{code}")]
+    Program {
+        code: ErrorCode,
+        context: ErrorContext,
+    },
+    /// An Anchor check failed, such as an account constraint or a missing
+    /// signature.
+    #[error("Anchor error {number}")]
+    Framework {
+        number: u32,
+        /// The error's name, known only when decoded from logs.
+        name: Option<String>,
+        context: ErrorContext,
+    },
+    /// A custom error number neither this program nor Anchor defines.
+    #[error("custom program error {number}")]
+    Unknown { number: u32 },
+}
+
+impl ProgramError {
+    /// Error for a bare custom error number, such as the one in
+    /// `InstructionError::Custom`.
+    pub fn from_number(number: u32) -> ProgramError {
+        if let Some(code) = error_code(number) {
+            return ProgramError::Program {
+                code,
+                context: ErrorContext::default(),
+            };
+        }
+        // Anchor's own errors sit below the user offset
+        if number < u32::from(ERROR_CODES[0]) {
+            return ProgramError::Framework {
+                number,
+                name: None,
+                context: ErrorContext::default(),
+            };
+        }
+        ProgramError::Unknown { number }
+    }
+
+    /// Decodes the last Anchor error in a transaction's log messages, or
+    /// `None` if the logs contain none.
+    pub fn from_logs<S: AsRef<str>>(logs: &[S]) -> Option<ProgramError> {
+        let lines: Vec<&str> = logs.iter().map(AsRef::as_ref).collect();
+        let (index, report) = lines
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, line)| Some((index, parse_report(log_text(line)?)?)))?;
+
+        let detail = lines[..index]
+            .iter()
+            .rev()
+            .map_while(|line| log_text(line))
+            .find(|text| !text.starts_with("Instruction: "))
+            .map(str::to_string);
+        let context = ErrorContext {
+            account: report.account,
+            compared: compared_values(&lines[index + 1..]),
+            detail,
+        };
+
+        Some(match error_code(report.number) {
+            Some(code) => ProgramError::Program { code, context },
+            None if report.number < u32::from(ERROR_CODES[0]) => ProgramError::Framework {
+                number: report.number,
+                name: Some(report.name),
+                context,
+            },
+            None => ProgramError::Unknown {
+                number: report.number,
+            },
+        })
+    }
+
+    pub fn number(&self) -> u32 {
+        match self {
+            ProgramError::Program { code, .. } => u32::from(*code),
+            ProgramError::Framework { number, .. } | ProgramError::Unknown { number } => *number,
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ProgramError::Program { context, .. } | ProgramError::Framework { context, .. } => {
+                Some(context)
+            }
+            ProgramError::Unknown { .. } => None,
+        }
+    }
+
+    /// A patient-facing message in `locale`, falling back to English and
+    /// then to the program's own message when a template is missing.
+    pub fn message(&self, locale: Locale) -> String {
+        let key = match self {
+            ProgramError::Program { code, .. } => code.name(),
+            ProgramError::Framework { context, .. } if context.account.is_some() => {
+                FRAMEWORK_ACCOUNT.to_string()
+            }
+            ProgramError::Framework { .. } => FRAMEWORK.to_string(),
+            ProgramError::Unknown { .. } => UNKNOWN.to_string(),
+        };
+        let template = [locale, Locale::En]
+            .iter()
+            .find_map(|locale| locale.templates().get(&key));
+        match template {
+            Some(template) => self.render(template),
+            None => self.to_string(),
+        }
+    }
+
+    fn render(&self, template: &str) -> String {
+        let context = self.context().cloned().unwrap_or_default();
+        let (left, right) = context.compared.unwrap_or_default();
+        template
+            .replace("{account}", context.account.as_deref().unwrap_or_default())
+            .replace("{left}", &left)
+            .replace("{right}", &right)
+            .replace("{number}", &self.number().to_string())
+    }
+}
+
+/// The program error with this number, if there is one.
+pub fn error_code(number: u32) -> Option<ErrorCode> {
+    let index = number.checked_sub(u32::from(ERROR_CODES[0]))?;
+    ERROR_CODES
+        .get(index as usize)
+        .copied()
+        .filter(|code| u32::from(*code) == number)
+}
+
+struct Report {
+    name: String,
+    number: u32,
+    account: Option<String>,
+}
+
+/// The text of a `Program log:` line.
+fn log_text(line: &str) -> Option<&str> {
+    line.strip_prefix("Program log: ")
+}
+
+/// Parses Anchor's `AnchorError ... Error Code: X. Error Number: N. ...` line.
+fn parse_report(text: &str) -> Option<Report> {
+    if !text.starts_with("AnchorError ") {
+        return None;
+    }
+    let (_, rest) = text.split_once("Error Code: ")?;
+    let (name, rest) = rest.split_once(". Error Number: ")?;
+    let (number, _) = rest.split_once(". ")?;
+    let account = text
+        .strip_prefix("AnchorError caused by account: ")
+        .and_then(|rest| rest.split_once(". "))
+        .map(|(account, _)| account.to_string());
+    Some(Report {
+        name: name.to_string(),
+        number: number.parse().ok()?,
+        account,
+    })
+}
+
+/// The `Left:`/`Right:` lines Anchor logs after a failed comparison, either
+/// inline for values or on the following line for public keys.
+fn compared_values(lines: &[&str]) -> Option<(String, String)> {
+    let texts: Vec<&str> = lines.iter().map_while(|line| log_text(line)).collect();
+    match texts.as_slice() {
+        ["Left:", left, "Right:", right, ..] => Some((left.to_string(), right.to_string())),
+        [left, right, ..] => Some((
+            left.strip_prefix("Left: ")?.to_string(),
+            right.strip_prefix("Right: ")?.to_string(),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,320 @@
+//! Claims intake from X12 837 professional claim files.
+//!
+//! Clearinghouses batch claims as 837P interchanges. [`parse_837`] reads one
+//! and returns a [`ClaimIntake`] per `CLM` segment, with its lines priced in
+//! the claim's base units and its service period taken from the `DTP*472`
+//! dates. [`ClaimIntake::create_claim`] then builds the `create_claim`
+//! arguments once the caller has mapped the billing provider's NPI and the
+//! subscriber's member id to wallets, which the file cannot say.
+//!
+//! Only what `create_claim` records is read: the billing provider and
+//! subscriber names, `CLM`, `SV1` and `DTP*472`. Delimiters come from the
+//! `ISA` header, so any separators the sender chose are accepted.
+
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::instruction::CreateClaim;
+use primal_health_solana_program::{ClaimDedup, LineItemInput};
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+const ISA_LEN: usize = 106;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntakeError {
+    #[error("input does not start with an ISA header")]
+    NotAnInterchange,
+    #[error("transaction set {0} is not an 837")]
+    UnsupportedTransaction(String),
+    #[error("segment {segment} ({id}) is malformed: {reason}")]
+    Malformed {
+        segment: usize,
+        id: String,
+        reason: &'static str,
+    },
+    #[error("{0:?} is not an amount with at most the claim's decimals")]
+    InvalidAmount(String),
+    #[error("{0:?} is not a CCYYMMDD date or date range")]
+    InvalidDate(String),
+    #[error("a line of claim {0} has no service date")]
+    MissingServiceDate(String),
+    #[error("lines of claim {control_number} total {lines}, not the billed {billed}")]
+    ChargeMismatch {
+        control_number: String,
+        billed: u64,
+        lines: u64,
+    },
+}
+
+/// A claim read from an 837, before its parties are mapped to wallets.
+#[derive(Clone)]
+pub struct ClaimIntake {
+    /// The patient control number (`CLM01`), used as the claim id.
+    pub control_number: String,
+    /// The billing provider's NPI (`NM1*85`).
+    pub billing_npi: String,
+    /// The subscriber's member id (`NM1*IL`). Claims for a dependent carry
+    /// the subscriber's id.
+    pub member_id: String,
+    /// The total charge (`CLM02`) in base units.
+    pub billed: u64,
+    pub line_items: Vec<LineItemInput>,
+    /// Midnight UTC of the first and last service dates over all lines.
+    pub service_start: i64,
+    pub service_end: i64,
+}
+
+impl ClaimIntake {
+    /// The `create_claim` arguments for this claim filed by `patient` with
+    /// `provider`, with its duplicate fingerprint. Attach supporting records
+    /// afterwards with `add_attachment`.
+    pub fn create_claim(&self, patient: &Pubkey, provider: &Pubkey) -> CreateClaim {
+        CreateClaim {
+            claim_id: self.control_number.clone(),
+            line_items: self.line_items.clone(),
+            attachments: Vec::new(),
+            service_start: self.service_start,
+            service_end: self.service_end,
+            fingerprint: ClaimDedup::fingerprint(
+                patient,
+                provider,
+                &self.line_items,
+                self.service_start,
+            ),
+        }
+    }
+}
+
+/// Parses every claim in an 837P interchange, with amounts scaled to
+/// `decimals` places, such as 6 for USDC. Each line's charge must divide
+/// evenly by its whole number of units, since claims store a unit price.
+pub fn parse_837(input: &str, decimals: u8) -> Result<Vec<ClaimIntake>, IntakeError> {
+    let input = input.trim_start();
+    let header: Vec<char> = input.chars().take(ISA_LEN).collect();
+    if !input.starts_with("ISA") || header.len() < ISA_LEN {
+        return Err(IntakeError::NotAnInterchange);
+    }
+    let (element, component, terminator) = (header[3], header[104], header[105]);
+
+    let mut reader = Reader::default();
+    let segments = input
+        .split(terminator)
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty());
+    for (index, segment) in segments.enumerate() {
+        let elements: Vec<&str> = segment.split(element).collect();
+        reader.read(index, &elements, component, decimals)?;
+    }
+    reader.finish_claim()?;
+    Ok(reader.claims)
+}
+
+#[derive(Default)]
+struct Reader {
+    billing_npi: String,
+    member_id: String,
+    claim: Option<DraftClaim>,
+    claims: Vec<ClaimIntake>,
+}
+
+struct DraftClaim {
+    control_number: String,
+    billed: u64,
+    dates: Option<(i64, i64)>,
+    lines: Vec<(LineItemInput, Option<(i64, i64)>)>,
+}
+
+impl Reader {
+    fn read(
+        &mut self,
+        index: usize,
+        elements: &[&str],
+        component: char,
+        decimals: u8,
+    ) -> Result<(), IntakeError> {
+        let element = |n: usize| elements.get(n).copied().unwrap_or("");
+        let malformed = |reason| IntakeError::Malformed {
+            segment: index,
+            id: element(0).to_string(),
+            reason,
+        };
+        match element(0) {
+            "ST" if element(1) != "837" => {
+                return Err(IntakeError::UnsupportedTransaction(element(1).into()))
+            }
+            "HL" | "SE" => self.finish_claim()?,
+            "NM1" => match element(1) {
+                "85" => self.billing_npi = element(9).to_string(),
+                "IL" => self.member_id = element(9).to_string(),
+                _ => {}
+            },
+            "CLM" => {
+                self.finish_claim()?;
+                if element(1).is_empty() {
+                    return Err(malformed("missing patient control number"));
+                }
+                self.claim = Some(DraftClaim {
+                    control_number: element(1).to_string(),
+                    billed: parse_amount(element(2), decimals)?,
+                    dates: None,
+                    lines: Vec::new(),
+                });
+            }
+            "SV1" => {
+                let claim = self
+                    .claim
+                    .as_mut()
+                    .ok_or_else(|| malformed("line outside a claim"))?;
+                let procedure: Vec<&str> = element(1).split(component).collect();
+                if procedure.len() < 2 || procedure[1].is_empty() {
+                    return Err(malformed("missing procedure code"));
+                }
+                let charge = parse_amount(element(2), decimals)?;
+                let units: u32 = element(4)
+                    .parse()
+                    .ok()
+                    .filter(|units| *units > 0)
+                    .ok_or_else(|| malformed("units must be a whole number above zero"))?;
+                if charge % units as u64 != 0 {
+                    return Err(malformed("charge does not divide evenly by units"));
+                }
+                let line = LineItemInput {
+                    code: procedure[1].to_string(),
+                    units,
+                    unit_price: charge / units as u64,
+                    // Up to four two-character modifiers fill the modifier
+                    modifier: procedure[2..].concat(),
+                };
+                claim.lines.push((line, None));
+            }
+            "DTP" if element(1) == "472" => {
+                let claim = self
+                    .claim
+                    .as_mut()
+                    .ok_or_else(|| malformed("date outside a claim"))?;
+                let dates = parse_dates(element(2), element(3))?;
+                match claim.lines.last_mut() {
+                    Some((_, line_dates)) => *line_dates = Some(dates),
+                    None => claim.dates = Some(dates),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish_claim(&mut self) -> Result<(), IntakeError> {
+        let Some(claim) = self.claim.take() else {
+            return Ok(());
+        };
+        let mut lines_total = 0u64;
+        let mut period: Option<(i64, i64)> = None;
+        for (line, dates) in &claim.lines {
+            let (start, end) = dates
+                .or(claim.dates)
+                .ok_or_else(|| IntakeError::MissingServiceDate(claim.control_number.clone()))?;
+            period = Some(match period {
+                Some((first, last)) => (first.min(start), last.max(end)),
+                None => (start, end),
+            });
+            lines_total = lines_total.saturating_add(line.unit_price * line.units as u64);
+        }
+        if lines_total != claim.billed {
+            return Err(IntakeError::ChargeMismatch {
+                control_number: claim.control_number,
+                billed: claim.billed,
+                lines: lines_total,
+            });
+        }
+        // A claim without lines is left for create_claim to reject
+        let (service_start, service_end) = period.or(claim.dates).unwrap_or_default();
+        self.claims.push(ClaimIntake {
+            control_number: claim.control_number,
+            billing_npi: self.billing_npi.clone(),
+            member_id: self.member_id.clone(),
+            billed: claim.billed,
+            line_items: claim.lines.into_iter().map(|(line, _)| line).collect(),
+            service_start,
+            service_end,
+        });
+        Ok(())
+    }
+}
+
+/// A non-negative decimal amount scaled to `decimals` places.
+fn parse_amount(value: &str, decimals: u8) -> Result<u64, IntakeError> {
+    let invalid = || IntakeError::InvalidAmount(value.to_string());
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !digits(whole)
+        || !digits(fraction)
+        || fraction.len() > decimals as usize
+    {
+        return Err(invalid());
+    }
+    let scale = 10u64.checked_pow(decimals as u32).ok_or_else(invalid)?;
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        let padded = format!("{fraction:0<width$}", width = decimals as usize);
+        padded.parse().map_err(|_| invalid())?
+    };
+    whole
+        .checked_mul(scale)
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+/// A `D8` date or `RD8` range as midnight UTC of its first and last days.
+fn parse_dates(format: &str, value: &str) -> Result<(i64, i64), IntakeError> {
+    let invalid = || IntakeError::InvalidDate(value.to_string());
+    let (start, end) = match format {
+        "D8" => (value, value),
+        "RD8" => value.split_once('-').ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+    let (start, end) = (
+        parse_date(start).ok_or_else(invalid)?,
+        parse_date(end).ok_or_else(invalid)?,
+    );
+    if end < start {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+fn parse_date(value: &str) -> Option<i64> {
+    if value.len() != 8 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year: i64 = value[..4].parse().ok()?;
+    let month: u32 = value[4..6].parse().ok()?;
+    let day: u32 = value[6..].parse().ok()?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_len = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if day == 0 || day > month_len {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * SECS_PER_DAY)
+}
+
+/// Days since 1970-01-01, after Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month as i64 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
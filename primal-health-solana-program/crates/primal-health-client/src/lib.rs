@@ -0,0 +1,17 @@
+//! Client-side helpers for integrating with the Primal Health program.
+//!
+//! [`crypto`] encrypts records before they are submitted, so the program only
+//! ever sees ciphertext, hashes and wrapped keys, and [`at_rest`] seals the
+//! sensitive columns an indexer stores. [`archive`] packages a patient's
+//! accounts into an encrypted, signed export. [`errors`] turns a failed
+//! transaction into a typed error with a message in the user's language.
+//! [`intake`] turns X12 837 claim files into `create_claim` arguments, and
+//! [`remittance`] summarizes paid claims as remittance advice for
+//! revenue-cycle tooling.
+
+pub mod archive;
+pub mod at_rest;
+pub mod crypto;
+pub mod errors;
+pub mod intake;
+pub mod remittance;
@@ -0,0 +1,245 @@
+//! X12 835-style remittance advice built from paid claims.
+//!
+//! Revenue-cycle tools reconcile payments against remittance advice: one
+//! batch per payer and period listing each claim paid, what was charged, what
+//! was allowed line by line and why the rest was not. [`remittances`] builds
+//! those batches from claim accounts an indexer has read, grouping them by
+//! the paying provider, the `YYYYMM` settlement period of the payment and the
+//! mint. A claim's line items carry the adjudication an explanation of
+//! benefits would, so no other account is needed. Batches serialize to JSON
+//! with serde, or to one CSV row per line with [`to_csv`].
+//!
+//! Amounts are as of when the claim was read. A partially paid claim reports
+//! what has been paid so far in the period of its latest payment.
+
+use std::collections::BTreeMap;
+
+use primal_health_solana_program::settlement::period_of;
+use primal_health_solana_program::{ClaimAccount, ClaimStatus};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemittanceError {
+    #[error("amounts of claim {0} overflow")]
+    AmountOverflow(String),
+}
+
+/// A claim as read from chain, with when its latest payment landed.
+#[derive(Clone)]
+pub struct PaidClaim {
+    /// Base58 address of the claim account.
+    pub address: String,
+    pub account: ClaimAccount,
+    /// Block time of the latest payment, which picks the period.
+    pub paid_at: i64,
+}
+
+/// One payer's remittance for one period and mint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remittance {
+    /// Base58 address of the paying provider.
+    pub payer: String,
+    /// `YYYYMM`, as in settlement summaries.
+    pub period: u32,
+    /// Base58 address of the mint, or empty for lamports.
+    pub mint: String,
+    /// Principal and interest over all claims.
+    pub total_paid: u64,
+    pub total_interest: u64,
+    pub claims: Vec<RemittanceClaim>,
+}
+
+/// A paid claim, the 835's claim payment loop.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemittanceClaim {
+    /// Base58 address of the claim account.
+    pub address: String,
+    pub claim_id: String,
+    /// The claim's id in the billing system it came from, which revenue-cycle
+    /// tools match on; empty if not set.
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    /// Base58 address of the patient paid.
+    pub patient: String,
+    /// `Paid` or `PartiallyPaid`.
+    pub status: String,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub paid_at: i64,
+    /// The billed amount.
+    pub charged: u64,
+    /// The approved amount of the claim's lines.
+    pub allowed: u64,
+    /// Principal and interest paid.
+    pub paid: u64,
+    pub interest: u64,
+    pub lines: Vec<RemittanceLine>,
+}
+
+/// A claim line with its adjudication, the 835's service payment loop.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemittanceLine {
+    pub code: String,
+    pub modifier: String,
+    pub units: u32,
+    /// The line's total; a down-coded line reports its down-coded price,
+    /// since that is all the claim keeps.
+    pub charged: u64,
+    /// The line's total if it is paid, 0 if denied.
+    pub allowed: u64,
+    /// `Approved`, `Denied` or `DownCoded`.
+    pub status: String,
+    /// Adjustment reason code recorded with the decision.
+    pub reason_code: u16,
+}
+
+/// Groups paid and partially paid claims into remittances by payer, period
+/// and mint, sorted by those and each listing its claims by payment time.
+/// Claims in any other status are skipped.
+pub fn remittances(claims: &[PaidClaim]) -> Result<Vec<Remittance>, RemittanceError> {
+    let mut batches: BTreeMap<(String, u32, String), Remittance> = BTreeMap::new();
+    for paid in claims {
+        let account = &paid.account;
+        if !matches!(
+            account.status,
+            ClaimStatus::Paid | ClaimStatus::PartiallyPaid
+        ) {
+            continue;
+        }
+        let claim = remittance_claim(paid)?;
+        let mint = if account.payment_mint() == Default::default() {
+            String::new()
+        } else {
+            account.payment_mint().to_string()
+        };
+        let payer = account.provider.to_string();
+        let period = period_of(paid.paid_at);
+        let batch = batches
+            .entry((payer.clone(), period, mint.clone()))
+            .or_insert_with(|| Remittance {
+                payer,
+                period,
+                mint,
+                total_paid: 0,
+                total_interest: 0,
+                claims: Vec::new(),
+            });
+        let overflow = || RemittanceError::AmountOverflow(paid.address.clone());
+        batch.total_paid = batch
+            .total_paid
+            .checked_add(claim.paid)
+            .ok_or_else(overflow)?;
+        batch.total_interest = batch
+            .total_interest
+            .checked_add(claim.interest)
+            .ok_or_else(overflow)?;
+        batch.claims.push(claim);
+    }
+
+    let mut batches: Vec<Remittance> = batches.into_values().collect();
+    for batch in &mut batches {
+        batch
+            .claims
+            .sort_by(|a, b| (a.paid_at, &a.claim_id).cmp(&(b.paid_at, &b.claim_id)));
+    }
+    Ok(batches)
+}
+
+fn remittance_claim(paid: &PaidClaim) -> Result<RemittanceClaim, RemittanceError> {
+    let account = &paid.account;
+    let overflow = || RemittanceError::AmountOverflow(paid.address.clone());
+    let lines = account
+        .line_items
+        .iter()
+        .map(|item| {
+            let charged = item.total().map_err(|_| overflow())?;
+            Ok(RemittanceLine {
+                code: item.code.clone(),
+                modifier: item.modifier.clone(),
+                units: item.units,
+                charged,
+                allowed: if item.status.is_payable() { charged } else { 0 },
+                status: format!("{:?}", item.status),
+                reason_code: item.reason_code,
+            })
+        })
+        .collect::<Result<Vec<_>, RemittanceError>>()?;
+    let allowed = account.approved_amount().map_err(|_| overflow())?;
+    let paid_total = account
+        .principal_paid
+        .checked_add(account.interest_paid)
+        .ok_or_else(overflow)?;
+
+    Ok(RemittanceClaim {
+        address: paid.address.clone(),
+        claim_id: account.claim_id.clone(),
+        external_ref: account.external_ref.clone(),
+        tags: account.tags.clone(),
+        patient: account.patient.to_string(),
+        status: format!("{:?}", account.status),
+        service_start: account.service_start,
+        service_end: account.service_end,
+        paid_at: paid.paid_at,
+        charged: account.amount,
+        allowed,
+        paid: paid_total,
+        interest: account.interest_paid,
+        lines,
+    })
+}
+
+const CSV_HEADER: &str = "payer,period,mint,claim,claim_id,external_ref,tags,patient,status,\
+service_start,service_end,paid_at,claim_charged,claim_allowed,claim_paid,claim_interest,\
+line,code,modifier,units,line_charged,line_allowed,line_status,reason_code";
+
+/// One CSV row per claim line, with the claim's fields repeated on each, for
+/// spreadsheets and tools that import flat files. Tags are joined with `;`.
+pub fn to_csv(remittances: &[Remittance]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for batch in remittances {
+        for claim in &batch.claims {
+            for (index, line) in claim.lines.iter().enumerate() {
+                let fields = [
+                    batch.payer.clone(),
+                    batch.period.to_string(),
+                    batch.mint.clone(),
+                    claim.address.clone(),
+                    claim.claim_id.clone(),
+                    claim.external_ref.clone(),
+                    claim.tags.join(";"),
+                    claim.patient.clone(),
+                    claim.status.clone(),
+                    claim.service_start.to_string(),
+                    claim.service_end.to_string(),
+                    claim.paid_at.to_string(),
+                    claim.charged.to_string(),
+                    claim.allowed.to_string(),
+                    claim.paid.to_string(),
+                    claim.interest.to_string(),
+                    index.to_string(),
+                    line.code.clone(),
+                    line.modifier.clone(),
+                    line.units.to_string(),
+                    line.charged.to_string(),
+                    line.allowed.to_string(),
+                    line.status.clone(),
+                    line.reason_code.to_string(),
+                ];
+                let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                out.push_str(&row.join(","));
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Quotes a field holding a comma, quote or line break, doubling its quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,57 @@
+use primal_health_client::archive::{
+    open_archive, seal_archive, ArchiveError, Export, ExportedAccount,
+};
+use primal_health_client::crypto::EncryptionKeypair;
+use rand_core::{OsRng, UnwrapErr};
+
+fn export() -> Export {
+    Export {
+        patient: "Patient1111111111111111111111111111111111111".into(),
+        slot: 42,
+        accounts: vec![ExportedAccount {
+            kind: "HealthDataAccount".into(),
+            address: "Record11111111111111111111111111111111111111".into(),
+            // Larger than a single on-chain record may hold
+            data: "A".repeat(64 * 1024),
+        }],
+    }
+}
+
+#[test]
+fn sealed_archives_open_for_the_recipient() {
+    let mut rng = UnwrapErr(OsRng);
+    let recipient = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_archive(&export(), &recipient.public, &mut rng).unwrap();
+
+    assert_eq!(open_archive(&sealed, &recipient.secret).unwrap(), export());
+}
+
+#[test]
+fn archives_do_not_open_for_anyone_else() {
+    let mut rng = UnwrapErr(OsRng);
+    let recipient = EncryptionKeypair::generate(&mut rng);
+    let stranger = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_archive(&export(), &recipient.public, &mut rng).unwrap();
+
+    assert!(open_archive(&sealed, &stranger.secret).is_err());
+}
+
+#[test]
+fn the_signing_message_covers_the_sealed_contents() {
+    let mut rng = UnwrapErr(OsRng);
+    let recipient = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_archive(&export(), &recipient.public, &mut rng).unwrap();
+
+    let mut signed = sealed.clone();
+    signed.signer = "Signer".into();
+    signed.signature = "Signature".into();
+    assert_eq!(signed.signing_message(), sealed.signing_message());
+
+    let mut relabeled = sealed.clone();
+    relabeled.patient = "Someone else".into();
+    assert_ne!(relabeled.signing_message(), sealed.signing_message());
+    assert!(matches!(
+        open_archive(&relabeled, &recipient.secret),
+        Err(ArchiveError::PatientMismatch)
+    ));
+}
@@ -0,0 +1,106 @@
+use primal_health_client::at_rest::{
+    blind_index, open_column, seal_column, AtRestError, ColumnStorage, DataKey, StaticKeys,
+};
+use rand_core::{OsRng, UnwrapErr};
+
+const DID: &str = "did:sol:9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+const ROW: &[u8] = &[7; 32];
+
+fn keys() -> StaticKeys {
+    StaticKeys::new("kms-2026-01", DataKey([1; 32]))
+}
+
+#[test]
+fn sealed_columns_open_with_the_same_column_and_row() {
+    let mut rng = UnwrapErr(OsRng);
+    let keys = keys();
+    let sealed = seal_column(&keys, "did", ROW, DID.as_bytes(), &mut rng).unwrap();
+
+    assert!(!sealed.contains("did:sol"));
+    assert_eq!(
+        open_column(&keys, "did", ROW, &sealed).unwrap(),
+        DID.as_bytes()
+    );
+    assert_ne!(
+        sealed,
+        seal_column(&keys, "did", ROW, DID.as_bytes(), &mut rng).unwrap()
+    );
+}
+
+#[test]
+fn values_moved_to_another_row_or_column_do_not_open() {
+    let mut rng = UnwrapErr(OsRng);
+    let keys = keys();
+    let sealed = seal_column(&keys, "did", ROW, DID.as_bytes(), &mut rng).unwrap();
+
+    assert_eq!(
+        open_column(&keys, "did", &[8; 32], &sealed),
+        Err(AtRestError::Decryption)
+    );
+    assert_eq!(
+        open_column(&keys, "note_hash", ROW, &sealed),
+        Err(AtRestError::Decryption)
+    );
+}
+
+#[test]
+fn rotated_keys_still_open_older_values() {
+    let mut rng = UnwrapErr(OsRng);
+    let mut rotated = keys();
+    let old = seal_column(&rotated, "did", ROW, DID.as_bytes(), &mut rng).unwrap();
+    rotated.rotate("kms-2026-07", DataKey([2; 32]));
+    let new = seal_column(&rotated, "did", ROW, DID.as_bytes(), &mut rng).unwrap();
+
+    assert_eq!(
+        open_column(&rotated, "did", ROW, &old).unwrap(),
+        DID.as_bytes()
+    );
+    assert_eq!(
+        open_column(&rotated, "did", ROW, &new).unwrap(),
+        DID.as_bytes()
+    );
+    assert_eq!(
+        open_column(&keys(), "did", ROW, &new),
+        Err(AtRestError::UnknownKey("kms-2026-07".into()))
+    );
+}
+
+#[test]
+fn malformed_values_are_rejected() {
+    let keys = keys();
+    assert_eq!(
+        open_column(&keys, "did", ROW, "not base64!"),
+        Err(AtRestError::InvalidEncoding)
+    );
+    assert_eq!(
+        open_column(&keys, "did", ROW, "AQ=="),
+        Err(AtRestError::InvalidEncoding)
+    );
+    assert_eq!(
+        open_column(&keys, "did", ROW, "CQA="),
+        Err(AtRestError::UnsupportedVersion(9))
+    );
+}
+
+#[test]
+fn blind_indexes_match_equal_values_only() {
+    let key = DataKey([3; 32]);
+    let index = blind_index(&key, "did", DID.as_bytes());
+
+    assert_eq!(index, blind_index(&key, "did", DID.as_bytes()));
+    assert_ne!(index, blind_index(&key, "did", b"did:sol:other"));
+    assert_ne!(index, blind_index(&key, "note_hash", DID.as_bytes()));
+    assert_ne!(index, blind_index(&DataKey([4; 32]), "did", DID.as_bytes()));
+}
+
+#[test]
+fn plaintext_storage_keeps_values_as_decoded() {
+    let mut rng = UnwrapErr(OsRng);
+    let plaintext: ColumnStorage<StaticKeys> = ColumnStorage::Plaintext;
+    let sealed = ColumnStorage::Sealed(keys());
+
+    assert_eq!(plaintext.encode("did", ROW, DID, &mut rng).unwrap(), DID);
+    let stored = sealed.encode("did", ROW, DID, &mut rng).unwrap();
+    assert_ne!(stored, DID);
+    assert_eq!(sealed.decode("did", ROW, &stored).unwrap(), DID);
+}
@@ -0,0 +1,97 @@
+use primal_health_client::crypto::{
+    decrypt_record, encrypt_record, open_record, record_hash, seal_record, unwrap_record_key,
+    wrap_record_key, CryptoError, EncryptionKeypair, RecordKey, KEY_ENVELOPE_LEN,
+};
+use primal_health_solana_program::MAX_ENCRYPTED_DATA_LEN;
+use rand_core::{OsRng, UnwrapErr};
+
+const FHIR: &[u8] = br#"{"resourceType":"Observation","code":{"text":"HbA1c"},"valueQuantity":{"value":5.4,"unit":"%"}}"#;
+
+#[test]
+fn sealed_records_open_for_the_recipient() {
+    let mut rng = UnwrapErr(OsRng);
+    let patient = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_record(FHIR, &patient.public, &mut rng).unwrap();
+
+    assert_eq!(sealed.key_envelope.len(), KEY_ENVELOPE_LEN);
+    assert_ne!(sealed.data_hash, [0; 32]);
+    assert!(sealed.encrypted_data.len() <= MAX_ENCRYPTED_DATA_LEN);
+    let opened = open_record(
+        &sealed.data_hash,
+        &sealed.encrypted_data,
+        &sealed.key_envelope,
+        &patient.secret,
+    )
+    .unwrap();
+    assert_eq!(opened, FHIR);
+}
+
+#[test]
+fn a_rewrapped_key_lets_a_grantee_decrypt() {
+    let mut rng = UnwrapErr(OsRng);
+    let grantee = EncryptionKeypair::generate(&mut rng);
+    let key = RecordKey::generate(&mut rng);
+    let (_, encrypted_data) = encrypt_record(&key, FHIR, &mut rng).unwrap();
+
+    let envelope = wrap_record_key(&key, &grantee.public, &mut rng).unwrap();
+    let unwrapped = unwrap_record_key(&envelope, &grantee.secret).unwrap();
+    assert_eq!(decrypt_record(&unwrapped, &encrypted_data).unwrap(), FHIR);
+}
+
+#[test]
+fn other_keys_cannot_unwrap_the_envelope() {
+    let mut rng = UnwrapErr(OsRng);
+    let patient = EncryptionKeypair::generate(&mut rng);
+    let stranger = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_record(FHIR, &patient.public, &mut rng).unwrap();
+
+    assert_eq!(
+        unwrap_record_key(&sealed.key_envelope, &stranger.secret).err(),
+        Some(CryptoError::Decryption)
+    );
+}
+
+#[test]
+fn tampering_is_detected() {
+    let mut rng = UnwrapErr(OsRng);
+    let patient = EncryptionKeypair::generate(&mut rng);
+    let sealed = seal_record(FHIR, &patient.public, &mut rng).unwrap();
+
+    // Swapping in other ciphertext breaks the hash binding
+    let other = seal_record(b"{}", &patient.public, &mut rng).unwrap();
+    assert_eq!(
+        open_record(
+            &sealed.data_hash,
+            &other.encrypted_data,
+            &sealed.key_envelope,
+            &patient.secret
+        )
+        .err(),
+        Some(CryptoError::HashMismatch)
+    );
+
+    // A ciphertext edited together with its hash fails authentication
+    let mut bytes = sealed.encrypted_data.clone().into_bytes();
+    // A middle character never carries base64 padding bits
+    let middle = bytes.len() / 2;
+    bytes[middle] = if bytes[middle] == b'A' { b'B' } else { b'A' };
+    let tampered = String::from_utf8(bytes).unwrap();
+    assert_eq!(
+        open_record(
+            &record_hash(&tampered),
+            &tampered,
+            &sealed.key_envelope,
+            &patient.secret
+        )
+        .err(),
+        Some(CryptoError::Decryption)
+    );
+}
+
+#[test]
+fn records_over_the_on_chain_limit_are_refused() {
+    let mut rng = UnwrapErr(OsRng);
+    let patient = EncryptionKeypair::generate(&mut rng);
+    let result = seal_record(&[0; MAX_ENCRYPTED_DATA_LEN], &patient.public, &mut rng);
+    assert!(matches!(result, Err(CryptoError::RecordTooLarge(_))));
+}
@@ -0,0 +1,176 @@
+use primal_health_client::errors::{error_code, Locale, ProgramError, ERROR_CODES};
+use primal_health_solana_program::ErrorCode;
+
+const IDL: &str = include_str!("../../primal-health-cpi/idls/primal_health_solana_program.json");
+
+fn logs(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn the_code_table_matches_the_idl() {
+    let idl: serde_json::Value = serde_json::from_str(IDL).unwrap();
+    let errors = idl["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), ERROR_CODES.len());
+    for (error, code) in errors.iter().zip(ERROR_CODES) {
+        assert_eq!(error["name"], code.name());
+        assert_eq!(error["code"], u32::from(*code));
+    }
+}
+
+#[test]
+fn every_locale_has_a_template_for_every_error() {
+    for locale in Locale::ALL {
+        let templates = locale.templates();
+        for code in ERROR_CODES {
+            assert!(
+                templates.contains_key(&code.name()),
+                "{locale:?} has no template for {}",
+                code.name()
+            );
+        }
+        assert_eq!(
+            templates.len(),
+            Locale::En.templates().len(),
+            "{locale:?} has templates English lacks"
+        );
+    }
+}
+
+#[test]
+fn numbers_map_to_program_errors() {
+    assert!(matches!(error_code(6000), Some(ErrorCode::Unauthorized)));
+    assert!(error_code(5999).is_none());
+    assert!(error_code(6000 + ERROR_CODES.len() as u32).is_none());
+
+    let error = ProgramError::from_number(u32::from(ErrorCode::GrantInactive));
+    assert!(matches!(
+        error,
+        ProgramError::Program {
+            code: ErrorCode::GrantInactive,
+            ..
+        }
+    ));
+    assert!(matches!(
+        ProgramError::from_number(2003),
+        ProgramError::Framework {
+            number: 2003,
+            name: None,
+            ..
+        }
+    ));
+    assert!(matches!(
+        ProgramError::from_number(9_999),
+        ProgramError::Unknown { number: 9_999 }
+    ));
+}
+
+#[test]
+fn program_errors_are_decoded_from_logs_with_their_explanation() {
+    let number = u32::from(ErrorCode::TenantFeeTooHigh);
+    let logs = logs(&[
+        "Program 2LjMTbA2Z3ZftCr8UCJ3c5cauBq48NRBbXbiy6Zkkhao invoke [1]",
+        "Program log: Instruction: CreateTenant",
+        "Program log: Tenant fee 1500 bps is above the maximum of 1000",
+        &format!(
+            "Program log: AnchorError thrown in programs/primal-health-solana-program/src/lib.rs:3380. Error Code: TenantFeeTooHigh. Error Number: {number}. Error Message: The tenant fee is above the maximum.."
+        ),
+        "Program 2LjMTbA2Z3ZftCr8UCJ3c5cauBq48NRBbXbiy6Zkkhao consumed 5210 of 200000 compute units",
+    ]);
+
+    let error = ProgramError::from_logs(&logs).unwrap();
+    let ProgramError::Program { code, context } = &error else {
+        panic!("expected a program error, got {error:?}");
+    };
+    assert_eq!(u32::from(*code), number);
+    assert_eq!(
+        context.detail.as_deref(),
+        Some("Tenant fee 1500 bps is above the maximum of 1000")
+    );
+    assert_eq!(context.account, None);
+    assert_eq!(
+        error.message(Locale::Es),
+        "La comisión de la red supera el máximo."
+    );
+    assert_eq!(
+        error.message(Locale::En),
+        "The tenant fee is above the maximum."
+    );
+}
+
+#[test]
+fn constraint_failures_keep_the_account_and_compared_keys() {
+    let logs = logs(&[
+        "Program log: Instruction: ProcessPayment",
+        "Program log: AnchorError caused by account: claim_account. Error Code: ConstraintSeeds. Error Number: 2006. Error Message: A seeds constraint was violated.",
+        "Program log: Left:",
+        "Program log: 11111111111111111111111111111111",
+        "Program log: Right:",
+        "Program log: SysvarC1ock11111111111111111111111111111111",
+    ]);
+
+    let error = ProgramError::from_logs(&logs).unwrap();
+    let ProgramError::Framework {
+        number,
+        name,
+        context,
+    } = &error
+    else {
+        panic!("expected a framework error, got {error:?}");
+    };
+    assert_eq!(*number, 2006);
+    assert_eq!(name.as_deref(), Some("ConstraintSeeds"));
+    assert_eq!(context.account.as_deref(), Some("claim_account"));
+    assert_eq!(
+        context.compared,
+        Some((
+            "11111111111111111111111111111111".to_string(),
+            "SysvarC1ock11111111111111111111111111111111".to_string()
+        ))
+    );
+    assert_eq!(context.detail, None);
+    assert_eq!(
+        error.message(Locale::Fr),
+        "La transaction n'a pas passé un contrôle de sécurité sur le compte « claim_account »."
+    );
+}
+
+#[test]
+fn compared_values_are_read_inline() {
+    let logs = logs(&[
+        "Program log: AnchorError thrown in src/lib.rs:10. Error Code: RequireGtViolated. Error Number: 2505. Error Message: A require_gt expression was violated.",
+        "Program log: Left: 0",
+        "Program log: Right: 5",
+    ]);
+    let error = ProgramError::from_logs(&logs).unwrap();
+    assert_eq!(
+        error.context().unwrap().compared,
+        Some(("0".to_string(), "5".to_string()))
+    );
+}
+
+#[test]
+fn logs_without_an_anchor_error_decode_to_nothing() {
+    let logs = logs(&[
+        "Program log: Instruction: InitializePatient",
+        "Program 11111111111111111111111111111111 failed: custom program error: 0x0",
+    ]);
+    assert!(ProgramError::from_logs(&logs).is_none());
+}
+
+#[test]
+fn unknown_numbers_render_the_number() {
+    let error = ProgramError::from_number(9_999);
+    assert_eq!(
+        error.message(Locale::Es),
+        "La transacción falló con el código de error 9999."
+    );
+}
+
+#[test]
+fn locales_are_chosen_by_language_tag() {
+    assert_eq!(Locale::from_tag("es-MX"), Some(Locale::Es));
+    assert_eq!(Locale::from_tag("FR_ca"), Some(Locale::Fr));
+    assert_eq!(Locale::from_tag("en"), Some(Locale::En));
+    assert_eq!(Locale::from_tag("de-DE"), None);
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_client::intake::{parse_837, IntakeError};
+use primal_health_solana_program::ClaimDedup;
+
+// 2024-10-01T00:00:00Z
+const OCTOBER_1: i64 = 1_727_740_800;
+const DAY: i64 = 24 * 60 * 60;
+
+const ISA: &str = "ISA*00*          *00*          *ZZ*SUBMITTER      *ZZ*RECEIVER       \
+*241015*1200*^*00501*000000001*0*P*:~";
+
+fn interchange(body: &str) -> String {
+    format!(
+        "{ISA}\nGS*HC*SUBMITTER*RECEIVER*20241015*1200*1*X*005010X222A1~\n\
+         ST*837*0001*005010X222A1~\nBHT*0019*00*BATCH1*20241015*1200*CH~\n{body}\
+         SE*20*0001~\nGE*1*1~\nIEA*1*000000001~\n"
+    )
+}
+
+const TWO_CLAIMS: &str = "HL*1**20*1~\nNM1*85*2*NORTH CLINIC*****XX*1234567893~\n\
+HL*2*1*22*0~\nSBR*P*18*******CI~\nNM1*IL*1*DOE*JANE****MI*MEMBER001~\n\
+CLM*PCN-1*175.5***11:B:1*Y*A*Y*Y~\nDTP*472*D8*20241001~\n\
+LX*1~\nSV1*HC:99213:25*100*UN*1***1~\n\
+LX*2~\nSV1*HC:80053*75.5*UN*2***1~\nDTP*472*D8*20241003~\n\
+HL*3*1*22*0~\nNM1*IL*1*ROE*RICHARD****MI*MEMBER002~\n\
+CLM*PCN-2*40***11:B:1*Y*A*Y*Y~\nDTP*472*RD8*20241005-20241007~\n\
+LX*1~\nSV1*HC:97110:GP:KX*40*UN*4***1~\n";
+
+#[test]
+fn each_claim_becomes_create_claim_arguments() {
+    let claims = parse_837(&interchange(TWO_CLAIMS), 2).unwrap();
+    assert_eq!(claims.len(), 2);
+
+    let first = &claims[0];
+    assert_eq!(first.control_number, "PCN-1");
+    assert_eq!(first.billing_npi, "1234567893");
+    assert_eq!(first.member_id, "MEMBER001");
+    assert_eq!(first.billed, 17_550);
+    let lines: Vec<_> = first
+        .line_items
+        .iter()
+        .map(|line| {
+            (
+                line.code.as_str(),
+                line.modifier.as_str(),
+                line.units,
+                line.unit_price,
+            )
+        })
+        .collect();
+    assert_eq!(lines, [("99213", "25", 1, 10_000), ("80053", "", 2, 3_775)]);
+    // The claim's date covers the first line; the second has its own
+    assert_eq!(first.service_start, OCTOBER_1);
+    assert_eq!(first.service_end, OCTOBER_1 + 2 * DAY);
+
+    let second = &claims[1];
+    assert_eq!(second.member_id, "MEMBER002");
+    assert_eq!(second.line_items[0].modifier, "GPKX");
+    assert_eq!(second.line_items[0].unit_price, 1_000);
+    assert_eq!(
+        (second.service_start, second.service_end),
+        (OCTOBER_1 + 4 * DAY, OCTOBER_1 + 6 * DAY)
+    );
+}
+
+#[test]
+fn arguments_carry_the_duplicate_fingerprint() {
+    let claims = parse_837(&interchange(TWO_CLAIMS), 6).unwrap();
+    let (patient, provider) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let args = claims[0].create_claim(&patient, &provider);
+
+    assert_eq!(args.claim_id, "PCN-1");
+    assert_eq!(args.line_items[0].unit_price, 100_000_000);
+    assert!(args.attachments.is_empty());
+    assert_eq!(
+        args.fingerprint,
+        ClaimDedup::fingerprint(&patient, &provider, &args.line_items, OCTOBER_1)
+    );
+}
+
+#[test]
+fn other_separators_are_read_from_the_header() {
+    let input = interchange(TWO_CLAIMS)
+        .replace('*', "|")
+        .replace(':', ">")
+        .replace('~', "\r\n");
+    let claims = parse_837(&input, 2).unwrap();
+    assert_eq!(claims[1].line_items[0].code, "97110");
+}
+
+#[test]
+fn inconsistent_claims_are_rejected() {
+    let mismatch = TWO_CLAIMS.replace("CLM*PCN-2*40*", "CLM*PCN-2*41*");
+    assert!(matches!(
+        parse_837(&interchange(&mismatch), 2),
+        Err(IntakeError::ChargeMismatch {
+            billed: 4_100,
+            lines: 4_000,
+            ..
+        })
+    ));
+
+    let undated = TWO_CLAIMS.replace("DTP*472*D8*20241001~\n", "");
+    assert!(matches!(
+        parse_837(&interchange(&undated), 2),
+        Err(IntakeError::MissingServiceDate(pcn)) if pcn == "PCN-1"
+    ));
+
+    let uneven = TWO_CLAIMS.replace("SV1*HC:80053*75.5*UN*2", "SV1*HC:80053*75.51*UN*2");
+    assert!(matches!(
+        parse_837(&interchange(&uneven), 2),
+        Err(IntakeError::Malformed { id, .. }) if id == "SV1"
+    ));
+
+    assert!(matches!(
+        parse_837(&interchange(TWO_CLAIMS), 0),
+        Err(IntakeError::InvalidAmount(amount)) if amount == "175.5"
+    ));
+    let bad_date = TWO_CLAIMS.replace("20241003", "20240231");
+    assert!(matches!(
+        parse_837(&interchange(&bad_date), 2),
+        Err(IntakeError::InvalidDate(_))
+    ));
+}
+
+#[test]
+fn only_837_interchanges_are_accepted() {
+    assert!(matches!(
+        parse_837("GS*HC~", 2),
+        Err(IntakeError::NotAnInterchange)
+    ));
+    let remittance = interchange(TWO_CLAIMS).replace("ST*837*", "ST*835*");
+    assert!(matches!(
+        parse_837(&remittance, 2),
+        Err(IntakeError::UnsupportedTransaction(set)) if set == "835"
+    ));
+}
@@ -0,0 +1,148 @@
+use primal_health_client::remittance::{remittances, to_csv, PaidClaim};
+use primal_health_solana_program::{ClaimAccount, ClaimLineItem, ClaimStatus, LineItemStatus};
+
+// 2024-10-01T00:00:00Z and 2024-11-01T00:00:00Z
+const OCTOBER: i64 = 1_727_740_800;
+const NOVEMBER: i64 = 1_730_419_200;
+
+fn line(code: &str, unit_price: u64, status: LineItemStatus) -> ClaimLineItem {
+    ClaimLineItem {
+        code: code.to_string(),
+        units: 2,
+        unit_price,
+        modifier: String::new(),
+        status,
+        reason_code: if status == LineItemStatus::Denied {
+            50
+        } else {
+            0
+        },
+    }
+}
+
+fn paid(claim_id: &str, provider: u8, status: ClaimStatus, paid_at: i64) -> PaidClaim {
+    let key = |n: u8| [n; 32].into();
+    PaidClaim {
+        address: format!("claim-{claim_id}"),
+        account: ClaimAccount {
+            claim_id: claim_id.to_string(),
+            patient: key(1),
+            provider: key(provider),
+            attachments: Vec::new(),
+            amount: 3_000,
+            line_items: vec![
+                line("99213", 1_000, LineItemStatus::Approved),
+                line("80053", 500, LineItemStatus::Denied),
+            ],
+            status,
+            timestamp: OCTOBER,
+            note_count: 0,
+            rejected_at: 0,
+            reopened: false,
+            verified_at: OCTOBER,
+            principal_paid: 2_000,
+            interest_paid: 10,
+            tenant: Default::default(),
+            mint: Default::default(),
+            external_ref: "CLM-1, rev \"A\"".to_string(),
+            tags: vec!["batch-7".to_string(), "payer-42".to_string()],
+            service_start: OCTOBER - 86_400,
+            service_end: OCTOBER - 86_400,
+            approval_policy: Default::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: Default::default(),
+            insurer: Default::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 10,
+        },
+        paid_at,
+    }
+}
+
+#[test]
+fn claims_are_grouped_by_payer_and_period() {
+    let claims = [
+        paid("b", 2, ClaimStatus::Paid, OCTOBER + 200),
+        paid("a", 2, ClaimStatus::Paid, OCTOBER + 100),
+        paid("c", 2, ClaimStatus::PartiallyPaid, NOVEMBER),
+        paid("d", 3, ClaimStatus::Paid, OCTOBER),
+        paid("e", 2, ClaimStatus::Verified, OCTOBER),
+    ];
+    let batches = remittances(&claims).unwrap();
+
+    let payer = claims[0].account.provider.to_string();
+    let keys: Vec<_> = batches
+        .iter()
+        .map(|batch| (batch.payer == payer, batch.period, batch.claims.len()))
+        .collect();
+    assert_eq!(keys.len(), 3);
+    assert!(keys.contains(&(true, 202410, 2)));
+    assert!(keys.contains(&(true, 202411, 1)));
+    assert!(keys.contains(&(false, 202410, 1)));
+
+    let october = batches
+        .iter()
+        .find(|batch| batch.payer == payer && batch.period == 202410)
+        .unwrap();
+    let ids: Vec<_> = october.claims.iter().map(|c| c.claim_id.as_str()).collect();
+    assert_eq!(ids, ["a", "b"]);
+    assert_eq!(october.total_paid, 4_020);
+    assert_eq!(october.total_interest, 20);
+    assert!(october.mint.is_empty());
+}
+
+#[test]
+fn lines_report_their_adjudication() {
+    let batches = remittances(&[paid("a", 2, ClaimStatus::Paid, OCTOBER)]).unwrap();
+    let claim = &batches[0].claims[0];
+    assert_eq!(
+        (claim.charged, claim.allowed, claim.paid),
+        (3_000, 2_000, 2_010)
+    );
+
+    let lines: Vec<_> = claim
+        .lines
+        .iter()
+        .map(|l| {
+            (
+                l.code.as_str(),
+                l.charged,
+                l.allowed,
+                l.status.as_str(),
+                l.reason_code,
+            )
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            ("99213", 2_000, 2_000, "Approved", 0),
+            ("80053", 1_000, 0, "Denied", 50),
+        ]
+    );
+}
+
+#[test]
+fn csv_has_a_row_per_line_with_quoted_fields() {
+    let batches = remittances(&[paid("a", 2, ClaimStatus::Paid, OCTOBER)]).unwrap();
+    let csv = to_csv(&batches);
+    let rows: Vec<_> = csv.lines().collect();
+
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("payer,period,mint,claim,"));
+    assert!(rows[1].contains(",202410,,claim-a,a,\"CLM-1, rev \"\"A\"\"\",batch-7;payer-42,"));
+    assert!(rows[1].ends_with(",0,99213,,2,2000,2000,Approved,0"));
+    assert!(rows[2].ends_with(",1,80053,,2,1000,0,Denied,50"));
+}
+
+#[test]
+fn a_payment_serializes_to_json() {
+    let batches = remittances(&[paid("a", 2, ClaimStatus::Paid, OCTOBER)]).unwrap();
+    let json = serde_json::to_value(&batches).unwrap();
+    assert_eq!(json[0]["period"], 202410);
+    assert_eq!(json[0]["claims"][0]["external_ref"], "CLM-1, rev \"A\"");
+    assert_eq!(json[0]["claims"][0]["lines"][1]["reason_code"], 50);
+}
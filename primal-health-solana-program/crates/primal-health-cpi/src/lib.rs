@@ -0,0 +1,19 @@
+//! CPI interface to the Primal Health program, for Anchor programs that call
+//! into it or read its accounts without depending on the program crate.
+//!
+//! The instruction builders, account types and constants are generated by
+//! `declare_program!` from the committed IDL in `idls/`, which is regenerated
+//! with `anchor idl build -o crates/primal-health-cpi/idls/primal_health_solana_program.json`
+//! from the workspace root whenever the program's interface changes. [`pda`]
+//! derives the program's addresses and [`verify`] holds the checks callers
+//! usually need before trusting an account.
+
+use anchor_lang::prelude::*;
+
+pub mod pda;
+pub mod verify;
+
+declare_program!(primal_health_solana_program);
+
+pub use self::primal_health_solana_program::program::PrimalHealthSolanaProgram;
+pub use self::primal_health_solana_program::{accounts, constants, cpi, events, types, ID};
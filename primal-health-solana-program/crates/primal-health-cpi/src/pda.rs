@@ -0,0 +1,297 @@
+//! Addresses of the program's accounts, derived with the same seeds as the
+//! program.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::ID;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+pub fn patient(authority: &Pubkey) -> Pubkey {
+    find(&[b"patient", authority.as_ref()])
+}
+
+pub fn provider(authority: &Pubkey) -> Pubkey {
+    find(&[b"provider", authority.as_ref()])
+}
+
+pub fn insurer(authority: &Pubkey) -> Pubkey {
+    find(&[b"insurer", authority.as_ref()])
+}
+
+/// The credentialing authority's record of whether `provider` may verify
+/// claims.
+pub fn provider_credential(provider: &Pubkey) -> Pubkey {
+    find(&[b"provider_credential", provider.as_ref()])
+}
+
+pub fn health_data(data_hash: &[u8; 32]) -> Pubkey {
+    find(&[b"health_data", data_hash])
+}
+
+/// A record whose payload is written on-chain in chunks.
+pub fn health_data_v2(data_hash: &[u8; 32]) -> Pubkey {
+    find(&[b"health_data_v2", data_hash])
+}
+
+/// Count of the records appended for `owner`.
+pub fn health_data_index(owner: &Pubkey) -> Pubkey {
+    find(&[b"health_data_index", owner.as_ref()])
+}
+
+/// The record appended for `owner` at `record_index`.
+pub fn health_record(owner: &Pubkey, record_index: u64) -> Pubkey {
+    find(&[
+        b"health_record",
+        owner.as_ref(),
+        &record_index.to_le_bytes(),
+    ])
+}
+
+/// A device registered to submit readings for `patient`.
+pub fn device(patient: &Pubkey, device: &Pubkey) -> Pubkey {
+    find(&[b"device", patient.as_ref(), device.as_ref()])
+}
+
+/// One UTC day of readings rolled up for `device_account`.
+pub fn device_rollup(device_account: &Pubkey, day: i64) -> Pubkey {
+    find(&[
+        b"device_rollup",
+        device_account.as_ref(),
+        &day.to_le_bytes(),
+    ])
+}
+
+/// Onboarding vault funded for `sponsor` to pay patients' rent.
+pub fn sponsor_vault(sponsor: &Pubkey) -> Pubkey {
+    find(&[b"sponsor_vault", sponsor.as_ref()])
+}
+
+pub fn grant(patient: &Pubkey, grantee: &Pubkey) -> Pubkey {
+    find(&[b"grant", patient.as_ref(), grantee.as_ref()])
+}
+
+pub fn access_offer(owner: &Pubkey, offer_id: &str) -> Pubkey {
+    find(&[b"access_offer", owner.as_ref(), offer_id.as_bytes()])
+}
+
+/// The grant `buyer` bought from the access offer at `offer`.
+pub fn access_purchase(offer: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    find(&[b"access_purchase", offer.as_ref(), buyer.as_ref()])
+}
+
+pub fn subscription_plan(owner: &Pubkey, plan_id: &str) -> Pubkey {
+    find(&[b"subscription_plan", owner.as_ref(), plan_id.as_bytes()])
+}
+
+/// `subscriber`'s subscription to the plan at `plan`.
+pub fn plan_subscription(plan: &Pubkey, subscriber: &Pubkey) -> Pubkey {
+    find(&[b"plan_subscription", plan.as_ref(), subscriber.as_ref()])
+}
+
+/// `patient`'s consent to `provider` using the health-data account at
+/// `record`.
+pub fn consent(patient: &Pubkey, provider: &Pubkey, record: &Pubkey) -> Pubkey {
+    find(&[
+        b"consent",
+        patient.as_ref(),
+        provider.as_ref(),
+        record.as_ref(),
+    ])
+}
+
+pub fn research_consent(patient: &Pubkey) -> Pubkey {
+    find(&[b"research_consent", patient.as_ref()])
+}
+
+/// The re-encryption request made under the consent at `consent`.
+pub fn reencryption_request(consent: &Pubkey) -> Pubkey {
+    find(&[b"reencryption", consent.as_ref()])
+}
+
+pub fn consent_ledger(patient: &Pubkey) -> Pubkey {
+    find(&[b"consent_ledger", patient.as_ref()])
+}
+
+pub fn dependent_link(guardian: &Pubkey, dependent: &Pubkey) -> Pubkey {
+    find(&[b"dependent", guardian.as_ref(), dependent.as_ref()])
+}
+
+pub fn delegate(patient: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    find(&[b"delegate", patient.as_ref(), delegate.as_ref()])
+}
+
+pub fn encryption_key(owner: &Pubkey) -> Pubkey {
+    find(&[b"encryption_key", owner.as_ref()])
+}
+
+/// The key envelope of the grant at `grant`.
+pub fn key_envelope(grant: &Pubkey) -> Pubkey {
+    find(&[b"key_envelope", grant.as_ref()])
+}
+
+pub fn claim(claim_id: &str) -> Pubkey {
+    find(&[b"claim", claim_id.as_bytes()])
+}
+
+/// The read grant a claim at `claim` gives its provider.
+pub fn claim_grant(claim: &Pubkey) -> Pubkey {
+    find(&[b"claim_grant", claim.as_ref()])
+}
+
+pub fn payment_schedule(claim: &Pubkey) -> Pubkey {
+    find(&[b"payment_schedule", claim.as_ref()])
+}
+
+/// The dispute of the rejected claim at `claim`.
+pub fn claim_dispute(claim: &Pubkey) -> Pubkey {
+    find(&[b"claim_dispute", claim.as_ref()])
+}
+
+pub fn second_opinion(claim: &Pubkey, consultant: &Pubkey) -> Pubkey {
+    find(&[b"second_opinion", claim.as_ref(), consultant.as_ref()])
+}
+
+pub fn care_team(patient: &Pubkey, team_id: u32) -> Pubkey {
+    find(&[b"care_team", patient.as_ref(), &team_id.to_le_bytes()])
+}
+
+/// `provider`'s emergency access request for the record with `data_hash`.
+pub fn emergency_access(data_hash: &[u8; 32], provider: &Pubkey) -> Pubkey {
+    find(&[b"emergency_access", data_hash, provider.as_ref()])
+}
+
+pub fn audit_trail(subject: &Pubkey) -> Pubkey {
+    find(&[b"audit_trail", subject.as_ref()])
+}
+
+/// Entry `sequence` of `subject`'s audit trail.
+pub fn audit_entry(subject: &Pubkey, sequence: u64) -> Pubkey {
+    find(&[b"audit_entry", subject.as_ref(), &sequence.to_le_bytes()])
+}
+
+pub fn fee_schedule(provider: &Pubkey) -> Pubkey {
+    find(&[b"fee_schedule", provider.as_ref()])
+}
+
+/// Appointment `appointment_id` booked with `provider`.
+pub fn appointment(provider: &Pubkey, appointment_id: &str) -> Pubkey {
+    find(&[b"appointment", provider.as_ref(), appointment_id.as_bytes()])
+}
+
+pub fn config() -> Pubkey {
+    find(&[b"config"])
+}
+
+pub fn protocol_treasury() -> Pubkey {
+    find(&[b"protocol_treasury"])
+}
+
+pub fn fee_rebates() -> Pubkey {
+    find(&[b"fee_rebates"])
+}
+
+/// The provider's lamport payment volume for a `YYYYMM` settlement period.
+pub fn provider_volume(provider: &Pubkey, period: u32) -> Pubkey {
+    find(&[b"provider_volume", provider.as_ref(), &period.to_le_bytes()])
+}
+
+/// Protocol fees paid in `mint`. Its vault is a token account this address
+/// owns, such as its associated token account.
+pub fn mint_treasury(mint: &Pubkey) -> Pubkey {
+    find(&[b"mint_treasury", mint.as_ref()])
+}
+
+pub fn clawback_config() -> Pubkey {
+    find(&[b"clawback_config"])
+}
+
+/// The hold on a lamport payment of the claim at `claim`.
+pub fn payment_hold(claim: &Pubkey) -> Pubkey {
+    find(&[b"payment_hold", claim.as_ref()])
+}
+
+/// The facilitator operated by `authority`.
+pub fn facilitator(authority: &Pubkey) -> Pubkey {
+    find(&[b"facilitator", authority.as_ref()])
+}
+
+pub fn crank_vault() -> Pubkey {
+    find(&[b"crank_vault"])
+}
+
+pub fn feature_flags() -> Pubkey {
+    find(&[b"feature_flags"])
+}
+
+pub fn dispute_config() -> Pubkey {
+    find(&[b"dispute_config"])
+}
+
+pub fn tenant(tenant_id: &str) -> Pubkey {
+    find(&[b"tenant", tenant_id.as_bytes()])
+}
+
+/// Membership of `member` in the tenant at `tenant`.
+pub fn tenant_member(tenant: &Pubkey, member: &Pubkey) -> Pubkey {
+    find(&[b"tenant_member", tenant.as_ref(), member.as_ref()])
+}
+
+/// Premium and research discount of the tenant's plan.
+pub fn plan_benefits(tenant: &Pubkey) -> Pubkey {
+    find(&[b"plan_benefits", tenant.as_ref()])
+}
+
+/// The patient's premium for a `YYYYMM` month of the tenant's plan.
+pub fn premium_payment(tenant: &Pubkey, patient: &Pubkey, period: u32) -> Pubkey {
+    find(&[
+        b"premium_payment",
+        tenant.as_ref(),
+        patient.as_ref(),
+        &period.to_le_bytes(),
+    ])
+}
+
+/// The patient's out-of-pocket tally for a calendar year of the tenant's
+/// plan.
+pub fn out_of_pocket(tenant: &Pubkey, patient: &Pubkey, year: u32) -> Pubkey {
+    find(&[
+        b"out_of_pocket",
+        tenant.as_ref(),
+        patient.as_ref(),
+        &year.to_le_bytes(),
+    ])
+}
+
+/// The provider's collateral against fraud.
+pub fn provider_stake(provider: &Pubkey) -> Pubkey {
+    find(&[b"provider_stake", provider.as_ref()])
+}
+
+/// The arbiter's fraud finding on a disputed claim.
+pub fn fraud_finding(claim: &Pubkey) -> Pubkey {
+    find(&[b"fraud_finding", claim.as_ref()])
+}
+
+/// A claim filed in the tenant at `tenant`.
+pub fn tenant_claim(tenant: &Pubkey, claim_id: &str) -> Pubkey {
+    find(&[b"claim", tenant.as_ref(), claim_id.as_bytes()])
+}
+
+/// Settlement summary for a `YYYYMM` period; for claims the provider is the
+/// payer and the patient the payee.
+pub fn settlement(payer: &Pubkey, payee: &Pubkey, period: u32) -> Pubkey {
+    find(&[
+        b"settlement",
+        payer.as_ref(),
+        payee.as_ref(),
+        &period.to_le_bytes(),
+    ])
+}
+
+/// Quality attestation by `validator` on the health-data account at `record`.
+pub fn quality_attestation(record: &Pubkey, validator: &Pubkey) -> Pubkey {
+    find(&[b"quality", record.as_ref(), validator.as_ref()])
+}
@@ -0,0 +1,107 @@
+//! Checks over the program's accounts, mirroring the ones it makes itself.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AnchorSerialize;
+use solana_program::hash::hashv;
+
+use crate::accounts::{
+    AccessGrant, ClaimAccount, ConsentAccount, ConsentLedger, DataAccessOffer, PlanSubscription,
+};
+use crate::events::ConsentReceipt;
+use crate::types::{ClaimStatus, DataCategory};
+
+/// Whether `grant` is active at `now` and gives `grantee` every bit of
+/// `scope` over `patient`'s records.
+pub fn grant_allows(
+    grant: &AccessGrant,
+    patient: &Pubkey,
+    grantee: &Pubkey,
+    scope: u8,
+    now: i64,
+) -> bool {
+    grant.patient == *patient
+        && grant.grantee == *grantee
+        && !grant.revoked
+        && (grant.expires_at == 0 || now < grant.expires_at)
+        && grant.scope & scope == scope
+}
+
+/// Whether `grant`, read from [`crate::pda::access_purchase`] of the offer
+/// and `buyer`, lets `buyer` fetch `resource_hash` at `now`. This is the check
+/// an x402 facilitator makes before serving a paid resource.
+pub fn purchase_allows(
+    offer: &DataAccessOffer,
+    grant: &AccessGrant,
+    buyer: &Pubkey,
+    resource_hash: &[u8; 32],
+    now: i64,
+) -> bool {
+    offer.resource_hash == *resource_hash
+        && grant_allows(
+            grant,
+            &offer.owner,
+            buyer,
+            crate::constants::GRANT_SCOPE_READ,
+            now,
+        )
+}
+
+/// Whether `subscription`, read from [`crate::pda::plan_subscription`], lets
+/// `subscriber` access `owner`'s records of `category` with every bit of
+/// `scope` at `now`. This is the check an x402 facilitator makes instead of
+/// charging a subscriber for each query.
+pub fn subscription_allows(
+    subscription: &PlanSubscription,
+    owner: &Pubkey,
+    subscriber: &Pubkey,
+    category: &DataCategory,
+    scope: u8,
+    now: i64,
+) -> bool {
+    subscription.owner == *owner
+        && subscription.subscriber == *subscriber
+        && now < subscription.current_period_end
+        && subscription.categories & (1 << *category as u8) != 0
+        && subscription.scope & scope == scope
+}
+
+/// Whether `consent` is active at `now` and lets `provider` use the record
+/// `data_hash` with every bit of `scope`.
+pub fn consent_allows(
+    consent: &ConsentAccount,
+    provider: &Pubkey,
+    data_hash: &[u8; 32],
+    scope: u8,
+    now: i64,
+) -> bool {
+    consent.provider == *provider
+        && consent.data_hash == *data_hash
+        && (consent.expires_at == 0 || now < consent.expires_at)
+        && consent.scope & scope == scope
+}
+
+/// Whether `claim` is between `patient` and `provider` and has been paid.
+pub fn claim_paid(claim: &ClaimAccount, patient: &Pubkey, provider: &Pubkey) -> bool {
+    claim.patient == *patient
+        && claim.provider == *provider
+        && matches!(claim.status, ClaimStatus::Paid)
+}
+
+/// The consent ledger head after folding `receipt` into `head`.
+pub fn chain_receipt(head: [u8; 32], receipt: &ConsentReceipt) -> [u8; 32] {
+    let receipt = receipt
+        .try_to_vec()
+        .expect("serializing a receipt into a Vec cannot fail");
+    hashv(&[&head, &receipt]).to_bytes()
+}
+
+/// Whether `receipts`, in order and starting from the ledger's first receipt,
+/// are exactly those folded into `ledger`.
+pub fn receipts_match_ledger(ledger: &ConsentLedger, receipts: &[ConsentReceipt]) -> bool {
+    let numbered = receipts
+        .iter()
+        .enumerate()
+        .all(|(i, receipt)| receipt.patient == ledger.patient && receipt.sequence == i as u64 + 1);
+    let head = receipts.iter().fold([0; 32], chain_receipt);
+    numbered && receipts.len() as u64 == ledger.receipt_count && head == ledger.head
+}
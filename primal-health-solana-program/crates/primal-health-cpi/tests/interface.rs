@@ -0,0 +1,486 @@
+//! The generated interface against the program crate it was generated from.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, AnchorSerialize, InstructionData,
+};
+use primal_health_cpi::primal_health_solana_program::client::args;
+use primal_health_cpi::{accounts, constants, events, pda, types, verify};
+use primal_health_solana_program as program;
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+/// Re-reads a program account through the generated type.
+fn reread<T: AccountDeserialize>(account: &impl AccountSerialize) -> T {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    T::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn receipt(sequence: u64) -> program::ConsentReceipt {
+    program::ConsentReceipt {
+        patient: key(1),
+        actor: key(1),
+        grantee: key(2),
+        action: program::ConsentAction::Grant,
+        scope: program::GRANT_SCOPE_READ,
+        lawful_basis: program::LawfulBasis::Consent,
+        expires_at: 0,
+        timestamp: 1_700_000_000 + sequence as i64,
+        sequence: 0,
+    }
+}
+
+#[test]
+fn program_id_and_constants_match_the_program() {
+    assert_eq!(primal_health_cpi::ID, program::ID);
+    assert_eq!(constants::GRANT_SCOPE_READ, program::GRANT_SCOPE_READ);
+    assert_eq!(constants::GRANT_SCOPE_ALL, program::GRANT_SCOPE_ALL);
+    assert_eq!(
+        constants::GUARDIAN_PERMISSIONS_ALL,
+        program::GUARDIAN_PERMISSIONS_ALL
+    );
+}
+
+#[test]
+fn pdas_match_the_program_seeds() {
+    let program_pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &program::ID).0;
+    let (patient, grantee) = (key(1), key(2));
+
+    assert_eq!(
+        pda::grant(&patient, &grantee),
+        program_pda(&[b"grant", patient.as_ref(), grantee.as_ref()])
+    );
+    assert_eq!(pda::claim("c1"), program_pda(&[b"claim", b"c1"]));
+    assert_eq!(
+        pda::claim_grant(&patient),
+        program_pda(&[b"claim_grant", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::payment_schedule(&patient),
+        program_pda(&[b"payment_schedule", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::second_opinion(&patient, &grantee),
+        program_pda(&[b"second_opinion", patient.as_ref(), grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::care_team(&patient, 2),
+        program_pda(&[b"care_team", patient.as_ref(), &2u32.to_le_bytes()])
+    );
+    assert_eq!(
+        pda::emergency_access(&[0xab; 32], &grantee),
+        program_pda(&[b"emergency_access", &[0xab; 32], grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::audit_trail(&patient),
+        program_pda(&[b"audit_trail", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::audit_entry(&patient, 3),
+        program_pda(&[b"audit_entry", patient.as_ref(), &3u64.to_le_bytes()])
+    );
+    assert_eq!(
+        pda::fee_schedule(&grantee),
+        program_pda(&[b"fee_schedule", grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::appointment(&grantee, "appt-1"),
+        program_pda(&[b"appointment", grantee.as_ref(), b"appt-1"])
+    );
+    assert_eq!(
+        pda::claim_dispute(&patient),
+        program_pda(&[b"claim_dispute", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::fraud_finding(&patient),
+        program_pda(&[b"fraud_finding", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::provider_stake(&grantee),
+        program_pda(&[b"provider_stake", grantee.as_ref()])
+    );
+    assert_eq!(pda::config(), program_pda(&[b"config"]));
+    assert_eq!(
+        pda::protocol_treasury(),
+        program_pda(&[b"protocol_treasury"])
+    );
+    assert_eq!(pda::fee_rebates(), program_pda(&[b"fee_rebates"]));
+    assert_eq!(
+        pda::provider_volume(&grantee, 202410),
+        program_pda(&[
+            b"provider_volume",
+            grantee.as_ref(),
+            &202410u32.to_le_bytes()
+        ])
+    );
+    let usdc = key(3);
+    assert_eq!(
+        pda::mint_treasury(&usdc),
+        program_pda(&[b"mint_treasury", usdc.as_ref()])
+    );
+    assert_eq!(pda::dispute_config(), program_pda(&[b"dispute_config"]));
+    assert_eq!(pda::clawback_config(), program_pda(&[b"clawback_config"]));
+    assert_eq!(pda::crank_vault(), program_pda(&[b"crank_vault"]));
+    assert_eq!(
+        pda::payment_hold(&usdc),
+        program_pda(&[b"payment_hold", usdc.as_ref()])
+    );
+    assert_eq!(
+        pda::facilitator(&grantee),
+        program_pda(&[b"facilitator", grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::insurer(&grantee),
+        program_pda(&[b"insurer", grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::delegate(&patient, &grantee),
+        program_pda(&[b"delegate", patient.as_ref(), grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::device(&patient, &grantee),
+        program_pda(&[b"device", patient.as_ref(), grantee.as_ref()])
+    );
+    assert_eq!(
+        pda::device_rollup(&grantee, 19_675),
+        program_pda(&[b"device_rollup", grantee.as_ref(), &19_675i64.to_le_bytes()])
+    );
+    assert_eq!(
+        pda::provider_credential(&grantee),
+        program_pda(&[b"provider_credential", grantee.as_ref()])
+    );
+    let record = pda::health_data(&[0xab; 32]);
+    assert_eq!(record, program_pda(&[b"health_data", &[0xab; 32]]));
+    assert_eq!(
+        pda::health_data_v2(&[0xab; 32]),
+        program_pda(&[b"health_data_v2", &[0xab; 32]])
+    );
+    assert_eq!(
+        pda::consent(&patient, &grantee, &record),
+        program_pda(&[
+            b"consent",
+            patient.as_ref(),
+            grantee.as_ref(),
+            record.as_ref()
+        ])
+    );
+    assert_eq!(
+        pda::research_consent(&patient),
+        program_pda(&[b"research_consent", patient.as_ref()])
+    );
+    assert_eq!(
+        pda::reencryption_request(&record),
+        program_pda(&[b"reencryption", record.as_ref()])
+    );
+    assert_eq!(
+        pda::health_record(&patient, 2),
+        program_pda(&[b"health_record", patient.as_ref(), &2u64.to_le_bytes()])
+    );
+    let offer = pda::access_offer(&patient, "labs");
+    assert_eq!(
+        offer,
+        program_pda(&[b"access_offer", patient.as_ref(), b"labs"])
+    );
+    assert_eq!(
+        pda::access_purchase(&offer, &grantee),
+        program_pda(&[b"access_purchase", offer.as_ref(), grantee.as_ref()])
+    );
+    let plan = pda::subscription_plan(&patient, "monthly");
+    assert_eq!(
+        plan,
+        program_pda(&[b"subscription_plan", patient.as_ref(), b"monthly"])
+    );
+    assert_eq!(
+        pda::plan_subscription(&plan, &grantee),
+        program_pda(&[b"plan_subscription", plan.as_ref(), grantee.as_ref()])
+    );
+    let tenant = pda::tenant("north");
+    assert_eq!(
+        pda::tenant_claim(&tenant, "c1"),
+        program_pda(&[b"claim", tenant.as_ref(), b"c1"])
+    );
+    assert_eq!(
+        pda::plan_benefits(&tenant),
+        program_pda(&[b"plan_benefits", tenant.as_ref()])
+    );
+    assert_eq!(
+        pda::premium_payment(&tenant, &patient, 202410),
+        program_pda(&[
+            b"premium_payment",
+            tenant.as_ref(),
+            patient.as_ref(),
+            &202410u32.to_le_bytes()
+        ])
+    );
+    assert_eq!(
+        pda::out_of_pocket(&tenant, &patient, 2024),
+        program_pda(&[
+            b"out_of_pocket",
+            tenant.as_ref(),
+            patient.as_ref(),
+            &2024u32.to_le_bytes()
+        ])
+    );
+    assert_eq!(
+        pda::settlement(&grantee, &patient, 202410),
+        program_pda(&[
+            b"settlement",
+            grantee.as_ref(),
+            patient.as_ref(),
+            &202410u32.to_le_bytes()
+        ])
+    );
+}
+
+#[test]
+fn instruction_data_matches_the_program() {
+    let generated = args::GrantAccess {
+        scope: constants::GRANT_SCOPE_READ,
+        expires_at: 1_800_000_000,
+        lawful_basis: types::LawfulBasis::Contract,
+        key_envelope: vec![7; 80],
+    };
+    let expected = program::instruction::GrantAccess {
+        scope: program::GRANT_SCOPE_READ,
+        expires_at: 1_800_000_000,
+        lawful_basis: program::LawfulBasis::Contract,
+        key_envelope: vec![7; 80],
+    };
+    assert_eq!(generated.data(), expected.data());
+}
+
+#[test]
+fn grants_read_through_the_generated_type_are_checked() {
+    let grant: accounts::AccessGrant = reread(&program::AccessGrant {
+        patient: key(1),
+        grantee: key(2),
+        scope: program::GRANT_SCOPE_READ,
+        granted_at: 1_700_000_000,
+        expires_at: 1_800_000_000,
+        revoked: false,
+        lawful_basis: program::LawfulBasis::Consent,
+        parent: Pubkey::default(),
+        depth: 0,
+        delegations: 0,
+        account_version: 2,
+    });
+    let read = constants::GRANT_SCOPE_READ;
+
+    assert!(verify::grant_allows(
+        &grant,
+        &key(1),
+        &key(2),
+        read,
+        1_750_000_000
+    ));
+    assert!(!verify::grant_allows(
+        &grant,
+        &key(1),
+        &key(3),
+        read,
+        1_750_000_000
+    ));
+    assert!(!verify::grant_allows(
+        &grant,
+        &key(1),
+        &key(2),
+        read,
+        1_800_000_000
+    ));
+    assert!(!verify::grant_allows(
+        &grant,
+        &key(1),
+        &key(2),
+        constants::GRANT_SCOPE_WRITE,
+        1_750_000_000
+    ));
+}
+
+#[test]
+fn consent_is_checked_against_its_record_and_scope() {
+    let consent: accounts::ConsentAccount = reread(&program::ConsentAccount {
+        patient: key(1),
+        provider: key(2),
+        record: pda::health_data(&[0xab; 32]),
+        data_hash: [0xab; 32],
+        scope: program::CONSENT_SCOPE_READ | program::CONSENT_SCOPE_RESEARCH,
+        granted_at: 1_700_000_000,
+        expires_at: 1_800_000_000,
+        account_version: 2,
+    });
+    let research = constants::CONSENT_SCOPE_RESEARCH;
+
+    assert!(verify::consent_allows(
+        &consent,
+        &key(2),
+        &[0xab; 32],
+        research,
+        1_750_000_000
+    ));
+    assert!(!verify::consent_allows(
+        &consent,
+        &key(3),
+        &[0xab; 32],
+        research,
+        1_750_000_000
+    ));
+    assert!(!verify::consent_allows(
+        &consent,
+        &key(2),
+        &[0xcd; 32],
+        research,
+        1_750_000_000
+    ));
+    assert!(!verify::consent_allows(
+        &consent,
+        &key(2),
+        &[0xab; 32],
+        constants::CONSENT_SCOPE_SHARE,
+        1_750_000_000
+    ));
+    assert!(!verify::consent_allows(
+        &consent,
+        &key(2),
+        &[0xab; 32],
+        research,
+        1_800_000_000
+    ));
+}
+
+#[test]
+fn purchased_access_is_checked_against_its_offer() {
+    let offer: accounts::DataAccessOffer = reread(&program::DataAccessOffer {
+        owner: key(1),
+        offer_id: "labs".to_string(),
+        resource_hash: [0xab; 32],
+        price: 5_000,
+        access_secs: 3_600,
+        sales: 1,
+        created_at: 1_700_000_000,
+        price_tiers: Vec::new(),
+        account_version: 3,
+    });
+    let mut grant = program::AccessGrant {
+        patient: key(1),
+        grantee: Pubkey::default(),
+        scope: 0,
+        granted_at: 0,
+        expires_at: 0,
+        revoked: false,
+        lawful_basis: program::LawfulBasis::Consent,
+        parent: Pubkey::default(),
+        depth: 0,
+        delegations: 0,
+        account_version: 2,
+    };
+    grant.extend_purchase(3_600, 1_700_000_100).unwrap();
+    grant.grantee = key(2);
+    let grant: accounts::AccessGrant = reread(&grant);
+
+    assert!(verify::purchase_allows(
+        &offer,
+        &grant,
+        &key(2),
+        &[0xab; 32],
+        1_700_003_000
+    ));
+    assert!(!verify::purchase_allows(
+        &offer,
+        &grant,
+        &key(2),
+        &[0xcd; 32],
+        1_700_003_000
+    ));
+    assert!(!verify::purchase_allows(
+        &offer,
+        &grant,
+        &key(3),
+        &[0xab; 32],
+        1_700_003_000
+    ));
+    assert!(!verify::purchase_allows(
+        &offer,
+        &grant,
+        &key(2),
+        &[0xab; 32],
+        1_700_003_700
+    ));
+}
+
+#[test]
+fn plan_subscriptions_are_checked_until_their_period_ends() {
+    let subscription: accounts::PlanSubscription = reread(&program::PlanSubscription {
+        plan: key(3),
+        owner: key(1),
+        subscriber: key(2),
+        categories: program::DataCategory::LabResult.bit(),
+        scope: program::GRANT_SCOPE_READ,
+        current_period_end: 1_700_003_600,
+        periods_paid: 1,
+        started_at: 1_700_000_000,
+        account_version: 1,
+    });
+    let lab = types::DataCategory::LabResult;
+
+    assert!(verify::subscription_allows(
+        &subscription,
+        &key(1),
+        &key(2),
+        &lab,
+        constants::GRANT_SCOPE_READ,
+        1_700_003_000
+    ));
+    assert!(!verify::subscription_allows(
+        &subscription,
+        &key(1),
+        &key(3),
+        &lab,
+        constants::GRANT_SCOPE_READ,
+        1_700_003_000
+    ));
+    assert!(!verify::subscription_allows(
+        &subscription,
+        &key(1),
+        &key(2),
+        &types::DataCategory::Imaging,
+        constants::GRANT_SCOPE_READ,
+        1_700_003_000
+    ));
+    assert!(!verify::subscription_allows(
+        &subscription,
+        &key(1),
+        &key(2),
+        &lab,
+        constants::GRANT_SCOPE_READ,
+        1_700_003_600
+    ));
+}
+
+#[test]
+fn emitted_receipts_verify_against_the_ledger() {
+    let mut ledger = program::ConsentLedger {
+        patient: Pubkey::default(),
+        receipt_count: 0,
+        head: [0; 32],
+        account_version: 1,
+    };
+    let receipts: Vec<events::ConsentReceipt> = (1..=3)
+        .map(|n| {
+            let mut receipt = receipt(n);
+            ledger.append(&mut receipt).unwrap();
+            let bytes = receipt.try_to_vec().unwrap();
+            events::ConsentReceipt::try_from_slice(&bytes).unwrap()
+        })
+        .collect();
+    let ledger: accounts::ConsentLedger = reread(&ledger);
+
+    assert!(verify::receipts_match_ledger(&ledger, &receipts));
+    assert!(!verify::receipts_match_ledger(&ledger, &receipts[..2]));
+
+    let mut tampered = receipts.clone();
+    tampered[1].timestamp += 1;
+    assert!(!verify::receipts_match_ledger(&ledger, &tampered));
+}
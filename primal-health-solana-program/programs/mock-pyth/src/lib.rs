@@ -0,0 +1,123 @@
+//! Mock Pyth pull-oracle for tests.
+//!
+//! Price accounts use the same name, discriminator and Borsh layout as the Pyth
+//! receiver's `PriceUpdateV2`, so code that decodes real price updates can read
+//! them unchanged; only the owning program id differs. Prices are set directly
+//! by the feed's write authority, with no signature verification.
+
+use anchor_lang::prelude::*;
+
+declare_id!("FtEK1G3kmRquciQUEzRBFW8AWZuyrXGjvPpfWaDi65XF");
+
+#[program]
+pub mod mock_pyth {
+    use super::*;
+
+    pub fn initialize_price_feed(
+        ctx: Context<InitializePriceFeed>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let price_update = &mut ctx.accounts.price_update;
+        price_update.write_authority = ctx.accounts.authority.key();
+        price_update.verification_level = VerificationLevel::Full;
+        price_update.price_message = PriceFeedMessage {
+            feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time: clock.unix_timestamp,
+            prev_publish_time: clock.unix_timestamp,
+            ema_price: price,
+            ema_conf: conf,
+        };
+        price_update.posted_slot = clock.slot;
+        Ok(())
+    }
+
+    /// Publishes a new price. `publish_time` is taken as given so tests can
+    /// produce stale or future-dated updates.
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: i64,
+    ) -> Result<()> {
+        let price_update = &mut ctx.accounts.price_update;
+        let message = &mut price_update.price_message;
+        message.prev_publish_time = message.publish_time;
+        message.price = price;
+        message.conf = conf;
+        message.exponent = exponent;
+        message.publish_time = publish_time;
+        message.ema_price = price;
+        message.ema_conf = conf;
+        price_update.posted_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    pub fn set_verification_level(
+        ctx: Context<SetPrice>,
+        verification_level: VerificationLevel,
+    ) -> Result<()> {
+        ctx.accounts.price_update.verification_level = verification_level;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct InitializePriceFeed<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PriceUpdateV2::LEN,
+        seeds = [b"price_feed", feed_id.as_ref()],
+        bump
+    )]
+    pub price_update: Account<'info, PriceUpdateV2>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(mut, has_one = write_authority)]
+    pub price_update: Account<'info, PriceUpdateV2>,
+    pub write_authority: Signer<'info>,
+}
+
+#[account]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    pub const LEN: usize = 8 + 32 + 2 + 32 + 8 + 8 + 4 + 8 + 8 + 8 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Discriminator};
+use mock_pyth::{PriceFeedMessage, PriceUpdateV2, VerificationLevel};
+
+/// Discriminator of the Pyth receiver's `PriceUpdateV2` account.
+const PYTH_PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[test]
+fn price_update_matches_pyth_receiver_layout() {
+    assert_eq!(
+        PriceUpdateV2::DISCRIMINATOR,
+        PYTH_PRICE_UPDATE_V2_DISCRIMINATOR
+    );
+
+    let update = PriceUpdateV2 {
+        write_authority: Pubkey::new_unique(),
+        verification_level: VerificationLevel::Partial { num_signatures: 3 },
+        price_message: PriceFeedMessage {
+            feed_id: [7; 32],
+            price: 6_512_345_000,
+            conf: 1_250_000,
+            exponent: -8,
+            publish_time: 1_700_000_000,
+            prev_publish_time: 1_699_999_999,
+            ema_price: 6_500_000_000,
+            ema_conf: 1_300_000,
+        },
+        posted_slot: 42,
+    };
+    let mut data = Vec::new();
+    update.try_serialize(&mut data).unwrap();
+    // Partial is the longest verification level variant
+    assert_eq!(data.len(), PriceUpdateV2::LEN);
+    assert_eq!(data[..8], PYTH_PRICE_UPDATE_V2_DISCRIMINATOR);
+}
@@ -0,0 +1,155 @@
+//! Mock Token-2022 transfer hook for tests.
+//!
+//! Implements the two instructions of the SPL transfer-hook interface using the
+//! interface's discriminators, so Token-2022 mints can point their transfer-hook
+//! extension at it. Every hooked transfer is counted in a `HookState` account,
+//! and the state's authority can make the hook reject transfers to exercise
+//! failure paths deterministically.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{create_account, CreateAccount};
+
+declare_id!("mexNrJJ4uWKK3vLYHoS5GpSMXnNXXQLfUgLkmGENMmV");
+
+/// `spl-transfer-hook-interface:execute`
+pub const EXECUTE_DISCRIMINATOR: [u8; 8] = [105, 37, 101, 197, 75, 251, 102, 26];
+/// `spl-transfer-hook-interface:initialize-extra-account-metas`
+pub const INITIALIZE_EXTRA_ACCOUNT_METAS_DISCRIMINATOR: [u8; 8] =
+    [43, 34, 13, 49, 167, 88, 235, 235];
+
+/// TLV header (discriminator + length) followed by a one-entry pod slice.
+pub const EXTRA_ACCOUNT_METAS_LEN: usize = 8 + 4 + 4 + EXTRA_ACCOUNT_META_LEN;
+const EXTRA_ACCOUNT_META_LEN: usize = 1 + 32 + 1 + 1;
+
+/// Validation account contents naming `hook_state` as a fixed-address,
+/// writable extra account for `execute`.
+pub fn extra_account_metas_data(hook_state: &Pubkey) -> [u8; EXTRA_ACCOUNT_METAS_LEN] {
+    let mut data = [0u8; EXTRA_ACCOUNT_METAS_LEN];
+    data[..8].copy_from_slice(&EXECUTE_DISCRIMINATOR);
+    data[8..12].copy_from_slice(&((4 + EXTRA_ACCOUNT_META_LEN) as u32).to_le_bytes());
+    data[12..16].copy_from_slice(&1u32.to_le_bytes());
+    // Meta discriminator 0 (fixed address), address, is_signer, is_writable
+    data[16] = 0;
+    data[17..49].copy_from_slice(hook_state.as_ref());
+    data[49] = 0;
+    data[50] = 1;
+    data
+}
+
+#[program]
+pub mod mock_transfer_hook {
+    use super::*;
+
+    /// Creates the validation account listing `HookState` as the single extra
+    /// (writable) account Token-2022 must pass to `execute`.
+    #[instruction(discriminator = &INITIALIZE_EXTRA_ACCOUNT_METAS_DISCRIMINATOR)]
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let mint = ctx.accounts.mint.key();
+        let bump = ctx.bumps.extra_account_meta_list;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"extra-account-metas", mint.as_ref(), &[bump]]];
+
+        create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.extra_account_meta_list.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            Rent::get()?.minimum_balance(EXTRA_ACCOUNT_METAS_LEN),
+            EXTRA_ACCOUNT_METAS_LEN as u64,
+            &ID,
+        )?;
+
+        ctx.accounts
+            .extra_account_meta_list
+            .try_borrow_mut_data()?
+            .copy_from_slice(&extra_account_metas_data(&ctx.accounts.hook_state.key()));
+
+        let hook_state = &mut ctx.accounts.hook_state;
+        hook_state.authority = ctx.accounts.payer.key();
+        hook_state.mint = mint;
+        hook_state.reject_transfers = false;
+        hook_state.transfer_count = 0;
+        hook_state.last_amount = 0;
+        Ok(())
+    }
+
+    #[instruction(discriminator = &EXECUTE_DISCRIMINATOR)]
+    pub fn execute(ctx: Context<Execute>, amount: u64) -> Result<()> {
+        let hook_state = &mut ctx.accounts.hook_state;
+        require!(!hook_state.reject_transfers, HookError::TransferRejected);
+
+        hook_state.transfer_count += 1;
+        hook_state.last_amount = amount;
+        Ok(())
+    }
+
+    pub fn set_reject_transfers(ctx: Context<SetRejectTransfers>, reject: bool) -> Result<()> {
+        ctx.accounts.hook_state.reject_transfers = reject;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    /// CHECK: Created here with the TLV layout Token-2022 expects
+    #[account(mut, seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: Only used as a seed; the mock does not inspect the mint
+    pub mint: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 1 + 8 + 8,
+        seeds = [b"hook_state", mint.key().as_ref()],
+        bump
+    )]
+    pub hook_state: Account<'info, HookState>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Account order is fixed by the transfer-hook interface.
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: Source token account, passed through by Token-2022
+    pub source_token: UncheckedAccount<'info>,
+    /// CHECK: Mint, used to locate the hook state
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: Destination token account, passed through by Token-2022
+    pub destination_token: UncheckedAccount<'info>,
+    /// CHECK: Source account owner or delegate
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: Validation account created by initialize_extra_account_meta_list
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"hook_state", mint.key().as_ref()], bump)]
+    pub hook_state: Account<'info, HookState>,
+}
+
+#[derive(Accounts)]
+pub struct SetRejectTransfers<'info> {
+    #[account(mut, has_one = authority)]
+    pub hook_state: Account<'info, HookState>,
+    pub authority: Signer<'info>,
+}
+
+#[account]
+pub struct HookState {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub reject_transfers: bool,
+    pub transfer_count: u64,
+    pub last_amount: u64,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("The mock transfer hook is configured to reject transfers.")]
+    TransferRejected,
+}
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::Pubkey;
+use mock_transfer_hook::{
+    extra_account_metas_data, EXECUTE_DISCRIMINATOR, EXTRA_ACCOUNT_METAS_LEN,
+};
+
+#[test]
+fn validation_account_lists_hook_state_as_writable() {
+    let hook_state = Pubkey::new_unique();
+    let data = extra_account_metas_data(&hook_state);
+
+    assert_eq!(data.len(), EXTRA_ACCOUNT_METAS_LEN);
+    assert_eq!(data[..8], EXECUTE_DISCRIMINATOR);
+    let value_len = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+    assert_eq!(value_len, EXTRA_ACCOUNT_METAS_LEN - 12);
+    assert_eq!(u32::from_le_bytes(data[12..16].try_into().unwrap()), 1);
+    assert_eq!(data[16], 0);
+    assert_eq!(&data[17..49], hook_state.as_ref());
+    assert_eq!((data[49], data[50]), (0, 1));
+}
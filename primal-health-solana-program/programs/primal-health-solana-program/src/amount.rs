@@ -0,0 +1,149 @@
+//! Token amounts with their mint's decimals.
+//!
+//! An [`Amount`] is raw base units plus the number of decimals of the mint
+//! they belong to, so amounts of 6- and 9-decimal mints cannot be added by
+//! accident and conversions between them round the way the caller asks.
+//! All arithmetic is overflow-checked; products and ratios are computed in
+//! `u128` and rounded once at the end.
+
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Decimals of native SOL amounts, which are held in lamports.
+pub const LAMPORT_DECIMALS: u8 = 9;
+/// Largest supported decimals; `10^19` is the largest power of ten in a `u64`.
+pub const MAX_DECIMALS: u8 = 19;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// How to resolve a result that falls between two base units.
+///
+/// Round payouts to the payee `Down` and charges to the payer `Up`, so the
+/// program never hands out more than it holds. `HalfEven` is for reporting,
+/// where rounding should not drift in either direction over many values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+    HalfEven,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Amount {
+    pub raw: u64,
+    pub decimals: u8,
+}
+
+impl Amount {
+    pub fn new(raw: u64, decimals: u8) -> Result<Self> {
+        if decimals > MAX_DECIMALS {
+            msg!(
+                "{} decimals is over the maximum of {}",
+                decimals,
+                MAX_DECIMALS
+            );
+            return err!(ErrorCode::InvalidDecimals);
+        }
+        Ok(Amount { raw, decimals })
+    }
+
+    pub fn lamports(raw: u64) -> Self {
+        Amount {
+            raw,
+            decimals: LAMPORT_DECIMALS,
+        }
+    }
+
+    pub fn zero(decimals: u8) -> Result<Self> {
+        Self::new(0, decimals)
+    }
+
+    pub fn checked_add(self, other: Amount) -> Result<Self> {
+        self.expect_same_decimals(other)?;
+        let raw = self
+            .raw
+            .checked_add(other.raw)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        Ok(Amount { raw, ..self })
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Result<Self> {
+        self.expect_same_decimals(other)?;
+        let raw = self
+            .raw
+            .checked_sub(other.raw)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        Ok(Amount { raw, ..self })
+    }
+
+    /// The amount times a whole number, such as a unit price times units.
+    pub fn checked_mul(self, factor: u64) -> Result<Self> {
+        let raw = self
+            .raw
+            .checked_mul(factor)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        Ok(Amount { raw, ..self })
+    }
+
+    /// `self * numerator / denominator`, rounded once.
+    pub fn mul_ratio(self, numerator: u64, denominator: u64, rounding: Rounding) -> Result<Self> {
+        if denominator == 0 {
+            msg!("Ratio {}/0 has a zero denominator", numerator);
+            return err!(ErrorCode::AmountOverflow);
+        }
+        let product = self.raw as u128 * numerator as u128;
+        let raw = divide(product, denominator as u128, rounding);
+        Ok(Amount {
+            raw: u64::try_from(raw).map_err(|_| ErrorCode::AmountOverflow)?,
+            ..self
+        })
+    }
+
+    /// The share of the amount given by `bps` basis points.
+    pub fn mul_bps(self, bps: u64, rounding: Rounding) -> Result<Self> {
+        self.mul_ratio(bps, BPS_DENOMINATOR, rounding)
+    }
+
+    /// The same value expressed with `decimals` decimals. Converting to fewer
+    /// decimals drops precision according to `rounding`.
+    pub fn rescale(self, decimals: u8, rounding: Rounding) -> Result<Self> {
+        let target = Amount::new(0, decimals)?;
+        let raw = if decimals >= self.decimals {
+            let scale = 10u128.pow((decimals - self.decimals) as u32);
+            self.raw as u128 * scale
+        } else {
+            let scale = 10u128.pow((self.decimals - decimals) as u32);
+            divide(self.raw as u128, scale, rounding)
+        };
+        Ok(Amount {
+            raw: u64::try_from(raw).map_err(|_| ErrorCode::AmountOverflow)?,
+            ..target
+        })
+    }
+
+    fn expect_same_decimals(self, other: Amount) -> Result<()> {
+        if self.decimals != other.decimals {
+            msg!(
+                "Cannot combine amounts with {} and {} decimals",
+                self.decimals,
+                other.decimals
+            );
+            return err!(ErrorCode::DecimalsMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn divide(numerator: u128, denominator: u128, rounding: Rounding) -> u128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::HalfEven => {
+            let twice = remainder * 2;
+            twice > denominator || (twice == denominator && quotient % 2 == 1)
+        }
+    };
+    quotient + round_up as u128
+}
@@ -0,0 +1,73 @@
+//! Timestamp source for all time-dependent logic.
+//!
+//! Instructions read the current time through [`now`] rather than the clock
+//! sysvar directly, so tests can move time without waiting for it:
+//!
+//! - host-side tests set a fixed time with [`set_override`];
+//! - program tests built with the `test-clock` feature call `warp_clock` and
+//!   pass the resulting [`ClockOffset`] account as a remaining account, which
+//!   shifts the sysvar time by the stored offset.
+//!
+//! Without the feature the remaining accounts are ignored and the sysvar is
+//! the only source of time.
+
+use anchor_lang::prelude::*;
+
+pub const CLOCK_OFFSET_SEED: &[u8] = b"clock_offset";
+
+/// Seconds added to the sysvar time when passed to an instruction.
+#[account]
+pub struct ClockOffset {
+    pub offset_secs: i64,
+    pub account_version: u8,
+}
+
+impl ClockOffset {
+    pub const SPACE: usize = 8 + 8 + 1;
+
+    pub fn address() -> Pubkey {
+        Pubkey::find_program_address(&[CLOCK_OFFSET_SEED], &crate::ID).0
+    }
+}
+
+/// Current unix timestamp as seen by the program.
+pub fn now(remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    #[cfg(not(target_os = "solana"))]
+    if let Some(timestamp) = OVERRIDE.with(|cell| cell.get()) {
+        return Ok(timestamp);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    Ok(timestamp + offset(remaining_accounts)?)
+}
+
+#[cfg(feature = "test-clock")]
+fn offset(remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    let address = ClockOffset::address();
+    let Some(info) = remaining_accounts.iter().find(|info| info.key() == address) else {
+        return Ok(0);
+    };
+    require_keys_eq!(
+        *info.owner,
+        crate::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    Ok(ClockOffset::try_deserialize(&mut &info.try_borrow_data()?[..])?.offset_secs)
+}
+
+#[cfg(not(feature = "test-clock"))]
+fn offset(_remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    Ok(0)
+}
+
+#[cfg(not(target_os = "solana"))]
+thread_local! {
+    static OVERRIDE: std::cell::Cell<Option<i64>> = const { std::cell::Cell::new(None) };
+}
+
+/// Pins [`now`] to `timestamp` on the current thread, or restores the sysvar
+/// with `None`. Host builds only.
+#[cfg(not(target_os = "solana"))]
+pub fn set_override(timestamp: Option<i64>) {
+    OVERRIDE.with(|cell| cell.set(timestamp));
+}
@@ -0,0 +1,113 @@
+//! Bootstrap instructions for demo deployments, compiled only with the `demo`
+//! feature.
+//!
+//! A demo admin creates the `DemoConfig` once, then registers provider
+//! accounts on behalf of provider keys, funds per-provider vaults that
+//! providers draw SOL from to pay claims (devnet airdrops are rate limited),
+//! and resets claims to `Pending` so a walkthrough can be repeated.
+
+use anchor_lang::prelude::*;
+
+use crate::{ClaimAccount, ProviderAccount};
+
+pub const DEMO_CONFIG_SEED: &[u8] = b"demo_config";
+pub const DEMO_VAULT_SEED: &[u8] = b"demo_vault";
+
+#[account]
+pub struct DemoConfig {
+    pub admin: Pubkey,
+    pub provider_count: u32,
+    pub account_version: u8,
+}
+
+impl DemoConfig {
+    pub const SPACE: usize = 8 + 32 + 4 + 1;
+}
+
+/// Lamports held for a demo provider above the vault's own rent.
+#[account]
+pub struct DemoVault {
+    pub provider: Pubkey,
+    pub total_funded: u64,
+    pub account_version: u8,
+}
+
+impl DemoVault {
+    pub const SPACE: usize = 8 + 32 + 8 + 1;
+}
+
+#[derive(Accounts)]
+pub struct DemoInitialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = DemoConfig::SPACE,
+        seeds = [DEMO_CONFIG_SEED],
+        bump
+    )]
+    pub demo_config: Account<'info, DemoConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String, name: String)]
+pub struct DemoRegisterProvider<'info> {
+    #[account(mut, seeds = [DEMO_CONFIG_SEED], bump, has_one = admin)]
+    pub demo_config: Account<'info, DemoConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = ProviderAccount::space(&did, &name),
+        seeds = [b"provider", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_account: Account<'info, ProviderAccount>,
+    /// CHECK: The demo provider's wallet; it does not need to sign
+    pub provider: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DemoFundVault<'info> {
+    #[account(seeds = [DEMO_CONFIG_SEED], bump, has_one = admin)]
+    pub demo_config: Account<'info, DemoConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = DemoVault::SPACE,
+        seeds = [DEMO_VAULT_SEED, provider.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, DemoVault>,
+    /// CHECK: The demo provider the vault belongs to
+    pub provider: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DemoWithdrawVault<'info> {
+    #[account(
+        mut,
+        seeds = [DEMO_VAULT_SEED, provider.key().as_ref()],
+        bump,
+        has_one = provider
+    )]
+    pub vault: Account<'info, DemoVault>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DemoResetClaim<'info> {
+    #[account(seeds = [DEMO_CONFIG_SEED], bump, has_one = admin)]
+    pub demo_config: Account<'info, DemoConfig>,
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    pub admin: Signer<'info>,
+}
@@ -0,0 +1,85 @@
+//! Merkle commitments over the fields of a record, for selective disclosure.
+//!
+//! Off-chain, a record is canonicalized into `(path, value)` pairs (a FHIRPath
+//! such as `Patient.extension.bloodType` and the RFC 8785 canonical JSON of its
+//! value), each pair is hashed with [`field_leaf`], and [`merkle_root`] is
+//! committed with `commit_field_root`. A holder can then prove a single field
+//! with [`merkle_proof`] without revealing the rest of the record.
+//!
+//! Device rollups commit a day of raw readings the same way, with one
+//! [`reading_leaf`] per reading.
+//!
+//! Leaves and inner nodes are domain-separated, and the leaf count is padded to
+//! a power of two with zeroed leaves so every proof has the same length.
+
+use solana_program::hash::hashv;
+
+pub const MAX_PROOF_LEN: usize = 16;
+pub const MAX_FIELD_PATH_LEN: usize = 128;
+pub const MAX_FIELD_VALUE_LEN: usize = 512;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+const EMPTY_LEAF: [u8; 32] = [0; 32];
+
+pub fn field_leaf(path: &str, value: &str) -> [u8; 32] {
+    let path_len = (path.len() as u32).to_le_bytes();
+    hashv(&[LEAF_PREFIX, &path_len, path.as_bytes(), value.as_bytes()]).to_bytes()
+}
+
+/// Leaf for one device reading of `value` taken at `timestamp`.
+pub fn reading_leaf(timestamp: i64, value: i64) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, &timestamp.to_le_bytes(), &value.to_le_bytes()]).to_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+fn padded_level(leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().max(1).next_power_of_two(), EMPTY_LEAF);
+    level
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| node(&pair[0], &pair[1]))
+        .collect()
+}
+
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = padded_level(leaves);
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes from the leaf at `index` up to the root.
+pub fn merkle_proof(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    let mut level = padded_level(leaves);
+    let mut index = index;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        proof.push(level[index ^ 1]);
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+/// Root implied by `leaf` sitting at `index` with the given sibling path.
+pub fn root_from_proof(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    proof
+        .iter()
+        .enumerate()
+        .fold(leaf, |hash, (depth, sibling)| {
+            if (index >> depth) & 1 == 0 {
+                node(&hash, sibling)
+            } else {
+                node(sibling, &hash)
+            }
+        })
+}
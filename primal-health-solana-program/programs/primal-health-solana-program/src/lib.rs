@@ -1,240 +1,13532 @@
 use anchor_lang::prelude::*;
+use solana_program::hash::hashv;
+
+use amount::{Amount, Rounding};
+use migration::Versioned;
+
+pub mod amount;
+pub mod clock;
+#[cfg(feature = "demo")]
+pub mod demo;
+pub mod disclosure;
+pub mod migration;
+pub mod settlement;
+pub mod token;
+
+#[cfg(feature = "demo")]
+use demo::*;
 
 declare_id!("2LjMTbA2Z3ZftCr8UCJ3c5cauBq48NRBbXbiy6Zkkhao");
 
+pub const MAX_BATCH_ENTRIES: usize = 8;
+/// Most claims `create_claims_batch` or `verify_claims_batch` take at once.
+pub const MAX_CLAIM_BATCH: usize = 10;
+pub const MAX_LINE_ITEMS: usize = 10;
+pub const MAX_CODE_LEN: usize = 16;
+pub const MAX_MODIFIER_LEN: usize = 8;
+pub const MAX_HASH_LEN: usize = 64;
+pub const NOTES_PER_PAGE: usize = 16;
+pub const MAX_ATTACHMENTS: usize = 5;
+/// Room for an X12 837 patient control number (CLM01, 38 bytes) or
+/// transaction reference (BHT03, 50 bytes).
+pub const MAX_EXTERNAL_REF_LEN: usize = 50;
+pub const MAX_CLAIM_TAGS: usize = 4;
+pub const MAX_TAG_LEN: usize = 16;
+pub const REOPEN_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+/// Time a provider has to pay a verified claim before interest accrues.
+pub const PAYMENT_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+/// Simple annual interest on overdue claims, in basis points.
+pub const LATE_INTEREST_BPS_PER_YEAR: u64 = 1_000;
+pub const SECS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+pub const MAX_DID_LEN: usize = 128;
+pub const MAX_NAME_LEN: usize = 64;
+/// Ids used as PDA seeds must fit in a single 32-byte seed.
+pub const MAX_SEED_ID_LEN: usize = 32;
+pub const MAX_POINTER_LEN: usize = 128;
+/// Largest ciphertext `primal-health-client` seals. Records written before
+/// payloads moved off-chain hold at most this much inline.
+pub const MAX_ENCRYPTED_DATA_LEN: usize = 1024;
+/// Largest payload a `HealthDataAccountV2` holds. Keeps the account under the
+/// 10 KiB a program can allocate in one instruction.
+pub const MAX_CHUNKED_RECORD_LEN: usize = 8 * 1024;
+/// Largest chunk `append_data_chunk` takes, so a chunk fits in a transaction.
+pub const MAX_DATA_CHUNK_LEN: usize = 900;
+/// Longest clawback window `set_clawback_window` accepts.
+pub const MAX_CLAWBACK_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+/// How long unstaked collateral stays slashable before it can be withdrawn.
+pub const STAKE_UNBONDING_SECS: i64 = 30 * 24 * 60 * 60;
+/// Largest reward `set_crank_reward` accepts for one crank.
+pub const MAX_CRANK_REWARD_LAMPORTS: u64 = 1_000_000;
+/// Shortest and longest claim lifetimes `set_claim_ttl` accepts, besides 0.
+pub const MIN_CLAIM_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+pub const MAX_CLAIM_TTL_SECS: i64 = SECS_PER_YEAR;
+/// Bumped whenever the byte layout of an account or event changes, so
+/// indexers and client SDKs can tell layouts apart. Pinned by the layout
+/// snapshot tests.
+pub const LAYOUT_VERSION: u8 = 24;
+/// Grantee may read the patient's records.
+#[constant]
+pub const GRANT_SCOPE_READ: u8 = 1 << 0;
+/// Grantee may submit records on the patient's behalf.
+#[constant]
+pub const GRANT_SCOPE_WRITE: u8 = 1 << 1;
+#[constant]
+pub const GRANT_SCOPE_ALL: u8 = GRANT_SCOPE_READ | GRANT_SCOPE_WRITE;
+/// Grantee may sub-grant their access to a team member. Not part of
+/// `GRANT_SCOPE_ALL`, which covers what a grant allows on the records.
+#[constant]
+pub const GRANT_SCOPE_DELEGATE: u8 = 1 << 2;
+/// Links from the patient's grant to the furthest sub-grant.
+pub const MAX_DELEGATION_DEPTH: u8 = 2;
+/// Provider may read the consented record.
+#[constant]
+pub const CONSENT_SCOPE_READ: u8 = 1 << 0;
+/// Provider may share the consented record with other parties treating the
+/// patient.
+#[constant]
+pub const CONSENT_SCOPE_SHARE: u8 = 1 << 1;
+/// Provider may use the consented record in research.
+#[constant]
+pub const CONSENT_SCOPE_RESEARCH: u8 = 1 << 2;
+#[constant]
+pub const CONSENT_SCOPE_ALL: u8 = CONSENT_SCOPE_READ | CONSENT_SCOPE_SHARE | CONSENT_SCOPE_RESEARCH;
+pub const MAX_POLICY_RULES: usize = 8;
+/// Approvers per approval policy, one bit each in a claim's `approvals`.
+pub const MAX_APPROVERS: usize = 8;
+/// Recency tiers per data access offer.
+pub const MAX_PRICE_TIERS: usize = 4;
+/// Volume tiers in the protocol fee rebate schedule.
+pub const MAX_REBATE_TIERS: usize = 4;
+/// Category mask covering every `DataCategory`.
+#[constant]
+pub const CATEGORY_ALL: u8 = u8::MAX;
+/// Guardian may file claims for the dependent.
+#[constant]
+pub const GUARDIAN_FILE_CLAIMS: u8 = 1 << 0;
+/// Guardian may grant and revoke access to the dependent's records.
+#[constant]
+pub const GUARDIAN_GRANT_ACCESS: u8 = 1 << 1;
+#[constant]
+pub const GUARDIAN_PERMISSIONS_ALL: u8 = GUARDIAN_FILE_CLAIMS | GUARDIAN_GRANT_ACCESS;
+/// Delegate may submit health records for the patient.
+#[constant]
+pub const DELEGATE_SUBMIT_DATA: u8 = 1 << 0;
+/// Delegate may file claims for the patient.
+#[constant]
+pub const DELEGATE_FILE_CLAIMS: u8 = 1 << 1;
+/// Delegate may grant and revoke consent and access to the patient's records.
+#[constant]
+pub const DELEGATE_GRANT_CONSENT: u8 = 1 << 2;
+#[constant]
+pub const DELEGATE_PERMISSIONS_ALL: u8 =
+    DELEGATE_SUBMIT_DATA | DELEGATE_FILE_CLAIMS | DELEGATE_GRANT_CONSENT;
+/// Room for an HPKE X25519 encapsulated key plus a wrapped 32-byte record key,
+/// with headroom for other suites.
+pub const MAX_KEY_ENVELOPE_LEN: usize = 128;
+pub const MAX_QUALITY_SCORE: u8 = 100;
+/// Claims against one provider in a UTC day above which a `FraudSignal` fires.
+pub const FRAUD_MAX_CLAIMS_PER_DAY: u32 = 50;
+/// Share of a provider's claims, in basis points, that may bill the same
+/// procedure code on more than one line before a `FraudSignal` fires.
+pub const FRAUD_MAX_DUPLICATE_CODE_BPS: u64 = 2_000;
+/// Claims a provider needs before its duplicate-code share is judged.
+pub const FRAUD_MIN_CLAIM_SAMPLE: u64 = 20;
+/// Feature flag for paid data subscriptions.
+#[constant]
+pub const FEATURE_SUBSCRIPTIONS: u64 = 1 << 0;
+/// Feature flag for sponsor vaults and sponsored onboarding.
+#[constant]
+pub const FEATURE_SPONSORSHIP: u64 = 1 << 1;
+/// Feature flag for tenant networks.
+#[constant]
+pub const FEATURE_TENANTS: u64 = 1 << 2;
+/// Feature flag for pay-per-access data offers.
+#[constant]
+pub const FEATURE_ACCESS_SALES: u64 = 1 << 3;
+#[constant]
+pub const FEATURE_ALL: u64 =
+    FEATURE_SUBSCRIPTIONS | FEATURE_SPONSORSHIP | FEATURE_TENANTS | FEATURE_ACCESS_SALES;
+/// Highest network fee a tenant may charge on claim payments.
+pub const MAX_TENANT_FEE_BPS: u16 = 1_000;
+/// Highest protocol fee the config may set on claim payments.
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 500;
+/// Longest adjudication deadline a claim can opt into.
+pub const MAX_AUTO_APPROVAL_DAYS: u16 = 180;
+/// Most installments a claim's payment schedule can split it into.
+pub const MAX_INSTALLMENTS: u8 = 24;
+/// Most records a patient can share with a second-opinion consultant.
+pub const MAX_SECOND_OPINION_RECORDS: usize = 8;
+/// Most providers in one care team.
+pub const MAX_CARE_TEAM_MEMBERS: usize = 8;
+/// Most records a patient can designate for one care team.
+pub const MAX_CARE_TEAM_RECORDS: usize = 16;
+/// Wait between an emergency access request and the access, during which
+/// the patient can veto it.
+pub const EMERGENCY_ACCESS_DELAY_SECS: i64 = 15 * 60;
+/// How long emergency access lasts once the delay has passed.
+pub const EMERGENCY_ACCESS_DURATION_SECS: i64 = 24 * 60 * 60;
+/// Most procedure codes a provider can price in their fee schedule.
+pub const MAX_FEE_SCHEDULE_ENTRIES: usize = 32;
+/// Most readings a device can be allowed per UTC day, one a minute.
+pub const MAX_DEVICE_READINGS_PER_DAY: u32 = 24 * 60;
+
 #[program]
 pub mod primal_health_solana_program {
     use super::*;
 
+    /// Registers the signer as a patient identified by `did`.
     pub fn initialize_patient(ctx: Context<InitializePatient>, did: String) -> Result<()> {
+        validate_did(&did)?;
+
+        let patient_account = &mut ctx.accounts.patient_account;
+        patient_account.authority = ctx.accounts.authority.key();
+        patient_account.did = did;
+        patient_account.account_version = PatientAccount::VERSION;
+        emit_patient_registered(patient_account, clock::now(ctx.remaining_accounts)?);
+        Ok(())
+    }
+
+    /// `initialize_patient` with the rent paid from the sponsor's vault, so a
+    /// clinic can onboard a patient who holds no SOL. The patient still signs.
+    pub fn initialize_patient_sponsored(
+        ctx: Context<InitializePatientSponsored>,
+        did: String,
+    ) -> Result<()> {
+        validate_did(&did)?;
+
         let patient_account = &mut ctx.accounts.patient_account;
         patient_account.authority = ctx.accounts.authority.key();
         patient_account.did = did;
+        patient_account.account_version = PatientAccount::VERSION;
+        emit_patient_registered(patient_account, clock::now(ctx.remaining_accounts)?);
+        reimburse_rent(
+            &mut ctx.accounts.sponsor_vault,
+            &ctx.accounts.sponsor,
+            &ctx.accounts.patient_account.to_account_info(),
+        )
+    }
+
+    /// Adds lamports to `sponsor`'s onboarding vault, creating it on first
+    /// use. Anyone may fund a vault; only the sponsor spends from it.
+    pub fn fund_sponsor_vault(ctx: Context<FundSponsorVault>, lamports: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.sponsor_vault;
+        vault.sponsor = ctx.accounts.sponsor.key();
+        vault.account_version = SponsorVault::VERSION;
+        vault.total_funded = vault
+            .total_funded
+            .checked_add(lamports)
+            .ok_or(ErrorCode::AmountOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.funder.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, lamports)
+    }
+
+    /// Moves unspent lamports from the sponsor's vault back to the sponsor,
+    /// leaving the vault's own rent.
+    pub fn withdraw_sponsor_vault(ctx: Context<WithdrawSponsorVault>, lamports: u64) -> Result<()> {
+        let vault = ctx.accounts.sponsor_vault.to_account_info();
+        let available = vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+        if lamports > available {
+            msg!(
+                "Requested {} lamports; the vault has {} available",
+                lamports,
+                available
+            );
+            return err!(ErrorCode::InsufficientSponsorFunds);
+        }
+
+        **vault.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.sponsor.try_borrow_mut_lamports()? += lamports;
         Ok(())
     }
 
+    /// Registers the signer as a provider with a DID and display name.
     pub fn initialize_provider(ctx: Context<InitializeProvider>, did: String, name: String) -> Result<()> {
+        validate_did(&did)?;
+        validate_not_empty("name", &name, ErrorCode::NameEmpty)?;
+        validate_len("name", &name, MAX_NAME_LEN, ErrorCode::NameTooLong)?;
+
         let provider_account = &mut ctx.accounts.provider_account;
         provider_account.authority = ctx.accounts.authority.key();
         provider_account.did = did;
         provider_account.name = name;
+        provider_account.account_version = ProviderAccount::VERSION;
+        Ok(())
+    }
+
+    /// Replaces the patient's DID, resizing the account to fit it.
+    pub fn update_patient(ctx: Context<UpdatePatient>, did: String) -> Result<()> {
+        let patient_account = &mut ctx.accounts.patient_account;
+        clear_account_data(&patient_account.to_account_info())?;
+        patient_account.update(did)
+    }
+
+    /// Replaces the provider's DID and display name, resizing the account to
+    /// fit them. The provider's credential stays with its wallet.
+    pub fn update_provider(ctx: Context<UpdateProvider>, did: String, name: String) -> Result<()> {
+        let provider_account = &mut ctx.accounts.provider_account;
+        clear_account_data(&provider_account.to_account_info())?;
+        provider_account.update(did, name)
+    }
+
+    /// Registers the signer as an insurer with a DID and display name, so
+    /// claims can name it as the payer that must approve them.
+    pub fn initialize_insurer(
+        ctx: Context<InitializeInsurer>,
+        did: String,
+        name: String,
+    ) -> Result<()> {
+        let insurer_account = &mut ctx.accounts.insurer_account;
+        insurer_account.authority = ctx.accounts.authority.key();
+        insurer_account.account_version = InsurerAccount::VERSION;
+        insurer_account.update(did, name)
+    }
+
+    /// Registers the signer's X25519 public key, which records are encrypted
+    /// to and grants wrap record keys to, as its version 1.
+    pub fn register_encryption_key(
+        ctx: Context<RegisterEncryptionKey>,
+        x25519_key: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let encryption_key = &mut ctx.accounts.encryption_key;
+        encryption_key.register(ctx.accounts.owner.key(), x25519_key, now)?;
+        emit_encryption_key_updated(encryption_key, now);
+        Ok(())
+    }
+
+    /// Replaces the signer's registered key with `x25519_key` under the next
+    /// version. Records keep the version they were encrypted to, and key
+    /// envelopes wrapped to the old key need re-issuing.
+    pub fn rotate_encryption_key(
+        ctx: Context<RotateEncryptionKey>,
+        x25519_key: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let encryption_key = &mut ctx.accounts.encryption_key;
+        encryption_key.rotate(x25519_key, now)?;
+        emit_encryption_key_updated(encryption_key, now);
+        Ok(())
+    }
+
+    /// Links the signing guardian to a dependent patient with the
+    /// `permissions` bits, until `expires_at` (the dependent's age of majority).
+    /// Both parties must already be registered patients and both sign.
+    pub fn link_dependent(
+        ctx: Context<LinkDependent>,
+        permissions: u8,
+        expires_at: i64,
+    ) -> Result<()> {
+        if permissions == 0 || permissions & !GUARDIAN_PERMISSIONS_ALL != 0 {
+            msg!(
+                "Guardian permissions {:#04b} are empty or have unknown bits",
+                permissions
+            );
+            return err!(ErrorCode::InvalidGuardianPermissions);
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        if expires_at <= now {
+            msg!(
+                "Dependent link expiry {} is not after now ({})",
+                expires_at,
+                now
+            );
+            return err!(ErrorCode::InvalidDependentExpiry);
+        }
+
+        let link = &mut ctx.accounts.dependent_link;
+        link.guardian = ctx.accounts.guardian.key();
+        link.account_version = DependentLink::VERSION;
+        link.dependent = ctx.accounts.dependent.key();
+        link.permissions = permissions;
+        link.linked_at = now;
+        link.expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Gives `delegate` power of attorney over the signing patient's account
+    /// with the `permissions` bits, at `["delegate", patient, delegate]`.
+    /// Unlike a guardian, a delegate needs no patient account of their own
+    /// and the power does not lapse. Setting it again replaces the
+    /// permissions; `revoke_delegate` ends it.
+    pub fn set_delegate(ctx: Context<SetDelegate>, permissions: u8) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.delegate_account.set(
+            ctx.accounts.patient.key(),
+            ctx.accounts.delegate.key(),
+            permissions,
+            now,
+        )
+    }
+
+    /// Ends a delegate's power of attorney, closing its account and returning
+    /// the rent to the patient.
+    pub fn revoke_delegate(_ctx: Context<RevokeDelegate>) -> Result<()> {
         Ok(())
     }
 
+    /// Records where an encrypted health record is stored off-chain, and the
+    /// commitment to its content, under its hash. The author is the
+    /// owner, a grantee holding an active write grant from the owner, or the
+    /// owner's delegate allowed to submit data.
+    ///
+    /// The owner's subscriptions to `category` can be passed as writable
+    /// remaining accounts; each accrues its per-record price to the owner.
     pub fn submit_health_data(
         ctx: Context<SubmitHealthData>,
-        data_hash: String,
-        encrypted_data: String,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+        category: DataCategory,
     ) -> Result<()> {
-        let health_data_account = &mut ctx.accounts.health_data_account;
-        health_data_account.owner = ctx.accounts.owner.key();
-        health_data_account.data_hash = data_hash;
-        health_data_account.encrypted_data = encrypted_data;
-        health_data_account.timestamp = Clock::get()?.unix_timestamp;
+        validate_record(&data_hash, &pointer)?;
+
+        let owner = ctx.accounts.owner.key();
+        let author = ctx.accounts.author.key();
+        let now = clock::now(ctx.remaining_accounts)?;
+        authorize_author(
+            RecordAuthor {
+                author,
+                owner,
+                delegate: ctx.accounts.delegate_account.as_deref(),
+                policy: ctx.accounts.access_policy.as_deref(),
+                grant: ctx.accounts.grant.as_deref(),
+                is_provider: ctx.accounts.author_provider.is_some(),
+            },
+            category.bit(),
+            now,
+        )?;
+        accrue_subscriptions(ctx.remaining_accounts, owner, category)?;
+
+        ctx.accounts.health_data_account.record(
+            owner,
+            author,
+            data_hash,
+            pointer,
+            ctx.accounts.owner_key.as_deref(),
+            now,
+        );
+        emit!(HealthDataSubmitted {
+            record: ctx.accounts.health_data_account.key(),
+            owner,
+            author,
+            data_hash: ctx.accounts.health_data_account.data_hash,
+            category,
+            timestamp: now,
+        });
         Ok(())
     }
 
-    pub fn create_claim(
-        ctx: Context<CreateClaim>,
-        claim_id: String,
-        amount: u64,
-        health_data_hash: String,
+    /// `submit_health_data` with the rent paid from the sponsor's vault, so
+    /// an author without SOL can submit through a sponsoring clinic.
+    pub fn submit_health_data_sponsored(
+        ctx: Context<SubmitHealthDataSponsored>,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+        category: DataCategory,
     ) -> Result<()> {
-        let claim_account = &mut ctx.accounts.claim_account;
-        claim_account.claim_id = claim_id;
-        claim_account.patient = ctx.accounts.patient.key();
-        claim_account.provider = ctx.accounts.provider.key();
-        claim_account.health_data_hash = health_data_hash;
-        claim_account.amount = amount;
-        claim_account.status = ClaimStatus::Pending;
-        claim_account.timestamp = Clock::get()?.unix_timestamp;
-        Ok(())
+        validate_record(&data_hash, &pointer)?;
+
+        let owner = ctx.accounts.owner.key();
+        let author = ctx.accounts.author.key();
+        let now = clock::now(ctx.remaining_accounts)?;
+        authorize_author(
+            RecordAuthor {
+                author,
+                owner,
+                delegate: ctx.accounts.delegate_account.as_deref(),
+                policy: ctx.accounts.access_policy.as_deref(),
+                grant: ctx.accounts.grant.as_deref(),
+                is_provider: ctx.accounts.author_provider.is_some(),
+            },
+            category.bit(),
+            now,
+        )?;
+        accrue_subscriptions(ctx.remaining_accounts, owner, category)?;
+
+        ctx.accounts.health_data_account.record(
+            owner,
+            author,
+            data_hash,
+            pointer,
+            ctx.accounts.owner_key.as_deref(),
+            now,
+        );
+        emit!(HealthDataSubmitted {
+            record: ctx.accounts.health_data_account.key(),
+            owner,
+            author,
+            data_hash: ctx.accounts.health_data_account.data_hash,
+            category,
+            timestamp: now,
+        });
+        reimburse_rent(
+            &mut ctx.accounts.sponsor_vault,
+            &ctx.accounts.sponsor,
+            &ctx.accounts.health_data_account.to_account_info(),
+        )
     }
 
-    pub fn verify_claim(ctx: Context<VerifyClaim>, status: ClaimStatus) -> Result<()> {
-        let claim_account = &mut ctx.accounts.claim_account;
-        
-        // Only the assigned provider can verify
-        require!(
-            claim_account.provider == ctx.accounts.provider.key(),
-            ErrorCode::Unauthorized
+    /// `submit_health_data` at the owner's next index, `["health_record",
+    /// owner, record_index]`, rather than under the record's hash, so a
+    /// client can list every appended record from `record_index` 0 up to the
+    /// owner's `HealthDataIndex::record_count`. `record_index` must equal that
+    /// count, so two authors appending at once cannot both take the slot.
+    pub fn append_health_record(
+        ctx: Context<AppendHealthRecord>,
+        record_index: u64,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+        category: DataCategory,
+    ) -> Result<()> {
+        validate_record(&data_hash, &pointer)?;
+
+        let owner = ctx.accounts.owner.key();
+        let author = ctx.accounts.author.key();
+        let now = clock::now(ctx.remaining_accounts)?;
+        authorize_author(
+            RecordAuthor {
+                author,
+                owner,
+                delegate: ctx.accounts.delegate_account.as_deref(),
+                policy: ctx.accounts.access_policy.as_deref(),
+                grant: ctx.accounts.grant.as_deref(),
+                is_provider: ctx.accounts.author_provider.is_some(),
+            },
+            category.bit(),
+            now,
+        )?;
+        ctx.accounts.health_data_index.append(owner, record_index)?;
+        accrue_subscriptions(ctx.remaining_accounts, owner, category)?;
+
+        ctx.accounts.health_data_account.record(
+            owner,
+            author,
+            data_hash,
+            pointer,
+            ctx.accounts.owner_key.as_deref(),
+            now,
         );
+        emit!(HealthDataSubmitted {
+            record: ctx.accounts.health_data_account.key(),
+            owner,
+            author,
+            data_hash: ctx.accounts.health_data_account.data_hash,
+            category,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Records up to `MAX_BATCH_ENTRIES` off-chain records in one account.
+    /// The author is checked for each entry's category as
+    /// `submit_health_data` checks it, and the owner's subscriptions passed
+    /// as remaining accounts accrue once per entry in their category.
+    pub fn submit_health_data_batch(
+        ctx: Context<SubmitHealthDataBatch>,
+        batch_id: String,
+        entries: Vec<HealthRecordEntry>,
+    ) -> Result<()> {
+        validate_not_empty("batch_id", &batch_id, ErrorCode::BatchIdEmpty)?;
+        validate_len(
+            "batch_id",
+            &batch_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::BatchIdTooLong,
+        )?;
+        require!(!entries.is_empty(), ErrorCode::EmptyBatch);
+        validate_count(
+            "entries",
+            entries.len(),
+            MAX_BATCH_ENTRIES,
+            ErrorCode::BatchTooLarge,
+        )?;
+        for entry in &entries {
+            validate_hash("data_hash", &entry.data_hash)?;
+            validate_not_empty("pointer", &entry.pointer, ErrorCode::PointerEmpty)?;
+            validate_len(
+                "pointer",
+                &entry.pointer,
+                MAX_POINTER_LEN,
+                ErrorCode::PointerTooLong,
+            )?;
+        }
+
+        let owner = ctx.accounts.owner.key();
+        let author = ctx.accounts.author.key();
+        let now = clock::now(ctx.remaining_accounts)?;
+        for entry in &entries {
+            authorize_author(
+                RecordAuthor {
+                    author,
+                    owner,
+                    delegate: ctx.accounts.delegate_account.as_deref(),
+                    policy: ctx.accounts.access_policy.as_deref(),
+                    grant: ctx.accounts.grant.as_deref(),
+                    is_provider: ctx.accounts.author_provider.is_some(),
+                },
+                entry.category.bit(),
+                now,
+            )?;
+            accrue_subscriptions(ctx.remaining_accounts, owner, entry.category)?;
+        }
 
-        claim_account.status = status;
+        let batch_account = &mut ctx.accounts.batch_account;
+        for entry in &entries {
+            emit!(HealthDataSubmitted {
+                record: batch_account.key(),
+                owner,
+                author,
+                data_hash: entry.data_hash,
+                category: entry.category,
+                timestamp: now,
+            });
+        }
+        batch_account.owner = owner;
+        batch_account.account_version = HealthDataBatchAccount::VERSION;
+        batch_account.batch_id = batch_id;
+        batch_account.entries = entries;
+        batch_account.timestamp = now;
         Ok(())
     }
 
-    pub fn process_payment(ctx: Context<ProcessPayment>) -> Result<()> {
-        let claim_account = &mut ctx.accounts.claim_account;
-        let provider = &mut ctx.accounts.provider;
-        let patient = &mut ctx.accounts.patient;
-        let system_program = &ctx.accounts.system_program;
+    /// Opens a `HealthDataAccountV2` at `["health_data_v2", data_hash]` for a
+    /// `total_len`-byte encrypted payload held on-chain, written with
+    /// `append_data_chunk` over as many transactions as it takes. The account
+    /// is zero-copy, so large payloads are never deserialized.
+    pub fn create_health_data_v2(
+        ctx: Context<CreateHealthDataV2>,
+        data_hash: [u8; 32],
+        commitment: [u8; 32],
+        total_len: u32,
+        category: DataCategory,
+    ) -> Result<()> {
+        validate_hash("data_hash", &data_hash)?;
+        validate_hash("commitment", &commitment)?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.health_data_account.load_init()?.begin(
+            ctx.accounts.owner.key(),
+            data_hash,
+            commitment,
+            total_len,
+            category,
+            now,
+        )
+    }
 
-        // Checks
-        require!(
-            claim_account.provider == provider.key(),
-            ErrorCode::Unauthorized
-        );
-        require!(
-            claim_account.patient == patient.key(),
-            ErrorCode::InvalidPatient
+    /// Writes `chunk` at `offset` of a `HealthDataAccountV2` payload. Chunks
+    /// go in order, so `offset` must be the bytes written so far; a retried
+    /// chunk that already landed fails with `ChunkOutOfOrder`. The last chunk
+    /// checks the payload against the record's commitment and finalizes it.
+    pub fn append_data_chunk(
+        ctx: Context<AppendDataChunk>,
+        offset: u32,
+        chunk: Vec<u8>,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let mut record = ctx.accounts.health_data_account.load_mut()?;
+        if record.append_chunk(offset, &chunk, now)? {
+            emit!(HealthDataSubmitted {
+                record: ctx.accounts.health_data_account.key(),
+                owner: record.owner,
+                author: record.owner,
+                data_hash: record.data_hash,
+                category: record.category()?,
+                timestamp: now,
+            });
+        }
+        Ok(())
+    }
+
+    /// Registers `device` to submit the signing patient's `category` readings
+    /// with `submit_device_reading`, at most `max_readings_per_day` per UTC
+    /// day. The device signs with its own key and never holds the patient's.
+    /// To change the category or limit, revoke the device and register it
+    /// again.
+    pub fn register_device(
+        ctx: Context<RegisterDevice>,
+        model_hash: [u8; 32],
+        category: DataCategory,
+        max_readings_per_day: u32,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.device_account.register(
+            ctx.accounts.patient.key(),
+            ctx.accounts.device.key(),
+            model_hash,
+            category,
+            max_readings_per_day,
+            now,
+        )
+    }
+
+    /// Removes a device, closing its account and returning the rent to the
+    /// patient. Readings it already submitted stay.
+    pub fn revoke_device(_ctx: Context<RevokeDevice>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stores a reading from a registered device as one of its patient's
+    /// records, like `submit_health_data` with the device as the author. The
+    /// category is the device's own, and readings past its daily limit fail
+    /// with `DeviceRateLimited`. The device pays for the record.
+    ///
+    /// The patient's subscriptions to the category can be passed as
+    /// writable remaining accounts; each accrues its per-record price.
+    pub fn submit_device_reading(
+        ctx: Context<SubmitDeviceReading>,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+    ) -> Result<()> {
+        validate_record(&data_hash, &pointer)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let device_account = &mut ctx.accounts.device_account;
+        device_account.record_reading(now)?;
+        let owner = device_account.patient;
+        let author = device_account.device;
+        let category = device_account.category;
+        accrue_subscriptions(ctx.remaining_accounts, owner, category)?;
+
+        ctx.accounts.health_data_account.record(
+            owner,
+            author,
+            data_hash,
+            pointer,
+            ctx.accounts.owner_key.as_deref(),
+            now,
         );
+        emit!(HealthDataSubmitted {
+            record: ctx.accounts.health_data_account.key(),
+            owner,
+            author,
+            data_hash: ctx.accounts.health_data_account.data_hash,
+            category,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Condenses a device's readings for the UTC day number `day` into a
+    /// `DeviceRollup` at `["device_rollup", device_account, day]`, with the
+    /// day as 8 little-endian bytes. `readings_root` is
+    /// `disclosure::merkle_root` over `disclosure::reading_leaf` of each raw
+    /// reading, so any of them can later be proven with
+    /// `verify_rollup_reading`. The day must have ended. Claims and access
+    /// offers reference the rollup by its `summary_hash`.
+    pub fn roll_up_device_readings(
+        ctx: Context<RollUpDeviceReadings>,
+        day: i64,
+        readings_root: [u8; 32],
+        summary: ReadingSummary,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let device_rollup = &mut ctx.accounts.device_rollup;
+        device_rollup.roll_up(
+            ctx.accounts.device_account.key(),
+            &ctx.accounts.device_account,
+            day,
+            readings_root,
+            summary,
+            now,
+        )
+    }
+
+    /// Checks one raw reading against a device rollup: it was taken on the
+    /// rollup's day, lies within its range and is a leaf of its root.
+    /// Simulate the transaction to check a reading without publishing it.
+    pub fn verify_rollup_reading(
+        ctx: Context<VerifyRollupReading>,
+        timestamp: i64,
+        value: i64,
+        index: u32,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts
+            .device_rollup
+            .verify_reading(timestamp, value, index, &proof)
+    }
+
+    /// Records that `child` was derived from `parent` (a summary, interpretation
+    /// or corrected version). Each record has at most one parent, set once.
+    pub fn link_records(ctx: Context<LinkRecords>) -> Result<()> {
+        let parent = &ctx.accounts.parent;
+        let child = &mut ctx.accounts.child;
+
         require!(
-            claim_account.status == ClaimStatus::Verified,
-            ErrorCode::ClaimNotVerified
+            child.derived_from == [0; 32],
+            ErrorCode::RecordAlreadyLinked
         );
+        // Parents must precede children in (timestamp, hash) order, which
+        // rules out cycles without walking the graph
+        if (parent.timestamp, &parent.data_hash) >= (child.timestamp, &child.data_hash) {
+            msg!(
+                "Parent {} ({}) does not precede child {} ({})",
+                hex(&parent.data_hash),
+                parent.timestamp,
+                hex(&child.data_hash),
+                child.timestamp
+            );
+            return err!(ErrorCode::InvalidProvenanceLink);
+        }
 
-        // Transfer SOL from provider to patient
-        let amount = claim_account.amount;
-        
-        let cpi_context = CpiContext::new(
-            system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: provider.to_account_info(),
-                to: patient.to_account_info(),
-            },
-        );
-        
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
+        child.derived_from = parent.data_hash;
+        Ok(())
+    }
+
+    /// Commits a merkle root over the record's canonicalized fields (see
+    /// `disclosure`). Set once, by the record's owner or author.
+    pub fn commit_field_root(
+        ctx: Context<CommitFieldRoot>,
+        root: [u8; 32],
+        field_count: u32,
+    ) -> Result<()> {
+        let record = &ctx.accounts.record;
+        let committer = ctx.accounts.committer.key();
+
+        if committer != record.owner && committer != record.author {
+            msg!(
+                "{} is neither the owner nor the author of the record",
+                committer
+            );
+            return err!(ErrorCode::Unauthorized);
+        }
+        if field_count == 0 || field_count as usize > 1 << disclosure::MAX_PROOF_LEN {
+            msg!("Field count {} is out of range", field_count);
+            return err!(ErrorCode::InvalidFieldCount);
+        }
 
-        // Update claim status
-        claim_account.status = ClaimStatus::Paid;
-        
+        let field_commitment = &mut ctx.accounts.field_commitment;
+        field_commitment.record = record.key();
+        field_commitment.account_version = FieldCommitment::VERSION;
+        field_commitment.root = root;
+        field_commitment.field_count = field_count;
+        field_commitment.committer = committer;
+        field_commitment.committed_at = clock::now(ctx.remaining_accounts)?;
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(did: String)]
-pub struct InitializePatient<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 4 + did.len() + 64,
-        seeds = [b"patient", authority.key().as_ref()],
-        bump
-    )]
-    pub patient_account: Account<'info, PatientAccount>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Checks one disclosed field against the record's committed root.
+    /// Instruction data is public, so simulate the transaction to check a
+    /// disclosure without publishing the field.
+    pub fn verify_field_disclosure(
+        ctx: Context<VerifyFieldDisclosure>,
+        field_path: String,
+        field_value: String,
+        index: u32,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        validate_len(
+            "field_path",
+            &field_path,
+            disclosure::MAX_FIELD_PATH_LEN,
+            ErrorCode::FieldPathTooLong,
+        )?;
+        validate_len(
+            "field_value",
+            &field_value,
+            disclosure::MAX_FIELD_VALUE_LEN,
+            ErrorCode::FieldValueTooLong,
+        )?;
 
-#[derive(Accounts)]
-#[instruction(did: String, name: String)]
-pub struct InitializeProvider<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 4 + did.len() + 4 + name.len() + 64,
-        seeds = [b"provider", authority.key().as_ref()],
-        bump
-    )]
-    pub provider_account: Account<'info, ProviderAccount>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let field_commitment = &ctx.accounts.field_commitment;
+        if index >= field_commitment.field_count {
+            msg!(
+                "Field index {} given; the record commits {} fields",
+                index,
+                field_commitment.field_count
+            );
+            return err!(ErrorCode::InvalidFieldIndex);
+        }
+        if proof.len() != field_commitment.depth() {
+            msg!(
+                "Proof has {} hashes; expected {}",
+                proof.len(),
+                field_commitment.depth()
+            );
+            return err!(ErrorCode::InvalidMerkleProof);
+        }
+        let leaf = disclosure::field_leaf(&field_path, &field_value);
+        if disclosure::root_from_proof(leaf, index, &proof) != field_commitment.root {
+            msg!("Proof for {} does not match the committed root", field_path);
+            return err!(ErrorCode::InvalidMerkleProof);
+        }
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(data_hash: String, encrypted_data: String)]
-pub struct SubmitHealthData<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + 32 + 4 + data_hash.len() + 4 + encrypted_data.len() + 8 + 64,
-        seeds = [b"health_data", data_hash.as_bytes()],
-        bump
-    )]
-    pub health_data_account: Account<'info, HealthDataAccount>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Checks a record's payload, fetched from its storage, against the
+    /// record's commitment. `content_hash` is the SHA-256 of the fetched
+    /// bytes. Simulate the transaction to check a download.
+    pub fn verify_commitment(ctx: Context<VerifyCommitment>, content_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.record.verify_commitment(&content_hash)
+    }
 
-#[derive(Accounts)]
-#[instruction(claim_id: String)]
-pub struct CreateClaim<'info> {
-    #[account(
-        init,
-        payer = patient,
-        space = 8 + 4 + claim_id.len() + 32 + 32 + 4 + 64 + 8 + 1 + 1 + 8 + 64,
-        seeds = [b"claim", claim_id.as_bytes()],
-        bump
-    )]
-    pub claim_account: Account<'info, ClaimAccount>,
-    #[account(mut)]
-    pub patient: Signer<'info>,
-    /// CHECK: The provider account is just a pubkey here for assignment
-    pub provider: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Creates the data validator registry with the signer as its admin. Call
+    /// it in the deployment transaction so nobody else can claim the registry.
+    pub fn initialize_validator_registry(ctx: Context<InitializeValidatorRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.validator_registry;
+        registry.admin = ctx.accounts.admin.key();
+        registry.account_version = ValidatorRegistry::VERSION;
+        registry.validator_count = 0;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct VerifyClaim<'info> {
-    #[account(mut)]
-    pub claim_account: Account<'info, ClaimAccount>,
-    pub provider: Signer<'info>,
-}
+    /// Designates `validator` as a data validator, or suspends one with
+    /// `active = false`. Suspended validators keep their past attestations.
+    pub fn designate_validator(ctx: Context<DesignateValidator>, active: bool) -> Result<()> {
+        let data_validator = &mut ctx.accounts.data_validator;
+        if data_validator.authority == Pubkey::default() {
+            data_validator.authority = ctx.accounts.validator.key();
+            data_validator.account_version = DataValidator::VERSION;
+            ctx.accounts.validator_registry.validator_count += 1;
+        }
+        data_validator.active = active;
+        data_validator.designated_at = clock::now(ctx.remaining_accounts)?;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ProcessPayment<'info> {
-    #[account(mut)]
-    pub claim_account: Account<'info, ClaimAccount>,
-    #[account(mut)]
-    pub provider: Signer<'info>,
-    /// CHECK: We are transferring funds to this account, verified by claim_account.patient
-    #[account(mut)]
-    pub patient: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Creates the provider credentialing registry with `authority` as the
+    /// party that verifies providers. Call it in the deployment transaction so
+    /// nobody else can claim the registry.
+    pub fn initialize_registry_authority(
+        ctx: Context<InitializeRegistryAuthority>,
+        authority: Pubkey,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry_authority;
+        registry.authority = authority;
+        registry.verified_count = 0;
+        registry.account_version = RegistryAuthority::VERSION;
+        Ok(())
+    }
 
-#[account]
-pub struct PatientAccount {
-    pub authority: Pubkey,
-    pub did: String,
-}
+    /// Marks a registered provider as credentialed, so it can verify claims.
+    /// Verifying an already verified provider only refreshes `verified_at`.
+    pub fn verify_provider(ctx: Context<VerifyProvider>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        if ctx
+            .accounts
+            .provider_credential
+            .verify(ctx.accounts.provider.key(), now)
+        {
+            ctx.accounts.registry_authority.verified_count += 1;
+        }
+        Ok(())
+    }
 
-#[account]
-pub struct ProviderAccount {
-    pub authority: Pubkey,
-    pub did: String,
-    pub name: String,
-}
+    /// Withdraws a provider's credential. Claims it verified before stay
+    /// verified and can still be paid.
+    pub fn revoke_provider(ctx: Context<RevokeProvider>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.provider_credential.revoke(now)?;
+        ctx.accounts.registry_authority.verified_count -= 1;
+        Ok(())
+    }
 
-#[account]
-pub struct HealthDataAccount {
-    pub owner: Pubkey,
-    pub data_hash: String,
-    pub encrypted_data: String,
-    pub timestamp: i64,
-}
+    /// Creates the program config with the signer as its admin, unpaused and
+    /// with a protocol fee of `fee_bps`, and the treasury the fee is paid
+    /// into. Every instruction that writes requires the config, so create it
+    /// first, in the deployment transaction, so nobody else can claim it.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let admin = ctx.accounts.admin.key();
+        ctx.accounts.protocol_treasury.account_version = ProtocolTreasury::VERSION;
+        ctx.accounts.config.initialize(admin, fee_bps, now)
+    }
 
-#[account]
-pub struct ClaimAccount {
-    pub claim_id: String,
-    pub patient: Pubkey,
-    pub provider: Pubkey,
-    pub health_data_hash: String,
-    pub amount: u64,
-    pub status: ClaimStatus,
-    pub timestamp: i64,
-}
+    /// Sets the protocol fee taken from lamport and token claim payments.
+    /// Payments pay the fee in force when they are made.
+    pub fn set_protocol_fee(ctx: Context<SetConfig>, fee_bps: u16) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        msg!(
+            "Protocol fee {} -> {} bps",
+            ctx.accounts.config.fee_bps,
+            fee_bps
+        );
+        ctx.accounts.config.set_fee(fee_bps, now)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum ClaimStatus {
-    Pending,
-    Verified,
-    Paid,
-    Rejected,
-}
+    /// Sets the protocol fee rebates, at `["fee_rebates"]`. A provider whose
+    /// lamport claim payments earlier in the settlement period reach a
+    /// tier's `min_volume` keeps that tier's `rebate_bps` of the treasury's
+    /// fee on later ones. Tiers ascend in volume and rebate; an empty list
+    /// turns rebates off. Like the other config instructions, it works while
+    /// the program is paused.
+    pub fn set_fee_rebates(ctx: Context<SetFeeRebates>, tiers: Vec<RebateTier>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.fee_rebates.set(tiers, now)
+    }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("You are not authorized to perform this action.")]
-    Unauthorized,
-    #[msg("The patient account does not match the claim.")]
-    InvalidPatient,
-    #[msg("The claim must be verified before payment.")]
-    ClaimNotVerified,
+    /// Sets how long claims filed from now on may stay undecided before
+    /// anyone can expire them with `expire_claim`; 0 turns expiry off.
+    /// Claims already filed keep their deadline. Upgrade a config created
+    /// before claims expired first.
+    pub fn set_claim_ttl(ctx: Context<SetConfig>, ttl_secs: i64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        msg!(
+            "Claim lifetime {} -> {}s",
+            ctx.accounts.config.claim_ttl_secs,
+            ttl_secs
+        );
+        ctx.accounts.config.set_claim_ttl(ttl_secs, now)
+    }
+
+    /// Sends `lamports` of collected protocol fees to `recipient`. The
+    /// treasury keeps its rent. Like the other config instructions, it works
+    /// while the program is paused.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, lamports: u64) -> Result<()> {
+        let treasury = ctx.accounts.protocol_treasury.to_account_info();
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(treasury.data_len()));
+        if lamports > available {
+            msg!(
+                "Requested {} lamports; the treasury has {} available",
+                lamports,
+                available
+            );
+            return err!(ErrorCode::InsufficientTreasuryFunds);
+        }
+
+        ctx.accounts.protocol_treasury.record_withdrawal(lamports)?;
+        **treasury.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+
+    /// Opens the treasury for protocol fees paid in `mint`, at
+    /// `["mint_treasury", mint]`. `vault` is a token account of the mint
+    /// owned by the treasury's address, normally its associated token
+    /// account, and holds the fees. `destination` is the token account
+    /// `sweep_mint_treasury` consolidates them into.
+    pub fn open_mint_treasury(ctx: Context<OpenMintTreasury>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        expect_mint_account(&accounts.mint)?;
+        expect_token_account(
+            &accounts.vault,
+            &accounts.mint,
+            Some(accounts.mint_treasury.key()),
+        )?;
+        expect_token_account(&accounts.destination, &accounts.mint, None)?;
+        let decimals = token::unpack_mint_decimals(&accounts.mint.try_borrow_data()?)?;
+
+        let mint_treasury = &mut ctx.accounts.mint_treasury;
+        mint_treasury.mint = ctx.accounts.mint.key();
+        mint_treasury.decimals = decimals;
+        mint_treasury.vault = ctx.accounts.vault.key();
+        mint_treasury.destination = ctx.accounts.destination.key();
+        mint_treasury.account_version = MintTreasury::VERSION;
+        Ok(())
+    }
+
+    /// Points a mint treasury's sweeps at another token account of its mint.
+    pub fn set_treasury_destination(ctx: Context<SetTreasuryDestination>) -> Result<()> {
+        expect_token_account(&ctx.accounts.destination, &ctx.accounts.mint, None)?;
+        let mint_treasury = &mut ctx.accounts.mint_treasury;
+        msg!(
+            "Treasury destination {} -> {}",
+            mint_treasury.destination,
+            ctx.accounts.destination.key()
+        );
+        mint_treasury.destination = ctx.accounts.destination.key();
+        Ok(())
+    }
+
+    /// Moves a mint treasury's whole vault balance to its configured
+    /// destination. Like `withdraw_treasury`, it works while the program is
+    /// paused.
+    pub fn sweep_mint_treasury(ctx: Context<SweepMintTreasury>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let amount = token::unpack_account(&accounts.vault.try_borrow_data()?)?.amount;
+        if amount == 0 {
+            msg!("The {} treasury is empty", accounts.mint.key());
+            return Ok(());
+        }
+        let mint = accounts.mint.key();
+        let bump = [ctx.bumps.mint_treasury];
+        let seeds: &[&[u8]] = &[b"mint_treasury", mint.as_ref(), &bump];
+        anchor_lang::solana_program::program::invoke_signed(
+            &token::transfer_checked(
+                accounts.token_program.key,
+                accounts.vault.key,
+                &mint,
+                accounts.destination.key,
+                &accounts.mint_treasury.key(),
+                amount,
+                accounts.mint_treasury.decimals,
+            ),
+            &[
+                accounts.vault.to_account_info(),
+                accounts.mint.to_account_info(),
+                accounts.destination.to_account_info(),
+                accounts.mint_treasury.to_account_info(),
+                accounts.token_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        ctx.accounts.mint_treasury.record_sweep(amount)
+    }
+
+    /// Approves the facilitator run by `authority`, at `["facilitator",
+    /// authority]`, to submit claim payments signed by `signing_key`, with
+    /// `fee_share_bps` of the protocol fee on them. Approving an existing
+    /// facilitator replaces its key and share and lifts any revocation. Like
+    /// the other config instructions, it works while the program is paused.
+    pub fn approve_facilitator(
+        ctx: Context<ApproveFacilitator>,
+        signing_key: Pubkey,
+        fee_share_bps: u16,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let facilitator = &mut ctx.accounts.facilitator;
+        facilitator.approve(
+            ctx.accounts.authority.key(),
+            signing_key,
+            fee_share_bps,
+            now,
+        )?;
+        emit_facilitator_updated(facilitator, now);
+        Ok(())
+    }
+
+    /// Replaces the facilitator's signing key, as its authority. Payments
+    /// signed with the old key are no longer accepted.
+    pub fn rotate_facilitator_key(
+        ctx: Context<RotateFacilitatorKey>,
+        signing_key: Pubkey,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let facilitator = &mut ctx.accounts.facilitator;
+        facilitator.rotate_key(signing_key, now)?;
+        emit_facilitator_updated(facilitator, now);
+        Ok(())
+    }
+
+    /// Stops attributing payments to the facilitator. Its account, with the
+    /// payments already attributed and any fees not yet withdrawn, is kept.
+    /// Like the other config instructions, it works while the program is
+    /// paused.
+    pub fn revoke_facilitator(ctx: Context<RevokeFacilitator>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let facilitator = &mut ctx.accounts.facilitator;
+        facilitator.revoked = true;
+        emit_facilitator_updated(facilitator, now);
+        Ok(())
+    }
+
+    /// Sends `lamports` of the facilitator's fee share to `recipient`, as its
+    /// authority. The account keeps its rent. Revoked facilitators can still
+    /// withdraw what they earned.
+    pub fn withdraw_facilitator_fees(
+        ctx: Context<WithdrawFacilitatorFees>,
+        lamports: u64,
+    ) -> Result<()> {
+        let facilitator = ctx.accounts.facilitator.to_account_info();
+        let available = facilitator
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(facilitator.data_len()));
+        if lamports > available {
+            msg!(
+                "Requested {} lamports; the facilitator has {} available",
+                lamports,
+                available
+            );
+            return err!(ErrorCode::InsufficientFacilitatorFees);
+        }
+
+        ctx.accounts.facilitator.record_withdrawal(lamports)?;
+        **facilitator.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+
+    /// Sets the lamports paid from the crank vault at `["crank_vault"]` to
+    /// whoever runs a due permissionless crank, up to
+    /// `MAX_CRANK_REWARD_LAMPORTS`. 0 turns rewards off. Like the other config
+    /// instructions, it works while the program is paused.
+    pub fn set_crank_reward(ctx: Context<SetCrankReward>, reward_lamports: u64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.crank_vault.set_reward(reward_lamports, now)
+    }
+
+    /// Adds `lamports` to the crank vault. Anyone may fund it.
+    pub fn fund_crank_vault(ctx: Context<FundCrankVault>, lamports: u64) -> Result<()> {
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.funder.to_account_info(),
+                to: ctx.accounts.crank_vault.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, lamports)
+    }
+
+    /// Pauses or resumes the program. While paused, every instruction that
+    /// writes fails with `ProgramPaused`; simulated checks and the config
+    /// instructions still work.
+    pub fn set_paused(ctx: Context<SetConfig>, paused: bool) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        msg!("Paused {} -> {}", ctx.accounts.config.paused, paused);
+        ctx.accounts.config.set_paused(paused, now);
+        Ok(())
+    }
+
+    /// Hands the config to `new_admin`, who must sign too so the config
+    /// cannot be handed to a key nobody holds.
+    pub fn transfer_admin(ctx: Context<TransferAdmin>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let new_admin = ctx.accounts.new_admin.key();
+        msg!("Admin {} -> {}", ctx.accounts.config.admin, new_admin);
+        ctx.accounts.config.transfer_admin(new_admin, now);
+        Ok(())
+    }
+
+    /// Creates the feature flags with the signer as their admin and `flags`
+    /// enabled. Call it in the deployment transaction so nobody else can
+    /// claim the flags.
+    pub fn initialize_feature_flags(
+        ctx: Context<InitializeFeatureFlags>,
+        flags: u64,
+    ) -> Result<()> {
+        let feature_flags = &mut ctx.accounts.feature_flags;
+        feature_flags.admin = ctx.accounts.admin.key();
+        feature_flags.account_version = FeatureFlags::VERSION;
+        feature_flags.flags = flags;
+        feature_flags.updated_at = clock::now(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    /// Replaces the enabled `FEATURE_*` flags. A disabled family rejects new
+    /// subscriptions, sponsorships or tenant claims at once, while accounts
+    /// already open can still be withdrawn from, cancelled or paid.
+    pub fn set_feature_flags(ctx: Context<SetFeatureFlags>, flags: u64) -> Result<()> {
+        let feature_flags = &mut ctx.accounts.feature_flags;
+        msg!("Feature flags {:#x} -> {:#x}", feature_flags.flags, flags);
+        feature_flags.flags = flags;
+        feature_flags.updated_at = clock::now(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    /// Attests that a record conforms to the FHIR profile with hash
+    /// `profile_hash`, with a quality `score` out of `MAX_QUALITY_SCORE`.
+    /// Attesting again replaces the validator's earlier attestation.
+    pub fn attest_record_quality(
+        ctx: Context<AttestRecordQuality>,
+        profile_hash: [u8; 32],
+        score: u8,
+    ) -> Result<()> {
+        let data_validator = &mut ctx.accounts.data_validator;
+        if !data_validator.active {
+            msg!("Validator {} is suspended", data_validator.authority);
+            return err!(ErrorCode::ValidatorInactive);
+        }
+        if score > MAX_QUALITY_SCORE {
+            msg!("Quality score {} is over {}", score, MAX_QUALITY_SCORE);
+            return err!(ErrorCode::InvalidQualityScore);
+        }
+        data_validator.attestation_count += 1;
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.record = ctx.accounts.record.key();
+        attestation.account_version = QualityAttestation::VERSION;
+        attestation.validator = data_validator.authority;
+        attestation.profile_hash = profile_hash;
+        attestation.score = score;
+        attestation.attested_at = clock::now(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    /// Creates the tenant network `tenant_id` with the signer as its admin.
+    /// Paying one of the tenant's claims also sends `fee_bps` of the amount
+    /// paid from the provider to `treasury`.
+    pub fn create_tenant(
+        ctx: Context<CreateTenant>,
+        tenant_id: String,
+        treasury: Pubkey,
+        fee_bps: u16,
+    ) -> Result<()> {
+        validate_not_empty("tenant_id", &tenant_id, ErrorCode::TenantIdEmpty)?;
+        validate_len(
+            "tenant_id",
+            &tenant_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::TenantIdTooLong,
+        )?;
+        validate_tenant_fee(fee_bps)?;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        let tenant = &mut ctx.accounts.tenant;
+        tenant.admin = ctx.accounts.admin.key();
+        tenant.account_version = Tenant::VERSION;
+        tenant.tenant_id = tenant_id;
+        tenant.treasury = treasury;
+        tenant.fee_bps = fee_bps;
+        tenant.member_count = 0;
+        tenant.created_at = now;
+        tenant.updated_at = now;
+        Ok(())
+    }
+
+    /// Replaces the tenant's admin, treasury and fee. Claims pay the fee in
+    /// force when they are paid, not when they were filed.
+    pub fn update_tenant(
+        ctx: Context<UpdateTenant>,
+        admin: Pubkey,
+        treasury: Pubkey,
+        fee_bps: u16,
+    ) -> Result<()> {
+        validate_tenant_fee(fee_bps)?;
+        let tenant = &mut ctx.accounts.tenant;
+        tenant.admin = admin;
+        tenant.treasury = treasury;
+        tenant.fee_bps = fee_bps;
+        tenant.updated_at = clock::now(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    /// Admits `member` to the tenant as a patient or provider. Only members
+    /// can be parties to the tenant's claims.
+    pub fn admit_tenant_member(ctx: Context<AdmitTenantMember>, role: TenantRole) -> Result<()> {
+        let membership = &mut ctx.accounts.membership;
+        membership.tenant = ctx.accounts.tenant.key();
+        membership.account_version = TenantMember::VERSION;
+        membership.member = ctx.accounts.member.key();
+        membership.role = role;
+        membership.admitted_at = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.tenant.member_count += 1;
+        Ok(())
+    }
+
+    /// Removes a member from the tenant. Claims they are already party to
+    /// can still be adjudicated and paid.
+    pub fn remove_tenant_member(ctx: Context<RemoveTenantMember>) -> Result<()> {
+        let tenant = &mut ctx.accounts.tenant;
+        tenant.member_count = tenant.member_count.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Sets the monthly `premium`, in lamports, that the tenant's patients
+    /// pay into its treasury, at `["plan_benefits", tenant]`. Patients whose
+    /// research consent covers every category in `research_categories` pay
+    /// `research_discount_bps` less; a discount of 0 turns it off. Once a
+    /// patient's share of their claims in a plan year reaches
+    /// `out_of_pocket_max` lamports, the provider covers the rest of every
+    /// claim it verifies that year; 0 sets no maximum.
+    pub fn set_plan_benefits(
+        ctx: Context<SetPlanBenefits>,
+        premium: u64,
+        research_discount_bps: u16,
+        research_categories: u8,
+        out_of_pocket_max: u64,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let plan_benefits = &mut ctx.accounts.plan_benefits;
+        plan_benefits.tenant = ctx.accounts.tenant.key();
+        plan_benefits.set(
+            premium,
+            research_discount_bps,
+            research_categories,
+            out_of_pocket_max,
+            now,
+        )
+    }
+
+    /// Pays the signing patient's premium for `period`, the current `YYYYMM`
+    /// month, into the tenant's treasury, and records it at
+    /// `["premium_payment", tenant, patient, period]` so each month is paid
+    /// once. Passing the patient's research consent takes off the plan's
+    /// research discount if the consent is active and covers the plan's
+    /// research categories.
+    pub fn pay_premium(ctx: Context<PayPremium>, period: u32) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_member(&ctx.accounts.patient_membership, TenantRole::Patient)?;
+        if period != settlement::period_of(now) {
+            msg!(
+                "Period {} is not the current one ({})",
+                period,
+                settlement::period_of(now)
+            );
+            return err!(ErrorCode::InvalidSettlementPeriod);
+        }
+        let plan_benefits = &ctx.accounts.plan_benefits;
+        let discount =
+            plan_benefits.research_discount(ctx.accounts.research_consent.as_deref(), now)?;
+        let amount = plan_benefits.premium - discount;
+
+        let payment = &mut ctx.accounts.premium_payment;
+        payment.tenant = ctx.accounts.tenant.key();
+        payment.account_version = PremiumPayment::VERSION;
+        payment.patient = ctx.accounts.patient.key();
+        payment.period = period;
+        payment.amount = amount;
+        payment.discount = discount;
+        payment.paid_at = now;
+        emit!(PremiumPaid {
+            tenant: payment.tenant,
+            patient: payment.patient,
+            period,
+            premium: plan_benefits.premium,
+            discount,
+            timestamp: now,
+        });
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.patient.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, amount)
+    }
+
+    /// Files a claim from `patient` to `provider`. The filer is the patient,
+    /// or a guardian or delegate allowed to file claims for them. The claimed
+    /// amount is the billed total of the line items. `service_start` and
+    /// `service_end` are when the care happened, which may be well before the
+    /// claim is filed.
+    /// `fingerprint` must be `ClaimDedup::fingerprint` of the claim with its
+    /// service start; a second claim for the same procedures starting on the
+    /// same service day fails with `DuplicateClaim`. With a `mint` the claim
+    /// is billed and paid in that SPL token instead of lamports. Passing
+    /// `claim_grant` gives the provider read access to the claim's
+    /// attachments until the claim is finished, with no separate consent.
+    /// With an `insurer` the claim is not paid until the insurer approves it
+    /// with `adjudicate_claim`.
+    pub fn create_claim(
+        ctx: Context<CreateClaim>,
+        claim_id: String,
+        line_items: Vec<LineItemInput>,
+        attachments: Vec<[u8; 32]>,
+        service_start: i64,
+        service_end: i64,
+        fingerprint: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_agent(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.guardian_link.as_deref(),
+            ctx.accounts.filer.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_FILE_CLAIMS,
+            GUARDIAN_FILE_CLAIMS,
+            now,
+        )?;
+        let mint = match &ctx.accounts.mint {
+            Some(mint) => {
+                expect_mint_account(mint)?;
+                mint.key()
+            }
+            None => Pubkey::default(),
+        };
+        file_claim(
+            ClaimFiling {
+                claim_account: &mut ctx.accounts.claim_account,
+                patient: ctx.accounts.patient.key(),
+                filer: ctx.accounts.filer.key(),
+                provider: ctx.accounts.provider.key(),
+                provider_stats: &mut ctx.accounts.provider_stats,
+                claim_dedup: &mut ctx.accounts.claim_dedup,
+                tenant: Pubkey::default(),
+                mint,
+                enrolled_since: 0,
+                insurer: insurer_key(ctx.accounts.insurer.as_deref()),
+                claim_grant: ctx.accounts.claim_grant.as_mut(),
+                expires_at: ctx.accounts.config.claim_expiry(now),
+            },
+            ClaimInput {
+                claim_id,
+                line_items,
+                attachments,
+                service_start,
+                service_end,
+                fingerprint,
+            },
+            now,
+        )
+    }
+
+    /// Files up to `MAX_CLAIM_BATCH` claims with the signer's `provider` in
+    /// one transaction, each checked as `create_claim` checks it. For each
+    /// claim, in order, pass six remaining accounts: the claim at
+    /// `["claim", claim_id]`, its dedup marker at
+    /// `["claim_dedup", fingerprint]`, the patient, the filer's delegate
+    /// account for that patient, the filer's guardian link at
+    /// `["dependent", filer, patient]`, and the claim's grant at
+    /// `["claim_grant", claim]`. Pass the program id in place of a delegate
+    /// or link the filer does not use, and of the grant for no grant. The
+    /// `insurer` and `mint`, when passed, apply to every claim in the batch.
+    /// The filer pays every account's rent.
+    pub fn create_claims_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateClaimsBatch<'info>>,
+        claims: Vec<ClaimInput>,
+    ) -> Result<()> {
+        require!(!claims.is_empty(), ErrorCode::EmptyBatch);
+        validate_count(
+            "claims",
+            claims.len(),
+            MAX_CLAIM_BATCH,
+            ErrorCode::BatchTooLarge,
+        )?;
+        let groups = batch_accounts(ctx.remaining_accounts, claims.len(), 6)?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        let accounts = ctx.accounts;
+        let filer = accounts.filer.key();
+        let mint = match &accounts.mint {
+            Some(mint) => {
+                expect_mint_account(mint)?;
+                mint.key()
+            }
+            None => Pubkey::default(),
+        };
+        let insurer = insurer_key(accounts.insurer.as_deref());
+        for (claim, group) in claims.into_iter().zip(groups.chunks(6)) {
+            let [claim_info, dedup_info, patient_info, delegate_info, link_info, grant_info] =
+                group
+            else {
+                unreachable!("batch_accounts returns whole groups");
+            };
+            validate_not_empty("claim_id", &claim.claim_id, ErrorCode::ClaimIdEmpty)?;
+            validate_len(
+                "claim_id",
+                &claim.claim_id,
+                MAX_SEED_ID_LEN,
+                ErrorCode::ClaimIdTooLong,
+            )?;
+            if *claim_info.owner == crate::ID {
+                msg!("Claim {} already exists", claim.claim_id);
+                return err!(ErrorCode::BatchAccountsMismatch);
+            }
+            let patient = patient_info.key();
+            let delegate = if delegate_info.key() == crate::ID {
+                None
+            } else {
+                expect_batch_address(
+                    delegate_info,
+                    &[b"delegate", patient.as_ref(), filer.as_ref()],
+                )?;
+                Some(Account::<Delegate>::try_from(delegate_info)?)
+            };
+            let link = if link_info.key() == crate::ID {
+                None
+            } else {
+                expect_batch_address(link_info, &[b"dependent", filer.as_ref(), patient.as_ref()])?;
+                Some(Account::<DependentLink>::try_from(link_info)?)
+            };
+            expect_agent(
+                delegate.as_deref(),
+                link.as_deref(),
+                filer,
+                patient,
+                DELEGATE_FILE_CLAIMS,
+                GUARDIAN_FILE_CLAIMS,
+                now,
+            )?;
+
+            let mut claim_account = init_batch_account::<ClaimAccount>(
+                claim_info,
+                &[b"claim", claim.claim_id.as_bytes()],
+                migration::new_claim_space(&claim.claim_id, claim.line_items.len()),
+                &accounts.filer,
+                &accounts.system_program,
+            )?;
+            let mut claim_dedup = init_batch_account::<ClaimDedup>(
+                dedup_info,
+                &[b"claim_dedup", claim.fingerprint.as_ref()],
+                ClaimDedup::SPACE,
+                &accounts.filer,
+                &accounts.system_program,
+            )?;
+            let mut claim_grant = if grant_info.key() == crate::ID {
+                None
+            } else {
+                Some(init_batch_account::<ClaimGrant>(
+                    grant_info,
+                    &[b"claim_grant", claim_info.key.as_ref()],
+                    ClaimGrant::SPACE,
+                    &accounts.filer,
+                    &accounts.system_program,
+                )?)
+            };
+            file_claim(
+                ClaimFiling {
+                    claim_account: &mut claim_account,
+                    patient,
+                    filer,
+                    provider: accounts.provider.key(),
+                    provider_stats: &mut accounts.provider_stats,
+                    claim_dedup: &mut claim_dedup,
+                    tenant: Pubkey::default(),
+                    mint,
+                    enrolled_since: 0,
+                    insurer,
+                    claim_grant: claim_grant.as_mut(),
+                    expires_at: accounts.config.claim_expiry(now),
+                },
+                claim,
+                now,
+            )?;
+            claim_account.exit(&crate::ID)?;
+            claim_dedup.exit(&crate::ID)?;
+            if let Some(claim_grant) = claim_grant {
+                claim_grant.exit(&crate::ID)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Files a claim inside a tenant network, like `create_claim`. The claim
+    /// lives at `["claim", tenant, claim_id]`, so tenants can reuse claim ids,
+    /// and both the patient and the provider must be members of the tenant.
+    /// Membership is the patient's enrollment in the network's plan, so the
+    /// care must not have started before the patient was admitted.
+    pub fn create_tenant_claim(
+        ctx: Context<CreateTenantClaim>,
+        claim_id: String,
+        line_items: Vec<LineItemInput>,
+        attachments: Vec<[u8; 32]>,
+        service_start: i64,
+        service_end: i64,
+        fingerprint: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_agent(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.guardian_link.as_deref(),
+            ctx.accounts.filer.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_FILE_CLAIMS,
+            GUARDIAN_FILE_CLAIMS,
+            now,
+        )?;
+        expect_member(&ctx.accounts.patient_membership, TenantRole::Patient)?;
+        expect_member(&ctx.accounts.provider_membership, TenantRole::Provider)?;
+        file_claim(
+            ClaimFiling {
+                claim_account: &mut ctx.accounts.claim_account,
+                patient: ctx.accounts.patient.key(),
+                filer: ctx.accounts.filer.key(),
+                provider: ctx.accounts.provider.key(),
+                provider_stats: &mut ctx.accounts.provider_stats,
+                claim_dedup: &mut ctx.accounts.claim_dedup,
+                tenant: ctx.accounts.tenant.key(),
+                mint: Pubkey::default(),
+                enrolled_since: ctx.accounts.patient_membership.admitted_at,
+                insurer: insurer_key(ctx.accounts.insurer.as_deref()),
+                claim_grant: ctx.accounts.claim_grant.as_mut(),
+                expires_at: ctx.accounts.config.claim_expiry(now),
+            },
+            ClaimInput {
+                claim_id,
+                line_items,
+                attachments,
+                service_start,
+                service_end,
+                fingerprint,
+            },
+            now,
+        )
+    }
+
+    /// Sets the signing provider's prices for procedure codes, at
+    /// `["fee_schedule", provider]`. Claims created from appointments bill
+    /// each service at its price here. Setting it again replaces the entries.
+    pub fn set_fee_schedule(
+        ctx: Context<SetFeeSchedule>,
+        entries: Vec<FeeScheduleEntry>,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts
+            .fee_schedule
+            .set(ctx.accounts.provider.key(), entries, now)
+    }
+
+    /// Books appointment `appointment_id` with a registered provider for the
+    /// signing patient at `scheduled_at`, at
+    /// `["appointment", provider, appointment_id]`. The patient pays for the
+    /// account.
+    pub fn schedule_appointment(
+        ctx: Context<ScheduleAppointment>,
+        appointment_id: String,
+        scheduled_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.appointment.schedule(
+            appointment_id,
+            ctx.accounts.patient.key(),
+            ctx.accounts.provider.key(),
+            scheduled_at,
+        )
+    }
+
+    /// Marks a scheduled appointment complete, as its provider, with the
+    /// services performed and the hashes of the records made during the
+    /// session.
+    pub fn complete_appointment(
+        ctx: Context<CompleteAppointment>,
+        services: Vec<AppointmentService>,
+        session_records: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts
+            .appointment
+            .complete(services, session_records, now)
+    }
+
+    /// Files the claim for a completed appointment, as its provider, who pays
+    /// the claim's rent. Each service is billed at its price in the
+    /// provider's fee schedule, the session records are attached, and the
+    /// service period runs from the appointment's start to its completion.
+    /// The appointment keeps the claim's address and cannot be billed again.
+    /// `fingerprint` must be `ClaimDedup::fingerprint` of the resulting line
+    /// items, as for `create_claim`.
+    pub fn create_claim_from_appointment(
+        ctx: Context<CreateClaimFromAppointment>,
+        claim_id: String,
+        fingerprint: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let appointment = &mut ctx.accounts.appointment;
+        let line_items = appointment.line_items(&ctx.accounts.fee_schedule)?;
+        file_claim(
+            ClaimFiling {
+                claim_account: &mut ctx.accounts.claim_account,
+                patient: appointment.patient,
+                filer: appointment.provider,
+                provider: appointment.provider,
+                provider_stats: &mut ctx.accounts.provider_stats,
+                claim_dedup: &mut ctx.accounts.claim_dedup,
+                tenant: Pubkey::default(),
+                mint: Pubkey::default(),
+                enrolled_since: 0,
+                insurer: Pubkey::default(),
+                claim_grant: ctx.accounts.claim_grant.as_mut(),
+                expires_at: ctx.accounts.config.claim_expiry(now),
+            },
+            ClaimInput {
+                claim_id,
+                line_items,
+                attachments: appointment.session_records.clone(),
+                service_start: appointment.scheduled_at,
+                service_end: appointment.completed_at,
+                fingerprint,
+            },
+            now,
+        )?;
+        let claim = ctx.accounts.claim_account.key();
+        appointment.bill(claim)?;
+        emit!(AppointmentBilled {
+            appointment: appointment.key(),
+            claim,
+            patient: appointment.patient,
+            provider: appointment.provider,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Takes on a pending claim, putting it under review. A provider must
+    /// accept a claim before adjudicating it.
+    pub fn accept_claim(ctx: Context<ReviewClaim>) -> Result<()> {
+        ctx.accounts.claim_account.accept()
+    }
+
+    /// Turns down a pending claim so the patient can see it will not be
+    /// adjudicated and reassign it with `reassign_claim`.
+    pub fn decline_claim(ctx: Context<ReviewClaim>) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        claim_account.decline()?;
+        emit_claim_decision(claim_account, false, clock::now(ctx.remaining_accounts)?)
+    }
+
+    /// Closes adjudication of an open claim as `Verified` (every line decided)
+    /// or `Rejected`. Only providers the credentialing authority has verified
+    /// can close a claim; a revoked provider can still decline pending ones.
+    /// Tenant claims take the tenant's plan benefits and, if the plan has an
+    /// out-of-pocket maximum, the patient's tally for the claim's year: once
+    /// the patient's share reaches the maximum, the provider covers the rest.
+    pub fn verify_claim(ctx: Context<VerifyClaim>, status: ClaimStatus) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        claim_account.verify(status, now)?;
+        apply_out_of_pocket_max(
+            claim_account,
+            ctx.accounts.plan_benefits.as_ref(),
+            ctx.accounts.out_of_pocket.as_mut(),
+            now,
+        )?;
+        emit_claim_decision(claim_account, false, now)
+    }
+
+    /// Closes up to `MAX_CLAIM_BATCH` of the signer's claims at once, like
+    /// `verify_claim`. Pass the claims as the first remaining accounts, in
+    /// the order of `statuses`. The batch fails as a whole if any claim
+    /// cannot take its status. Tenant claims are verified with `verify_claim`
+    /// to apply their plan's out-of-pocket maximum; a batch can only reject
+    /// them.
+    pub fn verify_claims_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyClaimsBatch<'info>>,
+        statuses: Vec<ClaimStatus>,
+    ) -> Result<()> {
+        require!(!statuses.is_empty(), ErrorCode::EmptyBatch);
+        validate_count(
+            "statuses",
+            statuses.len(),
+            MAX_CLAIM_BATCH,
+            ErrorCode::BatchTooLarge,
+        )?;
+        let infos = batch_accounts(ctx.remaining_accounts, statuses.len(), 1)?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        let provider = ctx.accounts.provider.key();
+        for (status, info) in statuses.into_iter().zip(infos) {
+            let mut claim_account = Account::<ClaimAccount>::try_from(info)?;
+            if claim_account.provider != provider {
+                msg!("Claim {} is assigned to another provider", info.key());
+                return err!(ErrorCode::Unauthorized);
+            }
+            if status == ClaimStatus::Verified && claim_account.tenant != Pubkey::default() {
+                msg!(
+                    "Claim {} is a tenant claim; verify it on its own",
+                    info.key()
+                );
+                return err!(ErrorCode::PlanBenefitsRequired);
+            }
+            claim_account.verify(status, now)?;
+            emit_claim_decision(&claim_account, false, now)?;
+            claim_account.exit(&crate::ID)?;
+        }
+        Ok(())
+    }
+
+    /// Opts a pending or under-review claim into auto-approval: if it is still
+    /// undecided `days` from now, anyone can verify it with
+    /// `auto_approve_claim`. The patient and provider both sign.
+    pub fn enable_auto_approval(ctx: Context<EnableAutoApproval>, days: u16) -> Result<()> {
+        if days == 0 || days > MAX_AUTO_APPROVAL_DAYS {
+            msg!(
+                "Auto-approval after {} days; must be 1 to {}",
+                days,
+                MAX_AUTO_APPROVAL_DAYS
+            );
+            return err!(ErrorCode::InvalidAutoApprovalWindow);
+        }
+        expect_status(
+            ctx.accounts.claim_account.status,
+            &AUTO_APPROVABLE_STATUSES,
+            ErrorCode::ClaimNotOpen,
+        )?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        let auto_approval = &mut ctx.accounts.auto_approval;
+        auto_approval.claim = ctx.accounts.claim_account.key();
+        auto_approval.deadline = now + days as i64 * 24 * 60 * 60;
+        auto_approval.created_at = now;
+        auto_approval.account_version = AutoApproval::VERSION;
+        Ok(())
+    }
+
+    /// Permissionless crank: verifies a claim whose auto-approval deadline
+    /// passed without a decision, approving every line still pending. The
+    /// opt-in is closed to the patient, also once the claim was decided some
+    /// other way. Tenant claims take the same accounts as in `verify_claim`.
+    pub fn auto_approve_claim(ctx: Context<AutoApproveClaim>) -> Result<()> {
+        let status = ctx.accounts.claim_account.status;
+        if !AUTO_APPROVABLE_STATUSES.contains(&status) && status != ClaimStatus::OnHold {
+            return Ok(());
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        let deadline = ctx.accounts.auto_approval.deadline;
+        if now < deadline {
+            msg!("Auto-approval is due at {}; now is {}", deadline, now);
+            return err!(ErrorCode::AutoApprovalNotDue);
+        }
+        let claim_account = &mut ctx.accounts.claim_account;
+        claim_account.auto_approve(now)?;
+        apply_out_of_pocket_max(
+            claim_account,
+            ctx.accounts.plan_benefits.as_ref(),
+            ctx.accounts.out_of_pocket.as_mut(),
+            now,
+        )?;
+        emit_claim_decision(claim_account, true, now)?;
+        pay_crank_reward(
+            ctx.accounts.crank_vault.as_mut(),
+            &ctx.accounts.cranker,
+            ctx.accounts.claim_account.key(),
+            now,
+        )
+    }
+
+    /// Permissionless crank: expires a claim still pending, under review or
+    /// on hold at its `expires_at`. Pass the claim's standard escrow, if any,
+    /// to close it back to the provider in the same transaction; a split
+    /// escrow needs two signatures, so settle it with `settle_claim`
+    /// afterwards. The expired claim can then be closed with `close_claim`.
+    pub fn expire_claim(ctx: Context<ExpireClaim>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let claim_account = &mut ctx.accounts.claim_account;
+        claim_account.expire(now)?;
+
+        let mut refunded = 0;
+        if let Some(claim_escrow) = &ctx.accounts.claim_escrow {
+            // An expired claim is owed nothing, so the escrow closes to the
+            // provider
+            let owed = claim_escrow.settle(claim_account)?;
+            claim_escrow.authorize_release(claim_account, owed, &[ctx.accounts.cranker.key()])?;
+            refunded = claim_escrow.amount;
+        }
+        emit!(ClaimExpired {
+            claim: claim_account.key(),
+            patient: claim_account.patient,
+            provider: claim_account.provider,
+            expires_at: claim_account.expires_at,
+            refunded,
+            timestamp: now,
+        });
+        pay_crank_reward(
+            ctx.accounts.crank_vault.as_mut(),
+            &ctx.accounts.cranker,
+            ctx.accounts.claim_account.key(),
+            now,
+        )
+    }
+
+    /// Pauses adjudication of an open claim until the patient supplies more
+    /// documentation. Attachments can be edited while the claim is on hold.
+    pub fn place_on_hold(ctx: Context<ReviewClaim>) -> Result<()> {
+        ctx.accounts.claim_account.place_on_hold()
+    }
+
+    /// Resumes adjudication of a claim on hold, putting it under review.
+    pub fn release_hold(ctx: Context<ReviewClaim>) -> Result<()> {
+        ctx.accounts.claim_account.release_hold()
+    }
+
+    /// Puts a rejected claim back under review with new evidence, once and
+    /// within `REOPEN_WINDOW_SECS` of the rejection.
+    pub fn reopen_claim(
+        ctx: Context<ReopenClaim>,
+        new_attachments: Vec<[u8; 32]>,
+        period: u32,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.claim_account.reopen(new_attachments, now)?;
+
+        let claim_account = &ctx.accounts.claim_account;
+        let summary = open_settlement(
+            &mut ctx.accounts.settlement,
+            claim_account.provider,
+            claim_account.patient,
+            period,
+            now,
+        )?;
+        summary.disputes += 1;
+        Ok(())
+    }
+
+    /// Creates the dispute settings with the signer as their admin. Call it
+    /// in the deployment transaction so nobody else can claim them.
+    pub fn initialize_dispute_config(
+        ctx: Context<InitializeDisputeConfig>,
+        arbiter: Pubkey,
+        resolution_secs: i64,
+        bond_lamports: u64,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let dispute_config = &mut ctx.accounts.dispute_config;
+        dispute_config.admin = ctx.accounts.admin.key();
+        dispute_config.set(arbiter, resolution_secs, bond_lamports, now)
+    }
+
+    /// Replaces the arbiter, the resolution deadline and the bond. Disputes
+    /// already open keep the arbiter, deadline and bond they were opened with.
+    pub fn set_dispute_config(
+        ctx: Context<SetDisputeConfig>,
+        arbiter: Pubkey,
+        resolution_secs: i64,
+        bond_lamports: u64,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts
+            .dispute_config
+            .set(arbiter, resolution_secs, bond_lamports, now)
+    }
+
+    /// Contests a rejected claim before the arbiter, once and within
+    /// `REOPEN_WINDOW_SECS` of the rejection. `reason_hash` is the hash of
+    /// the patient's encrypted statement, stored off-chain. The claim stays
+    /// `Disputed` until `resolve_dispute`. The patient locks the configured
+    /// bond in the dispute account until then.
+    pub fn dispute_claim(ctx: Context<DisputeClaim>, reason_hash: [u8; 32]) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let claim = ctx.accounts.claim_account.key();
+        ctx.accounts.claim_dispute.open(
+            claim,
+            &mut ctx.accounts.claim_account,
+            &ctx.accounts.dispute_config,
+            reason_hash,
+            now,
+        )?;
+
+        let claim_dispute = &ctx.accounts.claim_dispute;
+        emit!(ClaimDisputed {
+            claim,
+            patient: claim_dispute.patient,
+            provider: claim_dispute.provider,
+            arbiter: claim_dispute.arbiter,
+            reason_hash,
+            deadline: claim_dispute.deadline,
+            bond: claim_dispute.bond,
+            timestamp: now,
+        });
+        if claim_dispute.bond == 0 {
+            return Ok(());
+        }
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.patient.to_account_info(),
+                to: claim_dispute.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, claim_dispute.bond)
+    }
+
+    /// Settles a disputed claim. Before the deadline only the dispute's
+    /// arbiter can, and `patient_prevails` decides it: the patient prevailing
+    /// approves the claim's unpaid lines and verifies it, otherwise it goes
+    /// back to `Rejected`. From the deadline anyone can, and the claim is
+    /// decided for the patient whatever `patient_prevails` says. The bond
+    /// goes back to the patient if they prevail and to the provider if not.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, patient_prevails: bool) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let resolver = ctx.accounts.resolver.key();
+        ctx.accounts.claim_dispute.resolve(
+            &mut ctx.accounts.claim_account,
+            resolver,
+            patient_prevails,
+            now,
+        )?;
+        let bond = ctx.accounts.claim_dispute.release_bond()?;
+        if bond > 0 {
+            let recipient = if ctx.accounts.claim_dispute.patient_prevailed {
+                ctx.accounts.patient.to_account_info()
+            } else {
+                ctx.accounts.provider.to_account_info()
+            };
+            **ctx
+                .accounts
+                .claim_dispute
+                .to_account_info()
+                .try_borrow_mut_lamports()? -= bond;
+            **recipient.try_borrow_mut_lamports()? += bond;
+        }
+
+        let claim_dispute = &ctx.accounts.claim_dispute;
+        emit!(DisputeResolved {
+            claim: claim_dispute.claim,
+            resolver,
+            patient_prevailed: claim_dispute.patient_prevailed,
+            status: ctx.accounts.claim_account.status,
+            bond,
+            timestamp: now,
+        });
+        if now < claim_dispute.deadline {
+            return Ok(());
+        }
+        pay_crank_reward(
+            ctx.accounts.crank_vault.as_mut(),
+            &ctx.accounts.resolver,
+            ctx.accounts.claim_dispute.key(),
+            now,
+        )
+    }
+
+    /// Locks `lamports` of the provider's collateral in their stake at
+    /// `["provider_stake", provider]`, creating it on first use. The
+    /// arbiter can slash it with `rule_fraud` when a dispute of one of the
+    /// provider's claims turns out to be fraud.
+    pub fn stake_collateral(ctx: Context<StakeCollateral>, lamports: u64) -> Result<()> {
+        let provider_stake = &mut ctx.accounts.provider_stake;
+        provider_stake.provider = ctx.accounts.provider.key();
+        provider_stake.stake(lamports)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.provider.to_account_info(),
+                to: provider_stake.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, lamports)
+    }
+
+    /// Starts unbonding `lamports` of the provider's stake. They can still
+    /// be slashed until `withdraw_collateral` pays them out, which it can
+    /// from `STAKE_UNBONDING_SECS` after the latest unbonding.
+    pub fn unstake_collateral(ctx: Context<UnstakeCollateral>, lamports: u64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.provider_stake.unstake(lamports, now)
+    }
+
+    /// Pays the provider's unbonded collateral back to them, leaving the
+    /// stake account and whatever is still staked.
+    pub fn withdraw_collateral(ctx: Context<UnstakeCollateral>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let lamports = ctx.accounts.provider_stake.withdraw(now)?;
+        let stake = ctx.accounts.provider_stake.to_account_info();
+        **stake.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.provider.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+
+    /// Rules that the provider's rejection of a claim was fraud, after the
+    /// patient prevailed in its dispute. Only the dispute's arbiter can, once
+    /// per claim, and the finding stays at `["fraud_finding", claim]`. Up to
+    /// `slash_lamports` of the provider's collateral, staked or unbonding,
+    /// goes to the patient, and the fraud count in the provider's
+    /// `provider_stats` goes up. Pass no `provider_stake` for a provider who
+    /// never staked.
+    pub fn rule_fraud(ctx: Context<RuleFraud>, slash_lamports: u64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let arbiter = ctx.accounts.arbiter.key();
+        let fraud_finding = &mut ctx.accounts.fraud_finding;
+        fraud_finding.rule(&ctx.accounts.claim_dispute, arbiter, now)?;
+        if let Some(provider_stake) = ctx.accounts.provider_stake.as_mut() {
+            let slashed = provider_stake.slash(slash_lamports)?;
+            **provider_stake.to_account_info().try_borrow_mut_lamports()? -= slashed;
+            **ctx.accounts.patient.try_borrow_mut_lamports()? += slashed;
+            fraud_finding.slashed = slashed;
+        }
+        let provider_stats = &mut ctx.accounts.provider_stats;
+        provider_stats.record_fraud_finding(fraud_finding.provider)?;
+
+        emit!(FraudRuled {
+            claim: fraud_finding.claim,
+            provider: fraud_finding.provider,
+            patient: fraud_finding.patient,
+            arbiter,
+            slashed: fraud_finding.slashed,
+            fraud_findings: provider_stats.fraud_findings,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a claim the provider has not accepted yet. The claim closes
+    /// and its rent goes back to whoever paid it. Pass the claim's dedup
+    /// marker so it closes too and the same service can be billed again.
+    pub fn cancel_claim(ctx: Context<CancelClaim>) -> Result<()> {
+        expect_status(
+            ctx.accounts.claim_account.status,
+            &[ClaimStatus::Pending],
+            ErrorCode::ClaimNotPending,
+        )
+    }
+
+    /// Closes a claim that was paid in full, rejected, declined or expired,
+    /// returning its rent to whoever paid it. The dedup marker stays, so the
+    /// service cannot be billed again, a rejected claim can no longer be
+    /// reopened or disputed and a declined one can no longer be reassigned.
+    /// Pass the claim's dispute, if any, so it closes too.
+    pub fn close_claim(ctx: Context<CloseClaim>) -> Result<()> {
+        ctx.accounts.claim_account.expect_closable()
+    }
+
+    /// Totals the patient's claims, passed as remaining accounts, whose
+    /// service started in calendar year `year`, and returns the summary as
+    /// return data. Nothing is written, so a wallet can simulate the call to
+    /// render a benefits summary. Amounts are in `mint`, or lamports for
+    /// `Pubkey::default()`, and claims in other currencies or years are
+    /// skipped. Plans' deductibles are not recorded on chain, so pass the
+    /// plan's `deductible` to see how much of it the year's out-of-pocket
+    /// charges have met.
+    pub fn get_patient_year_summary(
+        ctx: Context<GetPatientYearSummary>,
+        year: u32,
+        mint: Pubkey,
+        deductible: u64,
+    ) -> Result<PatientYearSummary> {
+        let mut summary =
+            PatientYearSummary::new(ctx.accounts.patient.key(), year, mint, deductible);
+        for (i, info) in ctx.remaining_accounts.iter().enumerate() {
+            if ctx.remaining_accounts[..i]
+                .iter()
+                .any(|other| other.key == info.key)
+            {
+                msg!("Claim {} is passed more than once", info.key());
+                return err!(ErrorCode::DuplicateAccount);
+            }
+            if *info.owner != crate::ID {
+                msg!("Account {} is not a claim", info.key());
+                return err!(ErrorCode::UnknownAccountLayout);
+            }
+            let (claim, _) = migration::decode_claim(&info.try_borrow_data()?)?;
+            summary.add(&claim)?;
+        }
+        Ok(summary)
+    }
+
+    /// Returns proof of the patient's enrollment in the tenant's plan as
+    /// return data, with whether `provider` is in the tenant's network.
+    /// Nothing is written, so a clinic's front desk can verify coverage with
+    /// one simulation. The runtime tags return data with the program id, and
+    /// the proof's `digest` commits to its fields for the clinic's records.
+    /// Fails if the patient is not a patient member of the tenant.
+    pub fn get_coverage_proof(ctx: Context<GetCoverageProof>) -> Result<CoverageProof> {
+        let membership = &ctx.accounts.patient_membership;
+        expect_member(membership, TenantRole::Patient)?;
+        let in_network = ctx
+            .accounts
+            .provider_membership
+            .as_deref()
+            .is_some_and(|provider| provider.role == TenantRole::Provider);
+        Ok(CoverageProof::new(
+            ctx.accounts.tenant.tenant_id.clone(),
+            membership,
+            ctx.accounts.provider.key(),
+            in_network,
+            clock::now(ctx.remaining_accounts)?,
+        ))
+    }
+
+    /// Approves, denies or down-codes line item `index` of an open claim.
+    pub fn adjudicate_line(
+        ctx: Context<ReviewClaim>,
+        index: u8,
+        decision: LineDecision,
+        reason_code: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .claim_account
+            .adjudicate_line(index, decision, reason_code)
+    }
+
+    /// Moves a pending or declined claim nobody has adjudicated yet to
+    /// `new_provider`, for when the patient picked the wrong provider. The
+    /// claim is pending again unless the new provider co-signs, which accepts
+    /// it. A tenant claim's new provider must be a provider member of the
+    /// tenant. The claim's duplicate-billing marker is re-keyed to the new
+    /// provider.
+    pub fn reassign_claim(ctx: Context<ReassignClaim>, fingerprint: [u8; 32]) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        let new_provider = ctx.accounts.new_provider.key();
+        if new_provider == claim_account.provider {
+            msg!("The claim is already assigned to {}", new_provider);
+            return err!(ErrorCode::InvalidProvider);
+        }
+        claim_account.expect_unadjudicated()?;
+        if claim_account.tenant != Pubkey::default() {
+            let Some(membership) = ctx.accounts.new_provider_membership.as_ref() else {
+                msg!("{} is not a member of the claim's tenant", new_provider);
+                return err!(ErrorCode::NotTenantMember);
+            };
+            expect_member(membership, TenantRole::Provider)?;
+        }
+
+        let service_date = ctx.accounts.claim_dedup.service_date;
+        let expected = ClaimDedup::fingerprint_codes(
+            &claim_account.patient,
+            &new_provider,
+            claim_account
+                .line_items
+                .iter()
+                .map(|item| item.code.as_str()),
+            service_date,
+        );
+        if fingerprint != expected {
+            msg!("The fingerprint does not match the claim under its new provider");
+            return err!(ErrorCode::ClaimFingerprintMismatch);
+        }
+        let new_claim_dedup = &mut ctx.accounts.new_claim_dedup;
+        if new_claim_dedup.claim != Pubkey::default() {
+            msg!(
+                "The new provider was already billed for this service by claim {}",
+                new_claim_dedup.claim
+            );
+            return err!(ErrorCode::DuplicateClaim);
+        }
+        new_claim_dedup.claim = claim_account.key();
+        new_claim_dedup.service_date = service_date;
+        new_claim_dedup.created_at = clock::now(ctx.remaining_accounts)?;
+        new_claim_dedup.account_version = ClaimDedup::VERSION;
+
+        claim_account.provider = new_provider;
+        claim_account.status = ClaimStatus::Pending;
+        if ctx.accounts.new_provider.is_signer {
+            claim_account.accept()?;
+        }
+        Ok(())
+    }
+
+    /// Attaches a health-data record hash to a pending or held claim.
+    pub fn add_attachment(
+        ctx: Context<UpdateAttachments>,
+        health_data_hash: [u8; 32],
+    ) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+
+        expect_status(
+            claim_account.status,
+            &[ClaimStatus::Pending, ClaimStatus::OnHold],
+            ErrorCode::ClaimNotPending,
+        )?;
+        validate_hash("attachment", &health_data_hash)?;
+        validate_count(
+            "attachments",
+            claim_account.attachments.len() + 1,
+            MAX_ATTACHMENTS,
+            ErrorCode::TooManyAttachments,
+        )?;
+        if claim_account.attachments.contains(&health_data_hash) {
+            msg!(
+                "Attachment {} is already on the claim",
+                hex(&health_data_hash)
+            );
+            return err!(ErrorCode::DuplicateAttachment);
+        }
+
+        claim_account.attachments.push(health_data_hash);
+        Ok(())
+    }
+
+    /// Detaches a health-data record hash from a pending or held claim.
+    pub fn remove_attachment(
+        ctx: Context<UpdateAttachments>,
+        health_data_hash: [u8; 32],
+    ) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+
+        expect_status(
+            claim_account.status,
+            &[ClaimStatus::Pending, ClaimStatus::OnHold],
+            ErrorCode::ClaimNotPending,
+        )?;
+
+        let Some(position) = claim_account
+            .attachments
+            .iter()
+            .position(|hash| *hash == health_data_hash)
+        else {
+            msg!("Attachment {} is not on the claim", hex(&health_data_hash));
+            return err!(ErrorCode::AttachmentNotFound);
+        };
+        claim_account.attachments.remove(position);
+        Ok(())
+    }
+
+    /// Appends a note to the claim's thread. `page` must be the current page,
+    /// `note_count / NOTES_PER_PAGE`; the first note on a page creates it.
+    pub fn append_claim_note(
+        ctx: Context<AppendClaimNote>,
+        page: u32,
+        note_hash: String,
+    ) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        let author = ctx.accounts.author.key();
+
+        // Only the parties to the claim can add to its thread
+        if author != claim_account.patient && author != claim_account.provider {
+            msg!(
+                "{} is neither the patient nor the provider on the claim",
+                author
+            );
+            return err!(ErrorCode::Unauthorized);
+        }
+        validate_not_empty("note_hash", &note_hash, ErrorCode::HashEmpty)?;
+        validate_len(
+            "note_hash",
+            &note_hash,
+            MAX_HASH_LEN,
+            ErrorCode::HashTooLong,
+        )?;
+        let current_page = claim_account.note_count / NOTES_PER_PAGE as u32;
+        if page != current_page {
+            msg!(
+                "Note page {} given; the current page is {}",
+                page,
+                current_page
+            );
+            return err!(ErrorCode::InvalidNotePage);
+        }
+
+        let notes_page = &mut ctx.accounts.notes_page;
+        if notes_page.notes.is_empty() {
+            notes_page.claim = claim_account.key();
+            notes_page.page = page;
+            notes_page.account_version = ClaimNotesPage::VERSION;
+        }
+        notes_page.notes.push(ClaimNote {
+            author,
+            note_hash,
+            timestamp: clock::now(ctx.remaining_accounts)?,
+        });
+        claim_account.note_count += 1;
+        Ok(())
+    }
+
+    /// Replaces the claim's external reference and tags, as the patient or
+    /// the provider, so it can be matched against the billing system it came
+    /// from. They are bookkeeping only and can change in any status.
+    pub fn set_claim_references(
+        ctx: Context<SetClaimReferences>,
+        external_ref: String,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        let signer = ctx.accounts.signer.key();
+        if signer != claim_account.patient && signer != claim_account.provider {
+            msg!(
+                "{} is neither the patient nor the provider on the claim",
+                signer
+            );
+            return err!(ErrorCode::Unauthorized);
+        }
+        claim_account.set_references(external_ref, tags)
+    }
+
+    /// Creates or replaces the signer's approval policy `policy_id`. Claims
+    /// already attached keep the sign-offs they were attached with.
+    pub fn set_approval_policy(
+        ctx: Context<SetApprovalPolicy>,
+        policy_id: String,
+        threshold: u64,
+        approvers: Vec<Pubkey>,
+    ) -> Result<()> {
+        validate_not_empty("policy_id", &policy_id, ErrorCode::PolicyIdEmpty)?;
+        validate_len(
+            "policy_id",
+            &policy_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::PolicyIdTooLong,
+        )?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        let approval_policy = &mut ctx.accounts.approval_policy;
+        approval_policy.authority = ctx.accounts.authority.key();
+        approval_policy.policy_id = policy_id;
+        approval_policy.account_version = ApprovalPolicy::VERSION;
+        approval_policy.set(threshold, approvers, now)
+    }
+
+    /// Puts a claim not yet paid under one of its provider's approval
+    /// policies. Payment is then blocked until every approver has approved
+    /// with `approve_claim_payment` if the claim is billed over the policy's
+    /// threshold.
+    pub fn attach_approval_policy(ctx: Context<AttachApprovalPolicy>) -> Result<()> {
+        let address = ctx.accounts.approval_policy.key();
+        ctx.accounts
+            .claim_account
+            .attach_approval_policy(address, &ctx.accounts.approval_policy)
+    }
+
+    /// Records the signer's approval of a claim under the claim's policy.
+    pub fn approve_claim_payment(ctx: Context<ApproveClaimPayment>) -> Result<()> {
+        let bit = ctx
+            .accounts
+            .approval_policy
+            .approver_bit(&ctx.accounts.approver.key())?;
+        ctx.accounts.claim_account.approve(bit)
+    }
+
+    /// Approves or denies a verified claim as the insurer it names. Payment
+    /// waits for the approval; a denial rejects the claim, which the patient
+    /// can reopen like any other rejection.
+    pub fn adjudicate_claim(ctx: Context<AdjudicateClaim>, approve: bool) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        let now = clock::now(ctx.remaining_accounts)?;
+
+        claim_account.adjudicate(approve, now)?;
+        if approve {
+            emit!(InsurerApproved {
+                claim: claim_account.key(),
+                insurer: claim_account.insurer,
+                approved_amount: claim_account.approved_amount()?,
+                timestamp: now,
+            });
+            Ok(())
+        } else {
+            emit_claim_decision(claim_account, false, now)
+        }
+    }
+
+    /// Pays the outstanding approved amount of a verified or partially paid
+    /// claim from the provider to the patient, with simple interest at
+    /// `LATE_INTEREST_BPS_PER_YEAR` for the time past `PAYMENT_WINDOW_SECS`
+    /// since verification. The config's protocol fee is taken out of what the
+    /// patient receives and paid into the protocol treasury.
+    pub fn process_payment(ctx: Context<ProcessPayment>, period: u32) -> Result<()> {
+        pay_claim(ctx, period, None)
+    }
+
+    /// Pays `principal` of the outstanding approved amount, plus interest on
+    /// that part as in `process_payment`. The claim is `PartiallyPaid` until
+    /// the whole approved amount has been paid. With the claim's
+    /// `payment_schedule` the payment counts as its next installment.
+    pub fn process_partial_payment(
+        ctx: Context<ProcessPayment>,
+        period: u32,
+        principal: u64,
+    ) -> Result<()> {
+        pay_claim(ctx, period, Some(principal))
+    }
+
+    /// Agrees a schedule for paying a verified claim's outstanding amount in
+    /// `installment_count` installments, one due every `interval_secs` from
+    /// now, at `["payment_schedule", claim]`. The patient and provider both
+    /// sign and the patient pays for the schedule, which is set once. Each
+    /// lamport payment made with the schedule must cover at least an equal
+    /// share of what is still outstanding and emits `InstallmentPaid`. Late
+    /// interest accrues as without a schedule.
+    pub fn set_payment_schedule(
+        ctx: Context<SetPaymentSchedule>,
+        installment_count: u8,
+        interval_secs: i64,
+    ) -> Result<()> {
+        expect_status(
+            ctx.accounts.claim_account.status,
+            &PAYABLE_STATUSES,
+            ErrorCode::ClaimNotVerified,
+        )?;
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.payment_schedule.set(
+            ctx.accounts.claim_account.key(),
+            installment_count,
+            interval_secs,
+            now,
+        )
+    }
+
+    /// Pays a claim filed in an SPL token, such as USDC, from the provider's
+    /// token account of the claim's mint to the patient's, with interest as
+    /// in `process_payment`. The protocol fee goes to the mint's treasury.
+    /// Settlement summaries are kept in lamports, so a token payment is not
+    /// recorded in one.
+    pub fn process_token_payment(ctx: Context<ProcessTokenPayment>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let claim_account = &mut ctx.accounts.claim_account;
+        let mint = &ctx.accounts.mint;
+        expect_token_account(
+            &ctx.accounts.provider_token,
+            mint,
+            Some(ctx.accounts.provider.key()),
+        )?;
+        expect_token_account(
+            &ctx.accounts.patient_token,
+            mint,
+            Some(claim_account.patient),
+        )?;
+        let decimals = token::unpack_mint_decimals(&mint.try_borrow_data()?)?;
+        let amount = claim_account.settle_payment(now)?;
+        emit_claim_paid(claim_account, amount, now);
+
+        let protocol_fee = ctx.accounts.config.fee_on(amount)?;
+        if protocol_fee > 0 {
+            let (Some(mint_treasury), Some(vault)) = (
+                ctx.accounts.mint_treasury.as_mut(),
+                ctx.accounts.treasury_vault.as_ref(),
+            ) else {
+                msg!(
+                    "The protocol fee is {} bps; pass the mint's treasury and vault",
+                    ctx.accounts.config.fee_bps
+                );
+                return err!(ErrorCode::MintTreasuryRequired);
+            };
+            if vault.key() != mint_treasury.vault {
+                msg!(
+                    "Treasury vault {} is not {}",
+                    vault.key(),
+                    mint_treasury.vault
+                );
+                return err!(ErrorCode::InvalidTokenAccount);
+            }
+            mint_treasury.record_fee(protocol_fee)?;
+        }
+
+        // The protocol fee comes out of what the patient receives
+        let received = Amount::new(amount, decimals)?
+            .checked_sub(Amount::new(protocol_fee, decimals)?)?
+            .raw;
+        let accounts = &ctx.accounts;
+        transfer_claim_tokens(accounts, &accounts.patient_token, received, decimals)?;
+        if let (Some(vault), true) = (&accounts.treasury_vault, protocol_fee > 0) {
+            transfer_claim_tokens(accounts, vault, protocol_fee, decimals)?;
+        }
+        Ok(())
+    }
+
+    /// Locks lamports for a lamport claim outside any tenant in a vault at
+    /// `["claim_escrow", claim]`, so paying it no longer depends on the
+    /// provider's balance. An undecided claim locks its billed amount; a
+    /// verified one its outstanding approved amount with interest due.
+    pub fn fund_claim_escrow(ctx: Context<FundClaimEscrow>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        lock_claim_escrow(ctx.accounts, Pubkey::default(), now)
+    }
+
+    /// Locks a claim's payment like `fund_claim_escrow`, in split custody
+    /// with `arbiter`, for high-value claims. Besides the claim's status,
+    /// settling needs two signatures: the patient and the provider or
+    /// arbiter to pay the patient, or the provider and the patient or
+    /// arbiter to return a payment nobody is owed.
+    pub fn fund_split_escrow(ctx: Context<FundClaimEscrow>, arbiter: Pubkey) -> Result<()> {
+        if arbiter == Pubkey::default() {
+            msg!("A split escrow needs an arbiter");
+            return err!(ErrorCode::InvalidArbiter);
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        lock_claim_escrow(ctx.accounts, arbiter, now)
+    }
+
+    /// Settles an escrowed claim and closes its escrow. A verified claim is
+    /// paid to the patient from the escrow, less the protocol fee and any
+    /// facilitator share as in `process_payment`, and recorded in the
+    /// period's summary; a rejected, declined, expired or already paid one
+    /// pays nothing. The rest goes back to the provider. Anyone may settle a
+    /// standard escrow, since the claim's status decides where the lamports
+    /// go, and is paid the crank reward if `crank_vault` is passed; a split
+    /// escrow also needs the signatures `fund_split_escrow` names, from the
+    /// signer and `cosigner`. A fee rebate the provider has earned stays in
+    /// the escrow and goes back to them.
+    pub fn settle_claim(ctx: Context<SettleClaim>, period: u32) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let claim_account = &mut ctx.accounts.claim_account;
+        let amount = ctx.accounts.claim_escrow.settle(claim_account)?;
+        let signers: Vec<Pubkey> = [Some(&ctx.accounts.signer), ctx.accounts.cosigner.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|signer| signer.key())
+            .collect();
+        ctx.accounts
+            .claim_escrow
+            .authorize_release(claim_account, amount, &signers)?;
+        if amount > 0 {
+            emit_claim_paid(claim_account, amount, now);
+        }
+
+        let summary = open_settlement(
+            &mut ctx.accounts.settlement,
+            claim_account.provider,
+            claim_account.patient,
+            period,
+            now,
+        )?;
+        if amount > 0 {
+            let mut split = split_payment(
+                &ctx.accounts.config,
+                ctx.accounts.facilitator.as_mut(),
+                ctx.accounts.facilitator_signer.as_ref(),
+                claim_account.key(),
+                amount,
+                now,
+            )?;
+            rebate_fee(
+                ctx.accounts.fee_rebates.as_ref(),
+                ctx.accounts.provider_volume.as_mut(),
+                claim_account,
+                period,
+                amount,
+                &mut split,
+                now,
+            )?;
+            ctx.accounts
+                .protocol_treasury
+                .record_fee(split.treasury_fee)?;
+            if claim_account.status == ClaimStatus::Paid {
+                summary.claims_paid += 1;
+            }
+            summary.amount_paid = Amount::lamports(summary.amount_paid)
+                .checked_add(Amount::lamports(amount))?
+                .raw;
+
+            // The escrow closes to the provider with whatever is left
+            let escrow = ctx.accounts.claim_escrow.to_account_info();
+            **escrow.try_borrow_mut_lamports()? -= amount;
+            **ctx.accounts.patient.try_borrow_mut_lamports()? += split.received;
+            let treasury = ctx.accounts.protocol_treasury.to_account_info();
+            **treasury.try_borrow_mut_lamports()? += split.treasury_fee;
+            if let Some(facilitator) = &ctx.accounts.facilitator {
+                **facilitator.to_account_info().try_borrow_mut_lamports()? +=
+                    split.facilitator_share;
+            }
+        }
+        // A split escrow is released by the parties it names, not a keeper
+        if ctx.accounts.claim_escrow.is_split() {
+            return Ok(());
+        }
+        pay_crank_reward(
+            ctx.accounts.crank_vault.as_mut(),
+            &ctx.accounts.signer,
+            ctx.accounts.claim_account.key(),
+            now,
+        )
+    }
+
+    /// Sets how long lamport payments made through a `payment_hold` wait
+    /// before reaching the patient, at `["clawback_config"]`, and the
+    /// arbiter who can claw one back alone (`Pubkey::default()` for none).
+    /// A `window_secs` of 0 turns holds off. Holds already open keep their
+    /// release time and arbiter. Like the other config instructions, it
+    /// works while the program is paused.
+    pub fn set_clawback_window(
+        ctx: Context<SetClawbackWindow>,
+        window_secs: i64,
+        arbiter: Pubkey,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.clawback_config.set(window_secs, arbiter, now)
+    }
+
+    /// Pays a held payment out to the patient and closes the hold, returning
+    /// its rent to the provider. Anyone can release it once the window has
+    /// passed; the provider can release it early.
+    pub fn release_payment_hold(ctx: Context<ReleasePaymentHold>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let payment_hold = &ctx.accounts.payment_hold;
+        payment_hold.expect_releasable(ctx.accounts.signer.key(), now)?;
+
+        let amount = payment_hold.amount;
+        let due = now >= payment_hold.release_at;
+        let hold = payment_hold.to_account_info();
+        **hold.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.patient.try_borrow_mut_lamports()? += amount;
+        if !due {
+            return Ok(());
+        }
+        pay_crank_reward(
+            ctx.accounts.crank_vault.as_mut(),
+            &ctx.accounts.signer,
+            ctx.accounts.payment_hold.key(),
+            now,
+        )
+    }
+
+    /// Returns a held payment to the provider before its window ends and
+    /// undoes it on the claim, which can then be paid again. Needs both the
+    /// provider's and the patient's signatures, or the hold's arbiter's
+    /// alone. Protocol and tenant fees are not returned. The payment's
+    /// settlement summary records the returned lamports as a refund.
+    pub fn claw_back_payment(ctx: Context<ClawBackPayment>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let payment_hold = &ctx.accounts.payment_hold;
+        let signers: Vec<Pubkey> = [Some(&ctx.accounts.signer), ctx.accounts.cosigner.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|signer| signer.key())
+            .collect();
+        payment_hold.authorize_clawback(&signers, now)?;
+
+        let claim_account = &mut ctx.accounts.claim_account;
+        claim_account.reverse_payment(payment_hold.principal, payment_hold.interest)?;
+        let summary = &mut ctx.accounts.settlement;
+        summary.refunds = Amount::lamports(summary.refunds)
+            .checked_add(Amount::lamports(payment_hold.amount))?
+            .raw;
+        emit!(PaymentClawedBack {
+            claim: claim_account.key(),
+            provider: payment_hold.provider,
+            patient: payment_hold.patient,
+            amount: payment_hold.amount,
+            principal_paid: claim_account.principal_paid,
+            status: claim_account.status,
+            timestamp: now,
+        });
+
+        // The hold closes to the provider, so its lamports go back with the rent
+        Ok(())
+    }
+
+    /// Grants `grantee` the `scope` bits over the patient's records until
+    /// `expires_at` (0 for no expiry), replacing any earlier grant. The granter
+    /// is the patient, or a guardian or delegate allowed to grant access for
+    /// them.
+    ///
+    /// A non-empty `key_envelope` is the record key wrapped to the grantee's
+    /// registered encryption key, stored alongside the grant so the grantee
+    /// can decrypt without a separate key exchange.
+    pub fn grant_access(
+        ctx: Context<GrantAccess>,
+        scope: u8,
+        expires_at: i64,
+        lawful_basis: LawfulBasis,
+        key_envelope: Vec<u8>,
+    ) -> Result<()> {
+        if scope & GRANT_SCOPE_ALL == 0 || scope & !(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE) != 0 {
+            msg!("Grant scope {:#05b} is empty or has unknown bits", scope);
+            return err!(ErrorCode::InvalidGrantScope);
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_agent(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.guardian_link.as_deref(),
+            ctx.accounts.granter.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_GRANT_CONSENT,
+            GUARDIAN_GRANT_ACCESS,
+            now,
+        )?;
+        if expires_at != 0 && expires_at <= now {
+            msg!("Grant expiry {} is not after now ({})", expires_at, now);
+            return err!(ErrorCode::InvalidGrantExpiry);
+        }
+
+        let grant = &mut ctx.accounts.grant;
+        grant.expect_replaceable()?;
+        grant.patient = ctx.accounts.patient.key();
+        grant.account_version = AccessGrant::VERSION;
+        grant.grantee = ctx.accounts.grantee.key();
+        grant.scope = scope;
+        grant.granted_at = now;
+        grant.expires_at = expires_at;
+        grant.revoked = false;
+        grant.lawful_basis = lawful_basis;
+        grant.parent = Pubkey::default();
+        grant.depth = 0;
+
+        match (ctx.accounts.key_envelope.as_mut(), key_envelope.is_empty()) {
+            (None, true) => {}
+            (Some(envelope), false) => {
+                validate_count(
+                    "key_envelope",
+                    key_envelope.len(),
+                    MAX_KEY_ENVELOPE_LEN,
+                    ErrorCode::KeyEnvelopeTooLong,
+                )?;
+                let Some(grantee_key) = ctx.accounts.grantee_key.as_deref() else {
+                    msg!("The grantee has no registered encryption key");
+                    return err!(ErrorCode::EncryptionKeyRequired);
+                };
+                envelope.grant = grant.key();
+                envelope.recipient_key = grantee_key.x25519_key;
+                envelope.envelope = key_envelope;
+                envelope.updated_at = now;
+                envelope.account_version = KeyEnvelope::VERSION;
+            }
+            _ => {
+                msg!("Pass both the key envelope and its account, or neither");
+                return err!(ErrorCode::KeyEnvelopeMismatch);
+            }
+        }
+
+        record_consent(
+            ctx.accounts.consent_ledger.as_mut(),
+            ConsentReceipt {
+                patient: grant.patient,
+                actor: ctx.accounts.granter.key(),
+                grantee: grant.grantee,
+                action: ConsentAction::Grant,
+                scope,
+                lawful_basis,
+                expires_at,
+                timestamp: now,
+                sequence: 0,
+            },
+        )
+    }
+
+    /// Revokes a grant, as the patient or a guardian or delegate allowed to
+    /// grant access for them. The account is kept as a record of the consent.
+    ///
+    /// Grants delegated from it are revoked with it and are passed as
+    /// remaining accounts, each after the grant it was delegated from. A
+    /// delegated grant also needs the grant it came from as `parent_grant`.
+    pub fn revoke_access(ctx: Context<RevokeAccess>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_agent(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.guardian_link.as_deref(),
+            ctx.accounts.granter.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_GRANT_CONSENT,
+            GUARDIAN_GRANT_ACCESS,
+            now,
+        )?;
+        revoke_grant_chain(
+            &mut ctx.accounts.grant,
+            ctx.accounts.parent_grant.as_deref_mut(),
+            ctx.remaining_accounts,
+        )?;
+        let grant = &ctx.accounts.grant;
+
+        record_consent(
+            ctx.accounts.consent_ledger.as_mut(),
+            ConsentReceipt {
+                patient: grant.patient,
+                actor: ctx.accounts.granter.key(),
+                grantee: grant.grantee,
+                action: ConsentAction::Revoke,
+                scope: grant.scope,
+                lawful_basis: grant.lawful_basis,
+                expires_at: grant.expires_at,
+                timestamp: now,
+                sequence: 0,
+            },
+        )
+    }
+
+    /// Sub-grants the signing grantee's access to `delegate`, at the
+    /// delegate's `["grant", patient, delegate]` address, with `scope` until
+    /// `expires_at` (0 for the parent grant's expiry). The parent must carry
+    /// `GRANT_SCOPE_DELEGATE`, and chains stop at `MAX_DELEGATION_DEPTH`.
+    /// Delegating again to the same team member replaces their sub-grant.
+    pub fn delegate_access(ctx: Context<DelegateAccess>, scope: u8, expires_at: i64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let parent_key = ctx.accounts.parent_grant.key();
+        ctx.accounts.parent_grant.delegate(
+            parent_key,
+            &mut ctx.accounts.grant,
+            ctx.accounts.delegate.key(),
+            scope,
+            expires_at,
+            now,
+        )?;
+        let grant = &ctx.accounts.grant;
+
+        record_consent(
+            ctx.accounts.consent_ledger.as_mut(),
+            ConsentReceipt {
+                patient: grant.patient,
+                actor: ctx.accounts.delegator.key(),
+                grantee: grant.grantee,
+                action: ConsentAction::Delegate,
+                scope,
+                lawful_basis: grant.lawful_basis,
+                expires_at: grant.expires_at,
+                timestamp: now,
+                sequence: 0,
+            },
+        )
+    }
+
+    /// Revokes a grant the signer delegated, along with the grants delegated
+    /// from it, passed as remaining accounts as for `revoke_access`.
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        revoke_grant_chain(
+            &mut ctx.accounts.grant,
+            Some(&mut *ctx.accounts.parent_grant),
+            ctx.remaining_accounts,
+        )?;
+        let grant = &ctx.accounts.grant;
+
+        record_consent(
+            ctx.accounts.consent_ledger.as_mut(),
+            ConsentReceipt {
+                patient: grant.patient,
+                actor: ctx.accounts.delegator.key(),
+                grantee: grant.grantee,
+                action: ConsentAction::Revoke,
+                scope: grant.scope,
+                lawful_basis: grant.lawful_basis,
+                expires_at: grant.expires_at,
+                timestamp: now,
+                sequence: 0,
+            },
+        )
+    }
+
+    /// Consents to `provider` using one of the patient's records with the
+    /// `scope` bits until `expires_at` (0 for no expiry), at
+    /// `["consent", patient, provider, record]`. Granting again replaces the
+    /// earlier scope and expiry. The granter is the patient or a delegate
+    /// allowed to grant consent for them.
+    pub fn grant_consent(ctx: Context<GrantConsent>, scope: u8, expires_at: i64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_delegate(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.granter.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_GRANT_CONSENT,
+        )?;
+        let consent = &mut ctx.accounts.consent;
+        consent.grant(scope, expires_at, now)?;
+        consent.patient = ctx.accounts.patient.key();
+        consent.account_version = ConsentAccount::VERSION;
+        consent.provider = ctx.accounts.provider.key();
+        consent.record = ctx.accounts.record.key();
+        consent.data_hash = ctx.accounts.record.data_hash;
+        let target = consent.key();
+        record_audit(
+            ctx.accounts.audit_trail.as_mut(),
+            ctx.accounts.audit_entry.as_mut(),
+            ctx.accounts.patient.key(),
+            ctx.accounts.granter.key(),
+            AuditAction::ConsentGranted,
+            target,
+            now,
+        )
+    }
+
+    /// Withdraws a consent, as the patient or a delegate allowed to grant
+    /// consent for them, closing its account and returning the rent to the
+    /// patient.
+    pub fn revoke_consent(ctx: Context<RevokeConsent>) -> Result<()> {
+        expect_delegate(
+            ctx.accounts.delegate_account.as_deref(),
+            ctx.accounts.revoker.key(),
+            ctx.accounts.patient.key(),
+            DELEGATE_GRANT_CONSENT,
+        )
+    }
+
+    /// Consents to the signing patient's records in `categories` being used
+    /// in research until `expires_at` (0 for no expiry), at
+    /// `["research_consent", patient]`. Plans can discount the premiums of
+    /// patients who consent. Granting again replaces the categories and
+    /// expiry.
+    pub fn grant_research_consent(
+        ctx: Context<GrantResearchConsent>,
+        categories: u8,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let research_consent = &mut ctx.accounts.research_consent;
+        research_consent.grant(categories, expires_at, now)?;
+        research_consent.patient = ctx.accounts.patient.key();
+        research_consent.account_version = ResearchConsent::VERSION;
+        Ok(())
+    }
+
+    /// Withdraws the patient's research consent, closing its account and
+    /// returning the rent to them. Premiums paid from then on are not
+    /// discounted.
+    pub fn revoke_research_consent(_ctx: Context<RevokeResearchConsent>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Asks the patient to wrap the record's key to the signing provider's
+    /// registered encryption key, under the provider's consent to read it, at
+    /// `["reencryption", consent]`. Asking again, for example after rotating
+    /// keys, replaces the earlier request and any key it was fulfilled with.
+    pub fn request_reencryption(ctx: Context<RequestReencryption>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let Some(provider_key) = ctx.accounts.provider_key.as_deref() else {
+            msg!("The provider has no registered encryption key");
+            return err!(ErrorCode::EncryptionKeyRequired);
+        };
+        let request = &mut ctx.accounts.reencryption_request;
+        request.request(
+            ctx.accounts.consent.key(),
+            &ctx.accounts.consent,
+            &ctx.accounts.record,
+            provider_key,
+            now,
+        )?;
+        emit!(ReencryptionRequested {
+            request: request.key(),
+            patient: request.patient,
+            provider: request.provider,
+            record: request.record,
+            recipient_key: request.recipient_key,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Stores the record key wrapped to the provider's encryption key,
+    /// fulfilling their re-encryption request, as the patient. The patient's
+    /// client unwraps the key and wraps it again off-chain, so only the
+    /// wrapped key is posted. Fails if the consent has lapsed or the provider
+    /// has rotated keys since asking.
+    pub fn fulfill_reencryption(
+        ctx: Context<FulfillReencryption>,
+        wrapped_key: Vec<u8>,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let request = &mut ctx.accounts.reencryption_request;
+        request.fulfill(
+            wrapped_key,
+            &ctx.accounts.consent,
+            &ctx.accounts.provider_key,
+            now,
+        )?;
+        emit!(ReencryptionFulfilled {
+            request: request.key(),
+            patient: request.patient,
+            provider: request.provider,
+            record: request.record,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Closes a re-encryption request, as its provider, returning the rent
+    /// they paid.
+    pub fn close_reencryption_request(_ctx: Context<CloseReencryptionRequest>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Replaces the signing patient's default access rules. Rules are
+    /// evaluated in order and the first match decides.
+    pub fn set_access_policy(ctx: Context<SetAccessPolicy>, rules: Vec<PolicyRule>) -> Result<()> {
+        validate_count(
+            "rules",
+            rules.len(),
+            MAX_POLICY_RULES,
+            ErrorCode::TooManyPolicyRules,
+        )?;
+        for (index, rule) in rules.iter().enumerate() {
+            if rule.categories == 0 || rule.scope == 0 || rule.scope & !GRANT_SCOPE_ALL != 0 {
+                msg!(
+                    "Rule {} has an empty category mask or an invalid scope",
+                    index
+                );
+                return err!(ErrorCode::InvalidPolicyRule);
+            }
+        }
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        // The receipt's scope is everything the new rules allow without a grant
+        let allowed_scope = rules
+            .iter()
+            .filter(|rule| rule.effect == PolicyEffect::Allow)
+            .fold(0, |scope, rule| scope | rule.scope);
+
+        let access_policy = &mut ctx.accounts.access_policy;
+        access_policy.patient = ctx.accounts.patient.key();
+        access_policy.account_version = AccessPolicy::VERSION;
+        access_policy.rules = rules;
+        access_policy.updated_at = now;
+
+        record_consent(
+            ctx.accounts.consent_ledger.as_mut(),
+            ConsentReceipt {
+                patient: access_policy.patient,
+                actor: access_policy.patient,
+                grantee: Pubkey::default(),
+                action: ConsentAction::PolicyUpdate,
+                scope: allowed_scope,
+                lawful_basis: LawfulBasis::Consent,
+                expires_at: 0,
+                timestamp: now,
+                sequence: 0,
+            },
+        )
+    }
+
+    /// Succeeds if the signer may access the patient's records of `category`
+    /// with `scope`, under the patient's policy or an explicit grant. Meant to
+    /// be simulated by off-chain gatekeepers before releasing data.
+    pub fn check_access(
+        ctx: Context<CheckAccess>,
+        category: DataCategory,
+        scope: u8,
+    ) -> Result<()> {
+        if ctx.accounts.requester.key() == ctx.accounts.patient.key() {
+            return Ok(());
+        }
+        authorize(
+            ctx.accounts.access_policy.as_deref(),
+            ctx.accounts.grant.as_deref(),
+            ctx.accounts.requester_provider.is_some(),
+            category.bit(),
+            scope,
+            clock::now(ctx.remaining_accounts)?,
+        )
+    }
+
+    /// Succeeds if the signer may read `record` under the claim's grant: the
+    /// record is the patient's and attached to the claim, and the signer is
+    /// the claim's provider while the claim is not yet finished. Meant to be
+    /// simulated by off-chain gatekeepers, like `check_access`.
+    pub fn check_claim_access(ctx: Context<CheckClaimAccess>) -> Result<()> {
+        ctx.accounts.claim_grant.authorize(
+            &ctx.accounts.claim_account,
+            ctx.accounts.requester.key(),
+            &ctx.accounts.record,
+        )
+    }
+
+    /// Asks `consultant`, a registered provider other than the claim's, for a
+    /// second opinion on the signing patient's claim, at
+    /// `["second_opinion", claim, consultant]`. The consultant may read the
+    /// patient's records whose hashes are in `records` until `expires_at` or
+    /// until they give the opinion. The `fee` is escrowed in the account and
+    /// paid to the consultant by `submit_second_opinion`.
+    pub fn request_second_opinion(
+        ctx: Context<RequestSecondOpinion>,
+        records: Vec<[u8; 32]>,
+        fee: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let second_opinion = &mut ctx.accounts.second_opinion;
+        second_opinion.claim = ctx.accounts.claim_account.key();
+        second_opinion.request(
+            &ctx.accounts.claim_account,
+            ctx.accounts.consultant.key(),
+            records,
+            fee,
+            expires_at,
+            now,
+        )?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.patient.to_account_info(),
+                to: second_opinion.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, fee)
+    }
+
+    /// Succeeds if the signer may read `record` for a second opinion: they
+    /// are the consultant, the record is one the patient shared, and the
+    /// opinion is neither given nor expired. Meant to be simulated by
+    /// off-chain gatekeepers, like `check_claim_access`.
+    pub fn check_second_opinion_access(ctx: Context<CheckSecondOpinionAccess>) -> Result<()> {
+        ctx.accounts.second_opinion.authorize(
+            ctx.accounts.requester.key(),
+            &ctx.accounts.record,
+            clock::now(ctx.remaining_accounts)?,
+        )
+    }
+
+    /// Gives the consultant's opinion as a record in the patient's name,
+    /// authored by the consultant, and pays them the escrowed fee. The
+    /// opinion's hash is kept on the second opinion, which links it to the
+    /// claim, and the consultant's access ends.
+    pub fn submit_second_opinion(
+        ctx: Context<SubmitSecondOpinion>,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+    ) -> Result<()> {
+        validate_record(&data_hash, &pointer)?;
+
+        let now = clock::now(ctx.remaining_accounts)?;
+        let second_opinion = &mut ctx.accounts.second_opinion;
+        second_opinion.submit(data_hash, now)?;
+        let owner = second_opinion.patient;
+        let author = second_opinion.consultant;
+        let category = DataCategory::VisitNote;
+        accrue_subscriptions(ctx.remaining_accounts, owner, category)?;
+
+        ctx.accounts.health_data_account.record(
+            owner,
+            author,
+            data_hash,
+            pointer,
+            ctx.accounts.owner_key.as_deref(),
+            now,
+        );
+        emit!(HealthDataSubmitted {
+            record: ctx.accounts.health_data_account.key(),
+            owner,
+            author,
+            data_hash,
+            category,
+            timestamp: now,
+        });
+        emit!(SecondOpinionGiven {
+            claim: second_opinion.claim,
+            consultant: author,
+            opinion: data_hash,
+            fee: second_opinion.fee,
+            timestamp: now,
+        });
+
+        let fee = second_opinion.fee;
+        **second_opinion.to_account_info().try_borrow_mut_lamports()? -= fee;
+        **ctx.accounts.consultant.try_borrow_mut_lamports()? += fee;
+        Ok(())
+    }
+
+    /// Closes a second opinion to the patient once the opinion is given or
+    /// the request has expired. An expired request returns the unpaid fee
+    /// with the rent.
+    pub fn close_second_opinion(ctx: Context<CloseSecondOpinion>) -> Result<()> {
+        ctx.accounts
+            .second_opinion
+            .expect_closable(clock::now(ctx.remaining_accounts)?)
+    }
+
+    /// Sets the signing patient's care team `team_id`, at
+    /// `["care_team", patient, team_id]` with the id as 4 little-endian
+    /// bytes: the providers in `members` share read access to the records in
+    /// `records` and to the records attached to any of the patient's claims,
+    /// without a grant each. Setting it again replaces both lists.
+    pub fn set_care_team(
+        ctx: Context<SetCareTeam>,
+        team_id: u32,
+        members: Vec<Pubkey>,
+        records: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts
+            .care_team
+            .set(ctx.accounts.patient.key(), team_id, members, records, now)
+    }
+
+    /// Disbands a care team, closing its account and returning the rent to
+    /// the patient.
+    pub fn close_care_team(_ctx: Context<CloseCareTeam>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Succeeds if the signer, a member of the care team, may read `record`:
+    /// it is the patient's and designated for the team, or attached to
+    /// `claim_account`, one of the patient's claims. Meant to be simulated by
+    /// off-chain gatekeepers, like `check_claim_access`.
+    pub fn check_care_team_access(ctx: Context<CheckCareTeamAccess>) -> Result<()> {
+        ctx.accounts.care_team.authorize(
+            ctx.accounts.requester.key(),
+            &ctx.accounts.record,
+            ctx.accounts.claim_account.as_deref(),
+        )
+    }
+
+    /// Asks for break-glass access to the record with `data_hash` for the
+    /// signing provider, at `["emergency_access", data_hash, provider]`.
+    /// `justification_hash` commits to the reason given off-chain. Access
+    /// starts `EMERGENCY_ACCESS_DELAY_SECS` later, unless the patient vetoes
+    /// it first, and ends `EMERGENCY_ACCESS_DURATION_SECS` after that.
+    pub fn request_emergency_access(
+        ctx: Context<RequestEmergencyAccess>,
+        data_hash: [u8; 32],
+        justification_hash: [u8; 32],
+    ) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let emergency_access = &mut ctx.accounts.emergency_access;
+        emergency_access.request(
+            &ctx.accounts.health_data_account,
+            ctx.accounts.provider.key(),
+            justification_hash,
+            now,
+        )?;
+        emit!(EmergencyAccessRequested {
+            patient: emergency_access.patient,
+            provider: emergency_access.provider,
+            data_hash,
+            justification_hash,
+            available_at: emergency_access.available_at,
+            expires_at: emergency_access.expires_at,
+        });
+        Ok(())
+    }
+
+    /// Vetoes an emergency access request to the signing patient's record.
+    /// Only possible before the delay has passed.
+    pub fn veto_emergency_access(ctx: Context<VetoEmergencyAccess>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let emergency_access = &mut ctx.accounts.emergency_access;
+        emergency_access.veto(now)?;
+        emit!(EmergencyAccessVetoed {
+            patient: emergency_access.patient,
+            provider: emergency_access.provider,
+            data_hash: emergency_access.data_hash,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Succeeds if the signer is the provider and their emergency access to
+    /// `record` is open: past the delay, not vetoed and not expired. Meant to
+    /// be simulated by off-chain gatekeepers, like `check_claim_access`.
+    pub fn check_emergency_access(ctx: Context<CheckEmergencyAccess>) -> Result<()> {
+        ctx.accounts.emergency_access.authorize(
+            ctx.accounts.requester.key(),
+            clock::now(ctx.remaining_accounts)?,
+        )
+    }
+
+    /// Closes a vetoed or expired emergency access request, returning the
+    /// rent to the provider.
+    pub fn close_emergency_access(ctx: Context<CloseEmergencyAccess>) -> Result<()> {
+        ctx.accounts
+            .emergency_access
+            .expect_closable(clock::now(ctx.remaining_accounts)?)
+    }
+
+    /// Appends an entry to `subject`'s audit trail recording that the signer
+    /// took `action` on `target`, at `["audit_entry", subject, sequence]` with
+    /// the sequence as 8 little-endian bytes. Gatekeepers send it once a
+    /// simulated check such as `check_access` has passed and the data is
+    /// released; `grant_consent` and the lamport payments append their own
+    /// entries when given the trail. Entries cannot be changed or closed.
+    pub fn log_access(ctx: Context<LogAccess>, action: AuditAction, target: Pubkey) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        ctx.accounts.audit_trail.append(
+            ctx.accounts.subject.key(),
+            &mut ctx.accounts.audit_entry,
+            ctx.accounts.actor.key(),
+            action,
+            target,
+            now,
+        )
+    }
+
+    /// Subscribes the signer to the patient's new records in `category`,
+    /// paying `price_per_record` lamports for each from `deposit`. Needs an
+    /// active read grant from the patient.
+    pub fn subscribe_to_patient_data(
+        ctx: Context<SubscribeToPatientData>,
+        category: DataCategory,
+        price_per_record: u64,
+        deposit: u64,
+    ) -> Result<()> {
+        if price_per_record == 0 {
+            msg!("Subscriptions must pay for each record");
+            return err!(ErrorCode::InvalidSubscriptionPrice);
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        expect_grant(Some(&ctx.accounts.grant), GRANT_SCOPE_READ, now)?;
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.patient = ctx.accounts.patient.key();
+        subscription.account_version = Subscription::VERSION;
+        subscription.subscriber = ctx.accounts.subscriber.key();
+        subscription.category = category;
+        subscription.price_per_record = price_per_record;
+        subscription.deposited = deposit;
+        subscription.accrued = 0;
+        subscription.withdrawn = 0;
+        subscription.delivered = 0;
+        subscription.started_at = now;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.subscriber.to_account_info(),
+                to: subscription.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, deposit)
+    }
+
+    /// Adds `lamports` to a subscription's deposit.
+    pub fn fund_subscription(ctx: Context<FundSubscription>, lamports: u64) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.deposited = subscription
+            .deposited
+            .checked_add(lamports)
+            .ok_or(ErrorCode::AmountOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.subscriber.to_account_info(),
+                to: subscription.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, lamports)
+    }
+
+    /// Pays the patient everything accrued on a subscription so far.
+    pub fn withdraw_subscription_earnings(
+        ctx: Context<WithdrawSubscriptionEarnings>,
+    ) -> Result<()> {
+        let amount = ctx.accounts.subscription.withdrawable();
+        if amount == 0 {
+            msg!("Nothing has accrued since the last withdrawal");
+            return err!(ErrorCode::NothingToWithdraw);
+        }
+        ctx.accounts.subscription.withdrawn += amount;
+
+        let subscription = ctx.accounts.subscription.to_account_info();
+        **subscription.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.patient.try_borrow_mut_lamports()? += amount;
+        Ok(())
+    }
+
+    /// Ends a subscription, as either party. The patient is paid what has
+    /// accrued and the rest of the deposit returns to the subscriber.
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let subscription = &ctx.accounts.subscription;
+        if signer != subscription.patient && signer != subscription.subscriber {
+            msg!("{} is not a party to the subscription", signer);
+            return err!(ErrorCode::Unauthorized);
+        }
+
+        let amount = subscription.withdrawable();
+        let subscription = subscription.to_account_info();
+        **subscription.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.patient.try_borrow_mut_lamports()? += amount;
+        Ok(())
+    }
+
+    /// Offers recurring access to the owner's records in `categories` with
+    /// `scope`, for `price_per_period` lamports every `period_secs`, at
+    /// `["subscription_plan", owner, plan_id]`. The signer is the data owner.
+    pub fn create_subscription_plan(
+        ctx: Context<CreateSubscriptionPlan>,
+        plan_id: String,
+        price_per_period: u64,
+        period_secs: i64,
+        categories: u8,
+        scope: u8,
+    ) -> Result<()> {
+        validate_not_empty("plan_id", &plan_id, ErrorCode::PlanIdEmpty)?;
+        validate_len(
+            "plan_id",
+            &plan_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::PlanIdTooLong,
+        )?;
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        plan.owner = ctx.accounts.owner.key();
+        plan.account_version = SubscriptionPlan::VERSION;
+        plan.plan_id = plan_id;
+        plan.periods_sold = 0;
+        plan.created_at = clock::now(ctx.remaining_accounts)?;
+        plan.set_terms(price_per_period, period_secs, categories, scope)
+    }
+
+    /// Pays the plan's owner for one period and subscribes the signer, at
+    /// `["plan_subscription", plan, subscriber]`. x402 facilitators serve the
+    /// plan's records to the subscriber until `current_period_end`.
+    pub fn subscribe(ctx: Context<Subscribe>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.plan = plan.key();
+        subscription.account_version = PlanSubscription::VERSION;
+        subscription.owner = plan.owner;
+        subscription.subscriber = ctx.accounts.subscriber.key();
+        subscription.current_period_end = 0;
+        subscription.periods_paid = 0;
+        subscription.started_at = now;
+        subscription.renew(plan, now)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.subscriber.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, plan.price_per_period)
+    }
+
+    /// Pays for another period. A subscription that has not lapsed runs on
+    /// from its current period end; a lapsed one starts again now.
+    pub fn renew_subscription(ctx: Context<RenewSubscription>) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let plan = &mut ctx.accounts.subscription_plan;
+        ctx.accounts.subscription.renew(plan, now)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.subscriber.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, plan.price_per_period)
+    }
+
+    /// Offers read access to the record `resource_hash` for `price` lamports
+    /// per `access_secs` of access. The signer is the data owner.
+    /// `price_tiers` charge more for recent records: a purchase pays the first
+    /// tier the record is younger than, or `price` once it is older than all.
+    pub fn create_access_offer(
+        ctx: Context<CreateAccessOffer>,
+        offer_id: String,
+        resource_hash: [u8; 32],
+        price: u64,
+        access_secs: i64,
+        price_tiers: Vec<PriceTier>,
+    ) -> Result<()> {
+        validate_not_empty("offer_id", &offer_id, ErrorCode::OfferIdEmpty)?;
+        validate_len(
+            "offer_id",
+            &offer_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::OfferIdTooLong,
+        )?;
+        validate_hash("resource_hash", &resource_hash)?;
+        if price == 0 || access_secs <= 0 {
+            msg!(
+                "Offer of {} seconds for {} lamports; both must be positive",
+                access_secs,
+                price
+            );
+            return err!(ErrorCode::InvalidOfferTerms);
+        }
+
+        let offer = &mut ctx.accounts.access_offer;
+        offer.owner = ctx.accounts.owner.key();
+        offer.account_version = DataAccessOffer::VERSION;
+        offer.offer_id = offer_id;
+        offer.resource_hash = resource_hash;
+        offer.price = price;
+        offer.access_secs = access_secs;
+        offer.sales = 0;
+        offer.created_at = clock::now(ctx.remaining_accounts)?;
+        offer.set_price_tiers(price_tiers)
+    }
+
+    /// Withdraws an offer. Access already bought runs until it expires.
+    pub fn close_access_offer(_ctx: Context<CloseAccessOffer>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Pays the offer's price to its owner and grants the buyer read access
+    /// for `access_secs`, at `["access_purchase", offer, buyer]`. Buying again
+    /// extends access that has not lapsed. `expected_price` is the price the
+    /// buyer was quoted, so a re-created offer cannot charge more. Offers with
+    /// price tiers need the record, whose age picks the tier.
+    pub fn purchase_access(ctx: Context<PurchaseAccess>, expected_price: u64) -> Result<()> {
+        let now = clock::now(ctx.remaining_accounts)?;
+        let recorded_at = ctx.accounts.record.as_ref().map(|record| record.timestamp);
+        let offer = &mut ctx.accounts.access_offer;
+        let price = offer.price_at(recorded_at, now)?;
+        if expected_price != price {
+            msg!(
+                "Quoted {} lamports; the offer costs {}",
+                expected_price,
+                price
+            );
+            return err!(ErrorCode::OfferPriceMismatch);
+        }
+        offer.sales = offer
+            .sales
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+
+        let grant = &mut ctx.accounts.grant;
+        grant.extend_purchase(offer.access_secs, now)?;
+        grant.patient = offer.owner;
+        grant.account_version = AccessGrant::VERSION;
+        grant.grantee = ctx.accounts.buyer.key();
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, price)
+    }
+
+    /// Closes a settlement summary once its month is over, as the payer or
+    /// payee. The closed summary is the reconciled record for the period.
+    pub fn close_settlement_period(ctx: Context<CloseSettlementPeriod>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let summary = &mut ctx.accounts.settlement;
+        if signer != summary.payer && signer != summary.payee {
+            msg!("{} is not a party to the settlement", signer);
+            return err!(ErrorCode::Unauthorized);
+        }
+        if summary.closed {
+            msg!("Period {} is already closed", summary.period);
+            return err!(ErrorCode::SettlementPeriodClosed);
+        }
+        let now = clock::now(ctx.remaining_accounts)?;
+        let period_end = settlement::period_end(summary.period);
+        if now < period_end {
+            msg!(
+                "Period {} runs until {}; now is {}",
+                summary.period,
+                period_end,
+                now
+            );
+            return err!(ErrorCode::SettlementPeriodOpen);
+        }
+
+        summary.closed = true;
+        summary.closed_at = now;
+        Ok(())
+    }
+
+    /// Rewrites a claim stored in an earlier layout in the current one,
+    /// resizing the account. Either party to the claim may run it and pays
+    /// any extra rent.
+    pub fn migrate_claim(ctx: Context<MigrateClaim>) -> Result<()> {
+        let claim_info = ctx.accounts.claim_account.to_account_info();
+        let payer = &ctx.accounts.payer;
+
+        let (claim, converted) = migration::decode_claim(&claim_info.try_borrow_data()?)?;
+        require!(converted, ErrorCode::AlreadyMigrated);
+        if payer.key() != claim.patient && payer.key() != claim.provider {
+            msg!(
+                "{} is neither the patient nor the provider on the claim",
+                payer.key()
+            );
+            return err!(ErrorCode::Unauthorized);
+        }
+
+        let mut encoded = Vec::new();
+        claim.try_serialize(&mut encoded)?;
+        rewrite_account(
+            &claim_info,
+            &encoded,
+            migration::claim_space(&claim),
+            payer,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    /// Rewrites any program account at its type's current version (see
+    /// `migration::ACCOUNT_VERSIONS`), growing it if the new layout needs
+    /// more room. The contents are otherwise unchanged, so anyone may pay.
+    pub fn upgrade_account(ctx: Context<UpgradeAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        let Some((encoded, space)) = migration::upgrade(&info.try_borrow_data()?)? else {
+            msg!("{} is already at the current version", info.key());
+            return err!(ErrorCode::AlreadyMigrated);
+        };
+        rewrite_account(
+            &info,
+            &encoded,
+            space.max(info.data_len()),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    /// Creates the demo config with the signer as admin.
+    #[cfg(feature = "demo")]
+    pub fn demo_initialize(ctx: Context<DemoInitialize>) -> Result<()> {
+        let demo_config = &mut ctx.accounts.demo_config;
+        demo_config.admin = ctx.accounts.admin.key();
+        demo_config.account_version = DemoConfig::VERSION;
+        demo_config.provider_count = 0;
+        Ok(())
+    }
+
+    /// Creates a provider account for a demo provider without their signature.
+    #[cfg(feature = "demo")]
+    pub fn demo_register_provider(
+        ctx: Context<DemoRegisterProvider>,
+        did: String,
+        name: String,
+    ) -> Result<()> {
+        validate_did(&did)?;
+        validate_not_empty("name", &name, ErrorCode::NameEmpty)?;
+        validate_len("name", &name, MAX_NAME_LEN, ErrorCode::NameTooLong)?;
+
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.authority = ctx.accounts.provider.key();
+        provider_account.did = did;
+        provider_account.name = name;
+        provider_account.account_version = ProviderAccount::VERSION;
+        ctx.accounts.demo_config.provider_count += 1;
+        Ok(())
+    }
+
+    /// Tops up a demo provider's vault from the admin.
+    #[cfg(feature = "demo")]
+    pub fn demo_fund_vault(ctx: Context<DemoFundVault>, lamports: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.provider = ctx.accounts.provider.key();
+        vault.account_version = DemoVault::VERSION;
+        vault.total_funded = vault
+            .total_funded
+            .checked_add(lamports)
+            .ok_or(ErrorCode::AmountOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.admin.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, lamports)
+    }
+
+    /// Moves lamports from a demo vault to its provider, leaving the rent.
+    #[cfg(feature = "demo")]
+    pub fn demo_withdraw_vault(ctx: Context<DemoWithdrawVault>, lamports: u64) -> Result<()> {
+        let vault = ctx.accounts.vault.to_account_info();
+        let provider = ctx.accounts.provider.to_account_info();
+
+        let available = vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+        if lamports > available {
+            msg!(
+                "Requested {} lamports; the vault has {} available",
+                lamports,
+                available
+            );
+            return err!(ErrorCode::InsufficientVaultBalance);
+        }
+
+        **vault.try_borrow_mut_lamports()? -= lamports;
+        **provider.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+
+    /// Returns a claim to `Pending` with every line awaiting adjudication.
+    /// Down-coded lines keep their adjusted code and price.
+    #[cfg(feature = "demo")]
+    pub fn demo_reset_claim(ctx: Context<DemoResetClaim>) -> Result<()> {
+        let claim_account = &mut ctx.accounts.claim_account;
+        for item in claim_account.line_items.iter_mut() {
+            item.status = LineItemStatus::Pending;
+            item.reason_code = 0;
+        }
+        claim_account.status = ClaimStatus::Pending;
+        claim_account.rejected_at = 0;
+        claim_account.reopened = false;
+        claim_account.verified_at = 0;
+        claim_account.principal_paid = 0;
+        claim_account.interest_paid = 0;
+        claim_account.insurer_approved_at = 0;
+        Ok(())
+    }
+
+    /// Sets the offset added to the current time for instructions that are
+    /// passed the clock offset account. Test builds only; anyone may call it.
+    #[cfg(feature = "test-clock")]
+    pub fn warp_clock(ctx: Context<WarpClock>, offset_secs: i64) -> Result<()> {
+        let clock_offset = &mut ctx.accounts.clock_offset;
+        clock_offset.offset_secs = offset_secs;
+        clock_offset.account_version = clock::ClockOffset::VERSION;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(did: String)]
+pub struct InitializePatient<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PatientAccount::space(&did),
+        seeds = [b"patient", authority.key().as_ref()],
+        bump
+    )]
+    pub patient_account: Account<'info, PatientAccount>,
+    /// The patient's wallet; pays for the account.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String)]
+pub struct InitializePatientSponsored<'info> {
+    #[account(
+        init,
+        payer = sponsor,
+        space = PatientAccount::space(&did),
+        seeds = [b"patient", authority.key().as_ref()],
+        bump
+    )]
+    pub patient_account: Account<'info, PatientAccount>,
+    /// The patient's wallet; needs no lamports.
+    pub authority: Signer<'info>,
+    /// Fronts the rent, which the vault then reimburses.
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(mut, seeds = [b"sponsor_vault", sponsor.key().as_ref()], bump)]
+    pub sponsor_vault: Account<'info, SponsorVault>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SPONSORSHIP) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundSponsorVault<'info> {
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = SponsorVault::SPACE,
+        seeds = [b"sponsor_vault", sponsor.key().as_ref()],
+        bump
+    )]
+    pub sponsor_vault: Account<'info, SponsorVault>,
+    /// CHECK: The clinic or partner the vault belongs to
+    pub sponsor: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SPONSORSHIP) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSponsorVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"sponsor_vault", sponsor.key().as_ref()],
+        bump,
+        has_one = sponsor @ ErrorCode::Unauthorized
+    )]
+    pub sponsor_vault: Account<'info, SponsorVault>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String, name: String)]
+pub struct InitializeProvider<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProviderAccount::space(&did, &name),
+        seeds = [b"provider", authority.key().as_ref()],
+        bump
+    )]
+    pub provider_account: Account<'info, ProviderAccount>,
+    /// The provider's wallet; pays for the account.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String)]
+pub struct UpdatePatient<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        realloc = PatientAccount::space(&did),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub patient_account: Account<'info, PatientAccount>,
+    /// The patient's wallet; pays for a longer DID and is refunded for a
+    /// shorter one.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String, name: String)]
+pub struct UpdateProvider<'info> {
+    #[account(
+        mut,
+        seeds = [b"provider", authority.key().as_ref()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        realloc = ProviderAccount::space(&did, &name),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub provider_account: Account<'info, ProviderAccount>,
+    /// The provider's wallet; pays for longer values and is refunded for
+    /// shorter ones.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(did: String, name: String)]
+pub struct InitializeInsurer<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = InsurerAccount::space(&did, &name),
+        seeds = [b"insurer", authority.key().as_ref()],
+        bump
+    )]
+    pub insurer_account: Account<'info, InsurerAccount>,
+    /// The insurer's wallet; pays for the account.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterEncryptionKey<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = EncryptionKey::SPACE,
+        seeds = [b"encryption_key", owner.key().as_ref()],
+        bump
+    )]
+    pub encryption_key: Account<'info, EncryptionKey>,
+    /// The patient, provider or other party the key belongs to.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateEncryptionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"encryption_key", owner.key().as_ref()],
+        bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub encryption_key: Account<'info, EncryptionKey>,
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct LinkDependent<'info> {
+    #[account(
+        init,
+        payer = guardian,
+        space = DependentLink::SPACE,
+        seeds = [b"dependent", guardian.key().as_ref(), dependent.key().as_ref()],
+        bump
+    )]
+    pub dependent_link: Account<'info, DependentLink>,
+    #[account(seeds = [b"patient", guardian.key().as_ref()], bump)]
+    pub guardian_account: Account<'info, PatientAccount>,
+    #[account(seeds = [b"patient", dependent.key().as_ref()], bump)]
+    pub dependent_account: Account<'info, PatientAccount>,
+    /// The guardian's wallet; pays for the link.
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    /// The dependent's wallet, co-signing to accept the guardian.
+    pub dependent: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = Delegate::SPACE,
+        seeds = [b"delegate", patient.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegate_account: Account<'info, Delegate>,
+    #[account(seeds = [b"patient", patient.key().as_ref()], bump)]
+    pub patient_account: Account<'info, PatientAccount>,
+    /// The patient's wallet; pays for the account.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The caregiver or other party acting for the patient
+    pub delegate: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(mut, close = patient, has_one = patient @ ErrorCode::Unauthorized)]
+    pub delegate_account: Account<'info, Delegate>,
+    /// Receives the account's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32], pointer: RecordPointer)]
+pub struct SubmitHealthData<'info> {
+    #[account(
+        init,
+        payer = author,
+        space = HealthDataAccount::space(&pointer.cid),
+        seeds = [b"health_data", data_hash.as_ref()],
+        bump
+    )]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    /// CHECK: The patient the record belongs to; signs only when also the author
+    pub owner: UncheckedAccount<'info>,
+    /// The owner, a grantee with write scope or a delegate; pays for the account.
+    #[account(mut)]
+    pub author: Signer<'info>,
+    /// Required when the author is not the owner.
+    #[account(seeds = [b"grant", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub grant: Option<Account<'info, AccessGrant>>,
+    /// The owner's default rules, consulted before the grant.
+    #[account(seeds = [b"access_policy", owner.key().as_ref()], bump)]
+    pub access_policy: Option<Account<'info, AccessPolicy>>,
+    /// The author's provider registration, if any.
+    #[account(seeds = [b"provider", author.key().as_ref()], bump)]
+    pub author_provider: Option<Account<'info, ProviderAccount>>,
+    /// Required when the author is the owner's delegate rather than a grantee.
+    #[account(seeds = [b"delegate", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// The owner's registered encryption key; pass it when the payload is
+    /// encrypted to it, to record its version.
+    #[account(seeds = [b"encryption_key", owner.key().as_ref()], bump)]
+    pub owner_key: Option<Account<'info, EncryptionKey>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32], pointer: RecordPointer)]
+pub struct SubmitHealthDataSponsored<'info> {
+    #[account(
+        init,
+        payer = sponsor,
+        space = HealthDataAccount::space(&pointer.cid),
+        seeds = [b"health_data", data_hash.as_ref()],
+        bump
+    )]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    /// CHECK: The patient the record belongs to; signs only when also the author
+    pub owner: UncheckedAccount<'info>,
+    /// The owner, a grantee with write scope or a delegate; needs no lamports.
+    pub author: Signer<'info>,
+    /// Required when the author is not the owner.
+    #[account(seeds = [b"grant", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub grant: Option<Account<'info, AccessGrant>>,
+    /// The owner's default rules, consulted before the grant.
+    #[account(seeds = [b"access_policy", owner.key().as_ref()], bump)]
+    pub access_policy: Option<Account<'info, AccessPolicy>>,
+    /// The author's provider registration, if any.
+    #[account(seeds = [b"provider", author.key().as_ref()], bump)]
+    pub author_provider: Option<Account<'info, ProviderAccount>>,
+    /// Required when the author is the owner's delegate rather than a grantee.
+    #[account(seeds = [b"delegate", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// Fronts the rent, which the vault then reimburses.
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(mut, seeds = [b"sponsor_vault", sponsor.key().as_ref()], bump)]
+    pub sponsor_vault: Account<'info, SponsorVault>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SPONSORSHIP) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    /// The owner's registered encryption key; pass it when the payload is
+    /// encrypted to it, to record its version.
+    #[account(seeds = [b"encryption_key", owner.key().as_ref()], bump)]
+    pub owner_key: Option<Account<'info, EncryptionKey>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(record_index: u64, data_hash: [u8; 32], pointer: RecordPointer)]
+pub struct AppendHealthRecord<'info> {
+    #[account(
+        init_if_needed,
+        payer = author,
+        space = HealthDataIndex::SPACE,
+        seeds = [b"health_data_index", owner.key().as_ref()],
+        bump
+    )]
+    pub health_data_index: Account<'info, HealthDataIndex>,
+    #[account(
+        init,
+        payer = author,
+        space = HealthDataAccount::space(&pointer.cid),
+        seeds = [b"health_record", owner.key().as_ref(), &record_index.to_le_bytes()],
+        bump
+    )]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    /// CHECK: The patient the record belongs to; signs only when also the author
+    pub owner: UncheckedAccount<'info>,
+    /// The owner, a grantee with write scope or a delegate; pays for the accounts.
+    #[account(mut)]
+    pub author: Signer<'info>,
+    /// Required when the author is not the owner.
+    #[account(seeds = [b"grant", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub grant: Option<Account<'info, AccessGrant>>,
+    /// The owner's default rules, consulted before the grant.
+    #[account(seeds = [b"access_policy", owner.key().as_ref()], bump)]
+    pub access_policy: Option<Account<'info, AccessPolicy>>,
+    /// The author's provider registration, if any.
+    #[account(seeds = [b"provider", author.key().as_ref()], bump)]
+    pub author_provider: Option<Account<'info, ProviderAccount>>,
+    /// Required when the author is the owner's delegate rather than a grantee.
+    #[account(seeds = [b"delegate", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// The owner's registered encryption key; pass it when the payload is
+    /// encrypted to it, to record its version.
+    #[account(seeds = [b"encryption_key", owner.key().as_ref()], bump)]
+    pub owner_key: Option<Account<'info, EncryptionKey>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LinkRecords<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized)]
+    pub child: Account<'info, HealthDataAccount>,
+    /// Must belong to the same owner as the child.
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub parent: Account<'info, HealthDataAccount>,
+    /// The owner of both records.
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CommitFieldRoot<'info> {
+    #[account(
+        init,
+        payer = committer,
+        space = FieldCommitment::SPACE,
+        seeds = [b"field_root", record.key().as_ref()],
+        bump
+    )]
+    pub field_commitment: Account<'info, FieldCommitment>,
+    pub record: Account<'info, HealthDataAccount>,
+    /// The record's owner or author; pays for the account.
+    #[account(mut)]
+    pub committer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyFieldDisclosure<'info> {
+    #[account(seeds = [b"field_root", record.key().as_ref()], bump, has_one = record)]
+    pub field_commitment: Account<'info, FieldCommitment>,
+    pub record: Account<'info, HealthDataAccount>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCommitment<'info> {
+    pub record: Account<'info, HealthDataAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeValidatorRegistry<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ValidatorRegistry::SPACE,
+        seeds = [b"validator_registry"],
+        bump
+    )]
+    pub validator_registry: Account<'info, ValidatorRegistry>,
+    /// Becomes the registry admin; pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DesignateValidator<'info> {
+    #[account(mut, seeds = [b"validator_registry"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub validator_registry: Account<'info, ValidatorRegistry>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = DataValidator::SPACE,
+        seeds = [b"data_validator", validator.key().as_ref()],
+        bump
+    )]
+    pub data_validator: Account<'info, DataValidator>,
+    /// CHECK: The validator's wallet; it does not need to sign
+    pub validator: UncheckedAccount<'info>,
+    /// The registry admin; pays for a new validator account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistryAuthority<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = RegistryAuthority::SPACE,
+        seeds = [b"registry_authority"],
+        bump
+    )]
+    pub registry_authority: Account<'info, RegistryAuthority>,
+    /// Pays for the account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyProvider<'info> {
+    #[account(mut, seeds = [b"registry_authority"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub registry_authority: Account<'info, RegistryAuthority>,
+    /// Only registered providers can be verified.
+    #[account(seeds = [b"provider", provider.key().as_ref()], bump)]
+    pub provider_account: Account<'info, ProviderAccount>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ProviderCredential::SPACE,
+        seeds = [b"provider_credential", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_credential: Account<'info, ProviderCredential>,
+    /// CHECK: The provider's wallet; it does not need to sign
+    pub provider: UncheckedAccount<'info>,
+    /// The credentialing authority; pays for a new credential.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeProvider<'info> {
+    #[account(mut, seeds = [b"registry_authority"], bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub registry_authority: Account<'info, RegistryAuthority>,
+    #[account(
+        mut,
+        seeds = [b"provider_credential", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_credential: Account<'info, ProviderCredential>,
+    /// CHECK: The provider's wallet, used to derive the credential
+    pub provider: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(init, payer = admin, space = Config::SPACE, seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolTreasury::SPACE,
+        seeds = [b"protocol_treasury"],
+        bump
+    )]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    /// Becomes the config admin; pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeRebates<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    /// Pays for the account the first time.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FeeRebates::SPACE,
+        seeds = [b"fee_rebates"],
+        bump
+    )]
+    pub fee_rebates: Account<'info, FeeRebates>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+    #[account(mut, seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"protocol_treasury"], bump)]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    /// CHECK: Only receives lamports; chosen by the admin
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenMintTreasury<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    /// Pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = MintTreasury::SPACE,
+        seeds = [b"mint_treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_treasury: Account<'info, MintTreasury>,
+    /// CHECK: The fee mint, checked in the instruction
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: A token account of the mint owned by mint_treasury, checked in the instruction
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: A token account of the mint, checked in the instruction
+    pub destination: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasuryDestination<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"mint_treasury", mint.key().as_ref()], bump)]
+    pub mint_treasury: Account<'info, MintTreasury>,
+    /// CHECK: The treasury's mint, which derives mint_treasury
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: A token account of the mint, checked in the instruction
+    pub destination: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepMintTreasury<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"mint_treasury", mint.key().as_ref()],
+        bump,
+        has_one = vault @ ErrorCode::InvalidTokenAccount,
+        has_one = destination @ ErrorCode::InvalidTokenAccount
+    )]
+    pub mint_treasury: Account<'info, MintTreasury>,
+    /// CHECK: The treasury's mint, which derives mint_treasury
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: The treasury's vault, verified by mint_treasury.vault
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: The configured destination, verified by mint_treasury.destination
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    /// CHECK: The mint's token program, which must own the mint
+    #[account(address = *mint.owner @ ErrorCode::UnsupportedTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveFacilitator<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    /// Pays for the account the first time.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// CHECK: The facilitator's operator key, which derives the account
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FacilitatorAccount::SPACE,
+        seeds = [b"facilitator", authority.key().as_ref()],
+        bump
+    )]
+    pub facilitator: Account<'info, FacilitatorAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateFacilitatorKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"facilitator", authority.key().as_ref()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub facilitator: Account<'info, FacilitatorAccount>,
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeFacilitator<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"facilitator", facilitator.authority.as_ref()], bump)]
+    pub facilitator: Account<'info, FacilitatorAccount>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFacilitatorFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"facilitator", authority.key().as_ref()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub facilitator: Account<'info, FacilitatorAccount>,
+    pub authority: Signer<'info>,
+    /// CHECK: Only receives lamports; chosen by the authority
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetCrankReward<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    /// Pays for the vault the first time.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = CrankVault::SPACE,
+        seeds = [b"crank_vault"],
+        bump
+    )]
+    pub crank_vault: Account<'info, CrankVault>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundCrankVault<'info> {
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Account<'info, CrankVault>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeatureFlags<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = FeatureFlags::SPACE,
+        seeds = [b"feature_flags"],
+        bump
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    /// Becomes the flags admin; pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeatureFlags<'info> {
+    #[account(mut, seeds = [b"feature_flags"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDisputeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = DisputeConfig::SPACE,
+        seeds = [b"dispute_config"],
+        bump
+    )]
+    pub dispute_config: Account<'info, DisputeConfig>,
+    /// Becomes the dispute settings' admin; pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisputeConfig<'info> {
+    #[account(mut, seeds = [b"dispute_config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub dispute_config: Account<'info, DisputeConfig>,
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AttestRecordQuality<'info> {
+    #[account(
+        init_if_needed,
+        payer = validator,
+        space = QualityAttestation::SPACE,
+        seeds = [b"quality", record.key().as_ref(), validator.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, QualityAttestation>,
+    pub record: Account<'info, HealthDataAccount>,
+    #[account(mut, seeds = [b"data_validator", validator.key().as_ref()], bump)]
+    pub data_validator: Account<'info, DataValidator>,
+    /// The designated validator; pays for the attestation.
+    #[account(mut)]
+    pub validator: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: String, entries: Vec<HealthRecordEntry>)]
+pub struct SubmitHealthDataBatch<'info> {
+    #[account(
+        init,
+        payer = author,
+        space = 8 + 32 + 4 + batch_id.len() + 4 + entries.iter().map(HealthRecordEntry::space).sum::<usize>() + 8 + 1 + 64,
+        seeds = [b"health_data_batch", owner.key().as_ref(), batch_id.as_bytes()],
+        bump
+    )]
+    pub batch_account: Account<'info, HealthDataBatchAccount>,
+    /// CHECK: The patient the records belong to; signs only when also the author
+    pub owner: UncheckedAccount<'info>,
+    /// The owner, a grantee with write scope or a delegate; pays for the account.
+    #[account(mut)]
+    pub author: Signer<'info>,
+    /// Required when the author is not the owner.
+    #[account(seeds = [b"grant", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub grant: Option<Account<'info, AccessGrant>>,
+    /// The owner's default rules, consulted before the grant.
+    #[account(seeds = [b"access_policy", owner.key().as_ref()], bump)]
+    pub access_policy: Option<Account<'info, AccessPolicy>>,
+    /// The author's provider registration, if any.
+    #[account(seeds = [b"provider", author.key().as_ref()], bump)]
+    pub author_provider: Option<Account<'info, ProviderAccount>>,
+    /// Required when the author is the owner's delegate rather than a grantee.
+    #[account(seeds = [b"delegate", owner.key().as_ref(), author.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32])]
+pub struct CreateHealthDataV2<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = HealthDataAccountV2::SPACE,
+        seeds = [b"health_data_v2", data_hash.as_ref()],
+        bump
+    )]
+    pub health_data_account: AccountLoader<'info, HealthDataAccountV2>,
+    /// The record's owner; pays for the account.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AppendDataChunk<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized)]
+    pub health_data_account: AccountLoader<'info, HealthDataAccountV2>,
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterDevice<'info> {
+    #[account(
+        init,
+        payer = patient,
+        space = DeviceAccount::SPACE,
+        seeds = [b"device", patient.key().as_ref(), device.key().as_ref()],
+        bump
+    )]
+    pub device_account: Account<'info, DeviceAccount>,
+    #[account(seeds = [b"patient", patient.key().as_ref()], bump)]
+    pub patient_account: Account<'info, PatientAccount>,
+    /// The patient's wallet; pays for the device account.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The device's signing key, which need not sign its registration
+    pub device: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDevice<'info> {
+    #[account(mut, close = patient, has_one = patient @ ErrorCode::Unauthorized)]
+    pub device_account: Account<'info, DeviceAccount>,
+    /// Receives the account's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32], pointer: RecordPointer)]
+pub struct SubmitDeviceReading<'info> {
+    #[account(
+        init,
+        payer = device,
+        space = HealthDataAccount::space(&pointer.cid),
+        seeds = [b"health_data", data_hash.as_ref()],
+        bump
+    )]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    #[account(
+        mut,
+        seeds = [b"device", patient.key().as_ref(), device.key().as_ref()],
+        bump
+    )]
+    pub device_account: Account<'info, DeviceAccount>,
+    /// CHECK: The patient the device is registered to, checked by the seeds
+    pub patient: UncheckedAccount<'info>,
+    /// The registered device; pays for the record.
+    #[account(mut)]
+    pub device: Signer<'info>,
+    /// The owner's registered encryption key; pass it when the payload is
+    /// encrypted to it, to record its version.
+    #[account(seeds = [b"encryption_key", patient.key().as_ref()], bump)]
+    pub owner_key: Option<Account<'info, EncryptionKey>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(day: i64)]
+pub struct RollUpDeviceReadings<'info> {
+    #[account(
+        init,
+        payer = device,
+        space = DeviceRollup::SPACE,
+        seeds = [b"device_rollup", device_account.key().as_ref(), &day.to_le_bytes()],
+        bump
+    )]
+    pub device_rollup: Account<'info, DeviceRollup>,
+    #[account(seeds = [b"device", patient.key().as_ref(), device.key().as_ref()], bump)]
+    pub device_account: Account<'info, DeviceAccount>,
+    /// CHECK: The patient the device is registered to, checked by the seeds
+    pub patient: UncheckedAccount<'info>,
+    /// The registered device; pays for the rollup.
+    #[account(mut)]
+    pub device: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyRollupReading<'info> {
+    pub device_rollup: Account<'info, DeviceRollup>,
+}
+
+#[derive(Accounts)]
+#[instruction(claim_id: String, line_items: Vec<LineItemInput>, attachments: Vec<[u8; 32]>, service_start: i64, service_end: i64, fingerprint: [u8; 32])]
+pub struct CreateClaim<'info> {
+    #[account(
+        init,
+        payer = filer,
+        space = 8 + 4 + claim_id.len() + 32 + 32 + 4 + MAX_ATTACHMENTS * 32 + 8 + 4 + line_items.len() * ClaimLineItem::SPACE + 1 + 1 + 8 + 4 + 8 + 1 + 8 + 8 + 8 + 32 + 32 + 4 + MAX_EXTERNAL_REF_LEN + 4 + MAX_CLAIM_TAGS * (4 + MAX_TAG_LEN) + 8 + 8 + 32 + 1 + 1 + 32 + 32 + 8 + 8 + 1 + 64,
+        seeds = [b"claim", claim_id.as_bytes()],
+        bump
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// CHECK: The patient the claim is for; the filer is checked against it
+    pub patient: UncheckedAccount<'info>,
+    /// The patient, their guardian or their delegate; pays for the account.
+    #[account(mut)]
+    pub filer: Signer<'info>,
+    /// Required when the filer is a guardian.
+    #[account(seeds = [b"dependent", filer.key().as_ref(), patient.key().as_ref()], bump)]
+    pub guardian_link: Option<Account<'info, DependentLink>>,
+    /// Required when the filer is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), filer.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// CHECK: The provider account is just a pubkey here for assignment
+    pub provider: UncheckedAccount<'info>,
+    /// The insurer who must approve the claim before it is paid, if any.
+    pub insurer: Option<Account<'info, InsurerAccount>>,
+    /// CHECK: The SPL mint the claim is paid in, such as USDC, checked in the
+    /// instruction. Without it the claim is paid in lamports.
+    pub mint: Option<UncheckedAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ProviderClaimStats::SPACE,
+        seeds = [b"provider_stats", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stats: Account<'info, ProviderClaimStats>,
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ClaimDedup::SPACE,
+        seeds = [b"claim_dedup", fingerprint.as_ref()],
+        bump
+    )]
+    pub claim_dedup: Account<'info, ClaimDedup>,
+    /// Opts the claim into a read grant for its provider.
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ClaimGrant::SPACE,
+        seeds = [b"claim_grant", claim_account.key().as_ref()],
+        bump
+    )]
+    pub claim_grant: Option<Account<'info, ClaimGrant>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateClaimsBatch<'info> {
+    /// Files every claim in the batch; pays for the accounts.
+    #[account(mut)]
+    pub filer: Signer<'info>,
+    /// CHECK: The provider account is just a pubkey here for assignment
+    pub provider: UncheckedAccount<'info>,
+    /// The insurer who must approve every claim in the batch, if any.
+    pub insurer: Option<Account<'info, InsurerAccount>>,
+    /// CHECK: The SPL mint every claim in the batch is paid in, checked in
+    /// the instruction. Without it the claims are paid in lamports.
+    pub mint: Option<UncheckedAccount<'info>>,
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ProviderClaimStats::SPACE,
+        seeds = [b"provider_stats", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stats: Account<'info, ProviderClaimStats>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeSchedule<'info> {
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = FeeSchedule::SPACE,
+        seeds = [b"fee_schedule", provider.key().as_ref()],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(appointment_id: String)]
+pub struct ScheduleAppointment<'info> {
+    #[account(
+        init,
+        payer = patient,
+        space = Appointment::SPACE,
+        seeds = [b"appointment", provider.key().as_ref(), appointment_id.as_bytes()],
+        bump
+    )]
+    pub appointment: Account<'info, Appointment>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The provider booked, checked to be registered
+    pub provider: UncheckedAccount<'info>,
+    #[account(seeds = [b"provider", provider.key().as_ref()], bump)]
+    pub provider_account: Account<'info, ProviderAccount>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteAppointment<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::Unauthorized)]
+    pub appointment: Account<'info, Appointment>,
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(claim_id: String, fingerprint: [u8; 32])]
+pub struct CreateClaimFromAppointment<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::Unauthorized)]
+    pub appointment: Account<'info, Appointment>,
+    #[account(seeds = [b"fee_schedule", provider.key().as_ref()], bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + 4 + claim_id.len() + 32 + 32 + 4 + MAX_ATTACHMENTS * 32 + 8 + 4 + appointment.services.len() * ClaimLineItem::SPACE + 1 + 1 + 8 + 4 + 8 + 1 + 8 + 8 + 8 + 32 + 32 + 4 + MAX_EXTERNAL_REF_LEN + 4 + MAX_CLAIM_TAGS * (4 + MAX_TAG_LEN) + 8 + 8 + 32 + 1 + 1 + 32 + 32 + 8 + 8 + 1 + 64,
+        seeds = [b"claim", claim_id.as_bytes()],
+        bump
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The appointment's provider; pays for the claim.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderClaimStats::SPACE,
+        seeds = [b"provider_stats", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stats: Account<'info, ProviderClaimStats>,
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ClaimDedup::SPACE,
+        seeds = [b"claim_dedup", fingerprint.as_ref()],
+        bump
+    )]
+    pub claim_dedup: Account<'info, ClaimDedup>,
+    /// Opts the claim into a read grant for the provider.
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ClaimGrant::SPACE,
+        seeds = [b"claim_grant", claim_account.key().as_ref()],
+        bump
+    )]
+    pub claim_grant: Option<Account<'info, ClaimGrant>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tenant_id: String)]
+pub struct CreateTenant<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Tenant::SPACE,
+        seeds = [b"tenant", tenant_id.as_bytes()],
+        bump
+    )]
+    pub tenant: Account<'info, Tenant>,
+    /// Becomes the tenant admin; pays for the account.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_TENANTS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTenant<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub tenant: Account<'info, Tenant>,
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AdmitTenantMember<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub tenant: Account<'info, Tenant>,
+    #[account(
+        init,
+        payer = admin,
+        space = TenantMember::SPACE,
+        seeds = [b"tenant_member", tenant.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, TenantMember>,
+    /// CHECK: The member's wallet; it does not need to sign
+    pub member: UncheckedAccount<'info>,
+    /// The tenant admin; pays for the membership.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveTenantMember<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::Unauthorized)]
+    pub tenant: Account<'info, Tenant>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"tenant_member", tenant.key().as_ref(), membership.member.as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, TenantMember>,
+    /// The tenant admin; receives the membership's rent.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlanBenefits<'info> {
+    #[account(has_one = admin @ ErrorCode::Unauthorized)]
+    pub tenant: Account<'info, Tenant>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PlanBenefits::SPACE,
+        seeds = [b"plan_benefits", tenant.key().as_ref()],
+        bump
+    )]
+    pub plan_benefits: Account<'info, PlanBenefits>,
+    /// The tenant admin; pays for the plan benefits the first time.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(period: u32)]
+pub struct PayPremium<'info> {
+    pub tenant: Account<'info, Tenant>,
+    #[account(seeds = [b"plan_benefits", tenant.key().as_ref()], bump)]
+    pub plan_benefits: Account<'info, PlanBenefits>,
+    #[account(seeds = [b"tenant_member", tenant.key().as_ref(), patient.key().as_ref()], bump)]
+    pub patient_membership: Account<'info, TenantMember>,
+    /// The patient's research consent, for the plan's research discount.
+    #[account(seeds = [b"research_consent", patient.key().as_ref()], bump)]
+    pub research_consent: Option<Account<'info, ResearchConsent>>,
+    #[account(
+        init,
+        payer = patient,
+        space = PremiumPayment::SPACE,
+        seeds = [
+            b"premium_payment",
+            tenant.key().as_ref(),
+            patient.key().as_ref(),
+            &period.to_le_bytes()
+        ],
+        bump
+    )]
+    pub premium_payment: Account<'info, PremiumPayment>,
+    /// Pays the premium and the payment's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The tenant's treasury, checked against `tenant`; receives the premium
+    #[account(mut, address = tenant.treasury @ ErrorCode::TenantMismatch)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(claim_id: String, line_items: Vec<LineItemInput>, attachments: Vec<[u8; 32]>, service_start: i64, service_end: i64, fingerprint: [u8; 32])]
+pub struct CreateTenantClaim<'info> {
+    pub tenant: Account<'info, Tenant>,
+    #[account(
+        init,
+        payer = filer,
+        space = 8 + 4 + claim_id.len() + 32 + 32 + 4 + MAX_ATTACHMENTS * 32 + 8 + 4 + line_items.len() * ClaimLineItem::SPACE + 1 + 1 + 8 + 4 + 8 + 1 + 8 + 8 + 8 + 32 + 32 + 4 + MAX_EXTERNAL_REF_LEN + 4 + MAX_CLAIM_TAGS * (4 + MAX_TAG_LEN) + 8 + 8 + 32 + 1 + 1 + 32 + 32 + 8 + 8 + 1 + 64,
+        seeds = [b"claim", tenant.key().as_ref(), claim_id.as_bytes()],
+        bump
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// CHECK: The patient the claim is for; the filer is checked against it
+    pub patient: UncheckedAccount<'info>,
+    #[account(seeds = [b"tenant_member", tenant.key().as_ref(), patient.key().as_ref()], bump)]
+    pub patient_membership: Account<'info, TenantMember>,
+    /// The patient, their guardian or their delegate; pays for the account.
+    #[account(mut)]
+    pub filer: Signer<'info>,
+    /// Required when the filer is a guardian.
+    #[account(seeds = [b"dependent", filer.key().as_ref(), patient.key().as_ref()], bump)]
+    pub guardian_link: Option<Account<'info, DependentLink>>,
+    /// Required when the filer is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), filer.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// CHECK: The provider is just a pubkey here for assignment
+    pub provider: UncheckedAccount<'info>,
+    /// The insurer who must approve the claim before it is paid, if any.
+    pub insurer: Option<Account<'info, InsurerAccount>>,
+    #[account(seeds = [b"tenant_member", tenant.key().as_ref(), provider.key().as_ref()], bump)]
+    pub provider_membership: Account<'info, TenantMember>,
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ProviderClaimStats::SPACE,
+        seeds = [b"provider_stats", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stats: Account<'info, ProviderClaimStats>,
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ClaimDedup::SPACE,
+        seeds = [b"claim_dedup", fingerprint.as_ref()],
+        bump
+    )]
+    pub claim_dedup: Account<'info, ClaimDedup>,
+    /// Opts the claim into a read grant for its provider.
+    #[account(
+        init_if_needed,
+        payer = filer,
+        space = ClaimGrant::SPACE,
+        seeds = [b"claim_grant", claim_account.key().as_ref()],
+        bump
+    )]
+    pub claim_grant: Option<Account<'info, ClaimGrant>>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_TENANTS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReviewClaim<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Only the assigned provider can review.
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyClaim<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Only the assigned provider can verify; pays for the out-of-pocket
+    /// tally the first time.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(
+        seeds = [b"provider_credential", provider.key().as_ref()],
+        bump,
+        constraint = provider_credential.verified @ ErrorCode::ProviderNotVerified
+    )]
+    pub provider_credential: Account<'info, ProviderCredential>,
+    /// CHECK: The claim tenant's plan benefits, read if the tenant set any.
+    /// Required for tenant claims.
+    #[account(seeds = [b"plan_benefits", claim_account.tenant.as_ref()], bump)]
+    pub plan_benefits: Option<UncheckedAccount<'info>>,
+    /// The patient's tally for the claim's year of service, required if the
+    /// plan has an out-of-pocket maximum.
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = OutOfPocket::SPACE,
+        seeds = [
+            b"out_of_pocket",
+            claim_account.tenant.as_ref(),
+            claim_account.patient.as_ref(),
+            &claim_account.service_year().to_le_bytes()
+        ],
+        bump
+    )]
+    pub out_of_pocket: Option<Account<'info, OutOfPocket>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyClaimsBatch<'info> {
+    /// The provider every claim in the batch is assigned to.
+    pub provider: Signer<'info>,
+    #[account(
+        seeds = [b"provider_credential", provider.key().as_ref()],
+        bump,
+        constraint = provider_credential.verified @ ErrorCode::ProviderNotVerified
+    )]
+    pub provider_credential: Account<'info, ProviderCredential>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct EnableAutoApproval<'info> {
+    #[account(
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = provider @ ErrorCode::InvalidProvider
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Pays for the opt-in.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    pub provider: Signer<'info>,
+    /// Auto-approval verifies the claim, so it needs a verified provider too.
+    #[account(
+        seeds = [b"provider_credential", provider.key().as_ref()],
+        bump,
+        constraint = provider_credential.verified @ ErrorCode::ProviderNotVerified
+    )]
+    pub provider_credential: Account<'info, ProviderCredential>,
+    #[account(
+        init,
+        payer = patient,
+        space = AutoApproval::SPACE,
+        seeds = [b"auto_approval", claim_account.key().as_ref()],
+        bump
+    )]
+    pub auto_approval: Account<'info, AutoApproval>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AutoApproveClaim<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// CHECK: Receives the opt-in's rent, verified by claim_account.patient
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = patient,
+        seeds = [b"auto_approval", claim_account.key().as_ref()],
+        bump
+    )]
+    pub auto_approval: Account<'info, AutoApproval>,
+    /// Anyone may crank a due claim; receives the crank reward.
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    /// Pays the crank reward, if passed.
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Option<Account<'info, CrankVault>>,
+    /// CHECK: The claim tenant's plan benefits, read if the tenant set any.
+    /// Required for tenant claims.
+    #[account(seeds = [b"plan_benefits", claim_account.tenant.as_ref()], bump)]
+    pub plan_benefits: Option<UncheckedAccount<'info>>,
+    /// The patient's tally for the claim's year of service, required if the
+    /// plan has an out-of-pocket maximum; the cranker pays for it the first
+    /// time.
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = OutOfPocket::SPACE,
+        seeds = [
+            b"out_of_pocket",
+            claim_account.tenant.as_ref(),
+            claim_account.patient.as_ref(),
+            &claim_account.service_year().to_le_bytes()
+        ],
+        bump
+    )]
+    pub out_of_pocket: Option<Account<'info, OutOfPocket>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireClaim<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::InvalidProvider)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The claim's escrow, if funded; closed to the provider.
+    #[account(
+        mut,
+        seeds = [b"claim_escrow", claim_account.key().as_ref()],
+        bump,
+        close = provider
+    )]
+    pub claim_escrow: Option<Account<'info, ClaimEscrow>>,
+    /// CHECK: The claim's provider, verified by claim_account.provider; gets back the escrow
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    /// Anyone may expire a due claim; receives the crank reward.
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    /// Pays the crank reward, if passed.
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Option<Account<'info, CrankVault>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(fingerprint: [u8; 32])]
+pub struct ReassignClaim<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient who filed the claim; pays for the new dedup marker.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The provider is just a pubkey here for assignment; signing
+    /// accepts the claim.
+    pub new_provider: UncheckedAccount<'info>,
+    /// Required when the claim belongs to a tenant.
+    #[account(seeds = [b"tenant_member", claim_account.tenant.as_ref(), new_provider.key().as_ref()], bump)]
+    pub new_provider_membership: Option<Account<'info, TenantMember>>,
+    /// The claim's dedup marker under its current provider.
+    #[account(
+        mut,
+        close = patient,
+        constraint = claim_dedup.claim == claim_account.key() @ ErrorCode::ClaimFingerprintMismatch
+    )]
+    pub claim_dedup: Account<'info, ClaimDedup>,
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = ClaimDedup::SPACE,
+        seeds = [b"claim_dedup", fingerprint.as_ref()],
+        bump
+    )]
+    pub new_claim_dedup: Account<'info, ClaimDedup>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAttachments<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient who filed the claim.
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct AppendClaimNote<'info> {
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        init_if_needed,
+        payer = author,
+        space = 8 + 32 + 4 + 4 + NOTES_PER_PAGE * ClaimNote::SPACE + 1,
+        seeds = [b"claim_notes", claim_account.key().as_ref(), &page.to_le_bytes()],
+        bump
+    )]
+    pub notes_page: Account<'info, ClaimNotesPage>,
+    /// The patient or provider on the claim; pays for a new page.
+    #[account(mut)]
+    pub author: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimReferences<'info> {
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient or provider on the claim.
+    pub signer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(policy_id: String)]
+pub struct SetApprovalPolicy<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ApprovalPolicy::SPACE,
+        seeds = [b"approval_policy", authority.key().as_ref(), policy_id.as_bytes()],
+        bump
+    )]
+    pub approval_policy: Account<'info, ApprovalPolicy>,
+    /// The provider the policy is for.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AttachApprovalPolicy<'info> {
+    #[account(mut, has_one = provider @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(constraint = approval_policy.authority == provider.key() @ ErrorCode::Unauthorized)]
+    pub approval_policy: Account<'info, ApprovalPolicy>,
+    /// The assigned provider, whose policy it must be.
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveClaimPayment<'info> {
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(address = claim_account.approval_policy @ ErrorCode::NotAnApprover)]
+    pub approval_policy: Account<'info, ApprovalPolicy>,
+    pub approver: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AdjudicateClaim<'info> {
+    #[account(mut, has_one = insurer @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Only the insurer the claim names can adjudicate it.
+    pub insurer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(period: u32)]
+pub struct ProcessPayment<'info> {
+    #[account(
+        mut,
+        has_one = provider @ ErrorCode::Unauthorized,
+        has_one = patient @ ErrorCode::InvalidPatient
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The assigned provider, who pays the claim.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    /// CHECK: We are transferring funds to this account, verified by claim_account.patient
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// The provider and patient's summary for the current month.
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = SettlementSummary::SPACE,
+        seeds = [b"settlement", provider.key().as_ref(), patient.key().as_ref(), &period.to_le_bytes()],
+        bump
+    )]
+    pub settlement: Account<'info, SettlementSummary>,
+    /// Required for a tenant's claims.
+    pub tenant: Option<Account<'info, Tenant>>,
+    /// CHECK: The tenant's treasury, checked against `tenant`; receives the fee
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+    /// The claim's payment schedule, if any.
+    #[account(mut, seeds = [b"payment_schedule", claim_account.key().as_ref()], bump)]
+    pub payment_schedule: Option<Account<'info, PaymentSchedule>>,
+    /// Optional audit trail of the patient, with `audit_entry`.
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = AuditTrail::SPACE,
+        seeds = [b"audit_trail", patient.key().as_ref()],
+        bump
+    )]
+    pub audit_trail: Option<Account<'info, AuditTrail>>,
+    /// The trail's next entry.
+    #[account(
+        init,
+        payer = provider,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit_entry", patient.key().as_ref(), &next_audit_sequence(&audit_trail).to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Option<Account<'info, AuditEntry>>,
+    /// Receives the protocol fee.
+    #[account(mut, seeds = [b"protocol_treasury"], bump)]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    /// The clawback window, required with `payment_hold`.
+    #[account(seeds = [b"clawback_config"], bump)]
+    pub clawback_config: Option<Account<'info, ClawbackConfig>>,
+    /// Holds the patient's share for the clawback window instead of paying
+    /// it straight away.
+    #[account(
+        init,
+        payer = provider,
+        space = PaymentHold::SPACE,
+        seeds = [b"payment_hold", claim_account.key().as_ref()],
+        bump
+    )]
+    pub payment_hold: Option<Account<'info, PaymentHold>>,
+    /// The facilitator the payment is attributed to, with
+    /// `facilitator_signer`; receives its share of the protocol fee.
+    #[account(mut, seeds = [b"facilitator", facilitator.authority.as_ref()], bump)]
+    pub facilitator: Option<Account<'info, FacilitatorAccount>>,
+    /// The facilitator's current signing key.
+    pub facilitator_signer: Option<Signer<'info>>,
+    /// The protocol fee rebates, passed with `provider_volume` for the
+    /// provider's rebate.
+    #[account(seeds = [b"fee_rebates"], bump)]
+    pub fee_rebates: Option<Account<'info, FeeRebates>>,
+    /// The provider's volume for the current month, which counts the
+    /// payment.
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderVolume::SPACE,
+        seeds = [b"provider_volume", provider.key().as_ref(), &period.to_le_bytes()],
+        bump
+    )]
+    pub provider_volume: Option<Account<'info, ProviderVolume>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentSchedule<'info> {
+    #[account(
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = provider @ ErrorCode::InvalidProvider
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Pays for the schedule.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    pub provider: Signer<'info>,
+    #[account(
+        init,
+        payer = patient,
+        space = PaymentSchedule::SPACE,
+        seeds = [b"payment_schedule", claim_account.key().as_ref()],
+        bump
+    )]
+    pub payment_schedule: Account<'info, PaymentSchedule>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessTokenPayment<'info> {
+    #[account(
+        mut,
+        has_one = provider @ ErrorCode::Unauthorized,
+        has_one = patient @ ErrorCode::InvalidPatient,
+        constraint = claim_account.payment_mint() == mint.key() @ ErrorCode::ClaimMintMismatch
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The assigned provider, who pays the claim.
+    pub provider: Signer<'info>,
+    /// CHECK: The claim's patient, verified by claim_account.patient
+    pub patient: UncheckedAccount<'info>,
+    /// CHECK: The claim's mint, verified by claim_account.mint
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: The provider's token account of the mint, checked in the instruction
+    #[account(mut)]
+    pub provider_token: UncheckedAccount<'info>,
+    /// CHECK: The patient's token account of the mint, checked in the instruction
+    #[account(mut)]
+    pub patient_token: UncheckedAccount<'info>,
+    /// CHECK: The mint's token program, which must own the mint
+    #[account(address = *mint.owner @ ErrorCode::UnsupportedTokenProgram)]
+    pub token_program: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    /// Required while a protocol fee is set.
+    #[account(mut, seeds = [b"mint_treasury", mint.key().as_ref()], bump)]
+    pub mint_treasury: Option<Account<'info, MintTreasury>>,
+    /// CHECK: The mint treasury's vault, checked against `mint_treasury`; receives the fee
+    #[account(mut)]
+    pub treasury_vault: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct FundClaimEscrow<'info> {
+    #[account(has_one = provider @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        init,
+        payer = provider,
+        space = ClaimEscrow::SPACE,
+        seeds = [b"claim_escrow", claim_account.key().as_ref()],
+        bump
+    )]
+    pub claim_escrow: Account<'info, ClaimEscrow>,
+    /// The assigned provider, who locks the payment.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(period: u32)]
+pub struct SettleClaim<'info> {
+    #[account(
+        mut,
+        has_one = provider @ ErrorCode::InvalidProvider,
+        has_one = patient @ ErrorCode::InvalidPatient
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        mut,
+        seeds = [b"claim_escrow", claim_account.key().as_ref()],
+        bump,
+        close = provider
+    )]
+    pub claim_escrow: Account<'info, ClaimEscrow>,
+    /// CHECK: The claim's provider, verified by claim_account.provider; gets back what the patient is not owed
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    /// CHECK: We are transferring funds to this account, verified by claim_account.patient
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// The provider and patient's summary for the current month.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = SettlementSummary::SPACE,
+        seeds = [b"settlement", provider.key().as_ref(), patient.key().as_ref(), &period.to_le_bytes()],
+        bump
+    )]
+    pub settlement: Account<'info, SettlementSummary>,
+    /// Anyone; pays for a new summary and receives the crank reward for a
+    /// standard escrow.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// The second signature a split-custody escrow needs.
+    pub cosigner: Option<Signer<'info>>,
+    /// Pays the crank reward, if passed.
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Option<Account<'info, CrankVault>>,
+    /// Receives the protocol fee.
+    #[account(mut, seeds = [b"protocol_treasury"], bump)]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    /// The facilitator the payment is attributed to, with
+    /// `facilitator_signer`; receives its share of the protocol fee.
+    #[account(mut, seeds = [b"facilitator", facilitator.authority.as_ref()], bump)]
+    pub facilitator: Option<Account<'info, FacilitatorAccount>>,
+    /// The facilitator's current signing key.
+    pub facilitator_signer: Option<Signer<'info>>,
+    /// The protocol fee rebates, passed with `provider_volume` for the
+    /// provider's rebate.
+    #[account(seeds = [b"fee_rebates"], bump)]
+    pub fee_rebates: Option<Account<'info, FeeRebates>>,
+    /// The provider's volume for the current month, which counts a verified
+    /// claim's payment.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = ProviderVolume::SPACE,
+        seeds = [b"provider_volume", provider.key().as_ref(), &period.to_le_bytes()],
+        bump
+    )]
+    pub provider_volume: Option<Account<'info, ProviderVolume>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetClawbackWindow<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    /// Pays for the account the first time.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ClawbackConfig::SPACE,
+        seeds = [b"clawback_config"],
+        bump
+    )]
+    pub clawback_config: Account<'info, ClawbackConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleasePaymentHold<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_hold", payment_hold.claim.as_ref()],
+        bump,
+        has_one = provider @ ErrorCode::InvalidProvider,
+        has_one = patient @ ErrorCode::InvalidPatient,
+        close = provider
+    )]
+    pub payment_hold: Account<'info, PaymentHold>,
+    /// CHECK: The paying provider, verified by payment_hold.provider; gets the rent back
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    /// CHECK: We are transferring funds to this account, verified by payment_hold.patient
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// Anyone once the window has passed; before, only the provider.
+    /// Receives the crank reward for a due release.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// Pays the crank reward, if passed.
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Option<Account<'info, CrankVault>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ClawBackPayment<'info> {
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        mut,
+        seeds = [b"payment_hold", claim_account.key().as_ref()],
+        bump,
+        has_one = provider @ ErrorCode::InvalidProvider,
+        close = provider
+    )]
+    pub payment_hold: Account<'info, PaymentHold>,
+    /// CHECK: The paying provider, verified by payment_hold.provider; gets the payment back
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    /// The summary the payment was counted in.
+    #[account(
+        mut,
+        seeds = [
+            b"settlement",
+            payment_hold.provider.as_ref(),
+            payment_hold.patient.as_ref(),
+            &payment_hold.period.to_le_bytes()
+        ],
+        bump
+    )]
+    pub settlement: Account<'info, SettlementSummary>,
+    /// The arbiter, or the provider or patient with the other as `cosigner`.
+    pub signer: Signer<'info>,
+    pub cosigner: Option<Signer<'info>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelClaim<'info> {
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = rent_payer @ ErrorCode::Unauthorized
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient the claim is for; receives the opt-in's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: Receives the rent of the claim, its dedup marker and its grant,
+    /// verified by claim_account.rent_payer
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// The claim's dedup marker; claims filed before markers have none.
+    #[account(
+        mut,
+        close = rent_payer,
+        constraint = claim_dedup.claim == claim_account.key() @ ErrorCode::ClaimFingerprintMismatch
+    )]
+    pub claim_dedup: Option<Account<'info, ClaimDedup>>,
+    /// The claim's auto-approval opt-in, if any.
+    #[account(mut, close = patient, seeds = [b"auto_approval", claim_account.key().as_ref()], bump)]
+    pub auto_approval: Option<Account<'info, AutoApproval>>,
+    /// The claim's read grant, if any.
+    #[account(mut, close = rent_payer, seeds = [b"claim_grant", claim_account.key().as_ref()], bump)]
+    pub claim_grant: Option<Account<'info, ClaimGrant>>,
+    /// CHECK: Must hold no account, so no escrowed payment is stranded
+    #[account(
+        seeds = [b"claim_escrow", claim_account.key().as_ref()],
+        bump,
+        constraint = claim_escrow.data_is_empty() @ ErrorCode::ClaimEscrowed
+    )]
+    pub claim_escrow: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct GetPatientYearSummary<'info> {
+    /// CHECK: The patient whose claims are totalled; each claim is checked
+    /// against it
+    pub patient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetCoverageProof<'info> {
+    pub tenant: Account<'info, Tenant>,
+    /// CHECK: The patient whose coverage is proven
+    pub patient: UncheckedAccount<'info>,
+    #[account(seeds = [b"tenant_member", tenant.key().as_ref(), patient.key().as_ref()], bump)]
+    pub patient_membership: Account<'info, TenantMember>,
+    /// CHECK: The clinic's provider wallet whose network status is reported
+    pub provider: UncheckedAccount<'info>,
+    /// The provider's membership, if they are in the network.
+    #[account(seeds = [b"tenant_member", tenant.key().as_ref(), provider.key().as_ref()], bump)]
+    pub provider_membership: Option<Account<'info, TenantMember>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseClaim<'info> {
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = rent_payer @ ErrorCode::Unauthorized
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient the claim is for; receives the rent of the opt-in,
+    /// schedule and dispute.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: Receives the rent of the claim and its grant, verified by
+    /// claim_account.rent_payer
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// The claim's auto-approval opt-in, if any.
+    #[account(mut, close = patient, seeds = [b"auto_approval", claim_account.key().as_ref()], bump)]
+    pub auto_approval: Option<Account<'info, AutoApproval>>,
+    /// The claim's read grant, if any.
+    #[account(mut, close = rent_payer, seeds = [b"claim_grant", claim_account.key().as_ref()], bump)]
+    pub claim_grant: Option<Account<'info, ClaimGrant>>,
+    /// The claim's payment schedule, if any.
+    #[account(mut, close = patient, seeds = [b"payment_schedule", claim_account.key().as_ref()], bump)]
+    pub payment_schedule: Option<Account<'info, PaymentSchedule>>,
+    /// The claim's dispute, if any.
+    #[account(mut, close = patient, seeds = [b"claim_dispute", claim_account.key().as_ref()], bump)]
+    pub claim_dispute: Option<Account<'info, ClaimDispute>>,
+    /// CHECK: Must hold no account, so no escrowed payment is stranded
+    #[account(
+        seeds = [b"claim_escrow", claim_account.key().as_ref()],
+        bump,
+        constraint = claim_escrow.data_is_empty() @ ErrorCode::ClaimEscrowed
+    )]
+    pub claim_escrow: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_attachments: Vec<[u8; 32]>, period: u32)]
+pub struct ReopenClaim<'info> {
+    #[account(
+        mut,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = provider @ ErrorCode::InvalidProvider
+    )]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// The patient who filed the claim; pays for a new summary.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The claim's provider, verified by claim_account.provider
+    pub provider: UncheckedAccount<'info>,
+    /// The provider and patient's summary for the current month.
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = SettlementSummary::SPACE,
+        seeds = [b"settlement", provider.key().as_ref(), patient.key().as_ref(), &period.to_le_bytes()],
+        bump
+    )]
+    pub settlement: Account<'info, SettlementSummary>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeClaim<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        init,
+        payer = patient,
+        space = ClaimDispute::SPACE,
+        seeds = [b"claim_dispute", claim_account.key().as_ref()],
+        bump
+    )]
+    pub claim_dispute: Account<'info, ClaimDispute>,
+    #[account(seeds = [b"dispute_config"], bump)]
+    pub dispute_config: Account<'info, DisputeConfig>,
+    /// The patient who filed the claim; pays for the dispute and its bond.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(
+        mut,
+        seeds = [b"claim_dispute", claim_account.key().as_ref()],
+        bump,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = provider @ ErrorCode::Unauthorized
+    )]
+    pub claim_dispute: Account<'info, ClaimDispute>,
+    /// The dispute's arbiter, or anyone once the deadline has passed.
+    /// Receives the crank reward for a dispute past its deadline.
+    #[account(mut)]
+    pub resolver: Signer<'info>,
+    /// Pays the crank reward, if passed.
+    #[account(mut, seeds = [b"crank_vault"], bump)]
+    pub crank_vault: Option<Account<'info, CrankVault>>,
+    /// CHECK: Matched against the dispute; gets the bond back if the patient
+    /// prevails.
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// CHECK: Matched against the dispute; gets the bond if the rejection
+    /// stands.
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct StakeCollateral<'info> {
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderStake::SPACE,
+        seeds = [b"provider_stake", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stake: Account<'info, ProviderStake>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [b"provider_stake", provider.key().as_ref()],
+        bump,
+        has_one = provider @ ErrorCode::Unauthorized
+    )]
+    pub provider_stake: Account<'info, ProviderStake>,
+    /// Receives the collateral on withdrawal.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RuleFraud<'info> {
+    #[account(
+        seeds = [b"claim_dispute", claim_dispute.claim.as_ref()],
+        bump,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = provider @ ErrorCode::Unauthorized
+    )]
+    pub claim_dispute: Account<'info, ClaimDispute>,
+    #[account(
+        init,
+        payer = arbiter,
+        space = FraudFinding::SPACE,
+        seeds = [b"fraud_finding", claim_dispute.claim.as_ref()],
+        bump
+    )]
+    pub fraud_finding: Account<'info, FraudFinding>,
+    /// The provider's collateral, if they staked any.
+    #[account(mut, seeds = [b"provider_stake", provider.key().as_ref()], bump)]
+    pub provider_stake: Option<Account<'info, ProviderStake>>,
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        space = ProviderClaimStats::SPACE,
+        seeds = [b"provider_stats", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_stats: Account<'info, ProviderClaimStats>,
+    /// The dispute's arbiter; pays for the finding.
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+    /// CHECK: Matched against the dispute; receives the slashed collateral
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// CHECK: Matched against the dispute
+    pub provider: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSettlementPeriod<'info> {
+    #[account(mut)]
+    pub settlement: Account<'info, SettlementSummary>,
+    /// The payer or payee.
+    pub signer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct GrantAccess<'info> {
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = AccessGrant::SPACE,
+        seeds = [b"grant", patient.key().as_ref(), grantee.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, AccessGrant>,
+    /// CHECK: The patient whose records are shared; the granter is checked against it
+    pub patient: UncheckedAccount<'info>,
+    /// The patient, their guardian or their delegate; pays for the account.
+    #[account(mut)]
+    pub granter: Signer<'info>,
+    /// Required when the granter is a guardian.
+    #[account(seeds = [b"dependent", granter.key().as_ref(), patient.key().as_ref()], bump)]
+    pub guardian_link: Option<Account<'info, DependentLink>>,
+    /// Required when the granter is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), granter.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// CHECK: The provider or other party receiving access
+    pub grantee: UncheckedAccount<'info>,
+    /// Required with a key envelope; the key it is wrapped to.
+    #[account(seeds = [b"encryption_key", grantee.key().as_ref()], bump)]
+    pub grantee_key: Option<Account<'info, EncryptionKey>>,
+    /// Where a key envelope is stored, when one is passed.
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = KeyEnvelope::SPACE,
+        seeds = [b"key_envelope", grant.key().as_ref()],
+        bump
+    )]
+    pub key_envelope: Option<Account<'info, KeyEnvelope>>,
+    /// Optional hash chain of the patient's consent receipts.
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = ConsentLedger::SPACE,
+        seeds = [b"consent_ledger", patient.key().as_ref()],
+        bump
+    )]
+    pub consent_ledger: Option<Account<'info, ConsentLedger>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateAccess<'info> {
+    /// The delegator's own grant.
+    #[account(
+        mut,
+        constraint = parent_grant.grantee == delegator.key() @ ErrorCode::Unauthorized
+    )]
+    pub parent_grant: Account<'info, AccessGrant>,
+    /// The grantee of the parent grant; pays for the account.
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    /// CHECK: The team member receiving access
+    pub delegate: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = AccessGrant::SPACE,
+        seeds = [b"grant", parent_grant.patient.as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, AccessGrant>,
+    /// Optional hash chain of the patient's consent receipts.
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = ConsentLedger::SPACE,
+        seeds = [b"consent_ledger", parent_grant.patient.as_ref()],
+        bump
+    )]
+    pub consent_ledger: Option<Account<'info, ConsentLedger>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    /// The delegator's own grant.
+    #[account(
+        mut,
+        constraint = parent_grant.grantee == delegator.key() @ ErrorCode::Unauthorized
+    )]
+    pub parent_grant: Account<'info, AccessGrant>,
+    /// The grantee of the parent grant.
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    #[account(
+        mut,
+        constraint = grant.parent == parent_grant.key() @ ErrorCode::GrantChainMismatch
+    )]
+    pub grant: Account<'info, AccessGrant>,
+    /// Optional hash chain of the patient's consent receipts.
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = ConsentLedger::SPACE,
+        seeds = [b"consent_ledger", parent_grant.patient.as_ref()],
+        bump
+    )]
+    pub consent_ledger: Option<Account<'info, ConsentLedger>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccess<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub grant: Account<'info, AccessGrant>,
+    /// Required when the grant was delegated; the grant it came from.
+    #[account(mut, address = grant.parent @ ErrorCode::GrantChainMismatch)]
+    pub parent_grant: Option<Account<'info, AccessGrant>>,
+    /// CHECK: Matched against the grant; the granter is checked against it
+    pub patient: UncheckedAccount<'info>,
+    /// The patient, their guardian or their delegate.
+    #[account(mut)]
+    pub granter: Signer<'info>,
+    /// Required when the granter is a guardian.
+    #[account(seeds = [b"dependent", granter.key().as_ref(), patient.key().as_ref()], bump)]
+    pub guardian_link: Option<Account<'info, DependentLink>>,
+    /// Required when the granter is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), granter.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// Optional hash chain of the patient's consent receipts.
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = ConsentLedger::SPACE,
+        seeds = [b"consent_ledger", patient.key().as_ref()],
+        bump
+    )]
+    pub consent_ledger: Option<Account<'info, ConsentLedger>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrantConsent<'info> {
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = ConsentAccount::SPACE,
+        seeds = [b"consent", patient.key().as_ref(), provider.key().as_ref(), record.key().as_ref()],
+        bump
+    )]
+    pub consent: Account<'info, ConsentAccount>,
+    /// The record consented to, which must be the patient's.
+    #[account(constraint = record.owner == patient.key() @ ErrorCode::Unauthorized)]
+    pub record: Account<'info, HealthDataAccount>,
+    /// CHECK: The provider receiving consent
+    pub provider: UncheckedAccount<'info>,
+    /// CHECK: The record's owner; the granter is checked against it
+    pub patient: UncheckedAccount<'info>,
+    /// The patient or their delegate; pays for the account.
+    #[account(mut)]
+    pub granter: Signer<'info>,
+    /// Required when the granter is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), granter.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    /// Optional audit trail of the patient, with `audit_entry`.
+    #[account(
+        init_if_needed,
+        payer = granter,
+        space = AuditTrail::SPACE,
+        seeds = [b"audit_trail", patient.key().as_ref()],
+        bump
+    )]
+    pub audit_trail: Option<Account<'info, AuditTrail>>,
+    /// The trail's next entry.
+    #[account(
+        init,
+        payer = granter,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit_entry", patient.key().as_ref(), &next_audit_sequence(&audit_trail).to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Option<Account<'info, AuditEntry>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeConsent<'info> {
+    #[account(mut, close = patient, has_one = patient @ ErrorCode::Unauthorized)]
+    pub consent: Account<'info, ConsentAccount>,
+    /// CHECK: Matched against the consent; receives the account's rent
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// The patient or their delegate.
+    pub revoker: Signer<'info>,
+    /// Required when the revoker is a delegate.
+    #[account(seeds = [b"delegate", patient.key().as_ref(), revoker.key().as_ref()], bump)]
+    pub delegate_account: Option<Account<'info, Delegate>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct GrantResearchConsent<'info> {
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = ResearchConsent::SPACE,
+        seeds = [b"research_consent", patient.key().as_ref()],
+        bump
+    )]
+    pub research_consent: Account<'info, ResearchConsent>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeResearchConsent<'info> {
+    #[account(
+        mut,
+        close = patient,
+        seeds = [b"research_consent", patient.key().as_ref()],
+        bump
+    )]
+    pub research_consent: Account<'info, ResearchConsent>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RequestReencryption<'info> {
+    #[account(has_one = provider @ ErrorCode::Unauthorized)]
+    pub consent: Account<'info, ConsentAccount>,
+    /// The record consented to.
+    #[account(address = consent.record @ ErrorCode::InsufficientConsent)]
+    pub record: Account<'info, HealthDataAccount>,
+    /// The provider's registered encryption key, which the record key is
+    /// wrapped to.
+    #[account(seeds = [b"encryption_key", provider.key().as_ref()], bump)]
+    pub provider_key: Option<Account<'info, EncryptionKey>>,
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ReencryptionRequest::SPACE,
+        seeds = [b"reencryption", consent.key().as_ref()],
+        bump
+    )]
+    pub reencryption_request: Account<'info, ReencryptionRequest>,
+    /// The consented provider; pays for the request.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillReencryption<'info> {
+    #[account(
+        mut,
+        has_one = patient @ ErrorCode::Unauthorized,
+        has_one = consent @ ErrorCode::InsufficientConsent
+    )]
+    pub reencryption_request: Account<'info, ReencryptionRequest>,
+    pub consent: Account<'info, ConsentAccount>,
+    /// The provider's current encryption key.
+    #[account(seeds = [b"encryption_key", reencryption_request.provider.as_ref()], bump)]
+    pub provider_key: Account<'info, EncryptionKey>,
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReencryptionRequest<'info> {
+    #[account(mut, close = provider, has_one = provider @ ErrorCode::Unauthorized)]
+    pub reencryption_request: Account<'info, ReencryptionRequest>,
+    /// Receives the request's rent.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetAccessPolicy<'info> {
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = AccessPolicy::SPACE,
+        seeds = [b"access_policy", patient.key().as_ref()],
+        bump
+    )]
+    pub access_policy: Account<'info, AccessPolicy>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// Optional hash chain of the patient's consent receipts.
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = ConsentLedger::SPACE,
+        seeds = [b"consent_ledger", patient.key().as_ref()],
+        bump
+    )]
+    pub consent_ledger: Option<Account<'info, ConsentLedger>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAccess<'info> {
+    /// CHECK: The patient whose records are requested
+    pub patient: UncheckedAccount<'info>,
+    pub requester: Signer<'info>,
+    #[account(seeds = [b"access_policy", patient.key().as_ref()], bump)]
+    pub access_policy: Option<Account<'info, AccessPolicy>>,
+    #[account(seeds = [b"grant", patient.key().as_ref(), requester.key().as_ref()], bump)]
+    pub grant: Option<Account<'info, AccessGrant>>,
+    /// The requester's provider registration, if any.
+    #[account(seeds = [b"provider", requester.key().as_ref()], bump)]
+    pub requester_provider: Option<Account<'info, ProviderAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckClaimAccess<'info> {
+    pub claim_account: Account<'info, ClaimAccount>,
+    #[account(seeds = [b"claim_grant", claim_account.key().as_ref()], bump)]
+    pub claim_grant: Account<'info, ClaimGrant>,
+    /// The record requested; checked against the claim.
+    pub record: Account<'info, HealthDataAccount>,
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestSecondOpinion<'info> {
+    #[account(has_one = patient @ ErrorCode::Unauthorized)]
+    pub claim_account: Account<'info, ClaimAccount>,
+    /// Pays the fee and the account's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    /// CHECK: The second provider, checked to be registered
+    pub consultant: UncheckedAccount<'info>,
+    #[account(seeds = [b"provider", consultant.key().as_ref()], bump)]
+    pub consultant_provider: Account<'info, ProviderAccount>,
+    #[account(
+        init,
+        payer = patient,
+        space = SecondOpinion::SPACE,
+        seeds = [b"second_opinion", claim_account.key().as_ref(), consultant.key().as_ref()],
+        bump
+    )]
+    pub second_opinion: Account<'info, SecondOpinion>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckSecondOpinionAccess<'info> {
+    pub second_opinion: Account<'info, SecondOpinion>,
+    /// The record requested; checked against the shared records.
+    pub record: Account<'info, HealthDataAccount>,
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32], pointer: RecordPointer)]
+pub struct SubmitSecondOpinion<'info> {
+    #[account(mut, has_one = consultant @ ErrorCode::Unauthorized)]
+    pub second_opinion: Account<'info, SecondOpinion>,
+    #[account(
+        init,
+        payer = consultant,
+        space = HealthDataAccount::space(&pointer.cid),
+        seeds = [b"health_data", data_hash.as_ref()],
+        bump
+    )]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    /// Pays for the opinion record and receives the fee.
+    #[account(mut)]
+    pub consultant: Signer<'info>,
+    /// The owner's registered encryption key; pass it when the payload is
+    /// encrypted to it, to record its version.
+    #[account(seeds = [b"encryption_key", second_opinion.patient.as_ref()], bump)]
+    pub owner_key: Option<Account<'info, EncryptionKey>>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSecondOpinion<'info> {
+    #[account(mut, close = patient, has_one = patient @ ErrorCode::Unauthorized)]
+    pub second_opinion: Account<'info, SecondOpinion>,
+    /// Receives the account's rent and any unpaid fee.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(team_id: u32)]
+pub struct SetCareTeam<'info> {
+    #[account(
+        init_if_needed,
+        payer = patient,
+        space = CareTeam::SPACE,
+        seeds = [b"care_team", patient.key().as_ref(), &team_id.to_le_bytes()],
+        bump
+    )]
+    pub care_team: Account<'info, CareTeam>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCareTeam<'info> {
+    #[account(mut, close = patient, has_one = patient @ ErrorCode::Unauthorized)]
+    pub care_team: Account<'info, CareTeam>,
+    /// Receives the account's rent.
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CheckCareTeamAccess<'info> {
+    pub care_team: Account<'info, CareTeam>,
+    /// The record requested; checked against the team and the claim.
+    pub record: Account<'info, HealthDataAccount>,
+    /// The patient's claim the record is attached to, if it is not
+    /// designated for the team.
+    pub claim_account: Option<Account<'info, ClaimAccount>>,
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32])]
+pub struct RequestEmergencyAccess<'info> {
+    #[account(seeds = [b"health_data", data_hash.as_ref()], bump)]
+    pub health_data_account: Account<'info, HealthDataAccount>,
+    /// Only registered providers can break the glass.
+    #[account(seeds = [b"provider", provider.key().as_ref()], bump)]
+    pub provider_account: Account<'info, ProviderAccount>,
+    #[account(
+        init,
+        payer = provider,
+        space = EmergencyAccess::SPACE,
+        seeds = [b"emergency_access", data_hash.as_ref(), provider.key().as_ref()],
+        bump
+    )]
+    pub emergency_access: Account<'info, EmergencyAccess>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VetoEmergencyAccess<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub emergency_access: Account<'info, EmergencyAccess>,
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CheckEmergencyAccess<'info> {
+    pub emergency_access: Account<'info, EmergencyAccess>,
+    /// The record requested; must be the one access was asked for.
+    #[account(seeds = [b"health_data", emergency_access.data_hash.as_ref()], bump)]
+    pub record: Account<'info, HealthDataAccount>,
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEmergencyAccess<'info> {
+    #[account(mut, close = provider, has_one = provider @ ErrorCode::Unauthorized)]
+    pub emergency_access: Account<'info, EmergencyAccess>,
+    /// Receives the account's rent.
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct LogAccess<'info> {
+    /// CHECK: The patient whose trail the entry is appended to
+    pub subject: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = actor,
+        space = AuditTrail::SPACE,
+        seeds = [b"audit_trail", subject.key().as_ref()],
+        bump
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    #[account(
+        init,
+        payer = actor,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit_entry", subject.key().as_ref(), &audit_trail.entry_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+    /// Recorded as the entry's actor; pays for it.
+    #[account(mut)]
+    pub actor: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubscribeToPatientData<'info> {
+    #[account(
+        init,
+        payer = subscriber,
+        space = Subscription::SPACE,
+        seeds = [b"subscription", patient.key().as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    /// CHECK: The patient whose records are subscribed to
+    pub patient: UncheckedAccount<'info>,
+    /// The patient's read grant to the subscriber.
+    #[account(seeds = [b"grant", patient.key().as_ref(), subscriber.key().as_ref()], bump)]
+    pub grant: Account<'info, AccessGrant>,
+    /// The subscriber; pays for the account and the deposit.
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SUBSCRIPTIONS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundSubscription<'info> {
+    #[account(mut, has_one = subscriber @ ErrorCode::Unauthorized)]
+    pub subscription: Account<'info, Subscription>,
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SUBSCRIPTIONS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSubscriptionEarnings<'info> {
+    #[account(mut, has_one = patient @ ErrorCode::Unauthorized)]
+    pub subscription: Account<'info, Subscription>,
+    #[account(mut)]
+    pub patient: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        has_one = patient @ ErrorCode::InvalidPatient,
+        has_one = subscriber @ ErrorCode::Unauthorized,
+        close = subscriber
+    )]
+    pub subscription: Account<'info, Subscription>,
+    /// CHECK: Receives the accrued balance, verified by subscription.patient
+    #[account(mut)]
+    pub patient: UncheckedAccount<'info>,
+    /// CHECK: Receives the rest of the deposit, verified by subscription.subscriber
+    #[account(mut)]
+    pub subscriber: UncheckedAccount<'info>,
+    /// The patient or the subscriber.
+    pub signer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: String)]
+pub struct CreateSubscriptionPlan<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = SubscriptionPlan::SPACE,
+        seeds = [b"subscription_plan", owner.key().as_ref(), plan_id.as_bytes()],
+        bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    /// The data owner; pays for the account and receives the subscriptions.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SUBSCRIPTIONS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    /// CHECK: The plan's owner, verified by subscription_plan.owner; receives the price
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = subscriber,
+        space = PlanSubscription::SPACE,
+        seeds = [b"plan_subscription", subscription_plan.key().as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, PlanSubscription>,
+    /// Pays the price and for the subscription.
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SUBSCRIPTIONS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewSubscription<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    /// CHECK: The plan's owner, verified by subscription_plan.owner; receives the price
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"plan_subscription", subscription_plan.key().as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, PlanSubscription>,
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_SUBSCRIPTIONS) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: String)]
+pub struct CreateAccessOffer<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = DataAccessOffer::SPACE,
+        seeds = [b"access_offer", owner.key().as_ref(), offer_id.as_bytes()],
+        bump
+    )]
+    pub access_offer: Account<'info, DataAccessOffer>,
+    /// The data owner; pays for the account and receives the sales.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_ACCESS_SALES) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAccessOffer<'info> {
+    #[account(mut, close = owner, has_one = owner @ ErrorCode::Unauthorized)]
+    pub access_offer: Account<'info, DataAccessOffer>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseAccess<'info> {
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized)]
+    pub access_offer: Account<'info, DataAccessOffer>,
+    /// CHECK: The offer's owner, verified by access_offer.owner; receives the price
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    /// The record on offer, whose age prices offers with tiers.
+    #[account(
+        constraint = record.owner == access_offer.owner @ ErrorCode::OfferRecordMismatch,
+        constraint = record.data_hash == access_offer.resource_hash @ ErrorCode::OfferRecordMismatch
+    )]
+    pub record: Option<Account<'info, HealthDataAccount>>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = AccessGrant::SPACE,
+        seeds = [b"access_purchase", access_offer.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, AccessGrant>,
+    /// Pays the price and for the grant.
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        seeds = [b"feature_flags"],
+        bump,
+        constraint = feature_flags.enabled(FEATURE_ACCESS_SALES) @ ErrorCode::FeatureDisabled
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateClaim<'info> {
+    /// CHECK: Decoded by hand because it may still be in an old layout
+    #[account(mut, owner = crate::ID)]
+    pub claim_account: UncheckedAccount<'info>,
+    /// The patient or provider on the claim; pays any extra rent.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeAccount<'info> {
+    /// CHECK: Decoded by hand because it may still be in an old layout
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    /// Pays any extra rent.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds = [b"config"], bump, constraint = !config.paused @ ErrorCode::ProgramPaused)]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "test-clock")]
+#[derive(Accounts)]
+pub struct WarpClock<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = clock::ClockOffset::SPACE,
+        seeds = [clock::CLOCK_OFFSET_SEED],
+        bump
+    )]
+    pub clock_offset: Account<'info, clock::ClockOffset>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A registered patient, at `["patient", authority]`.
+#[account]
+pub struct PatientAccount {
+    pub authority: Pubkey,
+    pub did: String,
+    pub account_version: u8,
+}
+
+impl PatientAccount {
+    /// Space allocated for a patient identified by `did`.
+    pub fn space(did: &str) -> usize {
+        8 + 32 + 4 + did.len() + 1 + 64
+    }
+
+    pub fn update(&mut self, did: String) -> Result<()> {
+        validate_did(&did)?;
+        self.did = did;
+        Ok(())
+    }
+}
+
+/// A registered provider, at `["provider", authority]`.
+#[account]
+pub struct ProviderAccount {
+    pub authority: Pubkey,
+    pub did: String,
+    pub name: String,
+    pub account_version: u8,
+}
+
+impl ProviderAccount {
+    /// Space allocated for a provider with `did` and `name`.
+    pub fn space(did: &str, name: &str) -> usize {
+        8 + 32 + 4 + did.len() + 4 + name.len() + 1 + 64
+    }
+
+    pub fn update(&mut self, did: String, name: String) -> Result<()> {
+        validate_did(&did)?;
+        validate_not_empty("name", &name, ErrorCode::NameEmpty)?;
+        validate_len("name", &name, MAX_NAME_LEN, ErrorCode::NameTooLong)?;
+        self.did = did;
+        self.name = name;
+        Ok(())
+    }
+}
+
+/// A registered insurer, at `["insurer", authority]`.
+#[account]
+pub struct InsurerAccount {
+    pub authority: Pubkey,
+    pub did: String,
+    pub name: String,
+    pub account_version: u8,
+}
+
+impl InsurerAccount {
+    /// Space allocated for an insurer with `did` and `name`.
+    pub fn space(did: &str, name: &str) -> usize {
+        8 + 32 + 4 + did.len() + 4 + name.len() + 1 + 64
+    }
+
+    pub fn update(&mut self, did: String, name: String) -> Result<()> {
+        validate_did(&did)?;
+        validate_not_empty("name", &name, ErrorCode::NameEmpty)?;
+        validate_len("name", &name, MAX_NAME_LEN, ErrorCode::NameTooLong)?;
+        self.did = did;
+        self.name = name;
+        Ok(())
+    }
+}
+
+/// Where a record's encrypted payload is stored.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageKind {
+    Ipfs,
+    Arweave,
+    ShadowDrive,
+    /// The payload is on-chain, in `cid`. Only records written before
+    /// payloads moved off-chain, and upgraded since, are inline.
+    Inline,
+}
+
+/// The off-chain location of a record's encrypted payload, as submitted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordPointer {
+    pub storage: StorageKind,
+    /// The payload's content identifier: an IPFS CID, an Arweave transaction
+    /// id or a Shadow Drive file URL.
+    pub cid: String,
+    /// SHA-256 of the encrypted payload.
+    pub commitment: [u8; 32],
+}
+
+/// A pointer to an encrypted health record stored off-chain, at
+/// `["health_data", data_hash]`, or at `["health_record", owner,
+/// record_index]` when appended to the owner's index.
+#[account]
+pub struct HealthDataAccount {
+    pub owner: Pubkey,
+    /// SHA-256 of the encrypted record.
+    pub data_hash: [u8; 32],
+    pub storage: StorageKind,
+    /// Where the payload is in `storage`; for `Inline` records, the
+    /// ciphertext itself.
+    pub cid: String,
+    /// SHA-256 of the payload, checked with `verify_commitment`.
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+    /// Who submitted the record. Records written before delegated submission
+    /// read the zeroed padding here, i.e. `Pubkey::default()`, meaning the owner.
+    pub author: Pubkey,
+    /// Hash of the record this one was derived from; zeroed for original
+    /// records.
+    pub derived_from: [u8; 32],
+    /// Version of the owner's `EncryptionKey` the payload is encrypted to; 0
+    /// when the owner's key was not passed or the record predates key
+    /// versions.
+    pub key_version: u32,
+    pub account_version: u8,
+}
+
+impl HealthDataAccount {
+    /// Space allocated for a record pointing at `cid`.
+    pub fn space(cid: &str) -> usize {
+        8 + 32 + 32 + 1 + 4 + cid.len() + 32 + 8 + 32 + 32 + 4 + 1 + 60
+    }
+
+    pub fn record(
+        &mut self,
+        owner: Pubkey,
+        author: Pubkey,
+        data_hash: [u8; 32],
+        pointer: RecordPointer,
+        owner_key: Option<&EncryptionKey>,
+        now: i64,
+    ) {
+        self.owner = owner;
+        self.data_hash = data_hash;
+        self.storage = pointer.storage;
+        self.cid = pointer.cid;
+        self.commitment = pointer.commitment;
+        self.timestamp = now;
+        self.author = author;
+        self.derived_from = [0; 32];
+        self.key_version = owner_key.map_or(0, |key| key.key_version);
+        self.account_version = Self::VERSION;
+    }
+
+    pub fn verify_commitment(&self, content_hash: &[u8; 32]) -> Result<()> {
+        if *content_hash != self.commitment {
+            msg!(
+                "Content hashes to {}; the record commits to {}",
+                hex(content_hash),
+                hex(&self.commitment)
+            );
+            return err!(ErrorCode::CommitmentMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// How many records have been appended for a patient, at
+/// `["health_data_index", owner]`. Appended records sit at
+/// `["health_record", owner, index]` for every index below `record_count`,
+/// with the index as 8 little-endian bytes. Records submitted under their
+/// hash are not counted.
+#[account]
+pub struct HealthDataIndex {
+    pub owner: Pubkey,
+    pub record_count: u64,
+    pub account_version: u8,
+}
+
+impl HealthDataIndex {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 32;
+
+    /// Takes `record_index`, which must be the next free index, for a record
+    /// of `owner`.
+    pub fn append(&mut self, owner: Pubkey, record_index: u64) -> Result<()> {
+        if record_index != self.record_count {
+            msg!(
+                "Record index {} given; the next index is {}",
+                record_index,
+                self.record_count
+            );
+            return err!(ErrorCode::RecordIndexMismatch);
+        }
+        self.owner = owner;
+        self.record_count = self
+            .record_count
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// A wearable or other device allowed to submit one category of readings
+/// for a patient, at `["device", patient, device]`.
+#[account]
+pub struct DeviceAccount {
+    pub patient: Pubkey,
+    /// The device's own signing key.
+    pub device: Pubkey,
+    /// Hash of the device's make, model and firmware, for auditing.
+    pub model_hash: [u8; 32],
+    /// The only category the device can submit.
+    pub category: DataCategory,
+    pub max_readings_per_day: u32,
+    /// UTC day number (days since the epoch) that `readings_today` counts.
+    pub day: i64,
+    pub readings_today: u32,
+    pub total_readings: u64,
+    pub registered_at: i64,
+    pub account_version: u8,
+}
+
+impl DeviceAccount {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 4 + 8 + 4 + 8 + 8 + 1 + 32;
+
+    pub fn register(
+        &mut self,
+        patient: Pubkey,
+        device: Pubkey,
+        model_hash: [u8; 32],
+        category: DataCategory,
+        max_readings_per_day: u32,
+        now: i64,
+    ) -> Result<()> {
+        if device == patient {
+            msg!("The patient's own wallet cannot be registered as a device");
+            return err!(ErrorCode::InvalidDevice);
+        }
+        validate_hash("model_hash", &model_hash)?;
+        if max_readings_per_day == 0 || max_readings_per_day > MAX_DEVICE_READINGS_PER_DAY {
+            msg!(
+                "Daily reading limit {} is outside 1..={}",
+                max_readings_per_day,
+                MAX_DEVICE_READINGS_PER_DAY
+            );
+            return err!(ErrorCode::InvalidDeviceRateLimit);
+        }
+
+        self.patient = patient;
+        self.device = device;
+        self.model_hash = model_hash;
+        self.category = category;
+        self.max_readings_per_day = max_readings_per_day;
+        self.day = now.div_euclid(24 * 60 * 60);
+        self.readings_today = 0;
+        self.total_readings = 0;
+        self.registered_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Counts one reading submitted at `now`, failing once the device has
+    /// used its limit for the UTC day.
+    pub fn record_reading(&mut self, now: i64) -> Result<()> {
+        let day = now.div_euclid(24 * 60 * 60);
+        if day != self.day {
+            self.day = day;
+            self.readings_today = 0;
+        }
+        if self.readings_today >= self.max_readings_per_day {
+            msg!(
+                "Device {} has submitted its {} readings for today",
+                self.device,
+                self.max_readings_per_day
+            );
+            return err!(ErrorCode::DeviceRateLimited);
+        }
+        self.readings_today += 1;
+        self.total_readings = self.total_readings.saturating_add(1);
+        Ok(())
+    }
+}
+
+/// What a device reports about one day of readings, in the device's own
+/// units.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadingSummary {
+    pub reading_count: u32,
+    pub min_value: i64,
+    pub max_value: i64,
+    /// Mean of the readings, rounded toward zero.
+    pub avg_value: i64,
+}
+
+impl ReadingSummary {
+    pub const SPACE: usize = 4 + 8 + 8 + 8;
+}
+
+/// One UTC day of a device's readings, condensed to a merkle root over the
+/// raw readings and their summary, at `["device_rollup", device_account, day]`.
+#[account]
+pub struct DeviceRollup {
+    pub device_account: Pubkey,
+    pub patient: Pubkey,
+    pub category: DataCategory,
+    /// UTC day number (days since the epoch) the readings were taken on.
+    pub day: i64,
+    pub readings_root: [u8; 32],
+    pub summary: ReadingSummary,
+    /// `digest` of the rollup, which claims attach and access offers sell in
+    /// place of the readings.
+    pub summary_hash: [u8; 32],
+    pub rolled_up_at: i64,
+    pub account_version: u8,
+}
+
+impl DeviceRollup {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 32 + ReadingSummary::SPACE + 32 + 8 + 1 + 32;
+
+    pub fn roll_up(
+        &mut self,
+        device_account_key: Pubkey,
+        device_account: &DeviceAccount,
+        day: i64,
+        readings_root: [u8; 32],
+        summary: ReadingSummary,
+        now: i64,
+    ) -> Result<()> {
+        if day >= now.div_euclid(24 * 60 * 60) {
+            msg!("Day {} has not ended yet", day);
+            return err!(ErrorCode::RollupDayOpen);
+        }
+        if day < device_account.registered_at.div_euclid(24 * 60 * 60) {
+            msg!("Day {} is before the device was registered", day);
+            return err!(ErrorCode::InvalidRollup);
+        }
+        validate_hash("readings_root", &readings_root)?;
+        if summary.reading_count == 0
+            || summary.reading_count as usize > 1 << disclosure::MAX_PROOF_LEN
+        {
+            msg!("Reading count {} is out of range", summary.reading_count);
+            return err!(ErrorCode::InvalidRollup);
+        }
+        if summary.min_value > summary.avg_value || summary.avg_value > summary.max_value {
+            msg!(
+                "Average {} is not between the minimum {} and maximum {}",
+                summary.avg_value,
+                summary.min_value,
+                summary.max_value
+            );
+            return err!(ErrorCode::InvalidRollup);
+        }
+
+        self.device_account = device_account_key;
+        self.patient = device_account.patient;
+        self.category = device_account.category;
+        self.day = day;
+        self.readings_root = readings_root;
+        self.summary = summary;
+        self.summary_hash = self.digest();
+        self.rolled_up_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// The hash the rollup is referenced by, over the device, day, root and
+    /// summary.
+    pub fn digest(&self) -> [u8; 32] {
+        hashv(&[
+            b"device_rollup",
+            self.device_account.as_ref(),
+            &self.day.to_le_bytes(),
+            &self.readings_root,
+            &self.summary.reading_count.to_le_bytes(),
+            &self.summary.min_value.to_le_bytes(),
+            &self.summary.max_value.to_le_bytes(),
+            &self.summary.avg_value.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Number of sibling hashes in a proof for one of the readings.
+    pub fn depth(&self) -> usize {
+        (self.summary.reading_count as usize)
+            .next_power_of_two()
+            .trailing_zeros() as usize
+    }
+
+    pub fn verify_reading(
+        &self,
+        timestamp: i64,
+        value: i64,
+        index: u32,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        if timestamp.div_euclid(24 * 60 * 60) != self.day
+            || value < self.summary.min_value
+            || value > self.summary.max_value
+        {
+            msg!(
+                "Reading {} at {} is outside day {} or the range {}..={}",
+                value,
+                timestamp,
+                self.day,
+                self.summary.min_value,
+                self.summary.max_value
+            );
+            return err!(ErrorCode::ReadingOutsideRollup);
+        }
+        if index >= self.summary.reading_count {
+            msg!(
+                "Reading index {} given; the rollup has {} readings",
+                index,
+                self.summary.reading_count
+            );
+            return err!(ErrorCode::InvalidFieldIndex);
+        }
+        if proof.len() != self.depth() {
+            msg!(
+                "Proof has {} hashes; expected {}",
+                proof.len(),
+                self.depth()
+            );
+            return err!(ErrorCode::InvalidMerkleProof);
+        }
+        let leaf = disclosure::reading_leaf(timestamp, value);
+        if disclosure::root_from_proof(leaf, index, proof) != self.readings_root {
+            msg!("Proof for reading {} does not match the rollup", index);
+            return err!(ErrorCode::InvalidMerkleProof);
+        }
+        Ok(())
+    }
+}
+
+/// Merkle root over a record's fields, at `["field_root", record]`.
+#[account]
+pub struct FieldCommitment {
+    pub record: Pubkey,
+    pub root: [u8; 32],
+    pub field_count: u32,
+    pub committer: Pubkey,
+    pub committed_at: i64,
+    pub account_version: u8,
+}
+
+impl FieldCommitment {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + 32 + 8 + 1 + 32;
+
+    /// Number of sibling hashes in a proof for this commitment.
+    pub fn depth(&self) -> usize {
+        (self.field_count as usize)
+            .next_power_of_two()
+            .trailing_zeros() as usize
+    }
+}
+
+/// The singleton at `["validator_registry"]` whose admin designates data
+/// validators.
+#[account]
+pub struct ValidatorRegistry {
+    pub admin: Pubkey,
+    pub validator_count: u32,
+    pub account_version: u8,
+}
+
+impl ValidatorRegistry {
+    pub const SPACE: usize = 8 + 32 + 4 + 1 + 32;
+}
+
+/// A party trusted to attest record quality, at `["data_validator", authority]`.
+#[account]
+pub struct DataValidator {
+    pub authority: Pubkey,
+    pub active: bool,
+    /// When the validator was last designated or suspended.
+    pub designated_at: i64,
+    pub attestation_count: u64,
+    pub account_version: u8,
+}
+
+impl DataValidator {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 8 + 1 + 32;
+}
+
+/// The singleton at `["registry_authority"]` whose authority credentials
+/// providers.
+#[account]
+pub struct RegistryAuthority {
+    pub authority: Pubkey,
+    /// Providers currently verified.
+    pub verified_count: u32,
+    pub account_version: u8,
+}
+
+impl RegistryAuthority {
+    pub const SPACE: usize = 8 + 32 + 4 + 1 + 32;
+}
+
+/// The credentialing authority's finding on a provider, at
+/// `["provider_credential", provider]`. Only verified providers can verify
+/// claims.
+#[account]
+pub struct ProviderCredential {
+    pub provider: Pubkey,
+    pub verified: bool,
+    /// When the provider was last verified.
+    pub verified_at: i64,
+    /// When the credential was last revoked; 0 if never.
+    pub revoked_at: i64,
+    pub account_version: u8,
+}
+
+impl ProviderCredential {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 8 + 1 + 32;
+
+    /// Marks `provider` as verified at `now`, returning whether it was not
+    /// verified before.
+    pub fn verify(&mut self, provider: Pubkey, now: i64) -> bool {
+        let newly_verified = !self.verified;
+        self.provider = provider;
+        self.verified = true;
+        self.verified_at = now;
+        self.account_version = Self::VERSION;
+        newly_verified
+    }
+
+    pub fn revoke(&mut self, now: i64) -> Result<()> {
+        if !self.verified {
+            msg!("Provider {} is not verified", self.provider);
+            return err!(ErrorCode::ProviderNotVerified);
+        }
+        self.verified = false;
+        self.revoked_at = now;
+        Ok(())
+    }
+}
+
+/// A validator's finding that a record conforms to a FHIR profile, at
+/// `["quality", record, validator]`.
+#[account]
+pub struct QualityAttestation {
+    pub record: Pubkey,
+    pub validator: Pubkey,
+    /// Hash of the FHIR profile (StructureDefinition) the record was checked against.
+    pub profile_hash: [u8; 32],
+    /// Out of `MAX_QUALITY_SCORE`.
+    pub score: u8,
+    pub attested_at: i64,
+    pub account_version: u8,
+}
+
+impl QualityAttestation {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 8 + 1 + 32;
+
+    /// Whether the attestation is against `profile_hash` with at least
+    /// `min_score`, for filtering records by attested quality.
+    pub fn meets(&self, profile_hash: &[u8; 32], min_score: u8) -> bool {
+        self.profile_hash == *profile_hash && self.score >= min_score
+    }
+}
+
+/// Access a patient has granted to another party, at
+/// `["grant", patient, grantee]`.
+#[account]
+pub struct AccessGrant {
+    pub patient: Pubkey,
+    pub grantee: Pubkey,
+    /// Bitwise OR of the `GRANT_SCOPE_*` flags.
+    pub scope: u8,
+    pub granted_at: i64,
+    /// Unix time the grant lapses; 0 for no expiry.
+    pub expires_at: i64,
+    pub revoked: bool,
+    /// Grants made before lawful bases were recorded read `Consent` here.
+    pub lawful_basis: LawfulBasis,
+    /// The grant this one was delegated from; `Pubkey::default()` for grants
+    /// made by the patient.
+    pub parent: Pubkey,
+    /// Delegation links between the patient's grant and this one.
+    pub depth: u8,
+    /// Unrevoked grants delegated from this one.
+    pub delegations: u8,
+    pub account_version: u8,
+}
+
+impl AccessGrant {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 1 + 1 + 32 + 1 + 1 + 1 + 31;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.revoked && (self.expires_at == 0 || now < self.expires_at)
+    }
+
+    /// Whether the grant is active and covers every bit of `scope`.
+    pub fn allows(&self, scope: u8, now: i64) -> bool {
+        self.is_active(now) && self.scope & scope == scope
+    }
+
+    /// Adds `access_secs` of purchased read access at `now`: from the current
+    /// expiry while the grant is active, otherwise as a new term from `now`.
+    pub fn extend_purchase(&mut self, access_secs: i64, now: i64) -> Result<()> {
+        // A new grant reads as active with no expiry until it is filled in
+        let renewing = self.grantee != Pubkey::default() && self.is_active(now);
+        let start = if renewing {
+            self.expires_at
+        } else {
+            self.granted_at = now;
+            now
+        };
+        self.expires_at = start
+            .checked_add(access_secs)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.scope = GRANT_SCOPE_READ;
+        self.revoked = false;
+        self.lawful_basis = LawfulBasis::Contract;
+        Ok(())
+    }
+
+    /// Fails when the grant is an unrevoked delegation, which only the chain
+    /// it belongs to may replace.
+    pub fn expect_replaceable(&self) -> Result<()> {
+        if self.parent != Pubkey::default() && !self.revoked {
+            msg!(
+                "The grantee holds a grant delegated from {}; revoke it first",
+                self.parent
+            );
+            return err!(ErrorCode::GrantAlreadyHeld);
+        }
+        Ok(())
+    }
+
+    /// Makes this grant, at `address`, the parent of `grant` to `grantee`
+    /// with `scope` until `expires_at`, where 0 keeps this grant's expiry.
+    /// The sub-grant can be no broader and last no longer than this one, and
+    /// can itself delegate only while above `MAX_DELEGATION_DEPTH`.
+    pub fn delegate(
+        &mut self,
+        address: Pubkey,
+        grant: &mut AccessGrant,
+        grantee: Pubkey,
+        scope: u8,
+        expires_at: i64,
+        now: i64,
+    ) -> Result<()> {
+        if self.account_version != Self::VERSION {
+            msg!("Upgrade the grant before delegating from it");
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        if !self.is_active(now) {
+            msg!(
+                "Grant revoked: {}, expires at {}; now is {}",
+                self.revoked,
+                self.expires_at,
+                now
+            );
+            return err!(ErrorCode::GrantInactive);
+        }
+        if self.scope & GRANT_SCOPE_DELEGATE == 0 {
+            msg!("Grant scope {:#05b} lacks delegation", self.scope);
+            return err!(ErrorCode::GrantNotDelegable);
+        }
+        let depth = self.depth + 1;
+        if depth > MAX_DELEGATION_DEPTH {
+            msg!("The grant is already {} delegations deep", self.depth);
+            return err!(ErrorCode::DelegationTooDeep);
+        }
+        let delegable = if depth < MAX_DELEGATION_DEPTH {
+            GRANT_SCOPE_DELEGATE
+        } else {
+            0
+        };
+        let allowed = self.scope & (GRANT_SCOPE_ALL | delegable);
+        if scope & GRANT_SCOPE_ALL == 0 || scope & !allowed != 0 {
+            msg!(
+                "Scope {:#05b} is empty or not within {:#05b}",
+                scope,
+                allowed
+            );
+            return err!(ErrorCode::InvalidDelegationScope);
+        }
+        let expires_at = if expires_at == 0 {
+            self.expires_at
+        } else {
+            expires_at
+        };
+        if (expires_at != 0 && expires_at <= now)
+            || (self.expires_at != 0 && (expires_at == 0 || expires_at > self.expires_at))
+        {
+            msg!(
+                "Expiry {} is not after now ({}) and within the grant's ({})",
+                expires_at,
+                now,
+                self.expires_at
+            );
+            return err!(ErrorCode::InvalidGrantExpiry);
+        }
+
+        // A sub-grant already delegated from here holds one of `delegations`
+        let counted = grant.parent == address && !grant.revoked;
+        if grant.grantee != Pubkey::default() && grant.parent != address && !grant.revoked {
+            msg!("{} already holds an unrevoked grant", grantee);
+            return err!(ErrorCode::GrantAlreadyHeld);
+        }
+        if !counted {
+            self.delegations = self
+                .delegations
+                .checked_add(1)
+                .ok_or(ErrorCode::AmountOverflow)?;
+        }
+        grant.patient = self.patient;
+        grant.grantee = grantee;
+        grant.scope = scope;
+        grant.granted_at = now;
+        grant.expires_at = expires_at;
+        grant.revoked = false;
+        grant.lawful_basis = self.lawful_basis;
+        grant.parent = address;
+        grant.depth = depth;
+        grant.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Revokes this grant and `descendants`, each listed after its own parent
+    /// and keyed by address, releasing this grant's slot in `parent` when it
+    /// was delegated. Fails unless every unrevoked sub-grant is covered.
+    pub fn revoke_chain(
+        &mut self,
+        address: Pubkey,
+        parent: Option<&mut AccessGrant>,
+        descendants: &mut [(Pubkey, AccessGrant)],
+    ) -> Result<()> {
+        if self.parent != Pubkey::default() && !self.revoked {
+            let Some(parent) = parent else {
+                msg!("Pass the grant {} was delegated from", address);
+                return err!(ErrorCode::GrantChainMismatch);
+            };
+            parent.delegations = parent.delegations.saturating_sub(1);
+        }
+        self.revoked = true;
+
+        for i in 0..descendants.len() {
+            let (done, rest) = descendants.split_at_mut(i);
+            let (key, grant) = &mut rest[0];
+            let parent = if grant.parent == address {
+                &mut *self
+            } else if let Some((_, parent)) = done
+                .iter_mut()
+                .find(|(revoked, _)| *revoked == grant.parent)
+            {
+                parent
+            } else {
+                msg!("Grant {} is not delegated from one revoked before it", key);
+                return err!(ErrorCode::GrantChainMismatch);
+            };
+            if !grant.revoked {
+                parent.delegations = parent.delegations.saturating_sub(1);
+                grant.revoked = true;
+            }
+        }
+
+        let outstanding = self.delegations as usize
+            + descendants
+                .iter()
+                .map(|(_, grant)| grant.delegations as usize)
+                .sum::<usize>();
+        if outstanding != 0 {
+            msg!("{} delegated grants were not passed", outstanding);
+            return err!(ErrorCode::DelegationsNotRevoked);
+        }
+        Ok(())
+    }
+}
+
+/// A patient's consent to a provider using one of their records, at
+/// `["consent", patient, provider, record]` where `record` is the record's
+/// `["health_data", data_hash]` address. Revoking closes the account.
+#[account]
+pub struct ConsentAccount {
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub record: Pubkey,
+    pub data_hash: [u8; 32],
+    /// Bitwise OR of the `CONSENT_SCOPE_*` flags.
+    pub scope: u8,
+    pub granted_at: i64,
+    /// Unix time the consent lapses; 0 for no expiry.
+    pub expires_at: i64,
+    pub account_version: u8,
+}
+
+impl ConsentAccount {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 1 + 32;
+
+    /// Replaces the consent's scope and expiry as of `now`.
+    pub fn grant(&mut self, scope: u8, expires_at: i64, now: i64) -> Result<()> {
+        if scope == 0 || scope & !CONSENT_SCOPE_ALL != 0 {
+            msg!("Consent scope {:#05b} is empty or has unknown bits", scope);
+            return err!(ErrorCode::InvalidConsentScope);
+        }
+        if expires_at != 0 && expires_at <= now {
+            msg!("Consent expiry {} is not after now ({})", expires_at, now);
+            return err!(ErrorCode::InvalidConsentExpiry);
+        }
+        self.scope = scope;
+        self.granted_at = now;
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+
+    /// Whether the consent is active and covers every bit of `scope`.
+    pub fn allows(&self, scope: u8, now: i64) -> bool {
+        self.is_active(now) && self.scope & scope == scope
+    }
+}
+
+/// A patient's consent to their records in some categories being used in
+/// research, at `["research_consent", patient]`. Revoking closes the account.
+#[account]
+pub struct ResearchConsent {
+    pub patient: Pubkey,
+    /// Mask of `DataCategory::bit` values consented to.
+    pub categories: u8,
+    pub granted_at: i64,
+    /// Unix time the consent lapses; 0 for no expiry.
+    pub expires_at: i64,
+    pub account_version: u8,
+}
+
+impl ResearchConsent {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 8 + 1 + 32;
+
+    /// Replaces the consent's categories and expiry as of `now`.
+    pub fn grant(&mut self, categories: u8, expires_at: i64, now: i64) -> Result<()> {
+        if categories == 0 {
+            msg!("Research consent covers no categories");
+            return err!(ErrorCode::InvalidConsentScope);
+        }
+        if expires_at != 0 && expires_at <= now {
+            msg!("Consent expiry {} is not after now ({})", expires_at, now);
+            return err!(ErrorCode::InvalidConsentExpiry);
+        }
+        self.categories = categories;
+        self.granted_at = now;
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+
+    /// Whether the consent is active and covers every category in
+    /// `categories`.
+    pub fn covers(&self, categories: u8, now: i64) -> bool {
+        self.is_active(now) && self.categories & categories == categories
+    }
+}
+
+/// A provider's request for the key of a record they have consent to read,
+/// wrapped to their encryption key, at `["reencryption", consent]`. The
+/// patient fulfills it with the wrapped key; plaintext keys never go on-chain.
+#[account]
+pub struct ReencryptionRequest {
+    pub consent: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub record: Pubkey,
+    /// The patient's key version the record was stored under.
+    pub record_key_version: u32,
+    /// The provider's encryption key at the time of the request.
+    pub recipient_key: [u8; 32],
+    pub recipient_key_version: u32,
+    /// The record key wrapped to `recipient_key`; empty until fulfilled.
+    pub wrapped_key: Vec<u8>,
+    pub requested_at: i64,
+    /// 0 until fulfilled.
+    pub fulfilled_at: i64,
+    pub account_version: u8,
+}
+
+impl ReencryptionRequest {
+    pub const SPACE: usize =
+        8 + 32 + 32 + 32 + 32 + 4 + 32 + 4 + 4 + MAX_KEY_ENVELOPE_LEN + 8 + 8 + 1 + 32;
+
+    /// Starts a request for `record` under the consent at `consent_key`,
+    /// wrapped to `provider_key`.
+    pub fn request(
+        &mut self,
+        consent_key: Pubkey,
+        consent: &ConsentAccount,
+        record: &HealthDataAccount,
+        provider_key: &EncryptionKey,
+        now: i64,
+    ) -> Result<()> {
+        expect_read_consent(consent, now)?;
+        self.consent = consent_key;
+        self.patient = consent.patient;
+        self.provider = consent.provider;
+        self.record = consent.record;
+        self.record_key_version = record.key_version;
+        self.recipient_key = provider_key.x25519_key;
+        self.recipient_key_version = provider_key.key_version;
+        self.wrapped_key = Vec::new();
+        self.requested_at = now;
+        self.fulfilled_at = 0;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn is_fulfilled(&self) -> bool {
+        self.fulfilled_at != 0
+    }
+
+    /// Stores `wrapped_key` while the consent still covers reading and the
+    /// provider still holds the key the request was made for.
+    pub fn fulfill(
+        &mut self,
+        wrapped_key: Vec<u8>,
+        consent: &ConsentAccount,
+        provider_key: &EncryptionKey,
+        now: i64,
+    ) -> Result<()> {
+        if self.is_fulfilled() {
+            msg!("The request was fulfilled at {}", self.fulfilled_at);
+            return err!(ErrorCode::ReencryptionAlreadyFulfilled);
+        }
+        expect_read_consent(consent, now)?;
+        if provider_key.x25519_key != self.recipient_key {
+            msg!(
+                "The request is for key version {}; the provider is at {}",
+                self.recipient_key_version,
+                provider_key.key_version
+            );
+            return err!(ErrorCode::ReencryptionKeyStale);
+        }
+        if wrapped_key.is_empty() {
+            msg!("The wrapped key is empty");
+            return err!(ErrorCode::InvalidWrappedKey);
+        }
+        validate_count(
+            "wrapped_key",
+            wrapped_key.len(),
+            MAX_KEY_ENVELOPE_LEN,
+            ErrorCode::KeyEnvelopeTooLong,
+        )?;
+        self.wrapped_key = wrapped_key;
+        self.fulfilled_at = now;
+        Ok(())
+    }
+}
+
+fn expect_read_consent(consent: &ConsentAccount, now: i64) -> Result<()> {
+    if !consent.allows(CONSENT_SCOPE_READ, now) {
+        msg!(
+            "Consent scope {:#05b}, expires at {}; now is {}",
+            consent.scope,
+            consent.expires_at,
+            now
+        );
+        return err!(ErrorCode::InsufficientConsent);
+    }
+    Ok(())
+}
+
+/// A party's X25519 public key for encrypting records and wrapping record
+/// keys to, at `["encryption_key", owner]`.
+#[account]
+pub struct EncryptionKey {
+    pub owner: Pubkey,
+    pub x25519_key: [u8; 32],
+    pub updated_at: i64,
+    /// 1 for the first key registered, counting up with each rotation.
+    pub key_version: u32,
+    pub account_version: u8,
+}
+
+impl EncryptionKey {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 4 + 1 + 28;
+
+    pub fn register(&mut self, owner: Pubkey, x25519_key: [u8; 32], now: i64) -> Result<()> {
+        validate_encryption_key(&x25519_key)?;
+        self.owner = owner;
+        self.x25519_key = x25519_key;
+        self.updated_at = now;
+        self.key_version = 1;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn rotate(&mut self, x25519_key: [u8; 32], now: i64) -> Result<()> {
+        validate_encryption_key(&x25519_key)?;
+        if x25519_key == self.x25519_key {
+            msg!("Key version {} is already this key", self.key_version);
+            return err!(ErrorCode::EncryptionKeyUnchanged);
+        }
+        self.x25519_key = x25519_key;
+        self.updated_at = now;
+        self.key_version += 1;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// A guardian's standing to act for a dependent patient, at
+/// `["dependent", guardian, dependent]`.
+#[account]
+pub struct DependentLink {
+    pub guardian: Pubkey,
+    pub dependent: Pubkey,
+    /// Bitwise OR of the `GUARDIAN_*` flags.
+    pub permissions: u8,
+    pub linked_at: i64,
+    /// Unix time the dependent comes of age and the link lapses.
+    pub expires_at: i64,
+    pub account_version: u8,
+}
+
+impl DependentLink {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 1 + 32;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+
+    /// Whether the link is active and covers every bit of `permissions`.
+    pub fn allows(&self, permissions: u8, now: i64) -> bool {
+        self.is_active(now) && self.permissions & permissions == permissions
+    }
+}
+
+/// A delegate's power of attorney over a patient's account, at
+/// `["delegate", patient, delegate]`.
+#[account]
+pub struct Delegate {
+    pub patient: Pubkey,
+    pub delegate: Pubkey,
+    /// Bitwise OR of the `DELEGATE_*` flags.
+    pub permissions: u8,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl Delegate {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1 + 32;
+
+    pub fn set(
+        &mut self,
+        patient: Pubkey,
+        delegate: Pubkey,
+        permissions: u8,
+        now: i64,
+    ) -> Result<()> {
+        if delegate == patient {
+            msg!("A patient cannot be their own delegate");
+            return err!(ErrorCode::InvalidDelegate);
+        }
+        if permissions == 0 || permissions & !DELEGATE_PERMISSIONS_ALL != 0 {
+            msg!(
+                "Delegate permissions {:#05b} are empty or have unknown bits",
+                permissions
+            );
+            return err!(ErrorCode::InvalidDelegatePermissions);
+        }
+        self.patient = patient;
+        self.delegate = delegate;
+        self.permissions = permissions;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Fails unless the delegate holds every bit of `permissions`.
+    pub fn authorize(&self, permissions: u8) -> Result<()> {
+        if self.permissions & permissions != permissions {
+            msg!(
+                "Delegate permissions {:#05b} lack {:#05b}",
+                self.permissions,
+                permissions
+            );
+            return err!(ErrorCode::InsufficientDelegatePermissions);
+        }
+        Ok(())
+    }
+}
+
+/// The record key wrapped to a grantee, at `["key_envelope", grant]`.
+#[account]
+pub struct KeyEnvelope {
+    pub grant: Pubkey,
+    /// The grantee's encryption key at the time the envelope was made.
+    pub recipient_key: [u8; 32],
+    pub envelope: Vec<u8>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl KeyEnvelope {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + MAX_KEY_ENVELOPE_LEN + 8 + 1 + 32;
+
+    /// Whether the envelope is still wrapped to the grantee's current key. A
+    /// grantee who has rotated keys needs the envelope re-issued.
+    pub fn is_current(&self, grantee_key: &EncryptionKey) -> bool {
+        self.recipient_key == grantee_key.x25519_key
+    }
+}
+
+/// Lawful basis for processing, after GDPR Art. 6(1) (a)-(f).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LawfulBasis {
+    Consent,
+    Contract,
+    LegalObligation,
+    VitalInterests,
+    PublicTask,
+    LegitimateInterests,
+}
+
+/// Lamports a clinic or partner set aside to pay patients' rent, at
+/// `["sponsor_vault", sponsor]`. The account holds the lamports.
+#[account]
+pub struct SponsorVault {
+    pub sponsor: Pubkey,
+    pub total_funded: u64,
+    /// Rent paid out for sponsored accounts.
+    pub total_spent: u64,
+    pub sponsored_accounts: u32,
+    pub account_version: u8,
+}
+
+impl SponsorVault {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 4 + 1 + 32;
+}
+
+/// Lamports a provider locked to pay one claim, at `["claim_escrow", claim]`.
+/// The account holds the lamports.
+#[account]
+pub struct ClaimEscrow {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    /// Locked for the patient, on top of the account's rent.
+    pub amount: u64,
+    pub funded_at: i64,
+    /// Third key of a split-custody escrow, or `Pubkey::default()` for a
+    /// standard one.
+    pub arbiter: Pubkey,
+    pub account_version: u8,
+}
+
+impl ClaimEscrow {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 32 + 1 + 32;
+
+    /// Locks `claim`'s escrow amount at `now` for the claim at `claim_key`,
+    /// returning the lamports to transfer in.
+    pub fn fund(
+        &mut self,
+        claim_key: Pubkey,
+        claim: &ClaimAccount,
+        arbiter: Pubkey,
+        now: i64,
+    ) -> Result<u64> {
+        if arbiter == claim.patient || arbiter == claim.provider {
+            msg!("The arbiter {} is a party to the claim", arbiter);
+            return err!(ErrorCode::InvalidArbiter);
+        }
+        let amount = claim.escrow_amount(now)?;
+        self.claim = claim_key;
+        self.provider = claim.provider;
+        self.amount = amount;
+        self.funded_at = now;
+        self.arbiter = arbiter;
+        self.account_version = Self::VERSION;
+        Ok(amount)
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.arbiter != Pubkey::default()
+    }
+
+    /// Settles `claim` and returns what the escrow owes the patient. A
+    /// payable claim is paid in full, with interest only up to when the
+    /// escrow was funded, since the provider was not late after that. A
+    /// rejected, declined, expired or already paid claim is owed nothing.
+    pub fn settle(&self, claim: &mut ClaimAccount) -> Result<u64> {
+        if self.account_version != Self::VERSION {
+            msg!("Upgrade the escrow before settling it");
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        match claim.status {
+            ClaimStatus::Verified | ClaimStatus::PartiallyPaid => {
+                if claim.outstanding_principal()? == 0 {
+                    return Ok(0);
+                }
+                let owed = claim.settle_payment(self.funded_at.max(claim.verified_at))?;
+                if owed > self.amount {
+                    msg!("The claim owes {}; the escrow holds {}", owed, self.amount);
+                    return err!(ErrorCode::InsufficientEscrow);
+                }
+                Ok(owed)
+            }
+            ClaimStatus::Rejected
+            | ClaimStatus::Declined
+            | ClaimStatus::Expired
+            | ClaimStatus::Paid => Ok(0),
+            status => {
+                msg!("The claim is still {:?}", status);
+                err!(ErrorCode::ClaimNotVerified)
+            }
+        }
+    }
+
+    /// Checks that `signers` may release a split escrow that `owed` lamports
+    /// of go to the patient. Whoever receives the release must sign, with
+    /// the other party or the arbiter. A standard escrow needs no signature.
+    pub fn authorize_release(
+        &self,
+        claim: &ClaimAccount,
+        owed: u64,
+        signers: &[Pubkey],
+    ) -> Result<()> {
+        if !self.is_split() {
+            return Ok(());
+        }
+        let (recipient, counterparty) = if owed > 0 {
+            (claim.patient, self.provider)
+        } else {
+            (self.provider, claim.patient)
+        };
+        if !signers.contains(&recipient)
+            || !(signers.contains(&counterparty) || signers.contains(&self.arbiter))
+        {
+            msg!(
+                "Releasing to {} needs their signature and {}'s or the arbiter's",
+                recipient,
+                counterparty
+            );
+            return err!(ErrorCode::EscrowReleaseUnauthorized);
+        }
+        Ok(())
+    }
+}
+
+/// How long lamport payments made through a `PaymentHold` can be clawed
+/// back, at `["clawback_config"]`.
+#[account]
+pub struct ClawbackConfig {
+    /// Seconds a held payment waits before it can be released; 0 turns
+    /// holds off.
+    pub window_secs: i64,
+    /// Can claw back a held payment alone; `Pubkey::default()` for none.
+    pub arbiter: Pubkey,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl ClawbackConfig {
+    pub const SPACE: usize = 8 + 8 + 32 + 8 + 1 + 32;
+
+    pub fn set(&mut self, window_secs: i64, arbiter: Pubkey, now: i64) -> Result<()> {
+        if !(0..=MAX_CLAWBACK_WINDOW_SECS).contains(&window_secs) {
+            msg!(
+                "Clawback window of {}s is outside 0 to {}s",
+                window_secs,
+                MAX_CLAWBACK_WINDOW_SECS
+            );
+            return err!(ErrorCode::InvalidClawbackWindow);
+        }
+        self.window_secs = window_secs;
+        self.arbiter = arbiter;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// A lamport claim payment held for the clawback window before it reaches
+/// the patient, at `["payment_hold", claim]`. The account holds the lamports.
+#[account]
+pub struct PaymentHold {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub patient: Pubkey,
+    /// The clawback arbiter when the payment was made.
+    pub arbiter: Pubkey,
+    /// Held for the patient, on top of the account's rent: the payment less
+    /// the protocol fee.
+    pub amount: u64,
+    /// The principal and interest the payment settled on the claim.
+    pub principal: u64,
+    pub interest: u64,
+    /// The settlement period the payment was counted in.
+    pub period: u32,
+    pub held_at: i64,
+    pub release_at: i64,
+    pub account_version: u8,
+}
+
+impl PaymentHold {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 4 + 8 + 8 + 1 + 32;
+
+    /// Holds `amount` of a payment of `claim` that settled `principal` and
+    /// `interest`, under the window in `config`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn hold(
+        &mut self,
+        claim_key: Pubkey,
+        claim: &ClaimAccount,
+        config: &ClawbackConfig,
+        amount: u64,
+        principal: u64,
+        interest: u64,
+        period: u32,
+        now: i64,
+    ) -> Result<()> {
+        if config.window_secs == 0 {
+            msg!("Clawback holds are off");
+            return err!(ErrorCode::ClawbackDisabled);
+        }
+        self.claim = claim_key;
+        self.provider = claim.provider;
+        self.patient = claim.patient;
+        self.arbiter = config.arbiter;
+        self.amount = amount;
+        self.principal = principal;
+        self.interest = interest;
+        self.period = period;
+        self.held_at = now;
+        self.release_at = now + config.window_secs;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn expect_releasable(&self, signer: Pubkey, now: i64) -> Result<()> {
+        if now < self.release_at && signer != self.provider {
+            msg!(
+                "The payment is held until {}; only the provider can release it sooner",
+                self.release_at
+            );
+            return err!(ErrorCode::PaymentStillHeld);
+        }
+        Ok(())
+    }
+
+    /// Checks that `signers` may claw the payment back at `now`: the
+    /// arbiter alone, or the provider and patient together.
+    pub fn authorize_clawback(&self, signers: &[Pubkey], now: i64) -> Result<()> {
+        if now >= self.release_at {
+            msg!("The clawback window closed at {}", self.release_at);
+            return err!(ErrorCode::ClawbackWindowElapsed);
+        }
+        let by_arbiter = self.arbiter != Pubkey::default() && signers.contains(&self.arbiter);
+        let by_consent = signers.contains(&self.provider) && signers.contains(&self.patient);
+        if !by_arbiter && !by_consent {
+            msg!("A clawback needs the provider and the patient, or the arbiter");
+            return err!(ErrorCode::ClawbackUnauthorized);
+        }
+        Ok(())
+    }
+}
+
+/// Deployment-wide settings, at `["config"]`. Every instruction that writes
+/// takes it and fails while `paused`, so an incident can be contained
+/// without a redeploy.
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub paused: bool,
+    /// Protocol fee on claim payments, in basis points, at most
+    /// `MAX_PROTOCOL_FEE_BPS`.
+    pub fee_bps: u16,
+    pub updated_at: i64,
+    /// How long a new claim may stay undecided before anyone can expire it;
+    /// 0 lets claims stay open indefinitely.
+    pub claim_ttl_secs: i64,
+    pub account_version: u8,
+}
+
+impl Config {
+    pub const SPACE: usize = 8 + 32 + 1 + 2 + 8 + 8 + 1 + 56;
+
+    pub fn initialize(&mut self, admin: Pubkey, fee_bps: u16, now: i64) -> Result<()> {
+        self.admin = admin;
+        self.paused = false;
+        self.claim_ttl_secs = 0;
+        self.account_version = Self::VERSION;
+        self.set_fee(fee_bps, now)
+    }
+
+    pub fn set_fee(&mut self, fee_bps: u16, now: i64) -> Result<()> {
+        if fee_bps > MAX_PROTOCOL_FEE_BPS {
+            msg!(
+                "Protocol fee {} bps is above the maximum of {}",
+                fee_bps,
+                MAX_PROTOCOL_FEE_BPS
+            );
+            return err!(ErrorCode::ProtocolFeeTooHigh);
+        }
+        self.fee_bps = fee_bps;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// The protocol fee on paying `amount` of a claim, rounded down.
+    pub fn fee_on(&self, amount: u64) -> Result<u64> {
+        Ok(Amount::lamports(amount)
+            .mul_bps(self.fee_bps.into(), Rounding::Down)?
+            .raw)
+    }
+
+    /// Divides a lamport payment of `amount` of a claim. The protocol fee
+    /// comes out of what the patient receives, and a payment co-signed by a
+    /// facilitator's signing key gives the facilitator its share of the fee.
+    pub fn split_payment(
+        &self,
+        amount: u64,
+        facilitator: Option<(&mut FacilitatorAccount, Pubkey)>,
+    ) -> Result<PaymentSplit> {
+        let protocol_fee = self.fee_on(amount)?;
+        let facilitator_share = match facilitator {
+            Some((facilitator, signer)) => facilitator.attribute(signer, protocol_fee)?,
+            None => 0,
+        };
+        Ok(PaymentSplit {
+            received: Amount::lamports(amount)
+                .checked_sub(Amount::lamports(protocol_fee))?
+                .raw,
+            treasury_fee: protocol_fee - facilitator_share,
+            facilitator_share,
+        })
+    }
+
+    /// Sets how long claims filed from now on stay open, checked against
+    /// `MIN_CLAIM_TTL_SECS` and `MAX_CLAIM_TTL_SECS`. 0 turns expiry off.
+    pub fn set_claim_ttl(&mut self, ttl_secs: i64, now: i64) -> Result<()> {
+        // A config from before claims expired has no room for the setting
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Config is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        if ttl_secs != 0 && !(MIN_CLAIM_TTL_SECS..=MAX_CLAIM_TTL_SECS).contains(&ttl_secs) {
+            msg!(
+                "Claim lifetime {}s is outside {}s to {}s",
+                ttl_secs,
+                MIN_CLAIM_TTL_SECS,
+                MAX_CLAIM_TTL_SECS
+            );
+            return err!(ErrorCode::InvalidClaimTtl);
+        }
+        self.claim_ttl_secs = ttl_secs;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// When a claim filed at `now` expires, or 0 if claims do not expire.
+    pub fn claim_expiry(&self, now: i64) -> i64 {
+        if self.account_version != Self::VERSION || self.claim_ttl_secs == 0 {
+            return 0;
+        }
+        now.saturating_add(self.claim_ttl_secs)
+    }
+
+    pub fn set_paused(&mut self, paused: bool, now: i64) {
+        self.paused = paused;
+        self.updated_at = now;
+    }
+
+    pub fn transfer_admin(&mut self, new_admin: Pubkey, now: i64) {
+        self.admin = new_admin;
+        self.updated_at = now;
+    }
+}
+
+/// How a lamport claim payment is divided, from `Config::split_payment`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaymentSplit {
+    /// Paid to the patient: the payment less the protocol fee.
+    pub received: u64,
+    /// The protocol fee less the facilitator's share and, once `rebate_fee`
+    /// has run, the provider's volume rebate.
+    pub treasury_fee: u64,
+    pub facilitator_share: u64,
+}
+
+/// Rebates on the protocol fee for providers' lamport claim payments, at
+/// `["fee_rebates"]`, set by the config admin.
+#[account]
+pub struct FeeRebates {
+    /// Ascending in `min_volume` and `rebate_bps`.
+    pub tiers: Vec<RebateTier>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl FeeRebates {
+    pub const SPACE: usize = 8 + 4 + MAX_REBATE_TIERS * RebateTier::SPACE + 8 + 1 + 32;
+
+    pub fn set(&mut self, tiers: Vec<RebateTier>, now: i64) -> Result<()> {
+        if tiers.len() > MAX_REBATE_TIERS {
+            msg!(
+                "{} rebate tiers; at most {} are allowed",
+                tiers.len(),
+                MAX_REBATE_TIERS
+            );
+            return err!(ErrorCode::InvalidFeeRebates);
+        }
+        for (i, tier) in tiers.iter().enumerate() {
+            if tier.rebate_bps == 0 || u64::from(tier.rebate_bps) > amount::BPS_DENOMINATOR {
+                msg!("Tier {} rebates {} bps", i, tier.rebate_bps);
+                return err!(ErrorCode::InvalidFeeRebates);
+            }
+            if let Some(previous) = i.checked_sub(1).map(|j| &tiers[j]) {
+                if tier.min_volume <= previous.min_volume || tier.rebate_bps <= previous.rebate_bps
+                {
+                    msg!("Tier {} does not rise above tier {}", i, i - 1);
+                    return err!(ErrorCode::InvalidFeeRebates);
+                }
+            }
+        }
+        self.tiers = tiers;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// The rebate, in basis points of the treasury's fee, for a provider who
+    /// has been paid `volume` lamports so far in the period.
+    pub fn rebate_bps(&self, volume: u64) -> u16 {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+            .map_or(0, |tier| tier.rebate_bps)
+    }
+}
+
+/// The rebate for providers paid at least `min_volume` lamports in a period.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RebateTier {
+    pub min_volume: u64,
+    /// Share of the treasury's fee the provider keeps, in basis points.
+    pub rebate_bps: u16,
+}
+
+impl RebateTier {
+    pub const SPACE: usize = 8 + 2;
+}
+
+/// A provider's lamport claim payments in a settlement period, at
+/// `["provider_volume", provider, period]`.
+#[account]
+pub struct ProviderVolume {
+    pub provider: Pubkey,
+    pub period: u32,
+    /// Lamports of claims paid in the period.
+    pub volume: u64,
+    /// Protocol fee the provider kept as rebates in the period.
+    pub rebated: u64,
+    pub account_version: u8,
+}
+
+impl ProviderVolume {
+    pub const SPACE: usize = 8 + 32 + 4 + 8 + 8 + 1 + 32;
+
+    /// Counts a payment of `amount` whose treasury fee is `treasury_fee`,
+    /// returning the rebate on the fee at `rebate_bps`, rounded down.
+    pub fn record(&mut self, amount: u64, treasury_fee: u64, rebate_bps: u16) -> Result<u64> {
+        let rebate = Amount::lamports(treasury_fee)
+            .mul_bps(rebate_bps.into(), Rounding::Down)?
+            .raw;
+        self.volume = Amount::lamports(self.volume)
+            .checked_add(Amount::lamports(amount))?
+            .raw;
+        self.rebated = Amount::lamports(self.rebated)
+            .checked_add(Amount::lamports(rebate))?
+            .raw;
+        Ok(rebate)
+    }
+}
+
+/// Protocol fees collected from claim payments, at `["protocol_treasury"]`.
+/// The account holds the lamports.
+#[account]
+pub struct ProtocolTreasury {
+    pub total_collected: u64,
+    pub total_withdrawn: u64,
+    pub account_version: u8,
+}
+
+impl ProtocolTreasury {
+    pub const SPACE: usize = 8 + 8 + 8 + 1 + 32;
+
+    pub fn record_fee(&mut self, fee: u64) -> Result<()> {
+        self.total_collected = Amount::lamports(self.total_collected)
+            .checked_add(Amount::lamports(fee))?
+            .raw;
+        Ok(())
+    }
+
+    pub fn record_withdrawal(&mut self, lamports: u64) -> Result<()> {
+        self.total_withdrawn = Amount::lamports(self.total_withdrawn)
+            .checked_add(Amount::lamports(lamports))?
+            .raw;
+        Ok(())
+    }
+}
+
+/// An x402 facilitator the admin approved to submit claim payments, at
+/// `["facilitator", authority]`. The account holds the facilitator's share of
+/// the protocol fee until its authority withdraws it.
+#[account]
+pub struct FacilitatorAccount {
+    /// The operator key: rotates the signing key and withdraws fees.
+    pub authority: Pubkey,
+    /// The key that co-signs the payments attributed to the facilitator.
+    pub signing_key: Pubkey,
+    /// Share of the protocol fee on attributed payments, in basis points.
+    pub fee_share_bps: u16,
+    pub revoked: bool,
+    pub approved_at: i64,
+    pub key_rotated_at: i64,
+    /// Payments attributed to the facilitator.
+    pub settlements: u64,
+    pub total_earned: u64,
+    pub total_withdrawn: u64,
+    pub account_version: u8,
+}
+
+impl FacilitatorAccount {
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 1 + 8 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn approve(
+        &mut self,
+        authority: Pubkey,
+        signing_key: Pubkey,
+        fee_share_bps: u16,
+        now: i64,
+    ) -> Result<()> {
+        validate_facilitator_key(&signing_key)?;
+        if u64::from(fee_share_bps) > amount::BPS_DENOMINATOR {
+            msg!(
+                "A fee share of {} bps is above the whole fee",
+                fee_share_bps
+            );
+            return err!(ErrorCode::InvalidFeeShare);
+        }
+        self.authority = authority;
+        self.signing_key = signing_key;
+        self.fee_share_bps = fee_share_bps;
+        self.revoked = false;
+        self.approved_at = now;
+        self.key_rotated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn rotate_key(&mut self, signing_key: Pubkey, now: i64) -> Result<()> {
+        self.expect_active()?;
+        validate_facilitator_key(&signing_key)?;
+        if signing_key == self.signing_key {
+            msg!("{} is already the signing key", signing_key);
+            return err!(ErrorCode::InvalidFacilitatorKey);
+        }
+        self.signing_key = signing_key;
+        self.key_rotated_at = now;
+        Ok(())
+    }
+
+    pub fn expect_active(&self) -> Result<()> {
+        if self.revoked {
+            msg!("Facilitator {} is revoked", self.authority);
+            return err!(ErrorCode::FacilitatorRevoked);
+        }
+        Ok(())
+    }
+
+    /// Attributes a payment co-signed by `signer` to the facilitator and
+    /// returns its share of the payment's `protocol_fee`.
+    pub fn attribute(&mut self, signer: Pubkey, protocol_fee: u64) -> Result<u64> {
+        self.expect_active()?;
+        if signer != self.signing_key {
+            msg!(
+                "Signed by {}; the facilitator signs with {}",
+                signer,
+                self.signing_key
+            );
+            return err!(ErrorCode::FacilitatorKeyMismatch);
+        }
+        let share = Amount::lamports(protocol_fee)
+            .mul_bps(self.fee_share_bps.into(), Rounding::Down)?
+            .raw;
+        self.settlements += 1;
+        self.total_earned = Amount::lamports(self.total_earned)
+            .checked_add(Amount::lamports(share))?
+            .raw;
+        Ok(share)
+    }
+
+    pub fn record_withdrawal(&mut self, lamports: u64) -> Result<()> {
+        self.total_withdrawn = Amount::lamports(self.total_withdrawn)
+            .checked_add(Amount::lamports(lamports))?
+            .raw;
+        Ok(())
+    }
+}
+
+/// Lamports set aside to reward keepers for running permissionless cranks,
+/// at `["crank_vault"]`. The account holds the lamports.
+#[account]
+pub struct CrankVault {
+    /// Paid for each due crank; 0 for no rewards.
+    pub reward_lamports: u64,
+    /// Cranks rewarded so far.
+    pub rewards_paid: u64,
+    pub total_paid: u64,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl CrankVault {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn set_reward(&mut self, reward_lamports: u64, now: i64) -> Result<()> {
+        if reward_lamports > MAX_CRANK_REWARD_LAMPORTS {
+            msg!(
+                "A reward of {} lamports is above the limit of {}",
+                reward_lamports,
+                MAX_CRANK_REWARD_LAMPORTS
+            );
+            return err!(ErrorCode::InvalidCrankReward);
+        }
+        self.reward_lamports = reward_lamports;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// The reward for one crank out of `available` lamports, 0 when the
+    /// vault cannot cover all of it.
+    pub fn reward_from(&self, available: u64) -> u64 {
+        if available < self.reward_lamports {
+            0
+        } else {
+            self.reward_lamports
+        }
+    }
+
+    pub fn record_reward(&mut self, lamports: u64) -> Result<()> {
+        self.rewards_paid = self
+            .rewards_paid
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.total_paid = Amount::lamports(self.total_paid)
+            .checked_add(Amount::lamports(lamports))?
+            .raw;
+        Ok(())
+    }
+}
+
+/// Protocol fees collected from token claim payments in one mint, at
+/// `["mint_treasury", mint]`. The fees are held in `vault`, a token account
+/// the treasury's address owns.
+#[account]
+pub struct MintTreasury {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub vault: Pubkey,
+    /// The token account sweeps send the vault's balance to.
+    pub destination: Pubkey,
+    pub total_collected: u64,
+    pub total_swept: u64,
+    pub account_version: u8,
+}
+
+impl MintTreasury {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 32 + 8 + 8 + 1 + 32;
+
+    pub fn record_fee(&mut self, fee: u64) -> Result<()> {
+        self.total_collected = Amount::new(self.total_collected, self.decimals)?
+            .checked_add(Amount::new(fee, self.decimals)?)?
+            .raw;
+        Ok(())
+    }
+
+    pub fn record_sweep(&mut self, amount: u64) -> Result<()> {
+        self.total_swept = Amount::new(self.total_swept, self.decimals)?
+            .checked_add(Amount::new(amount, self.decimals)?)?
+            .raw;
+        Ok(())
+    }
+}
+
+/// Which newer instruction families are enabled, at `["feature_flags"]`.
+/// Lets a family be rolled out or switched off without a redeploy.
+#[account]
+pub struct FeatureFlags {
+    pub admin: Pubkey,
+    /// Bitwise OR of the enabled `FEATURE_*` flags.
+    pub flags: u64,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl FeatureFlags {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 32;
+
+    pub fn enabled(&self, feature: u64) -> bool {
+        self.flags & feature == feature
+    }
+}
+
+/// Who settles claim disputes and how long they have, at
+/// `["dispute_config"]`.
+#[account]
+pub struct DisputeConfig {
+    pub admin: Pubkey,
+    pub arbiter: Pubkey,
+    /// Seconds the arbiter has to resolve a dispute before it is decided for
+    /// the patient.
+    pub resolution_secs: i64,
+    pub updated_at: i64,
+    /// Lamports a patient locks to open a dispute, refunded if they prevail
+    /// and forfeited to the provider if not.
+    pub bond_lamports: u64,
+    pub account_version: u8,
+}
+
+impl DisputeConfig {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn set(
+        &mut self,
+        arbiter: Pubkey,
+        resolution_secs: i64,
+        bond_lamports: u64,
+        now: i64,
+    ) -> Result<()> {
+        if arbiter == Pubkey::default() || resolution_secs <= 0 {
+            msg!(
+                "Disputes need an arbiter and a positive deadline; got {} and {}",
+                arbiter,
+                resolution_secs
+            );
+            return err!(ErrorCode::InvalidDisputeConfig);
+        }
+        self.arbiter = arbiter;
+        self.resolution_secs = resolution_secs;
+        self.bond_lamports = bond_lamports;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// An independent health network sharing the deployment, at
+/// `["tenant", tenant_id]`. Its claims live under the tenant's own seed
+/// namespace and pay its fee to its treasury.
+#[account]
+pub struct Tenant {
+    pub admin: Pubkey,
+    pub tenant_id: String,
+    pub treasury: Pubkey,
+    /// Fee on each claim payment, paid by the provider on top of the claim.
+    pub fee_bps: u16,
+    pub member_count: u32,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl Tenant {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_SEED_ID_LEN + 32 + 2 + 4 + 8 + 8 + 1 + 32;
+
+    /// The fee on paying `amount` of a claim, rounded down.
+    pub fn fee_on(&self, amount: u64) -> Result<u64> {
+        Ok(Amount::lamports(amount)
+            .mul_bps(self.fee_bps.into(), Rounding::Down)?
+            .raw)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TenantRole {
+    Patient,
+    Provider,
+}
+
+/// A patient or provider admitted to a tenant, at
+/// `["tenant_member", tenant, member]`.
+#[account]
+pub struct TenantMember {
+    pub tenant: Pubkey,
+    pub member: Pubkey,
+    pub role: TenantRole,
+    pub admitted_at: i64,
+    pub account_version: u8,
+}
+
+impl TenantMember {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1 + 32;
+}
+
+/// A tenant plan's premium and its discount for patients who let their
+/// records be used in research, at `["plan_benefits", tenant]`.
+#[account]
+pub struct PlanBenefits {
+    pub tenant: Pubkey,
+    /// Lamports per month.
+    pub premium: u64,
+    /// Taken off the premium of patients whose research consent covers
+    /// `research_categories`.
+    pub research_discount_bps: u16,
+    /// Mask of `DataCategory::bit` values the research consent must cover.
+    pub research_categories: u8,
+    /// Lamports of patient share per plan year past which the payer covers
+    /// the rest; 0 for no maximum.
+    pub out_of_pocket_max: u64,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl PlanBenefits {
+    pub const SPACE: usize = 8 + 32 + 8 + 2 + 1 + 8 + 8 + 1 + 32;
+
+    pub fn set(
+        &mut self,
+        premium: u64,
+        research_discount_bps: u16,
+        research_categories: u8,
+        out_of_pocket_max: u64,
+        now: i64,
+    ) -> Result<()> {
+        if premium == 0
+            || u64::from(research_discount_bps) > amount::BPS_DENOMINATOR
+            || (research_discount_bps != 0 && research_categories == 0)
+        {
+            msg!(
+                "Premium of {} lamports with {} bps off for categories {:#04x}",
+                premium,
+                research_discount_bps,
+                research_categories
+            );
+            return err!(ErrorCode::InvalidPlanBenefits);
+        }
+        self.premium = premium;
+        self.research_discount_bps = research_discount_bps;
+        self.research_categories = research_categories;
+        self.out_of_pocket_max = out_of_pocket_max;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// The discount, rounded down, on the premium of a patient with
+    /// `research_consent` at `now`.
+    pub fn research_discount(
+        &self,
+        research_consent: Option<&ResearchConsent>,
+        now: i64,
+    ) -> Result<u64> {
+        if !research_consent.is_some_and(|consent| consent.covers(self.research_categories, now)) {
+            return Ok(0);
+        }
+        Ok(Amount::lamports(self.premium)
+            .mul_bps(self.research_discount_bps.into(), Rounding::Down)?
+            .raw)
+    }
+}
+
+/// A patient's premium for one month of a tenant plan, at
+/// `["premium_payment", tenant, patient, period]`.
+#[account]
+pub struct PremiumPayment {
+    pub tenant: Pubkey,
+    pub patient: Pubkey,
+    /// `YYYYMM`.
+    pub period: u32,
+    /// Lamports paid, after the discount.
+    pub amount: u64,
+    pub discount: u64,
+    pub paid_at: i64,
+    pub account_version: u8,
+}
+
+impl PremiumPayment {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + 8 + 8 + 8 + 1 + 32;
+}
+
+/// A patient's share of their verified claims under a tenant plan for one
+/// calendar year of service, at `["out_of_pocket", tenant, patient, year]`
+/// with the year as little-endian `u32`.
+#[account]
+pub struct OutOfPocket {
+    pub tenant: Pubkey,
+    pub patient: Pubkey,
+    pub year: u32,
+    /// What the patient bears, up to the plan's out-of-pocket maximum.
+    pub patient_share: u64,
+    /// Patient share past the maximum that providers covered.
+    pub payer_covered: u64,
+    pub account_version: u8,
+}
+
+impl OutOfPocket {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + 8 + 8 + 1 + 32;
+
+    /// Counts the patient share of `claim`, just verified, toward
+    /// `out_of_pocket_max` and moves any part past it to the payer. Returns
+    /// the part moved.
+    pub fn apply(&mut self, claim: &mut ClaimAccount, out_of_pocket_max: u64) -> Result<u64> {
+        self.tenant = claim.tenant;
+        self.patient = claim.patient;
+        self.year = claim.service_year();
+        self.account_version = Self::VERSION;
+        let share = claim.amount.saturating_sub(claim.approved_amount()?);
+        let counted = share.min(out_of_pocket_max.saturating_sub(self.patient_share));
+        let covered = share - counted;
+        add_to(&mut self.patient_share, counted)?;
+        add_to(&mut self.payer_covered, covered)?;
+        claim.payer_covered = covered;
+        Ok(covered)
+    }
+}
+
+/// Running hash of a patient's consent receipts, at
+/// `["consent_ledger", patient]`. Lets auditors check a sequence of
+/// `ConsentReceipt` events is complete and unaltered.
+#[account]
+pub struct ConsentLedger {
+    pub patient: Pubkey,
+    pub receipt_count: u64,
+    /// `sha256(previous head || receipt)` over the Borsh-encoded receipts.
+    pub head: [u8; 32],
+    pub account_version: u8,
+}
+
+impl ConsentLedger {
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 1 + 32;
+
+    /// Numbers `receipt` and folds it into the head.
+    pub fn append(&mut self, receipt: &mut ConsentReceipt) -> Result<()> {
+        self.patient = receipt.patient;
+        self.account_version = Self::VERSION;
+        self.receipt_count += 1;
+        receipt.sequence = self.receipt_count;
+        self.head = hashv(&[&self.head, &receipt.try_to_vec()?]).to_bytes();
+        Ok(())
+    }
+}
+
+/// Totals between one payer and payee for a calendar month, at
+/// `["settlement", payer, payee, period]` with the period as little-endian
+/// `u32` `YYYYMM`. For claims the provider is the payer and the patient the
+/// payee.
+#[account]
+pub struct SettlementSummary {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub period: u32,
+    pub claims_paid: u32,
+    /// Including late interest.
+    pub amount_paid: u64,
+    /// Tenant network fees paid on top of `amount_paid`.
+    pub fees: u64,
+    /// Payments clawed back to the payer from a payment hold.
+    pub refunds: u64,
+    /// Rejected claims the patient reopened for review.
+    pub disputes: u32,
+    pub closed: bool,
+    pub closed_at: i64,
+    pub account_version: u8,
+}
+
+impl SettlementSummary {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + 4 + 8 + 8 + 8 + 4 + 1 + 8 + 1 + 32;
+}
+
+/// A patient's claims for one calendar year of service in one currency,
+/// returned by `get_patient_year_summary`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PatientYearSummary {
+    pub patient: Pubkey,
+    pub year: u32,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    pub claims: u32,
+    pub billed: u64,
+    /// Billed on claims not decided yet, including declined ones awaiting
+    /// another provider.
+    pub pending: u64,
+    /// The approved amount of verified and paid claims.
+    pub approved: u64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    /// Billed on decided or expired claims but not approved, which the
+    /// patient bears.
+    pub out_of_pocket: u64,
+    /// The plan deductible passed in.
+    pub deductible: u64,
+    /// How much of the deductible `out_of_pocket` has met.
+    pub deductible_met: u64,
+}
+
+impl PatientYearSummary {
+    pub fn new(patient: Pubkey, year: u32, mint: Pubkey, deductible: u64) -> Self {
+        Self {
+            patient,
+            year,
+            mint,
+            claims: 0,
+            billed: 0,
+            pending: 0,
+            approved: 0,
+            principal_paid: 0,
+            interest_paid: 0,
+            out_of_pocket: 0,
+            deductible,
+            deductible_met: 0,
+        }
+    }
+
+    /// Counts `claim` if it is in the summary's currency and its service
+    /// started in the summary's year, returning whether it was counted.
+    /// Claims filed before service periods were recorded count by when they
+    /// were filed.
+    pub fn add(&mut self, claim: &ClaimAccount) -> Result<bool> {
+        if claim.patient != self.patient {
+            msg!("Claim {} is for another patient", claim.claim_id);
+            return err!(ErrorCode::InvalidPatient);
+        }
+        if claim.payment_mint() != self.mint || claim.service_year() != self.year {
+            return Ok(false);
+        }
+
+        self.claims = self
+            .claims
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        add_to(&mut self.billed, claim.amount)?;
+        match claim.status {
+            ClaimStatus::Pending
+            | ClaimStatus::UnderReview
+            | ClaimStatus::OnHold
+            | ClaimStatus::Declined
+            | ClaimStatus::Disputed => add_to(&mut self.pending, claim.amount)?,
+            ClaimStatus::Rejected | ClaimStatus::Expired => {
+                add_to(&mut self.out_of_pocket, claim.amount)?
+            }
+            ClaimStatus::Verified | ClaimStatus::PartiallyPaid | ClaimStatus::Paid => {
+                let approved = claim.approved_amount()?;
+                add_to(&mut self.approved, approved)?;
+                add_to(
+                    &mut self.out_of_pocket,
+                    claim.amount.saturating_sub(approved),
+                )?;
+            }
+        }
+        add_to(&mut self.principal_paid, claim.principal_paid)?;
+        add_to(&mut self.interest_paid, claim.interest_paid)?;
+        self.deductible_met = self.out_of_pocket.min(self.deductible);
+        Ok(true)
+    }
+}
+
+/// A patient's active coverage as of a moment, returned by
+/// `get_coverage_proof`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CoverageProof {
+    pub patient: Pubkey,
+    /// The tenant whose plan covers the patient.
+    pub tenant: Pubkey,
+    pub plan_id: String,
+    /// When the patient was admitted to the plan.
+    pub enrolled_since: i64,
+    pub provider: Pubkey,
+    /// Whether the provider is a provider member of the tenant.
+    pub in_network: bool,
+    pub as_of: i64,
+    /// Hash over the fields above.
+    pub digest: [u8; 32],
+}
+
+impl CoverageProof {
+    pub fn new(
+        plan_id: String,
+        membership: &TenantMember,
+        provider: Pubkey,
+        in_network: bool,
+        as_of: i64,
+    ) -> Self {
+        let mut proof = Self {
+            patient: membership.member,
+            tenant: membership.tenant,
+            plan_id,
+            enrolled_since: membership.admitted_at,
+            provider,
+            in_network,
+            as_of,
+            digest: [0; 32],
+        };
+        proof.digest = proof.compute_digest();
+        proof
+    }
+
+    /// The hash `digest` should hold, for checking a proof kept off-chain.
+    pub fn compute_digest(&self) -> [u8; 32] {
+        hashv(&[
+            b"coverage_proof",
+            self.patient.as_ref(),
+            self.tenant.as_ref(),
+            &(self.plan_id.len() as u32).to_le_bytes(),
+            self.plan_id.as_bytes(),
+            &self.enrolled_since.to_le_bytes(),
+            self.provider.as_ref(),
+            &[self.in_network as u8],
+            &self.as_of.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+fn add_to(total: &mut u64, amount: u64) -> Result<()> {
+    *total = total.checked_add(amount).ok_or(ErrorCode::AmountOverflow)?;
+    Ok(())
+}
+
+/// A paid subscription to a patient's new records in one category, at
+/// `["subscription", patient, subscriber]`. The account holds the deposit.
+#[account]
+pub struct Subscription {
+    pub patient: Pubkey,
+    pub subscriber: Pubkey,
+    pub category: DataCategory,
+    pub price_per_record: u64,
+    /// Lamports the subscriber has paid in.
+    pub deposited: u64,
+    /// Lamports earned by the patient, including those withdrawn.
+    pub accrued: u64,
+    pub withdrawn: u64,
+    pub delivered: u32,
+    pub started_at: i64,
+    pub account_version: u8,
+}
+
+impl Subscription {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 4 + 8 + 1 + 32;
+
+    /// Accrues one record's price if the deposit still covers it. Returns
+    /// whether the record was paid for.
+    pub fn accrue(&mut self) -> bool {
+        if self.deposited - self.accrued < self.price_per_record {
+            return false;
+        }
+        self.accrued += self.price_per_record;
+        self.delivered += 1;
+        true
+    }
+
+    pub fn withdrawable(&self) -> u64 {
+        self.accrued - self.withdrawn
+    }
+}
+
+/// Recurring access to the owner's records, at
+/// `["subscription_plan", owner, plan_id]`. Each subscriber holds a
+/// [`PlanSubscription`] at `["plan_subscription", plan, subscriber]`, which an
+/// x402 facilitator checks instead of charging for each query.
+#[account]
+pub struct SubscriptionPlan {
+    pub owner: Pubkey,
+    pub plan_id: String,
+    /// Lamports per period.
+    pub price_per_period: u64,
+    pub period_secs: i64,
+    /// Mask of `DataCategory::bit` values the plan covers.
+    pub categories: u8,
+    /// `GRANT_SCOPE_*` bits the plan covers.
+    pub scope: u8,
+    /// Periods paid for across all subscribers.
+    pub periods_sold: u64,
+    pub created_at: i64,
+    pub account_version: u8,
+}
+
+impl SubscriptionPlan {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_SEED_ID_LEN + 8 + 8 + 1 + 1 + 8 + 8 + 1 + 32;
+
+    /// Sets the plan's terms after checking the price and period are positive
+    /// and it covers some records with a scope a grant could give.
+    pub fn set_terms(
+        &mut self,
+        price_per_period: u64,
+        period_secs: i64,
+        categories: u8,
+        scope: u8,
+    ) -> Result<()> {
+        if price_per_period == 0 || period_secs <= 0 {
+            msg!(
+                "Plan of {} lamports every {} seconds; both must be positive",
+                price_per_period,
+                period_secs
+            );
+            return err!(ErrorCode::InvalidPlanTerms);
+        }
+        if categories == 0 || scope & GRANT_SCOPE_ALL == 0 || scope & !GRANT_SCOPE_ALL != 0 {
+            msg!(
+                "Plan covers categories {:#04x} with scope {:#04x}",
+                categories,
+                scope
+            );
+            return err!(ErrorCode::InvalidPlanTerms);
+        }
+        self.price_per_period = price_per_period;
+        self.period_secs = period_secs;
+        self.categories = categories;
+        self.scope = scope;
+        Ok(())
+    }
+}
+
+/// A subscriber's paid access under a [`SubscriptionPlan`], at
+/// `["plan_subscription", plan, subscriber]`.
+#[account]
+pub struct PlanSubscription {
+    pub plan: Pubkey,
+    pub owner: Pubkey,
+    pub subscriber: Pubkey,
+    /// Copied from the plan when last paid for.
+    pub categories: u8,
+    pub scope: u8,
+    /// Access lapses at this time unless renewed.
+    pub current_period_end: i64,
+    pub periods_paid: u32,
+    pub started_at: i64,
+    pub account_version: u8,
+}
+
+impl PlanSubscription {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 1 + 8 + 4 + 8 + 1 + 32;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.current_period_end
+    }
+
+    /// Whether the subscription lets the subscriber access the owner's records
+    /// of `category` with every bit of `scope` at `now`.
+    pub fn allows(&self, category: DataCategory, scope: u8, now: i64) -> bool {
+        self.is_active(now) && self.categories & category.bit() != 0 && self.scope & scope == scope
+    }
+
+    /// Adds one of `plan`'s periods, counting it on the plan. Access that has
+    /// not lapsed is extended from its end; lapsed access restarts at `now`.
+    pub fn renew(&mut self, plan: &mut SubscriptionPlan, now: i64) -> Result<()> {
+        let start = self.current_period_end.max(now);
+        self.current_period_end = start
+            .checked_add(plan.period_secs)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.periods_paid = self
+            .periods_paid
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.categories = plan.categories;
+        self.scope = plan.scope;
+        plan.periods_sold = plan
+            .periods_sold
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        Ok(())
+    }
+}
+
+/// Pay-per-access to one of the owner's records, at
+/// `["access_offer", owner, offer_id]`. Each purchase is an [`AccessGrant`] at
+/// `["access_purchase", offer, buyer]`, which an x402 facilitator checks
+/// before serving `resource_hash`.
+#[account]
+pub struct DataAccessOffer {
+    pub owner: Pubkey,
+    pub offer_id: String,
+    pub resource_hash: [u8; 32],
+    /// Lamports per purchase.
+    pub price: u64,
+    /// How long one purchase grants access for.
+    pub access_secs: i64,
+    pub sales: u64,
+    pub created_at: i64,
+    /// Prices for records younger than each tier's age, youngest first.
+    /// Offers created before tiers were recorded have none once upgraded.
+    pub price_tiers: Vec<PriceTier>,
+    pub account_version: u8,
+}
+
+impl DataAccessOffer {
+    pub const SPACE: usize = 8
+        + 32
+        + 4
+        + MAX_SEED_ID_LEN
+        + 32
+        + 8
+        + 8
+        + 8
+        + 8
+        + 4
+        + MAX_PRICE_TIERS * PriceTier::SPACE
+        + 1
+        + 32;
+
+    /// Replaces the tiers after checking they are positive and ordered from
+    /// the youngest records up.
+    pub fn set_price_tiers(&mut self, price_tiers: Vec<PriceTier>) -> Result<()> {
+        validate_count(
+            "price_tiers",
+            price_tiers.len(),
+            MAX_PRICE_TIERS,
+            ErrorCode::InvalidPriceTiers,
+        )?;
+        let mut younger_than = 0;
+        for tier in &price_tiers {
+            if tier.price == 0 || tier.max_age_secs <= younger_than {
+                msg!(
+                    "Tier of {} lamports up to {} seconds old; prices must be positive and ages increasing",
+                    tier.price,
+                    tier.max_age_secs
+                );
+                return err!(ErrorCode::InvalidPriceTiers);
+            }
+            younger_than = tier.max_age_secs;
+        }
+        self.price_tiers = price_tiers;
+        Ok(())
+    }
+
+    /// The price at `now` of access to a record written at `recorded_at`,
+    /// which offers with tiers need.
+    pub fn price_at(&self, recorded_at: Option<i64>, now: i64) -> Result<u64> {
+        // An offer from before tiers decodes with a stray tier until upgraded
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Offer is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        if self.price_tiers.is_empty() {
+            return Ok(self.price);
+        }
+        let Some(recorded_at) = recorded_at else {
+            msg!("The offer is priced by the record's age; pass the record");
+            return err!(ErrorCode::OfferRecordMissing);
+        };
+        let age = now.saturating_sub(recorded_at).max(0);
+        Ok(self
+            .price_tiers
+            .iter()
+            .find(|tier| age < tier.max_age_secs)
+            .map_or(self.price, |tier| tier.price))
+    }
+}
+
+/// The price of access to records younger than `max_age_secs`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceTier {
+    pub max_age_secs: i64,
+    /// Lamports per purchase.
+    pub price: u64,
+}
+
+impl PriceTier {
+    pub const SPACE: usize = 8 + 8;
+}
+
+/// A patient's default access rules, at `["access_policy", patient]`.
+#[account]
+pub struct AccessPolicy {
+    pub patient: Pubkey,
+    pub rules: Vec<PolicyRule>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl AccessPolicy {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_POLICY_RULES * PolicyRule::SPACE + 8 + 1 + 32;
+
+    /// Effect of the first rule matching the requester, every category in
+    /// `categories` and every bit of `scope`, if any.
+    pub fn decide(&self, is_provider: bool, categories: u8, scope: u8) -> Option<PolicyEffect> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.subject.matches(is_provider)
+                    && rule.categories & categories == categories
+                    && rule.scope & scope == scope
+            })
+            .map(|rule| rule.effect)
+    }
+}
+
+/// A default rule, such as "any provider may read lab results" or "imaging
+/// always needs an explicit grant".
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub subject: PolicySubject,
+    /// Mask of `DataCategory::bit` values the rule covers.
+    pub categories: u8,
+    /// `GRANT_SCOPE_*` bits the rule covers.
+    pub scope: u8,
+    pub effect: PolicyEffect,
+}
+
+impl PolicyRule {
+    pub const SPACE: usize = 1 + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicySubject {
+    Anyone,
+    /// Any wallet with a registered provider account.
+    RegisteredProvider,
+}
+
+impl PolicySubject {
+    pub fn matches(&self, is_provider: bool) -> bool {
+        match self {
+            PolicySubject::Anyone => true,
+            PolicySubject::RegisteredProvider => is_provider,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicyEffect {
+    Allow,
+    /// Stop evaluating rules and require an explicit grant.
+    RequireGrant,
+}
+
+/// Records imported together, at `["health_data_batch", owner, batch_id]`.
+#[account]
+pub struct HealthDataBatchAccount {
+    pub owner: Pubkey,
+    pub batch_id: String,
+    pub entries: Vec<HealthRecordEntry>,
+    pub timestamp: i64,
+    pub account_version: u8,
+}
+
+/// An encrypted record held on-chain, at `["health_data_v2", data_hash]`,
+/// for payloads too large to deserialize within the compute limit. The
+/// account is zero-copy with a fixed-size `data` buffer, written in chunks.
+/// Zero-copy layouts cannot grow, so the account is outside
+/// `upgrade_account`; a new layout would be a new type.
+#[account(zero_copy)]
+pub struct HealthDataAccountV2 {
+    pub owner: Pubkey,
+    /// SHA-256 of the encrypted record.
+    pub data_hash: [u8; 32],
+    /// SHA-256 of the payload, checked when its last chunk lands.
+    pub commitment: [u8; 32],
+    pub created_at: i64,
+    /// When the last chunk landed; 0 while the payload is being written.
+    pub finalized_at: i64,
+    pub total_len: u32,
+    pub written_len: u32,
+    /// The record's `DataCategory`.
+    pub category: u8,
+    pub account_version: u8,
+    pub _padding: [u8; 6],
+    /// The payload in its first `total_len` bytes.
+    pub data: [u8; MAX_CHUNKED_RECORD_LEN],
+}
+
+impl HealthDataAccountV2 {
+    pub const VERSION: u8 = 1;
+    pub const SPACE: usize = 8 + std::mem::size_of::<HealthDataAccountV2>();
+
+    pub fn begin(
+        &mut self,
+        owner: Pubkey,
+        data_hash: [u8; 32],
+        commitment: [u8; 32],
+        total_len: u32,
+        category: DataCategory,
+        now: i64,
+    ) -> Result<()> {
+        if total_len == 0 || total_len as usize > MAX_CHUNKED_RECORD_LEN {
+            msg!(
+                "Payload of {} bytes; records hold 1 to {}",
+                total_len,
+                MAX_CHUNKED_RECORD_LEN
+            );
+            return err!(ErrorCode::InvalidRecordLength);
+        }
+        self.owner = owner;
+        self.data_hash = data_hash;
+        self.commitment = commitment;
+        self.created_at = now;
+        self.total_len = total_len;
+        self.category = category as u8;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        self.finalized_at != 0
+    }
+
+    pub fn category(&self) -> Result<DataCategory> {
+        Ok(DataCategory::try_from_slice(&[self.category])?)
+    }
+
+    /// The payload written so far.
+    pub fn payload(&self) -> &[u8] {
+        &self.data[..self.written_len as usize]
+    }
+
+    /// Writes `chunk` at `offset`, returning whether it completed and
+    /// finalized the payload.
+    pub fn append_chunk(&mut self, offset: u32, chunk: &[u8], now: i64) -> Result<bool> {
+        if self.is_finalized() {
+            msg!("The record was finalized at {}", self.finalized_at);
+            return err!(ErrorCode::RecordFinalized);
+        }
+        if offset != self.written_len {
+            msg!(
+                "Chunk at offset {}; {} bytes are written",
+                offset,
+                self.written_len
+            );
+            return err!(ErrorCode::ChunkOutOfOrder);
+        }
+        let remaining = (self.total_len - self.written_len) as usize;
+        if chunk.is_empty() || chunk.len() > MAX_DATA_CHUNK_LEN || chunk.len() > remaining {
+            msg!(
+                "Chunk of {} bytes; {} remain, at most {} per chunk",
+                chunk.len(),
+                remaining,
+                MAX_DATA_CHUNK_LEN
+            );
+            return err!(ErrorCode::InvalidChunkLength);
+        }
+
+        let start = offset as usize;
+        self.data[start..start + chunk.len()].copy_from_slice(chunk);
+        self.written_len += chunk.len() as u32;
+        if self.written_len < self.total_len {
+            return Ok(false);
+        }
+        if hashv(&[self.payload()]).to_bytes() != self.commitment {
+            msg!("The payload does not match the record's commitment");
+            return err!(ErrorCode::CommitmentMismatch);
+        }
+        self.finalized_at = now;
+        Ok(true)
+    }
+}
+
+/// One record in a batch; the payload itself lives off-chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HealthRecordEntry {
+    /// SHA-256 of the encrypted payload.
+    pub data_hash: [u8; 32],
+    /// Off-chain location of the encrypted payload (e.g. an IPFS CID or Arweave tx id).
+    pub pointer: String,
+    pub category: DataCategory,
+}
+
+impl HealthRecordEntry {
+    pub fn space(&self) -> usize {
+        32 + 4 + self.pointer.len() + 1
+    }
+}
+
+/// Sign-offs a provider requires before paying its larger claims, at
+/// `["approval_policy", authority, policy_id]`. Claims attached to the policy
+/// and billed over `threshold` are not paid until every approver approved.
+#[account]
+pub struct ApprovalPolicy {
+    /// The provider the policy belongs to.
+    pub authority: Pubkey,
+    pub policy_id: String,
+    pub threshold: u64,
+    /// Such as the provider, an insurer and a medical director. Approver `i`
+    /// is bit `1 << i` of a claim's `approvals`.
+    pub approvers: Vec<Pubkey>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl ApprovalPolicy {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_SEED_ID_LEN + 8 + 4 + MAX_APPROVERS * 32 + 8 + 1 + 32;
+
+    /// Replaces the threshold and approvers, which must be 1 to
+    /// `MAX_APPROVERS` distinct keys.
+    pub fn set(&mut self, threshold: u64, approvers: Vec<Pubkey>, now: i64) -> Result<()> {
+        if approvers.is_empty() || approvers.len() > MAX_APPROVERS {
+            msg!(
+                "{} approvers given; must be 1 to {}",
+                approvers.len(),
+                MAX_APPROVERS
+            );
+            return err!(ErrorCode::InvalidApprovers);
+        }
+        for (i, approver) in approvers.iter().enumerate() {
+            if approvers[..i].contains(approver) {
+                msg!("Approver {} is listed more than once", approver);
+                return err!(ErrorCode::InvalidApprovers);
+            }
+        }
+        self.threshold = threshold;
+        self.approvers = approvers;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// The bit of `approver` in a claim's `approvals`.
+    pub fn approver_bit(&self, approver: &Pubkey) -> Result<u8> {
+        match self.approvers.iter().position(|key| key == approver) {
+            Some(index) => Ok(1 << index),
+            None => {
+                msg!("{} is not an approver under the policy", approver);
+                err!(ErrorCode::NotAnApprover)
+            }
+        }
+    }
+
+    /// The approver bits a claim billed `amount` needs: all of them over the
+    /// threshold, none otherwise.
+    pub fn required_for(&self, amount: u64) -> u8 {
+        if amount > self.threshold {
+            u8::MAX >> (8 - self.approvers.len())
+        } else {
+            0
+        }
+    }
+}
+
+/// An insurance claim, at `["claim", claim_id]`.
+#[account]
+pub struct ClaimAccount {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    /// Hashes of the health-data records supporting the claim.
+    pub attachments: Vec<[u8; 32]>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    /// Claims verified before this was recorded read 0 and accrue no interest.
+    pub verified_at: i64,
+    /// The approved amount paid, excluding interest.
+    pub principal_paid: u64,
+    /// Interest paid for settling after `PAYMENT_WINDOW_SECS`.
+    pub interest_paid: u64,
+    /// The tenant network the claim was filed in, or `Pubkey::default()`.
+    pub tenant: Pubkey,
+    /// The SPL mint the claim is paid in, or `Pubkey::default()` for
+    /// lamports.
+    pub mint: Pubkey,
+    /// The claim's id in the billing system it came from, such as a hospital
+    /// claim number or an X12 837 control number; empty if not set.
+    pub external_ref: String,
+    /// Labels for reconciliation, such as a remittance batch or payer code.
+    pub tags: Vec<String>,
+    /// When the care began and ended, as opposed to when the claim was filed.
+    /// Claims filed before these were recorded read 0 for both.
+    pub service_start: i64,
+    pub service_end: i64,
+    /// The provider's approval policy for the claim, or `Pubkey::default()`.
+    pub approval_policy: Pubkey,
+    /// Approver bits the policy requires before payment; 0 if none.
+    pub required_approvals: u8,
+    /// Approver bits recorded so far.
+    pub approvals: u8,
+    /// Who paid the claim's rent and gets it back when the claim is closed:
+    /// the filer, or the patient for claims filed before it was recorded.
+    pub rent_payer: Pubkey,
+    /// The insurer that must approve the claim before it is paid, or
+    /// `Pubkey::default()` for claims without one.
+    pub insurer: Pubkey,
+    /// When the insurer approved the claim; 0 until then.
+    pub insurer_approved_at: i64,
+    /// When anyone may expire the claim if it is still undecided; 0 for
+    /// never, including claims filed before this was recorded.
+    pub expires_at: i64,
+    /// The part of the patient's share the payer took on at verification
+    /// because the patient reached their plan's out-of-pocket maximum.
+    pub payer_covered: u64,
+    pub account_version: u8,
+}
+
+impl ClaimAccount {
+    /// The payable lines' total plus what the payer covers of the rest.
+    pub fn approved_amount(&self) -> Result<u64> {
+        let lines = self
+            .line_items
+            .iter()
+            .filter(|item| item.status.is_payable())
+            .try_fold(Amount::lamports(0), |acc, item| {
+                acc.checked_add(Amount::lamports(item.total()?))
+            })?
+            .raw;
+        let covered = self.payer_covered.min(self.amount.saturating_sub(lines));
+        Ok(lines + covered)
+    }
+
+    /// The calendar year service started in, or the claim was filed in for
+    /// claims filed before service periods were recorded.
+    pub fn service_year(&self) -> u32 {
+        let service_date = if self.service_start == 0 {
+            self.timestamp
+        } else {
+            self.service_start
+        };
+        settlement::period_of(service_date) / 100
+    }
+
+    pub fn adjudicate_line(
+        &mut self,
+        index: u8,
+        decision: LineDecision,
+        reason_code: u16,
+    ) -> Result<()> {
+        expect_status(self.status, &OPEN_STATUSES, ErrorCode::ClaimNotOpen)?;
+
+        let line_count = self.line_items.len();
+        let Some(item) = self.line_items.get_mut(index as usize) else {
+            msg!(
+                "Line item {} requested; the claim has {}",
+                index,
+                line_count
+            );
+            return err!(ErrorCode::InvalidLineItem);
+        };
+
+        match decision {
+            LineDecision::Approve => item.status = LineItemStatus::Approved,
+            LineDecision::Deny => item.status = LineItemStatus::Denied,
+            LineDecision::DownCode { code, unit_price } => {
+                validate_code(&code)?;
+                // A down-code may only lower what is owed for the line
+                if unit_price > item.unit_price {
+                    msg!(
+                        "Down-code unit price {} exceeds the billed {}",
+                        unit_price,
+                        item.unit_price
+                    );
+                    return err!(ErrorCode::InvalidDownCode);
+                }
+                item.code = code;
+                item.unit_price = unit_price;
+                item.status = LineItemStatus::DownCoded;
+            }
+        }
+        item.reason_code = reason_code;
+        Ok(())
+    }
+
+    /// Records the adjudicator's final decision on an open claim.
+    pub fn verify(&mut self, status: ClaimStatus, now: i64) -> Result<()> {
+        expect_status(self.status, &OPEN_STATUSES, ErrorCode::ClaimNotOpen)?;
+        match status {
+            ClaimStatus::Verified => {
+                let pending = self
+                    .line_items
+                    .iter()
+                    .filter(|item| item.status == LineItemStatus::Pending)
+                    .count();
+                if pending > 0 {
+                    msg!("{} line items are still pending adjudication", pending);
+                    return err!(ErrorCode::LineItemsNotAdjudicated);
+                }
+                self.verified_at = now;
+            }
+            ClaimStatus::Rejected => self.rejected_at = now,
+            _ => {
+                msg!("Cannot move claim from {:?} to {:?}", self.status, status);
+                return err!(ErrorCode::InvalidStatusTransition);
+            }
+        }
+
+        self.status = status;
+        Ok(())
+    }
+
+    /// Fails unless the claim was paid in full, rejected, declined or expired.
+    pub fn expect_closable(&self) -> Result<()> {
+        expect_status(self.status, &CLOSABLE_STATUSES, ErrorCode::ClaimNotClosable)
+    }
+
+    pub fn reopen(&mut self, new_attachments: Vec<[u8; 32]>, now: i64) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Rejected],
+            ErrorCode::ClaimNotRejected,
+        )?;
+        require!(!self.reopened, ErrorCode::ClaimAlreadyReopened);
+        let deadline = self.rejected_at + REOPEN_WINDOW_SECS;
+        if now > deadline {
+            msg!("Reopen window closed at {}; now is {}", deadline, now);
+            return err!(ErrorCode::ReopenWindowElapsed);
+        }
+        require!(!new_attachments.is_empty(), ErrorCode::NoNewEvidence);
+        validate_count(
+            "attachments",
+            self.attachments.len() + new_attachments.len(),
+            MAX_ATTACHMENTS,
+            ErrorCode::TooManyAttachments,
+        )?;
+        for hash in new_attachments {
+            validate_hash("attachment", &hash)?;
+            if self.attachments.contains(&hash) {
+                msg!("Attachment {} is already on the claim", hex(&hash));
+                return err!(ErrorCode::DuplicateAttachment);
+            }
+            self.attachments.push(hash);
+        }
+
+        self.reopened = true;
+        self.status = ClaimStatus::UnderReview;
+        Ok(())
+    }
+
+    /// Simple interest on `principal` from the end of the payment window to
+    /// `now`, rounded down.
+    pub fn interest_due(&self, principal: u64, now: i64) -> Result<u64> {
+        if self.verified_at == 0 {
+            return Ok(0);
+        }
+        let overdue = now - (self.verified_at + PAYMENT_WINDOW_SECS);
+        if overdue <= 0 {
+            return Ok(0);
+        }
+        let interest = Amount::lamports(principal).mul_ratio(
+            LATE_INTEREST_BPS_PER_YEAR * overdue as u64,
+            amount::BPS_DENOMINATOR * SECS_PER_YEAR as u64,
+            Rounding::Down,
+        )?;
+        Ok(interest.raw)
+    }
+
+    /// The mint the claim is paid in, `Pubkey::default()` for lamports.
+    /// Claims written before mints were recorded were all filed in lamports,
+    /// but read their version byte into `mint` until upgraded.
+    pub fn payment_mint(&self) -> Pubkey {
+        if self.account_version == 0 && self.mint == migration::V1_CLAIM_MINT {
+            Pubkey::default()
+        } else {
+            self.mint
+        }
+    }
+
+    /// Fails unless the claim is paid in `mint`, `Pubkey::default()` for
+    /// lamports.
+    pub fn expect_mint(&self, mint: &Pubkey) -> Result<()> {
+        if self.payment_mint() != *mint {
+            msg!("The claim is paid in {}, not {}", self.payment_mint(), mint);
+            return err!(ErrorCode::ClaimMintMismatch);
+        }
+        Ok(())
+    }
+
+    /// What a provider locks in escrow at `now`: the billed amount while the
+    /// claim is undecided, or the outstanding approved amount with the
+    /// interest due on it once verified. Only lamport claims outside tenants
+    /// can be escrowed, since the escrow does not carry a network fee.
+    pub fn escrow_amount(&self, now: i64) -> Result<u64> {
+        self.expect_mint(&Pubkey::default())?;
+        if self.tenant != Pubkey::default() {
+            msg!("Claims of tenant {} pay a network fee", self.tenant);
+            return err!(ErrorCode::ClaimNotEscrowable);
+        }
+        match self.status {
+            ClaimStatus::Pending | ClaimStatus::UnderReview | ClaimStatus::OnHold => {
+                Ok(self.amount)
+            }
+            ClaimStatus::Verified | ClaimStatus::PartiallyPaid => {
+                let outstanding = self.outstanding_principal()?;
+                Ok(Amount::lamports(outstanding)
+                    .checked_add(Amount::lamports(self.interest_due(outstanding, now)?))?
+                    .raw)
+            }
+            status => {
+                msg!("A {:?} claim has nothing left to pay", status);
+                err!(ErrorCode::ClaimNotEscrowable)
+            }
+        }
+    }
+
+    /// The approved amount not yet paid.
+    pub fn outstanding_principal(&self) -> Result<u64> {
+        Ok(self.approved_amount()?.saturating_sub(self.principal_paid))
+    }
+
+    /// Marks a verified or partially paid claim as paid at `now` and returns
+    /// the amount owed to the patient: the outstanding approved amount plus
+    /// any late interest on it.
+    pub fn settle_payment(&mut self, now: i64) -> Result<u64> {
+        expect_status(self.status, &PAYABLE_STATUSES, ErrorCode::ClaimNotVerified)?;
+        let outstanding = self.outstanding_principal()?;
+        self.settle_partial_payment(outstanding, now)
+    }
+
+    /// Pays `principal` of the outstanding approved amount at `now` and
+    /// returns it plus late interest on it. The claim is `Paid` once nothing
+    /// is outstanding and `PartiallyPaid` until then.
+    pub fn settle_partial_payment(&mut self, principal: u64, now: i64) -> Result<u64> {
+        expect_status(self.status, &PAYABLE_STATUSES, ErrorCode::ClaimNotVerified)?;
+        self.expect_approved()?;
+        let outstanding = self.outstanding_principal()?;
+        require!(outstanding > 0, ErrorCode::NoApprovedLineItems);
+        if principal == 0 || principal > outstanding {
+            msg!(
+                "Payment of {} must be between 1 and the outstanding {}",
+                principal,
+                outstanding
+            );
+            return err!(ErrorCode::InvalidPaymentAmount);
+        }
+        let interest = self.interest_due(principal, now)?;
+
+        self.principal_paid = Amount::lamports(self.principal_paid)
+            .checked_add(Amount::lamports(principal))?
+            .raw;
+        self.interest_paid = Amount::lamports(self.interest_paid)
+            .checked_add(Amount::lamports(interest))?
+            .raw;
+        self.status = if principal == outstanding {
+            ClaimStatus::Paid
+        } else {
+            ClaimStatus::PartiallyPaid
+        };
+        Ok(Amount::lamports(principal)
+            .checked_add(Amount::lamports(interest))?
+            .raw)
+    }
+
+    /// Undoes a payment that settled `principal` and `interest`, leaving the
+    /// claim `Verified`, or `PartiallyPaid` if other payments remain.
+    pub fn reverse_payment(&mut self, principal: u64, interest: u64) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Paid, ClaimStatus::PartiallyPaid],
+            ErrorCode::InvalidStatusTransition,
+        )?;
+        self.principal_paid = Amount::lamports(self.principal_paid)
+            .checked_sub(Amount::lamports(principal))?
+            .raw;
+        self.interest_paid = Amount::lamports(self.interest_paid)
+            .checked_sub(Amount::lamports(interest))?
+            .raw;
+        self.status = if self.principal_paid == 0 {
+            ClaimStatus::Verified
+        } else {
+            ClaimStatus::PartiallyPaid
+        };
+        Ok(())
+    }
+
+    /// Verifies an undecided claim whose auto-approval deadline passed at
+    /// `now`. Lines the provider already decided keep their decision.
+    pub fn auto_approve(&mut self, now: i64) -> Result<()> {
+        expect_status(
+            self.status,
+            &AUTO_APPROVABLE_STATUSES,
+            ErrorCode::ClaimNotOpen,
+        )?;
+        for item in self
+            .line_items
+            .iter_mut()
+            .filter(|item| item.status == LineItemStatus::Pending)
+        {
+            item.status = LineItemStatus::Approved;
+        }
+        self.verified_at = now;
+        self.status = ClaimStatus::Verified;
+        Ok(())
+    }
+
+    pub fn accept(&mut self) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Pending],
+            ErrorCode::ClaimNotPending,
+        )?;
+        self.status = ClaimStatus::UnderReview;
+        Ok(())
+    }
+
+    pub fn decline(&mut self) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Pending],
+            ErrorCode::ClaimNotPending,
+        )?;
+        self.status = ClaimStatus::Declined;
+        Ok(())
+    }
+
+    /// Checks the claim is pending or declined and no line has been adjudicated.
+    pub fn expect_unadjudicated(&self) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Pending, ClaimStatus::Declined],
+            ErrorCode::ClaimNotPending,
+        )?;
+        if self
+            .line_items
+            .iter()
+            .any(|item| item.status != LineItemStatus::Pending)
+        {
+            msg!("The provider has already adjudicated part of the claim");
+            return err!(ErrorCode::ClaimAlreadyAdjudicated);
+        }
+        Ok(())
+    }
+
+    /// Replaces the external reference and tags after checking their bounds.
+    pub fn set_references(&mut self, external_ref: String, tags: Vec<String>) -> Result<()> {
+        // Claims from before references were recorded have no room for them
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Claim is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        validate_len(
+            "external_ref",
+            &external_ref,
+            MAX_EXTERNAL_REF_LEN,
+            ErrorCode::ExternalRefTooLong,
+        )?;
+        validate_count("tags", tags.len(), MAX_CLAIM_TAGS, ErrorCode::TooManyTags)?;
+        for (i, tag) in tags.iter().enumerate() {
+            validate_not_empty("tag", tag, ErrorCode::TagEmpty)?;
+            validate_len("tag", tag, MAX_TAG_LEN, ErrorCode::TagTooLong)?;
+            if tags[..i].contains(tag) {
+                msg!("Tag {} is listed more than once", tag);
+                return err!(ErrorCode::DuplicateTag);
+            }
+        }
+
+        self.external_ref = external_ref;
+        self.tags = tags;
+        Ok(())
+    }
+
+    /// Puts the claim under `policy`, at `address`, clearing any approvals
+    /// recorded under an earlier one. The billed amount decides whether the
+    /// policy's approvers must all sign off, so a later change to the policy
+    /// does not affect the claim.
+    pub fn attach_approval_policy(
+        &mut self,
+        address: Pubkey,
+        policy: &ApprovalPolicy,
+    ) -> Result<()> {
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Claim is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        self.expect_unpaid()?;
+        self.approval_policy = address;
+        self.required_approvals = policy.required_for(self.amount);
+        self.approvals = 0;
+        Ok(())
+    }
+
+    /// Records the approval of the approver at `bit` in the claim's policy.
+    pub fn approve(&mut self, bit: u8) -> Result<()> {
+        self.expect_unpaid()?;
+        if self.approvals & bit != 0 {
+            msg!("Approver bit {:#04x} was already recorded", bit);
+            return err!(ErrorCode::AlreadyApproved);
+        }
+        self.approvals |= bit;
+        Ok(())
+    }
+
+    /// Fails while an approver the claim's policy requires, or the claim's
+    /// insurer, has not approved.
+    pub fn expect_approved(&self) -> Result<()> {
+        let missing = self.required_approvals & !self.approvals;
+        if missing != 0 {
+            msg!("Approver bits {:#04x} have not approved", missing);
+            return err!(ErrorCode::ApprovalsPending);
+        }
+        if self.insurer != Pubkey::default() && self.insurer_approved_at == 0 {
+            msg!("Insurer {} has not approved the claim", self.insurer);
+            return err!(ErrorCode::InsurerApprovalPending);
+        }
+        Ok(())
+    }
+
+    /// Records the insurer's approval of a verified claim at `now`, or
+    /// rejects the claim when `approve` is false.
+    pub fn adjudicate(&mut self, approve: bool, now: i64) -> Result<()> {
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Claim is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        expect_status(
+            self.status,
+            &[ClaimStatus::Verified],
+            ErrorCode::ClaimNotVerified,
+        )?;
+        if self.insurer_approved_at != 0 {
+            msg!(
+                "The insurer approved the claim at {}",
+                self.insurer_approved_at
+            );
+            return err!(ErrorCode::AlreadyApproved);
+        }
+        if approve {
+            self.insurer_approved_at = now;
+        } else {
+            self.status = ClaimStatus::Rejected;
+            self.rejected_at = now;
+        }
+        Ok(())
+    }
+
+    /// Expires the claim if it is still undecided at its `expires_at`.
+    pub fn expire(&mut self, now: i64) -> Result<()> {
+        // Claims from before expiry was recorded read their version byte as
+        // a deadline
+        if self.account_version != Self::VERSION {
+            msg!(
+                "Claim is at version {}; upgrade it to {} first",
+                self.account_version,
+                Self::VERSION
+            );
+            return err!(ErrorCode::AccountNotUpgraded);
+        }
+        expect_status(
+            self.status,
+            &EXPIRABLE_STATUSES,
+            ErrorCode::ClaimNotExpirable,
+        )?;
+        if self.expires_at == 0 || now < self.expires_at {
+            msg!("The claim expires at {}; now is {}", self.expires_at, now);
+            return err!(ErrorCode::ClaimNotExpired);
+        }
+        self.status = ClaimStatus::Expired;
+        Ok(())
+    }
+
+    fn expect_unpaid(&self) -> Result<()> {
+        if self.principal_paid > 0 || self.status == ClaimStatus::Paid {
+            msg!("The claim has been paid {}", self.principal_paid);
+            return err!(ErrorCode::PaymentStarted);
+        }
+        Ok(())
+    }
+
+    /// Records when the care happened. It must be over by `now`, and must not
+    /// start before `enrolled_since` when the patient's coverage began, if
+    /// that is not 0.
+    pub fn set_service_period(
+        &mut self,
+        service_start: i64,
+        service_end: i64,
+        enrolled_since: i64,
+        now: i64,
+    ) -> Result<()> {
+        if service_start <= 0 || service_start > now {
+            msg!(
+                "Service start {} is not in the past (now {})",
+                service_start,
+                now
+            );
+            return err!(ErrorCode::InvalidServiceDate);
+        }
+        if service_end < service_start || service_end > now {
+            msg!(
+                "Service end {} is not between its start {} and now ({})",
+                service_end,
+                service_start,
+                now
+            );
+            return err!(ErrorCode::InvalidServicePeriod);
+        }
+        if enrolled_since != 0 && service_start < enrolled_since {
+            msg!(
+                "Service started at {}, before the patient enrolled at {}",
+                service_start,
+                enrolled_since
+            );
+            return err!(ErrorCode::ServiceBeforeEnrollment);
+        }
+        self.service_start = service_start;
+        self.service_end = service_end;
+        Ok(())
+    }
+
+    pub fn place_on_hold(&mut self) -> Result<()> {
+        expect_status(self.status, &OPEN_STATUSES, ErrorCode::ClaimNotOpen)?;
+        self.status = ClaimStatus::OnHold;
+        Ok(())
+    }
+
+    pub fn release_hold(&mut self) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::OnHold],
+            ErrorCode::ClaimNotOnHold,
+        )?;
+        self.status = ClaimStatus::UnderReview;
+        Ok(())
+    }
+
+    /// Reverses the rejection of a disputed claim: every line not already
+    /// payable is approved, and the payment window starts over from `now`.
+    pub fn overturn(&mut self, now: i64) -> Result<()> {
+        expect_status(
+            self.status,
+            &[ClaimStatus::Disputed],
+            ErrorCode::ClaimNotDisputed,
+        )?;
+        for item in self
+            .line_items
+            .iter_mut()
+            .filter(|item| !item.status.is_payable())
+        {
+            item.status = LineItemStatus::Approved;
+        }
+        self.verified_at = now;
+        self.status = ClaimStatus::Verified;
+        Ok(())
+    }
+}
+
+/// A patient's challenge to a claim's rejection, at `["claim_dispute", claim]`.
+/// It keeps the arbiter, deadline and bond in force when it was opened, holds
+/// the bond until resolution, and stays after it so a claim is disputed at
+/// most once.
+#[account]
+pub struct ClaimDispute {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub arbiter: Pubkey,
+    /// Hash of the patient's encrypted statement, which is stored off-chain.
+    pub reason_hash: [u8; 32],
+    pub opened_at: i64,
+    /// From this time the dispute can only be decided for the patient.
+    pub deadline: i64,
+    /// Zero while the dispute is open.
+    pub resolved_at: i64,
+    pub patient_prevailed: bool,
+    /// Lamports the patient locked in this account; zero once paid out.
+    pub bond: u64,
+    pub account_version: u8,
+}
+
+impl ClaimDispute {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 32;
+
+    /// Opens a dispute of `claim`, moving it from `Rejected` to `Disputed`.
+    pub fn open(
+        &mut self,
+        claim_key: Pubkey,
+        claim: &mut ClaimAccount,
+        config: &DisputeConfig,
+        reason_hash: [u8; 32],
+        now: i64,
+    ) -> Result<()> {
+        expect_status(
+            claim.status,
+            &[ClaimStatus::Rejected],
+            ErrorCode::ClaimNotRejected,
+        )?;
+        let window_end = claim.rejected_at + REOPEN_WINDOW_SECS;
+        if now > window_end {
+            msg!("Dispute window closed at {}; now is {}", window_end, now);
+            return err!(ErrorCode::DisputeWindowElapsed);
+        }
+        validate_hash("reason_hash", &reason_hash)?;
+
+        self.claim = claim_key;
+        self.patient = claim.patient;
+        self.provider = claim.provider;
+        self.arbiter = config.arbiter;
+        self.reason_hash = reason_hash;
+        self.opened_at = now;
+        self.deadline = now
+            .checked_add(config.resolution_secs)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.resolved_at = 0;
+        self.patient_prevailed = false;
+        self.bond = config.bond_lamports;
+        self.account_version = Self::VERSION;
+        claim.status = ClaimStatus::Disputed;
+        Ok(())
+    }
+
+    /// Decides the dispute. Before the deadline only the arbiter can, as
+    /// `patient_prevails` says; from it anyone can, for the patient.
+    pub fn resolve(
+        &mut self,
+        claim: &mut ClaimAccount,
+        resolver: Pubkey,
+        patient_prevails: bool,
+        now: i64,
+    ) -> Result<()> {
+        if self.resolved_at != 0 {
+            msg!("The dispute was resolved at {}", self.resolved_at);
+            return err!(ErrorCode::ClaimNotDisputed);
+        }
+        let patient_prevails = if now < self.deadline {
+            if resolver != self.arbiter {
+                msg!(
+                    "Only the arbiter can resolve the dispute before {}",
+                    self.deadline
+                );
+                return err!(ErrorCode::Unauthorized);
+            }
+            patient_prevails
+        } else {
+            true
+        };
+
+        if patient_prevails {
+            claim.overturn(now)?;
+        } else {
+            expect_status(
+                claim.status,
+                &[ClaimStatus::Disputed],
+                ErrorCode::ClaimNotDisputed,
+            )?;
+            claim.status = ClaimStatus::Rejected;
+        }
+        self.resolved_at = now;
+        self.patient_prevailed = patient_prevails;
+        Ok(())
+    }
+
+    /// Takes the bond out of a resolved dispute, returning how much to pay:
+    /// to the patient if they prevailed, otherwise to the provider.
+    pub fn release_bond(&mut self) -> Result<u64> {
+        if self.resolved_at == 0 {
+            msg!("The dispute of {} is still open", self.claim);
+            return err!(ErrorCode::ClaimNotDisputed);
+        }
+        Ok(std::mem::take(&mut self.bond))
+    }
+}
+
+/// Lamports a provider put up as collateral against fraud, at
+/// `["provider_stake", provider]`. The account holds the lamports.
+#[account]
+pub struct ProviderStake {
+    pub provider: Pubkey,
+    /// Collateral on top of the account's rent, not counting `unbonding`.
+    pub staked: u64,
+    /// Collateral waiting to be withdrawn; it can still be slashed.
+    pub unbonding: u64,
+    /// From this time `unbonding` can be withdrawn.
+    pub unbonding_at: i64,
+    /// Lamports slashed by fraud findings so far.
+    pub slashed: u64,
+    pub account_version: u8,
+}
+
+impl ProviderStake {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn stake(&mut self, lamports: u64) -> Result<()> {
+        self.staked = Amount::lamports(self.staked)
+            .checked_add(Amount::lamports(lamports))?
+            .raw;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Moves `lamports` from the stake to unbonding, restarting the wait for
+    /// everything unbonding.
+    pub fn unstake(&mut self, lamports: u64, now: i64) -> Result<()> {
+        if lamports == 0 || lamports > self.staked {
+            msg!(
+                "Cannot unstake {} of {} staked lamports",
+                lamports,
+                self.staked
+            );
+            return err!(ErrorCode::InsufficientStake);
+        }
+        self.staked -= lamports;
+        self.unbonding += lamports;
+        self.unbonding_at = now + STAKE_UNBONDING_SECS;
+        Ok(())
+    }
+
+    /// Takes everything unbonding out, returning how much to pay the
+    /// provider.
+    pub fn withdraw(&mut self, now: i64) -> Result<u64> {
+        if self.unbonding == 0 {
+            msg!("Nothing is unbonding");
+            return err!(ErrorCode::NothingToWithdraw);
+        }
+        if now < self.unbonding_at {
+            msg!("The collateral unbonds at {}", self.unbonding_at);
+            return err!(ErrorCode::StakeStillUnbonding);
+        }
+        Ok(std::mem::take(&mut self.unbonding))
+    }
+
+    /// Takes up to `lamports` out, from the stake first and then from what
+    /// is unbonding, returning how much was taken.
+    pub fn slash(&mut self, lamports: u64) -> Result<u64> {
+        let from_staked = lamports.min(self.staked);
+        let from_unbonding = (lamports - from_staked).min(self.unbonding);
+        self.staked -= from_staked;
+        self.unbonding -= from_unbonding;
+        let slashed = from_staked + from_unbonding;
+        self.slashed = Amount::lamports(self.slashed)
+            .checked_add(Amount::lamports(slashed))?
+            .raw;
+        Ok(slashed)
+    }
+}
+
+/// The arbiter's ruling that a provider's rejection of a claim was fraud, at
+/// `["fraud_finding", claim]`. It stays, so a claim is ruled on at most once.
+#[account]
+pub struct FraudFinding {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub patient: Pubkey,
+    pub arbiter: Pubkey,
+    /// Collateral paid to the patient.
+    pub slashed: u64,
+    pub ruled_at: i64,
+    pub account_version: u8,
+}
+
+impl FraudFinding {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 1 + 32;
+
+    /// Records a finding on `dispute` by `arbiter`, who must be the
+    /// dispute's, once the patient has prevailed in it.
+    pub fn rule(&mut self, dispute: &ClaimDispute, arbiter: Pubkey, now: i64) -> Result<()> {
+        if arbiter != dispute.arbiter {
+            msg!(
+                "Only the dispute's arbiter {} can rule fraud",
+                dispute.arbiter
+            );
+            return err!(ErrorCode::Unauthorized);
+        }
+        if dispute.resolved_at == 0 || !dispute.patient_prevailed {
+            msg!(
+                "The patient has not prevailed in the dispute of {}",
+                dispute.claim
+            );
+            return err!(ErrorCode::DisputeNotUpheld);
+        }
+        self.claim = dispute.claim;
+        self.provider = dispute.provider;
+        self.patient = dispute.patient;
+        self.arbiter = arbiter;
+        self.slashed = 0;
+        self.ruled_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// A patient's read grant to their claim's provider, at
+/// `["claim_grant", claim]`. It covers the patient's records attached to the
+/// claim and lasts while the claim is assigned and unfinished, so it follows
+/// the claim through reassignment and ends once the claim is paid, rejected
+/// or declined.
+#[account]
+pub struct ClaimGrant {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    /// When the claim was filed; a grant left behind by a closed claim does
+    /// not carry over to a new claim with the same id.
+    pub filed_at: i64,
+    pub account_version: u8,
+}
+
+impl ClaimGrant {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1 + 32;
+
+    /// Fails unless `requester` may read `record` under the grant for
+    /// `claim`.
+    pub fn authorize(
+        &self,
+        claim: &ClaimAccount,
+        requester: Pubkey,
+        record: &HealthDataAccount,
+    ) -> Result<()> {
+        if self.filed_at != claim.timestamp || self.patient != claim.patient {
+            msg!("The grant was made for an earlier claim at this address");
+            return err!(ErrorCode::ClaimGrantLapsed);
+        }
+        if TERMINAL_STATUSES.contains(&claim.status) {
+            msg!("The claim is {:?}; its grant has lapsed", claim.status);
+            return err!(ErrorCode::ClaimGrantLapsed);
+        }
+        if requester != claim.provider {
+            msg!("{} is not the claim's provider", requester);
+            return err!(ErrorCode::Unauthorized);
+        }
+        if record.owner != claim.patient {
+            msg!(
+                "The record belongs to {}, not the claim's patient",
+                record.owner
+            );
+            return err!(ErrorCode::InvalidPatient);
+        }
+        if !claim.attachments.contains(&record.data_hash) {
+            msg!("Attachment {} is not on the claim", hex(&record.data_hash));
+            return err!(ErrorCode::AttachmentNotFound);
+        }
+        Ok(())
+    }
+}
+
+/// A patient's request for a second provider's opinion on a claim, at
+/// `["second_opinion", claim, consultant]`. The account holds the consult
+/// fee until the opinion is given.
+#[account]
+pub struct SecondOpinion {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub consultant: Pubkey,
+    /// Hashes of the patient's records the consultant may read.
+    pub records: Vec<[u8; 32]>,
+    /// Paid to the consultant with the opinion, on top of the account's rent.
+    pub fee: u64,
+    pub requested_at: i64,
+    /// When the consultant's access ends if no opinion has been given.
+    pub expires_at: i64,
+    /// Hash of the opinion record; zeroed until it is given.
+    pub opinion: [u8; 32],
+    pub submitted_at: i64,
+    pub account_version: u8,
+}
+
+impl SecondOpinion {
+    pub const SPACE: usize =
+        8 + 32 + 32 + 32 + 4 + 32 * MAX_SECOND_OPINION_RECORDS + 8 + 8 + 8 + 32 + 8 + 1 + 32;
+
+    /// Requests the opinion on `claim`, whose address the caller sets.
+    pub fn request(
+        &mut self,
+        claim: &ClaimAccount,
+        consultant: Pubkey,
+        records: Vec<[u8; 32]>,
+        fee: u64,
+        expires_at: i64,
+        now: i64,
+    ) -> Result<()> {
+        if consultant == claim.provider || consultant == claim.patient {
+            msg!("{} is a party to the claim", consultant);
+            return err!(ErrorCode::InvalidConsultant);
+        }
+        if records.is_empty() || fee == 0 || expires_at <= now {
+            msg!(
+                "{} records, fee {}, expiry {} at {}; need records, a fee and a future expiry",
+                records.len(),
+                fee,
+                expires_at,
+                now
+            );
+            return err!(ErrorCode::InvalidSecondOpinion);
+        }
+        validate_count(
+            "records",
+            records.len(),
+            MAX_SECOND_OPINION_RECORDS,
+            ErrorCode::InvalidSecondOpinion,
+        )?;
+        for record in &records {
+            validate_hash("records", record)?;
+        }
+        self.patient = claim.patient;
+        self.consultant = consultant;
+        self.records = records;
+        self.fee = fee;
+        self.requested_at = now;
+        self.expires_at = expires_at;
+        self.opinion = [0; 32];
+        self.submitted_at = 0;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Whether the opinion is still awaited: not yet given and not expired.
+    pub fn is_open(&self, now: i64) -> bool {
+        self.opinion == [0; 32] && now < self.expires_at
+    }
+
+    /// Fails unless `requester` may read `record` for the opinion.
+    pub fn authorize(&self, requester: Pubkey, record: &HealthDataAccount, now: i64) -> Result<()> {
+        if requester != self.consultant {
+            msg!("{} is not the consultant", requester);
+            return err!(ErrorCode::Unauthorized);
+        }
+        if !self.is_open(now) {
+            msg!(
+                "The opinion was given or the request expired at {}",
+                self.expires_at
+            );
+            return err!(ErrorCode::SecondOpinionClosed);
+        }
+        if record.owner != self.patient {
+            msg!("The record belongs to {}, not the patient", record.owner);
+            return err!(ErrorCode::InvalidPatient);
+        }
+        if !self.records.contains(&record.data_hash) {
+            msg!("Record {} was not shared", hex(&record.data_hash));
+            return err!(ErrorCode::RecordNotShared);
+        }
+        Ok(())
+    }
+
+    /// Records the opinion `opinion`, given at `now`.
+    pub fn submit(&mut self, opinion: [u8; 32], now: i64) -> Result<()> {
+        if !self.is_open(now) {
+            msg!(
+                "The opinion was given or the request expired at {}",
+                self.expires_at
+            );
+            return err!(ErrorCode::SecondOpinionClosed);
+        }
+        self.opinion = opinion;
+        self.submitted_at = now;
+        Ok(())
+    }
+
+    /// Fails while the consultant can still give the opinion.
+    pub fn expect_closable(&self, now: i64) -> Result<()> {
+        if self.is_open(now) {
+            msg!(
+                "The consultant has until {} to give the opinion",
+                self.expires_at
+            );
+            return err!(ErrorCode::SecondOpinionPending);
+        }
+        Ok(())
+    }
+}
+
+/// Providers caring for a patient together, at
+/// `["care_team", patient, team_id]`. Members read the records the patient
+/// designates for the team and those attached to the patient's claims.
+#[account]
+pub struct CareTeam {
+    pub patient: Pubkey,
+    pub team_id: u32,
+    /// The members' wallets.
+    pub members: Vec<Pubkey>,
+    /// Hashes of the patient's records designated for the team.
+    pub records: Vec<[u8; 32]>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl CareTeam {
+    pub const SPACE: usize = 8
+        + 32
+        + 4
+        + (4 + 32 * MAX_CARE_TEAM_MEMBERS)
+        + (4 + 32 * MAX_CARE_TEAM_RECORDS)
+        + 8
+        + 1
+        + 32;
+
+    pub fn set(
+        &mut self,
+        patient: Pubkey,
+        team_id: u32,
+        members: Vec<Pubkey>,
+        records: Vec<[u8; 32]>,
+        now: i64,
+    ) -> Result<()> {
+        if members.is_empty() {
+            msg!("A care team needs at least one member");
+            return err!(ErrorCode::InvalidCareTeam);
+        }
+        validate_count(
+            "members",
+            members.len(),
+            MAX_CARE_TEAM_MEMBERS,
+            ErrorCode::InvalidCareTeam,
+        )?;
+        validate_count(
+            "records",
+            records.len(),
+            MAX_CARE_TEAM_RECORDS,
+            ErrorCode::InvalidCareTeam,
+        )?;
+        for (index, member) in members.iter().enumerate() {
+            if *member == patient || members[..index].contains(member) {
+                msg!("Member {} is the patient or listed twice", member);
+                return err!(ErrorCode::InvalidCareTeam);
+            }
+        }
+        for record in &records {
+            validate_hash("records", record)?;
+        }
+        self.patient = patient;
+        self.team_id = team_id;
+        self.members = members;
+        self.records = records;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Fails unless `requester` is a member who may read `record`, which is
+    /// designated for the team or attached to `claim`.
+    pub fn authorize(
+        &self,
+        requester: Pubkey,
+        record: &HealthDataAccount,
+        claim: Option<&ClaimAccount>,
+    ) -> Result<()> {
+        if !self.members.contains(&requester) {
+            msg!("{} is not on the care team", requester);
+            return err!(ErrorCode::Unauthorized);
+        }
+        if record.owner != self.patient {
+            msg!("The record belongs to {}, not the patient", record.owner);
+            return err!(ErrorCode::InvalidPatient);
+        }
+        if self.records.contains(&record.data_hash) {
+            return Ok(());
+        }
+        match claim {
+            Some(claim)
+                if claim.patient == self.patient
+                    && claim.attachments.contains(&record.data_hash) =>
+            {
+                Ok(())
+            }
+            _ => {
+                msg!(
+                    "Record {} is neither designated for the team nor attached to the claim",
+                    hex(&record.data_hash)
+                );
+                err!(ErrorCode::CareTeamRecordNotShared)
+            }
+        }
+    }
+}
+
+/// A provider's prices for procedure codes, at `["fee_schedule", provider]`.
+#[account]
+pub struct FeeSchedule {
+    pub provider: Pubkey,
+    pub entries: Vec<FeeScheduleEntry>,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl FeeSchedule {
+    pub const SPACE: usize =
+        8 + 32 + 4 + MAX_FEE_SCHEDULE_ENTRIES * FeeScheduleEntry::SPACE + 8 + 1 + 32;
+
+    pub fn set(
+        &mut self,
+        provider: Pubkey,
+        entries: Vec<FeeScheduleEntry>,
+        now: i64,
+    ) -> Result<()> {
+        validate_count(
+            "entries",
+            entries.len(),
+            MAX_FEE_SCHEDULE_ENTRIES,
+            ErrorCode::InvalidFeeSchedule,
+        )?;
+        for (i, entry) in entries.iter().enumerate() {
+            validate_code(&entry.code)?;
+            if entry.unit_price == 0 || entries[..i].iter().any(|other| other.code == entry.code) {
+                msg!("Code {} is free or priced twice", entry.code);
+                return err!(ErrorCode::InvalidFeeSchedule);
+            }
+        }
+        self.provider = provider;
+        self.entries = entries;
+        self.updated_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// The price of one unit of `code`.
+    pub fn price_of(&self, code: &str) -> Result<u64> {
+        match self.entries.iter().find(|entry| entry.code == code) {
+            Some(entry) => Ok(entry.unit_price),
+            None => {
+                msg!("Code {} is not in the fee schedule", code);
+                err!(ErrorCode::CodeNotInFeeSchedule)
+            }
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FeeScheduleEntry {
+    pub code: String,
+    pub unit_price: u64,
+}
+
+impl FeeScheduleEntry {
+    pub const SPACE: usize = 4 + MAX_CODE_LEN + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppointmentStatus {
+    Scheduled,
+    Completed,
+    /// A claim has been filed for it.
+    Billed,
+}
+
+/// A procedure performed during an appointment.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AppointmentService {
+    pub code: String,
+    pub units: u32,
+}
+
+impl AppointmentService {
+    pub const SPACE: usize = 4 + MAX_CODE_LEN + 4;
+}
+
+/// A patient's visit with a provider, at
+/// `["appointment", provider, appointment_id]`. Once billed it links the
+/// visit, its session records and its claim.
+#[account]
+pub struct Appointment {
+    pub appointment_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub scheduled_at: i64,
+    pub status: AppointmentStatus,
+    /// Set when the appointment is completed.
+    pub services: Vec<AppointmentService>,
+    /// Hashes of the records made during the session.
+    pub session_records: Vec<[u8; 32]>,
+    pub completed_at: i64,
+    /// The claim filed for the appointment, or `Pubkey::default()`.
+    pub claim: Pubkey,
+    pub account_version: u8,
+}
+
+impl Appointment {
+    pub const SPACE: usize = 8
+        + 4
+        + MAX_SEED_ID_LEN
+        + 32
+        + 32
+        + 8
+        + 1
+        + 4
+        + MAX_LINE_ITEMS * AppointmentService::SPACE
+        + 4
+        + MAX_ATTACHMENTS * 32
+        + 8
+        + 32
+        + 1
+        + 32;
+
+    pub fn schedule(
+        &mut self,
+        appointment_id: String,
+        patient: Pubkey,
+        provider: Pubkey,
+        scheduled_at: i64,
+    ) -> Result<()> {
+        validate_not_empty(
+            "appointment_id",
+            &appointment_id,
+            ErrorCode::InvalidAppointment,
+        )?;
+        validate_len(
+            "appointment_id",
+            &appointment_id,
+            MAX_SEED_ID_LEN,
+            ErrorCode::InvalidAppointment,
+        )?;
+        if patient == provider || scheduled_at <= 0 {
+            msg!("Appointments need a time and a provider other than the patient");
+            return err!(ErrorCode::InvalidAppointment);
+        }
+        self.appointment_id = appointment_id;
+        self.patient = patient;
+        self.provider = provider;
+        self.scheduled_at = scheduled_at;
+        self.status = AppointmentStatus::Scheduled;
+        self.services = Vec::new();
+        self.session_records = Vec::new();
+        self.completed_at = 0;
+        self.claim = Pubkey::default();
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn complete(
+        &mut self,
+        services: Vec<AppointmentService>,
+        session_records: Vec<[u8; 32]>,
+        now: i64,
+    ) -> Result<()> {
+        self.expect_status(AppointmentStatus::Scheduled)?;
+        if now < self.scheduled_at || services.is_empty() {
+            msg!(
+                "{} services at {}; the appointment starts at {}",
+                services.len(),
+                now,
+                self.scheduled_at
+            );
+            return err!(ErrorCode::InvalidAppointment);
+        }
+        validate_count(
+            "services",
+            services.len(),
+            MAX_LINE_ITEMS,
+            ErrorCode::TooManyLineItems,
+        )?;
+        validate_count(
+            "session_records",
+            session_records.len(),
+            MAX_ATTACHMENTS,
+            ErrorCode::TooManyAttachments,
+        )?;
+        for service in &services {
+            validate_code(&service.code)?;
+            if service.units == 0 {
+                msg!("Service {} has zero units", service.code);
+                return err!(ErrorCode::ZeroUnits);
+            }
+        }
+        for record in &session_records {
+            validate_hash("session_records", record)?;
+        }
+        self.services = services;
+        self.session_records = session_records;
+        self.completed_at = now;
+        self.status = AppointmentStatus::Completed;
+        Ok(())
+    }
+
+    /// The claim's line items: each service at its unit price in `schedule`.
+    pub fn line_items(&self, schedule: &FeeSchedule) -> Result<Vec<LineItemInput>> {
+        self.expect_status(AppointmentStatus::Completed)?;
+        self.services
+            .iter()
+            .map(|service| {
+                Ok(LineItemInput {
+                    code: service.code.clone(),
+                    units: service.units,
+                    unit_price: schedule.price_of(&service.code)?,
+                    modifier: String::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Links the appointment to its claim, so it is not billed twice.
+    pub fn bill(&mut self, claim: Pubkey) -> Result<()> {
+        self.expect_status(AppointmentStatus::Completed)?;
+        self.claim = claim;
+        self.status = AppointmentStatus::Billed;
+        Ok(())
+    }
+
+    fn expect_status(&self, expected: AppointmentStatus) -> Result<()> {
+        if self.status != expected {
+            msg!("Appointment is {:?}; expected {:?}", self.status, expected);
+            return err!(ErrorCode::AppointmentStatusMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// A provider's break-glass request for one of a patient's records, at
+/// `["emergency_access", data_hash, provider]`. Kept until closed as the
+/// audit trail of the access.
+#[account]
+pub struct EmergencyAccess {
+    pub data_hash: [u8; 32],
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    /// Hash of the provider's off-chain justification.
+    pub justification_hash: [u8; 32],
+    pub requested_at: i64,
+    /// When the access starts and the patient can no longer veto it.
+    pub available_at: i64,
+    pub expires_at: i64,
+    /// When the patient vetoed the request; 0 if they did not.
+    pub vetoed_at: i64,
+    pub account_version: u8,
+}
+
+impl EmergencyAccess {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn request(
+        &mut self,
+        record: &HealthDataAccount,
+        provider: Pubkey,
+        justification_hash: [u8; 32],
+        now: i64,
+    ) -> Result<()> {
+        if provider == record.owner {
+            msg!("{} owns the record", provider);
+            return err!(ErrorCode::InvalidEmergencyAccess);
+        }
+        validate_hash("justification_hash", &justification_hash)?;
+        self.data_hash = record.data_hash;
+        self.patient = record.owner;
+        self.provider = provider;
+        self.justification_hash = justification_hash;
+        self.requested_at = now;
+        self.available_at = now + EMERGENCY_ACCESS_DELAY_SECS;
+        self.expires_at = self.available_at + EMERGENCY_ACCESS_DURATION_SECS;
+        self.vetoed_at = 0;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    pub fn veto(&mut self, now: i64) -> Result<()> {
+        if self.vetoed_at != 0 {
+            msg!("The request was vetoed at {}", self.vetoed_at);
+            return err!(ErrorCode::EmergencyAccessVetoed);
+        }
+        if now >= self.available_at {
+            msg!("The veto window closed at {}", self.available_at);
+            return err!(ErrorCode::EmergencyVetoWindowClosed);
+        }
+        self.vetoed_at = now;
+        Ok(())
+    }
+
+    /// Fails unless `requester` is the provider and the access is open.
+    pub fn authorize(&self, requester: Pubkey, now: i64) -> Result<()> {
+        if requester != self.provider {
+            msg!("{} did not request the access", requester);
+            return err!(ErrorCode::Unauthorized);
+        }
+        if self.vetoed_at != 0 {
+            msg!("The patient vetoed the request at {}", self.vetoed_at);
+            return err!(ErrorCode::EmergencyAccessVetoed);
+        }
+        if now < self.available_at {
+            msg!("Access starts at {}", self.available_at);
+            return err!(ErrorCode::EmergencyAccessPending);
+        }
+        if now >= self.expires_at {
+            msg!("Access expired at {}", self.expires_at);
+            return err!(ErrorCode::EmergencyAccessExpired);
+        }
+        Ok(())
+    }
+
+    /// Fails until the request is vetoed or has expired.
+    pub fn expect_closable(&self, now: i64) -> Result<()> {
+        if self.vetoed_at == 0 && now < self.expires_at {
+            msg!("Access lasts until {}", self.expires_at);
+            return err!(ErrorCode::EmergencyAccessActive);
+        }
+        Ok(())
+    }
+}
+
+/// Counter of a patient's audit entries, at `["audit_trail", subject]`.
+#[account]
+pub struct AuditTrail {
+    pub subject: Pubkey,
+    /// Entries appended so far; the next one takes this as its sequence.
+    pub entry_count: u64,
+    pub account_version: u8,
+}
+
+impl AuditTrail {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 32;
+
+    /// Fills in `entry` as the trail's next entry and counts it.
+    pub fn append(
+        &mut self,
+        subject: Pubkey,
+        entry: &mut AuditEntry,
+        actor: Pubkey,
+        action: AuditAction,
+        target: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        self.subject = subject;
+        self.account_version = Self::VERSION;
+        *entry = AuditEntry {
+            subject,
+            sequence: self.entry_count,
+            actor,
+            action,
+            target,
+            timestamp: now,
+            account_version: AuditEntry::VERSION,
+        };
+        self.entry_count = self
+            .entry_count
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        Ok(())
+    }
+}
+
+/// One action on a patient's data or claims, at
+/// `["audit_entry", subject, sequence]`. Never modified or closed.
+#[account]
+pub struct AuditEntry {
+    pub subject: Pubkey,
+    pub sequence: u64,
+    pub actor: Pubkey,
+    pub action: AuditAction,
+    /// The account acted on, such as a record, consent or claim.
+    pub target: Pubkey,
+    pub timestamp: i64,
+    pub account_version: u8,
+}
+
+impl AuditEntry {
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 1 + 32 + 8 + 1 + 32;
+}
+
+/// A claim's opt-in to auto-approval, at `["auto_approval", claim]`.
+#[account]
+pub struct AutoApproval {
+    pub claim: Pubkey,
+    /// When `auto_approve_claim` may verify the claim.
+    pub deadline: i64,
+    pub created_at: i64,
+    pub account_version: u8,
+}
+
+impl AutoApproval {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 32;
+}
+
+/// Installments agreed for paying a verified claim, at
+/// `["payment_schedule", claim]`.
+#[account]
+pub struct PaymentSchedule {
+    pub claim: Pubkey,
+    pub installment_count: u8,
+    pub installments_paid: u8,
+    /// Installment `n`, counting from 1, is due `n * interval_secs` after
+    /// `starts_at`.
+    pub interval_secs: i64,
+    pub starts_at: i64,
+    pub account_version: u8,
+}
+
+impl PaymentSchedule {
+    pub const SPACE: usize = 8 + 32 + 1 + 1 + 8 + 8 + 1 + 32;
+
+    pub fn set(
+        &mut self,
+        claim: Pubkey,
+        installment_count: u8,
+        interval_secs: i64,
+        now: i64,
+    ) -> Result<()> {
+        if !(2..=MAX_INSTALLMENTS).contains(&installment_count) || interval_secs <= 0 {
+            msg!(
+                "{} installments {} seconds apart; must be 2 to {} a positive interval apart",
+                installment_count,
+                interval_secs,
+                MAX_INSTALLMENTS
+            );
+            return err!(ErrorCode::InvalidPaymentSchedule);
+        }
+        self.claim = claim;
+        self.installment_count = installment_count;
+        self.installments_paid = 0;
+        self.interval_secs = interval_secs;
+        self.starts_at = now;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// When installment `installment`, counting from 1, is due.
+    pub fn due_at(&self, installment: u8) -> i64 {
+        self.starts_at
+            .saturating_add(self.interval_secs.saturating_mul(installment as i64))
+    }
+
+    /// The least principal the next installment must pay of `outstanding`:
+    /// an equal share of what remains, rounded up, so the last installment
+    /// pays the rest.
+    pub fn installment_due(&self, outstanding: u64) -> u64 {
+        let remaining = self
+            .installment_count
+            .saturating_sub(self.installments_paid)
+            .max(1);
+        outstanding.div_ceil(remaining as u64)
+    }
+
+    /// Counts a payment of `principal` out of `outstanding` as the next
+    /// installment and returns its number. Paying everything completes the
+    /// schedule early.
+    pub fn record(&mut self, principal: u64, outstanding: u64) -> Result<u8> {
+        let due = self.installment_due(outstanding);
+        if principal < due {
+            msg!(
+                "Payment of {} is less than the installment of {} due",
+                principal,
+                due
+            );
+            return err!(ErrorCode::InstallmentTooSmall);
+        }
+        let installment = self.installments_paid.saturating_add(1);
+        self.installments_paid = if principal >= outstanding {
+            self.installment_count
+        } else {
+            installment
+        };
+        Ok(installment)
+    }
+}
+
+/// Marks a service as billed, at `["claim_dedup", fingerprint]` where the
+/// fingerprint is [`ClaimDedup::fingerprint`] of the claim.
+#[account]
+pub struct ClaimDedup {
+    pub claim: Pubkey,
+    pub service_date: i64,
+    pub created_at: i64,
+    pub account_version: u8,
+}
+
+impl ClaimDedup {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 32;
+
+    /// Hash of who billed whom for which procedures on which day. Codes are
+    /// sorted so reordering the lines yields the same fingerprint, and the
+    /// service date is truncated to its UTC day.
+    pub fn fingerprint(
+        patient: &Pubkey,
+        provider: &Pubkey,
+        line_items: &[LineItemInput],
+        service_date: i64,
+    ) -> [u8; 32] {
+        Self::fingerprint_codes(
+            patient,
+            provider,
+            line_items.iter().map(|item| item.code.as_str()),
+            service_date,
+        )
+    }
+
+    /// [`ClaimDedup::fingerprint`] from the procedure codes alone.
+    pub fn fingerprint_codes<'a>(
+        patient: &Pubkey,
+        provider: &Pubkey,
+        codes: impl Iterator<Item = &'a str>,
+        service_date: i64,
+    ) -> [u8; 32] {
+        let mut codes: Vec<&str> = codes.collect();
+        codes.sort_unstable();
+        let day = service_date.div_euclid(24 * 60 * 60).to_le_bytes();
+        let lens: Vec<[u8; 4]> = codes
+            .iter()
+            .map(|code| (code.len() as u32).to_le_bytes())
+            .collect();
+        let mut parts: Vec<&[u8]> = vec![patient.as_ref(), provider.as_ref(), &day];
+        for (code, len) in codes.iter().zip(&lens) {
+            parts.push(len);
+            parts.push(code.as_bytes());
+        }
+        hashv(&parts).to_bytes()
+    }
+}
+
+/// Anomaly counters over the claims filed against a provider, at
+/// `["provider_stats", provider]`. Fee-schedule deviation is not tracked
+/// because there is no fee schedule to deviate from yet.
+#[account]
+pub struct ProviderClaimStats {
+    pub provider: Pubkey,
+    /// UTC day number (days since the epoch) that `claims_today` counts.
+    pub day: i64,
+    pub claims_today: u32,
+    pub total_claims: u64,
+    /// Claims billing the same procedure code on more than one line.
+    pub duplicate_code_claims: u64,
+    /// Disputes of the provider's claims the arbiter ruled fraud.
+    pub fraud_findings: u64,
+    pub account_version: u8,
+}
+
+impl ProviderClaimStats {
+    pub const SPACE: usize = 8 + 32 + 8 + 4 + 8 + 8 + 8 + 1 + 32;
+
+    /// Counts a fraud finding against `provider`.
+    pub fn record_fraud_finding(&mut self, provider: Pubkey) -> Result<()> {
+        self.provider = provider;
+        self.fraud_findings = self
+            .fraud_findings
+            .checked_add(1)
+            .ok_or(ErrorCode::AmountOverflow)?;
+        self.account_version = Self::VERSION;
+        Ok(())
+    }
+
+    /// Counts one claim filed at `now` and returns each threshold it newly
+    /// crossed as `(kind, value, threshold)`. Each signal fires once per
+    /// crossing, not on every claim past it.
+    pub fn record_claim(
+        &mut self,
+        now: i64,
+        duplicate_codes: bool,
+    ) -> Vec<(FraudSignalKind, u64, u64)> {
+        let mut signals = Vec::new();
+
+        let day = now.div_euclid(24 * 60 * 60);
+        if day != self.day {
+            self.day = day;
+            self.claims_today = 0;
+        }
+        self.claims_today = self.claims_today.saturating_add(1);
+        if self.claims_today == FRAUD_MAX_CLAIMS_PER_DAY + 1 {
+            signals.push((
+                FraudSignalKind::ClaimsPerDay,
+                self.claims_today as u64,
+                FRAUD_MAX_CLAIMS_PER_DAY as u64,
+            ));
+        }
+
+        let was_over = self.duplicate_code_bps() > FRAUD_MAX_DUPLICATE_CODE_BPS;
+        self.total_claims = self.total_claims.saturating_add(1);
+        if duplicate_codes {
+            self.duplicate_code_claims = self.duplicate_code_claims.saturating_add(1);
+        }
+        let share = self.duplicate_code_bps();
+        if !was_over && share > FRAUD_MAX_DUPLICATE_CODE_BPS {
+            signals.push((
+                FraudSignalKind::DuplicateCodes,
+                share,
+                FRAUD_MAX_DUPLICATE_CODE_BPS,
+            ));
+        }
+        signals
+    }
+
+    /// Share of claims with duplicate codes in basis points, or 0 until the
+    /// provider has `FRAUD_MIN_CLAIM_SAMPLE` claims.
+    pub fn duplicate_code_bps(&self) -> u64 {
+        if self.total_claims < FRAUD_MIN_CLAIM_SAMPLE {
+            return 0;
+        }
+        self.duplicate_code_claims.saturating_mul(10_000) / self.total_claims
+    }
+}
+
+/// One claim in `create_claims_batch`, with the arguments `create_claim`
+/// takes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimInput {
+    pub claim_id: String,
+    pub line_items: Vec<LineItemInput>,
+    pub attachments: Vec<[u8; 32]>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub fingerprint: [u8; 32],
+}
+
+/// A billed line as submitted with `create_claim`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LineItemInput {
+    pub code: String,
+    pub units: u32,
+    pub unit_price: u64,
+    pub modifier: String,
+}
+
+/// A billed line and its adjudication.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimLineItem {
+    /// Procedure code (e.g. CPT or HCPCS).
+    pub code: String,
+    pub units: u32,
+    pub unit_price: u64,
+    pub modifier: String,
+    pub status: LineItemStatus,
+    /// Adjustment reason code recorded with the adjudication decision.
+    pub reason_code: u16,
+}
+
+impl ClaimLineItem {
+    pub const SPACE: usize = 4 + MAX_CODE_LEN + 4 + 8 + 4 + MAX_MODIFIER_LEN + 1 + 2;
+
+    pub fn total(&self) -> Result<u64> {
+        Ok(Amount::lamports(self.unit_price)
+            .checked_mul(self.units as u64)?
+            .raw)
+    }
+}
+
+/// Adjudication state of a line; `Approved` and `DownCoded` lines are paid.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineItemStatus {
+    Pending,
+    Approved,
+    Denied,
+    DownCoded,
+}
+
+impl LineItemStatus {
+    pub fn is_payable(&self) -> bool {
+        matches!(self, LineItemStatus::Approved | LineItemStatus::DownCoded)
+    }
+}
+
+/// Adjudicator's decision on a line. A down-code may not raise the unit price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum LineDecision {
+    Approve,
+    Deny,
+    DownCode { code: String, unit_price: u64 },
+}
+
+/// Up to `NOTES_PER_PAGE` notes, at `["claim_notes", claim, page (u32 LE)]`.
+#[account]
+pub struct ClaimNotesPage {
+    pub claim: Pubkey,
+    pub page: u32,
+    pub notes: Vec<ClaimNote>,
+    pub account_version: u8,
+}
+
+/// A note by the patient or provider on a claim.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimNote {
+    pub author: Pubkey,
+    /// Hash of the encrypted note body, which is stored off-chain.
+    pub note_hash: String,
+    pub timestamp: i64,
+}
+
+impl ClaimNote {
+    pub const SPACE: usize = 32 + 4 + MAX_HASH_LEN + 8;
+}
+
+/// Claim lifecycle: `Pending` → `UnderReview` once the provider accepts →
+/// `Verified` → `Paid`, possibly through `PartiallyPaid`, or `Rejected` →
+/// `UnderReview` once via `reopen_claim`. A pending claim the provider turns
+/// down is `Declined` until reassigned. An open claim can be put `OnHold` for
+/// more documentation and released back to `UnderReview`. A rejected claim
+/// the patient disputes is `Disputed` until the arbiter sends it back to
+/// `Rejected` or on to `Verified`. A claim still undecided at its
+/// `expires_at` can be made `Expired` by anyone.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClaimStatus {
+    Pending,
+    Verified,
+    Paid,
+    Rejected,
+    UnderReview,
+    OnHold,
+    PartiallyPaid,
+    Declined,
+    Disputed,
+    Expired,
+}
+
+/// Statuses in which a claim can still be adjudicated.
+pub const OPEN_STATUSES: [ClaimStatus; 1] = [ClaimStatus::UnderReview];
+
+/// Statuses in which an undecided claim can be auto-approved: not yet
+/// accepted, or accepted and still under review.
+pub const AUTO_APPROVABLE_STATUSES: [ClaimStatus; 2] =
+    [ClaimStatus::Pending, ClaimStatus::UnderReview];
+
+/// Statuses in which an undecided claim can expire.
+pub const EXPIRABLE_STATUSES: [ClaimStatus; 3] = [
+    ClaimStatus::Pending,
+    ClaimStatus::UnderReview,
+    ClaimStatus::OnHold,
+];
+
+/// Statuses in which some of a claim's approved amount is still payable.
+pub const PAYABLE_STATUSES: [ClaimStatus; 2] = [ClaimStatus::Verified, ClaimStatus::PartiallyPaid];
+
+/// Statuses in which the claim's provider is done with it, ending its grant.
+pub const TERMINAL_STATUSES: [ClaimStatus; 4] = [
+    ClaimStatus::Paid,
+    ClaimStatus::Rejected,
+    ClaimStatus::Declined,
+    ClaimStatus::Expired,
+];
+
+/// Statuses in which a claim is finished and can be closed.
+pub const CLOSABLE_STATUSES: [ClaimStatus; 4] = [
+    ClaimStatus::Paid,
+    ClaimStatus::Rejected,
+    ClaimStatus::Declined,
+    ClaimStatus::Expired,
+];
+
+impl ClaimStatus {
+    /// Whether the claim can still be adjudicated.
+    pub fn is_open(&self) -> bool {
+        OPEN_STATUSES.contains(self)
+    }
+}
+
+/// Kind of clinical data a record holds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataCategory {
+    General,
+    LabResult,
+    Imaging,
+    Prescription,
+    VisitNote,
+    Vitals,
+    Immunization,
+}
+
+impl DataCategory {
+    /// This category's bit in a category mask.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsentAction {
+    Grant,
+    Revoke,
+    PolicyUpdate,
+    Delegate,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    RecordAccessed,
+    ConsentGranted,
+    ConsentRevoked,
+    ClaimFiled,
+    ClaimReviewed,
+    ClaimPaid,
+    EmergencyAccess,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FraudSignalKind {
+    ClaimsPerDay,
+    DuplicateCodes,
+}
+
+/// Emitted when a provider's claim counters cross a fraud heuristic threshold.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FraudSignal {
+    pub provider: Pubkey,
+    pub kind: FraudSignalKind,
+    pub value: u64,
+    pub threshold: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an arbiter rules a provider's rejection of a claim fraud.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FraudRuled {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub patient: Pubkey,
+    pub arbiter: Pubkey,
+    /// Collateral paid to the patient.
+    pub slashed: u64,
+    /// The provider's fraud findings, this one included.
+    pub fraud_findings: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every consent action.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsentReceipt {
+    pub patient: Pubkey,
+    /// The patient, or the guardian acting for them.
+    pub actor: Pubkey,
+    /// `Pubkey::default()` for policy updates, which apply to everyone.
+    pub grantee: Pubkey,
+    pub action: ConsentAction,
+    pub scope: u8,
+    pub lawful_basis: LawfulBasis,
+    pub expires_at: i64,
+    pub timestamp: i64,
+    /// Position in the patient's consent ledger; 0 when no ledger was passed.
+    pub sequence: u64,
+}
+
+/// Emitted when a patient account is registered.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatientRegistered {
+    pub patient: Pubkey,
+    pub authority: Pubkey,
+    pub did: String,
+    pub timestamp: i64,
+}
+
+/// Emitted when a party registers or rotates its encryption key.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionKeyUpdated {
+    pub owner: Pubkey,
+    pub x25519_key: [u8; 32],
+    /// 1 on registration.
+    pub key_version: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when a provider asks for a record key wrapped to them.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReencryptionRequested {
+    pub request: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub record: Pubkey,
+    pub recipient_key: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Emitted when a patient posts the wrapped key for a re-encryption request.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReencryptionFulfilled {
+    pub request: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub record: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted for each record stored, once per entry of a batch.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthDataSubmitted {
+    /// The health-data account, or the batch account holding the entry.
+    pub record: Pubkey,
+    pub owner: Pubkey,
+    pub author: Pubkey,
+    pub data_hash: [u8; 32],
+    pub category: DataCategory,
+    pub timestamp: i64,
+}
+
+/// Emitted after the `ClaimCreated` of a claim filed for an appointment.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppointmentBilled {
+    pub appointment: Pubkey,
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim is filed, in or outside a tenant.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimCreated {
+    pub claim: Pubkey,
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    /// The billed amount.
+    pub amount: u64,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim is verified by its provider or auto-approved.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimVerified {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub approved_amount: u64,
+    pub auto_approved: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim is rejected after review or declined unreviewed.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimRejected {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    /// `Rejected` or `Declined`.
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+}
+
+/// Emitted when an undecided claim expires.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimExpired {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub expires_at: i64,
+    /// Escrowed lamports returned to the provider, or 0 without an escrow.
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a patient disputes a claim's rejection.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimDisputed {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub arbiter: Pubkey,
+    pub reason_hash: [u8; 32],
+    pub deadline: i64,
+    /// Lamports locked by the patient.
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim dispute is decided, by the arbiter or the deadline.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisputeResolved {
+    pub claim: Pubkey,
+    pub resolver: Pubkey,
+    pub patient_prevailed: bool,
+    /// `Verified` or `Rejected`.
+    pub status: ClaimStatus,
+    /// Lamports of bond refunded to the patient if they prevailed, otherwise
+    /// forfeited to the provider.
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a held claim payment is clawed back to the provider.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentClawedBack {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub patient: Pubkey,
+    /// Lamports returned to the provider.
+    pub amount: u64,
+    /// The claim's total principal paid, after the clawback.
+    pub principal_paid: u64,
+    /// `Verified` or `PartiallyPaid`.
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+}
+
+/// Emitted when a facilitator is approved, rotates its signing key or is
+/// revoked.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FacilitatorUpdated {
+    pub facilitator: Pubkey,
+    pub authority: Pubkey,
+    pub signing_key: Pubkey,
+    pub fee_share_bps: u16,
+    pub revoked: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim payment is attributed to a facilitator.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentAttributed {
+    pub facilitator: Pubkey,
+    pub claim: Pubkey,
+    pub amount: u64,
+    /// The facilitator's share of the protocol fee.
+    pub fee_share: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a patient pays a month's premium.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PremiumPaid {
+    pub tenant: Pubkey,
+    pub patient: Pubkey,
+    pub period: u32,
+    /// The plan's premium, before the discount.
+    pub premium: u64,
+    pub discount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a verified claim takes the patient past their plan's
+/// out-of-pocket maximum and the provider covers the rest of their share.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutOfPocketMaxReached {
+    pub claim: Pubkey,
+    pub tenant: Pubkey,
+    pub patient: Pubkey,
+    pub year: u32,
+    /// The claim's patient share moved to the provider.
+    pub payer_covered: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a provider keeps part of the protocol fee on a claim payment
+/// for their volume in the period.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeRebated {
+    pub provider: Pubkey,
+    pub claim: Pubkey,
+    pub period: u32,
+    /// The provider's volume in the period, counting the payment.
+    pub volume: u64,
+    pub rebate_bps: u16,
+    pub rebate: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a keeper is paid for running a crank on `target`.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrankRewarded {
+    pub cranker: Pubkey,
+    pub target: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a claim's insurer approves it for payment.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsurerApproved {
+    pub claim: Pubkey,
+    pub insurer: Pubkey,
+    pub approved_amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a consultant gives a second opinion and is paid its fee.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecondOpinionGiven {
+    pub claim: Pubkey,
+    pub consultant: Pubkey,
+    /// Hash of the opinion record.
+    pub opinion: [u8; 32],
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a provider asks for emergency access, so the patient can be
+/// alerted in time to veto it.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmergencyAccessRequested {
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub data_hash: [u8; 32],
+    pub justification_hash: [u8; 32],
+    pub available_at: i64,
+    pub expires_at: i64,
+}
+
+/// Emitted when a patient vetoes an emergency access request.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmergencyAccessVetoed {
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub data_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Emitted for each payment of a claim with a payment schedule, after its
+/// `ClaimPaid`.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstallmentPaid {
+    pub claim: Pubkey,
+    /// This installment's number, counting from 1.
+    pub installment: u8,
+    pub installment_count: u8,
+    /// Principal paid by this installment, without interest.
+    pub principal: u64,
+    pub due_at: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted for every payment of a claim, partial, in a token or from escrow.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimPaid {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    /// This payment, including interest.
+    pub amount: u64,
+    /// Totals paid so far, after this payment.
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    /// `Paid` or `PartiallyPaid`.
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("You are not authorized to perform this action.")]
+    Unauthorized,
+    #[msg("The patient account does not match the claim.")]
+    InvalidPatient,
+    #[msg("The claim must be verified before payment.")]
+    ClaimNotVerified,
+    #[msg("A batch must contain at least one entry.")]
+    EmptyBatch,
+    #[msg("The batch contains too many entries.")]
+    BatchTooLarge,
+    #[msg("A claim must contain at least one line item.")]
+    NoLineItems,
+    #[msg("The claim contains too many line items.")]
+    TooManyLineItems,
+    #[msg("The procedure code is too long.")]
+    CodeTooLong,
+    #[msg("The modifier is too long.")]
+    ModifierTooLong,
+    #[msg("The line item does not exist.")]
+    InvalidLineItem,
+    #[msg("The claim must be pending for this action.")]
+    ClaimNotPending,
+    #[msg("A down-code may not increase the unit price.")]
+    InvalidDownCode,
+    #[msg("Every line item must be adjudicated before the claim is verified.")]
+    LineItemsNotAdjudicated,
+    #[msg("The hash is too long.")]
+    HashTooLong,
+    #[msg("The claim has too many attachments.")]
+    TooManyAttachments,
+    #[msg("The record is already attached to the claim.")]
+    DuplicateAttachment,
+    #[msg("The record is not attached to the claim.")]
+    AttachmentNotFound,
+    #[msg("Notes must be appended to the current page.")]
+    InvalidNotePage,
+    #[msg("The claim must be pending or under review for this action.")]
+    ClaimNotOpen,
+    #[msg("The claim cannot move to the requested status.")]
+    InvalidStatusTransition,
+    #[msg("Only rejected claims can be reopened or disputed.")]
+    ClaimNotRejected,
+    #[msg("The claim has already been reopened once.")]
+    ClaimAlreadyReopened,
+    #[msg("The window for reopening this claim has elapsed.")]
+    ReopenWindowElapsed,
+    #[msg("Reopening a claim requires new evidence.")]
+    NoNewEvidence,
+    #[msg("The claim has no approved line items to pay.")]
+    NoApprovedLineItems,
+    #[msg("Arithmetic overflow while computing the claim amount.")]
+    AmountOverflow,
+    #[msg("The DID must not be empty.")]
+    DidEmpty,
+    #[msg("The DID is too long.")]
+    DidTooLong,
+    #[msg("The provider name must not be empty.")]
+    NameEmpty,
+    #[msg("The provider name is too long.")]
+    NameTooLong,
+    #[msg("The hash must not be empty.")]
+    HashEmpty,
+    #[msg("The encrypted payload is too large.")]
+    EncryptedDataTooLong,
+    #[msg("The batch id must not be empty.")]
+    BatchIdEmpty,
+    #[msg("The batch id is too long.")]
+    BatchIdTooLong,
+    #[msg("The record pointer must not be empty.")]
+    PointerEmpty,
+    #[msg("The record pointer is too long.")]
+    PointerTooLong,
+    #[msg("The claim id must not be empty.")]
+    ClaimIdEmpty,
+    #[msg("The claim id is too long.")]
+    ClaimIdTooLong,
+    #[msg("The procedure code must not be empty.")]
+    CodeEmpty,
+    #[msg("A line item must bill at least one unit.")]
+    ZeroUnits,
+    #[msg("The account data matches no known layout.")]
+    UnknownAccountLayout,
+    #[msg("The account already uses the current layout.")]
+    AlreadyMigrated,
+    #[msg("The demo vault does not hold enough lamports.")]
+    InsufficientVaultBalance,
+    #[msg("The grant scope is empty or has unknown bits.")]
+    InvalidGrantScope,
+    #[msg("The grant expiry must be in the future.")]
+    InvalidGrantExpiry,
+    #[msg("An access grant is required for this action.")]
+    GrantRequired,
+    #[msg("The access grant is revoked or expired.")]
+    GrantInactive,
+    #[msg("The access grant does not cover this action.")]
+    InsufficientGrantScope,
+    #[msg("The record is already linked to a parent.")]
+    RecordAlreadyLinked,
+    #[msg("A parent record must precede the record derived from it.")]
+    InvalidProvenanceLink,
+    #[msg("The field count must be between 1 and 2^16.")]
+    InvalidFieldCount,
+    #[msg("The field path is too long.")]
+    FieldPathTooLong,
+    #[msg("The field value is too long.")]
+    FieldValueTooLong,
+    #[msg("The field index is outside the committed fields.")]
+    InvalidFieldIndex,
+    #[msg("The merkle proof does not match the committed root.")]
+    InvalidMerkleProof,
+    #[msg("The access policy has too many rules.")]
+    TooManyPolicyRules,
+    #[msg("A policy rule needs at least one category and a valid scope.")]
+    InvalidPolicyRule,
+    #[msg("Guardian permissions must be non-empty and only use known flags.")]
+    InvalidGuardianPermissions,
+    #[msg("A dependent link must expire in the future.")]
+    InvalidDependentExpiry,
+    #[msg("Only the patient or a linked guardian can do this.")]
+    GuardianLinkRequired,
+    #[msg("The dependent has come of age and the guardian link has lapsed.")]
+    GuardianLinkExpired,
+    #[msg("The guardian link does not allow this action.")]
+    InsufficientGuardianPermissions,
+    #[msg("The encryption key cannot be all zeros.")]
+    InvalidEncryptionKey,
+    #[msg("The key envelope is too long.")]
+    KeyEnvelopeTooLong,
+    #[msg("The grantee must register an encryption key first.")]
+    EncryptionKeyRequired,
+    #[msg("A key envelope needs both its bytes and its account.")]
+    KeyEnvelopeMismatch,
+    #[msg("The data validator is suspended.")]
+    ValidatorInactive,
+    #[msg("The quality score is out of range.")]
+    InvalidQualityScore,
+    #[msg("The price per record must be greater than zero.")]
+    InvalidSubscriptionPrice,
+    #[msg("There is nothing to withdraw.")]
+    NothingToWithdraw,
+    #[msg("The subscription is for another patient.")]
+    SubscriptionMismatch,
+    #[msg("The settlement period is not the current month.")]
+    InvalidSettlementPeriod,
+    #[msg("The settlement period has not ended yet.")]
+    SettlementPeriodOpen,
+    #[msg("The settlement period is already closed.")]
+    SettlementPeriodClosed,
+    #[msg("The provider account does not match the claim.")]
+    InvalidProvider,
+    #[msg("Amounts in different decimals cannot be combined.")]
+    DecimalsMismatch,
+    #[msg("The number of decimals is out of range.")]
+    InvalidDecimals,
+    #[msg("The service start date must be in the past.")]
+    InvalidServiceDate,
+    #[msg("This service has already been billed.")]
+    DuplicateClaim,
+    #[msg("The claim fingerprint does not match the claim.")]
+    ClaimFingerprintMismatch,
+    #[msg("The sponsor vault does not hold enough lamports.")]
+    InsufficientSponsorFunds,
+    #[msg("Tenant id cannot be empty.")]
+    TenantIdEmpty,
+    #[msg("Tenant id is too long.")]
+    TenantIdTooLong,
+    #[msg("The tenant fee is above the maximum.")]
+    TenantFeeTooHigh,
+    #[msg("The account is not a member of the tenant in that role.")]
+    NotTenantMember,
+    #[msg("The tenant or treasury does not match the claim.")]
+    TenantMismatch,
+    #[msg("This feature is disabled.")]
+    FeatureDisabled,
+    #[msg("The payment must be more than zero and at most the outstanding amount.")]
+    InvalidPaymentAmount,
+    #[msg("The claim must be on hold for this action.")]
+    ClaimNotOnHold,
+    #[msg("The provider has already adjudicated part of the claim.")]
+    ClaimAlreadyAdjudicated,
+    #[msg("The auto-approval window must be between 1 and 180 days.")]
+    InvalidAutoApprovalWindow,
+    #[msg("The claim's auto-approval deadline has not passed yet.")]
+    AutoApprovalNotDue,
+    #[msg("The claim is paid in a different mint.")]
+    ClaimMintMismatch,
+    #[msg("The account is not a token account of the expected mint.")]
+    InvalidTokenAccount,
+    #[msg("Only Token and Token-2022 mints are supported.")]
+    UnsupportedTokenProgram,
+    #[msg("Offer id cannot be empty.")]
+    OfferIdEmpty,
+    #[msg("Offer id is too long.")]
+    OfferIdTooLong,
+    #[msg("An offer must have a positive price and access period.")]
+    InvalidOfferTerms,
+    #[msg("The offer's price differs from the quoted price.")]
+    OfferPriceMismatch,
+    #[msg("The account must be upgraded with upgrade_account first.")]
+    AccountNotUpgraded,
+    #[msg("The external reference is too long.")]
+    ExternalRefTooLong,
+    #[msg("The claim has too many tags.")]
+    TooManyTags,
+    #[msg("A tag cannot be empty.")]
+    TagEmpty,
+    #[msg("The tag is too long.")]
+    TagTooLong,
+    #[msg("A tag is listed more than once.")]
+    DuplicateTag,
+    #[msg("The consent scope is empty or has unknown bits.")]
+    InvalidConsentScope,
+    #[msg("The consent expiry must be in the future.")]
+    InvalidConsentExpiry,
+    #[msg("The service must end in the past, no earlier than it started.")]
+    InvalidServicePeriod,
+    #[msg("The service started before the patient enrolled.")]
+    ServiceBeforeEnrollment,
+    #[msg("Only undecided or unpaid lamport claims outside tenants can be escrowed.")]
+    ClaimNotEscrowable,
+    #[msg("The escrow does not cover what the claim owes.")]
+    InsufficientEscrow,
+    #[msg("The approval policy id cannot be empty.")]
+    PolicyIdEmpty,
+    #[msg("The approval policy id is too long.")]
+    PolicyIdTooLong,
+    #[msg("An approval policy needs one to eight distinct approvers.")]
+    InvalidApprovers,
+    #[msg("The signer is not an approver under the claim's policy.")]
+    NotAnApprover,
+    #[msg("The approver has already approved this claim.")]
+    AlreadyApproved,
+    #[msg("The claim is missing approvals its policy requires.")]
+    ApprovalsPending,
+    #[msg("The claim has already been paid.")]
+    PaymentStarted,
+    #[msg("The record index is not the patient's next index.")]
+    RecordIndexMismatch,
+    #[msg("Only paid or rejected claims can be closed.")]
+    ClaimNotClosable,
+    #[msg("The claim has an escrow; settle it first.")]
+    ClaimEscrowed,
+    #[msg("An account was passed more than once.")]
+    DuplicateAccount,
+    #[msg("The provider is not verified by the credentialing authority.")]
+    ProviderNotVerified,
+    #[msg("Price tiers must have positive prices and increasing ages, up to MAX_PRICE_TIERS.")]
+    InvalidPriceTiers,
+    #[msg("The offer is priced by the record's age; pass the record.")]
+    OfferRecordMissing,
+    #[msg("The record is not the one the offer sells.")]
+    OfferRecordMismatch,
+    #[msg("The grant does not allow delegation.")]
+    GrantNotDelegable,
+    #[msg("Delegated grants cannot be sub-granted past MAX_DELEGATION_DEPTH.")]
+    DelegationTooDeep,
+    #[msg("A delegated scope must be a non-empty part of the delegator's scope.")]
+    InvalidDelegationScope,
+    #[msg("The grantee already holds another grant from this patient.")]
+    GrantAlreadyHeld,
+    #[msg("The grant is not delegated from the grant passed as its parent.")]
+    GrantChainMismatch,
+    #[msg("Grants delegated from a revoked grant must be revoked with it.")]
+    DelegationsNotRevoked,
+    #[msg("The claim's access grant ended with the claim.")]
+    ClaimGrantLapsed,
+    #[msg("The claim's insurer has not approved it.")]
+    InsurerApprovalPending,
+    #[msg("The insurer cannot be the claim's patient or provider.")]
+    InvalidInsurer,
+    #[msg("A patient cannot register their own wallet as a device.")]
+    InvalidDevice,
+    #[msg("A device's daily reading limit must be between 1 and MAX_DEVICE_READINGS_PER_DAY.")]
+    InvalidDeviceRateLimit,
+    #[msg("The device has used its readings for today.")]
+    DeviceRateLimited,
+    #[msg("A day's readings can only be rolled up once the day has ended.")]
+    RollupDayOpen,
+    #[msg("The rollup's day, reading count or summary values are inconsistent.")]
+    InvalidRollup,
+    #[msg("The reading falls outside the rollup's day or range.")]
+    ReadingOutsideRollup,
+    #[msg("A payment schedule needs 2 to 24 installments a positive interval apart.")]
+    InvalidPaymentSchedule,
+    #[msg("The payment is less than the installment due.")]
+    InstallmentTooSmall,
+    #[msg("A patient cannot be their own delegate.")]
+    InvalidDelegate,
+    #[msg("Delegate permissions are empty or have unknown bits.")]
+    InvalidDelegatePermissions,
+    #[msg("Only the patient or their delegate can do this.")]
+    DelegateRequired,
+    #[msg("The delegate's power of attorney does not allow this action.")]
+    InsufficientDelegatePermissions,
+    #[msg("A second opinion needs 1 to 8 records, a fee and a future expiry.")]
+    InvalidSecondOpinion,
+    #[msg("The consultant must be a provider other than the claim's.")]
+    InvalidConsultant,
+    #[msg("The record is not shared for this second opinion.")]
+    RecordNotShared,
+    #[msg("The second opinion has been given or has expired.")]
+    SecondOpinionClosed,
+    #[msg("The consultant can still give the second opinion.")]
+    SecondOpinionPending,
+    #[msg("A care team needs 1 to 8 distinct members besides the patient and up to 16 records.")]
+    InvalidCareTeam,
+    #[msg("The record is not designated for the care team or attached to the patient's claim.")]
+    CareTeamRecordNotShared,
+    #[msg("A patient cannot request emergency access to their own record.")]
+    InvalidEmergencyAccess,
+    #[msg("The patient vetoed the emergency access request.")]
+    EmergencyAccessVetoed,
+    #[msg("Emergency access has not started; the patient can still veto it.")]
+    EmergencyAccessPending,
+    #[msg("Emergency access has expired.")]
+    EmergencyAccessExpired,
+    #[msg("The emergency access can no longer be vetoed.")]
+    EmergencyVetoWindowClosed,
+    #[msg("Emergency access is still open.")]
+    EmergencyAccessActive,
+    #[msg("An audit entry needs both the trail and the entry's account.")]
+    AuditEntryMismatch,
+    #[msg("Fee schedules price up to 32 distinct codes, each above zero.")]
+    InvalidFeeSchedule,
+    #[msg("A service's code is not in the provider's fee schedule.")]
+    CodeNotInFeeSchedule,
+    #[msg("The appointment's id, time, provider or services are invalid.")]
+    InvalidAppointment,
+    #[msg("The appointment is not in the status the action requires.")]
+    AppointmentStatusMismatch,
+    #[msg("Record payloads must be stored off-chain, not inline.")]
+    InlineStorage,
+    #[msg("The content does not match the record's commitment.")]
+    CommitmentMismatch,
+    #[msg("Disputes need an arbiter and a positive resolution deadline.")]
+    InvalidDisputeConfig,
+    #[msg("The claim is not disputed.")]
+    ClaimNotDisputed,
+    #[msg("The window to dispute the rejection has closed.")]
+    DisputeWindowElapsed,
+    #[msg("A split escrow needs an arbiter other than the patient or provider.")]
+    InvalidArbiter,
+    #[msg("A split escrow release needs the recipient and one other signer.")]
+    EscrowReleaseUnauthorized,
+    #[msg("The program is paused.")]
+    ProgramPaused,
+    #[msg("The protocol fee is above MAX_PROTOCOL_FEE_BPS.")]
+    ProtocolFeeTooHigh,
+    #[msg("The treasury does not have enough lamports for this withdrawal.")]
+    InsufficientTreasuryFunds,
+    #[msg("Plan id cannot be empty.")]
+    PlanIdEmpty,
+    #[msg("Plan id is too long.")]
+    PlanIdTooLong,
+    #[msg("A plan must have a positive price and period and cover some records.")]
+    InvalidPlanTerms,
+    #[msg("The remaining accounts do not match the batch.")]
+    BatchAccountsMismatch,
+    #[msg("Token payments need the mint's treasury while a protocol fee is set.")]
+    MintTreasuryRequired,
+    #[msg("The payload length is zero or above MAX_CHUNKED_RECORD_LEN.")]
+    InvalidRecordLength,
+    #[msg("The chunk does not start where the written payload ends.")]
+    ChunkOutOfOrder,
+    #[msg("The chunk is empty, above MAX_DATA_CHUNK_LEN or past the payload's end.")]
+    InvalidChunkLength,
+    #[msg("The record is finalized and cannot take more chunks.")]
+    RecordFinalized,
+    #[msg("The clawback window is above MAX_CLAWBACK_WINDOW_SECS or negative.")]
+    InvalidClawbackWindow,
+    #[msg("Payment holds are off; no clawback window is set.")]
+    ClawbackDisabled,
+    #[msg("The payment is still held for the clawback window.")]
+    PaymentStillHeld,
+    #[msg("The clawback window has closed.")]
+    ClawbackWindowElapsed,
+    #[msg("A clawback needs the provider and the patient, or the arbiter.")]
+    ClawbackUnauthorized,
+    #[msg("The new encryption key is the one already registered.")]
+    EncryptionKeyUnchanged,
+    #[msg("The facilitator signing key is the default key or unchanged.")]
+    InvalidFacilitatorKey,
+    #[msg("The fee share is above 10000 bps.")]
+    InvalidFeeShare,
+    #[msg("The facilitator is revoked.")]
+    FacilitatorRevoked,
+    #[msg("The payment is not signed with the facilitator's signing key.")]
+    FacilitatorKeyMismatch,
+    #[msg("The facilitator does not hold enough lamports.")]
+    InsufficientFacilitatorFees,
+    #[msg("The consent has lapsed or does not cover reading this record.")]
+    InsufficientConsent,
+    #[msg("The re-encryption request is already fulfilled.")]
+    ReencryptionAlreadyFulfilled,
+    #[msg("The provider has rotated its encryption key since the request.")]
+    ReencryptionKeyStale,
+    #[msg("The wrapped key is empty.")]
+    InvalidWrappedKey,
+    #[msg("The crank reward is above MAX_CRANK_REWARD_LAMPORTS.")]
+    InvalidCrankReward,
+    #[msg("The claim lifetime must be 0 or between MIN_CLAIM_TTL_SECS and MAX_CLAIM_TTL_SECS.")]
+    InvalidClaimTtl,
+    #[msg("Only a pending, under-review or on-hold claim can expire.")]
+    ClaimNotExpirable,
+    #[msg("The claim has no expiry or has not reached it.")]
+    ClaimNotExpired,
+    #[msg("Rebate tiers must rise in volume and rebate, up to MAX_REBATE_TIERS tiers of at most 10000 bps.")]
+    InvalidFeeRebates,
+    #[msg("A fee rebate needs the provider's volume account for the period.")]
+    ProviderVolumeRequired,
+    #[msg("A plan's premium must be positive and its research discount at most 10000 bps, for some categories.")]
+    InvalidPlanBenefits,
+    #[msg("Verifying a tenant claim takes the tenant's plan benefits account.")]
+    PlanBenefitsRequired,
+    #[msg("The plan has an out-of-pocket maximum; pass the patient's out-of-pocket tally for the year.")]
+    OutOfPocketRequired,
+    #[msg("The provider does not have that much collateral staked.")]
+    InsufficientStake,
+    #[msg("The collateral is still unbonding.")]
+    StakeStillUnbonding,
+    #[msg("Fraud can only be ruled after the patient prevails in the dispute.")]
+    DisputeNotUpheld,
+}
+
+/// Accounts a new claim is written to, shared by `create_claim`,
+/// `create_tenant_claim` and `create_claim_from_appointment`.
+struct ClaimFiling<'a, 'info> {
+    claim_account: &'a mut Account<'info, ClaimAccount>,
+    patient: Pubkey,
+    /// Who pays the claim's rent: the patient or their agent, or the
+    /// provider billing an appointment.
+    filer: Pubkey,
+    provider: Pubkey,
+    provider_stats: &'a mut Account<'info, ProviderClaimStats>,
+    claim_dedup: &'a mut Account<'info, ClaimDedup>,
+    /// `Pubkey::default()` outside any tenant.
+    tenant: Pubkey,
+    /// `Pubkey::default()` for a claim paid in lamports.
+    mint: Pubkey,
+    /// When the patient enrolled in the plan covering the claim, or 0 when no
+    /// enrollment is known.
+    enrolled_since: i64,
+    /// The insurer that must approve payment, or `Pubkey::default()`.
+    insurer: Pubkey,
+    /// The claim's read grant for its provider, when the filer opts in.
+    claim_grant: Option<&'a mut Account<'info, ClaimGrant>>,
+    /// When the claim expires if still undecided, from `Config::claim_expiry`.
+    expires_at: i64,
+}
+
+/// Validates a new claim and records it, its dedup entry and the provider's
+/// claim stats. The caller has already checked who may file it.
+fn file_claim(filing: ClaimFiling, claim: ClaimInput, now: i64) -> Result<()> {
+    let ClaimInput {
+        claim_id,
+        line_items,
+        attachments,
+        service_start,
+        service_end,
+        fingerprint,
+    } = claim;
+    validate_not_empty("claim_id", &claim_id, ErrorCode::ClaimIdEmpty)?;
+    validate_len(
+        "claim_id",
+        &claim_id,
+        MAX_SEED_ID_LEN,
+        ErrorCode::ClaimIdTooLong,
+    )?;
+    require!(!line_items.is_empty(), ErrorCode::NoLineItems);
+    if filing.insurer == filing.patient || filing.insurer == filing.provider {
+        msg!("{} cannot insure its own claim", filing.insurer);
+        return err!(ErrorCode::InvalidInsurer);
+    }
+    validate_count(
+        "line_items",
+        line_items.len(),
+        MAX_LINE_ITEMS,
+        ErrorCode::TooManyLineItems,
+    )?;
+    validate_count(
+        "attachments",
+        attachments.len(),
+        MAX_ATTACHMENTS,
+        ErrorCode::TooManyAttachments,
+    )?;
+    for (i, hash) in attachments.iter().enumerate() {
+        validate_hash("attachment", hash)?;
+        if attachments[..i].contains(hash) {
+            msg!("Attachment {} is listed more than once", hex(hash));
+            return err!(ErrorCode::DuplicateAttachment);
+        }
+    }
+
+    filing.claim_account.set_service_period(
+        service_start,
+        service_end,
+        filing.enrolled_since,
+        now,
+    )?;
+    let expected = ClaimDedup::fingerprint(
+        &filing.patient,
+        &filing.provider,
+        &line_items,
+        service_start,
+    );
+    if fingerprint != expected {
+        msg!("The claim fingerprint does not match its contents");
+        return err!(ErrorCode::ClaimFingerprintMismatch);
+    }
+    // The dedup account is keyed by the claim's content, so an existing
+    // one means the same service was already billed
+    let claim_dedup = filing.claim_dedup;
+    if claim_dedup.claim != Pubkey::default() {
+        msg!(
+            "The same service was already billed as claim {}",
+            claim_dedup.claim
+        );
+        return err!(ErrorCode::DuplicateClaim);
+    }
+    claim_dedup.claim = filing.claim_account.key();
+    claim_dedup.service_date = service_start;
+    claim_dedup.created_at = now;
+    claim_dedup.account_version = ClaimDedup::VERSION;
+
+    let duplicate_codes = line_items
+        .iter()
+        .enumerate()
+        .any(|(i, input)| line_items[..i].iter().any(|other| other.code == input.code));
+    let provider = filing.provider;
+    let provider_stats = filing.provider_stats;
+    provider_stats.provider = provider;
+    provider_stats.account_version = ProviderClaimStats::VERSION;
+    for (kind, value, threshold) in provider_stats.record_claim(now, duplicate_codes) {
+        msg!(
+            "Provider {} crossed the {:?} threshold: {} > {}",
+            provider,
+            kind,
+            value,
+            threshold
+        );
+        emit!(FraudSignal {
+            provider,
+            kind,
+            value,
+            threshold,
+            timestamp: now,
+        });
+    }
+
+    let mut amount = Amount::lamports(0);
+    let mut items = Vec::with_capacity(line_items.len());
+    for (i, input) in line_items.into_iter().enumerate() {
+        validate_code(&input.code)?;
+        validate_len(
+            "modifier",
+            &input.modifier,
+            MAX_MODIFIER_LEN,
+            ErrorCode::ModifierTooLong,
+        )?;
+        if input.units == 0 {
+            msg!("Line item {} has zero units", i);
+            return err!(ErrorCode::ZeroUnits);
+        }
+        let item = ClaimLineItem {
+            code: input.code,
+            units: input.units,
+            unit_price: input.unit_price,
+            modifier: input.modifier,
+            status: LineItemStatus::Pending,
+            reason_code: 0,
+        };
+        amount = amount.checked_add(Amount::lamports(item.total()?))?;
+        items.push(item);
+    }
+
+    let claim_account = filing.claim_account;
+    claim_account.claim_id = claim_id;
+    claim_account.patient = filing.patient;
+    claim_account.provider = filing.provider;
+    claim_account.attachments = attachments;
+    claim_account.amount = amount.raw;
+    claim_account.line_items = items;
+    claim_account.note_count = 0;
+    claim_account.status = ClaimStatus::Pending;
+    claim_account.timestamp = now;
+    claim_account.rejected_at = 0;
+    claim_account.reopened = false;
+    claim_account.verified_at = 0;
+    claim_account.principal_paid = 0;
+    claim_account.interest_paid = 0;
+    claim_account.tenant = filing.tenant;
+    claim_account.mint = filing.mint;
+    claim_account.external_ref = String::new();
+    claim_account.tags = Vec::new();
+    claim_account.approval_policy = Pubkey::default();
+    claim_account.required_approvals = 0;
+    claim_account.approvals = 0;
+    claim_account.rent_payer = filing.filer;
+    claim_account.insurer = filing.insurer;
+    claim_account.insurer_approved_at = 0;
+    claim_account.expires_at = filing.expires_at;
+    claim_account.account_version = ClaimAccount::VERSION;
+    if let Some(claim_grant) = filing.claim_grant {
+        claim_grant.claim = claim_account.key();
+        claim_grant.patient = claim_account.patient;
+        claim_grant.filed_at = now;
+        claim_grant.account_version = ClaimGrant::VERSION;
+    }
+    emit!(ClaimCreated {
+        claim: claim_account.key(),
+        claim_id: claim_account.claim_id.clone(),
+        patient: claim_account.patient,
+        provider: claim_account.provider,
+        tenant: claim_account.tenant,
+        mint: claim_account.mint,
+        amount: claim_account.amount,
+        service_start: claim_account.service_start,
+        service_end: claim_account.service_end,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Checks that `info` is an initialized Token or Token-2022 mint.
+fn expect_mint_account(info: &AccountInfo) -> Result<()> {
+    if !token::is_token_program(info.owner) {
+        msg!("Mint {} is owned by {}", info.key(), info.owner);
+        return err!(ErrorCode::UnsupportedTokenProgram);
+    }
+    token::unpack_mint_decimals(&info.try_borrow_data()?)?;
+    Ok(())
+}
+
+/// `TransferChecked` of `amount` from the provider's token account to
+/// `destination`, signed by the provider.
+fn transfer_claim_tokens<'info>(
+    accounts: &ProcessTokenPayment<'info>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    anchor_lang::solana_program::program::invoke(
+        &token::transfer_checked(
+            accounts.token_program.key,
+            accounts.provider_token.key,
+            accounts.mint.key,
+            destination.key,
+            accounts.provider.key,
+            amount,
+            decimals,
+        ),
+        &[
+            accounts.provider_token.to_account_info(),
+            accounts.mint.to_account_info(),
+            destination.clone(),
+            accounts.provider.to_account_info(),
+            accounts.token_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Checks that `info` is a token account of `mint`, owned by `owner` if
+/// given, under the mint's token program.
+fn expect_token_account(
+    info: &AccountInfo,
+    mint: &AccountInfo,
+    owner: Option<Pubkey>,
+) -> Result<()> {
+    if info.owner != mint.owner {
+        msg!(
+            "Token account {} is owned by {}, not {}",
+            info.key(),
+            info.owner,
+            mint.owner
+        );
+        return err!(ErrorCode::InvalidTokenAccount);
+    }
+    let account = token::unpack_account(&info.try_borrow_data()?)?;
+    if account.mint != mint.key() {
+        msg!(
+            "Token account {} holds {}, not {}",
+            info.key(),
+            account.mint,
+            mint.key()
+        );
+        return err!(ErrorCode::InvalidTokenAccount);
+    }
+    if let Some(owner) = owner {
+        if account.owner != owner {
+            msg!(
+                "Token account {} belongs to {}, not {}",
+                info.key(),
+                account.owner,
+                owner
+            );
+            return err!(ErrorCode::InvalidTokenAccount);
+        }
+    }
+    Ok(())
+}
+
+fn validate_tenant_fee(fee_bps: u16) -> Result<()> {
+    if fee_bps > MAX_TENANT_FEE_BPS {
+        msg!(
+            "Tenant fee {} bps is above the maximum of {}",
+            fee_bps,
+            MAX_TENANT_FEE_BPS
+        );
+        return err!(ErrorCode::TenantFeeTooHigh);
+    }
+    Ok(())
+}
+
+fn expect_member(membership: &TenantMember, role: TenantRole) -> Result<()> {
+    if membership.role != role {
+        msg!(
+            "{} is a {:?} of the tenant, not a {:?}",
+            membership.member,
+            membership.role,
+            role
+        );
+        return err!(ErrorCode::NotTenantMember);
+    }
+    Ok(())
+}
+
+/// The wallet of the insurer a claim names, or `Pubkey::default()` for none.
+fn insurer_key(insurer: Option<&InsurerAccount>) -> Pubkey {
+    insurer.map_or(Pubkey::default(), |insurer| insurer.authority)
+}
+
+/// The network fee owed on paying `amount` of a claim filed in `claim_tenant`.
+/// Claims outside any tenant owe none.
+fn tenant_fee(
+    claim_tenant: Pubkey,
+    tenant: Option<&Account<Tenant>>,
+    treasury: Option<&UncheckedAccount>,
+    amount: u64,
+) -> Result<u64> {
+    if claim_tenant == Pubkey::default() {
+        return Ok(0);
+    }
+    let (Some(tenant), Some(treasury)) = (tenant, treasury) else {
+        msg!(
+            "Claims of tenant {} need its tenant and treasury",
+            claim_tenant
+        );
+        return err!(ErrorCode::TenantMismatch);
+    };
+    if tenant.key() != claim_tenant || treasury.key() != tenant.treasury {
+        msg!(
+            "Expected tenant {} and its treasury {}",
+            claim_tenant,
+            tenant.treasury
+        );
+        return err!(ErrorCode::TenantMismatch);
+    }
+    tenant.fee_on(amount)
+}
+
+fn validate_not_empty(field: &str, value: &str, error: ErrorCode) -> Result<()> {
+    if value.is_empty() {
+        msg!("{} must not be empty", field);
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+fn validate_len(field: &str, value: &str, max: usize, error: ErrorCode) -> Result<()> {
+    if value.len() > max {
+        msg!("{} is {} bytes; the limit is {}", field, value.len(), max);
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+fn validate_count(field: &str, count: usize, max: usize, error: ErrorCode) -> Result<()> {
+    if count > max {
+        msg!("{} has {} items; the limit is {}", field, count, max);
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Emits `PatientRegistered` for a patient account that was just created.
+fn emit_patient_registered(patient_account: &Account<PatientAccount>, now: i64) {
+    emit!(PatientRegistered {
+        patient: patient_account.key(),
+        authority: patient_account.authority,
+        did: patient_account.did.clone(),
+        timestamp: now,
+    });
+}
+
+fn emit_encryption_key_updated(encryption_key: &Account<EncryptionKey>, now: i64) {
+    emit!(EncryptionKeyUpdated {
+        owner: encryption_key.owner,
+        x25519_key: encryption_key.x25519_key,
+        key_version: encryption_key.key_version,
+        timestamp: now,
+    });
+}
+
+fn emit_facilitator_updated(facilitator: &Account<FacilitatorAccount>, now: i64) {
+    emit!(FacilitatorUpdated {
+        facilitator: facilitator.key(),
+        authority: facilitator.authority,
+        signing_key: facilitator.signing_key,
+        fee_share_bps: facilitator.fee_share_bps,
+        revoked: facilitator.revoked,
+        timestamp: now,
+    });
+}
+
+/// Holds the patient share of a tenant claim that was just verified to the
+/// plan's out-of-pocket maximum, if it sets one, moving the rest to the
+/// provider.
+fn apply_out_of_pocket_max(
+    claim_account: &mut Account<ClaimAccount>,
+    plan_benefits: Option<&UncheckedAccount>,
+    out_of_pocket: Option<&mut Account<OutOfPocket>>,
+    now: i64,
+) -> Result<()> {
+    if claim_account.status != ClaimStatus::Verified || claim_account.tenant == Pubkey::default() {
+        return Ok(());
+    }
+    let Some(plan_benefits) = plan_benefits else {
+        msg!("Claim {} is a tenant claim", claim_account.key());
+        return err!(ErrorCode::PlanBenefitsRequired);
+    };
+    if plan_benefits.data_is_empty() {
+        return Ok(());
+    }
+    if *plan_benefits.owner != crate::ID {
+        msg!("Account {} is not plan benefits", plan_benefits.key());
+        return err!(ErrorCode::UnknownAccountLayout);
+    }
+    let out_of_pocket_max =
+        PlanBenefits::try_deserialize(&mut &plan_benefits.try_borrow_data()?[..])?
+            .out_of_pocket_max;
+    if out_of_pocket_max == 0 {
+        return Ok(());
+    }
+    let Some(out_of_pocket) = out_of_pocket else {
+        msg!(
+            "Plan caps the patient share at {} lamports a year",
+            out_of_pocket_max
+        );
+        return err!(ErrorCode::OutOfPocketRequired);
+    };
+    let covered = out_of_pocket.apply(claim_account, out_of_pocket_max)?;
+    if covered > 0 {
+        emit!(OutOfPocketMaxReached {
+            claim: claim_account.key(),
+            tenant: out_of_pocket.tenant,
+            patient: out_of_pocket.patient,
+            year: out_of_pocket.year,
+            payer_covered: covered,
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
+
+/// Emits `ClaimVerified` or `ClaimRejected` for a claim that was just decided.
+fn emit_claim_decision(
+    claim_account: &Account<ClaimAccount>,
+    auto_approved: bool,
+    now: i64,
+) -> Result<()> {
+    if claim_account.status == ClaimStatus::Verified {
+        emit!(ClaimVerified {
+            claim: claim_account.key(),
+            provider: claim_account.provider,
+            approved_amount: claim_account.approved_amount()?,
+            auto_approved,
+            timestamp: now,
+        });
+    } else {
+        emit!(ClaimRejected {
+            claim: claim_account.key(),
+            provider: claim_account.provider,
+            status: claim_account.status,
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
+
+/// Opens the claim's escrow, with `arbiter` for split custody, and moves the
+/// provider's lamports into it.
+fn lock_claim_escrow(accounts: &mut FundClaimEscrow, arbiter: Pubkey, now: i64) -> Result<()> {
+    let claim = accounts.claim_account.key();
+    let amount = accounts
+        .claim_escrow
+        .fund(claim, &accounts.claim_account, arbiter, now)?;
+    let cpi_context = CpiContext::new(
+        accounts.system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: accounts.provider.to_account_info(),
+            to: accounts.claim_escrow.to_account_info(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)
+}
+
+fn emit_claim_paid(claim_account: &Account<ClaimAccount>, amount: u64, now: i64) {
+    emit!(ClaimPaid {
+        claim: claim_account.key(),
+        patient: claim_account.patient,
+        provider: claim_account.provider,
+        mint: claim_account.payment_mint(),
+        amount,
+        principal_paid: claim_account.principal_paid,
+        interest_paid: claim_account.interest_paid,
+        status: claim_account.status,
+        timestamp: now,
+    });
+}
+
+/// Checks `period` is the month of `now` and returns its summary, filling in
+/// the parties if it was just created.
+fn open_settlement<'a, 'info>(
+    summary: &'a mut Account<'info, SettlementSummary>,
+    payer: Pubkey,
+    payee: Pubkey,
+    period: u32,
+    now: i64,
+) -> Result<&'a mut Account<'info, SettlementSummary>> {
+    if period != settlement::period_of(now) {
+        msg!(
+            "Period {} is not the current one ({})",
+            period,
+            settlement::period_of(now)
+        );
+        return err!(ErrorCode::InvalidSettlementPeriod);
+    }
+    if summary.period == 0 {
+        summary.payer = payer;
+        summary.payee = payee;
+        summary.period = period;
+        summary.account_version = SettlementSummary::VERSION;
+    }
+    Ok(summary)
+}
+
+/// Pays `principal` of the claim's outstanding amount, or all of it, with
+/// any tenant fee, and records the payment in the period's summary.
+fn pay_claim(ctx: Context<ProcessPayment>, period: u32, principal: Option<u64>) -> Result<()> {
+    let claim_account = &mut ctx.accounts.claim_account;
+    let provider = &mut ctx.accounts.provider;
+    let patient = &mut ctx.accounts.patient;
+    let system_program = &ctx.accounts.system_program;
+    let now = clock::now(ctx.remaining_accounts)?;
+    claim_account.expect_mint(&Pubkey::default())?;
+
+    // Transfer SOL from provider to patient for the approved lines only
+    let outstanding = claim_account.outstanding_principal()?;
+    let paid_before = (claim_account.principal_paid, claim_account.interest_paid);
+    let amount = match principal {
+        Some(principal) => claim_account.settle_partial_payment(principal, now)?,
+        None => claim_account.settle_payment(now)?,
+    };
+    emit_claim_paid(claim_account, amount, now);
+    if let Some(schedule) = ctx.accounts.payment_schedule.as_mut() {
+        let principal = principal.unwrap_or(outstanding);
+        let installment = schedule.record(principal, outstanding)?;
+        emit!(InstallmentPaid {
+            claim: claim_account.key(),
+            installment,
+            installment_count: schedule.installment_count,
+            principal,
+            due_at: schedule.due_at(installment),
+            timestamp: now,
+        });
+    }
+    let fee = tenant_fee(
+        claim_account.tenant,
+        ctx.accounts.tenant.as_ref(),
+        ctx.accounts.treasury.as_ref(),
+        amount,
+    )?;
+    let mut split = split_payment(
+        &ctx.accounts.config,
+        ctx.accounts.facilitator.as_mut(),
+        ctx.accounts.facilitator_signer.as_ref(),
+        claim_account.key(),
+        amount,
+        now,
+    )?;
+    rebate_fee(
+        ctx.accounts.fee_rebates.as_ref(),
+        ctx.accounts.provider_volume.as_mut(),
+        claim_account,
+        period,
+        amount,
+        &mut split,
+        now,
+    )?;
+    ctx.accounts
+        .protocol_treasury
+        .record_fee(split.treasury_fee)?;
+
+    let summary = open_settlement(
+        &mut ctx.accounts.settlement,
+        provider.key(),
+        patient.key(),
+        period,
+        now,
+    )?;
+    if claim_account.status == ClaimStatus::Paid {
+        summary.claims_paid += 1;
+    }
+    summary.amount_paid = Amount::lamports(summary.amount_paid)
+        .checked_add(Amount::lamports(amount))?
+        .raw;
+    summary.fees = Amount::lamports(summary.fees)
+        .checked_add(Amount::lamports(fee))?
+        .raw;
+
+    // A held payment waits in the hold for the clawback window
+    let payee = match ctx.accounts.payment_hold.as_mut() {
+        Some(payment_hold) => {
+            let Some(clawback_config) = ctx.accounts.clawback_config.as_ref() else {
+                msg!("A payment hold needs the clawback config");
+                return err!(ErrorCode::ClawbackDisabled);
+            };
+            payment_hold.hold(
+                claim_account.key(),
+                claim_account,
+                clawback_config,
+                split.received,
+                claim_account.principal_paid - paid_before.0,
+                claim_account.interest_paid - paid_before.1,
+                period,
+                now,
+            )?;
+            payment_hold.to_account_info()
+        }
+        None => patient.to_account_info(),
+    };
+    let cpi_context = CpiContext::new(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: provider.to_account_info(),
+            to: payee,
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, split.received)?;
+
+    if split.treasury_fee > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: provider.to_account_info(),
+                to: ctx.accounts.protocol_treasury.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, split.treasury_fee)?;
+    }
+
+    if let (Some(facilitator), true) = (&ctx.accounts.facilitator, split.facilitator_share > 0) {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: provider.to_account_info(),
+                to: facilitator.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, split.facilitator_share)?;
+    }
+
+    if let (Some(treasury), true) = (&ctx.accounts.treasury, fee > 0) {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: provider.to_account_info(),
+                to: treasury.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, fee)?;
+    }
+
+    record_audit(
+        ctx.accounts.audit_trail.as_mut(),
+        ctx.accounts.audit_entry.as_mut(),
+        patient.key(),
+        provider.key(),
+        AuditAction::ClaimPaid,
+        claim_account.key(),
+        now,
+    )
+}
+
+/// Splits a lamport payment of `amount` on `claim` with
+/// `Config::split_payment`, attributing it to `facilitator` when the
+/// facilitator and its signer are passed.
+fn split_payment(
+    config: &Config,
+    facilitator: Option<&mut Account<FacilitatorAccount>>,
+    facilitator_signer: Option<&Signer>,
+    claim: Pubkey,
+    amount: u64,
+    now: i64,
+) -> Result<PaymentSplit> {
+    match (facilitator, facilitator_signer) {
+        (None, None) => config.split_payment(amount, None),
+        (Some(facilitator), Some(signer)) => {
+            let split = config.split_payment(amount, Some((facilitator, signer.key())))?;
+            emit!(PaymentAttributed {
+                facilitator: facilitator.key(),
+                claim,
+                amount,
+                fee_share: split.facilitator_share,
+                timestamp: now,
+            });
+            Ok(split)
+        }
+        _ => {
+            msg!("An attributed payment needs the facilitator and its signer");
+            err!(ErrorCode::FacilitatorKeyMismatch)
+        }
+    }
+}
+
+/// Counts a lamport payment of `amount` on `claim` in its provider's volume
+/// for `period`, if `provider_volume` is passed, and takes the rebate the
+/// volume earlier in the period has earned out of the treasury's fee. The
+/// rebate is never moved: the provider keeps it, either by not paying it or
+/// as part of the escrow that closes to them.
+fn rebate_fee(
+    fee_rebates: Option<&Account<FeeRebates>>,
+    provider_volume: Option<&mut Account<ProviderVolume>>,
+    claim: &Account<ClaimAccount>,
+    period: u32,
+    amount: u64,
+    split: &mut PaymentSplit,
+    now: i64,
+) -> Result<()> {
+    let Some(provider_volume) = provider_volume else {
+        if fee_rebates.is_some() {
+            msg!("A fee rebate needs the provider's volume for the period");
+            return err!(ErrorCode::ProviderVolumeRequired);
+        }
+        return Ok(());
+    };
+    if provider_volume.period == 0 {
+        provider_volume.provider = claim.provider;
+        provider_volume.period = period;
+        provider_volume.account_version = ProviderVolume::VERSION;
+    }
+    let rebate_bps = fee_rebates.map_or(0, |rebates| rebates.rebate_bps(provider_volume.volume));
+    let rebate = provider_volume.record(amount, split.treasury_fee, rebate_bps)?;
+    if rebate == 0 {
+        return Ok(());
+    }
+    split.treasury_fee -= rebate;
+    emit!(FeeRebated {
+        provider: claim.provider,
+        claim: claim.key(),
+        period,
+        volume: provider_volume.volume,
+        rebate_bps,
+        rebate,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Pays `cranker` the crank reward for running a due crank on `target`, if
+/// the crank vault was passed. A vault short of the reward pays nothing
+/// rather than failing the crank.
+fn pay_crank_reward(
+    crank_vault: Option<&mut Account<CrankVault>>,
+    cranker: &AccountInfo,
+    target: Pubkey,
+    now: i64,
+) -> Result<()> {
+    let Some(crank_vault) = crank_vault else {
+        return Ok(());
+    };
+    let vault = crank_vault.to_account_info();
+    let available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+    let reward = crank_vault.reward_from(available);
+    if reward == 0 {
+        if crank_vault.reward_lamports > 0 {
+            msg!("The crank vault has {} lamports available", available);
+        }
+        return Ok(());
+    }
+
+    crank_vault.record_reward(reward)?;
+    **vault.try_borrow_mut_lamports()? -= reward;
+    **cranker.try_borrow_mut_lamports()? += reward;
+    emit!(CrankRewarded {
+        cranker: cranker.key(),
+        target,
+        amount: reward,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Resizes `info` to `space`, with `payer` topping up the rent, and replaces
+/// its data with `encoded` followed by zeroes.
+fn rewrite_account<'info>(
+    info: &AccountInfo<'info>,
+    encoded: &[u8],
+    space: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.to_account_info(),
+                to: info.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, shortfall)?;
+    }
+    info.resize(space)?;
+
+    let mut data = info.try_borrow_mut_data()?;
+    data.fill(0);
+    data[..encoded.len()].copy_from_slice(encoded);
+    Ok(())
+}
+
+/// Splits the first `entries * per_entry` remaining accounts into the
+/// accounts of a batch. Accounts after them, such as the clock offset, are
+/// left alone.
+fn batch_accounts<'c, 'info>(
+    remaining_accounts: &'c [AccountInfo<'info>],
+    entries: usize,
+    per_entry: usize,
+) -> Result<&'c [AccountInfo<'info>]> {
+    let needed = entries * per_entry;
+    if remaining_accounts.len() < needed {
+        msg!(
+            "{} entries need {} remaining accounts; got {}",
+            entries,
+            needed,
+            remaining_accounts.len()
+        );
+        return err!(ErrorCode::BatchAccountsMismatch);
+    }
+    Ok(&remaining_accounts[..needed])
+}
+
+/// Fails unless `info` is the program address of `seeds`.
+fn expect_batch_address(info: &AccountInfo, seeds: &[&[u8]]) -> Result<u8> {
+    let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    if info.key() != address {
+        msg!("Expected account {}; got {}", address, info.key());
+        return err!(ErrorCode::BatchAccountsMismatch);
+    }
+    Ok(bump)
+}
+
+/// Loads the program account at `info`, the address of `seeds`, creating it
+/// with `space` bytes and `payer` funding the rent if it does not exist yet.
+/// Batch instructions take their new accounts as remaining accounts, so they
+/// cannot use `init`; the caller writes the account back with `exit`.
+fn init_batch_account<'info, T>(
+    info: &'info AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<Account<'info, T>>
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    let bump = [expect_batch_address(info, seeds)?];
+    if *info.owner == crate::ID {
+        return Account::try_from(info);
+    }
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump);
+    let signer: &[&[&[u8]]] = &[&signer_seeds];
+    // Anyone can send lamports to the address first, which would make
+    // `create_account` fail, so top it up, allocate and assign instead
+    let required = Rent::get()?.minimum_balance(space);
+    let shortfall = required.saturating_sub(info.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: info.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: info.clone(),
+            },
+            signer,
+        ),
+        &crate::ID,
+    )?;
+    Account::try_from_unchecked(info)
+}
+
+/// Zeroes an account's data before it is re-serialized shorter, so what was
+/// past the old values reads as padding again.
+fn clear_account_data(info: &AccountInfo) -> Result<()> {
+    info.try_borrow_mut_data()?.fill(0);
+    Ok(())
+}
+
+/// Repays `sponsor` from its vault for the rent it just fronted on `created`.
+/// The vault is program-owned, so it cannot fund the account creation itself.
+fn reimburse_rent<'info>(
+    vault: &mut Account<'info, SponsorVault>,
+    sponsor: &Signer<'info>,
+    created: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = created.lamports();
+    let vault_info = vault.to_account_info();
+    let available = vault_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault_info.data_len()));
+    if rent > available {
+        msg!(
+            "Rent of {} lamports exceeds the {} available in the sponsor vault",
+            rent,
+            available
+        );
+        return err!(ErrorCode::InsufficientSponsorFunds);
+    }
+
+    vault.total_spent = vault
+        .total_spent
+        .checked_add(rent)
+        .ok_or(ErrorCode::AmountOverflow)?;
+    vault.sponsored_accounts += 1;
+    **vault_info.try_borrow_mut_lamports()? -= rent;
+    **sponsor.try_borrow_mut_lamports()? += rent;
+    Ok(())
+}
+
+/// Accrues a delivery on every subscription among `remaining_accounts` that
+/// covers `category`. Other remaining accounts, such as the clock offset, are
+/// skipped.
+fn accrue_subscriptions(
+    remaining_accounts: &[AccountInfo],
+    owner: Pubkey,
+    category: DataCategory,
+) -> Result<()> {
+    for info in remaining_accounts {
+        if *info.owner != crate::ID
+            || !info
+                .try_borrow_data()?
+                .starts_with(Subscription::DISCRIMINATOR)
+        {
+            continue;
+        }
+        let mut data = info.try_borrow_mut_data()?;
+        let mut subscription = Subscription::try_deserialize(&mut &data[..])?;
+        if subscription.patient != owner {
+            msg!("Subscription {} is for another patient", info.key());
+            return err!(ErrorCode::SubscriptionMismatch);
+        }
+        if subscription.category != category {
+            continue;
+        }
+        if !subscription.accrue() {
+            msg!("Subscription {} has run out of deposit", info.key());
+            continue;
+        }
+        subscription.try_serialize(&mut &mut data[..])?;
+    }
+    Ok(())
+}
+
+/// Revokes `grant` and the delegated grants among `remaining_accounts`,
+/// writing the descendants back.
+fn revoke_grant_chain(
+    grant: &mut Account<AccessGrant>,
+    parent: Option<&mut AccessGrant>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    let mut infos = Vec::new();
+    let mut descendants = Vec::new();
+    for (i, info) in remaining_accounts.iter().enumerate() {
+        if *info.owner != crate::ID
+            || !info
+                .try_borrow_data()?
+                .starts_with(AccessGrant::DISCRIMINATOR)
+        {
+            continue;
+        }
+        if remaining_accounts[..i]
+            .iter()
+            .any(|earlier| earlier.key() == info.key())
+        {
+            msg!("Grant {} was passed twice", info.key());
+            return err!(ErrorCode::DuplicateAccount);
+        }
+        let descendant = AccessGrant::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        infos.push(info);
+        descendants.push((info.key(), descendant));
+    }
+
+    let address = grant.key();
+    grant.revoke_chain(address, parent, &mut descendants)?;
+    for (info, (_, descendant)) in infos.into_iter().zip(&descendants) {
+        descendant.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+/// Chains `receipt` into the ledger, if one was passed, and emits it.
+fn record_consent(
+    ledger: Option<&mut Account<ConsentLedger>>,
+    mut receipt: ConsentReceipt,
+) -> Result<()> {
+    if let Some(ledger) = ledger {
+        ledger.append(&mut receipt)?;
+    }
+    emit!(receipt);
+    Ok(())
+}
+
+/// Appends an audit entry when the instruction was given the trail and its
+/// next entry.
+fn record_audit(
+    trail: Option<&mut Account<AuditTrail>>,
+    entry: Option<&mut Account<AuditEntry>>,
+    subject: Pubkey,
+    actor: Pubkey,
+    action: AuditAction,
+    target: Pubkey,
+    now: i64,
+) -> Result<()> {
+    match (trail, entry) {
+        (Some(trail), Some(entry)) => trail.append(subject, entry, actor, action, target, now),
+        (None, None) => Ok(()),
+        _ => {
+            msg!("Pass both the audit trail and its next entry, or neither");
+            err!(ErrorCode::AuditEntryMismatch)
+        }
+    }
+}
+
+/// Sequence of the next entry of an optional audit trail, for its seeds.
+fn next_audit_sequence(trail: &Option<Account<AuditTrail>>) -> u64 {
+    trail.as_ref().map_or(0, |trail| trail.entry_count)
+}
+
+/// Allows access when the patient's policy allows it, and otherwise requires
+/// an explicit grant covering `scope`.
+fn authorize(
+    policy: Option<&AccessPolicy>,
+    grant: Option<&AccessGrant>,
+    is_provider: bool,
+    categories: u8,
+    scope: u8,
+    now: i64,
+) -> Result<()> {
+    if let Some(PolicyEffect::Allow) =
+        policy.and_then(|policy| policy.decide(is_provider, categories, scope))
+    {
+        msg!("Allowed by the patient's access policy");
+        return Ok(());
+    }
+    expect_grant(grant, scope, now)
+}
+
+/// Who is writing a record and what they can show for it, shared by
+/// `submit_health_data`, `submit_health_data_sponsored`,
+/// `append_health_record` and `submit_health_data_batch`.
+struct RecordAuthor<'a> {
+    author: Pubkey,
+    owner: Pubkey,
+    /// The owner's delegate account for the author, if passed.
+    delegate: Option<&'a Delegate>,
+    policy: Option<&'a AccessPolicy>,
+    grant: Option<&'a AccessGrant>,
+    is_provider: bool,
+}
+
+/// Allows the owner to write their own records, their delegate with
+/// `DELEGATE_SUBMIT_DATA`, and anyone else `authorize` allows to write
+/// records in `categories`.
+fn authorize_author(author: RecordAuthor, categories: u8, now: i64) -> Result<()> {
+    if author.author == author.owner {
+        return Ok(());
+    }
+    match author.delegate {
+        Some(delegate) => delegate.authorize(DELEGATE_SUBMIT_DATA),
+        None => authorize(
+            author.policy,
+            author.grant,
+            author.is_provider,
+            categories,
+            GRANT_SCOPE_WRITE,
+            now,
+        ),
+    }
+}
+
+fn expect_grant(grant: Option<&AccessGrant>, scope: u8, now: i64) -> Result<()> {
+    let Some(grant) = grant else {
+        msg!("No grant from the owner was passed");
+        return err!(ErrorCode::GrantRequired);
+    };
+    if !grant.is_active(now) {
+        msg!(
+            "Grant revoked: {}, expires at {}; now is {}",
+            grant.revoked,
+            grant.expires_at,
+            now
+        );
+        return err!(ErrorCode::GrantInactive);
+    }
+    if !grant.allows(scope, now) {
+        msg!("Grant scope {:#04b} lacks {:#04b}", grant.scope, scope);
+        return err!(ErrorCode::InsufficientGrantScope);
+    }
+    Ok(())
+}
+
+/// Allows `actor` to act for `patient` when they are the patient, or a
+/// guardian whose link is active and covers `permission`.
+fn expect_guardian(
+    link: Option<&DependentLink>,
+    actor: Pubkey,
+    patient: Pubkey,
+    permission: u8,
+    now: i64,
+) -> Result<()> {
+    if actor == patient {
+        return Ok(());
+    }
+    let Some(link) = link else {
+        msg!("{} is not the patient and passed no guardian link", actor);
+        return err!(ErrorCode::GuardianLinkRequired);
+    };
+    if !link.is_active(now) {
+        msg!(
+            "Guardian link expired at {}; now is {}",
+            link.expires_at,
+            now
+        );
+        return err!(ErrorCode::GuardianLinkExpired);
+    }
+    if !link.allows(permission, now) {
+        msg!(
+            "Guardian permissions {:#04b} lack {:#04b}",
+            link.permissions,
+            permission
+        );
+        return err!(ErrorCode::InsufficientGuardianPermissions);
+    }
+    Ok(())
+}
+
+/// Allows `actor` to act for `patient` when they are the patient, or a
+/// delegate whose power of attorney covers `permission`.
+fn expect_delegate(
+    delegate: Option<&Delegate>,
+    actor: Pubkey,
+    patient: Pubkey,
+    permission: u8,
+) -> Result<()> {
+    if actor == patient {
+        return Ok(());
+    }
+    let Some(delegate) = delegate else {
+        msg!("{} is not the patient and passed no delegate", actor);
+        return err!(ErrorCode::DelegateRequired);
+    };
+    delegate.authorize(permission)
+}
+
+/// `expect_delegate` when a delegate account is passed, and otherwise
+/// `expect_guardian`.
+fn expect_agent(
+    delegate: Option<&Delegate>,
+    link: Option<&DependentLink>,
+    actor: Pubkey,
+    patient: Pubkey,
+    delegate_permission: u8,
+    guardian_permission: u8,
+    now: i64,
+) -> Result<()> {
+    if delegate.is_some() {
+        return expect_delegate(delegate, actor, patient, delegate_permission);
+    }
+    expect_guardian(link, actor, patient, guardian_permission, now)
+}
+
+fn validate_record(data_hash: &[u8; 32], pointer: &RecordPointer) -> Result<()> {
+    validate_hash("data_hash", data_hash)?;
+    validate_hash("commitment", &pointer.commitment)?;
+    if pointer.storage == StorageKind::Inline {
+        msg!("New records must be stored off-chain");
+        return err!(ErrorCode::InlineStorage);
+    }
+    validate_not_empty("cid", &pointer.cid, ErrorCode::PointerEmpty)?;
+    validate_len(
+        "cid",
+        &pointer.cid,
+        MAX_POINTER_LEN,
+        ErrorCode::PointerTooLong,
+    )
+}
+
+fn validate_encryption_key(x25519_key: &[u8; 32]) -> Result<()> {
+    if *x25519_key == [0; 32] {
+        msg!("The encryption key is all zeros");
+        return err!(ErrorCode::InvalidEncryptionKey);
+    }
+    Ok(())
+}
+
+fn validate_facilitator_key(signing_key: &Pubkey) -> Result<()> {
+    if *signing_key == Pubkey::default() {
+        msg!("The facilitator signing key is the default key");
+        return err!(ErrorCode::InvalidFacilitatorKey);
+    }
+    Ok(())
+}
+
+fn validate_did(did: &str) -> Result<()> {
+    validate_not_empty("did", did, ErrorCode::DidEmpty)?;
+    validate_len("did", did, MAX_DID_LEN, ErrorCode::DidTooLong)
+}
+
+fn validate_code(code: &str) -> Result<()> {
+    validate_not_empty("code", code, ErrorCode::CodeEmpty)?;
+    validate_len("code", code, MAX_CODE_LEN, ErrorCode::CodeTooLong)
+}
+
+/// Rejects the zeroed hash, which stands for "none" in `derived_from`.
+fn validate_hash(field: &str, hash: &[u8; 32]) -> Result<()> {
+    if *hash == [0; 32] {
+        msg!("{} is all zeroes", field);
+        return err!(ErrorCode::HashEmpty);
+    }
+    Ok(())
+}
+
+/// Hex form of a hash, for logs.
+fn hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn expect_status(actual: ClaimStatus, expected: &[ClaimStatus], error: ErrorCode) -> Result<()> {
+    if !expected.contains(&actual) {
+        msg!(
+            "Claim status is {:?}; expected one of {:?}",
+            actual,
+            expected
+        );
+        return Err(error.into());
+    }
+    Ok(())
 }
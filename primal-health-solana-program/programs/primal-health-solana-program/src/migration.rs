@@ -0,0 +1,1857 @@
+//! Upgrades accounts written with earlier layouts to the current ones.
+//!
+//! Previous layouts are kept here verbatim so their bytes can still be read.
+//! Each migration decodes the old layout, converts it and re-encodes it with
+//! the current struct; `migrate_claim` then resizes the account to fit.
+//!
+//! Every account type also ends in an `account_version` byte, listed in
+//! [`ACCOUNT_VERSIONS`]. Accounts written before the byte existed read 0 from
+//! their padding, or from past the end of the data if they had none, and
+//! `upgrade_account` rewrites any of them with the current version.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use solana_program::hash::hashv;
+
+use crate::clock::ClockOffset;
+#[cfg(feature = "demo")]
+use crate::demo::{DemoConfig, DemoVault};
+use crate::{
+    AccessGrant, AccessPolicy, Appointment, ApprovalPolicy, AuditEntry, AuditTrail, AutoApproval,
+    CareTeam, ClaimAccount, ClaimDedup, ClaimDispute, ClaimEscrow, ClaimGrant, ClaimLineItem,
+    ClaimNotesPage, ClaimStatus, ClawbackConfig, Config, ConsentAccount, ConsentLedger, CrankVault,
+    DataAccessOffer, DataCategory, DataValidator, Delegate, DependentLink, DeviceAccount,
+    DeviceRollup, DisputeConfig, EmergencyAccess, EncryptionKey, ErrorCode, FacilitatorAccount,
+    FeatureFlags, FeeRebates, FeeSchedule, FieldCommitment, FraudFinding, HealthDataAccount,
+    HealthDataBatchAccount, HealthDataIndex, HealthRecordEntry, InsurerAccount, KeyEnvelope,
+    LawfulBasis, LineItemStatus, MintTreasury, OutOfPocket, PatientAccount, PaymentHold,
+    PaymentSchedule, PlanBenefits, PlanSubscription, PremiumPayment, PriceTier, ProtocolTreasury,
+    ProviderAccount, ProviderClaimStats, ProviderCredential, ProviderStake, ProviderVolume,
+    QualityAttestation, ReencryptionRequest, RegistryAuthority, ResearchConsent, SecondOpinion,
+    SettlementSummary, SponsorVault, StorageKind, Subscription, SubscriptionPlan, Tenant,
+    TenantMember, ValidatorRegistry,
+};
+
+/// An account type carrying an `account_version` byte.
+pub trait Versioned: AccountSerialize + AccountDeserialize + Discriminator + Sized {
+    /// Version written by the current layout.
+    const VERSION: u8;
+
+    fn account_version(&self) -> u8;
+
+    fn set_account_version(&mut self, version: u8);
+
+    /// Decodes the account from the current layout, or from the layout before
+    /// `account_version` was added when the data ends short of it.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        if let Ok(account) = Self::try_deserialize(&mut &data[..]) {
+            return Ok(account);
+        }
+        let mut padded = data.to_vec();
+        padded.push(0);
+        Self::try_deserialize(&mut &padded[..]).map_err(|_| {
+            msg!("Account matches no known layout");
+            error!(ErrorCode::UnknownAccountLayout)
+        })
+    }
+
+    /// Space the account needs once upgraded, given its encoded length.
+    fn upgraded_space(&self, encoded_len: usize) -> usize {
+        encoded_len
+    }
+}
+
+macro_rules! versioned {
+    ($($ty:ty => $version:literal),* $(,)?) => {
+        $(
+            impl Versioned for $ty {
+                const VERSION: u8 = $version;
+
+                fn account_version(&self) -> u8 {
+                    self.account_version
+                }
+
+                fn set_account_version(&mut self, version: u8) {
+                    self.account_version = version;
+                }
+            }
+        )*
+    };
+}
+
+versioned! {
+    PatientAccount => 1,
+    ProviderAccount => 1,
+    InsurerAccount => 1,
+    FieldCommitment => 1,
+    ValidatorRegistry => 1,
+    DataValidator => 1,
+    QualityAttestation => 1,
+    DependentLink => 1,
+    Delegate => 1,
+    KeyEnvelope => 1,
+    ReencryptionRequest => 1,
+    SponsorVault => 1,
+    ProtocolTreasury => 1,
+    MintTreasury => 1,
+    FacilitatorAccount => 1,
+    CrankVault => 1,
+    FeatureFlags => 1,
+    Tenant => 1,
+    TenantMember => 1,
+    ConsentLedger => 1,
+    SettlementSummary => 1,
+    Subscription => 1,
+    SubscriptionPlan => 1,
+    PlanSubscription => 1,
+    AccessPolicy => 1,
+    AutoApproval => 1,
+    PaymentSchedule => 1,
+    SecondOpinion => 1,
+    CareTeam => 1,
+    EmergencyAccess => 1,
+    AuditTrail => 1,
+    AuditEntry => 1,
+    FeeSchedule => 1,
+    Appointment => 1,
+    ClawbackConfig => 1,
+    PaymentHold => 1,
+    ClaimGrant => 1,
+    ClaimDedup => 1,
+    ClaimNotesPage => 1,
+    ApprovalPolicy => 1,
+    HealthDataIndex => 1,
+    DeviceAccount => 1,
+    DeviceRollup => 1,
+    RegistryAuthority => 1,
+    ProviderCredential => 1,
+    ClockOffset => 1,
+    FeeRebates => 1,
+    ProviderVolume => 1,
+    PremiumPayment => 1,
+    ResearchConsent => 1,
+    OutOfPocket => 1,
+    ProviderStake => 1,
+    FraudFinding => 1,
+}
+
+#[cfg(feature = "demo")]
+versioned! {
+    DemoConfig => 1,
+    DemoVault => 1,
+}
+
+impl Versioned for ClaimAccount {
+    const VERSION: u8 = 10;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// Claims have always been allocated with padding, so the layouts to
+    /// convert are `ClaimAccountV0` through `ClaimAccountV9`.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        match decode_current::<Self>(data) {
+            Some(claim) => Ok(claim),
+            None => decode_legacy_claim(data),
+        }
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        claim_space(self)
+    }
+}
+
+impl Versioned for AccessGrant {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// An `AccessGrantV1` is too short for the current layout; one written
+    /// before `account_version` existed reads 0 from past its end.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, AccessGrantV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Grant"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        AccessGrant::SPACE
+    }
+}
+
+impl Versioned for DataAccessOffer {
+    const VERSION: u8 = 3;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// A `DataAccessOfferV1` also decodes as a `DataAccessOfferV2`, with its
+    /// version byte read as the tier count, so each is told apart by version.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, DataAccessOfferV2>(data, |legacy| legacy.account_version == 2)
+                    .map(Into::into)
+            })
+            .or_else(|| {
+                decode_legacy::<Self, DataAccessOfferV1>(data, |legacy| legacy.account_version == 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Offer"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        DataAccessOffer::SPACE
+    }
+}
+
+impl Versioned for HealthDataAccount {
+    const VERSION: u8 = 4;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// A `HealthDataAccountV3` also decodes as the current layout, with its
+    /// version byte read as the key version, so the version is checked first.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, HealthDataAccountV3>(data, |legacy| {
+                    legacy.account_version == 3
+                })
+                .map(Into::into)
+            })
+            .or_else(|| {
+                decode_legacy::<Self, HealthDataAccountV2>(data, |legacy| {
+                    legacy.account_version == 2
+                })
+                .map(|legacy| HealthDataAccountV3::from(legacy).into())
+            })
+            .or_else(|| {
+                decode_legacy::<Self, HealthDataAccountV1>(data, |legacy| {
+                    legacy.account_version <= 1
+                })
+                .map(|legacy| HealthDataAccountV3::from(HealthDataAccountV2::from(legacy)).into())
+            })
+            .ok_or_else(|| unknown_layout("Health data"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        HealthDataAccount::space(&self.cid)
+    }
+}
+
+impl Versioned for EncryptionKey {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// An `EncryptionKeyV1` reads as the current layout with its version
+    /// byte as the key version, so it is told apart by `account_version`.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, EncryptionKeyV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Encryption key"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        EncryptionKey::SPACE
+    }
+}
+
+impl Versioned for Config {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// A `ConfigV1` reads as the current layout with its version byte as the
+    /// claim lifetime, so it is told apart by `account_version`.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, ConfigV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Config"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        Config::SPACE
+    }
+}
+
+impl Versioned for ConsentAccount {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, ConsentAccountV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Consent"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        ConsentAccount::SPACE
+    }
+}
+
+impl Versioned for ClaimEscrow {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    /// A `ClaimEscrowV1` also decodes as the current layout, reading its
+    /// version byte and padding as an arbiter, so it is told apart by version.
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, ClaimEscrowV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Escrow"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        ClaimEscrow::SPACE
+    }
+}
+
+impl Versioned for HealthDataBatchAccount {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, HealthDataBatchAccountV1>(data, |legacy| {
+                    legacy.account_version <= 1
+                })
+                .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Batch"))
+    }
+}
+
+impl Versioned for DisputeConfig {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, DisputeConfigV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Dispute config"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        DisputeConfig::SPACE
+    }
+}
+
+impl Versioned for ClaimDispute {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, ClaimDisputeV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Dispute"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        ClaimDispute::SPACE
+    }
+}
+
+impl Versioned for PlanBenefits {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, PlanBenefitsV1>(data, |legacy| legacy.account_version <= 1)
+                    .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Plan benefits"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        PlanBenefits::SPACE
+    }
+}
+
+impl Versioned for ProviderClaimStats {
+    const VERSION: u8 = 2;
+
+    fn account_version(&self) -> u8 {
+        self.account_version
+    }
+
+    fn set_account_version(&mut self, version: u8) {
+        self.account_version = version;
+    }
+
+    fn decode_any(data: &[u8]) -> Result<Self> {
+        decode_current(data)
+            .or_else(|| {
+                decode_legacy::<Self, ProviderClaimStatsV1>(data, |legacy| {
+                    legacy.account_version <= 1
+                })
+                .map(Into::into)
+            })
+            .ok_or_else(|| unknown_layout("Provider stats"))
+    }
+
+    fn upgraded_space(&self, _encoded_len: usize) -> usize {
+        ProviderClaimStats::SPACE
+    }
+}
+
+/// The account in the current layout, if it carries the current version.
+fn decode_current<T: Versioned>(data: &[u8]) -> Option<T> {
+    T::try_deserialize(&mut &data[..])
+        .ok()
+        .filter(|account| account.account_version() == T::VERSION)
+}
+
+/// The account in the earlier layout `L`, if `accepts` its version. A byte of
+/// padding stands in for the version of accounts written before it existed.
+fn decode_legacy<T: Versioned, L: AnchorDeserialize>(
+    data: &[u8],
+    accepts: impl Fn(&L) -> bool,
+) -> Option<L> {
+    let mut padded = data.to_vec();
+    padded.push(0);
+    padded
+        .strip_prefix(T::DISCRIMINATOR)
+        .and_then(|mut body| L::deserialize(&mut body).ok())
+        .filter(accepts)
+}
+
+fn unknown_layout(kind: &str) -> Error {
+    msg!("{} account matches no known layout", kind);
+    error!(ErrorCode::UnknownAccountLayout)
+}
+
+macro_rules! registry {
+    ($($(#[$meta:meta])* $ty:ty),* $(,)?) => {
+        /// Current version of every account type, by type name.
+        pub const ACCOUNT_VERSIONS: &[(&str, u8)] = &[
+            $($(#[$meta])* (stringify!($ty), <$ty as Versioned>::VERSION),)*
+        ];
+
+        /// Re-encodes a program account of any type at its current version,
+        /// returning the new data and the space it needs, or `None` when the
+        /// account is already current.
+        pub fn upgrade(data: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+            $(
+                $(#[$meta])*
+                if data.starts_with(<$ty as Discriminator>::DISCRIMINATOR) {
+                    return upgrade_as::<$ty>(data);
+                }
+            )*
+            msg!("Account is not one of this program's account types");
+            err!(ErrorCode::UnknownAccountLayout)
+        }
+    };
+}
+
+registry! {
+    PatientAccount,
+    ProviderAccount,
+    InsurerAccount,
+    HealthDataAccount,
+    FieldCommitment,
+    ValidatorRegistry,
+    DataValidator,
+    QualityAttestation,
+    AccessGrant,
+    EncryptionKey,
+    DependentLink,
+    Delegate,
+    KeyEnvelope,
+    SponsorVault,
+    Config,
+    ProtocolTreasury,
+    MintTreasury,
+    FacilitatorAccount,
+    CrankVault,
+    FeatureFlags,
+    Tenant,
+    TenantMember,
+    ConsentLedger,
+    SettlementSummary,
+    Subscription,
+    SubscriptionPlan,
+    PlanSubscription,
+    AccessPolicy,
+    ConsentAccount,
+    ReencryptionRequest,
+    DataAccessOffer,
+    HealthDataBatchAccount,
+    ClaimAccount,
+    AutoApproval,
+    PaymentSchedule,
+    SecondOpinion,
+    CareTeam,
+    EmergencyAccess,
+    AuditTrail,
+    AuditEntry,
+    FeeSchedule,
+    Appointment,
+    DisputeConfig,
+    ClawbackConfig,
+    PaymentHold,
+    ClaimDispute,
+    ClaimGrant,
+    ClaimDedup,
+    ProviderClaimStats,
+    ClaimNotesPage,
+    ClaimEscrow,
+    ApprovalPolicy,
+    HealthDataIndex,
+    DeviceAccount,
+    DeviceRollup,
+    RegistryAuthority,
+    ProviderCredential,
+    ClockOffset,
+    FeeRebates,
+    ProviderVolume,
+    PlanBenefits,
+    PremiumPayment,
+    ResearchConsent,
+    OutOfPocket,
+    ProviderStake,
+    FraudFinding,
+    #[cfg(feature = "demo")]
+    DemoConfig,
+    #[cfg(feature = "demo")]
+    DemoVault,
+}
+
+fn upgrade_as<T: Versioned>(data: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+    let mut account = T::decode_any(data)?;
+    if account.account_version() == T::VERSION {
+        return Ok(None);
+    }
+    account.set_account_version(T::VERSION);
+    let mut encoded = Vec::new();
+    account.try_serialize(&mut encoded)?;
+    let space = account.upgraded_space(encoded.len()).max(encoded.len());
+    Ok(Some((encoded, space)))
+}
+
+/// Code given to the single line item synthesized for a pre-line-item claim.
+pub const LEGACY_LINE_CODE: &str = "LEGACY";
+
+/// `ClaimAccount` as written before line items, attachments and notes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimAccountV0 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub health_data_hash: String,
+    pub amount: u64,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+}
+
+impl From<ClaimAccountV0> for ClaimAccount {
+    fn from(legacy: ClaimAccountV0) -> Self {
+        // The whole claimed amount becomes one line, already approved if the
+        // claim was verified or paid so the payment path still works
+        let line_status = match legacy.status {
+            ClaimStatus::Verified | ClaimStatus::Paid => LineItemStatus::Approved,
+            _ => LineItemStatus::Pending,
+        };
+        let attachments = if legacy.health_data_hash.is_empty() {
+            Vec::new()
+        } else {
+            vec![legacy_hash(&legacy.health_data_hash)]
+        };
+        // The rejection time was not recorded; creation is the best available
+        let rejected_at = if legacy.status == ClaimStatus::Rejected {
+            legacy.timestamp
+        } else {
+            0
+        };
+
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments,
+            amount: legacy.amount,
+            line_items: vec![ClaimLineItem {
+                code: LEGACY_LINE_CODE.to_string(),
+                units: 1,
+                unit_price: legacy.amount,
+                modifier: String::new(),
+                status: line_status,
+                reason_code: 0,
+            }],
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: 0,
+            rejected_at,
+            reopened: false,
+            verified_at: 0,
+            principal_paid: 0,
+            interest_paid: 0,
+            tenant: Pubkey::default(),
+            mint: Pubkey::default(),
+            external_ref: String::new(),
+            tags: Vec::new(),
+            service_start: 0,
+            service_end: 0,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before claims could be paid in an SPL token.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV1 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV1> for ClaimAccount {
+    fn from(legacy: ClaimAccountV1) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: Pubkey::default(),
+            external_ref: String::new(),
+            tags: Vec::new(),
+            service_start: 0,
+            service_end: 0,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// What a `ClaimAccountV1` read as a later layout has for `mint`: its
+/// version byte followed by padding.
+pub const V1_CLAIM_MINT: Pubkey = Pubkey::new_from_array({
+    let mut bytes = [0; 32];
+    bytes[0] = 1;
+    bytes
+});
+
+/// `ClaimAccount` as written before external references and tags.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV2 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV2> for ClaimAccount {
+    fn from(legacy: ClaimAccountV2) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: String::new(),
+            tags: Vec::new(),
+            service_start: 0,
+            service_end: 0,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before service periods were recorded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV3 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV3> for ClaimAccount {
+    fn from(legacy: ClaimAccountV3) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: 0,
+            service_end: 0,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before approval policies could be attached.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV4 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV4> for ClaimAccount {
+    fn from(legacy: ClaimAccountV4) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before the rent payer was recorded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV5 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub approval_policy: Pubkey,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV5> for ClaimAccount {
+    fn from(legacy: ClaimAccountV5) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: legacy.approval_policy,
+            required_approvals: legacy.required_approvals,
+            approvals: legacy.approvals,
+            // Filers were not recorded; the patient files most claims
+            rent_payer: legacy.patient,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written when attachments were stored as strings.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV6 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<String>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub approval_policy: Pubkey,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub rent_payer: Pubkey,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV6> for ClaimAccount {
+    fn from(legacy: ClaimAccountV6) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy_hashes(&legacy.attachments),
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: legacy.approval_policy,
+            required_approvals: legacy.required_approvals,
+            approvals: legacy.approvals,
+            rent_payer: legacy.rent_payer,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before claims could name an insurer.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV7 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<[u8; 32]>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub approval_policy: Pubkey,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub rent_payer: Pubkey,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV7> for ClaimAccount {
+    fn from(legacy: ClaimAccountV7) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy.attachments,
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: legacy.approval_policy,
+            required_approvals: legacy.required_approvals,
+            approvals: legacy.approvals,
+            rent_payer: legacy.rent_payer,
+            insurer: Pubkey::default(),
+            insurer_approved_at: 0,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before claims expired.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV8 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<[u8; 32]>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub approval_policy: Pubkey,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub rent_payer: Pubkey,
+    pub insurer: Pubkey,
+    pub insurer_approved_at: i64,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV8> for ClaimAccount {
+    fn from(legacy: ClaimAccountV8) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy.attachments,
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: legacy.approval_policy,
+            required_approvals: legacy.required_approvals,
+            approvals: legacy.approvals,
+            rent_payer: legacy.rent_payer,
+            insurer: legacy.insurer,
+            insurer_approved_at: legacy.insurer_approved_at,
+            expires_at: 0,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimAccount` as written before payers covered patient shares past the
+/// out-of-pocket maximum.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClaimAccountV9 {
+    pub claim_id: String,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub attachments: Vec<[u8; 32]>,
+    pub amount: u64,
+    pub line_items: Vec<ClaimLineItem>,
+    pub status: ClaimStatus,
+    pub timestamp: i64,
+    pub note_count: u32,
+    pub rejected_at: i64,
+    pub reopened: bool,
+    pub verified_at: i64,
+    pub principal_paid: u64,
+    pub interest_paid: u64,
+    pub tenant: Pubkey,
+    pub mint: Pubkey,
+    pub external_ref: String,
+    pub tags: Vec<String>,
+    pub service_start: i64,
+    pub service_end: i64,
+    pub approval_policy: Pubkey,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub rent_payer: Pubkey,
+    pub insurer: Pubkey,
+    pub insurer_approved_at: i64,
+    pub expires_at: i64,
+    pub account_version: u8,
+}
+
+impl From<ClaimAccountV9> for ClaimAccount {
+    fn from(legacy: ClaimAccountV9) -> Self {
+        ClaimAccount {
+            claim_id: legacy.claim_id,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            attachments: legacy.attachments,
+            amount: legacy.amount,
+            line_items: legacy.line_items,
+            status: legacy.status,
+            timestamp: legacy.timestamp,
+            note_count: legacy.note_count,
+            rejected_at: legacy.rejected_at,
+            reopened: legacy.reopened,
+            verified_at: legacy.verified_at,
+            principal_paid: legacy.principal_paid,
+            interest_paid: legacy.interest_paid,
+            tenant: legacy.tenant,
+            mint: legacy.mint,
+            external_ref: legacy.external_ref,
+            tags: legacy.tags,
+            service_start: legacy.service_start,
+            service_end: legacy.service_end,
+            approval_policy: legacy.approval_policy,
+            required_approvals: legacy.required_approvals,
+            approvals: legacy.approvals,
+            rent_payer: legacy.rent_payer,
+            insurer: legacy.insurer,
+            insurer_approved_at: legacy.insurer_approved_at,
+            expires_at: legacy.expires_at,
+            payer_covered: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// Decodes a claim account in any supported layout, returning the current
+/// layout and whether it had to be converted. A converted claim is stamped
+/// with the current version, so once written back it decodes as current.
+pub fn decode_claim(data: &[u8]) -> Result<(ClaimAccount, bool)> {
+    if let Some(claim) = decode_current::<ClaimAccount>(data) {
+        return Ok((claim, false));
+    }
+    let mut claim = decode_legacy_claim(data)?;
+    claim.account_version = ClaimAccount::VERSION;
+    Ok((claim, true))
+}
+
+/// Converts a claim written in an earlier layout.
+fn decode_legacy_claim(data: &[u8]) -> Result<ClaimAccount> {
+    let Some(mut body) = data.strip_prefix(ClaimAccount::DISCRIMINATOR) else {
+        msg!("Account is not a claim");
+        return err!(ErrorCode::UnknownAccountLayout);
+    };
+    // Older layouts also decode as later ones, with their version byte read
+    // as part of a later field, so each is tried by its exact version.
+    // Newer layouts are tried first: a `ClaimAccountV9` read as a V8 has the
+    // first byte of its `expires_at` as its version, a `ClaimAccountV8` read
+    // as a V7 the first byte of its `insurer`, a `ClaimAccountV7` read as a
+    // V6 fails on its attachments or, without any, keeps its version byte in
+    // place, a `ClaimAccountV6` read as a V5 has the first byte of its
+    // `rent_payer` as its version, a `ClaimAccountV5` read as a V4 the first
+    // byte of its `approval_policy`, a `ClaimAccountV4` read as a V3 the
+    // first byte of its `service_start`, a `ClaimAccountV3` read as a V2 the
+    // first byte of its `external_ref` length, and a V2's version byte cannot
+    // be mistaken for a V1's, which would sit at the start of its `mint`.
+    if let Ok(legacy) = ClaimAccountV9::deserialize(&mut &body[..]) {
+        if legacy.account_version == 9 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV8::deserialize(&mut &body[..]) {
+        if legacy.account_version == 8 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV7::deserialize(&mut &body[..]) {
+        if legacy.account_version == 7 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV6::deserialize(&mut &body[..]) {
+        if legacy.account_version == 6 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV5::deserialize(&mut &body[..]) {
+        if legacy.account_version == 5 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV4::deserialize(&mut &body[..]) {
+        if legacy.account_version == 4 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV3::deserialize(&mut &body[..]) {
+        if legacy.account_version == 3 {
+            return Ok(legacy.into());
+        }
+    }
+    if let Ok(legacy) = ClaimAccountV2::deserialize(&mut &body[..]) {
+        if legacy.account_version == 2 {
+            return Ok(legacy.into());
+        }
+    }
+    // Claims from before the version byte are `ClaimAccountV1`s without it,
+    // and read zero from their padding in every layout.
+    if let Ok(legacy) = ClaimAccountV1::deserialize(&mut &body[..]) {
+        if legacy.account_version <= 1 {
+            return Ok(legacy.into());
+        }
+    }
+    let legacy = ClaimAccountV0::deserialize(&mut body).map_err(|_| {
+        msg!("Claim account matches no known layout");
+        error!(ErrorCode::UnknownAccountLayout)
+    })?;
+    Ok(legacy.into())
+}
+
+/// Space allocated for a claim in the current layout, matching `create_claim`.
+pub fn claim_space(claim: &ClaimAccount) -> usize {
+    new_claim_space(&claim.claim_id, claim.line_items.len())
+}
+
+/// Space for a new claim with `claim_id` and `line_items` lines.
+pub fn new_claim_space(claim_id: &str, line_items: usize) -> usize {
+    8 + 4
+        + claim_id.len()
+        + 32
+        + 32
+        + 4
+        + crate::MAX_ATTACHMENTS * 32
+        + 8
+        + 4
+        + line_items * ClaimLineItem::SPACE
+        + 1
+        + 1
+        + 8
+        + 4
+        + 8
+        + 1
+        + 8
+        + 8
+        + 8
+        + 32
+        + 32
+        + 4
+        + crate::MAX_EXTERNAL_REF_LEN
+        + 4
+        + crate::MAX_CLAIM_TAGS * (4 + crate::MAX_TAG_LEN)
+        + 8
+        + 8
+        + 32
+        + 1
+        + 1
+        + 32
+        + 32
+        + 8
+        + 8
+        + 8
+        + 1
+        + 64
+}
+
+/// `DataAccessOffer` as written before price tiers.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DataAccessOfferV1 {
+    pub owner: Pubkey,
+    pub offer_id: String,
+    pub resource_hash: String,
+    pub price: u64,
+    pub access_secs: i64,
+    pub sales: u64,
+    pub created_at: i64,
+    pub account_version: u8,
+}
+
+impl From<DataAccessOfferV1> for DataAccessOffer {
+    fn from(legacy: DataAccessOfferV1) -> Self {
+        DataAccessOffer {
+            owner: legacy.owner,
+            offer_id: legacy.offer_id,
+            resource_hash: legacy_hash(&legacy.resource_hash),
+            price: legacy.price,
+            access_secs: legacy.access_secs,
+            sales: legacy.sales,
+            created_at: legacy.created_at,
+            price_tiers: Vec::new(),
+            account_version: 0,
+        }
+    }
+}
+
+/// `AccessGrant` as written before delegation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccessGrantV1 {
+    pub patient: Pubkey,
+    pub grantee: Pubkey,
+    pub scope: u8,
+    pub granted_at: i64,
+    pub expires_at: i64,
+    pub revoked: bool,
+    pub lawful_basis: LawfulBasis,
+    pub account_version: u8,
+}
+
+impl From<AccessGrantV1> for AccessGrant {
+    fn from(legacy: AccessGrantV1) -> Self {
+        AccessGrant {
+            patient: legacy.patient,
+            grantee: legacy.grantee,
+            scope: legacy.scope,
+            granted_at: legacy.granted_at,
+            expires_at: legacy.expires_at,
+            revoked: legacy.revoked,
+            lawful_basis: legacy.lawful_basis,
+            parent: Pubkey::default(),
+            depth: 0,
+            delegations: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `DataAccessOffer` as written when the resource hash was a string.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DataAccessOfferV2 {
+    pub owner: Pubkey,
+    pub offer_id: String,
+    pub resource_hash: String,
+    pub price: u64,
+    pub access_secs: i64,
+    pub sales: u64,
+    pub created_at: i64,
+    pub price_tiers: Vec<PriceTier>,
+    pub account_version: u8,
+}
+
+impl From<DataAccessOfferV2> for DataAccessOffer {
+    fn from(legacy: DataAccessOfferV2) -> Self {
+        DataAccessOffer {
+            owner: legacy.owner,
+            offer_id: legacy.offer_id,
+            resource_hash: legacy_hash(&legacy.resource_hash),
+            price: legacy.price,
+            access_secs: legacy.access_secs,
+            sales: legacy.sales,
+            created_at: legacy.created_at,
+            price_tiers: legacy.price_tiers,
+            account_version: 0,
+        }
+    }
+}
+
+/// `HealthDataAccount` as written when hashes were strings.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthDataAccountV1 {
+    pub owner: Pubkey,
+    pub data_hash: String,
+    pub encrypted_data: String,
+    pub timestamp: i64,
+    pub author: Pubkey,
+    pub derived_from: String,
+    pub account_version: u8,
+}
+
+impl From<HealthDataAccountV1> for HealthDataAccountV2 {
+    fn from(legacy: HealthDataAccountV1) -> Self {
+        HealthDataAccountV2 {
+            owner: legacy.owner,
+            data_hash: legacy_hash(&legacy.data_hash),
+            encrypted_data: legacy.encrypted_data,
+            timestamp: legacy.timestamp,
+            author: legacy.author,
+            derived_from: legacy_hash(&legacy.derived_from),
+            account_version: 0,
+        }
+    }
+}
+
+/// `HealthDataAccount` as written while the encrypted payload was stored in
+/// the account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthDataAccountV2 {
+    pub owner: Pubkey,
+    pub data_hash: [u8; 32],
+    pub encrypted_data: String,
+    pub timestamp: i64,
+    pub author: Pubkey,
+    pub derived_from: [u8; 32],
+    pub account_version: u8,
+}
+
+impl From<HealthDataAccountV2> for HealthDataAccountV3 {
+    fn from(legacy: HealthDataAccountV2) -> Self {
+        // The ciphertext stays where it was, so the record still reads; its
+        // commitment is its hash, as for an off-chain payload
+        HealthDataAccountV3 {
+            owner: legacy.owner,
+            data_hash: legacy.data_hash,
+            storage: StorageKind::Inline,
+            commitment: hashv(&[legacy.encrypted_data.as_bytes()]).to_bytes(),
+            cid: legacy.encrypted_data,
+            timestamp: legacy.timestamp,
+            author: legacy.author,
+            derived_from: legacy.derived_from,
+            account_version: 0,
+        }
+    }
+}
+
+/// `HealthDataAccount` as written before records noted the version of the
+/// owner's encryption key.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthDataAccountV3 {
+    pub owner: Pubkey,
+    pub data_hash: [u8; 32],
+    pub storage: StorageKind,
+    pub cid: String,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+    pub author: Pubkey,
+    pub derived_from: [u8; 32],
+    pub account_version: u8,
+}
+
+impl From<HealthDataAccountV3> for HealthDataAccount {
+    fn from(legacy: HealthDataAccountV3) -> Self {
+        HealthDataAccount {
+            owner: legacy.owner,
+            data_hash: legacy.data_hash,
+            storage: legacy.storage,
+            cid: legacy.cid,
+            commitment: legacy.commitment,
+            timestamp: legacy.timestamp,
+            author: legacy.author,
+            derived_from: legacy.derived_from,
+            key_version: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `EncryptionKey` as written before keys were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionKeyV1 {
+    pub owner: Pubkey,
+    pub x25519_key: [u8; 32],
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl From<EncryptionKeyV1> for EncryptionKey {
+    fn from(legacy: EncryptionKeyV1) -> Self {
+        // Whatever key was registered counts as the first
+        EncryptionKey {
+            owner: legacy.owner,
+            x25519_key: legacy.x25519_key,
+            updated_at: legacy.updated_at,
+            key_version: 1,
+            account_version: 0,
+        }
+    }
+}
+
+/// `Config` as written before claims expired.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigV1 {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub fee_bps: u16,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl From<ConfigV1> for Config {
+    fn from(legacy: ConfigV1) -> Self {
+        Config {
+            admin: legacy.admin,
+            paused: legacy.paused,
+            fee_bps: legacy.fee_bps,
+            updated_at: legacy.updated_at,
+            claim_ttl_secs: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimEscrow` as written before split custody.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimEscrowV1 {
+    pub claim: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub funded_at: i64,
+    pub account_version: u8,
+}
+
+impl From<ClaimEscrowV1> for ClaimEscrow {
+    fn from(legacy: ClaimEscrowV1) -> Self {
+        ClaimEscrow {
+            claim: legacy.claim,
+            provider: legacy.provider,
+            amount: legacy.amount,
+            funded_at: legacy.funded_at,
+            arbiter: Pubkey::default(),
+            account_version: 0,
+        }
+    }
+}
+
+/// `ConsentAccount` as written when the record hash was a string.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConsentAccountV1 {
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub record: Pubkey,
+    pub data_hash: String,
+    pub scope: u8,
+    pub granted_at: i64,
+    pub expires_at: i64,
+    pub account_version: u8,
+}
+
+impl From<ConsentAccountV1> for ConsentAccount {
+    fn from(legacy: ConsentAccountV1) -> Self {
+        ConsentAccount {
+            patient: legacy.patient,
+            provider: legacy.provider,
+            record: legacy.record,
+            data_hash: legacy_hash(&legacy.data_hash),
+            scope: legacy.scope,
+            granted_at: legacy.granted_at,
+            expires_at: legacy.expires_at,
+            account_version: 0,
+        }
+    }
+}
+
+/// `HealthDataBatchAccount` as written when entry hashes were strings.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HealthDataBatchAccountV1 {
+    pub owner: Pubkey,
+    pub batch_id: String,
+    pub entries: Vec<HealthRecordEntryV1>,
+    pub timestamp: i64,
+    pub account_version: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HealthRecordEntryV1 {
+    pub data_hash: String,
+    pub pointer: String,
+    pub category: DataCategory,
+}
+
+impl From<HealthDataBatchAccountV1> for HealthDataBatchAccount {
+    fn from(legacy: HealthDataBatchAccountV1) -> Self {
+        HealthDataBatchAccount {
+            owner: legacy.owner,
+            batch_id: legacy.batch_id,
+            entries: legacy
+                .entries
+                .into_iter()
+                .map(|entry| HealthRecordEntry {
+                    data_hash: legacy_hash(&entry.data_hash),
+                    pointer: entry.pointer,
+                    category: entry.category,
+                })
+                .collect(),
+            timestamp: legacy.timestamp,
+            account_version: 0,
+        }
+    }
+}
+
+/// `DisputeConfig` as written before disputes were bonded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DisputeConfigV1 {
+    pub admin: Pubkey,
+    pub arbiter: Pubkey,
+    pub resolution_secs: i64,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl From<DisputeConfigV1> for DisputeConfig {
+    fn from(legacy: DisputeConfigV1) -> Self {
+        DisputeConfig {
+            admin: legacy.admin,
+            arbiter: legacy.arbiter,
+            resolution_secs: legacy.resolution_secs,
+            updated_at: legacy.updated_at,
+            bond_lamports: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ClaimDispute` as written before disputes were bonded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimDisputeV1 {
+    pub claim: Pubkey,
+    pub patient: Pubkey,
+    pub provider: Pubkey,
+    pub arbiter: Pubkey,
+    pub reason_hash: [u8; 32],
+    pub opened_at: i64,
+    pub deadline: i64,
+    pub resolved_at: i64,
+    pub patient_prevailed: bool,
+    pub account_version: u8,
+}
+
+impl From<ClaimDisputeV1> for ClaimDispute {
+    fn from(legacy: ClaimDisputeV1) -> Self {
+        ClaimDispute {
+            claim: legacy.claim,
+            patient: legacy.patient,
+            provider: legacy.provider,
+            arbiter: legacy.arbiter,
+            reason_hash: legacy.reason_hash,
+            opened_at: legacy.opened_at,
+            deadline: legacy.deadline,
+            resolved_at: legacy.resolved_at,
+            patient_prevailed: legacy.patient_prevailed,
+            bond: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// `PlanBenefits` as written before plans had an out-of-pocket maximum.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlanBenefitsV1 {
+    pub tenant: Pubkey,
+    pub premium: u64,
+    pub research_discount_bps: u16,
+    pub research_categories: u8,
+    pub updated_at: i64,
+    pub account_version: u8,
+}
+
+impl From<PlanBenefitsV1> for PlanBenefits {
+    fn from(legacy: PlanBenefitsV1) -> Self {
+        PlanBenefits {
+            tenant: legacy.tenant,
+            premium: legacy.premium,
+            research_discount_bps: legacy.research_discount_bps,
+            research_categories: legacy.research_categories,
+            out_of_pocket_max: 0,
+            updated_at: legacy.updated_at,
+            account_version: 0,
+        }
+    }
+}
+
+/// `ProviderClaimStats` as written before fraud findings were counted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProviderClaimStatsV1 {
+    pub provider: Pubkey,
+    pub day: i64,
+    pub claims_today: u32,
+    pub total_claims: u64,
+    pub duplicate_code_claims: u64,
+    pub account_version: u8,
+}
+
+impl From<ProviderClaimStatsV1> for ProviderClaimStats {
+    fn from(legacy: ProviderClaimStatsV1) -> Self {
+        ProviderClaimStats {
+            provider: legacy.provider,
+            day: legacy.day,
+            claims_today: legacy.claims_today,
+            total_claims: legacy.total_claims,
+            duplicate_code_claims: legacy.duplicate_code_claims,
+            fraud_findings: 0,
+            account_version: 0,
+        }
+    }
+}
+
+/// Converts a hash stored as a string by an earlier layout. 64 hex digits
+/// decode to their bytes and an empty string to the zeroed hash; anything
+/// else, such as the 32-digit truncated hashes older clients submitted, is
+/// replaced by the SHA-256 of the string.
+pub fn legacy_hash(hash: &str) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    if hash.is_empty() {
+        return bytes;
+    }
+    if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return hashv(&[hash.as_bytes()]).to_bytes();
+    }
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hash[2 * i..2 * i + 2], 16).unwrap_or_default();
+    }
+    bytes
+}
+
+fn legacy_hashes(hashes: &[String]) -> Vec<[u8; 32]> {
+    hashes.iter().map(|hash| legacy_hash(hash)).collect()
+}
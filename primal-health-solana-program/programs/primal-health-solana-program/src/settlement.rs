@@ -0,0 +1,57 @@
+//! Calendar months used as settlement periods.
+//!
+//! A period is the UTC month as `YYYYMM` (`202410` for October 2024), so
+//! summaries sort and read naturally. Date arithmetic follows Howard Hinnant's
+//! `days_from_civil` / `civil_from_days` algorithms.
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Period containing `timestamp`.
+pub fn period_of(timestamp: i64) -> u32 {
+    let (year, month) = civil_from_days(timestamp.div_euclid(SECS_PER_DAY));
+    year as u32 * 100 + month
+}
+
+/// Whether `period` is a `YYYYMM` value with a valid month.
+pub fn is_valid(period: u32) -> bool {
+    (1..=12).contains(&(period % 100))
+}
+
+/// Unix time at which `period` ends, i.e. the start of the next month.
+pub fn period_end(period: u32) -> i64 {
+    let (year, month) = ((period / 100) as i64, period % 100);
+    let (year, month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    days_from_civil(year, month) * SECS_PER_DAY
+}
+
+/// Days since 1970-01-01 of the first day of `month` in `year`.
+fn days_from_civil(year: i64, month: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month as i64 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month)
+}
@@ -0,0 +1,85 @@
+//! The parts of the SPL Token interface the program needs to take claim
+//! payments in tokens, without depending on the token crates.
+//!
+//! Token and Token-2022 share the base account and mint layouts and the
+//! `TransferChecked` instruction, so either program's mints are accepted.
+//! Token-2022 extensions follow the base layout and are ignored here.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::ErrorCode;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Length of a base token account, before any extensions.
+pub const ACCOUNT_LEN: usize = 165;
+/// Length of a base mint, before any extensions.
+pub const MINT_LEN: usize = 82;
+const ACCOUNT_STATE_OFFSET: usize = 108;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const MINT_INITIALIZED_OFFSET: usize = 45;
+const TRANSFER_CHECKED: u8 = 12;
+
+/// The base fields of a token account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == TOKEN_PROGRAM_ID || *program_id == TOKEN_2022_PROGRAM_ID
+}
+
+/// Reads an initialized token account. Frozen accounts are read too; the
+/// token program rejects transfers out of them.
+pub fn unpack_account(data: &[u8]) -> Result<TokenAccount> {
+    if data.len() < ACCOUNT_LEN || data[ACCOUNT_STATE_OFFSET] == 0 {
+        msg!("Not an initialized token account");
+        return err!(ErrorCode::InvalidTokenAccount);
+    }
+    Ok(TokenAccount {
+        mint: Pubkey::new_from_array(data[0..32].try_into().unwrap()),
+        owner: Pubkey::new_from_array(data[32..64].try_into().unwrap()),
+        amount: u64::from_le_bytes(data[64..72].try_into().unwrap()),
+    })
+}
+
+/// Decimals of an initialized mint.
+pub fn unpack_mint_decimals(data: &[u8]) -> Result<u8> {
+    if data.len() < MINT_LEN || data[MINT_INITIALIZED_OFFSET] == 0 {
+        msg!("Not an initialized mint");
+        return err!(ErrorCode::InvalidTokenAccount);
+    }
+    Ok(data[MINT_DECIMALS_OFFSET])
+}
+
+/// `TransferChecked` of `amount` from `source` to `destination`, signed by
+/// `authority`.
+pub fn transfer_checked(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut data = Vec::with_capacity(10);
+    data.push(TRANSFER_CHECKED);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{
+    AccessGrant, LawfulBasis, GRANT_SCOPE_ALL, GRANT_SCOPE_READ, GRANT_SCOPE_WRITE,
+};
+
+fn grant(scope: u8, expires_at: i64) -> AccessGrant {
+    AccessGrant {
+        patient: Pubkey::new_unique(),
+        grantee: Pubkey::new_unique(),
+        scope,
+        granted_at: 1_000,
+        expires_at,
+        revoked: false,
+        lawful_basis: LawfulBasis::Consent,
+        parent: Pubkey::default(),
+        depth: 0,
+        delegations: 0,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn grants_cover_only_their_scope() {
+    let read = grant(GRANT_SCOPE_READ, 0);
+    assert!(read.allows(GRANT_SCOPE_READ, 2_000));
+    assert!(!read.allows(GRANT_SCOPE_WRITE, 2_000));
+    assert!(!read.allows(GRANT_SCOPE_ALL, 2_000));
+    assert!(grant(GRANT_SCOPE_ALL, 0).allows(GRANT_SCOPE_WRITE, 2_000));
+}
+
+#[test]
+fn grants_lapse_at_expiry_and_on_revocation() {
+    let mut write = grant(GRANT_SCOPE_WRITE, 5_000);
+    assert!(write.allows(GRANT_SCOPE_WRITE, 4_999));
+    assert!(!write.allows(GRANT_SCOPE_WRITE, 5_000));
+
+    write.expires_at = 0;
+    assert!(write.allows(GRANT_SCOPE_WRITE, i64::MAX));
+    write.revoked = true;
+    assert!(!write.is_active(2_000));
+}
+
+#[test]
+fn purchases_extend_access_until_it_lapses() {
+    let mut bought = grant(0, 0);
+    bought.grantee = Pubkey::default();
+    bought.extend_purchase(100, 2_000).unwrap();
+    assert_eq!((bought.granted_at, bought.expires_at), (2_000, 2_100));
+    assert_eq!(bought.lawful_basis, LawfulBasis::Contract);
+    assert!(bought.allows(GRANT_SCOPE_READ, 2_099));
+    assert!(!bought.allows(GRANT_SCOPE_WRITE, 2_099));
+
+    // Buying before the grant lapses adds to the remaining time
+    bought.grantee = Pubkey::new_unique();
+    bought.extend_purchase(100, 2_050).unwrap();
+    assert_eq!((bought.granted_at, bought.expires_at), (2_000, 2_200));
+
+    // Afterwards, or once revoked, a new term starts
+    bought.extend_purchase(100, 2_500).unwrap();
+    assert_eq!((bought.granted_at, bought.expires_at), (2_500, 2_600));
+    bought.revoked = true;
+    bought.extend_purchase(100, 2_550).unwrap();
+    assert_eq!((bought.granted_at, bought.expires_at), (2_550, 2_650));
+    assert!(bought.is_active(2_550));
+}
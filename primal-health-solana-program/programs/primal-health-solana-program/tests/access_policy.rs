@@ -0,0 +1,72 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{
+    AccessPolicy, DataCategory, PolicyEffect, PolicyRule, PolicySubject, CATEGORY_ALL,
+    GRANT_SCOPE_READ, GRANT_SCOPE_WRITE,
+};
+
+fn policy(rules: Vec<PolicyRule>) -> AccessPolicy {
+    AccessPolicy {
+        patient: Pubkey::new_unique(),
+        rules,
+        updated_at: 0,
+        account_version: 1,
+    }
+}
+
+fn rule(subject: PolicySubject, categories: u8, scope: u8, effect: PolicyEffect) -> PolicyRule {
+    PolicyRule {
+        subject,
+        categories,
+        scope,
+        effect,
+    }
+}
+
+#[test]
+fn first_matching_rule_decides() {
+    let imaging = DataCategory::Imaging.bit();
+    let labs = DataCategory::LabResult.bit();
+    let policy = policy(vec![
+        rule(
+            PolicySubject::Anyone,
+            imaging,
+            GRANT_SCOPE_READ,
+            PolicyEffect::RequireGrant,
+        ),
+        rule(
+            PolicySubject::RegisteredProvider,
+            CATEGORY_ALL,
+            GRANT_SCOPE_READ,
+            PolicyEffect::Allow,
+        ),
+    ]);
+
+    assert_eq!(
+        policy.decide(true, labs, GRANT_SCOPE_READ),
+        Some(PolicyEffect::Allow)
+    );
+    assert_eq!(
+        policy.decide(true, imaging, GRANT_SCOPE_READ),
+        Some(PolicyEffect::RequireGrant)
+    );
+    assert_eq!(policy.decide(false, labs, GRANT_SCOPE_READ), None);
+    assert_eq!(policy.decide(true, labs, GRANT_SCOPE_WRITE), None);
+}
+
+#[test]
+fn rules_must_cover_every_requested_category() {
+    let labs_and_vitals = DataCategory::LabResult.bit() | DataCategory::Vitals.bit();
+    let policy = policy(vec![rule(
+        PolicySubject::Anyone,
+        labs_and_vitals,
+        GRANT_SCOPE_WRITE,
+        PolicyEffect::Allow,
+    )]);
+
+    assert_eq!(
+        policy.decide(false, DataCategory::Vitals.bit(), GRANT_SCOPE_WRITE),
+        Some(PolicyEffect::Allow)
+    );
+    // Submissions ask for every category at once
+    assert_eq!(policy.decide(false, CATEGORY_ALL, GRANT_SCOPE_WRITE), None);
+}
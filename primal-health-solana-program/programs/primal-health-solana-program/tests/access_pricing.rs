@@ -0,0 +1,80 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{DataAccessOffer, ErrorCode, PriceTier, MAX_PRICE_TIERS};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn offer(price_tiers: Vec<PriceTier>) -> DataAccessOffer {
+    let mut offer = DataAccessOffer {
+        owner: Pubkey::new_unique(),
+        offer_id: "labs".to_string(),
+        resource_hash: [0xab; 32],
+        price: 1_000,
+        access_secs: DAY,
+        sales: 0,
+        created_at: NOW,
+        price_tiers: Vec::new(),
+        account_version: 3,
+    };
+    offer.set_price_tiers(price_tiers).unwrap();
+    offer
+}
+
+fn tier(max_age_days: i64, price: u64) -> PriceTier {
+    PriceTier {
+        max_age_secs: max_age_days * DAY,
+        price,
+    }
+}
+
+#[test]
+fn recent_records_cost_more() {
+    let offer = offer(vec![tier(7, 5_000), tier(30, 2_000)]);
+    assert_eq!(offer.price_at(Some(NOW - DAY), NOW).unwrap(), 5_000);
+    assert_eq!(offer.price_at(Some(NOW - 7 * DAY), NOW).unwrap(), 2_000);
+    assert_eq!(offer.price_at(Some(NOW - 29 * DAY), NOW).unwrap(), 2_000);
+    assert_eq!(offer.price_at(Some(NOW - 30 * DAY), NOW).unwrap(), 1_000);
+}
+
+#[test]
+fn records_dated_ahead_of_the_clock_take_the_youngest_tier() {
+    let offer = offer(vec![tier(30, 2_000)]);
+    assert_eq!(offer.price_at(Some(NOW + 60), NOW).unwrap(), 2_000);
+}
+
+#[test]
+fn untiered_offers_charge_the_base_price_without_the_record() {
+    let offer = offer(Vec::new());
+    assert_eq!(offer.price_at(None, NOW).unwrap(), 1_000);
+    assert_eq!(offer.price_at(Some(NOW), NOW).unwrap(), 1_000);
+
+    let tiered = self::offer(vec![tier(30, 2_000)]);
+    expect_error(tiered.price_at(None, NOW), ErrorCode::OfferRecordMissing);
+}
+
+#[test]
+fn tiers_must_be_positive_and_ordered() {
+    let mut offer = offer(Vec::new());
+    for tiers in [
+        vec![tier(30, 0)],
+        vec![tier(0, 2_000)],
+        vec![tier(30, 2_000), tier(7, 5_000)],
+        vec![tier(7, 5_000), tier(7, 4_000)],
+        (1..=MAX_PRICE_TIERS as i64 + 1)
+            .map(|days| tier(days, 1_000))
+            .collect(),
+    ] {
+        expect_error(offer.set_price_tiers(tiers), ErrorCode::InvalidPriceTiers);
+    }
+    assert!(offer.price_tiers.is_empty());
+}
+
+#[test]
+fn unupgraded_offers_cannot_be_priced() {
+    let mut offer = offer(Vec::new());
+    offer.account_version = 0;
+    expect_error(offer.price_at(None, NOW), ErrorCode::AccountNotUpgraded);
+}
@@ -0,0 +1,1614 @@
+//! Account-layout migration tests.
+//!
+//! Fixtures are encoded with vendored copies of the previous account structs,
+//! exactly as an older deployment would have written them, then upgraded with
+//! the program's migration code. The upgraded account must keep its meaning,
+//! fit the space `migrate_claim` allocates and round-trip through the current
+//! layout without being converted again. Accounts from before the version
+//! byte must come out of `upgrade` at their type's current version.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator};
+use primal_health_solana_program::clock::ClockOffset;
+use primal_health_solana_program::migration::{
+    claim_space, decode_claim, legacy_hash, upgrade, Versioned, ACCOUNT_VERSIONS, LEGACY_LINE_CODE,
+};
+use primal_health_solana_program::{
+    AccessGrant, ClaimAccount, ClaimDispute, ClaimEscrow, ClaimLineItem, ClaimStatus, Config,
+    ConsentAccount, DataAccessOffer, EncryptionKey, FeatureFlags, HealthDataAccount, LawfulBasis,
+    LineItemStatus, PlanBenefits, PriceTier, ProviderClaimStats, StorageKind, GRANT_SCOPE_READ,
+    MAX_ATTACHMENTS, MAX_HASH_LEN,
+};
+use solana_program::hash::hashv;
+
+/// Account structs as they were before the layout changes, copied verbatim.
+mod v0 {
+    use anchor_lang::prelude::*;
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub health_data_hash: String,
+        pub amount: u64,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct FeatureFlags {
+        pub admin: Pubkey,
+        pub flags: u64,
+        pub updated_at: i64,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClockOffset {
+        pub offset_secs: i64,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub enum ClaimStatus {
+        Pending,
+        Verified,
+        Paid,
+        Rejected,
+    }
+}
+
+/// `ClaimAccount` as it was before claims could be paid in an SPL token,
+/// `DataAccessOffer` before price tiers, `AccessGrant` before delegation,
+/// `ClaimEscrow` before split custody, `EncryptionKey` before keys were
+/// versioned, `Config` before claims expired, `ClaimDispute` before disputes
+/// were bonded, `PlanBenefits` before out-of-pocket maximums and
+/// `HealthDataAccount` and `ConsentAccount` while hashes were strings.
+mod v1 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus, LawfulBasis};
+
+    #[derive(AnchorSerialize)]
+    pub struct Config {
+        pub admin: Pubkey,
+        pub paused: bool,
+        pub fee_bps: u16,
+        pub updated_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimDispute {
+        pub claim: Pubkey,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub arbiter: Pubkey,
+        pub reason_hash: [u8; 32],
+        pub opened_at: i64,
+        pub deadline: i64,
+        pub resolved_at: i64,
+        pub patient_prevailed: bool,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct PlanBenefits {
+        pub tenant: Pubkey,
+        pub premium: u64,
+        pub research_discount_bps: u16,
+        pub research_categories: u8,
+        pub updated_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ProviderClaimStats {
+        pub provider: Pubkey,
+        pub day: i64,
+        pub claims_today: u32,
+        pub total_claims: u64,
+        pub duplicate_code_claims: u64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct EncryptionKey {
+        pub owner: Pubkey,
+        pub x25519_key: [u8; 32],
+        pub updated_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct HealthDataAccount {
+        pub owner: Pubkey,
+        pub data_hash: String,
+        pub encrypted_data: String,
+        pub timestamp: i64,
+        pub author: Pubkey,
+        pub derived_from: String,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimEscrow {
+        pub claim: Pubkey,
+        pub provider: Pubkey,
+        pub amount: u64,
+        pub funded_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ConsentAccount {
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub record: Pubkey,
+        pub data_hash: String,
+        pub scope: u8,
+        pub granted_at: i64,
+        pub expires_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct AccessGrant {
+        pub patient: Pubkey,
+        pub grantee: Pubkey,
+        pub scope: u8,
+        pub granted_at: i64,
+        pub expires_at: i64,
+        pub revoked: bool,
+        pub lawful_basis: LawfulBasis,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct DataAccessOffer {
+        pub owner: Pubkey,
+        pub offer_id: String,
+        pub resource_hash: String,
+        pub price: u64,
+        pub access_secs: i64,
+        pub sales: u64,
+        pub created_at: i64,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before claims carried external references,
+/// `DataAccessOffer` while its resource hash was a string and
+/// `HealthDataAccount` while payloads were stored inline.
+mod v2 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus, PriceTier};
+
+    #[derive(AnchorSerialize)]
+    pub struct HealthDataAccount {
+        pub owner: Pubkey,
+        pub data_hash: [u8; 32],
+        pub encrypted_data: String,
+        pub timestamp: i64,
+        pub author: Pubkey,
+        pub derived_from: [u8; 32],
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct DataAccessOffer {
+        pub owner: Pubkey,
+        pub offer_id: String,
+        pub resource_hash: String,
+        pub price: u64,
+        pub access_secs: i64,
+        pub sales: u64,
+        pub created_at: i64,
+        pub price_tiers: Vec<PriceTier>,
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before claims recorded their service period and
+/// `HealthDataAccount` before records noted their encryption key version.
+mod v3 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus, StorageKind};
+
+    #[derive(AnchorSerialize)]
+    pub struct HealthDataAccount {
+        pub owner: Pubkey,
+        pub data_hash: [u8; 32],
+        pub storage: StorageKind,
+        pub cid: String,
+        pub commitment: [u8; 32],
+        pub timestamp: i64,
+        pub author: Pubkey,
+        pub derived_from: [u8; 32],
+        pub account_version: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before approval policies could be attached.
+mod v4 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before the rent payer was recorded.
+mod v5 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub approval_policy: Pubkey,
+        pub required_approvals: u8,
+        pub approvals: u8,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was while attachments were strings.
+mod v6 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<String>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub approval_policy: Pubkey,
+        pub required_approvals: u8,
+        pub approvals: u8,
+        pub rent_payer: Pubkey,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before claims could name an insurer.
+mod v7 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<[u8; 32]>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub approval_policy: Pubkey,
+        pub required_approvals: u8,
+        pub approvals: u8,
+        pub rent_payer: Pubkey,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before claims expired.
+mod v8 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<[u8; 32]>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub approval_policy: Pubkey,
+        pub required_approvals: u8,
+        pub approvals: u8,
+        pub rent_payer: Pubkey,
+        pub insurer: Pubkey,
+        pub insurer_approved_at: i64,
+        pub account_version: u8,
+    }
+}
+
+/// `ClaimAccount` as it was before payers covered patient shares past the
+/// out-of-pocket maximum.
+mod v9 {
+    use anchor_lang::prelude::*;
+    use primal_health_solana_program::{ClaimLineItem, ClaimStatus};
+
+    #[derive(AnchorSerialize)]
+    pub struct ClaimAccount {
+        pub claim_id: String,
+        pub patient: Pubkey,
+        pub provider: Pubkey,
+        pub attachments: Vec<[u8; 32]>,
+        pub amount: u64,
+        pub line_items: Vec<ClaimLineItem>,
+        pub status: ClaimStatus,
+        pub timestamp: i64,
+        pub note_count: u32,
+        pub rejected_at: i64,
+        pub reopened: bool,
+        pub verified_at: i64,
+        pub principal_paid: u64,
+        pub interest_paid: u64,
+        pub tenant: Pubkey,
+        pub mint: Pubkey,
+        pub external_ref: String,
+        pub tags: Vec<String>,
+        pub service_start: i64,
+        pub service_end: i64,
+        pub approval_policy: Pubkey,
+        pub required_approvals: u8,
+        pub approvals: u8,
+        pub rent_payer: Pubkey,
+        pub insurer: Pubkey,
+        pub insurer_approved_at: i64,
+        pub expires_at: i64,
+        pub account_version: u8,
+    }
+}
+
+const CREATED_AT: i64 = 1_700_000_000;
+
+/// Bytes of a claim account written by the previous program version, including
+/// the 64 bytes of padding it allocated.
+fn v0_claim_bytes(status: v0::ClaimStatus, health_data_hash: &str) -> (Vec<u8>, Pubkey, Pubkey) {
+    let patient = Pubkey::new_unique();
+    let provider = Pubkey::new_unique();
+    let legacy = v0::ClaimAccount {
+        claim_id: "claim-0001".to_string(),
+        patient,
+        provider,
+        health_data_hash: health_data_hash.to_string(),
+        amount: 125_000,
+        status,
+        timestamp: CREATED_AT,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    data.resize(data.len() + 64, 0);
+    (data, patient, provider)
+}
+
+/// Account bytes as written before `account_version`, with `padding` zeroes.
+fn legacy_bytes(
+    discriminator: &[u8],
+    account: &impl anchor_lang::AnchorSerialize,
+    padding: usize,
+) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(account).unwrap());
+    data.resize(data.len() + padding, 0);
+    data
+}
+
+fn serialize(claim: &ClaimAccount) -> Vec<u8> {
+    let mut data = Vec::new();
+    claim.try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn v0_claims_upgrade_for_every_status() {
+    let cases = [
+        (
+            v0::ClaimStatus::Pending,
+            ClaimStatus::Pending,
+            LineItemStatus::Pending,
+        ),
+        (
+            v0::ClaimStatus::Verified,
+            ClaimStatus::Verified,
+            LineItemStatus::Approved,
+        ),
+        (
+            v0::ClaimStatus::Paid,
+            ClaimStatus::Paid,
+            LineItemStatus::Approved,
+        ),
+        (
+            v0::ClaimStatus::Rejected,
+            ClaimStatus::Rejected,
+            LineItemStatus::Pending,
+        ),
+    ];
+    for (legacy_status, status, line_status) in cases {
+        let (data, patient, provider) = v0_claim_bytes(legacy_status, &"ab".repeat(32));
+        let (claim, converted) = decode_claim(&data).unwrap();
+
+        assert!(converted);
+        assert_eq!(claim.claim_id, "claim-0001");
+        assert_eq!(claim.patient, patient);
+        assert_eq!(claim.provider, provider);
+        assert_eq!(claim.attachments, vec![[0xab; 32]]);
+        assert_eq!(claim.amount, 125_000);
+        assert_eq!(claim.status, status);
+        assert_eq!(claim.timestamp, CREATED_AT);
+        assert_eq!(claim.note_count, 0);
+        assert!(!claim.reopened);
+        let expected_rejected_at = if status == ClaimStatus::Rejected {
+            CREATED_AT
+        } else {
+            0
+        };
+        assert_eq!(claim.rejected_at, expected_rejected_at);
+
+        assert_eq!(claim.line_items.len(), 1);
+        let line = &claim.line_items[0];
+        assert_eq!(line.code, LEGACY_LINE_CODE);
+        assert_eq!(line.total().unwrap(), 125_000);
+        assert_eq!(line.status, line_status);
+    }
+}
+
+#[test]
+fn upgraded_claims_round_trip_through_the_current_layout() {
+    let (data, _, _) = v0_claim_bytes(v0::ClaimStatus::Verified, &"cd".repeat(32));
+    let (claim, _) = decode_claim(&data).unwrap();
+    let upgraded = serialize(&claim);
+
+    assert!(upgraded.len() <= claim_space(&claim));
+    let mut allocated = upgraded.clone();
+    allocated.resize(claim_space(&claim), 0);
+    let (decoded, converted) = decode_claim(&allocated).unwrap();
+    assert!(!converted, "a migrated claim must not be converted again");
+    assert_eq!(serialize(&decoded), upgraded);
+}
+
+#[test]
+fn upgraded_verified_claim_can_still_be_paid() {
+    let (data, _, _) = v0_claim_bytes(v0::ClaimStatus::Verified, "");
+    let (mut claim, _) = decode_claim(&data).unwrap();
+
+    assert!(claim.attachments.is_empty());
+    // No verification time was recorded, so no interest is owed however late
+    assert_eq!(claim.settle_payment(i64::MAX).unwrap(), 125_000);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+}
+
+#[test]
+fn unknown_layouts_are_rejected() {
+    let (mut data, _, _) = v0_claim_bytes(v0::ClaimStatus::Pending, "");
+    data[0] ^= 0xff;
+    assert!(decode_claim(&data).is_err());
+    assert!(decode_claim(&ClaimAccount::DISCRIMINATOR[..4]).is_err());
+}
+
+#[test]
+fn padded_accounts_upgrade_to_the_current_version_in_place() {
+    let admin = Pubkey::new_unique();
+    let legacy = v0::FeatureFlags {
+        admin,
+        flags: 0b101,
+        updated_at: CREATED_AT,
+    };
+    let data = legacy_bytes(FeatureFlags::DISCRIMINATOR, &legacy, 32);
+
+    let decoded = FeatureFlags::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(decoded.account_version, 0, "padding reads as version 0");
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert!(space <= data.len(), "no resize is needed");
+    let flags = FeatureFlags::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(flags.account_version, FeatureFlags::VERSION);
+    assert_eq!(flags.admin, admin);
+    assert_eq!(flags.flags, 0b101);
+    assert_eq!(flags.updated_at, CREATED_AT);
+}
+
+#[test]
+fn accounts_without_padding_grow_by_the_version_byte() {
+    let data = legacy_bytes(
+        ClockOffset::DISCRIMINATOR,
+        &v0::ClockOffset { offset_secs: -60 },
+        0,
+    );
+    assert!(ClockOffset::try_deserialize(&mut &data[..]).is_err());
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, data.len() + 1);
+    let offset = ClockOffset::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(offset.offset_secs, -60);
+    assert_eq!(offset.account_version, ClockOffset::VERSION);
+}
+
+#[test]
+fn v0_claims_upgrade_through_the_generic_path() {
+    let (data, _, _) = v0_claim_bytes(v0::ClaimStatus::Verified, "");
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+
+    let claim = ClaimAccount::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(claim.account_version, ClaimAccount::VERSION);
+    assert_eq!(claim.amount, 125_000);
+    assert_eq!(space, claim_space(&claim));
+}
+
+fn v1_claim_bytes(account_version: u8) -> Vec<u8> {
+    let legacy = v1::ClaimAccount {
+        claim_id: "claim-0002".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: vec!["ef".repeat(32)],
+        amount: 300,
+        line_items: vec![ClaimLineItem {
+            code: "99213".to_string(),
+            units: 2,
+            unit_price: 150,
+            modifier: String::new(),
+            status: LineItemStatus::Approved,
+            reason_code: 0,
+        }],
+        status: ClaimStatus::Verified,
+        timestamp: CREATED_AT,
+        note_count: 2,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: CREATED_AT + 60,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::new_unique(),
+        account_version,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    data.resize(data.len() + 64, 0);
+    data
+}
+
+#[test]
+fn v1_claims_upgrade_as_lamport_claims() {
+    for account_version in [0, 1] {
+        let data = v1_claim_bytes(account_version);
+        let (claim, _) = decode_claim(&data).unwrap();
+        assert_eq!(claim.claim_id, "claim-0002");
+        assert_eq!(claim.attachments, vec![[0xef; 32]]);
+        assert_eq!(claim.note_count, 2);
+        assert_eq!(claim.verified_at, CREATED_AT + 60);
+        assert_eq!(claim.payment_mint(), Pubkey::default());
+
+        let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+        assert_eq!(space, claim_space(&claim));
+        let mut allocated = upgraded.clone();
+        allocated.resize(space, 0);
+        let (decoded, converted) = decode_claim(&allocated).unwrap();
+        assert!(!converted);
+        assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+        assert_eq!(decoded.mint, Pubkey::default());
+        assert_eq!(decoded.tenant, claim.tenant);
+        assert_eq!(upgrade(&allocated).unwrap(), None);
+    }
+}
+
+fn v2_claim_bytes(mint: Pubkey) -> Vec<u8> {
+    let legacy = v2::ClaimAccount {
+        claim_id: "claim-0003".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: 500,
+        line_items: Vec::new(),
+        status: ClaimStatus::Verified,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: CREATED_AT + 60,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint,
+        account_version: 2,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    // With no attachments, the room reserved for them is zeroed padding too
+    data.resize(data.len() + MAX_ATTACHMENTS * (4 + MAX_HASH_LEN) + 64, 0);
+    data
+}
+
+#[test]
+fn v2_claims_upgrade_keeping_their_mint() {
+    let mint = Pubkey::new_unique();
+    let data = v2_claim_bytes(mint);
+
+    // Read in place before upgrading, the claim must still be paid in its mint
+    let unmigrated = ClaimAccount::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(unmigrated.account_version, 0);
+    assert_eq!(unmigrated.payment_mint(), mint);
+
+    let (claim, converted) = decode_claim(&data).unwrap();
+    assert!(converted);
+    assert_eq!(claim.claim_id, "claim-0003");
+    assert_eq!(claim.payment_mint(), mint);
+    assert!(claim.external_ref.is_empty());
+    assert!(claim.tags.is_empty());
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, claim_space(&claim));
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let (decoded, converted) = decode_claim(&allocated).unwrap();
+    assert!(!converted);
+    assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+    assert_eq!(decoded.mint, mint);
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+fn v3_claim_bytes(external_ref: &str) -> Vec<u8> {
+    let legacy = v3::ClaimAccount {
+        claim_id: "claim-0004".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: 700,
+        line_items: Vec::new(),
+        status: ClaimStatus::Pending,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::new_unique(),
+        external_ref: external_ref.to_string(),
+        tags: vec!["batch-7".to_string()],
+        account_version: 3,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    data.resize(data.len() + 64, 0);
+    data
+}
+
+#[test]
+fn v3_claims_upgrade_keeping_their_references() {
+    // A two-byte reference puts a 2 where a V2 claim keeps its version
+    for external_ref in ["CLM-0001", "A1"] {
+        let data = v3_claim_bytes(external_ref);
+        let (claim, converted) = decode_claim(&data).unwrap();
+        assert!(converted);
+        assert_eq!(claim.claim_id, "claim-0004");
+        assert_eq!(claim.external_ref, external_ref);
+        assert_eq!(claim.tags, vec!["batch-7".to_string()]);
+        assert_eq!((claim.service_start, claim.service_end), (0, 0));
+
+        let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+        assert_eq!(space, claim_space(&claim));
+        let mut allocated = upgraded.clone();
+        allocated.resize(space, 0);
+        let (decoded, converted) = decode_claim(&allocated).unwrap();
+        assert!(!converted);
+        assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+        assert_eq!(decoded.mint, claim.mint);
+        assert_eq!(upgrade(&allocated).unwrap(), None);
+    }
+}
+
+fn v4_claim_bytes(service_start: i64) -> Vec<u8> {
+    let legacy = v4::ClaimAccount {
+        claim_id: "claim-0005".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: 900,
+        line_items: Vec::new(),
+        status: ClaimStatus::Pending,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::default(),
+        external_ref: "CLM-0005".to_string(),
+        tags: Vec::new(),
+        service_start,
+        service_end: CREATED_AT,
+        account_version: 4,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    data.resize(data.len() + 64, 0);
+    data
+}
+
+#[test]
+fn v4_claims_upgrade_without_an_approval_policy() {
+    // 0x6553f003 puts a 3 where a V3 claim keeps its version
+    for service_start in [CREATED_AT - 86_400, 0x6553_f003] {
+        let data = v4_claim_bytes(service_start);
+        let (claim, converted) = decode_claim(&data).unwrap();
+        assert!(converted);
+        assert_eq!(claim.claim_id, "claim-0005");
+        assert_eq!(
+            (claim.service_start, claim.service_end),
+            (service_start, CREATED_AT)
+        );
+        assert_eq!(claim.approval_policy, Pubkey::default());
+        assert_eq!((claim.required_approvals, claim.approvals), (0, 0));
+
+        let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+        assert_eq!(space, claim_space(&claim));
+        let mut allocated = upgraded.clone();
+        allocated.resize(space, 0);
+        let (decoded, converted) = decode_claim(&allocated).unwrap();
+        assert!(!converted);
+        assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+        assert_eq!(decoded.external_ref, "CLM-0005");
+        assert_eq!(upgrade(&allocated).unwrap(), None);
+    }
+}
+
+fn v5_claim_bytes(approval_policy: Pubkey) -> Vec<u8> {
+    let legacy = v5::ClaimAccount {
+        claim_id: "claim-0006".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: 12_000,
+        line_items: Vec::new(),
+        status: ClaimStatus::Pending,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::default(),
+        external_ref: String::new(),
+        tags: Vec::new(),
+        service_start: CREATED_AT,
+        service_end: CREATED_AT,
+        approval_policy,
+        required_approvals: 0b11,
+        approvals: 0b01,
+        account_version: 5,
+    };
+    let mut data = ClaimAccount::DISCRIMINATOR.to_vec();
+    data.extend(anchor_lang::prelude::borsh::to_vec(&legacy).unwrap());
+    data.resize(data.len() + 64, 0);
+    data
+}
+
+#[test]
+fn v5_claims_upgrade_with_the_patient_as_rent_payer() {
+    // A policy address starting with 4 sits where a V4 claim keeps its version
+    let mut leading_four = [9; 32];
+    leading_four[0] = 4;
+    for approval_policy in [Pubkey::new_unique(), Pubkey::new_from_array(leading_four)] {
+        let data = v5_claim_bytes(approval_policy);
+        let (claim, converted) = decode_claim(&data).unwrap();
+        assert!(converted);
+        assert_eq!(claim.claim_id, "claim-0006");
+        assert_eq!(claim.approval_policy, approval_policy);
+        assert_eq!((claim.required_approvals, claim.approvals), (0b11, 0b01));
+        assert_eq!(claim.rent_payer, claim.patient);
+
+        let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+        assert_eq!(space, claim_space(&claim));
+        let mut allocated = upgraded.clone();
+        allocated.resize(space, 0);
+        let (decoded, converted) = decode_claim(&allocated).unwrap();
+        assert!(!converted);
+        assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+        assert_eq!(decoded.rent_payer, claim.patient);
+        assert_eq!(upgrade(&allocated).unwrap(), None);
+    }
+}
+
+#[test]
+fn v6_claims_upgrade_with_fixed_size_attachments() {
+    let legacy = v6::ClaimAccount {
+        claim_id: "claim-0007".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: vec!["ab".repeat(32), "record-1".to_string()],
+        amount: 400,
+        line_items: Vec::new(),
+        status: ClaimStatus::Pending,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::default(),
+        external_ref: String::new(),
+        tags: Vec::new(),
+        service_start: CREATED_AT,
+        service_end: CREATED_AT,
+        approval_policy: Pubkey::default(),
+        required_approvals: 0,
+        approvals: 0,
+        rent_payer: Pubkey::new_unique(),
+        account_version: 6,
+    };
+    let data = legacy_bytes(ClaimAccount::DISCRIMINATOR, &legacy, 64);
+    let (claim, converted) = decode_claim(&data).unwrap();
+    assert!(converted);
+    assert_eq!(claim.attachments, vec![[0xab; 32], legacy_hash("record-1")]);
+    assert_eq!(claim.rent_payer, legacy.rent_payer);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, claim_space(&claim));
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let (decoded, converted) = decode_claim(&allocated).unwrap();
+    assert!(!converted);
+    assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+#[test]
+fn v7_claims_upgrade_without_an_insurer() {
+    for attachments in [Vec::new(), vec![[0xab; 32]]] {
+        let legacy = v7::ClaimAccount {
+            claim_id: "claim-0008".to_string(),
+            patient: Pubkey::new_unique(),
+            provider: Pubkey::new_unique(),
+            attachments: attachments.clone(),
+            amount: 400,
+            line_items: Vec::new(),
+            status: ClaimStatus::Verified,
+            timestamp: CREATED_AT,
+            note_count: 0,
+            rejected_at: 0,
+            reopened: false,
+            verified_at: CREATED_AT,
+            principal_paid: 0,
+            interest_paid: 0,
+            tenant: Pubkey::default(),
+            mint: Pubkey::default(),
+            external_ref: String::new(),
+            tags: Vec::new(),
+            service_start: CREATED_AT,
+            service_end: CREATED_AT,
+            approval_policy: Pubkey::default(),
+            required_approvals: 0,
+            approvals: 0,
+            rent_payer: Pubkey::new_unique(),
+            account_version: 7,
+        };
+        let data = legacy_bytes(ClaimAccount::DISCRIMINATOR, &legacy, 64);
+        let (claim, converted) = decode_claim(&data).unwrap();
+        assert!(converted);
+        assert_eq!(claim.attachments, attachments);
+        assert_eq!(claim.rent_payer, legacy.rent_payer);
+        assert_eq!(
+            (claim.insurer, claim.insurer_approved_at),
+            (Pubkey::default(), 0)
+        );
+        // Claims without an insurer stay payable once verified
+        claim.expect_approved().unwrap();
+
+        let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+        assert_eq!(space, claim_space(&claim));
+        let mut allocated = upgraded.clone();
+        allocated.resize(space, 0);
+        let (decoded, converted) = decode_claim(&allocated).unwrap();
+        assert!(!converted);
+        assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+        assert_eq!(upgrade(&allocated).unwrap(), None);
+    }
+}
+
+#[test]
+fn v8_claims_upgrade_without_an_expiry() {
+    let legacy = v8::ClaimAccount {
+        claim_id: "claim-0009".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: vec![[0xab; 32]],
+        amount: 400,
+        line_items: Vec::new(),
+        status: ClaimStatus::Pending,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::default(),
+        external_ref: String::new(),
+        tags: Vec::new(),
+        service_start: CREATED_AT,
+        service_end: CREATED_AT,
+        approval_policy: Pubkey::default(),
+        required_approvals: 0,
+        approvals: 0,
+        rent_payer: Pubkey::new_unique(),
+        insurer: Pubkey::new_unique(),
+        insurer_approved_at: 0,
+        account_version: 8,
+    };
+    let data = legacy_bytes(ClaimAccount::DISCRIMINATOR, &legacy, 64);
+
+    // Read in place, its version byte is not taken for a deadline
+    let mut in_place = ClaimAccount::try_deserialize(&mut &data[..]).unwrap();
+    assert!(in_place.expire(i64::MAX).is_err());
+
+    let (claim, converted) = decode_claim(&data).unwrap();
+    assert!(converted);
+    assert_eq!(claim.insurer, legacy.insurer);
+    assert_eq!(claim.expires_at, 0);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, claim_space(&claim));
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let (mut decoded, converted) = decode_claim(&allocated).unwrap();
+    assert!(!converted);
+    assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+    // Upgraded claims never expire
+    assert!(decoded.expire(i64::MAX).is_err());
+    assert_eq!(decoded.status, ClaimStatus::Pending);
+}
+
+#[test]
+fn v9_claims_upgrade_with_nothing_covered_by_the_payer() {
+    let legacy = v9::ClaimAccount {
+        claim_id: "claim-0010".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: 400,
+        line_items: vec![ClaimLineItem {
+            code: "99213".to_string(),
+            units: 1,
+            unit_price: 400,
+            modifier: String::new(),
+            status: LineItemStatus::Denied,
+            reason_code: 0,
+        }],
+        status: ClaimStatus::Verified,
+        timestamp: CREATED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: CREATED_AT,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::new_unique(),
+        mint: Pubkey::default(),
+        external_ref: String::new(),
+        tags: Vec::new(),
+        service_start: CREATED_AT,
+        service_end: CREATED_AT,
+        approval_policy: Pubkey::default(),
+        required_approvals: 0,
+        approvals: 0,
+        rent_payer: Pubkey::new_unique(),
+        insurer: Pubkey::default(),
+        insurer_approved_at: 0,
+        expires_at: CREATED_AT + 2_592_000,
+        account_version: 9,
+    };
+    let data = legacy_bytes(ClaimAccount::DISCRIMINATOR, &legacy, 64);
+
+    let (claim, converted) = decode_claim(&data).unwrap();
+    assert!(converted);
+    assert_eq!(claim.expires_at, legacy.expires_at);
+    assert_eq!(claim.payer_covered, 0);
+    // The denied line stays the patient's
+    assert_eq!(claim.approved_amount().unwrap(), 0);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, claim_space(&claim));
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let (decoded, converted) = decode_claim(&allocated).unwrap();
+    assert!(!converted);
+    assert_eq!(decoded.account_version, ClaimAccount::VERSION);
+    assert_eq!(decoded.tenant, legacy.tenant);
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+#[test]
+fn v1_configs_upgrade_without_a_claim_lifetime() {
+    let legacy = v1::Config {
+        admin: Pubkey::new_unique(),
+        paused: true,
+        fee_bps: 25,
+        updated_at: CREATED_AT,
+        account_version: 1,
+    };
+    let data = legacy_bytes(Config::DISCRIMINATOR, &legacy, 64);
+
+    // Read in place, its version byte is not taken for a lifetime
+    let in_place = Config::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(in_place.claim_expiry(CREATED_AT), 0);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, data.len(), "the padding has room for the lifetime");
+    let config = Config::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(config.account_version, Config::VERSION);
+    assert_eq!(config.claim_ttl_secs, 0);
+    assert_eq!(
+        (
+            config.admin,
+            config.paused,
+            config.fee_bps,
+            config.updated_at
+        ),
+        (legacy.admin, true, 25, CREATED_AT)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn v1_disputes_upgrade_without_a_bond() {
+    let legacy = v1::ClaimDispute {
+        claim: Pubkey::new_unique(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        arbiter: Pubkey::new_unique(),
+        reason_hash: [7; 32],
+        opened_at: CREATED_AT,
+        deadline: CREATED_AT + 1_209_600,
+        resolved_at: 0,
+        patient_prevailed: false,
+        account_version: 1,
+    };
+    let data = legacy_bytes(ClaimDispute::DISCRIMINATOR, &legacy, 32);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, ClaimDispute::SPACE);
+    let dispute = ClaimDispute::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(dispute.account_version, ClaimDispute::VERSION);
+    assert_eq!(dispute.bond, 0);
+    assert_eq!(
+        (
+            dispute.claim,
+            dispute.patient,
+            dispute.provider,
+            dispute.arbiter
+        ),
+        (
+            legacy.claim,
+            legacy.patient,
+            legacy.provider,
+            legacy.arbiter
+        )
+    );
+    assert_eq!(
+        (dispute.reason_hash, dispute.deadline, dispute.resolved_at),
+        ([7; 32], legacy.deadline, 0)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn v1_plan_benefits_upgrade_without_an_out_of_pocket_maximum() {
+    let legacy = v1::PlanBenefits {
+        tenant: Pubkey::new_unique(),
+        premium: 250_000_000,
+        research_discount_bps: 1_500,
+        research_categories: 0b11,
+        updated_at: CREATED_AT,
+        account_version: 1,
+    };
+    let data = legacy_bytes(PlanBenefits::DISCRIMINATOR, &legacy, 32);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, PlanBenefits::SPACE);
+    let plan = PlanBenefits::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(plan.account_version, PlanBenefits::VERSION);
+    assert_eq!(plan.out_of_pocket_max, 0);
+    assert_eq!(
+        (
+            plan.tenant,
+            plan.premium,
+            plan.research_discount_bps,
+            plan.research_categories,
+            plan.updated_at
+        ),
+        (legacy.tenant, 250_000_000, 1_500, 0b11, CREATED_AT)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn v1_provider_stats_upgrade_without_fraud_findings() {
+    let legacy = v1::ProviderClaimStats {
+        provider: Pubkey::new_unique(),
+        day: 19_675,
+        claims_today: 3,
+        total_claims: 40,
+        duplicate_code_claims: 9,
+        account_version: 1,
+    };
+    let data = legacy_bytes(ProviderClaimStats::DISCRIMINATOR, &legacy, 32);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, ProviderClaimStats::SPACE);
+    let stats = ProviderClaimStats::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(stats.account_version, ProviderClaimStats::VERSION);
+    assert_eq!(stats.fraud_findings, 0);
+    assert_eq!(
+        (
+            stats.provider,
+            stats.day,
+            stats.claims_today,
+            stats.total_claims,
+            stats.duplicate_code_claims
+        ),
+        (legacy.provider, 19_675, 3, 40, 9)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn legacy_hashes_keep_hex_digests_and_hash_anything_else() {
+    assert_eq!(legacy_hash(""), [0; 32]);
+    assert_eq!(legacy_hash(&"0f".repeat(32)), [0x0f; 32]);
+    assert_eq!(legacy_hash(&"0F".repeat(32)), [0x0f; 32]);
+    // Truncated digests, and 64 characters that are not all hex, are hashed
+    let truncated = "ab".repeat(16);
+    assert_ne!(legacy_hash(&truncated), [0; 32]);
+    assert_ne!(legacy_hash(&truncated), legacy_hash(&"ab".repeat(32)));
+    assert_ne!(legacy_hash(&"zz".repeat(32)), [0; 32]);
+}
+
+#[test]
+fn string_hashed_records_upgrade_to_fixed_hashes() {
+    let legacy = v1::HealthDataAccount {
+        owner: Pubkey::new_unique(),
+        data_hash: "cd".repeat(32),
+        encrypted_data: "enc".to_string(),
+        timestamp: CREATED_AT,
+        author: Pubkey::default(),
+        derived_from: String::new(),
+        account_version: 1,
+    };
+    // Records were allocated with 64 bytes of padding
+    let data = legacy_bytes(HealthDataAccount::DISCRIMINATOR, &legacy, 64);
+    let record = HealthDataAccount::decode_any(&data).unwrap();
+    assert_eq!(record.data_hash, [0xcd; 32]);
+    assert_eq!(record.derived_from, [0; 32]);
+    assert_eq!(
+        (record.storage, record.cid.as_str()),
+        (StorageKind::Inline, "enc")
+    );
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, HealthDataAccount::space("enc"));
+    let decoded = HealthDataAccount::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(decoded.account_version, HealthDataAccount::VERSION);
+    assert_eq!(decoded.owner, legacy.owner);
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+
+    let linked = v1::HealthDataAccount {
+        derived_from: "ab".repeat(32),
+        ..legacy
+    };
+    let data = legacy_bytes(HealthDataAccount::DISCRIMINATOR, &linked, 64);
+    let record = HealthDataAccount::decode_any(&data).unwrap();
+    assert_eq!(record.derived_from, [0xab; 32]);
+}
+
+#[test]
+fn inline_records_upgrade_to_inline_pointers() {
+    let encrypted_data = "AQIDBAUGBwgJ".to_string();
+    let legacy = v2::HealthDataAccount {
+        owner: Pubkey::new_unique(),
+        data_hash: [0xcd; 32],
+        encrypted_data: encrypted_data.clone(),
+        timestamp: CREATED_AT,
+        author: Pubkey::new_unique(),
+        derived_from: [0xab; 32],
+        account_version: 2,
+    };
+    let data = legacy_bytes(HealthDataAccount::DISCRIMINATOR, &legacy, 64);
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, HealthDataAccount::space(&encrypted_data));
+    let record = HealthDataAccount::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(record.account_version, HealthDataAccount::VERSION);
+    assert_eq!(
+        (
+            record.owner,
+            record.data_hash,
+            record.author,
+            record.derived_from
+        ),
+        (
+            legacy.owner,
+            legacy.data_hash,
+            legacy.author,
+            legacy.derived_from
+        )
+    );
+    // The ciphertext stays readable, committed to by its hash
+    assert_eq!(record.storage, StorageKind::Inline);
+    assert_eq!(record.cid, encrypted_data);
+    record
+        .verify_commitment(&hashv(&[encrypted_data.as_bytes()]).to_bytes())
+        .unwrap();
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn v1_escrows_upgrade_as_standard_escrows() {
+    let legacy = v1::ClaimEscrow {
+        claim: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        amount: 1_500,
+        funded_at: CREATED_AT,
+        account_version: 1,
+    };
+    let data = legacy_bytes(ClaimEscrow::DISCRIMINATOR, &legacy, 32);
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, ClaimEscrow::SPACE);
+    let escrow = ClaimEscrow::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(
+        (
+            escrow.claim,
+            escrow.provider,
+            escrow.amount,
+            escrow.funded_at
+        ),
+        (legacy.claim, legacy.provider, 1_500, CREATED_AT)
+    );
+    assert_eq!(escrow.arbiter, Pubkey::default());
+    assert_eq!(escrow.account_version, ClaimEscrow::VERSION);
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn records_upgrade_without_a_key_version() {
+    let legacy = v3::HealthDataAccount {
+        owner: Pubkey::new_unique(),
+        data_hash: [0xcd; 32],
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [0xce; 32],
+        timestamp: CREATED_AT,
+        author: Pubkey::new_unique(),
+        derived_from: [0xab; 32],
+        account_version: 3,
+    };
+    let data = legacy_bytes(HealthDataAccount::DISCRIMINATOR, &legacy, 64);
+    // Read as is, the version byte lands in key_version
+    let stale = HealthDataAccount::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!((stale.key_version, stale.account_version), (3, 0));
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, HealthDataAccount::space(&legacy.cid));
+    assert!(space <= data.len(), "the key version fits in the padding");
+    let record = HealthDataAccount::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(record.account_version, HealthDataAccount::VERSION);
+    assert_eq!(record.key_version, 0);
+    assert_eq!(
+        (record.storage, record.cid.as_str(), record.commitment),
+        (StorageKind::Ipfs, "bafkrecord", legacy.commitment)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn registered_keys_upgrade_as_their_first_version() {
+    let legacy = v1::EncryptionKey {
+        owner: Pubkey::new_unique(),
+        x25519_key: [9; 32],
+        updated_at: CREATED_AT,
+        account_version: 1,
+    };
+    let data = legacy_bytes(EncryptionKey::DISCRIMINATOR, &legacy, 32);
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, EncryptionKey::SPACE);
+    let key = EncryptionKey::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(key.account_version, EncryptionKey::VERSION);
+    assert_eq!(key.key_version, 1);
+    assert_eq!(
+        (key.owner, key.x25519_key, key.updated_at),
+        (legacy.owner, legacy.x25519_key, legacy.updated_at)
+    );
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn string_hashed_consents_upgrade_to_fixed_hashes() {
+    let legacy = v1::ConsentAccount {
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        record: Pubkey::new_unique(),
+        data_hash: "ab".repeat(32),
+        scope: 1,
+        granted_at: CREATED_AT,
+        expires_at: 0,
+        account_version: 1,
+    };
+    let data = legacy_bytes(ConsentAccount::DISCRIMINATOR, &legacy, 32);
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, ConsentAccount::SPACE);
+    let consent = ConsentAccount::try_deserialize(&mut &upgraded[..]).unwrap();
+    assert_eq!(consent.data_hash, [0xab; 32]);
+    assert_eq!(consent.record, legacy.record);
+    assert_eq!(consent.account_version, ConsentAccount::VERSION);
+}
+
+#[test]
+fn v2_offers_upgrade_keeping_their_tiers() {
+    let legacy = v2::DataAccessOffer {
+        owner: Pubkey::new_unique(),
+        offer_id: "labs".to_string(),
+        resource_hash: "ab".repeat(32),
+        price: 5_000,
+        access_secs: 3_600,
+        sales: 1,
+        created_at: CREATED_AT,
+        price_tiers: vec![PriceTier {
+            max_age_secs: 86_400,
+            price: 9_000,
+        }],
+        account_version: 2,
+    };
+    let data = legacy_bytes(DataAccessOffer::DISCRIMINATOR, &legacy, 32);
+    let offer = DataAccessOffer::decode_any(&data).unwrap();
+    assert_eq!(offer.resource_hash, [0xab; 32]);
+    assert_eq!(offer.price_tiers.len(), 1);
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, DataAccessOffer::SPACE);
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let decoded = DataAccessOffer::try_deserialize(&mut &allocated[..]).unwrap();
+    assert_eq!(decoded.account_version, DataAccessOffer::VERSION);
+    assert_eq!(
+        decoded.price_at(Some(CREATED_AT), CREATED_AT).unwrap(),
+        9_000
+    );
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+#[test]
+fn v1_offers_upgrade_without_price_tiers() {
+    let legacy = v1::DataAccessOffer {
+        owner: Pubkey::new_unique(),
+        offer_id: "labs".to_string(),
+        resource_hash: "abc123".to_string(),
+        price: 5_000,
+        access_secs: 3_600,
+        sales: 3,
+        created_at: CREATED_AT,
+        account_version: 1,
+    };
+    // Offers were allocated with 32 bytes of padding
+    let data = legacy_bytes(DataAccessOffer::DISCRIMINATOR, &legacy, 32);
+    let offer = DataAccessOffer::decode_any(&data).unwrap();
+    assert_eq!((offer.price, offer.sales), (5_000, 3));
+    assert_eq!(offer.resource_hash, legacy_hash("abc123"));
+    assert!(offer.price_tiers.is_empty());
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, DataAccessOffer::SPACE);
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let decoded = DataAccessOffer::try_deserialize(&mut &allocated[..]).unwrap();
+    assert_eq!(decoded.account_version, DataAccessOffer::VERSION);
+    assert_eq!(decoded.price_at(None, CREATED_AT).unwrap(), 5_000);
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+#[test]
+fn v1_grants_upgrade_as_patient_grants() {
+    let legacy = v1::AccessGrant {
+        patient: Pubkey::new_unique(),
+        grantee: Pubkey::new_unique(),
+        scope: GRANT_SCOPE_READ,
+        granted_at: CREATED_AT,
+        expires_at: 0,
+        revoked: false,
+        lawful_basis: LawfulBasis::Consent,
+        account_version: 1,
+    };
+    // Grants were allocated with 31 bytes of padding
+    let data = legacy_bytes(AccessGrant::DISCRIMINATOR, &legacy, 31);
+    let grant = AccessGrant::decode_any(&data).unwrap();
+    assert_eq!(
+        (grant.patient, grant.grantee),
+        (legacy.patient, legacy.grantee)
+    );
+    assert_eq!(grant.parent, Pubkey::default());
+    assert_eq!((grant.depth, grant.delegations), (0, 0));
+
+    let (upgraded, space) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(space, AccessGrant::SPACE);
+    let mut allocated = upgraded.clone();
+    allocated.resize(space, 0);
+    let decoded = AccessGrant::try_deserialize(&mut &allocated[..]).unwrap();
+    assert_eq!(decoded.account_version, AccessGrant::VERSION);
+    assert!(decoded.allows(GRANT_SCOPE_READ, CREATED_AT));
+    assert_eq!(upgrade(&allocated).unwrap(), None);
+}
+
+#[test]
+fn current_accounts_are_left_alone() {
+    let data = legacy_bytes(
+        ClockOffset::DISCRIMINATOR,
+        &v0::ClockOffset { offset_secs: 5 },
+        0,
+    );
+    let (upgraded, _) = upgrade(&data).unwrap().unwrap();
+    assert_eq!(upgrade(&upgraded).unwrap(), None);
+}
+
+#[test]
+fn upgrade_rejects_foreign_accounts() {
+    assert!(upgrade(&[0xff; 64]).is_err());
+    assert!(upgrade(&[]).is_err());
+}
+
+#[test]
+fn every_account_type_is_registered_once() {
+    let mut names: Vec<_> = ACCOUNT_VERSIONS.iter().map(|(name, _)| *name).collect();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), ACCOUNT_VERSIONS.len());
+    assert!(names.contains(&"ClaimAccount"));
+    assert!(ACCOUNT_VERSIONS.iter().all(|(_, version)| *version > 0));
+}
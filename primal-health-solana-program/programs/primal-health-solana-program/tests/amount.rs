@@ -0,0 +1,66 @@
+use primal_health_solana_program::amount::{Amount, Rounding, LAMPORT_DECIMALS};
+
+/// An amount of a 6-decimal mint such as USDC.
+fn usdc(raw: u64) -> Amount {
+    Amount::new(raw, 6).unwrap()
+}
+
+#[test]
+fn amounts_in_different_decimals_do_not_mix() {
+    assert!(usdc(1).checked_add(Amount::lamports(1)).is_err());
+    assert!(usdc(1).checked_sub(Amount::lamports(0)).is_err());
+    assert_eq!(usdc(1).checked_add(usdc(2)).unwrap(), usdc(3));
+    assert!(usdc(1).checked_sub(usdc(2)).is_err());
+    assert!(usdc(u64::MAX).checked_add(usdc(1)).is_err());
+    assert!(Amount::new(1, 20).is_err());
+}
+
+#[test]
+fn rescaling_rounds_as_asked() {
+    let lamports = Amount::lamports(1_234_567_500);
+    assert_eq!(
+        lamports.rescale(6, Rounding::Down).unwrap(),
+        usdc(1_234_567)
+    );
+    assert_eq!(lamports.rescale(6, Rounding::Up).unwrap(), usdc(1_234_568));
+    // Exactly half rounds to the even neighbour
+    assert_eq!(
+        lamports.rescale(6, Rounding::HalfEven).unwrap(),
+        usdc(1_234_568)
+    );
+    assert_eq!(
+        Amount::lamports(1_234_566_500)
+            .rescale(6, Rounding::HalfEven)
+            .unwrap(),
+        usdc(1_234_566)
+    );
+
+    // Scaling up is exact, and overflows are reported rather than wrapped
+    assert_eq!(
+        usdc(1_234_567)
+            .rescale(LAMPORT_DECIMALS, Rounding::Down)
+            .unwrap(),
+        Amount::lamports(1_234_567_000)
+    );
+    assert!(usdc(u64::MAX)
+        .rescale(LAMPORT_DECIMALS, Rounding::Down)
+        .is_err());
+}
+
+#[test]
+fn basis_point_shares_round_once() {
+    // 2.5% of 0.000333 in 6 decimals is 8.325 base units
+    let premium = usdc(333);
+    assert_eq!(premium.mul_bps(250, Rounding::Down).unwrap(), usdc(8));
+    assert_eq!(premium.mul_bps(250, Rounding::Up).unwrap(), usdc(9));
+    assert_eq!(premium.mul_bps(250, Rounding::HalfEven).unwrap(), usdc(8));
+
+    // The product would overflow u64 but the result fits
+    let large = Amount::lamports(u64::MAX);
+    assert_eq!(
+        large.mul_ratio(u64::MAX, u64::MAX, Rounding::Down).unwrap(),
+        large
+    );
+    assert!(large.mul_ratio(1, 0, Rounding::Down).is_err());
+    assert!(large.mul_bps(10_001, Rounding::Down).is_err());
+}
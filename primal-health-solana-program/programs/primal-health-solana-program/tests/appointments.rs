@@ -0,0 +1,196 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    Appointment, AppointmentService, AppointmentStatus, ErrorCode, FeeSchedule, FeeScheduleEntry,
+    MAX_FEE_SCHEDULE_ENTRIES,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn entry(code: &str, unit_price: u64) -> FeeScheduleEntry {
+    FeeScheduleEntry {
+        code: code.to_string(),
+        unit_price,
+    }
+}
+
+fn service(code: &str, units: u32) -> AppointmentService {
+    AppointmentService {
+        code: code.to_string(),
+        units,
+    }
+}
+
+fn unset_schedule() -> FeeSchedule {
+    FeeSchedule {
+        provider: Pubkey::default(),
+        entries: Vec::new(),
+        updated_at: 0,
+        account_version: 0,
+    }
+}
+
+fn fee_schedule() -> FeeSchedule {
+    let mut schedule = unset_schedule();
+    schedule
+        .set(
+            Pubkey::new_unique(),
+            vec![entry("99213", 750), entry("85025", 120)],
+            NOW,
+        )
+        .unwrap();
+    schedule
+}
+
+fn scheduled() -> Appointment {
+    let mut appointment = Appointment {
+        appointment_id: String::new(),
+        patient: Pubkey::default(),
+        provider: Pubkey::default(),
+        scheduled_at: 0,
+        status: AppointmentStatus::Scheduled,
+        services: Vec::new(),
+        session_records: Vec::new(),
+        completed_at: 0,
+        claim: Pubkey::default(),
+        account_version: 0,
+    };
+    appointment
+        .schedule(
+            "appt-1".to_string(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            NOW,
+        )
+        .unwrap();
+    appointment
+}
+
+#[test]
+fn completed_services_are_priced_from_the_fee_schedule() {
+    let mut appointment = scheduled();
+    appointment
+        .complete(
+            vec![service("99213", 1), service("85025", 3)],
+            vec![[1; 32], [2; 32]],
+            NOW + 1_800,
+        )
+        .unwrap();
+    assert_eq!(appointment.status, AppointmentStatus::Completed);
+    assert_eq!(appointment.completed_at, NOW + 1_800);
+
+    let items = appointment.line_items(&fee_schedule()).unwrap();
+    let priced: Vec<_> = items
+        .iter()
+        .map(|item| (item.code.as_str(), item.units, item.unit_price))
+        .collect();
+    assert_eq!(priced, vec![("99213", 1, 750), ("85025", 3, 120)]);
+    assert!(items.iter().all(|item| item.modifier.is_empty()));
+}
+
+#[test]
+fn unpriced_codes_cannot_be_billed() {
+    let mut appointment = scheduled();
+    appointment
+        .complete(vec![service("70450", 1)], Vec::new(), NOW)
+        .unwrap();
+    expect_error(
+        appointment.line_items(&fee_schedule()),
+        ErrorCode::CodeNotInFeeSchedule,
+    );
+}
+
+#[test]
+fn appointments_are_billed_once_and_only_after_completion() {
+    let mut appointment = scheduled();
+    let claim = Pubkey::new_unique();
+    expect_error(
+        appointment.line_items(&fee_schedule()),
+        ErrorCode::AppointmentStatusMismatch,
+    );
+    expect_error(
+        appointment.bill(claim),
+        ErrorCode::AppointmentStatusMismatch,
+    );
+
+    appointment
+        .complete(vec![service("99213", 1)], Vec::new(), NOW)
+        .unwrap();
+    appointment.bill(claim).unwrap();
+    assert_eq!(
+        (appointment.status, appointment.claim),
+        (AppointmentStatus::Billed, claim)
+    );
+    expect_error(
+        appointment.bill(Pubkey::new_unique()),
+        ErrorCode::AppointmentStatusMismatch,
+    );
+    expect_error(
+        appointment.complete(vec![service("99213", 1)], Vec::new(), NOW),
+        ErrorCode::AppointmentStatusMismatch,
+    );
+}
+
+#[test]
+fn appointments_complete_with_services_once_started() {
+    let mut appointment = scheduled();
+    expect_error(
+        appointment.complete(vec![service("99213", 1)], Vec::new(), NOW - 1),
+        ErrorCode::InvalidAppointment,
+    );
+    expect_error(
+        appointment.complete(Vec::new(), Vec::new(), NOW),
+        ErrorCode::InvalidAppointment,
+    );
+    expect_error(
+        appointment.complete(vec![service("99213", 0)], Vec::new(), NOW),
+        ErrorCode::ZeroUnits,
+    );
+    expect_error(
+        appointment.complete(vec![service("99213", 1)], vec![[0; 32]], NOW),
+        ErrorCode::HashEmpty,
+    );
+
+    let patient = appointment.patient;
+    expect_error(
+        appointment.schedule("appt-2".to_string(), patient, patient, NOW),
+        ErrorCode::InvalidAppointment,
+    );
+    expect_error(
+        appointment.schedule(String::new(), patient, Pubkey::new_unique(), NOW),
+        ErrorCode::InvalidAppointment,
+    );
+}
+
+#[test]
+fn fee_schedules_price_distinct_codes() {
+    let provider = Pubkey::new_unique();
+    let mut schedule = unset_schedule();
+    for entries in [
+        vec![entry("99213", 0)],
+        vec![entry("99213", 750), entry("99213", 800)],
+        (0..=MAX_FEE_SCHEDULE_ENTRIES)
+            .map(|i| entry(&format!("{:05}", i), 100))
+            .collect(),
+    ] {
+        expect_error(
+            schedule.set(provider, entries, NOW),
+            ErrorCode::InvalidFeeSchedule,
+        );
+    }
+
+    schedule
+        .set(provider, vec![entry("99213", 750)], NOW)
+        .unwrap();
+    schedule
+        .set(provider, vec![entry("85025", 120)], NOW + 60)
+        .unwrap();
+    assert_eq!(schedule.entries, vec![entry("85025", 120)]);
+    assert_eq!(
+        (schedule.updated_at, schedule.account_version),
+        (NOW + 60, 1)
+    );
+    expect_error(schedule.price_of("99213"), ErrorCode::CodeNotInFeeSchedule);
+}
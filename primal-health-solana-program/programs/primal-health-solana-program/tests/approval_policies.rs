@@ -0,0 +1,177 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ApprovalPolicy, ClaimAccount, ClaimEscrow, ClaimStatus, ErrorCode, LineItemStatus,
+    MAX_APPROVERS,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn claim(amount: u64) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "approvals".to_string(),
+        status: ClaimStatus::Verified,
+        verified_at: NOW,
+        service_start: NOW,
+        service_end: NOW,
+        ..common::claim(vec![common::line(amount, LineItemStatus::Approved)])
+    }
+}
+
+/// Provider, insurer and medical director must approve claims over 10_000.
+fn policy(approvers: &[Pubkey]) -> ApprovalPolicy {
+    let mut policy = ApprovalPolicy {
+        authority: approvers[0],
+        policy_id: "over-10k".to_string(),
+        threshold: 0,
+        approvers: Vec::new(),
+        updated_at: 0,
+        account_version: 1,
+    };
+    policy.set(10_000, approvers.to_vec(), NOW).unwrap();
+    policy
+}
+
+#[test]
+fn claims_over_the_threshold_need_every_approver() {
+    let approvers = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let policy = policy(&approvers);
+    let mut claim = claim(12_000);
+    claim
+        .attach_approval_policy(Pubkey::new_unique(), &policy)
+        .unwrap();
+    assert_eq!(claim.required_approvals, 0b111);
+
+    for approver in &approvers[..2] {
+        claim
+            .approve(policy.approver_bit(approver).unwrap())
+            .unwrap();
+        expect_error(claim.settle_payment(NOW), ErrorCode::ApprovalsPending);
+    }
+    assert_eq!(claim.principal_paid, 0);
+
+    claim
+        .approve(policy.approver_bit(&approvers[2]).unwrap())
+        .unwrap();
+    assert_eq!(claim.settle_payment(NOW).unwrap(), 12_000);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+}
+
+#[test]
+fn claims_at_or_under_the_threshold_need_no_approvals() {
+    let policy = policy(&[Pubkey::new_unique(), Pubkey::new_unique()]);
+    let mut claim = claim(10_000);
+    claim
+        .attach_approval_policy(Pubkey::new_unique(), &policy)
+        .unwrap();
+    assert_eq!(claim.required_approvals, 0);
+    assert_eq!(claim.settle_payment(NOW).unwrap(), 10_000);
+}
+
+#[test]
+fn approvals_gate_partial_payments_and_escrow() {
+    let policy = policy(&[Pubkey::new_unique()]);
+    let mut claim = claim(20_000);
+    claim
+        .attach_approval_policy(Pubkey::new_unique(), &policy)
+        .unwrap();
+    expect_error(
+        claim.settle_partial_payment(5_000, NOW),
+        ErrorCode::ApprovalsPending,
+    );
+
+    let escrow = ClaimEscrow {
+        claim: Pubkey::new_unique(),
+        provider: claim.provider,
+        amount: 20_000,
+        funded_at: NOW,
+        arbiter: Pubkey::default(),
+        account_version: 2,
+    };
+    expect_error(escrow.settle(&mut claim), ErrorCode::ApprovalsPending);
+}
+
+#[test]
+fn each_approver_approves_once() {
+    let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let policy = policy(&approvers);
+    let mut claim = claim(12_000);
+    claim
+        .attach_approval_policy(Pubkey::new_unique(), &policy)
+        .unwrap();
+
+    let bit = policy.approver_bit(&approvers[1]).unwrap();
+    assert_eq!(bit, 0b10);
+    claim.approve(bit).unwrap();
+    expect_error(claim.approve(bit), ErrorCode::AlreadyApproved);
+    expect_error(
+        policy.approver_bit(&Pubkey::new_unique()),
+        ErrorCode::NotAnApprover,
+    );
+}
+
+#[test]
+fn reattaching_clears_earlier_approvals() {
+    let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let policy = policy(&approvers);
+    let mut claim = claim(12_000);
+    let address = Pubkey::new_unique();
+    claim.attach_approval_policy(address, &policy).unwrap();
+    claim
+        .approve(policy.approver_bit(&approvers[0]).unwrap())
+        .unwrap();
+
+    let stricter = self::policy(&[approvers[1]]);
+    let stricter_address = Pubkey::new_unique();
+    claim
+        .attach_approval_policy(stricter_address, &stricter)
+        .unwrap();
+    assert_eq!(claim.approval_policy, stricter_address);
+    assert_eq!((claim.required_approvals, claim.approvals), (0b1, 0));
+}
+
+#[test]
+fn paid_claims_cannot_change_policy_or_approvals() {
+    let policy = policy(&[Pubkey::new_unique()]);
+    let mut claim = claim(12_000);
+    claim.settle_partial_payment(2_000, NOW).unwrap();
+    expect_error(
+        claim.attach_approval_policy(Pubkey::new_unique(), &policy),
+        ErrorCode::PaymentStarted,
+    );
+    expect_error(claim.approve(0b1), ErrorCode::PaymentStarted);
+}
+
+#[test]
+fn unupgraded_claims_cannot_take_a_policy() {
+    let policy = policy(&[Pubkey::new_unique()]);
+    let mut claim = claim(12_000);
+    claim.account_version = 4;
+    expect_error(
+        claim.attach_approval_policy(Pubkey::new_unique(), &policy),
+        ErrorCode::AccountNotUpgraded,
+    );
+}
+
+#[test]
+fn policies_need_distinct_approvers_within_the_limit() {
+    let mut policy = policy(&[Pubkey::new_unique()]);
+    let key = Pubkey::new_unique();
+    expect_error(policy.set(1, Vec::new(), NOW), ErrorCode::InvalidApprovers);
+    expect_error(
+        policy.set(1, vec![key, Pubkey::new_unique(), key], NOW),
+        ErrorCode::InvalidApprovers,
+    );
+    let too_many: Vec<Pubkey> = (0..=MAX_APPROVERS).map(|_| Pubkey::new_unique()).collect();
+    expect_error(policy.set(1, too_many, NOW), ErrorCode::InvalidApprovers);
+
+    let full: Vec<Pubkey> = (0..MAX_APPROVERS).map(|_| Pubkey::new_unique()).collect();
+    policy.set(1, full, NOW).unwrap();
+    assert_eq!(policy.required_for(2), u8::MAX);
+}
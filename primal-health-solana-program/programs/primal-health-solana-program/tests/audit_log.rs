@@ -0,0 +1,98 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{AuditAction, AuditEntry, AuditTrail, ErrorCode};
+
+const NOW: i64 = 1_700_000_000;
+
+fn empty_trail() -> AuditTrail {
+    AuditTrail {
+        subject: Pubkey::default(),
+        entry_count: 0,
+        account_version: 0,
+    }
+}
+
+fn empty_entry() -> AuditEntry {
+    AuditEntry {
+        subject: Pubkey::default(),
+        sequence: 0,
+        actor: Pubkey::default(),
+        action: AuditAction::RecordAccessed,
+        target: Pubkey::default(),
+        timestamp: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn entries_record_who_did_what_to_which_account() {
+    let subject = Pubkey::new_unique();
+    let actor = Pubkey::new_unique();
+    let claim = Pubkey::new_unique();
+    let mut trail = empty_trail();
+    let mut entry = empty_entry();
+    trail
+        .append(
+            subject,
+            &mut entry,
+            actor,
+            AuditAction::ClaimPaid,
+            claim,
+            NOW,
+        )
+        .unwrap();
+    assert_eq!(
+        (entry.subject, entry.sequence, entry.actor, entry.target),
+        (subject, 0, actor, claim)
+    );
+    assert_eq!(entry.action, AuditAction::ClaimPaid);
+    assert_eq!((entry.timestamp, entry.account_version), (NOW, 1));
+    assert_eq!(
+        (trail.subject, trail.entry_count, trail.account_version),
+        (subject, 1, 1)
+    );
+}
+
+#[test]
+fn entries_are_numbered_in_order() {
+    let subject = Pubkey::new_unique();
+    let mut trail = empty_trail();
+    for (sequence, action) in [
+        AuditAction::ConsentGranted,
+        AuditAction::RecordAccessed,
+        AuditAction::ConsentRevoked,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut entry = empty_entry();
+        trail
+            .append(
+                subject,
+                &mut entry,
+                Pubkey::new_unique(),
+                action,
+                Pubkey::new_unique(),
+                NOW + sequence as i64,
+            )
+            .unwrap();
+        assert_eq!((entry.sequence, entry.action), (sequence as u64, action));
+    }
+    assert_eq!(trail.entry_count, 3);
+}
+
+#[test]
+fn a_full_trail_takes_no_more_entries() {
+    let mut trail = AuditTrail {
+        entry_count: u64::MAX,
+        ..empty_trail()
+    };
+    let result = trail.append(
+        Pubkey::new_unique(),
+        &mut empty_entry(),
+        Pubkey::new_unique(),
+        AuditAction::RecordAccessed,
+        Pubkey::new_unique(),
+        NOW,
+    );
+    assert_eq!(result.unwrap_err(), ErrorCode::AmountOverflow.into());
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    CareTeam, ClaimAccount, ErrorCode, HealthDataAccount, LineItemStatus, StorageKind,
+    MAX_CARE_TEAM_MEMBERS, MAX_CARE_TEAM_RECORDS,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn unset() -> CareTeam {
+    CareTeam {
+        patient: Pubkey::default(),
+        team_id: 0,
+        members: Vec::new(),
+        records: Vec::new(),
+        updated_at: 0,
+        account_version: 0,
+    }
+}
+
+fn team(patient: Pubkey, members: Vec<Pubkey>) -> CareTeam {
+    let mut team = unset();
+    team.set(patient, 1, members, vec![[1; 32]], NOW).unwrap();
+    team
+}
+
+fn claim(patient: Pubkey) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "care".to_string(),
+        patient,
+        attachments: vec![[2; 32]],
+        ..common::claim(vec![common::line(100, LineItemStatus::Pending)])
+    }
+}
+
+fn record(owner: Pubkey, data_hash: [u8; 32]) -> HealthDataAccount {
+    HealthDataAccount {
+        owner,
+        data_hash,
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [0xcc; 32],
+        timestamp: NOW,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn every_member_reads_the_designated_records() {
+    let patient = Pubkey::new_unique();
+    let members = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let team = team(patient, members.clone());
+    let designated = record(patient, [1; 32]);
+    for member in members {
+        team.authorize(member, &designated, None).unwrap();
+    }
+    expect_error(
+        team.authorize(Pubkey::new_unique(), &designated, None),
+        ErrorCode::Unauthorized,
+    );
+    expect_error(
+        team.authorize(
+            team.members[0],
+            &record(Pubkey::new_unique(), [1; 32]),
+            None,
+        ),
+        ErrorCode::InvalidPatient,
+    );
+}
+
+#[test]
+fn members_read_what_is_attached_to_the_patients_claims() {
+    let patient = Pubkey::new_unique();
+    let team = team(patient, vec![Pubkey::new_unique()]);
+    let member = team.members[0];
+    let attached = record(patient, [2; 32]);
+    expect_error(
+        team.authorize(member, &attached, None),
+        ErrorCode::CareTeamRecordNotShared,
+    );
+    team.authorize(member, &attached, Some(&claim(patient)))
+        .unwrap();
+
+    // Another patient's claim attaching the same hash does not count
+    expect_error(
+        team.authorize(member, &attached, Some(&claim(Pubkey::new_unique()))),
+        ErrorCode::CareTeamRecordNotShared,
+    );
+    expect_error(
+        team.authorize(member, &record(patient, [3; 32]), Some(&claim(patient))),
+        ErrorCode::CareTeamRecordNotShared,
+    );
+}
+
+#[test]
+fn setting_again_replaces_the_team() {
+    let patient = Pubkey::new_unique();
+    let departed = Pubkey::new_unique();
+    let mut team = team(patient, vec![departed]);
+    let joined = Pubkey::new_unique();
+    team.set(patient, 1, vec![joined], vec![[4; 32]], NOW + 60)
+        .unwrap();
+    assert_eq!(team.members, vec![joined]);
+    assert_eq!((team.updated_at, team.account_version), (NOW + 60, 1));
+    expect_error(
+        team.authorize(departed, &record(patient, [4; 32]), None),
+        ErrorCode::Unauthorized,
+    );
+    expect_error(
+        team.authorize(joined, &record(patient, [1; 32]), None),
+        ErrorCode::CareTeamRecordNotShared,
+    );
+}
+
+#[test]
+fn teams_need_distinct_members_other_than_the_patient() {
+    let patient = Pubkey::new_unique();
+    let member = Pubkey::new_unique();
+    let mut team = unset();
+    for (members, records) in [
+        (Vec::new(), Vec::new()),
+        (vec![patient], Vec::new()),
+        (vec![member, member], Vec::new()),
+        (
+            (0..=MAX_CARE_TEAM_MEMBERS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+            Vec::new(),
+        ),
+        (vec![member], vec![[1; 32]; MAX_CARE_TEAM_RECORDS + 1]),
+    ] {
+        expect_error(
+            team.set(patient, 1, members, records, NOW),
+            ErrorCode::InvalidCareTeam,
+        );
+    }
+    expect_error(
+        team.set(patient, 1, vec![member], vec![[0; 32]], NOW),
+        ErrorCode::HashEmpty,
+    );
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    DataCategory, ErrorCode, HealthDataAccountV2, MAX_CHUNKED_RECORD_LEN, MAX_DATA_CHUNK_LEN,
+};
+use solana_program::hash::hashv;
+
+const NOW: i64 = 1_700_000_000;
+
+fn sample_payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn record(payload: &[u8]) -> Box<HealthDataAccountV2> {
+    let mut record: Box<HealthDataAccountV2> = Box::new(bytemuck::Zeroable::zeroed());
+    record
+        .begin(
+            Pubkey::new_unique(),
+            [0xd1; 32],
+            hashv(&[payload]).to_bytes(),
+            payload.len() as u32,
+            DataCategory::Imaging,
+            NOW,
+        )
+        .unwrap();
+    record
+}
+
+// The space fits what one instruction can allocate
+const _: () = assert!(HealthDataAccountV2::SPACE > MAX_CHUNKED_RECORD_LEN);
+const _: () = assert!(HealthDataAccountV2::SPACE <= 10 * 1024);
+
+#[test]
+fn records_must_hold_something_that_fits() {
+    let mut record: Box<HealthDataAccountV2> = Box::new(bytemuck::Zeroable::zeroed());
+    let owner = Pubkey::new_unique();
+    for len in [0, MAX_CHUNKED_RECORD_LEN as u32 + 1] {
+        expect_error(
+            record.begin(owner, [1; 32], [2; 32], len, DataCategory::General, NOW),
+            ErrorCode::InvalidRecordLength,
+        );
+    }
+}
+
+#[test]
+fn chunks_in_order_finalize_the_record() {
+    let payload = sample_payload(MAX_CHUNKED_RECORD_LEN);
+    let mut record = record(&payload);
+    let chunks: Vec<_> = payload.chunks(MAX_DATA_CHUNK_LEN).collect();
+    let (last, rest) = chunks.split_last().unwrap();
+    let mut offset = 0;
+    for chunk in rest {
+        assert!(!record.append_chunk(offset, chunk, NOW).unwrap());
+        offset += chunk.len() as u32;
+    }
+    assert!(!record.is_finalized());
+
+    assert!(record.append_chunk(offset, last, NOW + 60).unwrap());
+    assert_eq!(record.finalized_at, NOW + 60);
+    assert_eq!(record.payload(), payload.as_slice());
+    assert_eq!(record.category().unwrap(), DataCategory::Imaging);
+    let end = record.written_len;
+    expect_error(
+        record.append_chunk(end, &[0], NOW),
+        ErrorCode::RecordFinalized,
+    );
+}
+
+#[test]
+fn retried_and_skipped_chunks_are_rejected() {
+    let payload = sample_payload(2_000);
+    let mut record = record(&payload);
+    record.append_chunk(0, &payload[..800], NOW).unwrap();
+    expect_error(
+        record.append_chunk(0, &payload[..800], NOW),
+        ErrorCode::ChunkOutOfOrder,
+    );
+    expect_error(
+        record.append_chunk(1_600, &payload[1_600..], NOW),
+        ErrorCode::ChunkOutOfOrder,
+    );
+    assert_eq!(record.written_len, 800);
+}
+
+#[test]
+fn chunks_must_be_sized_to_fit() {
+    let payload = sample_payload(1_000);
+    let mut record = record(&payload);
+    expect_error(
+        record.append_chunk(0, &[], NOW),
+        ErrorCode::InvalidChunkLength,
+    );
+    expect_error(
+        record.append_chunk(0, &sample_payload(MAX_DATA_CHUNK_LEN + 1), NOW),
+        ErrorCode::InvalidChunkLength,
+    );
+    record.append_chunk(0, &payload[..900], NOW).unwrap();
+    expect_error(
+        record.append_chunk(900, &sample_payload(101), NOW),
+        ErrorCode::InvalidChunkLength,
+    );
+}
+
+#[test]
+fn a_payload_that_misses_its_commitment_is_not_finalized() {
+    let payload = sample_payload(100);
+    let mut record = record(&payload);
+    let mut tampered = payload.clone();
+    tampered[42] ^= 1;
+    expect_error(
+        record.append_chunk(0, &tampered, NOW),
+        ErrorCode::CommitmentMismatch,
+    );
+    assert!(!record.is_finalized());
+}
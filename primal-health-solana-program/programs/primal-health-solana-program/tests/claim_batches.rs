@@ -0,0 +1,65 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use primal_health_solana_program::migration::{claim_space, new_claim_space};
+use primal_health_solana_program::{
+    ClaimAccount, ClaimLineItem, ClaimStatus, LineItemStatus, MAX_ATTACHMENTS, MAX_CLAIM_TAGS,
+    MAX_CODE_LEN, MAX_EXTERNAL_REF_LEN, MAX_LINE_ITEMS, MAX_MODIFIER_LEN, MAX_SEED_ID_LEN,
+    MAX_TAG_LEN,
+};
+
+fn full_claim(claim_id: &str, lines: usize) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: claim_id.to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: vec![[0xab; 32]; MAX_ATTACHMENTS],
+        amount: u64::MAX,
+        line_items: (0..lines)
+            .map(|_| ClaimLineItem {
+                code: "9".repeat(MAX_CODE_LEN),
+                units: u32::MAX,
+                unit_price: u64::MAX,
+                modifier: "M".repeat(MAX_MODIFIER_LEN),
+                status: LineItemStatus::DownCoded,
+                reason_code: u16::MAX,
+            })
+            .collect(),
+        status: ClaimStatus::Paid,
+        timestamp: 1_700_000_000,
+        note_count: u32::MAX,
+        rejected_at: 0,
+        reopened: true,
+        verified_at: 1_700_000_000,
+        principal_paid: u64::MAX,
+        interest_paid: u64::MAX,
+        tenant: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        external_ref: "R".repeat(MAX_EXTERNAL_REF_LEN),
+        tags: vec!["T".repeat(MAX_TAG_LEN); MAX_CLAIM_TAGS],
+        service_start: 1_699_000_000,
+        service_end: 1_699_900_000,
+        approval_policy: Pubkey::new_unique(),
+        required_approvals: u8::MAX,
+        approvals: u8::MAX,
+        rent_payer: Pubkey::new_unique(),
+        insurer: Pubkey::new_unique(),
+        insurer_approved_at: 1_700_000_000,
+        expires_at: 0,
+        payer_covered: 0,
+        account_version: 9,
+    }
+}
+
+#[test]
+fn batch_claims_get_the_space_create_claim_allocates() {
+    let claim_id = "C".repeat(MAX_SEED_ID_LEN);
+    for lines in [1, MAX_LINE_ITEMS] {
+        let claim = full_claim(&claim_id, lines);
+        let space = new_claim_space(&claim_id, lines);
+        assert_eq!(space, claim_space(&claim));
+
+        let mut data = Vec::new();
+        claim.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= space);
+    }
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{ClaimDedup, LineItemInput};
+
+const SERVICE_DATE: i64 = 1_700_000_000;
+
+fn line(code: &str, units: u32) -> LineItemInput {
+    LineItemInput {
+        code: code.into(),
+        units,
+        unit_price: 1_000,
+        modifier: String::new(),
+    }
+}
+
+#[test]
+fn reordered_lines_on_the_same_day_share_a_fingerprint() {
+    let (patient, provider) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let first = ClaimDedup::fingerprint(
+        &patient,
+        &provider,
+        &[line("99213", 1), line("80053", 1)],
+        SERVICE_DATE,
+    );
+    // Units, prices and the time of day do not make it a different service
+    let rebilled = ClaimDedup::fingerprint(
+        &patient,
+        &provider,
+        &[line("80053", 3), line("99213", 2)],
+        SERVICE_DATE + 60,
+    );
+    assert_eq!(first, rebilled);
+}
+
+#[test]
+fn parties_codes_and_service_day_change_the_fingerprint() {
+    let (patient, provider) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let lines = [line("99213", 1)];
+    let base = ClaimDedup::fingerprint(&patient, &provider, &lines, SERVICE_DATE);
+
+    let others = [
+        ClaimDedup::fingerprint(&Pubkey::new_unique(), &provider, &lines, SERVICE_DATE),
+        ClaimDedup::fingerprint(&patient, &Pubkey::new_unique(), &lines, SERVICE_DATE),
+        ClaimDedup::fingerprint(&patient, &provider, &[line("99214", 1)], SERVICE_DATE),
+        ClaimDedup::fingerprint(
+            &patient,
+            &provider,
+            &[line("99213", 1), line("99213", 1)],
+            SERVICE_DATE,
+        ),
+        ClaimDedup::fingerprint(&patient, &provider, &lines, SERVICE_DATE + 24 * 60 * 60),
+    ];
+    for other in others {
+        assert_ne!(base, other);
+    }
+}
+
+#[test]
+fn code_boundaries_are_part_of_the_fingerprint() {
+    let (patient, provider) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert_ne!(
+        ClaimDedup::fingerprint(
+            &patient,
+            &provider,
+            &[line("9921", 1), line("3", 1)],
+            SERVICE_DATE
+        ),
+        ClaimDedup::fingerprint(&patient, &provider, &[line("99213", 1)], SERVICE_DATE),
+    );
+}
+
+#[test]
+fn a_reassigned_claim_is_fingerprinted_from_its_codes() {
+    let (patient, provider) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let lines = [line("99213", 1), line("80053", 2)];
+    assert_eq!(
+        ClaimDedup::fingerprint(&patient, &provider, &lines, SERVICE_DATE),
+        ClaimDedup::fingerprint_codes(
+            &patient,
+            &provider,
+            ["80053", "99213"].into_iter(),
+            SERVICE_DATE
+        ),
+    );
+}
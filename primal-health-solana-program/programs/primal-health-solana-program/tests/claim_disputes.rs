@@ -0,0 +1,257 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimDispute, ClaimLineItem, ClaimStatus, DisputeConfig, ErrorCode,
+    LineItemStatus, REOPEN_WINDOW_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const REJECTED: i64 = NOW - 86_400;
+const RESOLUTION_SECS: i64 = 14 * 86_400;
+const DEADLINE: i64 = NOW + RESOLUTION_SECS;
+const BOND: u64 = 50_000_000;
+
+fn line(status: LineItemStatus) -> ClaimLineItem {
+    common::line(100, status)
+}
+
+fn rejected_claim() -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "dispute".to_string(),
+        attachments: vec![[1; 32]],
+        status: ClaimStatus::Rejected,
+        timestamp: REJECTED - 86_400,
+        rejected_at: REJECTED,
+        ..common::claim(vec![
+            line(LineItemStatus::Denied),
+            line(LineItemStatus::DownCoded),
+        ])
+    }
+}
+
+fn config() -> DisputeConfig {
+    let mut config = DisputeConfig {
+        admin: Pubkey::new_unique(),
+        arbiter: Pubkey::default(),
+        resolution_secs: 0,
+        updated_at: 0,
+        bond_lamports: 0,
+        account_version: 0,
+    };
+    config
+        .set(Pubkey::new_unique(), RESOLUTION_SECS, BOND, NOW - 60)
+        .unwrap();
+    config
+}
+
+fn unopened() -> ClaimDispute {
+    ClaimDispute {
+        claim: Pubkey::default(),
+        patient: Pubkey::default(),
+        provider: Pubkey::default(),
+        arbiter: Pubkey::default(),
+        reason_hash: [0; 32],
+        opened_at: 0,
+        deadline: 0,
+        resolved_at: 0,
+        patient_prevailed: false,
+        bond: 0,
+        account_version: 0,
+    }
+}
+
+fn disputed(claim: &mut ClaimAccount) -> ClaimDispute {
+    let mut dispute = unopened();
+    dispute
+        .open(Pubkey::new_unique(), claim, &config(), [2; 32], NOW)
+        .unwrap();
+    dispute
+}
+
+#[test]
+fn disputing_holds_the_claim_for_the_arbiter() {
+    let config = config();
+    let mut claim = rejected_claim();
+    let mut dispute = unopened();
+    let claim_key = Pubkey::new_unique();
+    dispute
+        .open(claim_key, &mut claim, &config, [2; 32], NOW)
+        .unwrap();
+    assert_eq!(claim.status, ClaimStatus::Disputed);
+    assert_eq!(
+        (dispute.claim, dispute.patient, dispute.provider),
+        (claim_key, claim.patient, claim.provider)
+    );
+    assert_eq!(
+        (dispute.arbiter, dispute.reason_hash),
+        (config.arbiter, [2; 32])
+    );
+    assert_eq!((dispute.opened_at, dispute.deadline), (NOW, DEADLINE));
+    assert_eq!(dispute.bond, BOND);
+    assert_eq!((dispute.resolved_at, dispute.account_version), (0, 2));
+    // The bond stays locked while the dispute is open
+    expect_error(dispute.release_bond(), ErrorCode::ClaimNotDisputed);
+    assert_eq!(dispute.bond, BOND);
+
+    // A disputed claim is neither closable nor reopenable
+    expect_error(claim.expect_closable(), ErrorCode::ClaimNotClosable);
+    expect_error(
+        claim.reopen(vec![[3; 32]], NOW),
+        ErrorCode::ClaimNotRejected,
+    );
+}
+
+#[test]
+fn only_rejected_claims_are_disputed_and_only_in_time() {
+    let config = config();
+    let window_end = REJECTED + REOPEN_WINDOW_SECS;
+    let mut claim = rejected_claim();
+    expect_error(
+        unopened().open(
+            Pubkey::new_unique(),
+            &mut claim,
+            &config,
+            [2; 32],
+            window_end + 1,
+        ),
+        ErrorCode::DisputeWindowElapsed,
+    );
+    expect_error(
+        unopened().open(Pubkey::new_unique(), &mut claim, &config, [0; 32], NOW),
+        ErrorCode::HashEmpty,
+    );
+    assert_eq!(claim.status, ClaimStatus::Rejected);
+    unopened()
+        .open(
+            Pubkey::new_unique(),
+            &mut claim,
+            &config,
+            [2; 32],
+            window_end,
+        )
+        .unwrap();
+
+    let mut claim = ClaimAccount {
+        status: ClaimStatus::Verified,
+        ..rejected_claim()
+    };
+    expect_error(
+        unopened().open(Pubkey::new_unique(), &mut claim, &config, [2; 32], NOW),
+        ErrorCode::ClaimNotRejected,
+    );
+}
+
+#[test]
+fn the_arbiter_can_uphold_the_rejection_before_the_deadline() {
+    let mut claim = rejected_claim();
+    let mut dispute = disputed(&mut claim);
+    expect_error(
+        dispute.resolve(&mut claim, Pubkey::new_unique(), false, DEADLINE - 1),
+        ErrorCode::Unauthorized,
+    );
+    let arbiter = dispute.arbiter;
+    dispute
+        .resolve(&mut claim, arbiter, false, DEADLINE - 1)
+        .unwrap();
+    assert_eq!(claim.status, ClaimStatus::Rejected);
+    assert_eq!(claim.rejected_at, REJECTED);
+    assert_eq!(
+        (dispute.resolved_at, dispute.patient_prevailed),
+        (DEADLINE - 1, false)
+    );
+    expect_error(
+        dispute.resolve(&mut claim, arbiter, true, DEADLINE),
+        ErrorCode::ClaimNotDisputed,
+    );
+    claim.expect_closable().unwrap();
+
+    // The bond is forfeited to the provider, and only paid out once
+    assert_eq!(dispute.release_bond().unwrap(), BOND);
+    assert_eq!(dispute.release_bond().unwrap(), 0);
+}
+
+#[test]
+fn a_patient_win_approves_the_unpaid_lines() {
+    let mut claim = rejected_claim();
+    let mut dispute = disputed(&mut claim);
+    let arbiter = dispute.arbiter;
+    dispute
+        .resolve(&mut claim, arbiter, true, NOW + 60)
+        .unwrap();
+    assert_eq!(claim.status, ClaimStatus::Verified);
+    assert_eq!(claim.verified_at, NOW + 60);
+    assert_eq!(
+        claim
+            .line_items
+            .iter()
+            .map(|item| item.status)
+            .collect::<Vec<_>>(),
+        vec![LineItemStatus::Approved, LineItemStatus::DownCoded]
+    );
+    assert!(dispute.patient_prevailed);
+    // The bond is refunded to the patient
+    assert_eq!(dispute.release_bond().unwrap(), BOND);
+    assert_eq!(dispute.bond, 0);
+}
+
+#[test]
+fn an_unresolved_dispute_goes_to_the_patient_at_the_deadline() {
+    let mut claim = rejected_claim();
+    let mut dispute = disputed(&mut claim);
+    let anyone = Pubkey::new_unique();
+    dispute
+        .resolve(&mut claim, anyone, false, DEADLINE)
+        .unwrap();
+    assert_eq!(claim.status, ClaimStatus::Verified);
+    assert_eq!(
+        (dispute.resolved_at, dispute.patient_prevailed),
+        (DEADLINE, true)
+    );
+    assert_eq!(dispute.release_bond().unwrap(), BOND);
+}
+
+#[test]
+fn disputes_keep_the_bond_they_were_opened_with() {
+    let mut config = config();
+    let mut claim = rejected_claim();
+    let mut dispute = unopened();
+    dispute
+        .open(Pubkey::new_unique(), &mut claim, &config, [2; 32], NOW)
+        .unwrap();
+    let arbiter = config.arbiter;
+    config.set(arbiter, RESOLUTION_SECS, 0, NOW).unwrap();
+    assert_eq!(dispute.bond, BOND);
+
+    // Without a bond configured, disputes are free to open
+    let mut claim = rejected_claim();
+    let mut dispute = unopened();
+    dispute
+        .open(Pubkey::new_unique(), &mut claim, &config, [2; 32], NOW)
+        .unwrap();
+    dispute.resolve(&mut claim, arbiter, false, NOW).unwrap();
+    assert_eq!(dispute.release_bond().unwrap(), 0);
+}
+
+#[test]
+fn disputes_need_an_arbiter_and_a_deadline() {
+    let mut config = config();
+    for (arbiter, resolution_secs) in [
+        (Pubkey::default(), RESOLUTION_SECS),
+        (Pubkey::new_unique(), 0),
+        (Pubkey::new_unique(), -1),
+    ] {
+        expect_error(
+            config.set(arbiter, resolution_secs, BOND, NOW),
+            ErrorCode::InvalidDisputeConfig,
+        );
+    }
+    let arbiter = Pubkey::new_unique();
+    config.set(arbiter, 3_600, 0, NOW).unwrap();
+    assert_eq!(
+        (config.arbiter, config.resolution_secs, config.updated_at),
+        (arbiter, 3_600, NOW)
+    );
+    assert_eq!(config.bond_lamports, 0);
+}
@@ -0,0 +1,273 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{expect_error, line};
+use primal_health_solana_program::{
+    ClaimAccount, ClaimEscrow, ClaimStatus, Config, ErrorCode, FacilitatorAccount, LineItemStatus,
+    PaymentSplit, PAYMENT_WINDOW_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const YEAR: i64 = 365 * 24 * 60 * 60;
+
+fn claim(status: ClaimStatus) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "escrowed".to_string(),
+        status,
+        verified_at: NOW,
+        service_start: NOW,
+        service_end: NOW,
+        ..common::claim(vec![
+            line(1_000, LineItemStatus::Approved),
+            line(500, LineItemStatus::Denied),
+        ])
+    }
+}
+
+fn escrow(claim: &ClaimAccount, now: i64) -> ClaimEscrow {
+    ClaimEscrow {
+        claim: Pubkey::new_unique(),
+        provider: claim.provider,
+        amount: claim.escrow_amount(now).unwrap(),
+        funded_at: now,
+        arbiter: Pubkey::default(),
+        account_version: 2,
+    }
+}
+
+#[test]
+fn undecided_claims_lock_the_billed_amount() {
+    let mut claim = claim(ClaimStatus::UnderReview);
+    let escrow = escrow(&claim, NOW);
+    assert_eq!(escrow.amount, 1_500);
+
+    // Verified late, but funded before: no interest is owed
+    claim.status = ClaimStatus::Verified;
+    let late = NOW + PAYMENT_WINDOW_SECS + YEAR;
+    claim.verified_at = late;
+    assert_eq!(escrow.settle(&mut claim).unwrap(), 1_000);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+    assert_eq!(claim.interest_paid, 0);
+}
+
+#[test]
+fn late_funding_locks_the_interest_due_then() {
+    let mut claim = claim(ClaimStatus::Verified);
+    let funded_at = NOW + PAYMENT_WINDOW_SECS + YEAR;
+    let escrow = escrow(&claim, funded_at);
+    // 10% a year on the approved 1_000
+    assert_eq!(escrow.amount, 1_100);
+
+    assert_eq!(escrow.settle(&mut claim).unwrap(), 1_100);
+    assert_eq!((claim.principal_paid, claim.interest_paid), (1_000, 100));
+}
+
+#[test]
+fn rejected_and_paid_claims_refund_the_provider() {
+    for status in [
+        ClaimStatus::Rejected,
+        ClaimStatus::Declined,
+        ClaimStatus::Paid,
+    ] {
+        let mut claim = claim(status);
+        let escrow = ClaimEscrow {
+            claim: Pubkey::new_unique(),
+            provider: claim.provider,
+            amount: 1_500,
+            funded_at: NOW,
+            arbiter: Pubkey::default(),
+            account_version: 2,
+        };
+        assert_eq!(escrow.settle(&mut claim).unwrap(), 0);
+        assert_eq!(claim.status, status);
+    }
+
+    // Nothing approved leaves nothing to pay
+    let mut claim = claim(ClaimStatus::Verified);
+    let escrow = escrow(&claim, NOW);
+    claim.line_items[0].status = LineItemStatus::Denied;
+    assert_eq!(escrow.settle(&mut claim).unwrap(), 0);
+}
+
+#[test]
+fn escrowed_settlements_pay_the_protocol_fee_like_direct_payments() {
+    let mut config = Config {
+        admin: Pubkey::default(),
+        paused: false,
+        fee_bps: 0,
+        updated_at: 0,
+        claim_ttl_secs: 0,
+        account_version: 0,
+    };
+    config.initialize(Pubkey::new_unique(), 100, NOW).unwrap();
+    let facilitator = || {
+        let mut facilitator = FacilitatorAccount {
+            authority: Pubkey::default(),
+            signing_key: Pubkey::default(),
+            fee_share_bps: 0,
+            revoked: false,
+            approved_at: 0,
+            key_rotated_at: 0,
+            settlements: 0,
+            total_earned: 0,
+            total_withdrawn: 0,
+            account_version: 0,
+        };
+        let signing_key = Pubkey::new_unique();
+        facilitator
+            .approve(Pubkey::new_unique(), signing_key, 2_000, NOW)
+            .unwrap();
+        (facilitator, signing_key)
+    };
+
+    let mut direct = claim(ClaimStatus::Verified);
+    let paid = direct.settle_payment(NOW).unwrap();
+    let mut escrowed = claim(ClaimStatus::Verified);
+    let settled = escrow(&escrowed, NOW).settle(&mut escrowed).unwrap();
+    assert_eq!(settled, paid);
+
+    // 1% of the approved 1_000, a fifth of it to the facilitator
+    let (mut direct_facilitator, direct_key) = facilitator();
+    let (mut escrow_facilitator, escrow_key) = facilitator();
+    let direct_split = config
+        .split_payment(paid, Some((&mut direct_facilitator, direct_key)))
+        .unwrap();
+    let escrow_split = config
+        .split_payment(settled, Some((&mut escrow_facilitator, escrow_key)))
+        .unwrap();
+    assert_eq!(escrow_split, direct_split);
+    assert_eq!(
+        escrow_split,
+        PaymentSplit {
+            received: 990,
+            treasury_fee: 8,
+            facilitator_share: 2,
+        }
+    );
+    assert_eq!(escrow_facilitator.total_earned, 2);
+    assert_eq!(
+        config.split_payment(settled, None).unwrap(),
+        PaymentSplit {
+            received: 990,
+            treasury_fee: 10,
+            facilitator_share: 0,
+        }
+    );
+}
+
+#[test]
+fn undecided_claims_cannot_be_settled() {
+    let mut claim = claim(ClaimStatus::OnHold);
+    let escrow = escrow(&claim, NOW);
+    expect_error(escrow.settle(&mut claim), ErrorCode::ClaimNotVerified);
+    assert_eq!(claim.principal_paid, 0);
+}
+
+#[test]
+fn settling_fails_when_the_escrow_falls_short() {
+    let mut claim = claim(ClaimStatus::Verified);
+    let mut escrow = escrow(&claim, NOW);
+    escrow.amount = 999;
+    expect_error(escrow.settle(&mut claim), ErrorCode::InsufficientEscrow);
+}
+
+#[test]
+fn only_lamport_claims_outside_tenants_can_be_escrowed() {
+    let mut claim = claim(ClaimStatus::Verified);
+    claim.tenant = Pubkey::new_unique();
+    expect_error(claim.escrow_amount(NOW), ErrorCode::ClaimNotEscrowable);
+
+    let mut claim = self::claim(ClaimStatus::Verified);
+    claim.mint = Pubkey::new_unique();
+    expect_error(claim.escrow_amount(NOW), ErrorCode::ClaimMintMismatch);
+
+    for status in [ClaimStatus::Paid, ClaimStatus::Rejected] {
+        expect_error(
+            self::claim(status).escrow_amount(NOW),
+            ErrorCode::ClaimNotEscrowable,
+        );
+    }
+}
+
+fn split_escrow(claim: &ClaimAccount, arbiter: Pubkey) -> ClaimEscrow {
+    let mut escrow = escrow(claim, NOW);
+    escrow
+        .fund(Pubkey::new_unique(), claim, arbiter, NOW)
+        .unwrap();
+    escrow
+}
+
+#[test]
+fn paying_from_a_split_escrow_needs_the_patient_and_one_other_key() {
+    let claim = claim(ClaimStatus::Verified);
+    let arbiter = Pubkey::new_unique();
+    let escrow = split_escrow(&claim, arbiter);
+    assert!(escrow.is_split());
+    assert_eq!((escrow.arbiter, escrow.amount), (arbiter, 1_000));
+
+    for signers in [
+        vec![claim.patient, claim.provider],
+        vec![arbiter, claim.patient],
+    ] {
+        escrow.authorize_release(&claim, 1_000, &signers).unwrap();
+    }
+    for signers in [
+        vec![claim.provider, arbiter],
+        vec![claim.patient],
+        vec![claim.patient, Pubkey::new_unique()],
+        Vec::new(),
+    ] {
+        expect_error(
+            escrow.authorize_release(&claim, 1_000, &signers),
+            ErrorCode::EscrowReleaseUnauthorized,
+        );
+    }
+}
+
+#[test]
+fn refunding_a_split_escrow_needs_the_provider_and_one_other_key() {
+    let claim = claim(ClaimStatus::Verified);
+    let arbiter = Pubkey::new_unique();
+    let escrow = split_escrow(&claim, arbiter);
+    escrow
+        .authorize_release(&claim, 0, &[claim.provider, claim.patient])
+        .unwrap();
+    escrow
+        .authorize_release(&claim, 0, &[claim.provider, arbiter])
+        .unwrap();
+    expect_error(
+        escrow.authorize_release(&claim, 0, &[claim.patient, arbiter]),
+        ErrorCode::EscrowReleaseUnauthorized,
+    );
+}
+
+#[test]
+fn standard_escrows_release_to_anyone() {
+    let claim = claim(ClaimStatus::Verified);
+    let escrow = escrow(&claim, NOW);
+    assert!(!escrow.is_split());
+    escrow
+        .authorize_release(&claim, 1_000, &[Pubkey::new_unique()])
+        .unwrap();
+}
+
+#[test]
+fn the_arbiter_is_not_a_party_to_the_claim() {
+    let claim = claim(ClaimStatus::Verified);
+    let mut escrow = escrow(&claim, NOW);
+    for arbiter in [claim.patient, claim.provider] {
+        expect_error(
+            escrow.fund(Pubkey::new_unique(), &claim, arbiter, NOW),
+            ErrorCode::InvalidArbiter,
+        );
+    }
+}
+
+#[test]
+fn escrows_are_upgraded_before_settling() {
+    let mut claim = claim(ClaimStatus::Verified);
+    let mut escrow = escrow(&claim, NOW);
+    escrow.account_version = 1;
+    expect_error(escrow.settle(&mut claim), ErrorCode::AccountNotUpgraded);
+    assert_eq!(claim.principal_paid, 0);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimEscrow, ClaimStatus, Config, ErrorCode, LineItemStatus, MAX_CLAIM_TTL_SECS,
+    MIN_CLAIM_TTL_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn config() -> Config {
+    let mut config = Config {
+        admin: Pubkey::default(),
+        paused: false,
+        fee_bps: 0,
+        updated_at: 0,
+        claim_ttl_secs: 0,
+        account_version: 0,
+    };
+    config.initialize(Pubkey::new_unique(), 0, NOW).unwrap();
+    config
+}
+
+fn claim(status: ClaimStatus, expires_at: i64) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "stale".to_string(),
+        attachments: vec![[0xab; 32]],
+        status,
+        expires_at,
+        ..common::claim(vec![common::line(1_000_000, LineItemStatus::Pending)])
+    }
+}
+
+#[test]
+fn claim_lifetimes_are_bounded() {
+    let mut config = config();
+    assert_eq!(config.claim_expiry(NOW), 0);
+    expect_error(
+        config.set_claim_ttl(MIN_CLAIM_TTL_SECS - 1, NOW),
+        ErrorCode::InvalidClaimTtl,
+    );
+    expect_error(
+        config.set_claim_ttl(MAX_CLAIM_TTL_SECS + 1, NOW),
+        ErrorCode::InvalidClaimTtl,
+    );
+    expect_error(config.set_claim_ttl(-DAY, NOW), ErrorCode::InvalidClaimTtl);
+
+    config.set_claim_ttl(30 * DAY, NOW + 60).unwrap();
+    assert_eq!(config.updated_at, NOW + 60);
+    assert_eq!(config.claim_expiry(NOW), NOW + 30 * DAY);
+    config.set_claim_ttl(0, NOW + 120).unwrap();
+    assert_eq!(config.claim_expiry(NOW), 0);
+}
+
+#[test]
+fn configs_must_be_upgraded_to_set_a_lifetime() {
+    let mut config = config();
+    config.account_version = 1;
+    expect_error(
+        config.set_claim_ttl(30 * DAY, NOW),
+        ErrorCode::AccountNotUpgraded,
+    );
+    // Its version byte reads as a lifetime, which is ignored
+    config.claim_ttl_secs = 1;
+    assert_eq!(config.claim_expiry(NOW), 0);
+}
+
+#[test]
+fn undecided_claims_expire_at_their_deadline() {
+    for status in [
+        ClaimStatus::Pending,
+        ClaimStatus::UnderReview,
+        ClaimStatus::OnHold,
+    ] {
+        let mut claim = claim(status, NOW + DAY);
+        expect_error(claim.expire(NOW + DAY - 1), ErrorCode::ClaimNotExpired);
+        assert_eq!(claim.status, status);
+        claim.expire(NOW + DAY).unwrap();
+        assert_eq!(claim.status, ClaimStatus::Expired);
+        claim.expect_closable().unwrap();
+        expect_error(claim.expire(NOW + DAY), ErrorCode::ClaimNotExpirable);
+    }
+}
+
+#[test]
+fn decided_claims_and_claims_without_a_deadline_do_not_expire() {
+    for status in [
+        ClaimStatus::Verified,
+        ClaimStatus::PartiallyPaid,
+        ClaimStatus::Paid,
+        ClaimStatus::Rejected,
+        ClaimStatus::Declined,
+        ClaimStatus::Disputed,
+    ] {
+        expect_error(
+            claim(status, NOW).expire(NOW + DAY),
+            ErrorCode::ClaimNotExpirable,
+        );
+    }
+    expect_error(
+        claim(ClaimStatus::Pending, 0).expire(i64::MAX),
+        ErrorCode::ClaimNotExpired,
+    );
+}
+
+#[test]
+fn expired_claims_return_their_escrow_to_the_provider() {
+    let mut claim = claim(ClaimStatus::Pending, NOW + DAY);
+    let escrow = ClaimEscrow {
+        claim: Pubkey::new_unique(),
+        provider: claim.provider,
+        amount: claim.amount,
+        funded_at: NOW,
+        arbiter: Pubkey::default(),
+        account_version: 2,
+    };
+    claim.expire(NOW + DAY).unwrap();
+    assert_eq!(escrow.settle(&mut claim).unwrap(), 0);
+    escrow
+        .authorize_release(&claim, 0, &[Pubkey::new_unique()])
+        .unwrap();
+
+    // A split escrow needs two signatures, so it is left to settle_claim
+    let split = ClaimEscrow {
+        arbiter: Pubkey::new_unique(),
+        ..escrow
+    };
+    expect_error(
+        split.authorize_release(&claim, 0, &[Pubkey::new_unique()]),
+        ErrorCode::EscrowReleaseUnauthorized,
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimGrant, ClaimStatus, ErrorCode, HealthDataAccount, LineItemStatus,
+    StorageKind,
+};
+
+const FILED_AT: i64 = 1_700_000_000;
+
+fn claim() -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "grant".to_string(),
+        attachments: vec![[1; 32], [2; 32]],
+        ..common::claim(vec![common::line(100, LineItemStatus::Pending)])
+    }
+}
+
+fn grant(claim: &ClaimAccount) -> ClaimGrant {
+    ClaimGrant {
+        claim: Pubkey::new_unique(),
+        patient: claim.patient,
+        filed_at: claim.timestamp,
+        account_version: 1,
+    }
+}
+
+fn record(owner: Pubkey, data_hash: [u8; 32]) -> HealthDataAccount {
+    HealthDataAccount {
+        owner,
+        data_hash,
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [0xcc; 32],
+        timestamp: FILED_AT,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn the_provider_reads_the_claims_attachments() {
+    let mut claim = claim();
+    let grant = grant(&claim);
+    let attached = record(claim.patient, [2; 32]);
+    for status in [
+        ClaimStatus::Pending,
+        ClaimStatus::UnderReview,
+        ClaimStatus::OnHold,
+        ClaimStatus::Verified,
+        ClaimStatus::PartiallyPaid,
+    ] {
+        claim.status = status;
+        grant.authorize(&claim, claim.provider, &attached).unwrap();
+    }
+
+    expect_error(
+        grant.authorize(&claim, Pubkey::new_unique(), &attached),
+        ErrorCode::Unauthorized,
+    );
+    expect_error(
+        grant.authorize(&claim, claim.provider, &record(claim.patient, [3; 32])),
+        ErrorCode::AttachmentNotFound,
+    );
+    // Another patient's record with an attached hash is not covered
+    expect_error(
+        grant.authorize(
+            &claim,
+            claim.provider,
+            &record(Pubkey::new_unique(), [1; 32]),
+        ),
+        ErrorCode::InvalidPatient,
+    );
+}
+
+#[test]
+fn the_grant_lapses_with_the_claim() {
+    let mut claim = claim();
+    let grant = grant(&claim);
+    let attached = record(claim.patient, [1; 32]);
+    for status in [
+        ClaimStatus::Paid,
+        ClaimStatus::Rejected,
+        ClaimStatus::Declined,
+    ] {
+        claim.status = status;
+        expect_error(
+            grant.authorize(&claim, claim.provider, &attached),
+            ErrorCode::ClaimGrantLapsed,
+        );
+    }
+}
+
+#[test]
+fn the_grant_follows_the_claim_to_its_new_provider() {
+    let mut claim = claim();
+    let grant = grant(&claim);
+    let attached = record(claim.patient, [1; 32]);
+    let old_provider = claim.provider;
+    claim.provider = Pubkey::new_unique();
+    grant.authorize(&claim, claim.provider, &attached).unwrap();
+    expect_error(
+        grant.authorize(&claim, old_provider, &attached),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn grants_left_by_a_closed_claim_do_not_carry_over() {
+    let old = claim();
+    let grant = grant(&old);
+    let mut refiled = claim();
+    refiled.patient = old.patient;
+    refiled.timestamp = FILED_AT + 60;
+    expect_error(
+        grant.authorize(
+            &refiled,
+            refiled.provider,
+            &record(refiled.patient, [1; 32]),
+        ),
+        ErrorCode::ClaimGrantLapsed,
+    );
+}
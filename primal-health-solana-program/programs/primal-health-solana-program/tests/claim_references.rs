@@ -0,0 +1,88 @@
+mod common;
+
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimStatus, ErrorCode, MAX_CLAIM_TAGS, MAX_EXTERNAL_REF_LEN, MAX_TAG_LEN,
+};
+
+fn claim() -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "refs".to_string(),
+        amount: 1_000,
+        status: ClaimStatus::Paid,
+        principal_paid: 1_000,
+        ..common::claim(Vec::new())
+    }
+}
+
+fn tags(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|tag| tag.to_string()).collect()
+}
+
+#[test]
+fn references_are_replaced_in_any_status() {
+    let mut claim = claim();
+    claim
+        .set_references("CLM-0001".into(), tags(&["batch-7", "payer-42"]))
+        .unwrap();
+    assert_eq!(claim.external_ref, "CLM-0001");
+    assert_eq!(claim.tags, tags(&["batch-7", "payer-42"]));
+
+    claim.set_references(String::new(), Vec::new()).unwrap();
+    assert!(claim.external_ref.is_empty());
+    assert!(claim.tags.is_empty());
+}
+
+#[test]
+fn references_fill_their_bounds() {
+    let mut claim = claim();
+    let full: Vec<String> = (0..MAX_CLAIM_TAGS)
+        .map(|i| format!("{i}").repeat(MAX_TAG_LEN))
+        .collect();
+    claim
+        .set_references("9".repeat(MAX_EXTERNAL_REF_LEN), full.clone())
+        .unwrap();
+    assert_eq!(claim.tags, full);
+}
+
+#[test]
+fn oversized_references_are_rejected() {
+    let mut claim = claim();
+    expect_error(
+        claim.set_references("9".repeat(MAX_EXTERNAL_REF_LEN + 1), Vec::new()),
+        ErrorCode::ExternalRefTooLong,
+    );
+    let too_many = (0..=MAX_CLAIM_TAGS).map(|i| format!("t{i}")).collect();
+    expect_error(
+        claim.set_references(String::new(), too_many),
+        ErrorCode::TooManyTags,
+    );
+    expect_error(
+        claim.set_references(String::new(), vec!["x".repeat(MAX_TAG_LEN + 1)]),
+        ErrorCode::TagTooLong,
+    );
+    assert!(claim.external_ref.is_empty() && claim.tags.is_empty());
+}
+
+#[test]
+fn tags_must_be_distinct_and_non_empty() {
+    let mut claim = claim();
+    expect_error(
+        claim.set_references(String::new(), tags(&["batch-7", ""])),
+        ErrorCode::TagEmpty,
+    );
+    expect_error(
+        claim.set_references(String::new(), tags(&["batch-7", "batch-7"])),
+        ErrorCode::DuplicateTag,
+    );
+}
+
+#[test]
+fn claims_must_be_upgraded_before_taking_references() {
+    let mut claim = claim();
+    claim.account_version = 0;
+    expect_error(
+        claim.set_references("CLM-0001".into(), Vec::new()),
+        ErrorCode::AccountNotUpgraded,
+    );
+}
@@ -0,0 +1,380 @@
+//! Property tests for the claim lifecycle.
+//!
+//! Random sequences of acceptance, adjudication, verification, auto-approval, hold,
+//! reopen, expiry and payment actions are applied to a claim alongside a model of the
+//! provider and patient balances. Each action is applied atomically, like a
+//! transaction: a failing action leaves the claim and balances untouched.
+
+mod common;
+
+use primal_health_solana_program::{
+    ClaimAccount, ClaimLineItem, ClaimStatus, LineDecision, LineItemStatus, PAYABLE_STATUSES,
+    REOPEN_WINDOW_SECS,
+};
+use proptest::prelude::*;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Accept,
+    Decline,
+    Approve(u8),
+    Deny(u8),
+    DownCode(u8, u64),
+    Verify(ClaimStatus),
+    AutoApprove,
+    Reopen(i64),
+    Hold,
+    Release,
+    Expire,
+    Pay,
+    PayPart(u64),
+    Wait(i64),
+}
+
+fn status() -> impl Strategy<Value = ClaimStatus> {
+    prop_oneof![
+        Just(ClaimStatus::Pending),
+        Just(ClaimStatus::Verified),
+        Just(ClaimStatus::Paid),
+        Just(ClaimStatus::Rejected),
+        Just(ClaimStatus::UnderReview),
+        Just(ClaimStatus::OnHold),
+        Just(ClaimStatus::PartiallyPaid),
+        Just(ClaimStatus::Declined),
+        Just(ClaimStatus::Expired),
+    ]
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        Just(Action::Accept),
+        Just(Action::Decline),
+        (0u8..6).prop_map(Action::Approve),
+        (0u8..6).prop_map(Action::Deny),
+        (0u8..6, 0u64..2_000).prop_map(|(i, price)| Action::DownCode(i, price)),
+        status().prop_map(Action::Verify),
+        Just(Action::AutoApprove),
+        (0i64..2 * REOPEN_WINDOW_SECS).prop_map(Action::Reopen),
+        Just(Action::Hold),
+        Just(Action::Release),
+        Just(Action::Expire),
+        Just(Action::Pay),
+        (0u64..3_000).prop_map(Action::PayPart),
+        (0i64..REOPEN_WINDOW_SECS).prop_map(Action::Wait),
+    ]
+}
+
+fn claim(lines: &[(u32, u64)]) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "prop".to_string(),
+        attachments: vec![[1; 32]],
+        amount: 0,
+        timestamp: 0,
+        ..common::claim(
+            lines
+                .iter()
+                .map(|&(units, unit_price)| ClaimLineItem {
+                    units,
+                    ..common::line(unit_price, LineItemStatus::Pending)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Status changes the lifecycle permits; anything else is a regression.
+fn allowed(from: ClaimStatus, to: ClaimStatus) -> bool {
+    use ClaimStatus::*;
+    matches!(
+        (from, to),
+        (Pending, UnderReview)
+            | (Pending, Declined)
+            | (Pending, Verified)
+            | (UnderReview, Verified)
+            | (UnderReview, Rejected)
+            | (UnderReview, OnHold)
+            | (OnHold, UnderReview)
+            | (Rejected, UnderReview)
+            | (Verified, Paid)
+            | (Verified, PartiallyPaid)
+            | (PartiallyPaid, Paid)
+            | (Pending, Expired)
+            | (UnderReview, Expired)
+            | (OnHold, Expired)
+    )
+}
+
+struct World {
+    claim: ClaimAccount,
+    provider_lamports: u64,
+    patient_lamports: u64,
+    now: i64,
+    payments: u32,
+}
+
+impl World {
+    /// Applies an action, returning the lamports paid if it was a successful payment.
+    fn apply(&mut self, action: &Action) -> Result<Option<u64>, ()> {
+        let mut claim = self.claim.clone();
+        let mut paid = None;
+
+        let result = match action {
+            Action::Accept => claim.accept(),
+            Action::Decline => claim.decline(),
+            Action::Approve(i) => claim.adjudicate_line(*i, LineDecision::Approve, 0),
+            Action::Deny(i) => claim.adjudicate_line(*i, LineDecision::Deny, 45),
+            Action::DownCode(i, unit_price) => claim.adjudicate_line(
+                *i,
+                LineDecision::DownCode {
+                    code: "99212".to_string(),
+                    unit_price: *unit_price,
+                },
+                59,
+            ),
+            Action::Verify(status) => claim.verify(*status, self.now),
+            Action::AutoApprove => claim.auto_approve(self.now),
+            Action::Reopen(after) => {
+                let now = claim.rejected_at + after;
+                claim.reopen(vec![[2; 32]], now)
+            }
+            Action::Hold => claim.place_on_hold(),
+            Action::Release => claim.release_hold(),
+            Action::Expire => claim.expire(self.now),
+            Action::Pay => claim
+                .settle_payment(self.now)
+                .and_then(|amount| self.transfer(amount, &mut paid)),
+            Action::PayPart(principal) => claim
+                .settle_partial_payment(*principal, self.now)
+                .and_then(|amount| self.transfer(amount, &mut paid)),
+            Action::Wait(secs) => {
+                self.now += secs;
+                Ok(())
+            }
+        };
+
+        if result.is_err() {
+            return Err(());
+        }
+        if let Some(amount) = paid {
+            self.provider_lamports -= amount;
+            self.patient_lamports += amount;
+            self.payments += 1;
+        }
+        self.claim = claim;
+        Ok(paid)
+    }
+
+    fn transfer(&self, amount: u64, paid: &mut Option<u64>) -> anchor_lang::Result<()> {
+        // The system transfer fails when the provider cannot cover it
+        if amount > self.provider_lamports {
+            return Err(anchor_lang::error::ErrorCode::ConstraintRaw.into());
+        }
+        *paid = Some(amount);
+        Ok(())
+    }
+}
+
+proptest! {
+    #[test]
+    fn claim_lifecycle_invariants(
+        lines in prop::collection::vec((1u32..5, 1u64..1_000), 1..6),
+        provider_lamports in 0u64..20_000,
+        actions in prop::collection::vec(action(), 1..40),
+    ) {
+        let mut claim = claim(&lines);
+        claim.expires_at = 1_700_000_000 + REOPEN_WINDOW_SECS;
+        let mut world = World {
+            claim,
+            provider_lamports,
+            patient_lamports: 0,
+            now: 1_700_000_000,
+            payments: 0,
+        };
+        let total = world.provider_lamports + world.patient_lamports;
+
+        for action in &actions {
+            let before = world.claim.clone();
+            let outcome = world.apply(action);
+            let after = &world.claim;
+
+            // Lamports only move between the provider and the patient
+            prop_assert_eq!(world.provider_lamports + world.patient_lamports, total);
+
+            // No status regression
+            if before.status != after.status {
+                prop_assert!(
+                    allowed(before.status, after.status),
+                    "illegal transition {:?} -> {:?} via {:?}",
+                    before.status,
+                    after.status,
+                    action
+                );
+            }
+
+            if let Ok(Some(amount)) = outcome {
+                // No payment without verification, and only for adjudicated lines
+                prop_assert!(PAYABLE_STATUSES.contains(&before.status));
+                prop_assert!(before
+                    .line_items
+                    .iter()
+                    .all(|item| item.status != LineItemStatus::Pending));
+                prop_assert_eq!(
+                    amount,
+                    (after.principal_paid - before.principal_paid)
+                        + (after.interest_paid - before.interest_paid)
+                );
+                prop_assert!(after.principal_paid > before.principal_paid);
+            }
+
+            // Never more than the approved amount is paid
+            prop_assert!(Some(after.principal_paid) <= after.approved_amount().ok());
+
+            // Down-codes never increase what is owed for a line
+            for (old, new) in before.line_items.iter().zip(&after.line_items) {
+                prop_assert!(new.unit_price <= old.unit_price);
+            }
+
+            prop_assert_eq!(
+                after.status == ClaimStatus::Paid,
+                world.payments > 0 && after.outstanding_principal().ok() == Some(0)
+            );
+            prop_assert_eq!(
+                after.status == ClaimStatus::PartiallyPaid,
+                world.payments > 0 && after.outstanding_principal().unwrap() > 0
+            );
+        }
+    }
+
+    #[test]
+    fn reopen_is_allowed_once_within_window(after in 0i64..2 * REOPEN_WINDOW_SECS) {
+        let mut claim = claim(&[(1, 100)]);
+        claim.accept().unwrap();
+        claim.adjudicate_line(0, LineDecision::Deny, 45).unwrap();
+        claim.verify(ClaimStatus::Rejected, 1_000).unwrap();
+
+        let reopened = claim.reopen(vec![[2; 32]], 1_000 + after);
+        prop_assert_eq!(reopened.is_ok(), after <= REOPEN_WINDOW_SECS);
+
+        if reopened.is_ok() {
+            claim.verify(ClaimStatus::Rejected, 2_000).unwrap();
+            prop_assert!(claim.reopen(vec![[3; 32]], 2_000).is_err());
+        }
+    }
+}
+
+#[test]
+fn holds_pause_adjudication_until_released() {
+    let mut claim = claim(&[(1, 100)]);
+    assert!(claim.place_on_hold().is_err());
+    claim.accept().unwrap();
+    claim.place_on_hold().unwrap();
+    assert_eq!(claim.status, ClaimStatus::OnHold);
+    assert!(claim.adjudicate_line(0, LineDecision::Approve, 0).is_err());
+    assert!(claim.verify(ClaimStatus::Verified, 1_000).is_err());
+    assert!(claim.place_on_hold().is_err());
+
+    claim.release_hold().unwrap();
+    assert_eq!(claim.status, ClaimStatus::UnderReview);
+    assert!(claim.release_hold().is_err());
+    claim.adjudicate_line(0, LineDecision::Approve, 0).unwrap();
+    claim.verify(ClaimStatus::Verified, 1_000).unwrap();
+    assert!(claim.place_on_hold().is_err());
+}
+
+#[test]
+fn partial_payments_add_up_to_the_approved_amount() {
+    let mut claim = claim(&[(2, 150), (1, 100)]);
+    claim.accept().unwrap();
+    claim.adjudicate_line(0, LineDecision::Approve, 0).unwrap();
+    claim.adjudicate_line(1, LineDecision::Approve, 0).unwrap();
+    claim.verify(ClaimStatus::Verified, 1_000).unwrap();
+
+    assert!(claim.settle_partial_payment(0, 1_000).is_err());
+    assert!(claim.settle_partial_payment(401, 1_000).is_err());
+    assert_eq!(claim.settle_partial_payment(250, 1_000).unwrap(), 250);
+    assert_eq!(claim.status, ClaimStatus::PartiallyPaid);
+    assert_eq!(claim.outstanding_principal().unwrap(), 150);
+
+    assert_eq!(claim.settle_payment(1_000).unwrap(), 150);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+    assert_eq!(claim.principal_paid, 400);
+    assert!(claim.settle_partial_payment(1, 1_000).is_err());
+}
+
+#[test]
+fn providers_accept_claims_before_adjudicating() {
+    let mut claim = claim(&[(1, 100)]);
+    assert!(claim.adjudicate_line(0, LineDecision::Approve, 0).is_err());
+    assert!(claim.verify(ClaimStatus::Rejected, 1_000).is_err());
+
+    let mut declined = claim.clone();
+    declined.decline().unwrap();
+    assert_eq!(declined.status, ClaimStatus::Declined);
+    assert!(declined.accept().is_err());
+    assert!(declined.decline().is_err());
+    assert!(declined
+        .adjudicate_line(0, LineDecision::Approve, 0)
+        .is_err());
+
+    claim.accept().unwrap();
+    assert_eq!(claim.status, ClaimStatus::UnderReview);
+    assert!(claim.accept().is_err());
+    assert!(claim.decline().is_err());
+    claim.adjudicate_line(0, LineDecision::Approve, 0).unwrap();
+}
+
+#[test]
+fn only_unaccepted_claims_can_be_reassigned() {
+    let mut claim = claim(&[(1, 100)]);
+    claim.expect_unadjudicated().unwrap();
+
+    let mut declined = claim.clone();
+    declined.decline().unwrap();
+    declined.expect_unadjudicated().unwrap();
+
+    claim.accept().unwrap();
+    assert!(claim.expect_unadjudicated().is_err());
+}
+
+#[test]
+fn auto_approval_keeps_decided_lines() {
+    let mut held = claim(&[(1, 100)]);
+    held.accept().unwrap();
+    held.place_on_hold().unwrap();
+    assert!(held.auto_approve(5_000).is_err());
+
+    let mut claim = claim(&[(1, 100), (2, 50)]);
+    claim.accept().unwrap();
+    claim.adjudicate_line(0, LineDecision::Deny, 45).unwrap();
+    claim.auto_approve(5_000).unwrap();
+    assert_eq!(claim.status, ClaimStatus::Verified);
+    assert_eq!(claim.verified_at, 5_000);
+    assert_eq!(claim.line_items[0].status, LineItemStatus::Denied);
+    assert_eq!(claim.line_items[1].status, LineItemStatus::Approved);
+    assert_eq!(claim.approved_amount().unwrap(), 100);
+    assert!(claim.auto_approve(6_000).is_err());
+}
+
+#[test]
+fn only_finished_claims_can_be_closed() {
+    let mut claim = claim(&[(1, 100)]);
+    assert!(claim.expect_closable().is_err());
+
+    let mut declined = claim.clone();
+    declined.decline().unwrap();
+    declined.expect_closable().unwrap();
+
+    claim.accept().unwrap();
+    claim.adjudicate_line(0, LineDecision::Deny, 45).unwrap();
+
+    let mut rejected = claim.clone();
+    rejected.verify(ClaimStatus::Rejected, 1_000).unwrap();
+    rejected.expect_closable().unwrap();
+
+    claim.line_items[0].status = LineItemStatus::Approved;
+    claim.verify(ClaimStatus::Verified, 1_000).unwrap();
+    claim.settle_partial_payment(40, 1_000).unwrap();
+    assert!(claim.expect_closable().is_err());
+    claim.settle_payment(1_000).unwrap();
+    claim.expect_closable().unwrap();
+}
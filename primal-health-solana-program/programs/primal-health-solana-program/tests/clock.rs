@@ -0,0 +1,11 @@
+use primal_health_solana_program::clock;
+
+#[test]
+fn override_pins_the_current_time() {
+    clock::set_override(Some(1_700_000_000));
+    assert_eq!(clock::now(&[]).unwrap(), 1_700_000_000);
+
+    // Without an override the host has no clock sysvar to fall back on
+    clock::set_override(None);
+    assert!(clock::now(&[]).is_err());
+}
@@ -0,0 +1,69 @@
+//! Helpers shared by the integration tests. Each test file pulls in the whole
+//! module with `mod common;` and uses only some of it.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::migration::Versioned;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimLineItem, ClaimStatus, ErrorCode, LineItemStatus,
+};
+
+/// When `claim` files its claims.
+pub const FILED_AT: i64 = 1_700_000_000;
+
+pub fn expect_error<T>(result: anchor_lang::Result<T>, code: ErrorCode) {
+    match result {
+        Err(err) => assert_eq!(err, code.into()),
+        Ok(_) => panic!("expected {code:?}"),
+    }
+}
+
+/// One unit of `99213` at `unit_price`.
+pub fn line(unit_price: u64, status: LineItemStatus) -> ClaimLineItem {
+    ClaimLineItem {
+        code: "99213".to_string(),
+        units: 1,
+        unit_price,
+        modifier: String::new(),
+        status,
+        reason_code: 0,
+    }
+}
+
+/// A pending lamport claim for `line_items`, billed at their total and filed
+/// at `FILED_AT` between two fresh parties. Tests set the fields they care
+/// about with struct update syntax, as in
+/// `ClaimAccount { status, ..common::claim(lines) }`.
+pub fn claim(line_items: Vec<ClaimLineItem>) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "claim".to_string(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        attachments: Vec::new(),
+        amount: line_items.iter().map(|item| item.total().unwrap()).sum(),
+        line_items,
+        status: ClaimStatus::Pending,
+        timestamp: FILED_AT,
+        note_count: 0,
+        rejected_at: 0,
+        reopened: false,
+        verified_at: 0,
+        principal_paid: 0,
+        interest_paid: 0,
+        tenant: Pubkey::default(),
+        mint: Pubkey::default(),
+        external_ref: String::new(),
+        tags: Vec::new(),
+        service_start: 0,
+        service_end: 0,
+        approval_policy: Pubkey::default(),
+        required_approvals: 0,
+        approvals: 0,
+        rent_payer: Pubkey::default(),
+        insurer: Pubkey::default(),
+        insurer_approved_at: 0,
+        expires_at: 0,
+        payer_covered: 0,
+        account_version: ClaimAccount::VERSION,
+    }
+}
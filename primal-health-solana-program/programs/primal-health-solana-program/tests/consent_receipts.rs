@@ -0,0 +1,96 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorSerialize};
+use primal_health_solana_program::migration::Versioned;
+use primal_health_solana_program::{
+    AccessGrant, ConsentAction, ConsentLedger, ConsentReceipt, LawfulBasis, GRANT_SCOPE_READ,
+};
+use solana_program::hash::hashv;
+
+fn receipt(action: ConsentAction, timestamp: i64) -> ConsentReceipt {
+    ConsentReceipt {
+        patient: Pubkey::new_from_array([1; 32]),
+        actor: Pubkey::new_from_array([1; 32]),
+        grantee: Pubkey::new_from_array([2; 32]),
+        action,
+        scope: GRANT_SCOPE_READ,
+        lawful_basis: LawfulBasis::Consent,
+        expires_at: 0,
+        timestamp,
+        sequence: 0,
+    }
+}
+
+fn empty_ledger() -> ConsentLedger {
+    ConsentLedger {
+        patient: Pubkey::default(),
+        receipt_count: 0,
+        head: [0; 32],
+        account_version: 1,
+    }
+}
+
+#[test]
+fn ledger_numbers_and_chains_receipts() {
+    let mut ledger = empty_ledger();
+    let mut first = receipt(ConsentAction::Grant, 1_000);
+    let mut second = receipt(ConsentAction::Revoke, 2_000);
+    ledger.append(&mut first).unwrap();
+    ledger.append(&mut second).unwrap();
+
+    assert_eq!((first.sequence, second.sequence), (1, 2));
+    assert_eq!(ledger.receipt_count, 2);
+    assert_eq!(ledger.patient, first.patient);
+
+    // An auditor replaying the emitted receipts reaches the same head
+    let head = [&first, &second].iter().fold([0; 32], |head, receipt| {
+        hashv(&[&head, &receipt.try_to_vec().unwrap()]).to_bytes()
+    });
+    assert_eq!(ledger.head, head);
+}
+
+#[test]
+fn reordered_receipts_give_a_different_head() {
+    let mut ledger = empty_ledger();
+    ledger
+        .append(&mut receipt(ConsentAction::Grant, 1_000))
+        .unwrap();
+    ledger
+        .append(&mut receipt(ConsentAction::Revoke, 2_000))
+        .unwrap();
+
+    let mut reordered = empty_ledger();
+    reordered
+        .append(&mut receipt(ConsentAction::Revoke, 2_000))
+        .unwrap();
+    reordered
+        .append(&mut receipt(ConsentAction::Grant, 1_000))
+        .unwrap();
+    assert_ne!(ledger.head, reordered.head);
+}
+
+#[test]
+fn grants_written_before_lawful_bases_read_as_consent() {
+    let grant = AccessGrant {
+        patient: Pubkey::new_unique(),
+        grantee: Pubkey::new_unique(),
+        scope: GRANT_SCOPE_READ,
+        granted_at: 1_000,
+        expires_at: 0,
+        revoked: false,
+        lawful_basis: LawfulBasis::LegalObligation,
+        parent: Pubkey::default(),
+        depth: 0,
+        delegations: 0,
+        account_version: 2,
+    };
+    let mut data = Vec::new();
+    grant.try_serialize(&mut data).unwrap();
+    // Drop everything from the basis on and zero-pad as the previous layout's
+    // space did
+    data.truncate(8 + 32 + 32 + 1 + 8 + 8 + 1);
+    data.resize(AccessGrant::SPACE, 0);
+
+    let decoded = AccessGrant::decode_any(&data).unwrap();
+    assert_eq!(decoded.lawful_basis, LawfulBasis::Consent);
+    assert_eq!(decoded.grantee, grant.grantee);
+}
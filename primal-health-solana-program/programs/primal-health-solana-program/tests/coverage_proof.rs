@@ -0,0 +1,61 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{CoverageProof, TenantMember, TenantRole};
+
+const NOW: i64 = 1_700_000_000;
+const ENROLLED: i64 = NOW - 90 * 86_400;
+
+fn membership() -> TenantMember {
+    TenantMember {
+        tenant: Pubkey::new_unique(),
+        member: Pubkey::new_unique(),
+        role: TenantRole::Patient,
+        admitted_at: ENROLLED,
+        account_version: 1,
+    }
+}
+
+fn prove(membership: &TenantMember, provider: Pubkey, in_network: bool) -> CoverageProof {
+    CoverageProof::new(
+        "acme-ppo".to_string(),
+        membership,
+        provider,
+        in_network,
+        NOW,
+    )
+}
+
+#[test]
+fn the_proof_summarizes_the_enrollment() {
+    let membership = membership();
+    let provider = Pubkey::new_unique();
+    let proof = prove(&membership, provider, true);
+    assert_eq!(
+        (proof.patient, proof.tenant, proof.provider),
+        (membership.member, membership.tenant, provider)
+    );
+    assert_eq!(proof.plan_id, "acme-ppo");
+    assert_eq!((proof.enrolled_since, proof.as_of), (ENROLLED, NOW));
+    assert!(proof.in_network);
+    assert_eq!(proof.digest, proof.compute_digest());
+}
+
+#[test]
+fn the_digest_commits_to_every_field() {
+    let membership = membership();
+    let provider = Pubkey::new_unique();
+    let proof = prove(&membership, provider, true);
+    assert_ne!(proof.digest, prove(&membership, provider, false).digest);
+
+    let mut other = proof.clone();
+    other.plan_id = "acme-hmo".to_string();
+    assert_ne!(other.compute_digest(), proof.digest);
+    let mut other = proof.clone();
+    other.enrolled_since -= 1;
+    assert_ne!(other.compute_digest(), proof.digest);
+    let mut other = proof.clone();
+    other.as_of += 1;
+    assert_ne!(other.compute_digest(), proof.digest);
+    let mut other = proof.clone();
+    other.provider = Pubkey::new_unique();
+    assert_ne!(other.compute_digest(), proof.digest);
+}
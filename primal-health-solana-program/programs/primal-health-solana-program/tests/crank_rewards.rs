@@ -0,0 +1,58 @@
+mod common;
+
+use common::expect_error;
+use primal_health_solana_program::{CrankVault, ErrorCode, MAX_CRANK_REWARD_LAMPORTS};
+
+const NOW: i64 = 1_700_000_000;
+
+fn vault(reward_lamports: u64) -> CrankVault {
+    let mut vault = CrankVault {
+        reward_lamports: 0,
+        rewards_paid: 0,
+        total_paid: 0,
+        updated_at: 0,
+        account_version: 0,
+    };
+    vault.set_reward(reward_lamports, NOW).unwrap();
+    vault
+}
+
+#[test]
+fn crank_rewards_are_bounded() {
+    let mut vault = vault(10_000);
+    expect_error(
+        vault.set_reward(MAX_CRANK_REWARD_LAMPORTS + 1, NOW),
+        ErrorCode::InvalidCrankReward,
+    );
+    assert_eq!(vault.reward_lamports, 10_000);
+    vault
+        .set_reward(MAX_CRANK_REWARD_LAMPORTS, NOW + 60)
+        .unwrap();
+    assert_eq!(vault.updated_at, NOW + 60);
+}
+
+#[test]
+fn a_short_vault_pays_nothing() {
+    let vault = vault(10_000);
+    assert_eq!(vault.reward_from(25_000), 10_000);
+    assert_eq!(vault.reward_from(10_000), 10_000);
+    assert_eq!(vault.reward_from(9_999), 0);
+    assert_eq!(self::vault(0).reward_from(25_000), 0);
+}
+
+#[test]
+fn rewards_are_totalled() {
+    let mut vault = vault(10_000);
+    vault.record_reward(10_000).unwrap();
+    vault.record_reward(10_000).unwrap();
+    assert_eq!(vault.rewards_paid, 2);
+    assert_eq!(vault.total_paid, 20_000);
+}
+
+#[test]
+fn reward_counts_do_not_wrap() {
+    let mut vault = vault(10_000);
+    vault.rewards_paid = u64::MAX;
+    expect_error(vault.record_reward(10_000), ErrorCode::AmountOverflow);
+    assert_eq!(vault.total_paid, 0);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    Delegate, ErrorCode, DELEGATE_FILE_CLAIMS, DELEGATE_GRANT_CONSENT, DELEGATE_PERMISSIONS_ALL,
+    DELEGATE_SUBMIT_DATA,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn unset() -> Delegate {
+    Delegate {
+        patient: Pubkey::default(),
+        delegate: Pubkey::default(),
+        permissions: 0,
+        updated_at: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn delegates_act_within_their_permissions() {
+    let mut delegate = unset();
+    delegate
+        .set(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            DELEGATE_SUBMIT_DATA | DELEGATE_FILE_CLAIMS,
+            NOW,
+        )
+        .unwrap();
+    delegate.authorize(DELEGATE_SUBMIT_DATA).unwrap();
+    delegate.authorize(DELEGATE_FILE_CLAIMS).unwrap();
+    expect_error(
+        delegate.authorize(DELEGATE_GRANT_CONSENT),
+        ErrorCode::InsufficientDelegatePermissions,
+    );
+}
+
+#[test]
+fn setting_again_replaces_the_permissions() {
+    let patient = Pubkey::new_unique();
+    let caregiver = Pubkey::new_unique();
+    let mut delegate = unset();
+    delegate
+        .set(patient, caregiver, DELEGATE_PERMISSIONS_ALL, NOW)
+        .unwrap();
+    delegate
+        .set(patient, caregiver, DELEGATE_GRANT_CONSENT, NOW + 60)
+        .unwrap();
+    assert_eq!(delegate.permissions, DELEGATE_GRANT_CONSENT);
+    assert_eq!(
+        (delegate.updated_at, delegate.account_version),
+        (NOW + 60, 1)
+    );
+    expect_error(
+        delegate.authorize(DELEGATE_SUBMIT_DATA),
+        ErrorCode::InsufficientDelegatePermissions,
+    );
+}
+
+#[test]
+fn delegates_need_known_permissions_and_another_wallet() {
+    let patient = Pubkey::new_unique();
+    let mut delegate = unset();
+    expect_error(
+        delegate.set(patient, patient, DELEGATE_SUBMIT_DATA, NOW),
+        ErrorCode::InvalidDelegate,
+    );
+    for permissions in [0, DELEGATE_PERMISSIONS_ALL + 1] {
+        expect_error(
+            delegate.set(patient, Pubkey::new_unique(), permissions, NOW),
+            ErrorCode::InvalidDelegatePermissions,
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{
+    DependentLink, GUARDIAN_FILE_CLAIMS, GUARDIAN_GRANT_ACCESS, GUARDIAN_PERMISSIONS_ALL,
+};
+
+/// A dependent who comes of age at this Unix time.
+const MAJORITY_AT: i64 = 1_900_000_000;
+
+fn link(permissions: u8) -> DependentLink {
+    DependentLink {
+        guardian: Pubkey::new_unique(),
+        dependent: Pubkey::new_unique(),
+        permissions,
+        linked_at: 1_700_000_000,
+        expires_at: MAJORITY_AT,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn links_cover_only_their_permissions() {
+    let claims_only = link(GUARDIAN_FILE_CLAIMS);
+    assert!(claims_only.allows(GUARDIAN_FILE_CLAIMS, 1_800_000_000));
+    assert!(!claims_only.allows(GUARDIAN_GRANT_ACCESS, 1_800_000_000));
+    assert!(!claims_only.allows(GUARDIAN_PERMISSIONS_ALL, 1_800_000_000));
+    assert!(link(GUARDIAN_PERMISSIONS_ALL).allows(GUARDIAN_GRANT_ACCESS, 1_800_000_000));
+}
+
+#[test]
+fn links_lapse_when_the_dependent_comes_of_age() {
+    let full = link(GUARDIAN_PERMISSIONS_ALL);
+    assert!(full.is_active(MAJORITY_AT - 1));
+    assert!(!full.is_active(MAJORITY_AT));
+    assert!(!full.allows(GUARDIAN_FILE_CLAIMS, MAJORITY_AT + 1));
+}
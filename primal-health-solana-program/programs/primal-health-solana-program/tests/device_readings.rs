@@ -0,0 +1,106 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    DataCategory, DeviceAccount, ErrorCode, MAX_DEVICE_READINGS_PER_DAY,
+};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn unregistered() -> DeviceAccount {
+    DeviceAccount {
+        patient: Pubkey::default(),
+        device: Pubkey::default(),
+        model_hash: [0; 32],
+        category: DataCategory::General,
+        max_readings_per_day: 0,
+        day: 0,
+        readings_today: 0,
+        total_readings: 0,
+        registered_at: 0,
+        account_version: 0,
+    }
+}
+
+fn registered(max_readings_per_day: u32) -> DeviceAccount {
+    let mut device = unregistered();
+    device
+        .register(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            [4; 32],
+            DataCategory::Vitals,
+            max_readings_per_day,
+            NOW,
+        )
+        .unwrap();
+    device
+}
+
+#[test]
+fn devices_submit_up_to_their_daily_limit() {
+    let mut device = registered(3);
+    for _ in 0..3 {
+        device.record_reading(NOW + 60).unwrap();
+    }
+    expect_error(
+        device.record_reading(NOW + 120),
+        ErrorCode::DeviceRateLimited,
+    );
+    assert_eq!((device.readings_today, device.total_readings), (3, 3));
+}
+
+#[test]
+fn the_limit_resets_each_utc_day() {
+    let mut device = registered(1);
+    device.record_reading(NOW).unwrap();
+    expect_error(
+        device.record_reading(NOW + 60),
+        ErrorCode::DeviceRateLimited,
+    );
+
+    let midnight = (NOW.div_euclid(DAY) + 1) * DAY;
+    expect_error(
+        device.record_reading(midnight - 1),
+        ErrorCode::DeviceRateLimited,
+    );
+    device.record_reading(midnight).unwrap();
+    assert_eq!((device.readings_today, device.total_readings), (1, 2));
+}
+
+#[test]
+fn registration_checks_the_device_and_its_limit() {
+    let patient = Pubkey::new_unique();
+    let device = Pubkey::new_unique();
+    let mut account = unregistered();
+    expect_error(
+        account.register(patient, patient, [4; 32], DataCategory::Vitals, 1, NOW),
+        ErrorCode::InvalidDevice,
+    );
+    expect_error(
+        account.register(patient, device, [0; 32], DataCategory::Vitals, 1, NOW),
+        ErrorCode::HashEmpty,
+    );
+    for limit in [0, MAX_DEVICE_READINGS_PER_DAY + 1] {
+        expect_error(
+            account.register(patient, device, [4; 32], DataCategory::Vitals, limit, NOW),
+            ErrorCode::InvalidDeviceRateLimit,
+        );
+    }
+
+    account
+        .register(
+            patient,
+            device,
+            [4; 32],
+            DataCategory::Vitals,
+            MAX_DEVICE_READINGS_PER_DAY,
+            NOW,
+        )
+        .unwrap();
+    assert_eq!((account.patient, account.device), (patient, device));
+    assert_eq!(account.category, DataCategory::Vitals);
+    assert_eq!((account.registered_at, account.account_version), (NOW, 1));
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::disclosure::{merkle_proof, merkle_root, reading_leaf};
+use primal_health_solana_program::{
+    DataCategory, DeviceAccount, DeviceRollup, ErrorCode, ReadingSummary,
+};
+
+const DAY: i64 = 24 * 60 * 60;
+/// The rolled-up day; the device was registered the day before.
+const ROLLUP_DAY: i64 = 19_675;
+const NOW: i64 = (ROLLUP_DAY + 1) * DAY + 3_600;
+
+/// Hourly heart-rate readings over the day.
+fn readings() -> Vec<(i64, i64)> {
+    (0..24)
+        .map(|hour| (ROLLUP_DAY * DAY + hour * 3_600, 60 + hour % 8))
+        .collect()
+}
+
+fn leaves(readings: &[(i64, i64)]) -> Vec<[u8; 32]> {
+    readings
+        .iter()
+        .map(|&(timestamp, value)| reading_leaf(timestamp, value))
+        .collect()
+}
+
+fn summary() -> ReadingSummary {
+    ReadingSummary {
+        reading_count: 24,
+        min_value: 60,
+        max_value: 67,
+        avg_value: 63,
+    }
+}
+
+fn device() -> DeviceAccount {
+    DeviceAccount {
+        patient: Pubkey::new_unique(),
+        device: Pubkey::new_unique(),
+        model_hash: [4; 32],
+        category: DataCategory::Vitals,
+        max_readings_per_day: 24,
+        day: ROLLUP_DAY,
+        readings_today: 24,
+        total_readings: 24,
+        registered_at: (ROLLUP_DAY - 1) * DAY,
+        account_version: 1,
+    }
+}
+
+fn empty_rollup() -> DeviceRollup {
+    DeviceRollup {
+        device_account: Pubkey::default(),
+        patient: Pubkey::default(),
+        category: DataCategory::General,
+        day: 0,
+        readings_root: [0; 32],
+        summary: ReadingSummary {
+            reading_count: 0,
+            min_value: 0,
+            max_value: 0,
+            avg_value: 0,
+        },
+        summary_hash: [0; 32],
+        rolled_up_at: 0,
+        account_version: 0,
+    }
+}
+
+fn rolled_up() -> DeviceRollup {
+    let mut rollup = empty_rollup();
+    rollup
+        .roll_up(
+            Pubkey::new_unique(),
+            &device(),
+            ROLLUP_DAY,
+            merkle_root(&leaves(&readings())),
+            summary(),
+            NOW,
+        )
+        .unwrap();
+    rollup
+}
+
+#[test]
+fn rollups_take_the_devices_patient_and_category() {
+    let device = device();
+    let address = Pubkey::new_unique();
+    let mut rollup = empty_rollup();
+    rollup
+        .roll_up(address, &device, ROLLUP_DAY, [9; 32], summary(), NOW)
+        .unwrap();
+    assert_eq!(rollup.device_account, address);
+    assert_eq!(
+        (rollup.patient, rollup.category),
+        (device.patient, device.category)
+    );
+    assert_eq!(rollup.summary_hash, rollup.digest());
+    assert_eq!((rollup.rolled_up_at, rollup.account_version), (NOW, 1));
+}
+
+#[test]
+fn the_summary_hash_covers_the_root_and_summary() {
+    let rollup = rolled_up();
+    let mut other = rollup.clone();
+    other.summary.max_value += 1;
+    assert_ne!(other.digest(), rollup.summary_hash);
+    let mut other = rollup.clone();
+    other.readings_root = [9; 32];
+    assert_ne!(other.digest(), rollup.summary_hash);
+    let mut other = rollup.clone();
+    other.device_account = Pubkey::new_unique();
+    assert_ne!(other.digest(), rollup.summary_hash);
+}
+
+#[test]
+fn only_finished_days_since_registration_roll_up() {
+    let device = device();
+    let mut rollup = empty_rollup();
+    let today = NOW.div_euclid(DAY);
+    expect_error(
+        rollup.roll_up(
+            Pubkey::new_unique(),
+            &device,
+            today,
+            [9; 32],
+            summary(),
+            NOW,
+        ),
+        ErrorCode::RollupDayOpen,
+    );
+    expect_error(
+        rollup.roll_up(
+            Pubkey::new_unique(),
+            &device,
+            ROLLUP_DAY - 2,
+            [9; 32],
+            summary(),
+            NOW,
+        ),
+        ErrorCode::InvalidRollup,
+    );
+}
+
+#[test]
+fn summaries_must_be_consistent() {
+    let device = device();
+    let mut rollup = empty_rollup();
+    let mut roll_up = |readings_root, summary| {
+        rollup.roll_up(
+            Pubkey::new_unique(),
+            &device,
+            ROLLUP_DAY,
+            readings_root,
+            summary,
+            NOW,
+        )
+    };
+    expect_error(roll_up([0; 32], summary()), ErrorCode::HashEmpty);
+    for inconsistent in [
+        ReadingSummary {
+            reading_count: 0,
+            ..summary()
+        },
+        ReadingSummary {
+            avg_value: 59,
+            ..summary()
+        },
+        ReadingSummary {
+            avg_value: 68,
+            ..summary()
+        },
+        ReadingSummary {
+            min_value: 70,
+            ..summary()
+        },
+    ] {
+        expect_error(roll_up([9; 32], inconsistent), ErrorCode::InvalidRollup);
+    }
+}
+
+#[test]
+fn raw_readings_prove_against_the_rollup() {
+    let rollup = rolled_up();
+    let readings = readings();
+    let leaves = leaves(&readings);
+    for index in [0, 7, 23] {
+        let (timestamp, value) = readings[index];
+        rollup
+            .verify_reading(
+                timestamp,
+                value,
+                index as u32,
+                &merkle_proof(&leaves, index),
+            )
+            .unwrap();
+    }
+
+    let (timestamp, value) = readings[2];
+    let proof = merkle_proof(&leaves, 2);
+    expect_error(
+        rollup.verify_reading(timestamp, value + 1, 2, &proof),
+        ErrorCode::InvalidMerkleProof,
+    );
+    expect_error(
+        rollup.verify_reading(timestamp, value, 3, &proof),
+        ErrorCode::InvalidMerkleProof,
+    );
+    expect_error(
+        rollup.verify_reading(timestamp, value, 24, &proof),
+        ErrorCode::InvalidFieldIndex,
+    );
+    expect_error(
+        rollup.verify_reading(timestamp, value, 2, &proof[1..]),
+        ErrorCode::InvalidMerkleProof,
+    );
+}
+
+#[test]
+fn readings_off_the_day_or_out_of_range_are_rejected() {
+    let rollup = rolled_up();
+    let proof = merkle_proof(&leaves(&readings()), 0);
+    for (timestamp, value) in [
+        (ROLLUP_DAY * DAY - 1, 60),
+        ((ROLLUP_DAY + 1) * DAY, 60),
+        (ROLLUP_DAY * DAY, 59),
+        (ROLLUP_DAY * DAY, 68),
+    ] {
+        expect_error(
+            rollup.verify_reading(timestamp, value, 0, &proof),
+            ErrorCode::ReadingOutsideRollup,
+        );
+    }
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    EmergencyAccess, ErrorCode, HealthDataAccount, StorageKind, EMERGENCY_ACCESS_DELAY_SECS,
+    EMERGENCY_ACCESS_DURATION_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const OPENS: i64 = NOW + EMERGENCY_ACCESS_DELAY_SECS;
+const EXPIRES: i64 = OPENS + EMERGENCY_ACCESS_DURATION_SECS;
+
+fn record() -> HealthDataAccount {
+    HealthDataAccount {
+        owner: Pubkey::new_unique(),
+        data_hash: [1; 32],
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [0xcc; 32],
+        timestamp: NOW,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 2,
+    }
+}
+
+fn unrequested() -> EmergencyAccess {
+    EmergencyAccess {
+        data_hash: [0; 32],
+        patient: Pubkey::default(),
+        provider: Pubkey::default(),
+        justification_hash: [0; 32],
+        requested_at: 0,
+        available_at: 0,
+        expires_at: 0,
+        vetoed_at: 0,
+        account_version: 0,
+    }
+}
+
+fn requested() -> EmergencyAccess {
+    let mut access = unrequested();
+    access
+        .request(&record(), Pubkey::new_unique(), [2; 32], NOW)
+        .unwrap();
+    access
+}
+
+#[test]
+fn access_opens_after_the_delay_and_lapses() {
+    let access = requested();
+    assert_eq!((access.available_at, access.expires_at), (OPENS, EXPIRES));
+    let provider = access.provider;
+    expect_error(
+        access.authorize(provider, OPENS - 1),
+        ErrorCode::EmergencyAccessPending,
+    );
+    access.authorize(provider, OPENS).unwrap();
+    access.authorize(provider, EXPIRES - 1).unwrap();
+    expect_error(
+        access.authorize(provider, EXPIRES),
+        ErrorCode::EmergencyAccessExpired,
+    );
+    expect_error(
+        access.authorize(Pubkey::new_unique(), OPENS),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn the_patient_can_veto_only_during_the_delay() {
+    let mut access = requested();
+    expect_error(access.veto(OPENS), ErrorCode::EmergencyVetoWindowClosed);
+
+    access.veto(OPENS - 1).unwrap();
+    assert_eq!(access.vetoed_at, OPENS - 1);
+    expect_error(
+        access.authorize(access.provider, OPENS),
+        ErrorCode::EmergencyAccessVetoed,
+    );
+    expect_error(access.veto(OPENS - 1), ErrorCode::EmergencyAccessVetoed);
+}
+
+#[test]
+fn requests_close_once_vetoed_or_expired() {
+    let mut access = requested();
+    expect_error(
+        access.expect_closable(EXPIRES - 1),
+        ErrorCode::EmergencyAccessActive,
+    );
+    access.expect_closable(EXPIRES).unwrap();
+    access.veto(NOW + 60).unwrap();
+    access.expect_closable(NOW + 60).unwrap();
+}
+
+#[test]
+fn requests_need_a_justification_and_another_wallet() {
+    let record = record();
+    let mut access = unrequested();
+    expect_error(
+        access.request(&record, record.owner, [2; 32], NOW),
+        ErrorCode::InvalidEmergencyAccess,
+    );
+    expect_error(
+        access.request(&record, Pubkey::new_unique(), [0; 32], NOW),
+        ErrorCode::HashEmpty,
+    );
+
+    let provider = Pubkey::new_unique();
+    access.request(&record, provider, [2; 32], NOW).unwrap();
+    assert_eq!(
+        (access.data_hash, access.patient, access.provider),
+        (record.data_hash, record.owner, provider)
+    );
+    assert_eq!((access.requested_at, access.account_version), (NOW, 1));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::migration::Versioned;
+use primal_health_solana_program::{
+    EncryptionKey, ErrorCode, HealthDataAccount, RecordPointer, StorageKind,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn registered(owner: Pubkey, x25519_key: [u8; 32]) -> EncryptionKey {
+    let mut key = EncryptionKey {
+        owner: Pubkey::default(),
+        x25519_key: [0; 32],
+        updated_at: 0,
+        key_version: 0,
+        account_version: 0,
+    };
+    key.register(owner, x25519_key, NOW).unwrap();
+    key
+}
+
+fn record(owner: Pubkey, owner_key: Option<&EncryptionKey>) -> HealthDataAccount {
+    let mut record = HealthDataAccount {
+        owner: Pubkey::default(),
+        data_hash: [0; 32],
+        storage: StorageKind::Ipfs,
+        cid: String::new(),
+        commitment: [0; 32],
+        timestamp: 0,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 0,
+    };
+    record.record(
+        owner,
+        owner,
+        [1; 32],
+        RecordPointer {
+            storage: StorageKind::Ipfs,
+            cid: "bafkrecord".to_string(),
+            commitment: [2; 32],
+        },
+        owner_key,
+        NOW,
+    );
+    record
+}
+
+#[test]
+fn registered_keys_start_at_version_one() {
+    let owner = Pubkey::new_unique();
+    let key = registered(owner, [7; 32]);
+    assert_eq!(key.owner, owner);
+    assert_eq!(key.key_version, 1);
+    assert_eq!(key.account_version, EncryptionKey::VERSION);
+
+    let mut empty = key.clone();
+    expect_error(
+        empty.register(owner, [0; 32], NOW),
+        ErrorCode::InvalidEncryptionKey,
+    );
+}
+
+#[test]
+fn rotating_moves_to_the_next_version() {
+    let mut key = registered(Pubkey::new_unique(), [7; 32]);
+    key.rotate([8; 32], NOW + 60).unwrap();
+    assert_eq!(key.x25519_key, [8; 32]);
+    assert_eq!(key.key_version, 2);
+    assert_eq!(key.updated_at, NOW + 60);
+
+    expect_error(
+        key.rotate([8; 32], NOW + 120),
+        ErrorCode::EncryptionKeyUnchanged,
+    );
+    expect_error(
+        key.rotate([0; 32], NOW + 120),
+        ErrorCode::InvalidEncryptionKey,
+    );
+    assert_eq!(key.key_version, 2);
+}
+
+#[test]
+fn records_keep_the_key_version_they_were_encrypted_to() {
+    let owner = Pubkey::new_unique();
+    let mut key = registered(owner, [7; 32]);
+    key.rotate([8; 32], NOW).unwrap();
+
+    assert_eq!(record(owner, Some(&key)).key_version, 2);
+    assert_eq!(record(owner, None).key_version, 0);
+}
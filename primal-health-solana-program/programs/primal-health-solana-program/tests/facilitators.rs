@@ -0,0 +1,107 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{ErrorCode, FacilitatorAccount};
+
+const NOW: i64 = 1_700_000_000;
+
+fn empty() -> FacilitatorAccount {
+    FacilitatorAccount {
+        authority: Pubkey::default(),
+        signing_key: Pubkey::default(),
+        fee_share_bps: 0,
+        revoked: false,
+        approved_at: 0,
+        key_rotated_at: 0,
+        settlements: 0,
+        total_earned: 0,
+        total_withdrawn: 0,
+        account_version: 0,
+    }
+}
+
+fn approved(fee_share_bps: u16) -> FacilitatorAccount {
+    let mut facilitator = empty();
+    facilitator
+        .approve(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            fee_share_bps,
+            NOW,
+        )
+        .unwrap();
+    facilitator
+}
+
+#[test]
+fn approval_needs_a_signing_key_and_a_share_of_at_most_the_fee() {
+    let authority = Pubkey::new_unique();
+    expect_error(
+        empty().approve(authority, Pubkey::default(), 2_000, NOW),
+        ErrorCode::InvalidFacilitatorKey,
+    );
+    expect_error(
+        empty().approve(authority, Pubkey::new_unique(), 10_001, NOW),
+        ErrorCode::InvalidFeeShare,
+    );
+
+    let mut facilitator = approved(10_000);
+    facilitator.revoked = true;
+    facilitator
+        .approve(authority, Pubkey::new_unique(), 2_000, NOW + 60)
+        .unwrap();
+    assert!(!facilitator.revoked);
+    assert_eq!(facilitator.authority, authority);
+    assert_eq!(facilitator.approved_at, NOW + 60);
+}
+
+#[test]
+fn payments_are_attributed_to_the_current_signing_key() {
+    let mut facilitator = approved(2_000);
+    let old_key = facilitator.signing_key;
+    assert_eq!(facilitator.attribute(old_key, 5_000).unwrap(), 1_000);
+    assert_eq!(facilitator.attribute(old_key, 7).unwrap(), 1);
+    assert_eq!(facilitator.settlements, 2);
+    assert_eq!(facilitator.total_earned, 1_001);
+
+    let new_key = Pubkey::new_unique();
+    facilitator.rotate_key(new_key, NOW + 60).unwrap();
+    assert_eq!(facilitator.key_rotated_at, NOW + 60);
+    expect_error(
+        facilitator.attribute(old_key, 5_000),
+        ErrorCode::FacilitatorKeyMismatch,
+    );
+    assert_eq!(facilitator.attribute(new_key, 5_000).unwrap(), 1_000);
+}
+
+#[test]
+fn rotation_needs_a_new_key() {
+    let mut facilitator = approved(2_000);
+    let current = facilitator.signing_key;
+    expect_error(
+        facilitator.rotate_key(current, NOW),
+        ErrorCode::InvalidFacilitatorKey,
+    );
+    expect_error(
+        facilitator.rotate_key(Pubkey::default(), NOW),
+        ErrorCode::InvalidFacilitatorKey,
+    );
+}
+
+#[test]
+fn revoked_facilitators_are_not_attributed_or_rotated() {
+    let mut facilitator = approved(2_000);
+    let key = facilitator.signing_key;
+    facilitator.revoked = true;
+    expect_error(
+        facilitator.attribute(key, 5_000),
+        ErrorCode::FacilitatorRevoked,
+    );
+    expect_error(
+        facilitator.rotate_key(Pubkey::new_unique(), NOW),
+        ErrorCode::FacilitatorRevoked,
+    );
+    facilitator.record_withdrawal(500).unwrap();
+    assert_eq!(facilitator.total_withdrawn, 500);
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{
+    FeatureFlags, FEATURE_ACCESS_SALES, FEATURE_ALL, FEATURE_SPONSORSHIP, FEATURE_SUBSCRIPTIONS,
+    FEATURE_TENANTS,
+};
+
+fn flags(flags: u64) -> FeatureFlags {
+    FeatureFlags {
+        admin: Pubkey::new_unique(),
+        flags,
+        updated_at: 1_700_000_000,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn only_the_set_families_are_enabled() {
+    let flags = flags(FEATURE_SUBSCRIPTIONS | FEATURE_TENANTS);
+    assert!(flags.enabled(FEATURE_SUBSCRIPTIONS));
+    assert!(flags.enabled(FEATURE_TENANTS));
+    assert!(!flags.enabled(FEATURE_SPONSORSHIP));
+    assert!(!flags.enabled(FEATURE_ALL));
+}
+
+#[test]
+fn cleared_flags_disable_everything() {
+    let none = flags(0);
+    for feature in [FEATURE_SUBSCRIPTIONS, FEATURE_SPONSORSHIP, FEATURE_TENANTS] {
+        assert!(!none.enabled(feature));
+        assert!(flags(FEATURE_ALL).enabled(feature));
+    }
+}
+
+#[test]
+fn flags_are_distinct_bits() {
+    let features = [
+        FEATURE_SUBSCRIPTIONS,
+        FEATURE_SPONSORSHIP,
+        FEATURE_TENANTS,
+        FEATURE_ACCESS_SALES,
+    ];
+    for feature in features {
+        assert_eq!(feature.count_ones(), 1);
+    }
+    assert_eq!(features.iter().fold(0, |all, f| all | f), FEATURE_ALL);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    Config, ErrorCode, FeeRebates, ProviderVolume, RebateTier, MAX_REBATE_TIERS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const SOL: u64 = 1_000_000_000;
+
+fn tier(min_volume: u64, rebate_bps: u16) -> RebateTier {
+    RebateTier {
+        min_volume,
+        rebate_bps,
+    }
+}
+
+fn rebates(tiers: Vec<RebateTier>) -> FeeRebates {
+    let mut rebates = FeeRebates {
+        tiers: Vec::new(),
+        updated_at: 0,
+        account_version: 0,
+    };
+    rebates.set(tiers, NOW).unwrap();
+    rebates
+}
+
+fn volume() -> ProviderVolume {
+    ProviderVolume {
+        provider: Pubkey::new_unique(),
+        period: 202410,
+        volume: 0,
+        rebated: 0,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn the_rebate_is_the_highest_tier_reached() {
+    let schedule = rebates(vec![tier(10 * SOL, 1_000), tier(100 * SOL, 2_500)]);
+    assert_eq!(schedule.rebate_bps(0), 0);
+    assert_eq!(schedule.rebate_bps(10 * SOL - 1), 0);
+    assert_eq!(schedule.rebate_bps(10 * SOL), 1_000);
+    assert_eq!(schedule.rebate_bps(100 * SOL - 1), 1_000);
+    assert_eq!(schedule.rebate_bps(u64::MAX), 2_500);
+    assert_eq!((schedule.updated_at, schedule.account_version), (NOW, 1));
+
+    // No tiers, no rebates
+    assert_eq!(rebates(Vec::new()).rebate_bps(u64::MAX), 0);
+}
+
+#[test]
+fn tiers_must_rise_in_volume_and_rebate() {
+    let mut rebates = rebates(Vec::new());
+    for tiers in [
+        vec![tier(0, 0)],
+        vec![tier(0, 10_001)],
+        vec![tier(10 * SOL, 1_000), tier(10 * SOL, 2_000)],
+        vec![tier(10 * SOL, 1_000), tier(SOL, 2_000)],
+        vec![tier(10 * SOL, 2_000), tier(100 * SOL, 2_000)],
+        (0..=MAX_REBATE_TIERS as u64)
+            .map(|n| tier(n * SOL, 100 * (n as u16 + 1)))
+            .collect(),
+    ] {
+        expect_error(rebates.set(tiers, NOW + 60), ErrorCode::InvalidFeeRebates);
+    }
+    assert_eq!(rebates.updated_at, NOW);
+    rebates.set(vec![tier(0, 10_000)], NOW + 60).unwrap();
+    assert_eq!(rebates.tiers, vec![tier(0, 10_000)]);
+}
+
+#[test]
+fn volume_earlier_in_the_period_earns_the_rebate() {
+    let rebates = rebates(vec![tier(10 * SOL, 2_500)]);
+    let mut config = Config {
+        admin: Pubkey::new_unique(),
+        paused: false,
+        fee_bps: 0,
+        updated_at: 0,
+        claim_ttl_secs: 0,
+        account_version: 0,
+    };
+    config.initialize(config.admin, 100, NOW).unwrap();
+    let mut volume = volume();
+
+    // The payment that reaches the tier is not rebated itself
+    let fee = config.split_payment(10 * SOL, None).unwrap().treasury_fee;
+    let rebate = volume
+        .record(10 * SOL, fee, rebates.rebate_bps(volume.volume))
+        .unwrap();
+    assert_eq!(rebate, 0);
+    assert_eq!(volume.volume, 10 * SOL);
+
+    let fee = config.split_payment(SOL, None).unwrap().treasury_fee;
+    let rebate = volume
+        .record(SOL, fee, rebates.rebate_bps(volume.volume))
+        .unwrap();
+    assert_eq!((fee, rebate), (SOL / 100, SOL / 400));
+    assert_eq!((volume.volume, volume.rebated), (11 * SOL, SOL / 400));
+}
+
+#[test]
+fn rebates_round_down() {
+    let mut volume = volume();
+    assert_eq!(volume.record(1_000, 3, 2_500).unwrap(), 0);
+    assert_eq!(volume.record(1_000, 7, 2_500).unwrap(), 1);
+    assert_eq!((volume.volume, volume.rebated), (2_000, 1));
+}
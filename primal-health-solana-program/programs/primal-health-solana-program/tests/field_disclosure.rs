@@ -0,0 +1,76 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::disclosure::{
+    field_leaf, merkle_proof, merkle_root, root_from_proof,
+};
+use primal_health_solana_program::FieldCommitment;
+
+const FIELDS: [(&str, &str); 5] = [
+    ("Patient.birthDate", "\"1984-03-09\""),
+    ("Patient.extension.bloodType", "\"O-\""),
+    ("Patient.gender", "\"female\""),
+    ("Observation.code", "{\"coding\":[{\"code\":\"2345-7\"}]}"),
+    ("Observation.valueQuantity.value", "95"),
+];
+
+fn leaves() -> Vec<[u8; 32]> {
+    FIELDS
+        .iter()
+        .map(|(path, value)| field_leaf(path, value))
+        .collect()
+}
+
+fn commitment(root: [u8; 32]) -> FieldCommitment {
+    FieldCommitment {
+        record: Pubkey::new_unique(),
+        root,
+        field_count: FIELDS.len() as u32,
+        committer: Pubkey::new_unique(),
+        committed_at: 0,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn every_field_proves_against_the_root() {
+    let leaves = leaves();
+    let root = merkle_root(&leaves);
+    let depth = commitment(root).depth();
+
+    for (index, (path, value)) in FIELDS.iter().enumerate() {
+        let proof = merkle_proof(&leaves, index);
+        assert_eq!(proof.len(), depth);
+        assert_eq!(
+            root_from_proof(field_leaf(path, value), index as u32, &proof),
+            root
+        );
+    }
+}
+
+#[test]
+fn altered_disclosures_do_not_prove() {
+    let leaves = leaves();
+    let root = merkle_root(&leaves);
+    let proof = merkle_proof(&leaves, 1);
+
+    let (path, _) = FIELDS[1];
+    assert_ne!(
+        root_from_proof(field_leaf(path, "\"AB+\""), 1, &proof),
+        root
+    );
+    assert_ne!(
+        root_from_proof(field_leaf("Patient.gender", "\"O-\""), 1, &proof),
+        root
+    );
+    assert_ne!(root_from_proof(leaves[1], 2, &proof), root);
+    // The path length is hashed, so path and value cannot trade bytes
+    assert_ne!(field_leaf("ab", "c"), field_leaf("a", "bc"));
+}
+
+#[test]
+fn proof_depth_follows_the_padded_field_count() {
+    let mut commitment = commitment([0; 32]);
+    for (field_count, depth) in [(1, 0), (2, 1), (3, 2), (4, 2), (5, 3), (1 << 16, 16)] {
+        commitment.field_count = field_count;
+        assert_eq!(commitment.depth(), depth, "{field_count} fields");
+    }
+}
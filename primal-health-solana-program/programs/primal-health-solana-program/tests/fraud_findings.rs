@@ -0,0 +1,157 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimDispute, ErrorCode, FraudFinding, ProviderClaimStats, ProviderStake, STAKE_UNBONDING_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const SOL: u64 = 1_000_000_000;
+
+fn stake(staked: u64) -> ProviderStake {
+    let mut stake = ProviderStake {
+        provider: Pubkey::new_unique(),
+        staked: 0,
+        unbonding: 0,
+        unbonding_at: 0,
+        slashed: 0,
+        account_version: 0,
+    };
+    stake.stake(staked).unwrap();
+    stake
+}
+
+fn resolved_dispute(patient_prevailed: bool) -> ClaimDispute {
+    ClaimDispute {
+        claim: Pubkey::new_unique(),
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        arbiter: Pubkey::new_unique(),
+        reason_hash: [2; 32],
+        opened_at: NOW - 86_400,
+        deadline: NOW + 86_400,
+        resolved_at: NOW - 60,
+        patient_prevailed,
+        bond: 0,
+        account_version: 1,
+    }
+}
+
+fn unruled() -> FraudFinding {
+    FraudFinding {
+        claim: Pubkey::default(),
+        provider: Pubkey::default(),
+        patient: Pubkey::default(),
+        arbiter: Pubkey::default(),
+        slashed: 0,
+        ruled_at: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn unstaked_collateral_waits_out_the_unbonding_period() {
+    let mut stake = stake(5 * SOL);
+    stake.stake(SOL).unwrap();
+    assert_eq!((stake.staked, stake.account_version), (6 * SOL, 1));
+
+    expect_error(stake.unstake(7 * SOL, NOW), ErrorCode::InsufficientStake);
+    expect_error(stake.unstake(0, NOW), ErrorCode::InsufficientStake);
+    expect_error(stake.withdraw(NOW), ErrorCode::NothingToWithdraw);
+
+    stake.unstake(2 * SOL, NOW).unwrap();
+    assert_eq!((stake.staked, stake.unbonding), (4 * SOL, 2 * SOL));
+    assert_eq!(stake.unbonding_at, NOW + STAKE_UNBONDING_SECS);
+
+    // Unbonding more restarts the wait for all of it
+    stake.unstake(SOL, NOW + 60).unwrap();
+    expect_error(
+        stake.withdraw(NOW + STAKE_UNBONDING_SECS),
+        ErrorCode::StakeStillUnbonding,
+    );
+    assert_eq!(
+        stake.withdraw(NOW + 60 + STAKE_UNBONDING_SECS).unwrap(),
+        3 * SOL
+    );
+    assert_eq!((stake.staked, stake.unbonding), (3 * SOL, 0));
+}
+
+#[test]
+fn slashing_takes_the_stake_before_what_is_unbonding() {
+    let mut stake = stake(3 * SOL);
+    stake.unstake(SOL, NOW).unwrap();
+
+    assert_eq!(stake.slash(SOL).unwrap(), SOL);
+    assert_eq!((stake.staked, stake.unbonding), (SOL, SOL));
+    assert_eq!(stake.slash(3 * SOL / 2).unwrap(), 3 * SOL / 2);
+    assert_eq!((stake.staked, stake.unbonding), (0, SOL / 2));
+
+    // Never more than is left
+    assert_eq!(stake.slash(10 * SOL).unwrap(), SOL / 2);
+    assert_eq!(stake.slash(SOL).unwrap(), 0);
+    assert_eq!(
+        (stake.staked, stake.unbonding, stake.slashed),
+        (0, 0, 3 * SOL)
+    );
+}
+
+#[test]
+fn only_the_arbiter_rules_fraud_once_the_patient_prevails() {
+    let dispute = resolved_dispute(true);
+    let mut finding = unruled();
+    expect_error(
+        finding.rule(&dispute, Pubkey::new_unique(), NOW),
+        ErrorCode::Unauthorized,
+    );
+    let rejected = resolved_dispute(false);
+    expect_error(
+        finding.rule(&rejected, rejected.arbiter, NOW),
+        ErrorCode::DisputeNotUpheld,
+    );
+    let open = ClaimDispute {
+        resolved_at: 0,
+        ..resolved_dispute(true)
+    };
+    expect_error(
+        finding.rule(&open, open.arbiter, NOW),
+        ErrorCode::DisputeNotUpheld,
+    );
+
+    finding.rule(&dispute, dispute.arbiter, NOW).unwrap();
+    assert_eq!(
+        (
+            finding.claim,
+            finding.provider,
+            finding.patient,
+            finding.arbiter
+        ),
+        (
+            dispute.claim,
+            dispute.provider,
+            dispute.patient,
+            dispute.arbiter
+        )
+    );
+    assert_eq!((finding.ruled_at, finding.account_version), (NOW, 1));
+}
+
+#[test]
+fn fraud_findings_count_against_the_provider() {
+    let provider = Pubkey::new_unique();
+    let mut stats = ProviderClaimStats {
+        provider: Pubkey::default(),
+        day: 0,
+        claims_today: 0,
+        total_claims: 0,
+        duplicate_code_claims: 0,
+        fraud_findings: 0,
+        account_version: 0,
+    };
+    stats.record_fraud_finding(provider).unwrap();
+    stats.record_fraud_finding(provider).unwrap();
+    assert_eq!(
+        (stats.provider, stats.fraud_findings, stats.account_version),
+        (provider, 2, 2)
+    );
+}
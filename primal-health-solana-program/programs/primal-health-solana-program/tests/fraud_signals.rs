@@ -0,0 +1,87 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{
+    FraudSignalKind, ProviderClaimStats, FRAUD_MAX_CLAIMS_PER_DAY, FRAUD_MAX_DUPLICATE_CODE_BPS,
+    FRAUD_MIN_CLAIM_SAMPLE,
+};
+
+const DAY: i64 = 24 * 60 * 60;
+const NOW: i64 = 1_700_000_000;
+
+fn stats() -> ProviderClaimStats {
+    ProviderClaimStats {
+        provider: Pubkey::new_unique(),
+        day: 0,
+        claims_today: 0,
+        total_claims: 0,
+        duplicate_code_claims: 0,
+        fraud_findings: 0,
+        account_version: 1,
+    }
+}
+
+fn kinds(signals: Vec<(FraudSignalKind, u64, u64)>) -> Vec<FraudSignalKind> {
+    signals.into_iter().map(|(kind, _, _)| kind).collect()
+}
+
+#[test]
+fn claims_per_day_signal_fires_once_when_crossed() {
+    let mut stats = stats();
+    for _ in 0..FRAUD_MAX_CLAIMS_PER_DAY {
+        assert!(stats.record_claim(NOW, false).is_empty());
+    }
+    assert_eq!(
+        stats.record_claim(NOW, false),
+        vec![(
+            FraudSignalKind::ClaimsPerDay,
+            FRAUD_MAX_CLAIMS_PER_DAY as u64 + 1,
+            FRAUD_MAX_CLAIMS_PER_DAY as u64
+        )]
+    );
+    assert!(stats.record_claim(NOW, false).is_empty());
+}
+
+#[test]
+fn daily_count_resets_on_a_new_utc_day() {
+    let mut stats = stats();
+    for _ in 0..FRAUD_MAX_CLAIMS_PER_DAY {
+        stats.record_claim(NOW, false);
+    }
+    assert!(stats.record_claim(NOW + DAY, false).is_empty());
+    assert_eq!(stats.claims_today, 1);
+    assert_eq!(stats.total_claims, FRAUD_MAX_CLAIMS_PER_DAY as u64 + 1);
+}
+
+#[test]
+fn duplicate_code_share_is_not_judged_below_the_sample_size() {
+    let mut stats = stats();
+    for n in 0..FRAUD_MIN_CLAIM_SAMPLE - 1 {
+        assert!(stats.record_claim(NOW + n as i64 * DAY, true).is_empty());
+    }
+    assert_eq!(stats.duplicate_code_bps(), 0);
+    assert_eq!(
+        kinds(stats.record_claim(NOW + DAY * 100, true)),
+        vec![FraudSignalKind::DuplicateCodes]
+    );
+}
+
+#[test]
+fn duplicate_code_signal_fires_again_only_after_dropping_below() {
+    let mut stats = stats();
+    stats.total_claims = FRAUD_MIN_CLAIM_SAMPLE * 10;
+    stats.duplicate_code_claims = stats.total_claims * FRAUD_MAX_DUPLICATE_CODE_BPS / 10_000;
+    assert_eq!(stats.duplicate_code_bps(), FRAUD_MAX_DUPLICATE_CODE_BPS);
+
+    assert_eq!(
+        kinds(stats.record_claim(NOW, true)),
+        vec![FraudSignalKind::DuplicateCodes]
+    );
+    assert!(stats.record_claim(NOW + DAY, true).is_empty());
+
+    while stats.duplicate_code_bps() > FRAUD_MAX_DUPLICATE_CODE_BPS {
+        assert!(stats.record_claim(NOW + 2 * DAY, false).is_empty());
+    }
+    assert_eq!(
+        kinds(stats.record_claim(NOW + 3 * DAY, true)),
+        vec![FraudSignalKind::DuplicateCodes]
+    );
+}
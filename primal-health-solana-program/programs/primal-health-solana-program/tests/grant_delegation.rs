@@ -0,0 +1,257 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    AccessGrant, ErrorCode, LawfulBasis, GRANT_SCOPE_ALL, GRANT_SCOPE_DELEGATE, GRANT_SCOPE_READ,
+    GRANT_SCOPE_WRITE,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn empty() -> AccessGrant {
+    AccessGrant {
+        patient: Pubkey::default(),
+        grantee: Pubkey::default(),
+        scope: 0,
+        granted_at: 0,
+        expires_at: 0,
+        revoked: false,
+        lawful_basis: LawfulBasis::Consent,
+        parent: Pubkey::default(),
+        depth: 0,
+        delegations: 0,
+        account_version: 0,
+    }
+}
+
+/// The attending physician's grant from the patient.
+fn attending(scope: u8, expires_at: i64) -> AccessGrant {
+    AccessGrant {
+        patient: Pubkey::new_unique(),
+        grantee: Pubkey::new_unique(),
+        scope,
+        granted_at: NOW,
+        expires_at,
+        lawful_basis: LawfulBasis::VitalInterests,
+        account_version: 2,
+        ..empty()
+    }
+}
+
+fn delegate(
+    parent: &mut AccessGrant,
+    address: Pubkey,
+    scope: u8,
+    expires_at: i64,
+) -> anchor_lang::Result<AccessGrant> {
+    let mut grant = empty();
+    parent.delegate(
+        address,
+        &mut grant,
+        Pubkey::new_unique(),
+        scope,
+        expires_at,
+        NOW,
+    )?;
+    Ok(grant)
+}
+
+#[test]
+fn delegated_grants_record_the_chain() {
+    let root_key = Pubkey::new_unique();
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, NOW + 1_000);
+    let mut resident = delegate(
+        &mut root,
+        root_key,
+        GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE,
+        0,
+    )
+    .unwrap();
+    assert_eq!((resident.parent, resident.depth), (root_key, 1));
+    assert_eq!(resident.patient, root.patient);
+    assert_eq!(resident.lawful_basis, LawfulBasis::VitalInterests);
+    assert_eq!(resident.expires_at, NOW + 1_000);
+    assert!(resident.allows(GRANT_SCOPE_READ, NOW));
+    assert_eq!(root.delegations, 1);
+
+    let resident_key = Pubkey::new_unique();
+    let nurse = delegate(&mut resident, resident_key, GRANT_SCOPE_READ, NOW + 500).unwrap();
+    assert_eq!((nurse.parent, nurse.depth), (resident_key, 2));
+    assert_eq!(resident.delegations, 1);
+}
+
+#[test]
+fn chains_stop_at_the_depth_limit() {
+    let mut root = attending(GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE, 0);
+    // The last link in the chain cannot carry delegation on
+    expect_error(
+        delegate(
+            &mut delegate(
+                &mut root,
+                Pubkey::new_unique(),
+                GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE,
+                0,
+            )
+            .unwrap(),
+            Pubkey::new_unique(),
+            GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE,
+            0,
+        ),
+        ErrorCode::InvalidDelegationScope,
+    );
+
+    let mut deepest = attending(GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE, 0);
+    deepest.depth = 2;
+    expect_error(
+        delegate(&mut deepest, Pubkey::new_unique(), GRANT_SCOPE_READ, 0),
+        ErrorCode::DelegationTooDeep,
+    );
+}
+
+#[test]
+fn sub_grants_stay_within_the_parent() {
+    let mut plain = attending(GRANT_SCOPE_ALL, 0);
+    expect_error(
+        delegate(&mut plain, Pubkey::new_unique(), GRANT_SCOPE_READ, 0),
+        ErrorCode::GrantNotDelegable,
+    );
+
+    let mut root = attending(GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE, NOW + 1_000);
+    let address = Pubkey::new_unique();
+    expect_error(
+        delegate(&mut root, address, GRANT_SCOPE_WRITE, 0),
+        ErrorCode::InvalidDelegationScope,
+    );
+    expect_error(
+        delegate(&mut root, address, GRANT_SCOPE_DELEGATE, 0),
+        ErrorCode::InvalidDelegationScope,
+    );
+    expect_error(
+        delegate(&mut root, address, GRANT_SCOPE_READ, NOW + 1_001),
+        ErrorCode::InvalidGrantExpiry,
+    );
+    expect_error(
+        delegate(&mut root, address, GRANT_SCOPE_READ, NOW),
+        ErrorCode::InvalidGrantExpiry,
+    );
+
+    root.revoked = true;
+    expect_error(
+        delegate(&mut root, address, GRANT_SCOPE_READ, 0),
+        ErrorCode::GrantInactive,
+    );
+    assert_eq!(root.delegations, 0);
+}
+
+#[test]
+fn delegating_again_replaces_the_sub_grant() {
+    let address = Pubkey::new_unique();
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    let mut grant = delegate(&mut root, address, GRANT_SCOPE_ALL, 0).unwrap();
+    let grantee = grant.grantee;
+    root.delegate(address, &mut grant, grantee, GRANT_SCOPE_READ, 0, NOW)
+        .unwrap();
+    assert_eq!(grant.scope, GRANT_SCOPE_READ);
+    assert_eq!(root.delegations, 1);
+
+    // Another grantee's delegator cannot take the grant over
+    let mut other = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    expect_error(
+        other.delegate(
+            Pubkey::new_unique(),
+            &mut grant,
+            grantee,
+            GRANT_SCOPE_READ,
+            0,
+            NOW,
+        ),
+        ErrorCode::GrantAlreadyHeld,
+    );
+    expect_error(grant.expect_replaceable(), ErrorCode::GrantAlreadyHeld);
+}
+
+#[test]
+fn revocation_propagates_down_the_chain() {
+    let (root_key, resident_key, nurse_key) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    let mut resident = delegate(
+        &mut root,
+        root_key,
+        GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE,
+        0,
+    )
+    .unwrap();
+    let nurse = delegate(&mut resident, resident_key, GRANT_SCOPE_READ, 0).unwrap();
+
+    let mut descendants = vec![(resident_key, resident), (nurse_key, nurse)];
+    root.revoke_chain(root_key, None, &mut descendants).unwrap();
+    assert!(root.revoked && descendants.iter().all(|(_, grant)| grant.revoked));
+    assert_eq!(root.delegations, 0);
+    assert!(!descendants[1].1.is_active(NOW));
+
+    // Children must follow their parent, and none may be left out
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    let mut resident = delegate(
+        &mut root,
+        root_key,
+        GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE,
+        0,
+    )
+    .unwrap();
+    let nurse = delegate(&mut resident, resident_key, GRANT_SCOPE_READ, 0).unwrap();
+    expect_error(
+        root.clone().revoke_chain(
+            root_key,
+            None,
+            &mut [(nurse_key, nurse), (resident_key, resident.clone())],
+        ),
+        ErrorCode::GrantChainMismatch,
+    );
+    expect_error(
+        root.revoke_chain(root_key, None, &mut [(resident_key, resident)]),
+        ErrorCode::DelegationsNotRevoked,
+    );
+}
+
+#[test]
+fn revoking_a_sub_grant_frees_its_slot() {
+    let (root_key, resident_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    let mut resident = delegate(&mut root, root_key, GRANT_SCOPE_READ, 0).unwrap();
+
+    expect_error(
+        resident.clone().revoke_chain(resident_key, None, &mut []),
+        ErrorCode::GrantChainMismatch,
+    );
+    resident
+        .revoke_chain(resident_key, Some(&mut root), &mut [])
+        .unwrap();
+    assert_eq!(root.delegations, 0);
+    // Revoking twice does not free the slot again
+    resident
+        .revoke_chain(resident_key, Some(&mut root), &mut [])
+        .unwrap();
+    assert_eq!(root.delegations, 0);
+
+    // The revoked grant may be delegated again, or replaced by the patient
+    resident.expect_replaceable().unwrap();
+    let grantee = resident.grantee;
+    root.delegate(root_key, &mut resident, grantee, GRANT_SCOPE_READ, 0, NOW)
+        .unwrap();
+    assert_eq!(root.delegations, 1);
+}
+
+#[test]
+fn unupgraded_grants_cannot_delegate() {
+    let mut root = attending(GRANT_SCOPE_ALL | GRANT_SCOPE_DELEGATE, 0);
+    root.account_version = 1;
+    expect_error(
+        delegate(&mut root, Pubkey::new_unique(), GRANT_SCOPE_READ, 0),
+        ErrorCode::AccountNotUpgraded,
+    );
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{ErrorCode, HealthDataIndex};
+
+fn empty_index() -> HealthDataIndex {
+    HealthDataIndex {
+        owner: Pubkey::default(),
+        record_count: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn records_are_appended_at_consecutive_indices() {
+    let owner = Pubkey::new_unique();
+    let mut index = empty_index();
+    for record_index in 0..3 {
+        index.append(owner, record_index).unwrap();
+    }
+    assert_eq!(index.owner, owner);
+    assert_eq!(index.record_count, 3);
+    assert_eq!(index.account_version, 1);
+}
+
+#[test]
+fn only_the_next_index_can_be_taken() {
+    let owner = Pubkey::new_unique();
+    let mut index = empty_index();
+    expect_error(index.append(owner, 1), ErrorCode::RecordIndexMismatch);
+    index.append(owner, 0).unwrap();
+
+    // A second author who read the count before the first append
+    expect_error(index.append(owner, 0), ErrorCode::RecordIndexMismatch);
+    assert_eq!(index.record_count, 1);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimEscrow, ClaimStatus, ErrorCode, InsurerAccount, LineItemStatus, MAX_NAME_LEN,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn insured_claim(amount: u64) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "insured".to_string(),
+        status: ClaimStatus::Verified,
+        verified_at: NOW,
+        service_start: NOW,
+        service_end: NOW,
+        insurer: Pubkey::new_unique(),
+        ..common::claim(vec![common::line(amount, LineItemStatus::Approved)])
+    }
+}
+
+#[test]
+fn insured_claims_wait_for_the_insurer() {
+    let mut claim = insured_claim(5_000);
+    expect_error(claim.settle_payment(NOW), ErrorCode::InsurerApprovalPending);
+    expect_error(
+        claim.settle_partial_payment(1_000, NOW),
+        ErrorCode::InsurerApprovalPending,
+    );
+    let escrow = ClaimEscrow {
+        claim: Pubkey::new_unique(),
+        provider: claim.provider,
+        amount: 5_000,
+        funded_at: NOW,
+        arbiter: Pubkey::default(),
+        account_version: 2,
+    };
+    expect_error(escrow.settle(&mut claim), ErrorCode::InsurerApprovalPending);
+    assert_eq!(claim.principal_paid, 0);
+
+    claim.adjudicate(true, NOW + 60).unwrap();
+    assert_eq!(claim.insurer_approved_at, NOW + 60);
+    assert_eq!(claim.settle_payment(NOW + 60).unwrap(), 5_000);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+}
+
+#[test]
+fn claims_without_an_insurer_pay_on_verification() {
+    let mut claim = insured_claim(5_000);
+    claim.insurer = Pubkey::default();
+    assert_eq!(claim.settle_payment(NOW).unwrap(), 5_000);
+}
+
+#[test]
+fn the_insurer_adjudicates_verified_claims_once() {
+    let mut claim = insured_claim(5_000);
+    claim.status = ClaimStatus::UnderReview;
+    expect_error(claim.adjudicate(true, NOW), ErrorCode::ClaimNotVerified);
+
+    claim.status = ClaimStatus::Verified;
+    claim.adjudicate(true, NOW).unwrap();
+    expect_error(claim.adjudicate(true, NOW), ErrorCode::AlreadyApproved);
+    expect_error(claim.adjudicate(false, NOW), ErrorCode::AlreadyApproved);
+    assert_eq!(claim.status, ClaimStatus::Verified);
+}
+
+#[test]
+fn a_denial_rejects_the_claim_for_reopening() {
+    let mut claim = insured_claim(5_000);
+    claim.adjudicate(false, NOW).unwrap();
+    assert_eq!(claim.status, ClaimStatus::Rejected);
+    assert_eq!((claim.rejected_at, claim.insurer_approved_at), (NOW, 0));
+    expect_error(claim.settle_payment(NOW), ErrorCode::ClaimNotVerified);
+
+    // The patient can contest the denial like any rejection
+    claim.reopen(vec![[7; 32]], NOW + 60).unwrap();
+    assert_eq!(claim.status, ClaimStatus::UnderReview);
+}
+
+#[test]
+fn unupgraded_claims_cannot_be_adjudicated() {
+    let mut claim = insured_claim(5_000);
+    claim.account_version = 7;
+    expect_error(claim.adjudicate(true, NOW), ErrorCode::AccountNotUpgraded);
+}
+
+#[test]
+fn insurers_register_like_providers() {
+    let mut insurer = InsurerAccount {
+        authority: Pubkey::new_unique(),
+        did: String::new(),
+        name: String::new(),
+        account_version: 1,
+    };
+    expect_error(
+        insurer.update("did:sol:insurer".to_string(), "n".repeat(MAX_NAME_LEN + 1)),
+        ErrorCode::NameTooLong,
+    );
+    insurer
+        .update("did:sol:insurer".to_string(), "Mutual".to_string())
+        .unwrap();
+    assert_eq!(
+        InsurerAccount::space(&insurer.did, &insurer.name),
+        8 + 32 + 4 + 15 + 4 + 6 + 1 + 64
+    );
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{EncryptionKey, KeyEnvelope};
+
+fn grantee_key(x25519_key: [u8; 32]) -> EncryptionKey {
+    EncryptionKey {
+        owner: Pubkey::new_unique(),
+        x25519_key,
+        updated_at: 1_700_000_000,
+        key_version: 1,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn envelopes_go_stale_when_the_grantee_rotates_keys() {
+    let mut key = grantee_key([1; 32]);
+    let envelope = KeyEnvelope {
+        grant: Pubkey::new_unique(),
+        recipient_key: key.x25519_key,
+        envelope: vec![0xee; 80],
+        updated_at: 1_700_000_000,
+        account_version: 1,
+    };
+    assert!(envelope.is_current(&key));
+
+    key.rotate([2; 32], 1_700_000_100).unwrap();
+    assert!(!envelope.is_current(&key));
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use primal_health_solana_program::{
+    ClaimAccount, ClaimStatus, LineDecision, LineItemStatus, LATE_INTEREST_BPS_PER_YEAR,
+    PAYMENT_WINDOW_SECS, SECS_PER_YEAR,
+};
+
+const VERIFIED_AT: i64 = 1_700_000_000;
+const PRINCIPAL: u64 = 1_000_000_000;
+
+fn verified_claim() -> ClaimAccount {
+    let mut claim = ClaimAccount {
+        claim_id: "late".to_string(),
+        timestamp: VERIFIED_AT - 60,
+        ..common::claim(vec![common::line(PRINCIPAL, LineItemStatus::Pending)])
+    };
+    claim.accept().unwrap();
+    claim.adjudicate_line(0, LineDecision::Approve, 0).unwrap();
+    claim.verify(ClaimStatus::Verified, VERIFIED_AT).unwrap();
+    claim
+}
+
+#[test]
+fn no_interest_within_the_payment_window() {
+    let mut claim = verified_claim();
+    assert_eq!(claim.verified_at, VERIFIED_AT);
+
+    let amount = claim
+        .settle_payment(VERIFIED_AT + PAYMENT_WINDOW_SECS)
+        .unwrap();
+    assert_eq!(amount, PRINCIPAL);
+    assert_eq!((claim.principal_paid, claim.interest_paid), (PRINCIPAL, 0));
+}
+
+#[test]
+fn overdue_payment_owes_simple_interest_from_the_window_end() {
+    let mut claim = verified_claim();
+    let paid_at = VERIFIED_AT + PAYMENT_WINDOW_SECS + SECS_PER_YEAR / 2;
+
+    let amount = claim.settle_payment(paid_at).unwrap();
+    let interest = PRINCIPAL * LATE_INTEREST_BPS_PER_YEAR / 10_000 / 2;
+    assert_eq!(claim.interest_paid, interest);
+    assert_eq!(claim.principal_paid, PRINCIPAL);
+    assert_eq!(amount, PRINCIPAL + interest);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+}
+
+#[test]
+fn interest_rounds_down_to_whole_lamports() {
+    let claim = verified_claim();
+    let window_end = VERIFIED_AT + PAYMENT_WINDOW_SECS;
+
+    assert_eq!(
+        claim.interest_due(1, window_end + SECS_PER_YEAR).unwrap(),
+        0
+    );
+    assert_eq!(claim.interest_due(PRINCIPAL, window_end + 1).unwrap(), 3);
+}
@@ -0,0 +1,1279 @@
+//! Byte-layout snapshots of every account and event type.
+//!
+//! Each type is serialized from a fixed sample and compared with
+//! `tests/snapshots/layout_v<LAYOUT_VERSION>.txt`. A changed layout fails the
+//! test until `LAYOUT_VERSION` is bumped and a snapshot for the new version is
+//! recorded with `UPDATE_LAYOUT_SNAPSHOTS=1 cargo test --test layout_snapshots`.
+//! Recording never overwrites an existing entry; it only adds types that are
+//! missing from the snapshot.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Discriminator, Event};
+use primal_health_solana_program::clock::ClockOffset;
+use primal_health_solana_program::{
+    AccessGrant, AccessPolicy, Appointment, AppointmentBilled, AppointmentService,
+    AppointmentStatus, ApprovalPolicy, AuditAction, AuditEntry, AuditTrail, AutoApproval, CareTeam,
+    ClaimAccount, ClaimCreated, ClaimDedup, ClaimDispute, ClaimDisputed, ClaimEscrow, ClaimExpired,
+    ClaimGrant, ClaimLineItem, ClaimNote, ClaimNotesPage, ClaimPaid, ClaimRejected, ClaimStatus,
+    ClaimVerified, ClawbackConfig, Config, ConsentAccount, ConsentAction, ConsentLedger,
+    ConsentReceipt, CrankRewarded, CrankVault, DataAccessOffer, DataCategory, DataValidator,
+    Delegate, DependentLink, DeviceAccount, DeviceRollup, DisputeConfig, DisputeResolved,
+    EmergencyAccess, EmergencyAccessRequested, EmergencyAccessVetoed, EncryptionKey,
+    EncryptionKeyUpdated, FacilitatorAccount, FacilitatorUpdated, FeatureFlags, FeeRebated,
+    FeeRebates, FeeSchedule, FeeScheduleEntry, FieldCommitment, FraudFinding, FraudRuled,
+    FraudSignal, FraudSignalKind, HealthDataAccount, HealthDataAccountV2, HealthDataBatchAccount,
+    HealthDataIndex, HealthDataSubmitted, HealthRecordEntry, InstallmentPaid, InsurerAccount,
+    InsurerApproved, KeyEnvelope, LawfulBasis, LineItemStatus, MintTreasury, OutOfPocket,
+    OutOfPocketMaxReached, PatientAccount, PatientRegistered, PaymentAttributed, PaymentClawedBack,
+    PaymentHold, PaymentSchedule, PlanBenefits, PlanSubscription, PolicyEffect, PolicyRule,
+    PolicySubject, PremiumPaid, PremiumPayment, PriceTier, ProtocolTreasury, ProviderAccount,
+    ProviderClaimStats, ProviderCredential, ProviderStake, ProviderVolume, QualityAttestation,
+    ReadingSummary, RebateTier, ReencryptionFulfilled, ReencryptionRequest, ReencryptionRequested,
+    RegistryAuthority, ResearchConsent, SecondOpinion, SecondOpinionGiven, SettlementSummary,
+    SponsorVault, StorageKind, Subscription, SubscriptionPlan, Tenant, TenantMember, TenantRole,
+    ValidatorRegistry, CATEGORY_ALL, CONSENT_SCOPE_READ, CONSENT_SCOPE_RESEARCH,
+    DELEGATE_PERMISSIONS_ALL, FEATURE_SUBSCRIPTIONS, FEATURE_TENANTS, GRANT_SCOPE_READ,
+    GRANT_SCOPE_WRITE, GUARDIAN_PERMISSIONS_ALL, LAYOUT_VERSION,
+};
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+fn account_bytes(account: &impl AccountSerialize) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn zero_copy_bytes<T: bytemuck::Pod + Discriminator>(account: &T) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(account));
+    data
+}
+
+fn samples() -> BTreeMap<&'static str, Vec<u8>> {
+    let mut samples = BTreeMap::new();
+    samples.insert(
+        "PatientAccount",
+        account_bytes(&PatientAccount {
+            authority: key(1),
+            did: "did:sol:patient".into(),
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "InsurerAccount",
+        account_bytes(&InsurerAccount {
+            authority: key(22),
+            did: "did:sol:insurer".into(),
+            name: "Mutual".into(),
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ProviderAccount",
+        account_bytes(&ProviderAccount {
+            authority: key(2),
+            did: "did:sol:provider".into(),
+            name: "General".into(),
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "HealthDataAccount",
+        account_bytes(&HealthDataAccount {
+            owner: key(1),
+            data_hash: [0xd1; 32],
+            storage: StorageKind::Arweave,
+            cid: "ar-tx".into(),
+            commitment: [0xd2; 32],
+            timestamp: 1_700_000_000,
+            author: key(2),
+            derived_from: [0xd0; 32],
+            key_version: 2,
+            account_version: 4,
+        }),
+    );
+    samples.insert(
+        "FieldCommitment",
+        account_bytes(&FieldCommitment {
+            record: key(4),
+            root: [5; 32],
+            field_count: 12,
+            committer: key(1),
+            committed_at: 1_700_000_005,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "AccessPolicy",
+        account_bytes(&AccessPolicy {
+            patient: key(1),
+            rules: vec![
+                PolicyRule {
+                    subject: PolicySubject::RegisteredProvider,
+                    categories: DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+                    scope: GRANT_SCOPE_READ,
+                    effect: PolicyEffect::Allow,
+                },
+                PolicyRule {
+                    subject: PolicySubject::Anyone,
+                    categories: CATEGORY_ALL,
+                    scope: GRANT_SCOPE_READ,
+                    effect: PolicyEffect::RequireGrant,
+                },
+            ],
+            updated_at: 1_700_000_006,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ConsentAccount",
+        account_bytes(&ConsentAccount {
+            patient: key(1),
+            provider: key(2),
+            record: key(3),
+            data_hash: [0xab; 32],
+            scope: CONSENT_SCOPE_READ | CONSENT_SCOPE_RESEARCH,
+            granted_at: 1_700_000_000,
+            expires_at: 1_700_086_400,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "ReencryptionRequest",
+        account_bytes(&ReencryptionRequest {
+            consent: key(28),
+            patient: key(1),
+            provider: key(2),
+            record: key(3),
+            record_key_version: 2,
+            recipient_key: [9; 32],
+            recipient_key_version: 1,
+            wrapped_key: vec![0xcd; 72],
+            requested_at: 1_700_000_000,
+            fulfilled_at: 1_700_003_600,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "DataAccessOffer",
+        account_bytes(&DataAccessOffer {
+            owner: key(1),
+            offer_id: "lab-2024".to_string(),
+            resource_hash: [0xab; 32],
+            price: 5_000,
+            access_secs: 86_400,
+            sales: 2,
+            created_at: 1_700_000_000,
+            price_tiers: vec![PriceTier {
+                max_age_secs: 2_592_000,
+                price: 8_000,
+            }],
+            account_version: 3,
+        }),
+    );
+    samples.insert(
+        "AccessGrant",
+        account_bytes(&AccessGrant {
+            patient: key(1),
+            grantee: key(2),
+            scope: GRANT_SCOPE_WRITE,
+            granted_at: 1_700_000_000,
+            expires_at: 1_800_000_000,
+            revoked: false,
+            lawful_basis: LawfulBasis::Contract,
+            parent: key(3),
+            depth: 1,
+            delegations: 0,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "DependentLink",
+        account_bytes(&DependentLink {
+            guardian: key(1),
+            dependent: key(8),
+            permissions: GUARDIAN_PERMISSIONS_ALL,
+            linked_at: 1_700_000_008,
+            expires_at: 2_000_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "Delegate",
+        account_bytes(&Delegate {
+            patient: key(1),
+            delegate: key(28),
+            permissions: DELEGATE_PERMISSIONS_ALL,
+            updated_at: 1_700_000_009,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "EncryptionKey",
+        account_bytes(&EncryptionKey {
+            owner: key(2),
+            x25519_key: [9; 32],
+            updated_at: 1_700_000_009,
+            key_version: 2,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "KeyEnvelope",
+        account_bytes(&KeyEnvelope {
+            grant: key(10),
+            recipient_key: [9; 32],
+            envelope: vec![11; 80],
+            updated_at: 1_700_000_010,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ValidatorRegistry",
+        account_bytes(&ValidatorRegistry {
+            admin: key(12),
+            validator_count: 2,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "DataValidator",
+        account_bytes(&DataValidator {
+            authority: key(13),
+            active: true,
+            designated_at: 1_700_000_011,
+            attestation_count: 40,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "QualityAttestation",
+        account_bytes(&QualityAttestation {
+            record: key(4),
+            validator: key(13),
+            profile_hash: [14; 32],
+            score: 87,
+            attested_at: 1_700_000_012,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "Subscription",
+        account_bytes(&Subscription {
+            patient: key(1),
+            subscriber: key(15),
+            category: DataCategory::LabResult,
+            price_per_record: 1_000_000,
+            deposited: 10_000_000,
+            accrued: 3_000_000,
+            withdrawn: 2_000_000,
+            delivered: 3,
+            started_at: 1_700_000_013,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "SubscriptionPlan",
+        account_bytes(&SubscriptionPlan {
+            owner: key(1),
+            plan_id: "research-monthly".to_string(),
+            price_per_period: 50_000_000,
+            period_secs: 2_592_000,
+            categories: DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+            scope: GRANT_SCOPE_READ,
+            periods_sold: 4,
+            created_at: 1_700_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "PlanSubscription",
+        account_bytes(&PlanSubscription {
+            plan: key(16),
+            owner: key(1),
+            subscriber: key(15),
+            categories: DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+            scope: GRANT_SCOPE_READ,
+            current_period_end: 1_705_184_000,
+            periods_paid: 2,
+            started_at: 1_700_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "SettlementSummary",
+        account_bytes(&SettlementSummary {
+            payer: key(2),
+            payee: key(1),
+            period: 202410,
+            claims_paid: 4,
+            amount_paid: 1_200,
+            fees: 0,
+            refunds: 0,
+            disputes: 1,
+            closed: true,
+            closed_at: 1_730_419_200,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "SponsorVault",
+        account_bytes(&SponsorVault {
+            sponsor: key(2),
+            total_funded: 5_000_000_000,
+            total_spent: 3_000_000,
+            sponsored_accounts: 2,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "FeatureFlags",
+        account_bytes(&FeatureFlags {
+            admin: key(19),
+            flags: FEATURE_SUBSCRIPTIONS | FEATURE_TENANTS,
+            updated_at: 1_700_000_019,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "Config",
+        account_bytes(&Config {
+            admin: key(19),
+            paused: true,
+            fee_bps: 25,
+            updated_at: 1_700_000_019,
+            claim_ttl_secs: 7_776_000,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "ProtocolTreasury",
+        account_bytes(&ProtocolTreasury {
+            total_collected: 12_500,
+            total_withdrawn: 2_500,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "MintTreasury",
+        account_bytes(&MintTreasury {
+            mint: key(17),
+            decimals: 6,
+            vault: key(18),
+            destination: key(19),
+            total_collected: 4_000_000,
+            total_swept: 1_500_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "FacilitatorAccount",
+        account_bytes(&FacilitatorAccount {
+            authority: key(25),
+            signing_key: key(26),
+            fee_share_bps: 2_000,
+            revoked: false,
+            approved_at: 1_700_000_019,
+            key_rotated_at: 1_700_050_000,
+            settlements: 3,
+            total_earned: 7_500,
+            total_withdrawn: 2_500,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "CrankVault",
+        account_bytes(&CrankVault {
+            reward_lamports: 10_000,
+            rewards_paid: 4,
+            total_paid: 40_000,
+            updated_at: 1_700_000_019,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "DisputeConfig",
+        account_bytes(&DisputeConfig {
+            admin: key(19),
+            arbiter: key(24),
+            resolution_secs: 1_209_600,
+            updated_at: 1_700_000_019,
+            bond_lamports: 50_000_000,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "ClawbackConfig",
+        account_bytes(&ClawbackConfig {
+            window_secs: 259_200,
+            arbiter: key(24),
+            updated_at: 1_700_000_019,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "FeeRebates",
+        account_bytes(&FeeRebates {
+            tiers: vec![
+                RebateTier {
+                    min_volume: 0,
+                    rebate_bps: 1_000,
+                },
+                RebateTier {
+                    min_volume: 50_000_000_000,
+                    rebate_bps: 2_500,
+                },
+            ],
+            updated_at: 1_700_000_019,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ProviderVolume",
+        account_bytes(&ProviderVolume {
+            provider: key(2),
+            period: 202410,
+            volume: 60_000_000_000,
+            rebated: 37_500,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "PlanBenefits",
+        account_bytes(&PlanBenefits {
+            tenant: key(9),
+            premium: 250_000_000,
+            research_discount_bps: 1_500,
+            research_categories: DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+            out_of_pocket_max: 3_000_000_000,
+            updated_at: 1_700_000_020,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "PremiumPayment",
+        account_bytes(&PremiumPayment {
+            tenant: key(9),
+            patient: key(1),
+            period: 202_311,
+            amount: 212_500_000,
+            discount: 37_500_000,
+            paid_at: 1_700_000_021,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "OutOfPocket",
+        account_bytes(&OutOfPocket {
+            tenant: key(9),
+            patient: key(1),
+            year: 2023,
+            patient_share: 3_000_000_000,
+            payer_covered: 125_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ProviderStake",
+        account_bytes(&ProviderStake {
+            provider: key(2),
+            staked: 5_000_000_000,
+            unbonding: 1_000_000_000,
+            unbonding_at: 1_702_592_023,
+            slashed: 250_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "FraudFinding",
+        account_bytes(&FraudFinding {
+            claim: key(3),
+            provider: key(2),
+            patient: key(1),
+            arbiter: key(31),
+            slashed: 250_000_000,
+            ruled_at: 1_700_000_023,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ResearchConsent",
+        account_bytes(&ResearchConsent {
+            patient: key(1),
+            categories: CATEGORY_ALL,
+            granted_at: 1_700_000_022,
+            expires_at: 1_731_536_022,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "PaymentHold",
+        account_bytes(&PaymentHold {
+            claim: key(3),
+            provider: key(2),
+            patient: key(1),
+            arbiter: key(24),
+            amount: 1_497_500,
+            principal: 1_500_000,
+            interest: 0,
+            period: 202_311,
+            held_at: 1_700_000_000,
+            release_at: 1_700_259_200,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "Tenant",
+        account_bytes(&Tenant {
+            admin: key(16),
+            tenant_id: "north".into(),
+            treasury: key(17),
+            fee_bps: 250,
+            member_count: 12,
+            created_at: 1_700_000_016,
+            updated_at: 1_700_000_017,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "TenantMember",
+        account_bytes(&TenantMember {
+            tenant: key(18),
+            member: key(2),
+            role: TenantRole::Provider,
+            admitted_at: 1_700_000_018,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ConsentLedger",
+        account_bytes(&ConsentLedger {
+            patient: key(1),
+            receipt_count: 3,
+            head: [6; 32],
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ConsentReceipt",
+        ConsentReceipt {
+            patient: key(1),
+            actor: key(7),
+            grantee: key(2),
+            action: ConsentAction::Revoke,
+            scope: GRANT_SCOPE_READ,
+            lawful_basis: LawfulBasis::VitalInterests,
+            expires_at: 1_800_000_000,
+            timestamp: 1_700_000_007,
+            sequence: 3,
+        }
+        .data(),
+    );
+    let mut chunked: HealthDataAccountV2 = bytemuck::Zeroable::zeroed();
+    chunked.owner = key(1);
+    chunked.data_hash = [0x22; 32];
+    chunked.commitment = [0x33; 32];
+    chunked.created_at = 1_700_000_000;
+    chunked.finalized_at = 1_700_000_060;
+    chunked.total_len = 4;
+    chunked.written_len = 4;
+    chunked.category = DataCategory::LabResult as u8;
+    chunked.account_version = 1;
+    chunked.data[..4].copy_from_slice(&[0xee; 4]);
+    samples.insert("HealthDataAccountV2", zero_copy_bytes(&chunked));
+    samples.insert(
+        "HealthDataBatchAccount",
+        account_bytes(&HealthDataBatchAccount {
+            owner: key(1),
+            batch_id: "b1".into(),
+            entries: vec![HealthRecordEntry {
+                data_hash: [0xd2; 32],
+                pointer: "ipfs://x".into(),
+                category: DataCategory::Imaging,
+            }],
+            timestamp: 1_700_000_001,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "ClaimAccount",
+        account_bytes(&ClaimAccount {
+            claim_id: "c1".into(),
+            patient: key(1),
+            provider: key(2),
+            attachments: vec![[0xd1; 32]],
+            amount: 300,
+            line_items: vec![ClaimLineItem {
+                code: "99213".into(),
+                units: 2,
+                unit_price: 150,
+                modifier: "25".into(),
+                status: LineItemStatus::DownCoded,
+                reason_code: 45,
+            }],
+            status: ClaimStatus::UnderReview,
+            timestamp: 1_700_000_002,
+            note_count: 1,
+            rejected_at: 1_700_000_003,
+            reopened: true,
+            verified_at: 1_700_000_015,
+            principal_paid: 300,
+            interest_paid: 2,
+            tenant: key(16),
+            mint: key(17),
+            external_ref: "CLM-0001".into(),
+            tags: vec!["batch-7".into()],
+            service_start: 1_699_900_000,
+            service_end: 1_699_986_400,
+            approval_policy: key(18),
+            required_approvals: 0b111,
+            approvals: 0b011,
+            rent_payer: key(21),
+            insurer: key(22),
+            insurer_approved_at: 1_700_100_000,
+            expires_at: 1_702_700_000,
+            payer_covered: 0,
+            account_version: 10,
+        }),
+    );
+    samples.insert(
+        "AutoApproval",
+        account_bytes(&AutoApproval {
+            claim: key(3),
+            deadline: 1_702_592_002,
+            created_at: 1_700_000_002,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ClaimGrant",
+        account_bytes(&ClaimGrant {
+            claim: key(3),
+            patient: key(1),
+            filed_at: 1_700_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ClaimDedup",
+        account_bytes(&ClaimDedup {
+            claim: key(3),
+            service_date: 1_699_990_000,
+            created_at: 1_700_000_002,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ProviderClaimStats",
+        account_bytes(&ProviderClaimStats {
+            provider: key(2),
+            day: 19_675,
+            claims_today: 51,
+            total_claims: 400,
+            duplicate_code_claims: 90,
+            fraud_findings: 2,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "FraudSignal",
+        FraudSignal {
+            provider: key(2),
+            kind: FraudSignalKind::DuplicateCodes,
+            value: 2_250,
+            threshold: 2_000,
+            timestamp: 1_700_000_014,
+        }
+        .data(),
+    );
+    samples.insert(
+        "PatientRegistered",
+        PatientRegistered {
+            patient: key(4),
+            authority: key(1),
+            did: "did:example:123".to_string(),
+            timestamp: 1_700_000_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ReencryptionRequested",
+        ReencryptionRequested {
+            request: key(29),
+            patient: key(1),
+            provider: key(2),
+            record: key(3),
+            recipient_key: [9; 32],
+            timestamp: 1_700_000_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ReencryptionFulfilled",
+        ReencryptionFulfilled {
+            request: key(29),
+            patient: key(1),
+            provider: key(2),
+            record: key(3),
+            timestamp: 1_700_003_600,
+        }
+        .data(),
+    );
+    samples.insert(
+        "EncryptionKeyUpdated",
+        EncryptionKeyUpdated {
+            owner: key(2),
+            x25519_key: [9; 32],
+            key_version: 2,
+            timestamp: 1_700_000_009,
+        }
+        .data(),
+    );
+    samples.insert(
+        "HealthDataSubmitted",
+        HealthDataSubmitted {
+            record: key(5),
+            owner: key(1),
+            author: key(2),
+            data_hash: [0xab; 32],
+            category: DataCategory::LabResult,
+            timestamp: 1_700_000_001,
+        }
+        .data(),
+    );
+    samples.insert(
+        "AppointmentBilled",
+        AppointmentBilled {
+            appointment: key(4),
+            claim: key(3),
+            patient: key(1),
+            provider: key(2),
+            timestamp: 1_700_000_002,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimCreated",
+        ClaimCreated {
+            claim: key(3),
+            claim_id: "claim-1".to_string(),
+            patient: key(1),
+            provider: key(2),
+            tenant: key(6),
+            mint: key(7),
+            amount: 1_500,
+            service_start: 1_699_900_000,
+            service_end: 1_699_950_000,
+            timestamp: 1_700_000_002,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimVerified",
+        ClaimVerified {
+            claim: key(3),
+            provider: key(2),
+            approved_amount: 1_000,
+            auto_approved: true,
+            timestamp: 1_700_000_003,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimRejected",
+        ClaimRejected {
+            claim: key(3),
+            provider: key(2),
+            status: ClaimStatus::Declined,
+            timestamp: 1_700_000_004,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimExpired",
+        ClaimExpired {
+            claim: key(3),
+            patient: key(1),
+            provider: key(2),
+            expires_at: 1_702_700_000,
+            refunded: 1_000,
+            timestamp: 1_702_700_060,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimDisputed",
+        ClaimDisputed {
+            claim: key(3),
+            patient: key(1),
+            provider: key(2),
+            arbiter: key(24),
+            reason_hash: [7; 32],
+            deadline: 1_701_209_600,
+            bond: 50_000_000,
+            timestamp: 1_700_000_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "DisputeResolved",
+        DisputeResolved {
+            claim: key(3),
+            resolver: key(24),
+            patient_prevailed: false,
+            status: ClaimStatus::Rejected,
+            bond: 50_000_000,
+            timestamp: 1_700_500_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "PaymentClawedBack",
+        PaymentClawedBack {
+            claim: key(3),
+            provider: key(2),
+            patient: key(1),
+            amount: 1_497_500,
+            principal_paid: 0,
+            status: ClaimStatus::Verified,
+            timestamp: 1_700_100_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "FacilitatorUpdated",
+        FacilitatorUpdated {
+            facilitator: key(27),
+            authority: key(25),
+            signing_key: key(26),
+            fee_share_bps: 2_000,
+            revoked: false,
+            timestamp: 1_700_050_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "PaymentAttributed",
+        PaymentAttributed {
+            facilitator: key(27),
+            claim: key(3),
+            amount: 1_500_000,
+            fee_share: 750,
+            timestamp: 1_700_100_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "FeeRebated",
+        FeeRebated {
+            provider: key(2),
+            claim: key(3),
+            period: 202410,
+            volume: 60_000_000_000,
+            rebate_bps: 2_500,
+            rebate: 937,
+            timestamp: 1_700_100_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "PremiumPaid",
+        PremiumPaid {
+            tenant: key(9),
+            patient: key(1),
+            period: 202_311,
+            premium: 250_000_000,
+            discount: 37_500_000,
+            timestamp: 1_700_000_021,
+        }
+        .data(),
+    );
+    samples.insert(
+        "OutOfPocketMaxReached",
+        OutOfPocketMaxReached {
+            claim: key(3),
+            tenant: key(9),
+            patient: key(1),
+            year: 2023,
+            payer_covered: 125_000,
+            timestamp: 1_700_000_022,
+        }
+        .data(),
+    );
+    samples.insert(
+        "FraudRuled",
+        FraudRuled {
+            claim: key(3),
+            provider: key(2),
+            patient: key(1),
+            arbiter: key(31),
+            slashed: 250_000_000,
+            fraud_findings: 2,
+            timestamp: 1_700_000_023,
+        }
+        .data(),
+    );
+    samples.insert(
+        "CrankRewarded",
+        CrankRewarded {
+            cranker: key(30),
+            target: key(3),
+            amount: 10_000,
+            timestamp: 1_700_100_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "InsurerApproved",
+        InsurerApproved {
+            claim: key(3),
+            insurer: key(22),
+            approved_amount: 1_000,
+            timestamp: 1_700_100_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimPaid",
+        ClaimPaid {
+            claim: key(3),
+            patient: key(1),
+            provider: key(2),
+            mint: key(7),
+            amount: 1_010,
+            principal_paid: 1_000,
+            interest_paid: 10,
+            status: ClaimStatus::Paid,
+            timestamp: 1_700_000_005,
+        }
+        .data(),
+    );
+    samples.insert(
+        "InstallmentPaid",
+        InstallmentPaid {
+            claim: key(3),
+            installment: 3,
+            installment_count: 6,
+            principal: 250,
+            due_at: 1_707_776_000,
+            timestamp: 1_707_700_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "SecondOpinionGiven",
+        SecondOpinionGiven {
+            claim: key(3),
+            consultant: key(29),
+            opinion: [32; 32],
+            fee: 250_000,
+            timestamp: 1_700_400_000,
+        }
+        .data(),
+    );
+    samples.insert(
+        "EmergencyAccessRequested",
+        EmergencyAccessRequested {
+            patient: key(1),
+            provider: key(2),
+            data_hash: [30; 32],
+            justification_hash: [31; 32],
+            available_at: 1_700_000_900,
+            expires_at: 1_700_087_300,
+        }
+        .data(),
+    );
+    samples.insert(
+        "EmergencyAccessVetoed",
+        EmergencyAccessVetoed {
+            patient: key(1),
+            provider: key(2),
+            data_hash: [30; 32],
+            timestamp: 1_700_000_300,
+        }
+        .data(),
+    );
+    samples.insert(
+        "ClaimNotesPage",
+        account_bytes(&ClaimNotesPage {
+            claim: key(3),
+            page: 0,
+            notes: vec![ClaimNote {
+                author: key(2),
+                note_hash: "n1".into(),
+                timestamp: 1_700_000_004,
+            }],
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ClaimEscrow",
+        account_bytes(&ClaimEscrow {
+            claim: key(3),
+            provider: key(2),
+            amount: 1_500,
+            funded_at: 1_700_000_005,
+            arbiter: key(24),
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "ApprovalPolicy",
+        account_bytes(&ApprovalPolicy {
+            authority: key(2),
+            policy_id: "over-10k".into(),
+            threshold: 10_000,
+            approvers: vec![key(2), key(19), key(20)],
+            updated_at: 1_700_000_006,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "HealthDataIndex",
+        account_bytes(&HealthDataIndex {
+            owner: key(1),
+            record_count: 3,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "DeviceAccount",
+        account_bytes(&DeviceAccount {
+            patient: key(1),
+            device: key(23),
+            model_hash: [24; 32],
+            category: DataCategory::Vitals,
+            max_readings_per_day: 96,
+            day: 19_675,
+            readings_today: 12,
+            total_readings: 340,
+            registered_at: 1_699_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "DeviceRollup",
+        account_bytes(&DeviceRollup {
+            device_account: key(25),
+            patient: key(1),
+            category: DataCategory::Vitals,
+            day: 19_674,
+            readings_root: [26; 32],
+            summary: ReadingSummary {
+                reading_count: 96,
+                min_value: 58,
+                max_value: 131,
+                avg_value: 74,
+            },
+            summary_hash: [27; 32],
+            rolled_up_at: 1_700_000_400,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "PaymentSchedule",
+        account_bytes(&PaymentSchedule {
+            claim: key(3),
+            installment_count: 6,
+            installments_paid: 2,
+            interval_secs: 2_592_000,
+            starts_at: 1_700_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "SecondOpinion",
+        account_bytes(&SecondOpinion {
+            claim: key(3),
+            patient: key(1),
+            consultant: key(29),
+            records: vec![[30; 32], [31; 32]],
+            fee: 250_000,
+            requested_at: 1_700_000_000,
+            expires_at: 1_701_209_600,
+            opinion: [32; 32],
+            submitted_at: 1_700_400_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "CareTeam",
+        account_bytes(&CareTeam {
+            patient: key(1),
+            team_id: 2,
+            members: vec![key(2), key(29)],
+            records: vec![[30; 32]],
+            updated_at: 1_700_000_010,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "EmergencyAccess",
+        account_bytes(&EmergencyAccess {
+            data_hash: [30; 32],
+            patient: key(1),
+            provider: key(2),
+            justification_hash: [31; 32],
+            requested_at: 1_700_000_000,
+            available_at: 1_700_000_900,
+            expires_at: 1_700_087_300,
+            vetoed_at: 0,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "AuditTrail",
+        account_bytes(&AuditTrail {
+            subject: key(1),
+            entry_count: 4,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "AuditEntry",
+        account_bytes(&AuditEntry {
+            subject: key(1),
+            sequence: 3,
+            actor: key(2),
+            action: AuditAction::ClaimPaid,
+            target: key(3),
+            timestamp: 1_700_000_600,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "FeeSchedule",
+        account_bytes(&FeeSchedule {
+            provider: key(2),
+            entries: vec![FeeScheduleEntry {
+                code: "99213".to_string(),
+                unit_price: 750,
+            }],
+            updated_at: 1_700_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "Appointment",
+        account_bytes(&Appointment {
+            appointment_id: "appt-1".to_string(),
+            patient: key(1),
+            provider: key(2),
+            scheduled_at: 1_699_990_000,
+            status: AppointmentStatus::Billed,
+            services: vec![AppointmentService {
+                code: "99213".to_string(),
+                units: 2,
+            }],
+            session_records: vec![[9; 32]],
+            completed_at: 1_699_993_600,
+            claim: key(3),
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ClaimDispute",
+        account_bytes(&ClaimDispute {
+            claim: key(3),
+            patient: key(1),
+            provider: key(2),
+            arbiter: key(24),
+            reason_hash: [7; 32],
+            opened_at: 1_700_000_000,
+            deadline: 1_701_209_600,
+            resolved_at: 1_700_500_000,
+            patient_prevailed: true,
+            bond: 0,
+            account_version: 2,
+        }),
+    );
+    samples.insert(
+        "RegistryAuthority",
+        account_bytes(&RegistryAuthority {
+            authority: key(1),
+            verified_count: 2,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ProviderCredential",
+        account_bytes(&ProviderCredential {
+            provider: key(2),
+            verified: true,
+            verified_at: 1_700_000_000,
+            revoked_at: 1_699_000_000,
+            account_version: 1,
+        }),
+    );
+    samples.insert(
+        "ClockOffset",
+        account_bytes(&ClockOffset {
+            offset_secs: -60,
+            account_version: 1,
+        }),
+    );
+    samples
+}
+
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("layout_v{LAYOUT_VERSION}.txt"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+#[test]
+fn layouts_match_the_snapshot_for_the_current_version() {
+    let path = snapshot_path();
+    let mut snapshot: BTreeMap<String, String> = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(name, bytes)| (name.to_string(), bytes.to_string()))
+        .collect();
+    let update = std::env::var_os("UPDATE_LAYOUT_SNAPSHOTS").is_some();
+
+    let mut changed = Vec::new();
+    let mut missing = Vec::new();
+    for (name, bytes) in samples() {
+        let bytes = hex(&bytes);
+        match snapshot.get(name) {
+            Some(expected) if *expected == bytes => {}
+            Some(_) => changed.push(name),
+            None if update => {
+                snapshot.insert(name.to_string(), bytes);
+            }
+            None => missing.push(name),
+        }
+    }
+
+    assert!(
+        changed.is_empty(),
+        "layout of {changed:?} changed; bump LAYOUT_VERSION (now {LAYOUT_VERSION}) and record a new snapshot"
+    );
+    assert!(
+        missing.is_empty(),
+        "{missing:?} missing from {}; record with UPDATE_LAYOUT_SNAPSHOTS=1",
+        path.display()
+    );
+    if update {
+        let contents: String = snapshot
+            .iter()
+            .map(|(name, bytes)| format!("{name} {bytes}\n"))
+            .collect();
+        std::fs::write(&path, contents).unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::line;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimStatus, LineItemStatus, OutOfPocket, PatientYearSummary,
+};
+
+/// 2023-11-14.
+const NOW: i64 = 1_700_000_000;
+
+/// A verified tenant claim for 1_000 lamports of which `approved` is
+/// approved, leaving the rest to the patient.
+fn verified_claim(tenant: Pubkey, patient: Pubkey, approved: u64) -> ClaimAccount {
+    ClaimAccount {
+        patient,
+        tenant,
+        status: ClaimStatus::Verified,
+        verified_at: NOW,
+        service_start: NOW,
+        service_end: NOW,
+        ..common::claim(vec![
+            line(approved, LineItemStatus::Approved),
+            line(1_000 - approved, LineItemStatus::Denied),
+        ])
+    }
+}
+
+fn tally() -> OutOfPocket {
+    OutOfPocket {
+        tenant: Pubkey::default(),
+        patient: Pubkey::default(),
+        year: 0,
+        patient_share: 0,
+        payer_covered: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn the_payer_covers_the_patient_share_past_the_maximum() {
+    let tenant = Pubkey::new_unique();
+    let patient = Pubkey::new_unique();
+    let mut tally = tally();
+
+    let mut first = verified_claim(tenant, patient, 400);
+    assert_eq!(tally.apply(&mut first, 1_000).unwrap(), 0);
+    assert_eq!(first.approved_amount().unwrap(), 400);
+    assert_eq!(
+        (
+            tally.tenant,
+            tally.patient,
+            tally.year,
+            tally.account_version
+        ),
+        (tenant, patient, 2023, 1)
+    );
+
+    // 600 of the maximum are used, so 400 of this claim's 600 are covered
+    let mut second = verified_claim(tenant, patient, 400);
+    assert_eq!(tally.apply(&mut second, 1_000).unwrap(), 200);
+    assert_eq!(second.payer_covered, 200);
+    assert_eq!(second.approved_amount().unwrap(), 600);
+    assert_eq!(second.outstanding_principal().unwrap(), 600);
+
+    // Past the maximum the patient pays nothing more
+    let mut third = verified_claim(tenant, patient, 100);
+    assert_eq!(tally.apply(&mut third, 1_000).unwrap(), 900);
+    assert_eq!(third.approved_amount().unwrap(), 1_000);
+    assert_eq!((tally.patient_share, tally.payer_covered), (1_000, 1_100));
+}
+
+#[test]
+fn covered_shares_leave_the_year_summary_out_of_pocket() {
+    let tenant = Pubkey::new_unique();
+    let patient = Pubkey::new_unique();
+    let mut tally = tally();
+    let mut summary = PatientYearSummary::new(patient, 2023, Pubkey::default(), 0);
+    for approved in [200, 500] {
+        let mut claim = verified_claim(tenant, patient, approved);
+        tally.apply(&mut claim, 1_000).unwrap();
+        assert!(summary.add(&claim).unwrap());
+    }
+    assert_eq!(summary.out_of_pocket, 1_000);
+    assert_eq!(summary.approved, 1_000);
+    assert_eq!(summary.out_of_pocket, tally.patient_share);
+}
+
+#[test]
+fn approved_amounts_never_exceed_the_billed_amount() {
+    // A claim whose rejection is overturned has every line approved, so what
+    // the payer covered at verification adds nothing more
+    let mut claim = verified_claim(Pubkey::new_unique(), Pubkey::new_unique(), 400);
+    claim.payer_covered = 600;
+    for item in claim.line_items.iter_mut() {
+        item.status = LineItemStatus::Approved;
+    }
+    assert_eq!(claim.approved_amount().unwrap(), claim.amount);
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimStatus, ClawbackConfig, ErrorCode, LineItemStatus, PaymentHold,
+    MAX_CLAWBACK_WINDOW_SECS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn paid_claim(principal_paid: u64) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "held".to_string(),
+        attachments: vec![[0xab; 32]],
+        status: if principal_paid == 1_000_000 {
+            ClaimStatus::Paid
+        } else {
+            ClaimStatus::PartiallyPaid
+        },
+        timestamp: NOW - DAY,
+        verified_at: NOW - 60,
+        principal_paid,
+        ..common::claim(vec![common::line(1_000_000, LineItemStatus::Approved)])
+    }
+}
+
+fn config(window_secs: i64, arbiter: Pubkey) -> ClawbackConfig {
+    let mut config = ClawbackConfig {
+        window_secs: 0,
+        arbiter: Pubkey::default(),
+        updated_at: 0,
+        account_version: 0,
+    };
+    config.set(window_secs, arbiter, NOW).unwrap();
+    config
+}
+
+fn empty_hold() -> PaymentHold {
+    PaymentHold {
+        claim: Pubkey::default(),
+        provider: Pubkey::default(),
+        patient: Pubkey::default(),
+        arbiter: Pubkey::default(),
+        amount: 0,
+        principal: 0,
+        interest: 0,
+        period: 0,
+        held_at: 0,
+        release_at: 0,
+        account_version: 0,
+    }
+}
+
+fn hold(claim: &ClaimAccount, config: &ClawbackConfig) -> PaymentHold {
+    let mut hold = empty_hold();
+    hold.hold(
+        Pubkey::new_unique(),
+        claim,
+        config,
+        990_000,
+        1_000_000,
+        0,
+        7,
+        NOW,
+    )
+    .unwrap();
+    hold
+}
+
+#[test]
+fn clawback_windows_are_bounded() {
+    let mut config = config(DAY, Pubkey::default());
+    expect_error(
+        config.set(-1, Pubkey::default(), NOW),
+        ErrorCode::InvalidClawbackWindow,
+    );
+    expect_error(
+        config.set(MAX_CLAWBACK_WINDOW_SECS + 1, Pubkey::default(), NOW),
+        ErrorCode::InvalidClawbackWindow,
+    );
+    config
+        .set(MAX_CLAWBACK_WINDOW_SECS, Pubkey::default(), NOW)
+        .unwrap();
+    assert_eq!(config.window_secs, MAX_CLAWBACK_WINDOW_SECS);
+}
+
+#[test]
+fn holds_need_a_clawback_window() {
+    let claim = paid_claim(1_000_000);
+    let off = config(0, Pubkey::default());
+    expect_error(
+        empty_hold().hold(
+            Pubkey::new_unique(),
+            &claim,
+            &off,
+            990_000,
+            1_000_000,
+            0,
+            7,
+            NOW,
+        ),
+        ErrorCode::ClawbackDisabled,
+    );
+
+    let hold = hold(&claim, &config(3 * DAY, Pubkey::default()));
+    assert_eq!(hold.provider, claim.provider);
+    assert_eq!(hold.patient, claim.patient);
+    assert_eq!(hold.release_at, NOW + 3 * DAY);
+}
+
+#[test]
+fn only_the_provider_releases_a_hold_early() {
+    let claim = paid_claim(1_000_000);
+    let hold = hold(&claim, &config(DAY, Pubkey::default()));
+    let anyone = Pubkey::new_unique();
+
+    expect_error(
+        hold.expect_releasable(anyone, NOW + DAY - 1),
+        ErrorCode::PaymentStillHeld,
+    );
+    expect_error(
+        hold.expect_releasable(claim.patient, NOW),
+        ErrorCode::PaymentStillHeld,
+    );
+    hold.expect_releasable(claim.provider, NOW).unwrap();
+    hold.expect_releasable(anyone, NOW + DAY).unwrap();
+}
+
+#[test]
+fn clawbacks_need_the_arbiter_or_both_parties_within_the_window() {
+    let claim = paid_claim(1_000_000);
+    let arbiter = Pubkey::new_unique();
+    let hold = hold(&claim, &config(DAY, arbiter));
+
+    hold.authorize_clawback(&[arbiter], NOW).unwrap();
+    hold.authorize_clawback(&[claim.provider, claim.patient], NOW)
+        .unwrap();
+    expect_error(
+        hold.authorize_clawback(&[claim.provider], NOW),
+        ErrorCode::ClawbackUnauthorized,
+    );
+    expect_error(
+        hold.authorize_clawback(&[claim.patient, Pubkey::new_unique()], NOW),
+        ErrorCode::ClawbackUnauthorized,
+    );
+    expect_error(
+        hold.authorize_clawback(&[arbiter], NOW + DAY),
+        ErrorCode::ClawbackWindowElapsed,
+    );
+}
+
+#[test]
+fn holds_without_an_arbiter_need_both_parties() {
+    let claim = paid_claim(1_000_000);
+    let hold = hold(&claim, &config(DAY, Pubkey::default()));
+    expect_error(
+        hold.authorize_clawback(&[Pubkey::default()], NOW),
+        ErrorCode::ClawbackUnauthorized,
+    );
+}
+
+#[test]
+fn reversing_a_payment_reopens_the_claim_for_payment() {
+    let mut claim = paid_claim(1_000_000);
+    claim.reverse_payment(1_000_000, 0).unwrap();
+    assert_eq!(claim.principal_paid, 0);
+    assert_eq!(claim.status, ClaimStatus::Verified);
+
+    let mut claim = paid_claim(1_000_000);
+    claim.reverse_payment(400_000, 0).unwrap();
+    assert_eq!(claim.principal_paid, 600_000);
+    assert_eq!(claim.status, ClaimStatus::PartiallyPaid);
+
+    let mut claim = paid_claim(1_000_000);
+    claim.status = ClaimStatus::Verified;
+    expect_error(
+        claim.reverse_payment(1_000_000, 0),
+        ErrorCode::InvalidStatusTransition,
+    );
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{ErrorCode, PaymentSchedule, MAX_INSTALLMENTS};
+
+const NOW: i64 = 1_700_000_000;
+const MONTH: i64 = 30 * 24 * 60 * 60;
+
+fn unset() -> PaymentSchedule {
+    PaymentSchedule {
+        claim: Pubkey::default(),
+        installment_count: 0,
+        installments_paid: 0,
+        interval_secs: 0,
+        starts_at: 0,
+        account_version: 0,
+    }
+}
+
+fn schedule(installment_count: u8) -> PaymentSchedule {
+    let mut schedule = unset();
+    schedule
+        .set(Pubkey::new_unique(), installment_count, MONTH, NOW)
+        .unwrap();
+    schedule
+}
+
+#[test]
+fn schedules_need_several_installments_a_positive_interval_apart() {
+    let claim = Pubkey::new_unique();
+    let mut schedule = unset();
+    for (count, interval) in [
+        (1, MONTH),
+        (MAX_INSTALLMENTS + 1, MONTH),
+        (3, 0),
+        (3, -MONTH),
+    ] {
+        expect_error(
+            schedule.set(claim, count, interval, NOW),
+            ErrorCode::InvalidPaymentSchedule,
+        );
+    }
+
+    schedule.set(claim, MAX_INSTALLMENTS, MONTH, NOW).unwrap();
+    assert_eq!(schedule.claim, claim);
+    assert_eq!(schedule.installments_paid, 0);
+    assert_eq!((schedule.starts_at, schedule.account_version), (NOW, 1));
+}
+
+#[test]
+fn installments_split_what_is_outstanding_evenly() {
+    let mut schedule = schedule(3);
+    assert_eq!(schedule.installment_due(1_000), 334);
+    assert_eq!(schedule.record(334, 1_000).unwrap(), 1);
+    assert_eq!(schedule.installment_due(666), 333);
+    assert_eq!(schedule.record(333, 666).unwrap(), 2);
+    // The last installment pays the rest
+    assert_eq!(schedule.installment_due(333), 333);
+    assert_eq!(schedule.record(333, 333).unwrap(), 3);
+    assert_eq!(schedule.installments_paid, 3);
+}
+
+#[test]
+fn installments_cannot_fall_short() {
+    let mut schedule = schedule(4);
+    expect_error(schedule.record(249, 1_000), ErrorCode::InstallmentTooSmall);
+    assert_eq!(schedule.installments_paid, 0);
+
+    // Overpaying one installment lowers the ones after it
+    assert_eq!(schedule.record(400, 1_000).unwrap(), 1);
+    assert_eq!(schedule.installment_due(600), 200);
+}
+
+#[test]
+fn paying_everything_completes_the_schedule_early() {
+    let mut schedule = schedule(6);
+    assert_eq!(schedule.record(500, 3_000).unwrap(), 1);
+    assert_eq!(schedule.record(2_500, 2_500).unwrap(), 2);
+    assert_eq!(schedule.installments_paid, 6);
+}
+
+#[test]
+fn installments_fall_due_an_interval_apart() {
+    let schedule = schedule(3);
+    assert_eq!(schedule.due_at(1), NOW + MONTH);
+    assert_eq!(schedule.due_at(3), NOW + 3 * MONTH);
+}
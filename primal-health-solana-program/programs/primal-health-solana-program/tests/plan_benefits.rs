@@ -0,0 +1,113 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    DataCategory, ErrorCode, PlanBenefits, ResearchConsent, CATEGORY_ALL,
+};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn plan(premium: u64, research_discount_bps: u16, research_categories: u8) -> PlanBenefits {
+    let mut plan = PlanBenefits {
+        tenant: Pubkey::new_unique(),
+        premium: 0,
+        research_discount_bps: 0,
+        research_categories: 0,
+        out_of_pocket_max: 0,
+        updated_at: 0,
+        account_version: 0,
+    };
+    plan.set(premium, research_discount_bps, research_categories, 0, NOW)
+        .unwrap();
+    plan
+}
+
+fn consent(categories: u8, expires_at: i64) -> ResearchConsent {
+    let mut consent = ResearchConsent {
+        patient: Pubkey::new_unique(),
+        categories: 0,
+        granted_at: 0,
+        expires_at: 0,
+        account_version: 1,
+    };
+    consent.grant(categories, expires_at, NOW).unwrap();
+    consent
+}
+
+#[test]
+fn the_discount_needs_an_active_consent_to_every_research_category() {
+    let labs_and_imaging = DataCategory::LabResult.bit() | DataCategory::Imaging.bit();
+    let plan = plan(100_000, 2_000, labs_and_imaging);
+    assert_eq!((plan.updated_at, plan.account_version), (NOW, 2));
+
+    assert_eq!(plan.research_discount(None, NOW).unwrap(), 0);
+    let labs_only = consent(DataCategory::LabResult.bit(), 0);
+    assert_eq!(plan.research_discount(Some(&labs_only), NOW).unwrap(), 0);
+    let everything = consent(CATEGORY_ALL, 0);
+    assert_eq!(
+        plan.research_discount(Some(&everything), NOW).unwrap(),
+        20_000
+    );
+
+    // A lapsed consent no longer earns the discount
+    let lapsing = consent(labs_and_imaging, NOW + DAY);
+    assert_eq!(
+        plan.research_discount(Some(&lapsing), NOW + DAY - 1)
+            .unwrap(),
+        20_000
+    );
+    assert_eq!(
+        plan.research_discount(Some(&lapsing), NOW + DAY).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn discounts_round_down() {
+    let plan = plan(999, 2_500, CATEGORY_ALL);
+    let consent = consent(CATEGORY_ALL, 0);
+    assert_eq!(plan.research_discount(Some(&consent), NOW).unwrap(), 249);
+
+    // Without a discount the consent changes nothing
+    let plan = self::plan(999, 0, 0);
+    assert_eq!(plan.research_discount(Some(&consent), NOW).unwrap(), 0);
+}
+
+#[test]
+fn plans_need_a_premium_and_a_discount_for_some_categories() {
+    let mut plan = plan(100_000, 0, 0);
+    for (premium, bps, categories) in [
+        (0, 0, 0),
+        (100_000, 10_001, CATEGORY_ALL),
+        (100_000, 500, 0),
+    ] {
+        expect_error(
+            plan.set(premium, bps, categories, 0, NOW + 60),
+            ErrorCode::InvalidPlanBenefits,
+        );
+    }
+    assert_eq!(plan.updated_at, NOW);
+    plan.set(100_000, 10_000, CATEGORY_ALL, 5_000_000, NOW + 60)
+        .unwrap();
+    assert_eq!(plan.research_discount_bps, 10_000);
+    assert_eq!(plan.out_of_pocket_max, 5_000_000);
+}
+
+#[test]
+fn research_consent_needs_categories_and_a_future_expiry() {
+    let mut consent = consent(CATEGORY_ALL, 0);
+    expect_error(consent.grant(0, 0, NOW), ErrorCode::InvalidConsentScope);
+    expect_error(
+        consent.grant(CATEGORY_ALL, NOW, NOW),
+        ErrorCode::InvalidConsentExpiry,
+    );
+    consent
+        .grant(DataCategory::Vitals.bit(), NOW + DAY, NOW + 60)
+        .unwrap();
+    assert_eq!(
+        (consent.categories, consent.granted_at, consent.expires_at),
+        (DataCategory::Vitals.bit(), NOW + 60, NOW + DAY)
+    );
+}
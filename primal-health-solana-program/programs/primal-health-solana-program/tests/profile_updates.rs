@@ -0,0 +1,80 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use common::expect_error;
+use primal_health_solana_program::{
+    ErrorCode, PatientAccount, ProviderAccount, MAX_DID_LEN, MAX_NAME_LEN,
+};
+
+fn provider() -> ProviderAccount {
+    ProviderAccount {
+        authority: Pubkey::new_unique(),
+        did: "did:sol:clinic".to_string(),
+        name: "Clinic".to_string(),
+        account_version: 1,
+    }
+}
+
+fn encoded_len(account: &impl AccountSerialize) -> usize {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data.len()
+}
+
+#[test]
+fn providers_can_rename_and_rotate_their_did() {
+    let mut provider = provider();
+    let authority = provider.authority;
+    let did = format!("did:sol:{}", "a".repeat(MAX_DID_LEN - 8));
+    provider
+        .update(did.clone(), "Clinic of the Valley".to_string())
+        .unwrap();
+    assert_eq!(provider.did, did);
+    assert_eq!(provider.name, "Clinic of the Valley");
+    assert_eq!(provider.authority, authority);
+
+    // The account is resized to the new values, keeping its padding
+    assert_eq!(
+        ProviderAccount::space(&provider.did, &provider.name),
+        encoded_len(&provider) + 64
+    );
+}
+
+#[test]
+fn provider_updates_are_validated_like_registration() {
+    let mut provider = provider();
+    expect_error(
+        provider.update(String::new(), "Clinic".to_string()),
+        ErrorCode::DidEmpty,
+    );
+    expect_error(
+        provider.update("d".repeat(MAX_DID_LEN + 1), "Clinic".to_string()),
+        ErrorCode::DidTooLong,
+    );
+    expect_error(
+        provider.update("did:sol:clinic".to_string(), String::new()),
+        ErrorCode::NameEmpty,
+    );
+    expect_error(
+        provider.update("did:sol:clinic".to_string(), "n".repeat(MAX_NAME_LEN + 1)),
+        ErrorCode::NameTooLong,
+    );
+    assert_eq!(provider.name, "Clinic");
+}
+
+#[test]
+fn patients_can_rotate_their_did() {
+    let mut patient = PatientAccount {
+        authority: Pubkey::new_unique(),
+        did: "did:sol:patient-with-a-long-identifier".to_string(),
+        account_version: 1,
+    };
+    patient.update("did:sol:p".to_string()).unwrap();
+    assert_eq!(patient.did, "did:sol:p");
+    assert_eq!(
+        PatientAccount::space(&patient.did),
+        encoded_len(&patient) + 64
+    );
+    expect_error(patient.update(String::new()), ErrorCode::DidEmpty);
+}
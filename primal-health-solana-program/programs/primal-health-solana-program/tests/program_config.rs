@@ -0,0 +1,130 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    Config, ErrorCode, MintTreasury, ProtocolTreasury, MAX_PROTOCOL_FEE_BPS,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn blank() -> Config {
+    Config {
+        admin: Pubkey::default(),
+        paused: true,
+        fee_bps: 0,
+        updated_at: 0,
+        claim_ttl_secs: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn a_new_config_starts_unpaused() {
+    let admin = Pubkey::new_unique();
+    let mut config = blank();
+    config.initialize(admin, 30, NOW).unwrap();
+    assert_eq!(config.admin, admin);
+    assert!(!config.paused);
+    assert_eq!(config.fee_bps, 30);
+    assert_eq!(config.updated_at, NOW);
+    assert_eq!(config.account_version, 2);
+}
+
+#[test]
+fn the_protocol_fee_is_capped() {
+    let mut config = blank();
+    config
+        .initialize(Pubkey::new_unique(), MAX_PROTOCOL_FEE_BPS, NOW)
+        .unwrap();
+    expect_error(
+        blank().initialize(Pubkey::new_unique(), MAX_PROTOCOL_FEE_BPS + 1, NOW),
+        ErrorCode::ProtocolFeeTooHigh,
+    );
+}
+
+#[test]
+fn pausing_can_be_undone() {
+    let mut config = blank();
+    config.initialize(Pubkey::new_unique(), 0, NOW).unwrap();
+    config.set_paused(true, NOW + 1);
+    assert!(config.paused);
+    assert_eq!(config.updated_at, NOW + 1);
+    config.set_paused(false, NOW + 2);
+    assert!(!config.paused);
+    assert_eq!(config.updated_at, NOW + 2);
+}
+
+#[test]
+fn transferring_admin_keeps_the_pause_state() {
+    let new_admin = Pubkey::new_unique();
+    let mut config = blank();
+    config.initialize(Pubkey::new_unique(), 10, NOW).unwrap();
+    config.set_paused(true, NOW);
+    config.transfer_admin(new_admin, NOW + 5);
+    assert_eq!(config.admin, new_admin);
+    assert!(config.paused);
+    assert_eq!(config.fee_bps, 10);
+    assert_eq!(config.updated_at, NOW + 5);
+}
+
+#[test]
+fn the_protocol_fee_can_be_changed_within_the_cap() {
+    let mut config = blank();
+    config.initialize(Pubkey::new_unique(), 0, NOW).unwrap();
+    config.set_fee(40, NOW + 1).unwrap();
+    assert_eq!(config.fee_bps, 40);
+    assert_eq!(config.updated_at, NOW + 1);
+    expect_error(
+        config.set_fee(MAX_PROTOCOL_FEE_BPS + 1, NOW + 2),
+        ErrorCode::ProtocolFeeTooHigh,
+    );
+    assert_eq!(config.fee_bps, 40);
+    assert_eq!(config.updated_at, NOW + 1);
+}
+
+#[test]
+fn the_protocol_fee_rounds_down() {
+    let mut config = blank();
+    config.initialize(Pubkey::new_unique(), 25, NOW).unwrap();
+    assert_eq!(config.fee_on(1_000_000).unwrap(), 2_500);
+    assert_eq!(config.fee_on(399).unwrap(), 0);
+    config.set_fee(0, NOW).unwrap();
+    assert_eq!(config.fee_on(1_000_000).unwrap(), 0);
+}
+
+#[test]
+fn the_treasury_tracks_fees_and_withdrawals() {
+    let mut treasury = ProtocolTreasury {
+        total_collected: 0,
+        total_withdrawn: 0,
+        account_version: 1,
+    };
+    treasury.record_fee(2_500).unwrap();
+    treasury.record_fee(500).unwrap();
+    treasury.record_withdrawal(1_000).unwrap();
+    assert_eq!(treasury.total_collected, 3_000);
+    assert_eq!(treasury.total_withdrawn, 1_000);
+    treasury.total_collected = u64::MAX;
+    expect_error(treasury.record_fee(1), ErrorCode::AmountOverflow);
+}
+
+#[test]
+fn mint_treasuries_track_fees_and_sweeps() {
+    let mut treasury = MintTreasury {
+        mint: Pubkey::new_unique(),
+        decimals: 6,
+        vault: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        total_collected: 0,
+        total_swept: 0,
+        account_version: 1,
+    };
+    treasury.record_fee(62_500).unwrap();
+    treasury.record_fee(12_500).unwrap();
+    treasury.record_sweep(75_000).unwrap();
+    assert_eq!(treasury.total_collected, 75_000);
+    assert_eq!(treasury.total_swept, 75_000);
+    treasury.total_swept = u64::MAX;
+    expect_error(treasury.record_sweep(1), ErrorCode::AmountOverflow);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{ErrorCode, ProviderCredential};
+
+const NOW: i64 = 1_700_000_000;
+
+fn new_credential() -> ProviderCredential {
+    ProviderCredential {
+        provider: Pubkey::default(),
+        verified: false,
+        verified_at: 0,
+        revoked_at: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn verifying_twice_counts_once() {
+    let provider = Pubkey::new_unique();
+    let mut credential = new_credential();
+    assert!(credential.verify(provider, NOW));
+    assert!(!credential.verify(provider, NOW + 60));
+    assert_eq!(credential.provider, provider);
+    assert!(credential.verified);
+    assert_eq!(credential.verified_at, NOW + 60);
+    assert_eq!(credential.account_version, 1);
+}
+
+#[test]
+fn revoked_providers_can_be_verified_again() {
+    let provider = Pubkey::new_unique();
+    let mut credential = new_credential();
+    credential.verify(provider, NOW);
+    credential.revoke(NOW + 60).unwrap();
+    assert!(!credential.verified);
+    assert_eq!(credential.revoked_at, NOW + 60);
+
+    assert!(credential.verify(provider, NOW + 120));
+    assert_eq!(credential.revoked_at, NOW + 60);
+}
+
+#[test]
+fn only_verified_providers_can_be_revoked() {
+    let mut credential = new_credential();
+    credential.verify(Pubkey::new_unique(), NOW);
+    credential.revoke(NOW).unwrap();
+    expect_error(credential.revoke(NOW), ErrorCode::ProviderNotVerified);
+}
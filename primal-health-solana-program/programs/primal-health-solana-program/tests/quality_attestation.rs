@@ -0,0 +1,26 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{QualityAttestation, MAX_QUALITY_SCORE};
+
+const US_CORE_OBSERVATION: [u8; 32] = [1; 32];
+const IPS_OBSERVATION: [u8; 32] = [2; 32];
+
+fn attestation(score: u8) -> QualityAttestation {
+    QualityAttestation {
+        record: Pubkey::new_unique(),
+        validator: Pubkey::new_unique(),
+        profile_hash: US_CORE_OBSERVATION,
+        score,
+        attested_at: 1_700_000_000,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn attestations_meet_thresholds_for_their_profile_only() {
+    let attested = attestation(80);
+    assert!(attested.meets(&US_CORE_OBSERVATION, 80));
+    assert!(attested.meets(&US_CORE_OBSERVATION, 0));
+    assert!(!attested.meets(&US_CORE_OBSERVATION, 81));
+    assert!(!attested.meets(&IPS_OBSERVATION, 0));
+    assert!(attestation(MAX_QUALITY_SCORE).meets(&US_CORE_OBSERVATION, MAX_QUALITY_SCORE));
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{ErrorCode, HealthDataAccount, RecordPointer, StorageKind};
+use solana_program::hash::hashv;
+
+const NOW: i64 = 1_700_000_000;
+const PAYLOAD: &[u8] = b"AQIDBAUGBwgJ";
+
+fn recorded(storage: StorageKind, cid: &str) -> HealthDataAccount {
+    let mut record = HealthDataAccount {
+        owner: Pubkey::default(),
+        data_hash: [0; 32],
+        storage: StorageKind::Ipfs,
+        cid: String::new(),
+        commitment: [0; 32],
+        timestamp: 0,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 0,
+    };
+    let owner = Pubkey::new_unique();
+    record.record(
+        owner,
+        owner,
+        [1; 32],
+        RecordPointer {
+            storage,
+            cid: cid.to_string(),
+            commitment: hashv(&[PAYLOAD]).to_bytes(),
+        },
+        None,
+        NOW,
+    );
+    record
+}
+
+#[test]
+fn records_keep_the_pointer_and_commitment() {
+    let record = recorded(
+        StorageKind::Arweave,
+        "bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U",
+    );
+    assert_eq!(record.storage, StorageKind::Arweave);
+    assert_eq!(record.cid, "bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U");
+    assert_eq!(record.commitment, hashv(&[PAYLOAD]).to_bytes());
+}
+
+#[test]
+fn only_the_committed_content_verifies() {
+    let record = recorded(StorageKind::Ipfs, "bafkreigh2akiscaildc");
+    record
+        .verify_commitment(&hashv(&[PAYLOAD]).to_bytes())
+        .unwrap();
+    let tampered = hashv(&[b"AQIDBAUGBwgK"]).to_bytes();
+    assert_eq!(
+        record.verify_commitment(&tampered).unwrap_err(),
+        ErrorCode::CommitmentMismatch.into()
+    );
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ConsentAccount, ErrorCode, CONSENT_SCOPE_ALL, CONSENT_SCOPE_READ, CONSENT_SCOPE_RESEARCH,
+    CONSENT_SCOPE_SHARE,
+};
+
+fn consent() -> ConsentAccount {
+    ConsentAccount {
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        record: Pubkey::new_unique(),
+        data_hash: [0xab; 32],
+        scope: 0,
+        granted_at: 0,
+        expires_at: 0,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn consent_covers_only_its_scope() {
+    let mut consent = consent();
+    consent
+        .grant(CONSENT_SCOPE_READ | CONSENT_SCOPE_SHARE, 0, 1_000)
+        .unwrap();
+    assert_eq!(consent.granted_at, 1_000);
+    assert!(consent.allows(CONSENT_SCOPE_READ, 2_000));
+    assert!(consent.allows(CONSENT_SCOPE_READ | CONSENT_SCOPE_SHARE, 2_000));
+    assert!(!consent.allows(CONSENT_SCOPE_RESEARCH, 2_000));
+    assert!(!consent.allows(CONSENT_SCOPE_ALL, 2_000));
+}
+
+#[test]
+fn consent_lapses_at_expiry() {
+    let mut consent = consent();
+    consent.grant(CONSENT_SCOPE_RESEARCH, 5_000, 1_000).unwrap();
+    assert!(consent.allows(CONSENT_SCOPE_RESEARCH, 4_999));
+    assert!(!consent.is_active(5_000));
+
+    // Granting again replaces the terms
+    consent.grant(CONSENT_SCOPE_ALL, 0, 6_000).unwrap();
+    assert!(consent.allows(CONSENT_SCOPE_ALL, i64::MAX));
+}
+
+#[test]
+fn invalid_terms_are_rejected() {
+    let mut consent = consent();
+    expect_error(consent.grant(0, 0, 1_000), ErrorCode::InvalidConsentScope);
+    expect_error(
+        consent.grant(CONSENT_SCOPE_ALL + 1, 0, 1_000),
+        ErrorCode::InvalidConsentScope,
+    );
+    expect_error(
+        consent.grant(CONSENT_SCOPE_READ, 1_000, 1_000),
+        ErrorCode::InvalidConsentExpiry,
+    );
+    assert_eq!(consent.scope, 0);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ConsentAccount, EncryptionKey, ErrorCode, HealthDataAccount, ReencryptionRequest, StorageKind,
+    CONSENT_SCOPE_READ, CONSENT_SCOPE_RESEARCH, MAX_KEY_ENVELOPE_LEN,
+};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn consent(scope: u8) -> ConsentAccount {
+    ConsentAccount {
+        patient: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        record: Pubkey::new_unique(),
+        data_hash: [1; 32],
+        scope,
+        granted_at: NOW,
+        expires_at: NOW + DAY,
+        account_version: 2,
+    }
+}
+
+fn record(consent: &ConsentAccount) -> HealthDataAccount {
+    HealthDataAccount {
+        owner: consent.patient,
+        data_hash: consent.data_hash,
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [2; 32],
+        timestamp: NOW,
+        author: consent.patient,
+        derived_from: [0; 32],
+        key_version: 3,
+        account_version: 4,
+    }
+}
+
+fn provider_key(consent: &ConsentAccount) -> EncryptionKey {
+    let mut key = EncryptionKey {
+        owner: Pubkey::default(),
+        x25519_key: [0; 32],
+        updated_at: 0,
+        key_version: 0,
+        account_version: 0,
+    };
+    key.register(consent.provider, [7; 32], NOW).unwrap();
+    key
+}
+
+fn empty() -> ReencryptionRequest {
+    ReencryptionRequest {
+        consent: Pubkey::default(),
+        patient: Pubkey::default(),
+        provider: Pubkey::default(),
+        record: Pubkey::default(),
+        record_key_version: 0,
+        recipient_key: [0; 32],
+        recipient_key_version: 0,
+        wrapped_key: Vec::new(),
+        requested_at: 0,
+        fulfilled_at: 0,
+        account_version: 0,
+    }
+}
+
+#[test]
+fn requests_need_consent_to_read_the_record() {
+    let consent_key = Pubkey::new_unique();
+    let consent = consent(CONSENT_SCOPE_READ);
+    let key = provider_key(&consent);
+    let mut request = empty();
+    request
+        .request(consent_key, &consent, &record(&consent), &key, NOW)
+        .unwrap();
+    assert_eq!(request.consent, consent_key);
+    assert_eq!(request.patient, consent.patient);
+    assert_eq!(request.provider, consent.provider);
+    assert_eq!(request.record, consent.record);
+    assert_eq!(request.record_key_version, 3);
+    assert_eq!(request.recipient_key, [7; 32]);
+    assert_eq!(request.recipient_key_version, 1);
+    assert!(!request.is_fulfilled());
+
+    let research = self::consent(CONSENT_SCOPE_RESEARCH);
+    expect_error(
+        empty().request(consent_key, &research, &record(&research), &key, NOW),
+        ErrorCode::InsufficientConsent,
+    );
+    expect_error(
+        empty().request(consent_key, &consent, &record(&consent), &key, NOW + DAY),
+        ErrorCode::InsufficientConsent,
+    );
+}
+
+#[test]
+fn fulfilling_stores_the_wrapped_key_once() {
+    let consent = consent(CONSENT_SCOPE_READ);
+    let key = provider_key(&consent);
+    let mut request = empty();
+    request
+        .request(Pubkey::new_unique(), &consent, &record(&consent), &key, NOW)
+        .unwrap();
+
+    expect_error(
+        request.fulfill(Vec::new(), &consent, &key, NOW),
+        ErrorCode::InvalidWrappedKey,
+    );
+    expect_error(
+        request.fulfill(vec![1; MAX_KEY_ENVELOPE_LEN + 1], &consent, &key, NOW),
+        ErrorCode::KeyEnvelopeTooLong,
+    );
+    request
+        .fulfill(vec![1; 72], &consent, &key, NOW + 60)
+        .unwrap();
+    assert!(request.is_fulfilled());
+    assert_eq!(request.wrapped_key, vec![1; 72]);
+    assert_eq!(request.fulfilled_at, NOW + 60);
+    expect_error(
+        request.fulfill(vec![2; 72], &consent, &key, NOW + 120),
+        ErrorCode::ReencryptionAlreadyFulfilled,
+    );
+}
+
+#[test]
+fn fulfilling_fails_after_the_provider_rotates_or_consent_lapses() {
+    let consent = consent(CONSENT_SCOPE_READ);
+    let mut key = provider_key(&consent);
+    let mut request = empty();
+    request
+        .request(Pubkey::new_unique(), &consent, &record(&consent), &key, NOW)
+        .unwrap();
+
+    expect_error(
+        request.fulfill(vec![1; 72], &consent, &key, NOW + DAY),
+        ErrorCode::InsufficientConsent,
+    );
+    key.rotate([8; 32], NOW + 60).unwrap();
+    expect_error(
+        request.fulfill(vec![1; 72], &consent, &key, NOW + 120),
+        ErrorCode::ReencryptionKeyStale,
+    );
+
+    // Asking again picks up the new key
+    request
+        .request(
+            request.consent,
+            &consent,
+            &record(&consent),
+            &key,
+            NOW + 180,
+        )
+        .unwrap();
+    assert_eq!(request.recipient_key_version, 2);
+    request
+        .fulfill(vec![1; 72], &consent, &key, NOW + 240)
+        .unwrap();
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ErrorCode, HealthDataAccount, LineItemStatus, SecondOpinion, StorageKind,
+    MAX_SECOND_OPINION_RECORDS,
+};
+
+const NOW: i64 = 1_700_000_000;
+const WEEK: i64 = 7 * 24 * 60 * 60;
+
+fn claim() -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "opinion".to_string(),
+        attachments: vec![[1; 32]],
+        ..common::claim(vec![common::line(100, LineItemStatus::Pending)])
+    }
+}
+
+fn unrequested() -> SecondOpinion {
+    SecondOpinion {
+        claim: Pubkey::default(),
+        patient: Pubkey::default(),
+        consultant: Pubkey::default(),
+        records: Vec::new(),
+        fee: 0,
+        requested_at: 0,
+        expires_at: 0,
+        opinion: [0; 32],
+        submitted_at: 0,
+        account_version: 0,
+    }
+}
+
+fn requested(claim: &ClaimAccount) -> SecondOpinion {
+    let mut opinion = unrequested();
+    opinion
+        .request(
+            claim,
+            Pubkey::new_unique(),
+            vec![[1; 32], [2; 32]],
+            250_000,
+            NOW + WEEK,
+            NOW,
+        )
+        .unwrap();
+    opinion
+}
+
+fn record(owner: Pubkey, data_hash: [u8; 32]) -> HealthDataAccount {
+    HealthDataAccount {
+        owner,
+        data_hash,
+        storage: StorageKind::Ipfs,
+        cid: "bafkrecord".to_string(),
+        commitment: [0xcc; 32],
+        timestamp: NOW,
+        author: Pubkey::default(),
+        derived_from: [0; 32],
+        key_version: 0,
+        account_version: 2,
+    }
+}
+
+#[test]
+fn the_consultant_reads_the_shared_records_until_the_opinion() {
+    let claim = claim();
+    let mut opinion = requested(&claim);
+    assert_eq!(opinion.patient, claim.patient);
+    let shared = record(claim.patient, [2; 32]);
+    opinion
+        .authorize(opinion.consultant, &shared, NOW + 60)
+        .unwrap();
+
+    expect_error(
+        opinion.authorize(claim.provider, &shared, NOW + 60),
+        ErrorCode::Unauthorized,
+    );
+    expect_error(
+        opinion.authorize(opinion.consultant, &record(claim.patient, [3; 32]), NOW),
+        ErrorCode::RecordNotShared,
+    );
+    expect_error(
+        opinion.authorize(
+            opinion.consultant,
+            &record(Pubkey::new_unique(), [1; 32]),
+            NOW,
+        ),
+        ErrorCode::InvalidPatient,
+    );
+
+    opinion.submit([9; 32], NOW + 120).unwrap();
+    assert_eq!(
+        (opinion.opinion, opinion.submitted_at),
+        ([9; 32], NOW + 120)
+    );
+    expect_error(
+        opinion.authorize(opinion.consultant, &shared, NOW + 180),
+        ErrorCode::SecondOpinionClosed,
+    );
+    expect_error(
+        opinion.submit([8; 32], NOW + 180),
+        ErrorCode::SecondOpinionClosed,
+    );
+}
+
+#[test]
+fn access_and_the_fee_lapse_at_expiry() {
+    let claim = claim();
+    let mut opinion = requested(&claim);
+    expect_error(
+        opinion.expect_closable(NOW + WEEK - 1),
+        ErrorCode::SecondOpinionPending,
+    );
+    opinion.expect_closable(NOW + WEEK).unwrap();
+    expect_error(
+        opinion.authorize(
+            opinion.consultant,
+            &record(claim.patient, [1; 32]),
+            NOW + WEEK,
+        ),
+        ErrorCode::SecondOpinionClosed,
+    );
+    expect_error(
+        opinion.submit([9; 32], NOW + WEEK),
+        ErrorCode::SecondOpinionClosed,
+    );
+}
+
+#[test]
+fn a_given_opinion_can_be_closed_at_once() {
+    let claim = claim();
+    let mut opinion = requested(&claim);
+    opinion.submit([9; 32], NOW + 60).unwrap();
+    opinion.expect_closable(NOW + 60).unwrap();
+}
+
+#[test]
+fn requests_need_an_outside_consultant_records_a_fee_and_an_expiry() {
+    let claim = claim();
+    let mut opinion = unrequested();
+    for consultant in [claim.provider, claim.patient] {
+        expect_error(
+            opinion.request(&claim, consultant, vec![[1; 32]], 1, NOW + WEEK, NOW),
+            ErrorCode::InvalidConsultant,
+        );
+    }
+
+    let consultant = Pubkey::new_unique();
+    for (records, fee, expires_at) in [
+        (Vec::new(), 1, NOW + WEEK),
+        (vec![[1; 32]; MAX_SECOND_OPINION_RECORDS + 1], 1, NOW + WEEK),
+        (vec![[1; 32]], 0, NOW + WEEK),
+        (vec![[1; 32]], 1, NOW),
+    ] {
+        expect_error(
+            opinion.request(&claim, consultant, records, fee, expires_at, NOW),
+            ErrorCode::InvalidSecondOpinion,
+        );
+    }
+    expect_error(
+        opinion.request(&claim, consultant, vec![[0; 32]], 1, NOW + WEEK, NOW),
+        ErrorCode::HashEmpty,
+    );
+}
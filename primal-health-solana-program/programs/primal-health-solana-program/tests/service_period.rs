@@ -0,0 +1,63 @@
+mod common;
+
+use common::expect_error;
+use primal_health_solana_program::{ClaimAccount, ErrorCode};
+
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 60 * 60;
+
+fn claim() -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "stay".to_string(),
+        ..common::claim(Vec::new())
+    }
+}
+
+#[test]
+fn care_can_predate_filing() {
+    let mut claim = claim();
+    claim
+        .set_service_period(NOW - 30 * DAY, NOW - 27 * DAY, 0, NOW)
+        .unwrap();
+    assert_eq!(claim.service_start, NOW - 30 * DAY);
+    assert_eq!(claim.service_end, NOW - 27 * DAY);
+    assert_eq!(claim.timestamp, NOW);
+
+    // A single visit starts and ends at once
+    claim.set_service_period(NOW, NOW, 0, NOW).unwrap();
+}
+
+#[test]
+fn care_must_be_over_and_in_order() {
+    let mut claim = claim();
+    expect_error(
+        claim.set_service_period(0, NOW, 0, NOW),
+        ErrorCode::InvalidServiceDate,
+    );
+    expect_error(
+        claim.set_service_period(NOW + 1, NOW + 1, 0, NOW),
+        ErrorCode::InvalidServiceDate,
+    );
+    expect_error(
+        claim.set_service_period(NOW - DAY, NOW - 2 * DAY, 0, NOW),
+        ErrorCode::InvalidServicePeriod,
+    );
+    expect_error(
+        claim.set_service_period(NOW - DAY, NOW + DAY, 0, NOW),
+        ErrorCode::InvalidServicePeriod,
+    );
+    assert_eq!((claim.service_start, claim.service_end), (0, 0));
+}
+
+#[test]
+fn care_must_start_after_enrollment() {
+    let mut claim = claim();
+    let enrolled = NOW - 10 * DAY;
+    expect_error(
+        claim.set_service_period(enrolled - 1, NOW - DAY, enrolled, NOW),
+        ErrorCode::ServiceBeforeEnrollment,
+    );
+    claim
+        .set_service_period(enrolled, NOW - DAY, enrolled, NOW)
+        .unwrap();
+}
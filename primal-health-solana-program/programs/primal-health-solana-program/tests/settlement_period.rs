@@ -0,0 +1,32 @@
+use primal_health_solana_program::settlement::{is_valid, period_end, period_of};
+
+#[test]
+fn timestamps_map_to_their_utc_month() {
+    assert_eq!(period_of(0), 197001);
+    // 2024-02-29T23:59:59Z and the second after
+    assert_eq!(period_of(1_709_251_199), 202402);
+    assert_eq!(period_of(1_709_251_200), 202403);
+    // 2023-12-31T23:59:59Z
+    assert_eq!(period_of(1_704_067_199), 202312);
+    assert_eq!(period_of(1_704_067_200), 202401);
+}
+
+#[test]
+fn periods_end_at_the_start_of_the_next_month() {
+    assert_eq!(period_end(202402), 1_709_251_200);
+    assert_eq!(period_end(202312), 1_704_067_200);
+    for timestamp in (0..2_000_000_000).step_by(86_400 * 7 + 3_601) {
+        let period = period_of(timestamp);
+        assert!(is_valid(period));
+        assert!(timestamp < period_end(period));
+        assert_eq!(period_of(period_end(period) - 1), period);
+        assert_ne!(period_of(period_end(period)), period);
+    }
+}
+
+#[test]
+fn months_outside_one_to_twelve_are_invalid() {
+    assert!(!is_valid(202400));
+    assert!(!is_valid(202413));
+    assert!(is_valid(202412));
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{DataCategory, Subscription};
+
+fn subscription(price_per_record: u64, deposited: u64) -> Subscription {
+    Subscription {
+        patient: Pubkey::new_unique(),
+        subscriber: Pubkey::new_unique(),
+        category: DataCategory::LabResult,
+        price_per_record,
+        deposited,
+        accrued: 0,
+        withdrawn: 0,
+        delivered: 0,
+        started_at: 1_700_000_000,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn deliveries_accrue_until_the_deposit_runs_out() {
+    let mut subscription = subscription(400, 1_000);
+    assert!(subscription.accrue());
+    assert!(subscription.accrue());
+    assert!(!subscription.accrue());
+    assert_eq!(subscription.accrued, 800);
+    assert_eq!(subscription.delivered, 2);
+
+    // Topping up resumes payment for the next record
+    subscription.deposited += 200;
+    assert!(subscription.accrue());
+    assert_eq!(subscription.accrued, 1_200);
+}
+
+#[test]
+fn withdrawals_leave_only_new_accruals_withdrawable() {
+    let mut subscription = subscription(250, 1_000);
+    subscription.accrue();
+    subscription.accrue();
+    assert_eq!(subscription.withdrawable(), 500);
+
+    subscription.withdrawn += subscription.withdrawable();
+    assert_eq!(subscription.withdrawable(), 0);
+    subscription.accrue();
+    assert_eq!(subscription.withdrawable(), 250);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    DataCategory, ErrorCode, PlanSubscription, SubscriptionPlan, GRANT_SCOPE_DELEGATE,
+    GRANT_SCOPE_READ, GRANT_SCOPE_WRITE,
+};
+
+const NOW: i64 = 1_700_000_000;
+const MONTH: i64 = 30 * 24 * 60 * 60;
+
+fn plan() -> SubscriptionPlan {
+    let mut plan = SubscriptionPlan {
+        owner: Pubkey::new_unique(),
+        plan_id: "research-monthly".to_string(),
+        price_per_period: 0,
+        period_secs: 0,
+        categories: 0,
+        scope: 0,
+        periods_sold: 0,
+        created_at: NOW,
+        account_version: 1,
+    };
+    plan.set_terms(
+        50_000,
+        MONTH,
+        DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+        GRANT_SCOPE_READ,
+    )
+    .unwrap();
+    plan
+}
+
+fn subscribe(plan: &mut SubscriptionPlan, now: i64) -> PlanSubscription {
+    let mut subscription = PlanSubscription {
+        plan: Pubkey::new_unique(),
+        owner: plan.owner,
+        subscriber: Pubkey::new_unique(),
+        categories: 0,
+        scope: 0,
+        current_period_end: 0,
+        periods_paid: 0,
+        started_at: now,
+        account_version: 1,
+    };
+    subscription.renew(plan, now).unwrap();
+    subscription
+}
+
+#[test]
+fn plans_need_a_price_a_period_and_records_to_cover() {
+    let mut plan = plan();
+    let lab = DataCategory::LabResult.bit();
+    expect_error(
+        plan.set_terms(0, MONTH, lab, GRANT_SCOPE_READ),
+        ErrorCode::InvalidPlanTerms,
+    );
+    expect_error(
+        plan.set_terms(50_000, 0, lab, GRANT_SCOPE_READ),
+        ErrorCode::InvalidPlanTerms,
+    );
+    expect_error(
+        plan.set_terms(50_000, MONTH, 0, GRANT_SCOPE_READ),
+        ErrorCode::InvalidPlanTerms,
+    );
+    expect_error(
+        plan.set_terms(50_000, MONTH, lab, 0),
+        ErrorCode::InvalidPlanTerms,
+    );
+    expect_error(
+        plan.set_terms(50_000, MONTH, lab, GRANT_SCOPE_READ | GRANT_SCOPE_DELEGATE),
+        ErrorCode::InvalidPlanTerms,
+    );
+    plan.set_terms(80_000, MONTH, lab, GRANT_SCOPE_READ | GRANT_SCOPE_WRITE)
+        .unwrap();
+    assert_eq!(plan.price_per_period, 80_000);
+    assert_eq!(plan.categories, lab);
+}
+
+#[test]
+fn a_subscription_covers_the_plan_until_its_period_ends() {
+    let mut plan = plan();
+    let subscription = subscribe(&mut plan, NOW);
+    assert_eq!(subscription.current_period_end, NOW + MONTH);
+    assert_eq!(subscription.periods_paid, 1);
+    assert_eq!(plan.periods_sold, 1);
+
+    assert!(subscription.allows(DataCategory::LabResult, GRANT_SCOPE_READ, NOW));
+    assert!(subscription.allows(DataCategory::Vitals, GRANT_SCOPE_READ, NOW + MONTH - 1));
+    assert!(!subscription.allows(DataCategory::Vitals, GRANT_SCOPE_READ, NOW + MONTH));
+    assert!(!subscription.allows(DataCategory::Imaging, GRANT_SCOPE_READ, NOW));
+    assert!(!subscription.allows(DataCategory::LabResult, GRANT_SCOPE_WRITE, NOW));
+}
+
+#[test]
+fn renewing_early_extends_from_the_period_end() {
+    let mut plan = plan();
+    let mut subscription = subscribe(&mut plan, NOW);
+    subscription.renew(&mut plan, NOW + 10).unwrap();
+    assert_eq!(subscription.current_period_end, NOW + 2 * MONTH);
+    assert_eq!(subscription.periods_paid, 2);
+    assert_eq!(plan.periods_sold, 2);
+}
+
+#[test]
+fn renewing_after_a_lapse_starts_a_new_period_now() {
+    let mut plan = plan();
+    let mut subscription = subscribe(&mut plan, NOW);
+    let later = NOW + 3 * MONTH;
+    assert!(!subscription.is_active(later));
+    subscription.renew(&mut plan, later).unwrap();
+    assert_eq!(subscription.current_period_end, later + MONTH);
+    assert!(subscription.is_active(later));
+    assert_eq!(subscription.started_at, NOW);
+}
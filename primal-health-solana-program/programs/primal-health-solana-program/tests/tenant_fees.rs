@@ -0,0 +1,34 @@
+use anchor_lang::prelude::Pubkey;
+use primal_health_solana_program::{Tenant, MAX_TENANT_FEE_BPS};
+
+fn tenant(fee_bps: u16) -> Tenant {
+    Tenant {
+        admin: Pubkey::new_unique(),
+        tenant_id: "north".into(),
+        treasury: Pubkey::new_unique(),
+        fee_bps,
+        member_count: 0,
+        created_at: 1_700_000_000,
+        updated_at: 1_700_000_000,
+        account_version: 1,
+    }
+}
+
+#[test]
+fn fees_are_a_share_of_the_payment_rounded_down() {
+    assert_eq!(tenant(250).fee_on(1_000_000).unwrap(), 25_000);
+    assert_eq!(tenant(250).fee_on(39).unwrap(), 0);
+    assert_eq!(tenant(250).fee_on(41).unwrap(), 1);
+}
+
+#[test]
+fn tenants_without_a_fee_charge_nothing() {
+    assert_eq!(tenant(0).fee_on(u64::MAX).unwrap(), 0);
+}
+
+#[test]
+fn the_largest_fee_does_not_overflow() {
+    let fee = tenant(MAX_TENANT_FEE_BPS).fee_on(u64::MAX).unwrap();
+    // 10% of the whole range, computed without an intermediate overflow
+    assert_eq!(fee, u64::MAX / 10);
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::expect_error;
+use primal_health_solana_program::{
+    ClaimAccount, ClaimStatus, ErrorCode, LineDecision, LineItemStatus,
+};
+
+const NOW: i64 = 1_700_000_000;
+
+fn claim(mint: Pubkey) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "usdc".to_string(),
+        attachments: vec![[0xab; 32]],
+        timestamp: NOW - 60,
+        mint,
+        ..common::claim(vec![common::line(25_000_000, LineItemStatus::Pending)])
+    }
+}
+
+#[test]
+fn token_claims_are_only_paid_in_their_mint() {
+    let usdc = Pubkey::new_unique();
+    let claim = claim(usdc);
+    claim.expect_mint(&usdc).unwrap();
+    expect_error(
+        claim.expect_mint(&Pubkey::default()),
+        ErrorCode::ClaimMintMismatch,
+    );
+    expect_error(
+        claim.expect_mint(&Pubkey::new_unique()),
+        ErrorCode::ClaimMintMismatch,
+    );
+}
+
+#[test]
+fn lamport_claims_take_no_mint() {
+    let claim = claim(Pubkey::default());
+    claim.expect_mint(&Pubkey::default()).unwrap();
+    expect_error(
+        claim.expect_mint(&Pubkey::new_unique()),
+        ErrorCode::ClaimMintMismatch,
+    );
+}
+
+#[test]
+fn token_claims_settle_in_base_units() {
+    let mut claim = claim(Pubkey::new_unique());
+    claim.accept().unwrap();
+    claim.adjudicate_line(0, LineDecision::Approve, 0).unwrap();
+    claim.verify(ClaimStatus::Verified, NOW).unwrap();
+    assert_eq!(claim.settle_payment(NOW).unwrap(), 25_000_000);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{expect_error, line};
+use primal_health_solana_program::{
+    ClaimAccount, ClaimLineItem, ClaimStatus, ErrorCode, LineItemStatus, PatientYearSummary,
+};
+
+/// 2023-11-14, and a year earlier.
+const NOW: i64 = 1_700_000_000;
+const LAST_YEAR: i64 = NOW - 365 * 86_400;
+
+fn claim(patient: Pubkey, status: ClaimStatus, line_items: Vec<ClaimLineItem>) -> ClaimAccount {
+    ClaimAccount {
+        claim_id: "summary".to_string(),
+        patient,
+        status,
+        service_start: NOW,
+        service_end: NOW,
+        ..common::claim(line_items)
+    }
+}
+
+#[test]
+fn claims_are_totalled_by_status() {
+    let patient = Pubkey::new_unique();
+    let mut summary = PatientYearSummary::new(patient, 2023, Pubkey::default(), 1_000);
+
+    let pending = claim(
+        patient,
+        ClaimStatus::UnderReview,
+        vec![line(400, LineItemStatus::Pending)],
+    );
+    let rejected = claim(
+        patient,
+        ClaimStatus::Rejected,
+        vec![line(300, LineItemStatus::Denied)],
+    );
+    let mut paid = claim(
+        patient,
+        ClaimStatus::Paid,
+        vec![
+            line(5_000, LineItemStatus::Approved),
+            line(500, LineItemStatus::Denied),
+        ],
+    );
+    paid.principal_paid = 5_000;
+    paid.interest_paid = 25;
+    for claim in [&pending, &rejected, &paid] {
+        assert!(summary.add(claim).unwrap());
+    }
+
+    assert_eq!(summary.claims, 3);
+    assert_eq!(summary.billed, 6_200);
+    assert_eq!(summary.pending, 400);
+    assert_eq!(summary.approved, 5_000);
+    assert_eq!((summary.principal_paid, summary.interest_paid), (5_000, 25));
+    assert_eq!(summary.out_of_pocket, 800);
+    assert_eq!(summary.deductible_met, 800);
+}
+
+#[test]
+fn the_deductible_is_met_at_most_once() {
+    let patient = Pubkey::new_unique();
+    let mut summary = PatientYearSummary::new(patient, 2023, Pubkey::default(), 500);
+    let rejected = claim(
+        patient,
+        ClaimStatus::Rejected,
+        vec![line(300, LineItemStatus::Denied)],
+    );
+    summary.add(&rejected).unwrap();
+    summary.add(&rejected).unwrap();
+    assert_eq!(summary.out_of_pocket, 600);
+    assert_eq!(summary.deductible_met, 500);
+}
+
+#[test]
+fn claims_in_other_years_or_currencies_are_skipped() {
+    let patient = Pubkey::new_unique();
+    let mut summary = PatientYearSummary::new(patient, 2023, Pubkey::default(), 0);
+
+    let mut last_year = claim(
+        patient,
+        ClaimStatus::Pending,
+        vec![line(100, LineItemStatus::Pending)],
+    );
+    last_year.service_start = LAST_YEAR;
+    let mut in_usdc = claim(
+        patient,
+        ClaimStatus::Pending,
+        vec![line(100, LineItemStatus::Pending)],
+    );
+    in_usdc.mint = Pubkey::new_unique();
+    assert!(!summary.add(&last_year).unwrap());
+    assert!(!summary.add(&in_usdc).unwrap());
+    assert_eq!(
+        summary,
+        PatientYearSummary::new(patient, 2023, Pubkey::default(), 0)
+    );
+}
+
+#[test]
+fn claims_without_a_service_period_count_by_filing_date() {
+    let patient = Pubkey::new_unique();
+    let mut summary = PatientYearSummary::new(patient, 2022, Pubkey::default(), 0);
+    let mut filed_last_year = claim(
+        patient,
+        ClaimStatus::Pending,
+        vec![line(100, LineItemStatus::Pending)],
+    );
+    filed_last_year.timestamp = LAST_YEAR;
+    filed_last_year.service_start = 0;
+    filed_last_year.service_end = 0;
+    assert!(summary.add(&filed_last_year).unwrap());
+    assert_eq!(summary.pending, 100);
+}
+
+#[test]
+fn other_patients_claims_are_refused() {
+    let mut summary = PatientYearSummary::new(Pubkey::new_unique(), 2023, Pubkey::default(), 0);
+    let other = claim(
+        Pubkey::new_unique(),
+        ClaimStatus::Pending,
+        vec![line(100, LineItemStatus::Pending)],
+    );
+    expect_error(summary.add(&other), ErrorCode::InvalidPatient);
+}
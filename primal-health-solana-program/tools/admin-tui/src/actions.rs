@@ -0,0 +1,185 @@
+//! The admin instructions the dashboard can send, each checked against the
+//! latest snapshot before it is offered for confirmation.
+
+use anchor_client::anchor_lang::solana_program::instruction::Instruction;
+use anchor_client::anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature, Signer};
+use anchor_client::solana_sdk::transaction::Transaction;
+use anyhow::{bail, Context, Result};
+use primal_health_solana_program::migration::Versioned;
+use primal_health_solana_program::{accounts, instruction, ProviderCredential, ID};
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::state::{pda, Snapshot};
+
+pub enum Action {
+    /// `set_paused`, as the config admin.
+    SetPaused(bool),
+    /// `resolve_dispute` on `claim`, as its arbiter or, past the deadline,
+    /// as anyone.
+    Resolve {
+        claim: Pubkey,
+        patient: Pubkey,
+        provider: Pubkey,
+        patient_prevails: bool,
+    },
+    /// `revoke_provider`, as the credentialing registry authority.
+    Suspend { provider: Pubkey },
+}
+
+impl Action {
+    pub fn describe(&self) -> String {
+        match self {
+            Action::SetPaused(true) => "Pause the program".to_string(),
+            Action::SetPaused(false) => "Resume the program".to_string(),
+            Action::Resolve {
+                claim,
+                patient_prevails,
+                ..
+            } => format!(
+                "Resolve the dispute of claim {claim} for the {}",
+                if *patient_prevails {
+                    "patient"
+                } else {
+                    "provider"
+                }
+            ),
+            Action::Suspend { provider } => format!("Revoke the credential of provider {provider}"),
+        }
+    }
+
+    /// Fails with the reason the program would reject the action from
+    /// `signer`, so it is never sent just to fail.
+    pub fn check(&self, rpc: &RpcClient, snapshot: &Snapshot, signer: &Pubkey) -> Result<()> {
+        match self {
+            Action::SetPaused(paused) => {
+                let config = snapshot.config.as_ref().context("no program config")?;
+                if config.admin != *signer {
+                    bail!("only the config admin {} can pause", config.admin);
+                }
+                if config.paused == *paused {
+                    bail!("the program is already {}", state_name(*paused));
+                }
+            }
+            Action::Resolve {
+                claim,
+                patient_prevails,
+                ..
+            } => {
+                let (_, dispute) = snapshot
+                    .disputes
+                    .iter()
+                    .find(|(_, dispute)| dispute.claim == *claim)
+                    .context("the dispute is no longer open")?;
+                if snapshot.now < dispute.deadline && dispute.arbiter != *signer {
+                    bail!(
+                        "only the arbiter {} can resolve before the deadline",
+                        dispute.arbiter
+                    );
+                }
+                if snapshot.now >= dispute.deadline && !patient_prevails {
+                    bail!("past the deadline the dispute is always decided for the patient");
+                }
+                expect_running(snapshot)?;
+            }
+            Action::Suspend { provider } => {
+                let registry = snapshot
+                    .registry
+                    .as_ref()
+                    .context("no credentialing registry")?;
+                if registry.authority != *signer {
+                    bail!(
+                        "only the registry authority {} can revoke credentials",
+                        registry.authority
+                    );
+                }
+                let credential = rpc
+                    .get_account(&pda(&[b"provider_credential", provider.as_ref()]))
+                    .ok()
+                    .and_then(|account| ProviderCredential::decode_any(&account.data).ok());
+                if !credential.is_some_and(|credential| credential.verified) {
+                    bail!("provider {provider} holds no verified credential");
+                }
+                expect_running(snapshot)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn instruction(&self, signer: Pubkey) -> Instruction {
+        let config = pda(&[b"config"]);
+        let (accounts, data) = match self {
+            Action::SetPaused(paused) => (
+                accounts::SetConfig {
+                    config,
+                    admin: signer,
+                }
+                .to_account_metas(None),
+                instruction::SetPaused { paused: *paused }.data(),
+            ),
+            Action::Resolve {
+                claim,
+                patient,
+                provider,
+                patient_prevails,
+            } => (
+                accounts::ResolveDispute {
+                    claim_account: *claim,
+                    claim_dispute: pda(&[b"claim_dispute", claim.as_ref()]),
+                    resolver: signer,
+                    crank_vault: None,
+                    patient: *patient,
+                    provider: *provider,
+                    config,
+                }
+                .to_account_metas(None),
+                instruction::ResolveDispute {
+                    patient_prevails: *patient_prevails,
+                }
+                .data(),
+            ),
+            Action::Suspend { provider } => (
+                accounts::RevokeProvider {
+                    registry_authority: pda(&[b"registry_authority"]),
+                    provider_credential: pda(&[b"provider_credential", provider.as_ref()]),
+                    provider: *provider,
+                    authority: signer,
+                    config,
+                }
+                .to_account_metas(None),
+                instruction::RevokeProvider {}.data(),
+            ),
+        };
+        Instruction {
+            program_id: ID,
+            accounts,
+            data,
+        }
+    }
+
+    pub fn send(&self, rpc: &RpcClient, signer: &Keypair) -> Result<Signature> {
+        let tx = Transaction::new_signed_with_payer(
+            &[self.instruction(signer.pubkey())],
+            Some(&signer.pubkey()),
+            &[signer],
+            rpc.get_latest_blockhash()?,
+        );
+        Ok(rpc.send_and_confirm_transaction(&tx)?)
+    }
+}
+
+pub fn state_name(paused: bool) -> &'static str {
+    if paused {
+        "paused"
+    } else {
+        "running"
+    }
+}
+
+fn expect_running(snapshot: &Snapshot) -> Result<()> {
+    if snapshot.config.as_ref().is_some_and(|config| config.paused) {
+        bail!("the program is paused; resume it first");
+    }
+    Ok(())
+}
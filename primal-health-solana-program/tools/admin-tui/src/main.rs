@@ -0,0 +1,257 @@
+//! Terminal dashboard for operators.
+//!
+//! Shows program config and pause state, account counts, claims by status,
+//! open disputes with their deadlines and claims the fraud heuristics flag,
+//! refreshed on an interval. With `--keypair` it can also pause or resume the
+//! program, resolve a dispute and revoke a provider's credential; each action
+//! is checked against the latest state and confirmed before it is sent.
+//!
+//! Usage: `admin-tui [--rpc <url>] [--keypair <path>] [--refresh <secs>]`.
+//! Without `--keypair` the dashboard is read-only.
+
+mod actions;
+mod state;
+mod ui;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use anyhow::{anyhow, bail, Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::DefaultTerminal;
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::actions::Action;
+use crate::state::Snapshot;
+
+const DEFAULT_RPC: &str = "http://127.0.0.1:8899";
+
+struct Options {
+    rpc_url: String,
+    keypair: Option<PathBuf>,
+    refresh: Duration,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        rpc_url: DEFAULT_RPC.to_string(),
+        keypair: None,
+        refresh: Duration::from_secs(10),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--rpc" => options.rpc_url = value()?,
+            "--keypair" => options.keypair = Some(value()?.into()),
+            "--refresh" => {
+                options.refresh = Duration::from_secs(
+                    value()?
+                        .parse()
+                        .context("--refresh must be whole seconds")?,
+                )
+            }
+            other => bail!("unknown argument {other}"),
+        }
+    }
+    if options.refresh.is_zero() {
+        bail!("--refresh must be positive");
+    }
+    Ok(options)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Overview,
+    Disputes,
+    Flagged,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 3] = [Tab::Overview, Tab::Disputes, Tab::Flagged];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Disputes => "Disputes",
+            Tab::Flagged => "Flagged claims",
+        }
+    }
+}
+
+pub struct App {
+    rpc: RpcClient,
+    pub rpc_url: String,
+    keypair: Option<Keypair>,
+    pub signer: Option<Pubkey>,
+    pub tab: Tab,
+    /// Row selected in the disputes or flagged claims table.
+    pub selected: usize,
+    pub snapshot: Option<Snapshot>,
+    /// Outcome of the last refresh or action.
+    pub status: String,
+    /// Action awaiting confirmation.
+    pub pending: Option<Action>,
+}
+
+impl App {
+    fn rows(&self) -> usize {
+        match (&self.snapshot, self.tab) {
+            (Some(snapshot), Tab::Disputes) => snapshot.disputes.len(),
+            (Some(snapshot), Tab::Flagged) => snapshot.flagged.len(),
+            _ => 0,
+        }
+    }
+
+    fn refresh(&mut self) {
+        match state::fetch(&self.rpc) {
+            Ok(snapshot) => {
+                self.snapshot = Some(snapshot);
+                self.selected = self.selected.min(self.rows().saturating_sub(1));
+            }
+            Err(err) => self.status = format!("refresh failed: {err:#}"),
+        }
+    }
+
+    fn switch_tab(&mut self, step: usize) {
+        let index = (self.tab as usize + step) % Tab::ALL.len();
+        self.tab = Tab::ALL[index];
+        self.selected = 0;
+    }
+
+    /// The action a key asks for on the current tab and selection.
+    fn action(&self, key: char) -> Option<Action> {
+        let snapshot = self.snapshot.as_ref()?;
+        match (self.tab, key) {
+            (Tab::Overview, 'p') => {
+                let paused = snapshot.config.as_ref().is_some_and(|config| config.paused);
+                Some(Action::SetPaused(!paused))
+            }
+            (Tab::Disputes, 'a' | 'd') => {
+                let (_, dispute) = snapshot.disputes.get(self.selected)?;
+                Some(Action::Resolve {
+                    claim: dispute.claim,
+                    patient: dispute.patient,
+                    provider: dispute.provider,
+                    patient_prevails: key == 'a',
+                })
+            }
+            (Tab::Flagged, 's') => {
+                let claim = snapshot.flagged.get(self.selected)?;
+                Some(Action::Suspend {
+                    provider: claim.provider,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Offers `action` for confirmation if the program would accept it.
+    fn propose(&mut self, action: Action) {
+        let (Some(signer), Some(snapshot)) = (&self.signer, &self.snapshot) else {
+            self.status = "read-only; pass --keypair to act".to_string();
+            return;
+        };
+        match action.check(&self.rpc, snapshot, signer) {
+            Ok(()) => self.pending = Some(action),
+            Err(err) => self.status = format!("{}: {err:#}", action.describe()),
+        }
+    }
+
+    fn confirm(&mut self) {
+        let (Some(action), Some(keypair)) = (self.pending.take(), &self.keypair) else {
+            return;
+        };
+        let sent = action.send(&self.rpc, keypair);
+        self.refresh();
+        self.status = match sent {
+            Ok(signature) => format!("{}: sent {signature}", action.describe()),
+            Err(err) => format!("{}: failed: {err:#}", action.describe()),
+        };
+    }
+
+    /// Handles one key press; false once the user quits.
+    fn on_key(&mut self, key: KeyCode) -> bool {
+        if self.pending.is_some() {
+            match key {
+                KeyCode::Char('y') => self.confirm(),
+                KeyCode::Char('n') | KeyCode::Esc => {
+                    self.pending = None;
+                    self.status = "cancelled".to_string();
+                }
+                _ => {}
+            }
+            return true;
+        }
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('r') => {
+                self.status = "refreshed".to_string();
+                self.refresh();
+            }
+            KeyCode::Right | KeyCode::Tab => self.switch_tab(1),
+            KeyCode::Left | KeyCode::BackTab => self.switch_tab(Tab::ALL.len() - 1),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.rows().saturating_sub(1)),
+            KeyCode::Char(key) => {
+                if let Some(action) = self.action(key) {
+                    self.propose(action);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App, refresh: Duration) -> Result<()> {
+    let mut last_refresh = Instant::now();
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        let timeout = refresh.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !app.on_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+        if last_refresh.elapsed() >= refresh {
+            app.refresh();
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let options = parse_args()?;
+    let keypair = options
+        .keypair
+        .as_ref()
+        .map(|path| {
+            read_keypair_file(path).map_err(|err| anyhow!("cannot read {}: {err}", path.display()))
+        })
+        .transpose()?;
+    let rpc =
+        RpcClient::new_with_commitment(options.rpc_url.clone(), CommitmentConfig::confirmed());
+    let mut app = App {
+        rpc,
+        rpc_url: options.rpc_url,
+        signer: keypair.as_ref().map(Keypair::pubkey),
+        keypair,
+        tab: Tab::Overview,
+        selected: 0,
+        snapshot: None,
+        status: String::new(),
+        pending: None,
+    };
+    app.refresh();
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, options.refresh);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,207 @@
+//! Reads the program state the dashboard shows.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anchor_client::anchor_lang::Discriminator;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{Context, Result};
+use primal_health_solana_program::migration::{decode_claim, Versioned};
+use primal_health_solana_program::{
+    ClaimAccount, ClaimDispute, ClaimStatus, Config, CrankVault, DisputeConfig, HealthDataAccount,
+    PatientAccount, ProtocolTreasury, ProviderAccount, ProviderClaimStats, RegistryAuthority,
+    FRAUD_MAX_CLAIMS_PER_DAY, FRAUD_MAX_DUPLICATE_CODE_BPS, ID,
+};
+use solana_account_decoder_client_types::{UiAccountEncoding, UiDataSliceConfig};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// A singleton account with the lamports it holds.
+pub struct Held<T> {
+    pub account: T,
+    pub lamports: u64,
+}
+
+/// An undecided claim worth an operator's attention.
+pub struct FlaggedClaim {
+    pub address: Pubkey,
+    pub claim_id: String,
+    pub provider: Pubkey,
+    pub status: ClaimStatus,
+    pub amount: u64,
+    /// `Pubkey::default()` for a claim billed in lamports.
+    pub mint: Pubkey,
+    pub reason: String,
+}
+
+/// Program state as of one refresh.
+pub struct Snapshot {
+    /// Cluster time of the refresh, for deadlines.
+    pub now: i64,
+    pub config: Option<Config>,
+    pub dispute_config: Option<DisputeConfig>,
+    pub registry: Option<RegistryAuthority>,
+    pub treasury: Option<Held<ProtocolTreasury>>,
+    pub crank_vault: Option<Held<CrankVault>>,
+    pub patients: usize,
+    pub providers: usize,
+    pub records: usize,
+    /// Claim count by status.
+    pub claims: BTreeMap<String, usize>,
+    /// Open disputes, soonest deadline first.
+    pub disputes: Vec<(Pubkey, ClaimDispute)>,
+    pub flagged: Vec<FlaggedClaim>,
+}
+
+fn filters<T: Discriminator>() -> Vec<RpcFilterType> {
+    vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        T::DISCRIMINATOR,
+    ))]
+}
+
+/// Data of every program account of type `T`.
+fn program_accounts<T: Discriminator>(rpc: &RpcClient) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters::<T>()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc
+        .get_program_accounts_with_config(&ID, config)
+        .context("getProgramAccounts failed")?;
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect())
+}
+
+/// Number of program accounts of type `T`, without downloading their data.
+fn count<T: Discriminator>(rpc: &RpcClient) -> Result<usize> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters::<T>()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: 0,
+                length: 0,
+            }),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    Ok(rpc
+        .get_program_accounts_with_config(&ID, config)
+        .context("getProgramAccounts failed")?
+        .len())
+}
+
+/// The singleton at `seeds`, in any layout, if it exists.
+fn singleton<T: Versioned>(rpc: &RpcClient, seeds: &[&[u8]]) -> Result<Option<Held<T>>> {
+    let account = rpc
+        .get_account_with_commitment(&pda(seeds), rpc.commitment())
+        .context("getAccountInfo failed")?
+        .value;
+    Ok(account.and_then(|account| {
+        T::decode_any(&account.data).ok().map(|decoded| Held {
+            account: decoded,
+            lamports: account.lamports,
+        })
+    }))
+}
+
+/// Why claims against `stats`' provider are flagged, if they are.
+fn provider_flag(stats: &ProviderClaimStats, now: i64) -> Option<String> {
+    if stats.fraud_findings > 0 {
+        return Some(format!("provider ruled fraud {}x", stats.fraud_findings));
+    }
+    if stats.day == now.div_euclid(SECS_PER_DAY) && stats.claims_today > FRAUD_MAX_CLAIMS_PER_DAY {
+        return Some(format!("provider filed {} today", stats.claims_today));
+    }
+    let duplicate_bps = stats.duplicate_code_bps();
+    if duplicate_bps > FRAUD_MAX_DUPLICATE_CODE_BPS {
+        return Some(format!(
+            "provider duplicate codes {}.{:02}%",
+            duplicate_bps / 100,
+            duplicate_bps % 100
+        ));
+    }
+    None
+}
+
+pub fn fetch(rpc: &RpcClient) -> Result<Snapshot> {
+    let slot = rpc.get_slot()?;
+    let now = rpc.get_block_time(slot).context("getBlockTime failed")?;
+
+    let flagged_providers: HashMap<Pubkey, String> = program_accounts::<ProviderClaimStats>(rpc)?
+        .into_iter()
+        .filter_map(|(_, data)| ProviderClaimStats::decode_any(&data).ok())
+        .filter_map(|stats| provider_flag(&stats, now).map(|flag| (stats.provider, flag)))
+        .collect();
+
+    let mut claims = BTreeMap::new();
+    let mut flagged = Vec::new();
+    for (address, data) in program_accounts::<ClaimAccount>(rpc)? {
+        let Ok((claim, _)) = decode_claim(&data) else {
+            continue;
+        };
+        *claims.entry(format!("{:?}", claim.status)).or_insert(0) += 1;
+
+        let reason = match claim.status {
+            ClaimStatus::OnHold => Some("on hold".to_string()),
+            ClaimStatus::Pending | ClaimStatus::UnderReview => {
+                flagged_providers.get(&claim.provider).cloned()
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            flagged.push(FlaggedClaim {
+                address,
+                claim_id: claim.claim_id,
+                provider: claim.provider,
+                status: claim.status,
+                amount: claim.amount,
+                mint: claim.mint,
+                reason,
+            });
+        }
+    }
+    flagged.sort_by(|a, b| a.claim_id.cmp(&b.claim_id));
+
+    let mut disputes: Vec<(Pubkey, ClaimDispute)> = program_accounts::<ClaimDispute>(rpc)?
+        .into_iter()
+        .filter_map(|(address, data)| {
+            ClaimDispute::decode_any(&data)
+                .ok()
+                .map(|dispute| (address, dispute))
+        })
+        .filter(|(_, dispute)| dispute.resolved_at == 0)
+        .collect();
+    disputes.sort_by_key(|(_, dispute)| dispute.deadline);
+
+    Ok(Snapshot {
+        now,
+        config: singleton::<Config>(rpc, &[b"config"])?.map(|held| held.account),
+        dispute_config: singleton::<DisputeConfig>(rpc, &[b"dispute_config"])?
+            .map(|held| held.account),
+        registry: singleton::<RegistryAuthority>(rpc, &[b"registry_authority"])?
+            .map(|held| held.account),
+        treasury: singleton(rpc, &[b"protocol_treasury"])?,
+        crank_vault: singleton(rpc, &[b"crank_vault"])?,
+        patients: count::<PatientAccount>(rpc)?,
+        providers: count::<ProviderAccount>(rpc)?,
+        records: count::<HealthDataAccount>(rpc)?,
+        claims,
+        disputes,
+        flagged,
+    })
+}
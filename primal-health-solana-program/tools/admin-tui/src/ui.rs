@@ -0,0 +1,290 @@
+//! Draws the dashboard.
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Row, Table, TableState, Tabs, Wrap};
+use ratatui::Frame;
+
+use crate::actions::state_name;
+use crate::state::Snapshot;
+use crate::{App, Tab};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// `abcd…wxyz`, enough to tell accounts apart in a column.
+fn short(key: &Pubkey) -> String {
+    let key = key.to_string();
+    format!("{}…{}", &key[..4], &key[key.len() - 4..])
+}
+
+fn sol(lamports: u64) -> String {
+    format!(
+        "{}.{:09} SOL",
+        lamports / LAMPORTS_PER_SOL,
+        lamports % LAMPORTS_PER_SOL
+    )
+}
+
+/// `in 2d 3h` or `5h ago`, from `now` to `at`.
+fn relative(at: i64, now: i64) -> String {
+    let secs = (at - now).unsigned_abs();
+    let span = match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3_599 => format!("{}m", secs / 60),
+        3_600..=86_399 => format!("{}h {}m", secs / 3_600, secs % 3_600 / 60),
+        _ => format!("{}d {}h", secs / 86_400, secs % 86_400 / 3_600),
+    };
+    if at >= now {
+        format!("in {span}")
+    } else {
+        format!("{span} ago")
+    }
+}
+
+fn field(name: &str, value: impl Into<String>) -> Line<'static> {
+    Line::from(vec![format!("{name:<20}").bold(), value.into().into()])
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(4),
+    ])
+    .areas(frame.area());
+
+    let state = match app.snapshot.as_ref().and_then(|s| s.config.as_ref()) {
+        Some(config) if config.paused => state_name(true).red().bold(),
+        Some(_) => state_name(false).green().bold(),
+        None => "no config".yellow(),
+    };
+    let title = Line::from(vec![
+        " Primal Health admin · ".into(),
+        app.rpc_url.clone().into(),
+        " · ".into(),
+        state,
+        " ".into(),
+    ]);
+    let tabs = Tabs::new(Tab::ALL.iter().map(|tab| tab.title()))
+        .select(app.tab as usize)
+        .highlight_style(Style::new().reversed())
+        .block(Block::bordered().title(title));
+    frame.render_widget(tabs, header);
+
+    match &app.snapshot {
+        Some(snapshot) => match app.tab {
+            Tab::Overview => draw_overview(frame, body, snapshot),
+            Tab::Disputes => draw_disputes(frame, body, snapshot, app.selected),
+            Tab::Flagged => draw_flagged(frame, body, snapshot, app.selected),
+        },
+        None => frame.render_widget(Paragraph::new("Loading…").block(Block::bordered()), body),
+    }
+
+    let keys = match app.tab {
+        Tab::Overview => "p pause/resume",
+        Tab::Disputes => "a uphold for patient · d decide for provider",
+        Tab::Flagged => "s revoke provider credential",
+    };
+    let signer = match &app.signer {
+        Some(signer) => format!("signing as {signer}"),
+        None => "read-only; pass --keypair to act".to_string(),
+    };
+    let help = vec![
+        Line::from(format!(
+            "←/→ tab · ↑/↓ select · {keys} · r refresh · q quit · {signer}"
+        )),
+        Line::from(app.status.clone()).fg(Color::Cyan),
+    ];
+    frame.render_widget(
+        Paragraph::new(help)
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered()),
+        footer,
+    );
+
+    if let Some(action) = &app.pending {
+        let area = popup(frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(action.describe()),
+                Line::from(""),
+                Line::from("y send · n cancel".bold()),
+            ])
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title(" Confirm ").yellow()),
+            area,
+        );
+    }
+}
+
+fn popup(area: Rect) -> Rect {
+    let [_, row, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(6),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, popup, _] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Percentage(60),
+        Constraint::Fill(1),
+    ])
+    .areas(row);
+    popup
+}
+
+fn draw_overview(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let [stats, config] =
+        Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(area);
+
+    let mut lines = vec![
+        field("Patients", snapshot.patients.to_string()),
+        field("Providers", snapshot.providers.to_string()),
+        field("Health records", snapshot.records.to_string()),
+        field("Open disputes", snapshot.disputes.len().to_string()),
+        field("Flagged claims", snapshot.flagged.len().to_string()),
+        Line::from(""),
+        Line::from("Claims by status".bold().underlined()),
+    ];
+    lines.extend(
+        snapshot
+            .claims
+            .iter()
+            .map(|(status, count)| field(status, count.to_string())),
+    );
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Program stats ")),
+        stats,
+    );
+
+    let mut lines = Vec::new();
+    match &snapshot.config {
+        Some(config) => lines.extend([
+            field("Admin", config.admin.to_string()),
+            field("State", state_name(config.paused)),
+            field("Protocol fee", format!("{} bps", config.fee_bps)),
+            field(
+                "Claim lifetime",
+                if config.claim_ttl_secs == 0 {
+                    "unlimited".to_string()
+                } else {
+                    format!("{} days", config.claim_ttl_secs / 86_400)
+                },
+            ),
+        ]),
+        None => lines.push(field("Config", "not initialized")),
+    }
+    if let Some(disputes) = &snapshot.dispute_config {
+        lines.push(field("Arbiter", disputes.arbiter.to_string()));
+        lines.push(field(
+            "Resolution window",
+            format!("{} days", disputes.resolution_secs / 86_400),
+        ));
+        lines.push(field("Dispute bond", sol(disputes.bond_lamports)));
+    }
+    if let Some(registry) = &snapshot.registry {
+        lines.push(field("Registry authority", registry.authority.to_string()));
+        lines.push(field(
+            "Verified providers",
+            registry.verified_count.to_string(),
+        ));
+    }
+    if let Some(treasury) = &snapshot.treasury {
+        lines.push(field("Treasury balance", sol(treasury.lamports)));
+        lines.push(field(
+            "Fees collected",
+            sol(treasury.account.total_collected),
+        ));
+    }
+    if let Some(vault) = &snapshot.crank_vault {
+        lines.push(field(
+            "Crank reward",
+            format!("{} lamports", vault.account.reward_lamports),
+        ));
+        lines.push(field("Crank vault", sol(vault.lamports)));
+    }
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Configuration ")),
+        config,
+    );
+}
+
+fn draw_disputes(frame: &mut Frame, area: Rect, snapshot: &Snapshot, selected: usize) {
+    let rows = snapshot.disputes.iter().map(|(_, dispute)| {
+        let deadline = relative(dispute.deadline, snapshot.now);
+        let deadline = if dispute.deadline <= snapshot.now {
+            deadline.red()
+        } else {
+            deadline.into()
+        };
+        Row::new(vec![
+            short(&dispute.claim).into(),
+            short(&dispute.patient).into(),
+            short(&dispute.provider).into(),
+            short(&dispute.arbiter).into(),
+            relative(dispute.opened_at, snapshot.now).into(),
+            deadline,
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Min(14),
+        ],
+    )
+    .header(
+        Row::new([
+            "Claim", "Patient", "Provider", "Arbiter", "Opened", "Deadline",
+        ])
+        .bold(),
+    )
+    .row_highlight_style(Style::new().reversed())
+    .block(Block::bordered().title(" Open disputes "));
+    frame.render_stateful_widget(
+        table,
+        area,
+        &mut TableState::default().with_selected(Some(selected)),
+    );
+}
+
+fn draw_flagged(frame: &mut Frame, area: Rect, snapshot: &Snapshot, selected: usize) {
+    let rows = snapshot.flagged.iter().map(|claim| {
+        Row::new(vec![
+            claim.claim_id.clone(),
+            short(&claim.provider),
+            format!("{:?}", claim.status),
+            if claim.mint == Pubkey::default() {
+                sol(claim.amount)
+            } else {
+                format!("{} of {}", claim.amount, short(&claim.mint))
+            },
+            claim.reason.clone(),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Min(20),
+        ],
+    )
+    .header(Row::new(["Claim", "Provider", "Status", "Billed", "Flag"]).bold())
+    .row_highlight_style(Style::new().reversed())
+    .block(Block::bordered().title(" Flagged claims "));
+    frame.render_stateful_widget(
+        table,
+        area,
+        &mut TableState::default().with_selected(Some(selected)),
+    );
+}
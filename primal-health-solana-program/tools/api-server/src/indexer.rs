@@ -0,0 +1,260 @@
+//! Keeps an in-memory index of the accounts and events the API serves.
+//!
+//! Accounts are re-read in full on every refresh, so the index never holds a
+//! closed account for longer than one interval. Consent receipts are events,
+//! which only transaction logs keep: each refresh reads the program's
+//! transactions since the last one it saw and appends their receipts.
+
+use std::collections::BTreeMap;
+
+use anchor_client::anchor_lang::{AnchorDeserialize, Discriminator};
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Signature;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use primal_health_solana_program::migration::{decode_claim, Versioned};
+use primal_health_solana_program::{
+    ClaimAccount, ConsentReceipt, PatientAccount, ProviderAccount, ProviderClaimStats, ID,
+};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig,
+};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+
+/// Most signatures `getSignaturesForAddress` returns per call.
+const SIGNATURES_PER_CALL: usize = 1_000;
+/// Prefix of the log line Anchor writes an event to.
+const EVENT_LOG_PREFIX: &str = "Program data: ";
+
+/// A consent receipt with the transaction that emitted it.
+pub struct Receipt {
+    /// Position in the index, oldest first; the list cursor.
+    pub id: u64,
+    pub signature: String,
+    pub slot: u64,
+    pub receipt: ConsentReceipt,
+}
+
+/// What the API serves, as of the last refresh.
+#[derive(Default)]
+pub struct Index {
+    /// Slot of the last refresh; 0 until the first one completes.
+    pub slot: u64,
+    pub claims: BTreeMap<Pubkey, ClaimAccount>,
+    /// Keyed by authority, with the account's address.
+    pub patients: BTreeMap<Pubkey, (Pubkey, PatientAccount)>,
+    pub providers: BTreeMap<Pubkey, (Pubkey, ProviderAccount)>,
+    /// Keyed by provider.
+    pub provider_stats: BTreeMap<Pubkey, ProviderClaimStats>,
+    pub receipts: Vec<Receipt>,
+    /// Newest program transaction whose receipts are indexed.
+    last_signature: Option<Signature>,
+}
+
+impl Index {
+    pub fn is_ready(&self) -> bool {
+        self.slot != 0
+    }
+
+    /// Signature the next refresh reads receipts from.
+    pub fn last_signature(&self) -> Option<Signature> {
+        self.last_signature
+    }
+}
+
+/// Everything the index is rebuilt from, read before the index is locked so
+/// requests are not held up by RPC calls.
+pub struct Refresh {
+    slot: u64,
+    claims: BTreeMap<Pubkey, ClaimAccount>,
+    patients: BTreeMap<Pubkey, (Pubkey, PatientAccount)>,
+    providers: BTreeMap<Pubkey, (Pubkey, ProviderAccount)>,
+    provider_stats: BTreeMap<Pubkey, ProviderClaimStats>,
+    /// New receipts, oldest first.
+    receipts: Vec<(String, u64, ConsentReceipt)>,
+    /// Program transactions read for receipts.
+    transactions: usize,
+    last_signature: Option<Signature>,
+}
+
+impl Refresh {
+    /// Reads the program's accounts and the receipts emitted since
+    /// `last_signature`, or in the latest `backfill` transactions on the
+    /// first refresh.
+    pub fn read(
+        rpc: &RpcClient,
+        last_signature: Option<Signature>,
+        backfill: usize,
+    ) -> Result<Refresh> {
+        let slot = rpc.get_slot().context("getSlot failed")?;
+
+        let mut claims = BTreeMap::new();
+        for (address, data) in program_accounts::<ClaimAccount>(rpc)? {
+            if let Ok((claim, _)) = decode_claim(&data) {
+                claims.insert(address, claim);
+            }
+        }
+        let patients = decoded::<PatientAccount>(rpc)?
+            .map(|(address, patient)| (patient.authority, (address, patient)))
+            .collect();
+        let providers = decoded::<ProviderAccount>(rpc)?
+            .map(|(address, provider)| (provider.authority, (address, provider)))
+            .collect();
+        let provider_stats = decoded::<ProviderClaimStats>(rpc)?
+            .map(|(_, stats)| (stats.provider, stats))
+            .collect();
+
+        let (receipts, transactions, newest) = read_receipts(rpc, last_signature, backfill)?;
+        Ok(Refresh {
+            slot,
+            claims,
+            patients,
+            providers,
+            provider_stats,
+            receipts,
+            transactions,
+            last_signature: newest.or(last_signature),
+        })
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    pub fn transactions(&self) -> usize {
+        self.transactions
+    }
+
+    /// Events this refresh adds to the index.
+    pub fn events(&self) -> usize {
+        self.receipts.len()
+    }
+
+    pub fn apply(self, index: &mut Index) {
+        index.slot = self.slot;
+        index.claims = self.claims;
+        index.patients = self.patients;
+        index.providers = self.providers;
+        index.provider_stats = self.provider_stats;
+        for (signature, slot, receipt) in self.receipts {
+            let id = index.receipts.len() as u64;
+            index.receipts.push(Receipt {
+                id,
+                signature,
+                slot,
+                receipt,
+            });
+        }
+        index.last_signature = self.last_signature;
+    }
+}
+
+/// Data of every program account of type `T`.
+fn program_accounts<T: Discriminator>(rpc: &RpcClient) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            T::DISCRIMINATOR,
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc
+        .get_program_accounts_with_config(&ID, config)
+        .context("getProgramAccounts failed")?;
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect())
+}
+
+/// Every program account of type `T` that decodes in some known layout.
+fn decoded<T: Versioned + Discriminator>(
+    rpc: &RpcClient,
+) -> Result<impl Iterator<Item = (Pubkey, T)>> {
+    Ok(program_accounts::<T>(rpc)?
+        .into_iter()
+        .filter_map(|(address, data)| T::decode_any(&data).ok().map(|account| (address, account))))
+}
+
+/// Consent receipts in the program's transactions after `until`, oldest
+/// first, with how many transactions were read and the newest signature. Without `until` only the latest
+/// `backfill` transactions are read.
+#[allow(clippy::type_complexity)]
+fn read_receipts(
+    rpc: &RpcClient,
+    until: Option<Signature>,
+    backfill: usize,
+) -> Result<(Vec<(String, u64, ConsentReceipt)>, usize, Option<Signature>)> {
+    let mut statuses = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(SIGNATURES_PER_CALL),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let page = rpc
+            .get_signatures_for_address_with_config(&ID, config)
+            .context("getSignaturesForAddress failed")?;
+        let done = page.len() < SIGNATURES_PER_CALL;
+        before = match page.last() {
+            Some(status) => Some(status.signature.parse().context("bad signature")?),
+            None => None,
+        };
+        statuses.extend(page);
+        if done || (until.is_none() && statuses.len() >= backfill) {
+            break;
+        }
+    }
+    if until.is_none() {
+        statuses.truncate(backfill);
+    }
+    let newest = match statuses.first() {
+        Some(status) => Some(status.signature.parse().context("bad signature")?),
+        None => None,
+    };
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let transactions = statuses.len();
+    let mut receipts = Vec::new();
+    // Oldest first, skipping failed transactions, which emitted nothing
+    for status in statuses
+        .into_iter()
+        .rev()
+        .filter(|status| status.err.is_none())
+    {
+        let signature: Signature = status.signature.parse().context("bad signature")?;
+        let transaction = rpc
+            .get_transaction_with_config(&signature, config)
+            .with_context(|| format!("getTransaction {signature} failed"))?;
+        let logs: Option<Vec<String>> = transaction
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages.into());
+        for receipt in logs.iter().flatten().filter_map(|log| consent_receipt(log)) {
+            receipts.push((status.signature.clone(), status.slot, receipt));
+        }
+    }
+    Ok((receipts, transactions, newest))
+}
+
+/// The consent receipt a log line carries, if it is one.
+fn consent_receipt(log: &str) -> Option<ConsentReceipt> {
+    let data = STANDARD.decode(log.strip_prefix(EVENT_LOG_PREFIX)?).ok()?;
+    let payload = data.strip_prefix(ConsentReceipt::DISCRIMINATOR)?;
+    ConsentReceipt::try_from_slice(payload).ok()
+}
@@ -0,0 +1,235 @@
+//! REST API for hospital systems that integrate over HTTP rather than RPC.
+//!
+//! Serves claims, patients, providers and consent receipts as JSON from an
+//! in-memory index of the program's accounts and events, refreshed on an
+//! interval. Every `/v1` request needs an `X-Api-Key` header holding one of
+//! the keys in `--api-keys`, a file with one key per line. The OpenAPI
+//! document is served at `/openapi.json`, and Prometheus metrics at
+//! `/metrics`. The server speaks plain HTTP; put it behind a
+//! TLS-terminating proxy.
+//!
+//! Usage: `api-server --api-keys <path> [--rpc <url>] [--listen <addr>]
+//! [--refresh <secs>] [--backfill <transactions>]`.
+
+mod indexer;
+mod metrics;
+mod openapi;
+mod routes;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::hash::{hash, Hash};
+use anyhow::{anyhow, bail, Context, Result};
+use solana_rpc_client::rpc_client::RpcClient;
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::indexer::{Index, Refresh};
+use crate::metrics::Metrics;
+use crate::routes::{error, Reply};
+
+const DEFAULT_RPC: &str = "http://127.0.0.1:8899";
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+const WORKERS: usize = 4;
+
+struct Options {
+    rpc_url: String,
+    listen: String,
+    api_keys: PathBuf,
+    refresh: Duration,
+    backfill: usize,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        rpc_url: DEFAULT_RPC.to_string(),
+        listen: DEFAULT_LISTEN.to_string(),
+        api_keys: PathBuf::new(),
+        refresh: Duration::from_secs(30),
+        backfill: 1_000,
+    };
+    let mut api_keys = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--rpc" => options.rpc_url = value()?,
+            "--listen" => options.listen = value()?,
+            "--api-keys" => api_keys = Some(value()?.into()),
+            "--refresh" => {
+                options.refresh = Duration::from_secs(
+                    value()?
+                        .parse()
+                        .context("--refresh must be whole seconds")?,
+                )
+            }
+            "--backfill" => {
+                options.backfill = value()?
+                    .parse()
+                    .context("--backfill must be a number of transactions")?
+            }
+            other => bail!("unknown argument {other}"),
+        }
+    }
+    options.api_keys = api_keys.context("--api-keys is required")?;
+    if options.refresh.is_zero() {
+        bail!("--refresh must be positive");
+    }
+    Ok(options)
+}
+
+/// The accepted API keys, kept as hashes so how long a comparison takes
+/// says nothing about the keys themselves.
+struct ApiKeys(Vec<Hash>);
+
+impl ApiKeys {
+    /// Reads one key per line, skipping blank lines and `#` comments.
+    fn load(path: &PathBuf) -> Result<ApiKeys> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let keys: Vec<Hash> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|key| hash(key.as_bytes()))
+            .collect();
+        if keys.is_empty() {
+            bail!("{} holds no API keys", path.display());
+        }
+        Ok(ApiKeys(keys))
+    }
+
+    fn allows(&self, key: &str) -> bool {
+        let key = hash(key.as_bytes());
+        self.0.contains(&key)
+    }
+}
+
+/// Rebuilds the index every `refresh`, keeping the previous one when a
+/// refresh fails.
+fn index_forever(
+    rpc: RpcClient,
+    index: Arc<RwLock<Index>>,
+    metrics: Arc<Metrics>,
+    refresh: Duration,
+    backfill: usize,
+) {
+    loop {
+        let last_signature = index.read().unwrap().last_signature();
+        let started = Instant::now();
+        match Refresh::read(&rpc, last_signature, backfill) {
+            Ok(update) => {
+                metrics.record_refresh(
+                    update.slot(),
+                    update.transactions(),
+                    update.events(),
+                    started.elapsed(),
+                );
+                update.apply(&mut index.write().unwrap());
+            }
+            Err(err) => {
+                metrics.record_rpc_error();
+                eprintln!("index refresh failed: {err:#}");
+            }
+        }
+        thread::sleep(refresh);
+    }
+}
+
+fn query(url: &str) -> HashMap<String, String> {
+    let Some((_, query)) = url.split_once('?') else {
+        return HashMap::new();
+    };
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn reply(request: &Request, keys: &ApiKeys, index: &RwLock<Index>) -> Reply {
+    if request.method() != &tiny_http::Method::Get {
+        return error(405, "Only GET is supported");
+    }
+    let url = request.url();
+    let path = url.split('?').next().unwrap_or_default();
+    let Some((route, params)) = routes::route(path) else {
+        return error(404, "Not found");
+    };
+    if route.auth {
+        let key = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("X-Api-Key"))
+            .map(|header| header.value.as_str());
+        if !key.is_some_and(|key| keys.allows(key)) {
+            return error(401, "Missing or unknown API key");
+        }
+    }
+    if route.path == "/openapi.json" {
+        return (200, openapi::document());
+    }
+    let index = index.read().unwrap();
+    if !index.is_ready() {
+        return error(503, "The index has not been built yet");
+    }
+    routes::handle(&index, route, &params, &query(url))
+}
+
+fn serve(server: &Server, keys: &ApiKeys, index: &RwLock<Index>, metrics: &Metrics) {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let metrics_type =
+        Header::from_bytes("Content-Type", "text/plain; version=0.0.4; charset=utf-8").unwrap();
+    loop {
+        let request = match server.recv() {
+            Ok(request) => request,
+            Err(err) => {
+                eprintln!("accept failed: {err}");
+                continue;
+            }
+        };
+        // Scrapers need no API key, as with /health
+        let response = if request.method() == &tiny_http::Method::Get
+            && request.url().split('?').next() == Some("/metrics")
+        {
+            Response::from_string(metrics.render()).with_header(metrics_type.clone())
+        } else {
+            let (status, body) = reply(&request, keys, index);
+            metrics.record_response(status);
+            Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone())
+        };
+        if let Err(err) = request.respond(response) {
+            eprintln!("respond failed: {err}");
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let options = parse_args()?;
+    let keys = ApiKeys::load(&options.api_keys)?;
+    let server = Server::http(&options.listen)
+        .map_err(|err| anyhow!("cannot listen on {}: {err}", options.listen))?;
+    eprintln!("listening on {}", options.listen);
+
+    let index = Arc::new(RwLock::new(Index::default()));
+    let metrics = Arc::new(Metrics::default());
+    let rpc = RpcClient::new_with_commitment(options.rpc_url, CommitmentConfig::confirmed());
+    let indexer = {
+        let index = Arc::clone(&index);
+        let metrics = Arc::clone(&metrics);
+        thread::spawn(move || index_forever(rpc, index, metrics, options.refresh, options.backfill))
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..WORKERS {
+            scope.spawn(|| serve(&server, &keys, &index, &metrics));
+        }
+    });
+    indexer.join().map_err(|_| anyhow!("the indexer panicked"))
+}
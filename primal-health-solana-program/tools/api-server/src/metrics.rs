@@ -0,0 +1,132 @@
+//! Prometheus metrics for the indexer and the API, served at `/metrics` in
+//! the text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the refresh duration histogram's buckets, in seconds.
+const REFRESH_BUCKETS: [f64; 8] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative, one per bucket.
+    buckets: [u64; REFRESH_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (count, bound) in self.buckets.iter_mut().zip(REFRESH_BUCKETS) {
+            if value <= bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    refreshes: AtomicU64,
+    rpc_errors: AtomicU64,
+    transactions: AtomicU64,
+    events: AtomicU64,
+    slot: AtomicU64,
+    refresh_seconds: Mutex<Histogram>,
+    /// Responses by status code.
+    responses: Mutex<BTreeMap<u16, u64>>,
+}
+
+impl Metrics {
+    pub fn record_refresh(&self, slot: u64, transactions: usize, events: usize, took: Duration) {
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+        self.transactions
+            .fetch_add(transactions as u64, Ordering::Relaxed);
+        self.events.fetch_add(events as u64, Ordering::Relaxed);
+        self.slot.store(slot, Ordering::Relaxed);
+        self.refresh_seconds
+            .lock()
+            .unwrap()
+            .observe(took.as_secs_f64());
+    }
+
+    /// A refresh abandoned because an RPC call failed.
+    pub fn record_rpc_error(&self) {
+        self.rpc_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_response(&self, status: u16) {
+        *self.responses.lock().unwrap().entry(status).or_default() += 1;
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "primal_health_indexer_refreshes_total",
+                "Index refreshes that completed",
+                &self.refreshes,
+            ),
+            (
+                "primal_health_indexer_rpc_errors_total",
+                "Index refreshes abandoned because an RPC call failed",
+                &self.rpc_errors,
+            ),
+            (
+                "primal_health_indexer_transactions_processed_total",
+                "Program transactions read for events",
+                &self.transactions,
+            ),
+            (
+                "primal_health_indexer_events_processed_total",
+                "Consent receipt events added to the index",
+                &self.events,
+            ),
+        ];
+        for (name, help, value) in counters {
+            metric(&mut out, name, help, "counter");
+            writeln!(out, "{name} {}", value.load(Ordering::Relaxed)).unwrap();
+        }
+
+        let name = "primal_health_indexer_slot";
+        metric(
+            &mut out,
+            name,
+            "Slot of the last completed refresh",
+            "gauge",
+        );
+        writeln!(out, "{name} {}", self.slot.load(Ordering::Relaxed)).unwrap();
+
+        let name = "primal_health_indexer_refresh_duration_seconds";
+        metric(
+            &mut out,
+            name,
+            "Time to read a refresh from RPC",
+            "histogram",
+        );
+        let histogram = self.refresh_seconds.lock().unwrap();
+        for (bound, count) in REFRESH_BUCKETS.iter().zip(histogram.buckets) {
+            writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}").unwrap();
+        }
+        writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", histogram.count).unwrap();
+        writeln!(out, "{name}_sum {}", histogram.sum).unwrap();
+        writeln!(out, "{name}_count {}", histogram.count).unwrap();
+
+        let name = "primal_health_api_responses_total";
+        metric(&mut out, name, "API responses by status code", "counter");
+        for (status, count) in self.responses.lock().unwrap().iter() {
+            writeln!(out, "{name}{{status=\"{status}\"}} {count}").unwrap();
+        }
+        out
+    }
+}
+
+fn metric(out: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} {kind}").unwrap();
+}
@@ -0,0 +1,192 @@
+//! The OpenAPI document, generated from the route table so the two cannot
+//! drift apart.
+
+use serde_json::{json, Map, Value};
+
+use crate::routes::{In, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, ROUTES};
+
+fn pubkey() -> Value {
+    json!({ "type": "string", "description": "Base58 public key" })
+}
+
+fn optional(schema: Value) -> Value {
+    let mut schema = schema;
+    schema["nullable"] = json!(true);
+    schema
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer", "format": "int64" })
+}
+
+/// Unix seconds, `null` when the program stores 0.
+fn time() -> Value {
+    json!({ "type": "integer", "format": "int64", "nullable": true, "description": "Unix seconds" })
+}
+
+fn object(properties: Value) -> Value {
+    let required: Vec<&String> = properties.as_object().unwrap().keys().collect();
+    json!({ "type": "object", "required": required, "properties": properties })
+}
+
+fn page_of(schema: &str) -> Value {
+    object(json!({
+        "items": { "type": "array", "items": { "$ref": format!("#/components/schemas/{schema}") } },
+        "nextCursor": optional(json!({ "type": "string", "description": "Pass as `cursor` for the next page" })),
+    }))
+}
+
+fn schemas() -> Value {
+    let status = json!({
+        "type": "string",
+        "enum": [
+            "Pending", "Verified", "Paid", "Rejected", "UnderReview",
+            "OnHold", "PartiallyPaid", "Declined", "Disputed", "Expired"
+        ]
+    });
+    json!({
+        "Error": object(json!({ "error": { "type": "string" } })),
+        "Health": object(json!({ "slot": integer() })),
+        "OpenApi": { "type": "object" },
+        "LineItem": object(json!({
+            "code": { "type": "string" },
+            "units": { "type": "integer" },
+            "unitPrice": integer(),
+            "modifier": { "type": "string" },
+            "status": { "type": "string" },
+            "reasonCode": { "type": "integer" },
+        })),
+        "Claim": object(json!({
+            "address": pubkey(),
+            "claimId": { "type": "string" },
+            "patient": pubkey(),
+            "provider": pubkey(),
+            "tenant": optional(pubkey()),
+            "insurer": optional(pubkey()),
+            "mint": optional(json!({ "type": "string", "description": "Token mint; null for lamports" })),
+            "status": status,
+            "amount": integer(),
+            "approvedAmount": optional(integer()),
+            "principalPaid": integer(),
+            "interestPaid": integer(),
+            "lineItems": { "type": "array", "items": { "$ref": "#/components/schemas/LineItem" } },
+            "externalRef": { "type": "string" },
+            "tags": { "type": "array", "items": { "type": "string" } },
+            "filedAt": integer(),
+            "verifiedAt": time(),
+            "rejectedAt": time(),
+            "expiresAt": time(),
+            "serviceStart": time(),
+            "serviceEnd": time(),
+        })),
+        "ClaimPage": page_of("Claim"),
+        "Patient": object(json!({
+            "address": pubkey(),
+            "authority": pubkey(),
+            "did": { "type": "string" },
+            "claims": { "type": "integer", "description": "Claims filed for the patient" },
+        })),
+        "Provider": object(json!({
+            "address": pubkey(),
+            "authority": pubkey(),
+            "did": { "type": "string" },
+            "name": { "type": "string" },
+            "stats": optional(object(json!({
+                "totalClaims": integer(),
+                "duplicateCodeBps": integer(),
+                "fraudFindings": integer(),
+            }))),
+        })),
+        "ProviderPage": page_of("Provider"),
+        "Receipt": object(json!({
+            "id": integer(),
+            "signature": { "type": "string" },
+            "slot": integer(),
+            "patient": pubkey(),
+            "actor": pubkey(),
+            "grantee": optional(pubkey()),
+            "action": { "type": "string", "enum": ["Grant", "Revoke", "PolicyUpdate", "Delegate"] },
+            "scope": { "type": "integer" },
+            "lawfulBasis": { "type": "string" },
+            "expiresAt": time(),
+            "timestamp": integer(),
+            "sequence": integer(),
+        })),
+        "ReceiptPage": page_of("Receipt"),
+    })
+}
+
+fn response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{schema}") }
+            }
+        }
+    })
+}
+
+pub fn document() -> Value {
+    let mut paths = Map::new();
+    for route in ROUTES {
+        let parameters: Vec<Value> = route
+            .params
+            .iter()
+            .map(|param| {
+                let (location, required) = match param.location {
+                    In::Path => ("path", true),
+                    In::Query => ("query", false),
+                };
+                let schema = if param.name == "limit" {
+                    json!({ "type": "integer", "minimum": 1, "maximum": MAX_PAGE_SIZE, "default": DEFAULT_PAGE_SIZE })
+                } else {
+                    json!({ "type": "string" })
+                };
+                json!({
+                    "name": param.name,
+                    "in": location,
+                    "required": required,
+                    "description": param.description,
+                    "schema": schema,
+                })
+            })
+            .collect();
+        let mut responses = json!({
+            "200": response("OK", route.schema),
+            "400": response("Invalid parameter", "Error"),
+            "503": response("The index has not been built yet", "Error"),
+        });
+        if route.path.contains('{') {
+            responses["404"] = response("Not found", "Error");
+        }
+        let mut operation = json!({
+            "summary": route.summary,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if route.auth {
+            operation["responses"]["401"] = response("Missing or unknown API key", "Error");
+        } else {
+            operation["security"] = json!([]);
+        }
+        paths.insert(route.path.to_string(), json!({ "get": operation }));
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Primal Health API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Read-only access to claims, patients, providers and consent receipts, from an index of the program's accounts and events.",
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-Api-Key" }
+            }
+        },
+        "security": [{ "apiKey": [] }],
+    })
+}
@@ -0,0 +1,460 @@
+//! The REST endpoints: their table, which the OpenAPI document is generated
+//! from, and the handlers that answer them from the index.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use primal_health_solana_program::{ClaimAccount, ClaimStatus, ConsentReceipt, ProviderClaimStats};
+use serde_json::{json, Value};
+
+use crate::indexer::{Index, Receipt};
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 200;
+
+pub enum In {
+    Path,
+    Query,
+}
+
+pub struct Param {
+    pub name: &'static str,
+    pub location: In,
+    pub description: &'static str,
+}
+
+pub struct Route {
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub params: &'static [Param],
+    /// Schema of a successful response, under `components/schemas`.
+    pub schema: &'static str,
+    /// Whether the route needs an API key.
+    pub auth: bool,
+}
+
+const LIMIT: Param = Param {
+    name: "limit",
+    location: In::Query,
+    description: "Page size, 50 by default and at most 200",
+};
+const CURSOR: Param = Param {
+    name: "cursor",
+    location: In::Query,
+    description: "The previous page's `nextCursor`",
+};
+
+pub const ROUTES: &[Route] = &[
+    Route {
+        path: "/health",
+        summary: "Slot the index was last refreshed at",
+        params: &[],
+        schema: "Health",
+        auth: false,
+    },
+    Route {
+        path: "/openapi.json",
+        summary: "This document",
+        params: &[],
+        schema: "OpenApi",
+        auth: false,
+    },
+    Route {
+        path: "/v1/claims",
+        summary: "Claims, by address",
+        params: &[
+            Param {
+                name: "patient",
+                location: In::Query,
+                description: "Only claims for this patient",
+            },
+            Param {
+                name: "provider",
+                location: In::Query,
+                description: "Only claims assigned to this provider",
+            },
+            Param {
+                name: "status",
+                location: In::Query,
+                description: "Only claims in this status, such as `Verified`",
+            },
+            LIMIT,
+            CURSOR,
+        ],
+        schema: "ClaimPage",
+        auth: true,
+    },
+    Route {
+        path: "/v1/claims/{address}",
+        summary: "One claim",
+        params: &[Param {
+            name: "address",
+            location: In::Path,
+            description: "The claim account",
+        }],
+        schema: "Claim",
+        auth: true,
+    },
+    Route {
+        path: "/v1/patients/{authority}",
+        summary: "A registered patient",
+        params: &[Param {
+            name: "authority",
+            location: In::Path,
+            description: "The patient's wallet",
+        }],
+        schema: "Patient",
+        auth: true,
+    },
+    Route {
+        path: "/v1/providers",
+        summary: "Registered providers, by wallet",
+        params: &[LIMIT, CURSOR],
+        schema: "ProviderPage",
+        auth: true,
+    },
+    Route {
+        path: "/v1/providers/{authority}",
+        summary: "A registered provider with their claim counters",
+        params: &[Param {
+            name: "authority",
+            location: In::Path,
+            description: "The provider's wallet",
+        }],
+        schema: "Provider",
+        auth: true,
+    },
+    Route {
+        path: "/v1/receipts",
+        summary: "Consent receipts, oldest first",
+        params: &[
+            Param {
+                name: "patient",
+                location: In::Query,
+                description: "Only receipts for this patient",
+            },
+            Param {
+                name: "grantee",
+                location: In::Query,
+                description: "Only receipts naming this grantee",
+            },
+            LIMIT,
+            CURSOR,
+        ],
+        schema: "ReceiptPage",
+        auth: true,
+    },
+];
+
+/// The route `path` matches, with its path parameters.
+pub fn route(path: &str) -> Option<(&'static Route, HashMap<&'static str, String>)> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    ROUTES.iter().find_map(|route| {
+        let pattern: Vec<&'static str> = route.path.split('/').collect();
+        if pattern.len() != segments.len() {
+            return None;
+        }
+        let mut params = HashMap::new();
+        for (part, segment) in pattern.iter().zip(&segments) {
+            match part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                Some(name) => {
+                    params.insert(name, segment.to_string());
+                }
+                None if part == segment => {}
+                None => return None,
+            }
+        }
+        Some((route, params))
+    })
+}
+
+/// A response: status code and JSON body.
+pub type Reply = (u16, Value);
+
+pub fn error(status: u16, message: &str) -> Reply {
+    (status, json!({ "error": message }))
+}
+
+fn pubkey(name: &str, value: &str) -> Result<Pubkey, Reply> {
+    Pubkey::from_str(value).map_err(|_| error(400, &format!("Invalid {name}")))
+}
+
+fn optional_pubkey(query: &HashMap<String, String>, name: &str) -> Result<Option<Pubkey>, Reply> {
+    query.get(name).map(|value| pubkey(name, value)).transpose()
+}
+
+fn limit(query: &HashMap<String, String>) -> Result<usize, Reply> {
+    match query.get("limit") {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(value) => match value.parse() {
+            Ok(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+            _ => Err(error(400, &format!("limit must be 1 to {MAX_PAGE_SIZE}"))),
+        },
+    }
+}
+
+fn status(value: &str) -> Option<ClaimStatus> {
+    [
+        ClaimStatus::Pending,
+        ClaimStatus::Verified,
+        ClaimStatus::Paid,
+        ClaimStatus::Rejected,
+        ClaimStatus::UnderReview,
+        ClaimStatus::OnHold,
+        ClaimStatus::PartiallyPaid,
+        ClaimStatus::Declined,
+        ClaimStatus::Disputed,
+        ClaimStatus::Expired,
+    ]
+    .into_iter()
+    .find(|status| format!("{status:?}") == value)
+}
+
+/// Up to `limit` items after `cursor`, with the cursor of the next page.
+fn page<'a, K: ToString + 'a, T: 'a>(
+    items: impl Iterator<Item = (K, T)>,
+    limit: usize,
+    render: impl Fn(&K, T) -> Value,
+) -> Value {
+    let mut items: Vec<(K, T)> = items.take(limit + 1).collect();
+    let more = items.len() > limit;
+    items.truncate(limit);
+    let next_cursor = match (more, items.last()) {
+        (true, Some((key, _))) => Value::String(key.to_string()),
+        _ => Value::Null,
+    };
+    let items: Vec<Value> = items
+        .into_iter()
+        .map(|(key, item)| render(&key, item))
+        .collect();
+    json!({ "items": items, "nextCursor": next_cursor })
+}
+
+/// Answers `route` from `index`.
+pub fn handle(
+    index: &Index,
+    route: &Route,
+    params: &HashMap<&str, String>,
+    query: &HashMap<String, String>,
+) -> Reply {
+    let result = match route.path {
+        "/health" => Ok((200, json!({ "slot": index.slot }))),
+        "/v1/claims" => claims(index, query),
+        "/v1/claims/{address}" => pubkey("address", &params["address"]).and_then(|address| {
+            index
+                .claims
+                .get(&address)
+                .map(|claim| (200, claim_json(&address, claim)))
+                .ok_or_else(|| error(404, "Claim not found"))
+        }),
+        "/v1/patients/{authority}" => {
+            pubkey("authority", &params["authority"]).and_then(|authority| {
+                index
+                    .patients
+                    .get(&authority)
+                    .map(|(address, patient)| {
+                        let claims = index
+                            .claims
+                            .values()
+                            .filter(|claim| claim.patient == authority)
+                            .count();
+                        (
+                            200,
+                            json!({
+                                "address": address.to_string(),
+                                "authority": authority.to_string(),
+                                "did": patient.did,
+                                "claims": claims,
+                            }),
+                        )
+                    })
+                    .ok_or_else(|| error(404, "Patient not found"))
+            })
+        }
+        "/v1/providers" => providers(index, query),
+        "/v1/providers/{authority}" => {
+            pubkey("authority", &params["authority"]).and_then(|authority| {
+                index
+                    .providers
+                    .get(&authority)
+                    .map(|(address, provider)| {
+                        let mut body =
+                            provider_json(address, &authority, &provider.did, &provider.name);
+                        body["stats"] = stats_json(index.provider_stats.get(&authority));
+                        (200, body)
+                    })
+                    .ok_or_else(|| error(404, "Provider not found"))
+            })
+        }
+        "/v1/receipts" => receipts(index, query),
+        _ => Err(error(404, "Not found")),
+    };
+    result.unwrap_or_else(|reply| reply)
+}
+
+fn claims(index: &Index, query: &HashMap<String, String>) -> Result<Reply, Reply> {
+    let patient = optional_pubkey(query, "patient")?;
+    let provider = optional_pubkey(query, "provider")?;
+    let status = match query.get("status") {
+        Some(value) => Some(status(value).ok_or_else(|| error(400, "Invalid status"))?),
+        None => None,
+    };
+    let cursor = optional_pubkey(query, "cursor")?;
+    let limit = limit(query)?;
+
+    let after = |address: &&Pubkey| cursor.is_none_or(|cursor| **address > cursor);
+    let items = index
+        .claims
+        .iter()
+        .filter(|(address, _)| after(address))
+        .filter(|(_, claim)| patient.is_none_or(|patient| claim.patient == patient))
+        .filter(|(_, claim)| provider.is_none_or(|provider| claim.provider == provider))
+        .filter(|(_, claim)| status.is_none_or(|status| claim.status == status));
+    Ok((
+        200,
+        page(items, limit, |address, claim| claim_json(address, claim)),
+    ))
+}
+
+fn providers(index: &Index, query: &HashMap<String, String>) -> Result<Reply, Reply> {
+    let cursor = optional_pubkey(query, "cursor")?;
+    let limit = limit(query)?;
+    let items = index
+        .providers
+        .iter()
+        .filter(|(authority, _)| cursor.is_none_or(|cursor| **authority > cursor));
+    Ok((
+        200,
+        page(items, limit, |authority, (address, provider)| {
+            provider_json(address, authority, &provider.did, &provider.name)
+        }),
+    ))
+}
+
+fn receipts(index: &Index, query: &HashMap<String, String>) -> Result<Reply, Reply> {
+    let patient = optional_pubkey(query, "patient")?;
+    let grantee = optional_pubkey(query, "grantee")?;
+    let cursor = match query.get("cursor") {
+        Some(value) => Some(
+            value
+                .parse::<u64>()
+                .map_err(|_| error(400, "Invalid cursor"))?,
+        ),
+        None => None,
+    };
+    let limit = limit(query)?;
+    let start = cursor.map_or(0, |cursor| cursor as usize + 1);
+    let items = index
+        .receipts
+        .iter()
+        .skip(start)
+        .filter(|item| patient.is_none_or(|patient| item.receipt.patient == patient))
+        .filter(|item| grantee.is_none_or(|grantee| item.receipt.grantee == grantee))
+        .map(|item| (item.id, item));
+    Ok((200, page(items, limit, |_, item| receipt_json(item))))
+}
+
+fn claim_json(address: &Pubkey, claim: &ClaimAccount) -> Value {
+    let line_items: Vec<Value> = claim
+        .line_items
+        .iter()
+        .map(|item| {
+            json!({
+                "code": item.code,
+                "units": item.units,
+                "unitPrice": item.unit_price,
+                "modifier": item.modifier,
+                "status": format!("{:?}", item.status),
+                "reasonCode": item.reason_code,
+            })
+        })
+        .collect();
+    json!({
+        "address": address.to_string(),
+        "claimId": claim.claim_id,
+        "patient": claim.patient.to_string(),
+        "provider": claim.provider.to_string(),
+        "tenant": optional_key(&claim.tenant),
+        "insurer": optional_key(&claim.insurer),
+        "mint": optional_key(&claim.mint),
+        "status": format!("{:?}", claim.status),
+        "amount": claim.amount,
+        "approvedAmount": claim.approved_amount().ok(),
+        "principalPaid": claim.principal_paid,
+        "interestPaid": claim.interest_paid,
+        "lineItems": line_items,
+        "externalRef": claim.external_ref,
+        "tags": claim.tags,
+        "filedAt": claim.timestamp,
+        "verifiedAt": optional_time(claim.verified_at),
+        "rejectedAt": optional_time(claim.rejected_at),
+        "expiresAt": optional_time(claim.expires_at),
+        "serviceStart": optional_time(claim.service_start),
+        "serviceEnd": optional_time(claim.service_end),
+    })
+}
+
+fn provider_json(address: &Pubkey, authority: &Pubkey, did: &str, name: &str) -> Value {
+    json!({
+        "address": address.to_string(),
+        "authority": authority.to_string(),
+        "did": did,
+        "name": name,
+    })
+}
+
+fn stats_json(stats: Option<&ProviderClaimStats>) -> Value {
+    let Some(stats) = stats else {
+        return Value::Null;
+    };
+    json!({
+        "totalClaims": stats.total_claims,
+        "duplicateCodeBps": stats.duplicate_code_bps(),
+        "fraudFindings": stats.fraud_findings,
+    })
+}
+
+fn receipt_json(item: &Receipt) -> Value {
+    let ConsentReceipt {
+        patient,
+        actor,
+        grantee,
+        action,
+        scope,
+        lawful_basis,
+        expires_at,
+        timestamp,
+        sequence,
+    } = &item.receipt;
+    json!({
+        "id": item.id,
+        "signature": item.signature,
+        "slot": item.slot,
+        "patient": patient.to_string(),
+        "actor": actor.to_string(),
+        "grantee": optional_key(grantee),
+        "action": format!("{action:?}"),
+        "scope": scope,
+        "lawfulBasis": format!("{lawful_basis:?}"),
+        "expiresAt": optional_time(*expires_at),
+        "timestamp": timestamp,
+        "sequence": sequence,
+    })
+}
+
+/// `null` for `Pubkey::default()`, which the program uses for "none".
+fn optional_key(key: &Pubkey) -> Value {
+    if *key == Pubkey::default() {
+        return Value::Null;
+    }
+    Value::String(key.to_string())
+}
+
+/// `null` for 0, which the program uses for "not yet" or "never".
+fn optional_time(time: i64) -> Value {
+    if time == 0 {
+        return Value::Null;
+    }
+    json!(time)
+}
@@ -0,0 +1,2461 @@
+use std::collections::BTreeMap;
+
+use anchor_lang::prelude::AccountMeta;
+use anchor_lang::system_program;
+use anyhow::Result;
+use primal_health_solana_program::{
+    accounts, disclosure, instruction, settlement, AppointmentService, AuditAction, ClaimDedup,
+    ClaimStatus, DataCategory, FeeScheduleEntry, HealthRecordEntry, LawfulBasis, LineDecision,
+    LineItemInput, PolicyEffect, PolicyRule, PolicySubject, PriceTier, ReadingSummary, RebateTier,
+    RecordPointer, StorageKind, TenantRole, CONSENT_SCOPE_READ, CONSENT_SCOPE_RESEARCH,
+    DELEGATE_PERMISSIONS_ALL, FEATURE_ALL, GRANT_SCOPE_DELEGATE, GRANT_SCOPE_READ,
+    GRANT_SCOPE_WRITE, GUARDIAN_PERMISSIONS_ALL, MAX_ATTACHMENTS, MAX_CARE_TEAM_MEMBERS,
+    MAX_CARE_TEAM_RECORDS, MAX_FEE_SCHEDULE_ENTRIES, MAX_KEY_ENVELOPE_LEN, MAX_LINE_ITEMS,
+    MAX_POINTER_LEN, MAX_POLICY_RULES, MAX_PRICE_TIERS, MAX_REBATE_TIERS,
+    MAX_SECOND_OPINION_RECORDS, MAX_TENANT_FEE_BPS,
+};
+use solana_signer::Signer;
+
+use crate::env::{BenchEnv, LAMPORTS_PER_SOL};
+
+/// Compute units consumed, keyed by benchmark name.
+pub type Measurements = BTreeMap<String, u64>;
+
+/// Walks every instruction through a realistic claim lifecycle, recording the
+/// compute units of each call. Claims use the maximum number of line items so
+/// the loops over them are measured at their worst case.
+pub fn run_all(env: &mut BenchEnv) -> Result<Measurements> {
+    let mut results = Measurements::new();
+    let patient = env.funded_keypair()?;
+    let provider = env.funded_keypair()?;
+
+    // Every instruction that writes requires the config, so it comes first
+    let flags_admin = env.funded_keypair()?;
+    let config = env.pda(&[b"config"]);
+    let protocol_treasury = env.pda(&[b"protocol_treasury"]);
+    let cu = env.send(
+        accounts::InitializeConfig {
+            config,
+            protocol_treasury,
+            admin: flags_admin.pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig { fee_bps: 0 },
+        &[&flags_admin],
+    )?;
+    results.insert("initialize_config".into(), cu);
+    let cu = env.send(
+        accounts::SetConfig {
+            config,
+            admin: flags_admin.pubkey(),
+        },
+        instruction::SetPaused { paused: false },
+        &[&flags_admin],
+    )?;
+    results.insert("set_paused".into(), cu);
+    // A non-zero fee so payments include the transfer to the treasury
+    let cu = env.send(
+        accounts::SetConfig {
+            config,
+            admin: flags_admin.pubkey(),
+        },
+        instruction::SetProtocolFee { fee_bps: 25 },
+        &[&flags_admin],
+    )?;
+    results.insert("set_protocol_fee".into(), cu);
+    // The first tier starts at no volume so every payment below is rebated
+    let fee_rebates = env.pda(&[b"fee_rebates"]);
+    let cu = env.send(
+        accounts::SetFeeRebates {
+            config,
+            admin: flags_admin.pubkey(),
+            fee_rebates,
+            system_program: system_program::ID,
+        },
+        instruction::SetFeeRebates {
+            tiers: (0..MAX_REBATE_TIERS as u64)
+                .map(|n| RebateTier {
+                    min_volume: n * 10 * LAMPORTS_PER_SOL,
+                    rebate_bps: 1_000 * (n as u16 + 1),
+                })
+                .collect(),
+        },
+        &[&flags_admin],
+    )?;
+    results.insert("set_fee_rebates".into(), cu);
+    let cu = env.send(
+        accounts::SetConfig {
+            config,
+            admin: flags_admin.pubkey(),
+        },
+        instruction::SetClaimTtl {
+            ttl_secs: 90 * 24 * 60 * 60,
+        },
+        &[&flags_admin],
+    )?;
+    results.insert("set_claim_ttl".into(), cu);
+
+    // Every gated family is enabled so the benchmark can reach it
+    let feature_flags = env.pda(&[b"feature_flags"]);
+    let cu = env.send(
+        accounts::InitializeFeatureFlags {
+            feature_flags,
+            admin: flags_admin.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeFeatureFlags { flags: 0 },
+        &[&flags_admin],
+    )?;
+    results.insert("initialize_feature_flags".into(), cu);
+    let cu = env.send(
+        accounts::SetFeatureFlags {
+            feature_flags,
+            admin: flags_admin.pubkey(),
+            config,
+        },
+        instruction::SetFeatureFlags { flags: FEATURE_ALL },
+        &[&flags_admin],
+    )?;
+    results.insert("set_feature_flags".into(), cu);
+
+    let patient_account = env.pda(&[b"patient", patient.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::InitializePatient {
+            patient_account,
+            authority: patient.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePatient {
+            did: format!("did:sol:{}", patient.pubkey()),
+        },
+        &[&patient],
+    )?;
+    results.insert("initialize_patient".into(), cu);
+
+    let provider_account = env.pda(&[b"provider", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::InitializeProvider {
+            provider_account,
+            authority: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProvider {
+            did: format!("did:sol:{}", provider.pubkey()),
+            name: "Benchmark General Hospital".into(),
+        },
+        &[&provider],
+    )?;
+    results.insert("initialize_provider".into(), cu);
+    // A longer name, so the account grows
+    let cu = env.send(
+        accounts::UpdateProvider {
+            provider_account,
+            authority: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateProvider {
+            did: format!("did:sol:{}", provider.pubkey()),
+            name: "Benchmark General Hospital and Clinics".into(),
+        },
+        &[&provider],
+    )?;
+    results.insert("update_provider".into(), cu);
+
+    let insurer = env.funded_keypair()?;
+    let cu = env.send(
+        accounts::InitializeInsurer {
+            insurer_account: env.pda(&[b"insurer", insurer.pubkey().as_ref()]),
+            authority: insurer.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeInsurer {
+            did: format!("did:sol:{}", insurer.pubkey()),
+            name: "Benchmark Mutual".into(),
+        },
+        &[&insurer],
+    )?;
+    results.insert("initialize_insurer".into(), cu);
+
+    // The provider is credentialed so it can verify claims
+    let credentialing = env.funded_keypair()?;
+    let registry_authority = env.pda(&[b"registry_authority"]);
+    let cu = env.send(
+        accounts::InitializeRegistryAuthority {
+            registry_authority,
+            payer: credentialing.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRegistryAuthority {
+            authority: credentialing.pubkey(),
+        },
+        &[&credentialing],
+    )?;
+    results.insert("initialize_registry_authority".into(), cu);
+    let provider_credential = env.pda(&[b"provider_credential", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::VerifyProvider {
+            registry_authority,
+            provider_account,
+            provider_credential,
+            provider: provider.pubkey(),
+            authority: credentialing.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyProvider {},
+        &[&credentialing],
+    )?;
+    results.insert("verify_provider".into(), cu);
+
+    let data_hash = record_hash(0);
+    let cu = env.send(
+        accounts::SubmitHealthData {
+            health_data_account: env.pda(&[b"health_data", data_hash.as_ref()]),
+            owner: patient.pubkey(),
+            author: patient.pubkey(),
+            grant: None,
+            access_policy: None,
+            author_provider: None,
+            delegate_account: None,
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitHealthData {
+            data_hash,
+            pointer: pointer(data_hash),
+            category: DataCategory::LabResult,
+        },
+        &[&patient],
+    )?;
+    results.insert("submit_health_data".into(), cu);
+    let cu = env.send(
+        accounts::VerifyCommitment {
+            record: env.pda(&[b"health_data", data_hash.as_ref()]),
+        },
+        instruction::VerifyCommitment {
+            content_hash: data_hash,
+        },
+        &[&patient],
+    )?;
+    results.insert("verify_commitment".into(), cu);
+
+    // The first append also creates the patient's index
+    for record_index in 0..2u64 {
+        let cu = env.send(
+            accounts::AppendHealthRecord {
+                health_data_index: env.pda(&[b"health_data_index", patient.pubkey().as_ref()]),
+                health_data_account: env.pda(&[
+                    b"health_record",
+                    patient.pubkey().as_ref(),
+                    &record_index.to_le_bytes(),
+                ]),
+                owner: patient.pubkey(),
+                author: patient.pubkey(),
+                grant: None,
+                access_policy: None,
+                author_provider: None,
+                delegate_account: None,
+                owner_key: None,
+                config,
+                system_program: system_program::ID,
+            },
+            instruction::AppendHealthRecord {
+                record_index,
+                data_hash: record_hash(60 + record_index as usize),
+                pointer: pointer(record_hash(60 + record_index as usize)),
+                category: DataCategory::LabResult,
+            },
+            &[&patient],
+        )?;
+        results.insert("append_health_record".into(), cu);
+    }
+
+    // A wearable submits vitals with its own key
+    let device = env.funded_keypair()?;
+    let device_account = env.pda(&[
+        b"device",
+        patient.pubkey().as_ref(),
+        device.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::RegisterDevice {
+            device_account,
+            patient_account,
+            patient: patient.pubkey(),
+            device: device.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterDevice {
+            model_hash: [0x5a; 32],
+            category: DataCategory::Vitals,
+            max_readings_per_day: 96,
+        },
+        &[&patient],
+    )?;
+    results.insert("register_device".into(), cu);
+    let reading_hash = record_hash(70);
+    let cu = env.send(
+        accounts::SubmitDeviceReading {
+            health_data_account: env.pda(&[b"health_data", reading_hash.as_ref()]),
+            device_account,
+            patient: patient.pubkey(),
+            device: device.pubkey(),
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitDeviceReading {
+            data_hash: reading_hash,
+            pointer: pointer(reading_hash),
+        },
+        &[&device],
+    )?;
+    results.insert("submit_device_reading".into(), cu);
+
+    // The provider sponsors a patient who holds no SOL
+    let sponsor_vault = env.pda(&[b"sponsor_vault", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::FundSponsorVault {
+            sponsor_vault,
+            sponsor: provider.pubkey(),
+            funder: provider.pubkey(),
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::FundSponsorVault {
+            lamports: LAMPORTS_PER_SOL,
+        },
+        &[&provider],
+    )?;
+    results.insert("fund_sponsor_vault".into(), cu);
+    let sponsored = solana_keypair::Keypair::new();
+    let cu = env.send(
+        accounts::InitializePatientSponsored {
+            patient_account: env.pda(&[b"patient", sponsored.pubkey().as_ref()]),
+            authority: sponsored.pubkey(),
+            sponsor: provider.pubkey(),
+            sponsor_vault,
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePatientSponsored {
+            did: format!("did:sol:{}", sponsored.pubkey()),
+        },
+        &[&provider, &sponsored],
+    )?;
+    results.insert("initialize_patient_sponsored".into(), cu);
+    let sponsored_hash = record_hash(50);
+    let cu = env.send(
+        accounts::SubmitHealthDataSponsored {
+            health_data_account: env.pda(&[b"health_data", sponsored_hash.as_ref()]),
+            owner: sponsored.pubkey(),
+            author: sponsored.pubkey(),
+            grant: None,
+            access_policy: None,
+            author_provider: None,
+            delegate_account: None,
+            sponsor: provider.pubkey(),
+            sponsor_vault,
+            feature_flags,
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitHealthDataSponsored {
+            data_hash: sponsored_hash,
+            pointer: pointer(sponsored_hash),
+            category: DataCategory::LabResult,
+        },
+        &[&provider, &sponsored],
+    )?;
+    results.insert("submit_health_data_sponsored".into(), cu);
+    let cu = env.send(
+        accounts::WithdrawSponsorVault {
+            sponsor_vault,
+            sponsor: provider.pubkey(),
+            config,
+        },
+        instruction::WithdrawSponsorVault {
+            lamports: LAMPORTS_PER_SOL / 2,
+        },
+        &[&provider],
+    )?;
+    results.insert("withdraw_sponsor_vault".into(), cu);
+
+    let grant = env.pda(&[
+        b"grant",
+        patient.pubkey().as_ref(),
+        provider.pubkey().as_ref(),
+    ]);
+    let grantee_key = env.pda(&[b"encryption_key", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::RegisterEncryptionKey {
+            encryption_key: grantee_key,
+            owner: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterEncryptionKey {
+            x25519_key: [9; 32],
+        },
+        &[&provider],
+    )?;
+    results.insert("register_encryption_key".into(), cu);
+
+    let cu = env.send(
+        accounts::RotateEncryptionKey {
+            encryption_key: grantee_key,
+            owner: provider.pubkey(),
+            config,
+        },
+        instruction::RotateEncryptionKey {
+            x25519_key: [10; 32],
+        },
+        &[&provider],
+    )?;
+    results.insert("rotate_encryption_key".into(), cu);
+
+    // Consent actions chain into the ledger, so its hashing is measured too
+    let consent_ledger = env.pda(&[b"consent_ledger", patient.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::GrantAccess {
+            grant,
+            patient: patient.pubkey(),
+            granter: patient.pubkey(),
+            guardian_link: None,
+            delegate_account: None,
+            grantee: provider.pubkey(),
+            grantee_key: Some(grantee_key),
+            key_envelope: Some(env.pda(&[b"key_envelope", grant.as_ref()])),
+            consent_ledger: Some(consent_ledger),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::GrantAccess {
+            scope: GRANT_SCOPE_WRITE | GRANT_SCOPE_DELEGATE,
+            expires_at: 0,
+            lawful_basis: LawfulBasis::Consent,
+            key_envelope: vec![7; MAX_KEY_ENVELOPE_LEN],
+        },
+        &[&patient],
+    )?;
+    results.insert("grant_access".into(), cu);
+
+    let delegated_hash = record_hash(40);
+    let cu = env.send(
+        accounts::SubmitHealthData {
+            health_data_account: env.pda(&[b"health_data", delegated_hash.as_ref()]),
+            owner: patient.pubkey(),
+            author: provider.pubkey(),
+            grant: Some(grant),
+            access_policy: None,
+            author_provider: Some(provider_account),
+            delegate_account: None,
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitHealthData {
+            data_hash: delegated_hash,
+            pointer: pointer(delegated_hash),
+            category: DataCategory::LabResult,
+        },
+        &[&provider],
+    )?;
+    results.insert("submit_health_data_delegated".into(), cu);
+
+    let cu = env.send(
+        accounts::LinkRecords {
+            child: env.pda(&[b"health_data", delegated_hash.as_ref()]),
+            parent: env.pda(&[b"health_data", data_hash.as_ref()]),
+            owner: patient.pubkey(),
+            config,
+        },
+        instruction::LinkRecords {},
+        &[&patient],
+    )?;
+    results.insert("link_records".into(), cu);
+
+    // A 16-field record, so disclosure proofs have four levels
+    let record = env.pda(&[b"health_data", data_hash.as_ref()]);
+    let field_commitment = env.pda(&[b"field_root", record.as_ref()]);
+    let fields: Vec<(String, String)> = (0..16)
+        .map(|n| (format!("Observation.component[{n}].value"), format!("{n}")))
+        .collect();
+    let leaves: Vec<[u8; 32]> = fields
+        .iter()
+        .map(|(path, value)| disclosure::field_leaf(path, value))
+        .collect();
+    let cu = env.send(
+        accounts::CommitFieldRoot {
+            field_commitment,
+            record,
+            committer: patient.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CommitFieldRoot {
+            root: disclosure::merkle_root(&leaves),
+            field_count: leaves.len() as u32,
+        },
+        &[&patient],
+    )?;
+    results.insert("commit_field_root".into(), cu);
+    let (field_path, field_value) = fields[5].clone();
+    let cu = env.send(
+        accounts::VerifyFieldDisclosure {
+            field_commitment,
+            record,
+        },
+        instruction::VerifyFieldDisclosure {
+            field_path,
+            field_value,
+            index: 5,
+            proof: disclosure::merkle_proof(&leaves, 5),
+        },
+        &[&patient],
+    )?;
+    results.insert("verify_field_disclosure".into(), cu);
+
+    let admin = env.funded_keypair()?;
+    let validator = env.funded_keypair()?;
+    let validator_registry = env.pda(&[b"validator_registry"]);
+    let data_validator = env.pda(&[b"data_validator", validator.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::InitializeValidatorRegistry {
+            validator_registry,
+            admin: admin.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeValidatorRegistry {},
+        &[&admin],
+    )?;
+    results.insert("initialize_validator_registry".into(), cu);
+    let cu = env.send(
+        accounts::DesignateValidator {
+            validator_registry,
+            data_validator,
+            validator: validator.pubkey(),
+            admin: admin.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::DesignateValidator { active: true },
+        &[&admin],
+    )?;
+    results.insert("designate_validator".into(), cu);
+    let cu = env.send(
+        accounts::AttestRecordQuality {
+            attestation: env.pda(&[b"quality", record.as_ref(), validator.pubkey().as_ref()]),
+            record,
+            data_validator,
+            validator: validator.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::AttestRecordQuality {
+            profile_hash: [3; 32],
+            score: 92,
+        },
+        &[&validator],
+    )?;
+    results.insert("attest_record_quality".into(), cu);
+
+    // Revoking the provider's grant also revokes the one delegated from it
+    let resident = env.funded_keypair()?;
+    let delegated_grant = env.pda(&[
+        b"grant",
+        patient.pubkey().as_ref(),
+        resident.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::DelegateAccess {
+            parent_grant: grant,
+            delegator: provider.pubkey(),
+            delegate: resident.pubkey(),
+            grant: delegated_grant,
+            consent_ledger: Some(consent_ledger),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::DelegateAccess {
+            scope: GRANT_SCOPE_WRITE,
+            expires_at: 0,
+        },
+        &[&provider],
+    )?;
+    results.insert("delegate_access".into(), cu);
+
+    let cu = env.send_with_remaining(
+        accounts::RevokeAccess {
+            grant,
+            parent_grant: None,
+            patient: patient.pubkey(),
+            granter: patient.pubkey(),
+            guardian_link: None,
+            delegate_account: None,
+            consent_ledger: Some(consent_ledger),
+            config,
+            system_program: system_program::ID,
+        },
+        vec![AccountMeta::new(delegated_grant, false)],
+        instruction::RevokeAccess {},
+        &[&patient],
+    )?;
+    results.insert("revoke_access".into(), cu);
+
+    let consent = env.pda(&[
+        b"consent",
+        patient.pubkey().as_ref(),
+        provider.pubkey().as_ref(),
+        record.as_ref(),
+    ]);
+    let audit_trail = env.pda(&[b"audit_trail", patient.pubkey().as_ref()]);
+    let audit_entry = |sequence: u64| {
+        env.pda(&[
+            b"audit_entry",
+            patient.pubkey().as_ref(),
+            &sequence.to_le_bytes(),
+        ])
+    };
+    let audit_entries = [audit_entry(0), audit_entry(1)];
+    // A caregiver with power of attorney consents for the patient
+    let caregiver = env.funded_keypair()?;
+    let delegate_account = env.pda(&[
+        b"delegate",
+        patient.pubkey().as_ref(),
+        caregiver.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::SetDelegate {
+            delegate_account,
+            patient_account,
+            patient: patient.pubkey(),
+            delegate: caregiver.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetDelegate {
+            permissions: DELEGATE_PERMISSIONS_ALL,
+        },
+        &[&patient],
+    )?;
+    results.insert("set_delegate".into(), cu);
+    let cu = env.send(
+        accounts::GrantConsent {
+            consent,
+            record,
+            provider: provider.pubkey(),
+            patient: patient.pubkey(),
+            granter: caregiver.pubkey(),
+            delegate_account: Some(delegate_account),
+            audit_trail: Some(audit_trail),
+            audit_entry: Some(audit_entries[0]),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::GrantConsent {
+            scope: CONSENT_SCOPE_READ | CONSENT_SCOPE_RESEARCH,
+            expires_at: 0,
+        },
+        &[&caregiver],
+    )?;
+    results.insert("grant_consent".into(), cu);
+    // The provider's gatekeeper logs the read it then releases
+    let cu = env.send(
+        accounts::LogAccess {
+            subject: patient.pubkey(),
+            audit_trail,
+            audit_entry: audit_entries[1],
+            actor: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::LogAccess {
+            action: AuditAction::RecordAccessed,
+            target: record,
+        },
+        &[&provider],
+    )?;
+    results.insert("log_access".into(), cu);
+    let cu = env.send(
+        accounts::RevokeConsent {
+            consent,
+            patient: patient.pubkey(),
+            revoker: patient.pubkey(),
+            delegate_account: None,
+            config,
+        },
+        instruction::RevokeConsent {},
+        &[&patient],
+    )?;
+    results.insert("revoke_consent".into(), cu);
+    let cu = env.send(
+        accounts::RevokeDelegate {
+            delegate_account,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::RevokeDelegate {},
+        &[&patient],
+    )?;
+    results.insert("revoke_delegate".into(), cu);
+
+    let access_policy = env.pda(&[b"access_policy", patient.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::SetAccessPolicy {
+            access_policy,
+            patient: patient.pubkey(),
+            consent_ledger: Some(consent_ledger),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetAccessPolicy {
+            rules: vec![
+                PolicyRule {
+                    subject: PolicySubject::Anyone,
+                    categories: DataCategory::Imaging.bit(),
+                    scope: GRANT_SCOPE_READ,
+                    effect: PolicyEffect::RequireGrant,
+                };
+                MAX_POLICY_RULES - 1
+            ]
+            .into_iter()
+            .chain([PolicyRule {
+                subject: PolicySubject::RegisteredProvider,
+                categories: DataCategory::LabResult.bit(),
+                scope: GRANT_SCOPE_READ,
+                effect: PolicyEffect::Allow,
+            }])
+            .collect(),
+        },
+        &[&patient],
+    )?;
+    results.insert("set_access_policy".into(), cu);
+    // The grant is revoked, so access comes from the last policy rule
+    let cu = env.send(
+        accounts::CheckAccess {
+            patient: patient.pubkey(),
+            requester: provider.pubkey(),
+            access_policy: Some(access_policy),
+            grant: Some(grant),
+            requester_provider: Some(provider_account),
+        },
+        instruction::CheckAccess {
+            category: DataCategory::LabResult,
+            scope: GRANT_SCOPE_READ,
+        },
+        &[&provider],
+    )?;
+    results.insert("check_access".into(), cu);
+
+    // The patient acts as guardian for a newly registered dependent
+    let dependent = env.funded_keypair()?;
+    let dependent_account = env.pda(&[b"patient", dependent.pubkey().as_ref()]);
+    env.send(
+        accounts::InitializePatient {
+            patient_account: dependent_account,
+            authority: dependent.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePatient {
+            did: format!("did:sol:{}", dependent.pubkey()),
+        },
+        &[&dependent],
+    )?;
+    let dependent_link = env.pda(&[
+        b"dependent",
+        patient.pubkey().as_ref(),
+        dependent.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::LinkDependent {
+            dependent_link,
+            guardian_account: patient_account,
+            dependent_account,
+            guardian: patient.pubkey(),
+            dependent: dependent.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::LinkDependent {
+            permissions: GUARDIAN_PERMISSIONS_ALL,
+            expires_at: i64::MAX,
+        },
+        &[&patient, &dependent],
+    )?;
+    results.insert("link_dependent".into(), cu);
+    let claim_id = "bench-dependent";
+    let line_items = vec![LineItemInput {
+        code: "99213".into(),
+        units: 1,
+        unit_price: 1_000_000,
+        modifier: String::new(),
+    }];
+    let service_date = env.now();
+    let fingerprint = ClaimDedup::fingerprint(
+        &dependent.pubkey(),
+        &provider.pubkey(),
+        &line_items,
+        service_date,
+    );
+    let cu = env.send(
+        accounts::CreateClaim {
+            claim_account: env.pda(&[b"claim", claim_id.as_bytes()]),
+            patient: dependent.pubkey(),
+            filer: patient.pubkey(),
+            guardian_link: Some(dependent_link),
+            delegate_account: None,
+            provider: provider.pubkey(),
+            insurer: None,
+            mint: None,
+            provider_stats: env.pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+            claim_dedup: env.pda(&[b"claim_dedup", &fingerprint]),
+            claim_grant: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CreateClaim {
+            claim_id: claim_id.to_string(),
+            line_items,
+            attachments: Vec::new(),
+            service_start: service_date,
+            service_end: service_date,
+            fingerprint,
+        },
+        &[&patient],
+    )?;
+    results.insert("create_claim_as_guardian".into(), cu);
+
+    // A research org pays for each new lab result
+    let research = env.funded_keypair()?;
+    let research_grant = env.pda(&[
+        b"grant",
+        patient.pubkey().as_ref(),
+        research.pubkey().as_ref(),
+    ]);
+    env.send(
+        accounts::GrantAccess {
+            grant: research_grant,
+            patient: patient.pubkey(),
+            granter: patient.pubkey(),
+            guardian_link: None,
+            delegate_account: None,
+            grantee: research.pubkey(),
+            grantee_key: None,
+            key_envelope: None,
+            consent_ledger: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::GrantAccess {
+            scope: GRANT_SCOPE_READ,
+            expires_at: 0,
+            lawful_basis: LawfulBasis::Consent,
+            key_envelope: Vec::new(),
+        },
+        &[&patient],
+    )?;
+    let subscription = env.pda(&[
+        b"subscription",
+        patient.pubkey().as_ref(),
+        research.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::SubscribeToPatientData {
+            subscription,
+            patient: patient.pubkey(),
+            grant: research_grant,
+            subscriber: research.pubkey(),
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubscribeToPatientData {
+            category: DataCategory::LabResult,
+            price_per_record: 1_000_000,
+            deposit: 10_000_000,
+        },
+        &[&research],
+    )?;
+    results.insert("subscribe_to_patient_data".into(), cu);
+    let subscribed_hash = record_hash(41);
+    let cu = env.send_with_remaining(
+        accounts::SubmitHealthData {
+            health_data_account: env.pda(&[b"health_data", subscribed_hash.as_ref()]),
+            owner: patient.pubkey(),
+            author: patient.pubkey(),
+            grant: None,
+            access_policy: None,
+            author_provider: None,
+            delegate_account: None,
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        vec![AccountMeta::new(subscription, false)],
+        instruction::SubmitHealthData {
+            data_hash: subscribed_hash,
+            pointer: pointer(subscribed_hash),
+            category: DataCategory::LabResult,
+        },
+        &[&patient],
+    )?;
+    results.insert("submit_health_data_subscribed".into(), cu);
+    let cu = env.send(
+        accounts::WithdrawSubscriptionEarnings {
+            subscription,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::WithdrawSubscriptionEarnings {},
+        &[&patient],
+    )?;
+    results.insert("withdraw_subscription_earnings".into(), cu);
+    let cu = env.send(
+        accounts::CancelSubscription {
+            subscription,
+            patient: patient.pubkey(),
+            subscriber: research.pubkey(),
+            signer: research.pubkey(),
+            config,
+        },
+        instruction::CancelSubscription {},
+        &[&research],
+    )?;
+    results.insert("cancel_subscription".into(), cu);
+
+    // Pay-per-access offers
+    let offer_id = "bench-offer".to_string();
+    let access_offer = env.pda(&[
+        b"access_offer",
+        patient.pubkey().as_ref(),
+        offer_id.as_bytes(),
+    ]);
+    let cu = env.send(
+        accounts::CreateAccessOffer {
+            access_offer,
+            owner: patient.pubkey(),
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CreateAccessOffer {
+            offer_id,
+            resource_hash: subscribed_hash,
+            price: 1_000_000,
+            access_secs: 24 * 60 * 60,
+            // The record was just written, so it falls in the first tier
+            price_tiers: (1..=MAX_PRICE_TIERS as u64)
+                .map(|n| PriceTier {
+                    max_age_secs: n as i64 * 30 * 24 * 60 * 60,
+                    price: 1_000_000 * (1 + MAX_PRICE_TIERS as u64 - n),
+                })
+                .collect(),
+        },
+        &[&patient],
+    )?;
+    results.insert("create_access_offer".into(), cu);
+    let purchased_grant = env.pda(&[
+        b"access_purchase",
+        access_offer.as_ref(),
+        research.pubkey().as_ref(),
+    ]);
+    let subscribed_record = env.pda(&[b"health_data", subscribed_hash.as_ref()]);
+    let purchase = || accounts::PurchaseAccess {
+        access_offer,
+        owner: patient.pubkey(),
+        record: Some(subscribed_record),
+        grant: purchased_grant,
+        buyer: research.pubkey(),
+        feature_flags,
+        config,
+        system_program: system_program::ID,
+    };
+    // The first purchase pays for the grant; the second extends it
+    for name in ["purchase_access", "purchase_access_renewal"] {
+        let cu = env.send(
+            purchase(),
+            instruction::PurchaseAccess {
+                expected_price: 1_000_000 * MAX_PRICE_TIERS as u64,
+            },
+            &[&research],
+        )?;
+        results.insert(name.into(), cu);
+    }
+    let cu = env.send(
+        accounts::CloseAccessOffer {
+            access_offer,
+            owner: patient.pubkey(),
+            config,
+        },
+        instruction::CloseAccessOffer {},
+        &[&patient],
+    )?;
+    results.insert("close_access_offer".into(), cu);
+
+    let batch_id = "bench".to_string();
+    let cu = env.send(
+        accounts::SubmitHealthDataBatch {
+            batch_account: env.pda(&[
+                b"health_data_batch",
+                patient.pubkey().as_ref(),
+                batch_id.as_bytes(),
+            ]),
+            owner: patient.pubkey(),
+            author: patient.pubkey(),
+            grant: None,
+            access_policy: None,
+            author_provider: None,
+            delegate_account: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitHealthDataBatch {
+            batch_id,
+            entries: (1..=4)
+                .map(|n| HealthRecordEntry {
+                    data_hash: record_hash(n),
+                    pointer: format!("ipfs://bench/{n}"),
+                    category: DataCategory::LabResult,
+                })
+                .collect(),
+        },
+        &[&patient],
+    )?;
+    results.insert("submit_health_data_batch".into(), cu);
+
+    // Paid path
+    let period = env.current_period();
+    let settlement = env.pda(&[
+        b"settlement",
+        provider.pubkey().as_ref(),
+        patient.pubkey().as_ref(),
+        &period.to_le_bytes(),
+    ]);
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-paid", 1)?;
+    let cu = env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    results.insert("accept_claim".into(), cu);
+    let cu = env.send(
+        accounts::CheckClaimAccess {
+            claim_account: claim,
+            claim_grant: env.pda(&[b"claim_grant", claim.as_ref()]),
+            record: env.pda(&[b"health_data", record_hash(0).as_ref()]),
+            requester: provider.pubkey(),
+        },
+        instruction::CheckClaimAccess {},
+        &[&provider],
+    )?;
+    results.insert("check_claim_access".into(), cu);
+
+    // A second provider reviews the claim's records for the patient
+    let consultant = env.funded_keypair()?;
+    let consultant_provider = env.pda(&[b"provider", consultant.pubkey().as_ref()]);
+    env.send(
+        accounts::InitializeProvider {
+            provider_account: consultant_provider,
+            authority: consultant.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProvider {
+            did: format!("did:sol:{}", consultant.pubkey()),
+            name: "Benchmark Consultant".into(),
+        },
+        &[&consultant],
+    )?;
+    let second_opinion = env.pda(&[
+        b"second_opinion",
+        claim.as_ref(),
+        consultant.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::RequestSecondOpinion {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            consultant: consultant.pubkey(),
+            consultant_provider,
+            second_opinion,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RequestSecondOpinion {
+            records: (0..MAX_SECOND_OPINION_RECORDS).map(record_hash).collect(),
+            fee: 1_000_000,
+            expires_at: env.now() + 7 * 24 * 60 * 60,
+        },
+        &[&patient],
+    )?;
+    results.insert("request_second_opinion".into(), cu);
+    let cu = env.send(
+        accounts::CheckSecondOpinionAccess {
+            second_opinion,
+            record: env.pda(&[b"health_data", record_hash(0).as_ref()]),
+            requester: consultant.pubkey(),
+        },
+        instruction::CheckSecondOpinionAccess {},
+        &[&consultant],
+    )?;
+    results.insert("check_second_opinion_access".into(), cu);
+    let opinion = record_hash(80);
+    let cu = env.send(
+        accounts::SubmitSecondOpinion {
+            second_opinion,
+            health_data_account: env.pda(&[b"health_data", opinion.as_ref()]),
+            consultant: consultant.pubkey(),
+            owner_key: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitSecondOpinion {
+            data_hash: opinion,
+            pointer: pointer(opinion),
+        },
+        &[&consultant],
+    )?;
+    results.insert("submit_second_opinion".into(), cu);
+    let cu = env.send(
+        accounts::CloseSecondOpinion {
+            second_opinion,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::CloseSecondOpinion {},
+        &[&patient],
+    )?;
+    results.insert("close_second_opinion".into(), cu);
+
+    // The consultant joins the patient's care team; the record is reached
+    // through the claim rather than the designated hashes
+    let team_id = 1u32;
+    let care_team = env.pda(&[
+        b"care_team",
+        patient.pubkey().as_ref(),
+        &team_id.to_le_bytes(),
+    ]);
+    let mut members = vec![consultant.pubkey()];
+    members.extend((1..MAX_CARE_TEAM_MEMBERS).map(|_| solana_keypair::Keypair::new().pubkey()));
+    let cu = env.send(
+        accounts::SetCareTeam {
+            care_team,
+            patient: patient.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetCareTeam {
+            team_id,
+            members,
+            records: (1..=MAX_CARE_TEAM_RECORDS).map(record_hash).collect(),
+        },
+        &[&patient],
+    )?;
+    results.insert("set_care_team".into(), cu);
+    let cu = env.send(
+        accounts::CheckCareTeamAccess {
+            care_team,
+            record: env.pda(&[b"health_data", record_hash(0).as_ref()]),
+            claim_account: Some(claim),
+            requester: consultant.pubkey(),
+        },
+        instruction::CheckCareTeamAccess {},
+        &[&consultant],
+    )?;
+    results.insert("check_care_team_access".into(), cu);
+    let cu = env.send(
+        accounts::CloseCareTeam {
+            care_team,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::CloseCareTeam {},
+        &[&patient],
+    )?;
+    results.insert("close_care_team".into(), cu);
+
+    // The patient vetoes a break-glass request during the delay
+    let emergency_record = record_hash(0);
+    let emergency_access = env.pda(&[
+        b"emergency_access",
+        emergency_record.as_ref(),
+        provider.pubkey().as_ref(),
+    ]);
+    let cu = env.send(
+        accounts::RequestEmergencyAccess {
+            health_data_account: env.pda(&[b"health_data", emergency_record.as_ref()]),
+            provider_account: env.pda(&[b"provider", provider.pubkey().as_ref()]),
+            emergency_access,
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RequestEmergencyAccess {
+            data_hash: emergency_record,
+            justification_hash: record_hash(81),
+        },
+        &[&provider],
+    )?;
+    results.insert("request_emergency_access".into(), cu);
+    let cu = env.send(
+        accounts::VetoEmergencyAccess {
+            emergency_access,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::VetoEmergencyAccess {},
+        &[&patient],
+    )?;
+    results.insert("veto_emergency_access".into(), cu);
+    let cu = env.send(
+        accounts::CloseEmergencyAccess {
+            emergency_access,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::CloseEmergencyAccess {},
+        &[&provider],
+    )?;
+    results.insert("close_emergency_access".into(), cu);
+    for index in 0..MAX_LINE_ITEMS as u8 {
+        let cu = env.send(
+            accounts::ReviewClaim {
+                claim_account: claim,
+                provider: provider.pubkey(),
+                config,
+            },
+            instruction::AdjudicateLine {
+                index,
+                decision: LineDecision::Approve,
+                reason_code: 0,
+            },
+            &[&provider],
+        )?;
+        results.insert("adjudicate_line".into(), cu);
+    }
+    let cu = env.send(
+        accounts::VerifyClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Verified,
+        },
+        &[&provider],
+    )?;
+    results.insert("verify_claim".into(), cu);
+    // One installment per line item, so the partial payment below is the first
+    let payment_schedule = env.pda(&[b"payment_schedule", claim.as_ref()]);
+    let cu = env.send(
+        accounts::SetPaymentSchedule {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            payment_schedule,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetPaymentSchedule {
+            installment_count: MAX_LINE_ITEMS as u8,
+            interval_secs: 30 * 24 * 60 * 60,
+        },
+        &[&patient, &provider],
+    )?;
+    results.insert("set_payment_schedule".into(), cu);
+    let provider_volume = env.pda(&[
+        b"provider_volume",
+        provider.pubkey().as_ref(),
+        &period.to_le_bytes(),
+    ]);
+    let payment = || accounts::ProcessPayment {
+        claim_account: claim,
+        provider: provider.pubkey(),
+        patient: patient.pubkey(),
+        settlement,
+        tenant: None,
+        treasury: None,
+        payment_schedule: Some(payment_schedule),
+        audit_trail: None,
+        audit_entry: None,
+        protocol_treasury,
+        clawback_config: None,
+        payment_hold: None,
+        facilitator: None,
+        facilitator_signer: None,
+        fee_rebates: Some(fee_rebates),
+        provider_volume: Some(provider_volume),
+        config,
+        system_program: system_program::ID,
+    };
+    let cu = env.send(
+        payment(),
+        instruction::ProcessPartialPayment {
+            period,
+            principal: 1_000_000,
+        },
+        &[&provider],
+    )?;
+    results.insert("process_partial_payment".into(), cu);
+    let cu = env.send(
+        payment(),
+        instruction::ProcessPayment { period },
+        &[&provider],
+    )?;
+    results.insert("process_payment".into(), cu);
+    let cu = env.send_with_remaining(
+        accounts::GetPatientYearSummary {
+            patient: patient.pubkey(),
+        },
+        vec![AccountMeta::new_readonly(claim, false)],
+        instruction::GetPatientYearSummary {
+            year: period / 100,
+            mint: Default::default(),
+            deductible: 1_000_000,
+        },
+        &[&patient],
+    )?;
+    results.insert("get_patient_year_summary".into(), cu);
+    let cu = env.send(
+        accounts::CloseClaim {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            rent_payer: patient.pubkey(),
+            auto_approval: None,
+            claim_grant: Some(env.pda(&[b"claim_grant", claim.as_ref()])),
+            payment_schedule: Some(payment_schedule),
+            claim_dispute: None,
+            claim_escrow: env.pda(&[b"claim_escrow", claim.as_ref()]),
+            config,
+        },
+        instruction::CloseClaim {},
+        &[&patient],
+    )?;
+    results.insert("close_claim".into(), cu);
+
+    // A visit billed straight from the provider's fee schedule, pricing the
+    // most services against the longest schedule
+    let fee_schedule = env.pda(&[b"fee_schedule", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::SetFeeSchedule {
+            fee_schedule,
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetFeeSchedule {
+            entries: (0..MAX_FEE_SCHEDULE_ENTRIES)
+                .map(|n| FeeScheduleEntry {
+                    code: format!("{}", 99200 + n),
+                    unit_price: 1_000_000,
+                })
+                .collect(),
+        },
+        &[&provider],
+    )?;
+    results.insert("set_fee_schedule".into(), cu);
+    let appointment_id = "bench-visit";
+    let appointment = env.pda(&[
+        b"appointment",
+        provider.pubkey().as_ref(),
+        appointment_id.as_bytes(),
+    ]);
+    let scheduled_at = env.now() - 2 * 60 * 60;
+    let cu = env.send(
+        accounts::ScheduleAppointment {
+            appointment,
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            provider_account,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::ScheduleAppointment {
+            appointment_id: appointment_id.to_string(),
+            scheduled_at,
+        },
+        &[&patient],
+    )?;
+    results.insert("schedule_appointment".into(), cu);
+    let services: Vec<_> = (MAX_FEE_SCHEDULE_ENTRIES - MAX_LINE_ITEMS..MAX_FEE_SCHEDULE_ENTRIES)
+        .map(|n| AppointmentService {
+            code: format!("{}", 99200 + n),
+            units: 2,
+        })
+        .collect();
+    let cu = env.send(
+        accounts::CompleteAppointment {
+            appointment,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::CompleteAppointment {
+            services: services.clone(),
+            session_records: (0..MAX_ATTACHMENTS).map(|n| record_hash(90 + n)).collect(),
+        },
+        &[&provider],
+    )?;
+    results.insert("complete_appointment".into(), cu);
+    let claim_id = "bench-visit";
+    let claim_account = env.pda(&[b"claim", claim_id.as_bytes()]);
+    let line_items: Vec<_> = services
+        .into_iter()
+        .map(|service| LineItemInput {
+            code: service.code,
+            units: service.units,
+            unit_price: 1_000_000,
+            modifier: String::new(),
+        })
+        .collect();
+    let fingerprint = ClaimDedup::fingerprint(
+        &patient.pubkey(),
+        &provider.pubkey(),
+        &line_items,
+        scheduled_at,
+    );
+    let cu = env.send(
+        accounts::CreateClaimFromAppointment {
+            appointment,
+            fee_schedule,
+            claim_account,
+            provider: provider.pubkey(),
+            provider_stats: env.pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+            claim_dedup: env.pda(&[b"claim_dedup", &fingerprint]),
+            claim_grant: Some(env.pda(&[b"claim_grant", claim_account.as_ref()])),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CreateClaimFromAppointment {
+            claim_id: claim_id.to_string(),
+            fingerprint,
+        },
+        &[&provider],
+    )?;
+    results.insert("create_claim_from_appointment".into(), cu);
+
+    // Rejected, then disputed by the patient and overturned by the arbiter
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-dispute", 7)?;
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    env.send(
+        accounts::VerifyClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Rejected,
+        },
+        &[&provider],
+    )?;
+    let arbiter = env.funded_keypair()?;
+    let dispute_config = env.pda(&[b"dispute_config"]);
+    let cu = env.send(
+        accounts::InitializeDisputeConfig {
+            dispute_config,
+            admin: flags_admin.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeDisputeConfig {
+            arbiter: arbiter.pubkey(),
+            resolution_secs: 14 * 24 * 60 * 60,
+            bond_lamports: 50_000_000,
+        },
+        &[&flags_admin],
+    )?;
+    results.insert("initialize_dispute_config".into(), cu);
+    let claim_dispute = env.pda(&[b"claim_dispute", claim.as_ref()]);
+    let cu = env.send(
+        accounts::DisputeClaim {
+            claim_account: claim,
+            claim_dispute,
+            dispute_config,
+            patient: patient.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::DisputeClaim {
+            reason_hash: record_hash(95),
+        },
+        &[&patient],
+    )?;
+    results.insert("dispute_claim".into(), cu);
+    let cu = env.send(
+        accounts::ResolveDispute {
+            claim_account: claim,
+            claim_dispute,
+            resolver: arbiter.pubkey(),
+            crank_vault: None,
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::ResolveDispute {
+            patient_prevails: true,
+        },
+        &[&arbiter],
+    )?;
+    results.insert("resolve_dispute".into(), cu);
+
+    // The arbiter rules the rejection fraud and slashes the provider's stake
+    let provider_stake = env.pda(&[b"provider_stake", provider.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::StakeCollateral {
+            provider_stake,
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::StakeCollateral {
+            lamports: 200_000_000,
+        },
+        &[&provider],
+    )?;
+    results.insert("stake_collateral".into(), cu);
+    let cu = env.send(
+        accounts::UnstakeCollateral {
+            provider_stake,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::UnstakeCollateral {
+            lamports: 50_000_000,
+        },
+        &[&provider],
+    )?;
+    results.insert("unstake_collateral".into(), cu);
+    let cu = env.send(
+        accounts::RuleFraud {
+            claim_dispute,
+            fraud_finding: env.pda(&[b"fraud_finding", claim.as_ref()]),
+            provider_stake: Some(provider_stake),
+            provider_stats: env.pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+            arbiter: arbiter.pubkey(),
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RuleFraud {
+            slash_lamports: 100_000_000,
+        },
+        &[&arbiter],
+    )?;
+    results.insert("rule_fraud".into(), cu);
+
+    // Filed by mistake and withdrawn before the provider accepts it
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-cancel", 6)?;
+    let fingerprint = ClaimDedup::fingerprint(
+        &patient.pubkey(),
+        &provider.pubkey(),
+        &claim_lines(),
+        env.now() - 6 * 24 * 60 * 60,
+    );
+    let cu = env.send(
+        accounts::CancelClaim {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            rent_payer: patient.pubkey(),
+            claim_dedup: Some(env.pda(&[b"claim_dedup", &fingerprint])),
+            auto_approval: None,
+            claim_grant: Some(env.pda(&[b"claim_grant", claim.as_ref()])),
+            claim_escrow: env.pda(&[b"claim_escrow", claim.as_ref()]),
+            config,
+        },
+        instruction::CancelClaim {},
+        &[&patient],
+    )?;
+    results.insert("cancel_claim".into(), cu);
+
+    // Escrowed while in review, then refunded on rejection
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-escrow", 4)?;
+    let claim_escrow = env.pda(&[b"claim_escrow", claim.as_ref()]);
+    let cu = env.send(
+        accounts::FundClaimEscrow {
+            claim_account: claim,
+            claim_escrow,
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::FundClaimEscrow {},
+        &[&provider],
+    )?;
+    results.insert("fund_claim_escrow".into(), cu);
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    env.send(
+        accounts::VerifyClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Rejected,
+        },
+        &[&provider],
+    )?;
+    let cu = env.send(
+        accounts::SettleClaim {
+            claim_account: claim,
+            claim_escrow,
+            provider: provider.pubkey(),
+            patient: patient.pubkey(),
+            settlement,
+            signer: patient.pubkey(),
+            cosigner: None,
+            crank_vault: None,
+            protocol_treasury,
+            facilitator: None,
+            facilitator_signer: None,
+            fee_rebates: None,
+            provider_volume: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SettleClaim { period },
+        &[&patient],
+    )?;
+    results.insert("settle_claim".into(), cu);
+
+    // In split custody with the arbiter, refunded with the provider and
+    // arbiter signing
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-split", 8)?;
+    let claim_escrow = env.pda(&[b"claim_escrow", claim.as_ref()]);
+    let cu = env.send(
+        accounts::FundClaimEscrow {
+            claim_account: claim,
+            claim_escrow,
+            provider: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::FundSplitEscrow {
+            arbiter: arbiter.pubkey(),
+        },
+        &[&provider],
+    )?;
+    results.insert("fund_split_escrow".into(), cu);
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    env.send(
+        accounts::VerifyClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Rejected,
+        },
+        &[&provider],
+    )?;
+    let cu = env.send(
+        accounts::SettleClaim {
+            claim_account: claim,
+            claim_escrow,
+            provider: provider.pubkey(),
+            patient: patient.pubkey(),
+            settlement,
+            signer: provider.pubkey(),
+            cosigner: Some(arbiter.pubkey()),
+            crank_vault: None,
+            protocol_treasury,
+            facilitator: None,
+            facilitator_signer: None,
+            fee_rebates: None,
+            provider_volume: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SettleClaim { period },
+        &[&provider, &arbiter],
+    )?;
+    results.insert("settle_split_escrow".into(), cu);
+
+    // Under a policy needing the provider and an insurer
+    let insurer = env.funded_keypair()?;
+    let policy_id = "bench-policy";
+    let approval_policy = env.pda(&[
+        b"approval_policy",
+        provider.pubkey().as_ref(),
+        policy_id.as_bytes(),
+    ]);
+    let cu = env.send(
+        accounts::SetApprovalPolicy {
+            approval_policy,
+            authority: provider.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetApprovalPolicy {
+            policy_id: policy_id.to_string(),
+            threshold: 0,
+            approvers: vec![provider.pubkey(), insurer.pubkey()],
+        },
+        &[&provider],
+    )?;
+    results.insert("set_approval_policy".into(), cu);
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-approvals", 5)?;
+    let cu = env.send(
+        accounts::AttachApprovalPolicy {
+            claim_account: claim,
+            approval_policy,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AttachApprovalPolicy {},
+        &[&provider],
+    )?;
+    results.insert("attach_approval_policy".into(), cu);
+    for approver in [&provider, &insurer] {
+        let cu = env.send(
+            accounts::ApproveClaimPayment {
+                claim_account: claim,
+                approval_policy,
+                approver: approver.pubkey(),
+                config,
+            },
+            instruction::ApproveClaimPayment {},
+            &[approver],
+        )?;
+        results.insert("approve_claim_payment".into(), cu);
+    }
+
+    // A tenant network with its own members, claims and fee
+    let tenant_admin = env.funded_keypair()?;
+    let treasury = env.funded_keypair()?.pubkey();
+    let tenant_id = "bench-network";
+    let tenant = env.pda(&[b"tenant", tenant_id.as_bytes()]);
+    let cu = env.send(
+        accounts::CreateTenant {
+            tenant,
+            admin: tenant_admin.pubkey(),
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CreateTenant {
+            tenant_id: tenant_id.to_string(),
+            treasury,
+            fee_bps: 200,
+        },
+        &[&tenant_admin],
+    )?;
+    results.insert("create_tenant".into(), cu);
+    let cu = env.send(
+        accounts::UpdateTenant {
+            tenant,
+            admin: tenant_admin.pubkey(),
+            config,
+        },
+        instruction::UpdateTenant {
+            admin: tenant_admin.pubkey(),
+            treasury,
+            fee_bps: MAX_TENANT_FEE_BPS,
+        },
+        &[&tenant_admin],
+    )?;
+    results.insert("update_tenant".into(), cu);
+    let mut memberships = Vec::new();
+    for (member, role) in [
+        (&patient, TenantRole::Patient),
+        (&provider, TenantRole::Provider),
+    ] {
+        let membership = env.pda(&[b"tenant_member", tenant.as_ref(), member.pubkey().as_ref()]);
+        let cu = env.send(
+            accounts::AdmitTenantMember {
+                tenant,
+                membership,
+                member: member.pubkey(),
+                admin: tenant_admin.pubkey(),
+                config,
+                system_program: system_program::ID,
+            },
+            instruction::AdmitTenantMember { role },
+            &[&tenant_admin],
+        )?;
+        results.insert("admit_tenant_member".into(), cu);
+        memberships.push(membership);
+    }
+    // The provider's front desk checks the patient's coverage
+    let cu = env.send(
+        accounts::GetCoverageProof {
+            tenant,
+            patient: patient.pubkey(),
+            patient_membership: memberships[0],
+            provider: provider.pubkey(),
+            provider_membership: Some(memberships[1]),
+        },
+        instruction::GetCoverageProof {},
+        &[&provider],
+    )?;
+    results.insert("get_coverage_proof".into(), cu);
+    // The plan discounts the premium of patients who consent to research,
+    // so the premium below is paid with the consent checked
+    let plan_benefits = env.pda(&[b"plan_benefits", tenant.as_ref()]);
+    let cu = env.send(
+        accounts::SetPlanBenefits {
+            tenant,
+            plan_benefits,
+            admin: tenant_admin.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::SetPlanBenefits {
+            premium: LAMPORTS_PER_SOL / 10,
+            research_discount_bps: 1_500,
+            research_categories: DataCategory::LabResult.bit(),
+            out_of_pocket_max: LAMPORTS_PER_SOL,
+        },
+        &[&tenant_admin],
+    )?;
+    results.insert("set_plan_benefits".into(), cu);
+    let research_consent = env.pda(&[b"research_consent", patient.pubkey().as_ref()]);
+    let cu = env.send(
+        accounts::GrantResearchConsent {
+            research_consent,
+            patient: patient.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::GrantResearchConsent {
+            categories: DataCategory::LabResult.bit() | DataCategory::Vitals.bit(),
+            expires_at: 0,
+        },
+        &[&patient],
+    )?;
+    results.insert("grant_research_consent".into(), cu);
+    let cu = env.send(
+        accounts::PayPremium {
+            tenant,
+            plan_benefits,
+            patient_membership: memberships[0],
+            research_consent: Some(research_consent),
+            premium_payment: env.pda(&[
+                b"premium_payment",
+                tenant.as_ref(),
+                patient.pubkey().as_ref(),
+                &period.to_le_bytes(),
+            ]),
+            patient: patient.pubkey(),
+            treasury,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::PayPremium { period },
+        &[&patient],
+    )?;
+    results.insert("pay_premium".into(), cu);
+    let cu = env.send(
+        accounts::RevokeResearchConsent {
+            research_consent,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::RevokeResearchConsent {},
+        &[&patient],
+    )?;
+    results.insert("revoke_research_consent".into(), cu);
+
+    let claim_id = "bench-tenant";
+    let tenant_claim = env.pda(&[b"claim", tenant.as_ref(), claim_id.as_bytes()]);
+    let line_items = vec![LineItemInput {
+        code: "99213".into(),
+        units: 1,
+        unit_price: 1_000_000,
+        modifier: String::new(),
+    }];
+    // Tenant claims cannot be for care before the patient was admitted
+    let service_date = env.now();
+    let fingerprint = ClaimDedup::fingerprint(
+        &patient.pubkey(),
+        &provider.pubkey(),
+        &line_items,
+        service_date,
+    );
+    let cu = env.send(
+        accounts::CreateTenantClaim {
+            tenant,
+            claim_account: tenant_claim,
+            patient: patient.pubkey(),
+            patient_membership: memberships[0],
+            filer: patient.pubkey(),
+            guardian_link: None,
+            delegate_account: None,
+            provider: provider.pubkey(),
+            insurer: None,
+            provider_membership: memberships[1],
+            provider_stats: env.pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+            claim_dedup: env.pda(&[b"claim_dedup", &fingerprint]),
+            claim_grant: None,
+            feature_flags,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::CreateTenantClaim {
+            claim_id: claim_id.to_string(),
+            line_items,
+            attachments: Vec::new(),
+            service_start: service_date,
+            service_end: service_date,
+            fingerprint,
+        },
+        &[&patient],
+    )?;
+    results.insert("create_tenant_claim".into(), cu);
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: tenant_claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: tenant_claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AdjudicateLine {
+            index: 0,
+            decision: LineDecision::Approve,
+            reason_code: 0,
+        },
+        &[&provider],
+    )?;
+    // The claim is filed this month, so its year of service is this one
+    let year = period / 100;
+    let out_of_pocket = env.pda(&[
+        b"out_of_pocket",
+        tenant.as_ref(),
+        patient.pubkey().as_ref(),
+        &year.to_le_bytes(),
+    ]);
+    let cu = env.send(
+        accounts::VerifyClaim {
+            claim_account: tenant_claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: Some(plan_benefits),
+            out_of_pocket: Some(out_of_pocket),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Verified,
+        },
+        &[&provider],
+    )?;
+    results.insert("verify_tenant_claim".into(), cu);
+    let cu = env.send(
+        accounts::ProcessPayment {
+            claim_account: tenant_claim,
+            provider: provider.pubkey(),
+            patient: patient.pubkey(),
+            settlement,
+            tenant: Some(tenant),
+            treasury: Some(treasury),
+            payment_schedule: None,
+            audit_trail: None,
+            audit_entry: None,
+            protocol_treasury,
+            clawback_config: None,
+            payment_hold: None,
+            facilitator: None,
+            facilitator_signer: None,
+            fee_rebates: None,
+            provider_volume: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::ProcessPayment { period },
+        &[&provider],
+    )?;
+    results.insert("process_payment_with_tenant_fee".into(), cu);
+    let cu = env.send(
+        accounts::WithdrawTreasury {
+            config,
+            admin: flags_admin.pubkey(),
+            protocol_treasury,
+            recipient: flags_admin.pubkey(),
+        },
+        instruction::WithdrawTreasury { lamports: 1 },
+        &[&flags_admin],
+    )?;
+    results.insert("withdraw_treasury".into(), cu);
+    let facilitator_authority = solana_keypair::Keypair::new().pubkey();
+    let cu = env.send(
+        accounts::ApproveFacilitator {
+            config,
+            admin: flags_admin.pubkey(),
+            authority: facilitator_authority,
+            facilitator: env.pda(&[b"facilitator", facilitator_authority.as_ref()]),
+            system_program: system_program::ID,
+        },
+        instruction::ApproveFacilitator {
+            signing_key: solana_keypair::Keypair::new().pubkey(),
+            fee_share_bps: 2_000,
+        },
+        &[&flags_admin],
+    )?;
+    results.insert("approve_facilitator".into(), cu);
+    let cu = env.send(
+        accounts::RemoveTenantMember {
+            tenant,
+            membership: memberships[0],
+            admin: tenant_admin.pubkey(),
+            config,
+        },
+        instruction::RemoveTenantMember {},
+        &[&tenant_admin],
+    )?;
+    results.insert("remove_tenant_member".into(), cu);
+
+    // Declining a claim and moving it to another provider
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-reassign", 3)?;
+    let cu = env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::DeclineClaim {},
+        &[&provider],
+    )?;
+    results.insert("decline_claim".into(), cu);
+    let service_date = env.now() - 3 * 24 * 60 * 60;
+    let codes: Vec<String> = (0..MAX_LINE_ITEMS)
+        .map(|n| format!("{}", 99200 + n))
+        .collect();
+    let fingerprint_for = |provider: &anchor_lang::prelude::Pubkey| {
+        ClaimDedup::fingerprint_codes(
+            &patient.pubkey(),
+            provider,
+            codes.iter().map(String::as_str),
+            service_date,
+        )
+    };
+    let new_provider = env.funded_keypair()?.pubkey();
+    let fingerprint = fingerprint_for(&new_provider);
+    let cu = env.send(
+        accounts::ReassignClaim {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            new_provider,
+            new_provider_membership: None,
+            claim_dedup: env.pda(&[b"claim_dedup", &fingerprint_for(&provider.pubkey())]),
+            new_claim_dedup: env.pda(&[b"claim_dedup", &fingerprint]),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::ReassignClaim { fingerprint },
+        &[&patient],
+    )?;
+    results.insert("reassign_claim".into(), cu);
+
+    // Holds, attachment edits, notes and the reject/reopen path
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-reopen", 2)?;
+    env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::AcceptClaim {},
+        &[&provider],
+    )?;
+    let cu = env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::PlaceOnHold {},
+        &[&provider],
+    )?;
+    results.insert("place_on_hold".into(), cu);
+    let extra = record_hash(10);
+    let cu = env.send(
+        accounts::UpdateAttachments {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::AddAttachment {
+            health_data_hash: extra,
+        },
+        &[&patient],
+    )?;
+    results.insert("add_attachment".into(), cu);
+    let cu = env.send(
+        accounts::UpdateAttachments {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            config,
+        },
+        instruction::RemoveAttachment {
+            health_data_hash: extra,
+        },
+        &[&patient],
+    )?;
+    results.insert("remove_attachment".into(), cu);
+    let cu = env.send(
+        accounts::ReviewClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            config,
+        },
+        instruction::ReleaseHold {},
+        &[&provider],
+    )?;
+    results.insert("release_hold".into(), cu);
+
+    let notes_page = env.pda(&[b"claim_notes", claim.as_ref(), &0u32.to_le_bytes()]);
+    for (n, author) in [&patient, &provider].into_iter().enumerate() {
+        let cu = env.send(
+            accounts::AppendClaimNote {
+                claim_account: claim,
+                notes_page,
+                author: author.pubkey(),
+                config,
+                system_program: system_program::ID,
+            },
+            instruction::AppendClaimNote {
+                page: 0,
+                note_hash: format!("{:064x}", 20 + n),
+            },
+            &[author],
+        )?;
+        // The first note pays for the page; the second is the steady-state cost
+        let name = if n == 0 {
+            "append_claim_note_new_page"
+        } else {
+            "append_claim_note"
+        };
+        results.insert(name.into(), cu);
+    }
+    let cu = env.send(
+        accounts::SetClaimReferences {
+            claim_account: claim,
+            signer: provider.pubkey(),
+            config,
+        },
+        instruction::SetClaimReferences {
+            external_ref: "CLM-0001".into(),
+            tags: vec!["batch-7".into(), "payer-42".into()],
+        },
+        &[&provider],
+    )?;
+    results.insert("set_claim_references".into(), cu);
+
+    let cu = env.send(
+        accounts::VerifyClaim {
+            claim_account: claim,
+            provider: provider.pubkey(),
+            provider_credential,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::VerifyClaim {
+            status: ClaimStatus::Rejected,
+        },
+        &[&provider],
+    )?;
+    results.insert("verify_claim_reject".into(), cu);
+    let cu = env.send(
+        accounts::ReopenClaim {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            settlement,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::ReopenClaim {
+            new_attachments: vec![record_hash(30)],
+            period,
+        },
+        &[&patient],
+    )?;
+    results.insert("reopen_claim".into(), cu);
+
+    env.warp_to(settlement::period_end(period));
+    let cu = env.send(
+        accounts::CloseSettlementPeriod {
+            settlement,
+            signer: provider.pubkey(),
+            config,
+        },
+        instruction::CloseSettlementPeriod {},
+        &[&provider],
+    )?;
+    results.insert("close_settlement_period".into(), cu);
+
+    // Auto-approval of a claim the provider never decided
+    let claim = create_claim(env, &mut results, &patient, &provider, "bench-auto", 1)?;
+    let auto_approval = env.pda(&[b"auto_approval", claim.as_ref()]);
+    let cu = env.send(
+        accounts::EnableAutoApproval {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            provider: provider.pubkey(),
+            provider_credential,
+            auto_approval,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::EnableAutoApproval { days: 1 },
+        &[&patient, &provider],
+    )?;
+    results.insert("enable_auto_approval".into(), cu);
+    // The consultant breaks the glass; the access is open a day later
+    let emergency_access = env.pda(&[
+        b"emergency_access",
+        emergency_record.as_ref(),
+        consultant.pubkey().as_ref(),
+    ]);
+    env.send(
+        accounts::RequestEmergencyAccess {
+            health_data_account: env.pda(&[b"health_data", emergency_record.as_ref()]),
+            provider_account: consultant_provider,
+            emergency_access,
+            provider: consultant.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RequestEmergencyAccess {
+            data_hash: emergency_record,
+            justification_hash: record_hash(82),
+        },
+        &[&consultant],
+    )?;
+    env.warp_to(env.now() + 24 * 60 * 60);
+    let cranker = env.funded_keypair()?;
+    let cu = env.send(
+        accounts::AutoApproveClaim {
+            claim_account: claim,
+            patient: patient.pubkey(),
+            auto_approval,
+            cranker: cranker.pubkey(),
+            crank_vault: None,
+            plan_benefits: None,
+            out_of_pocket: None,
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::AutoApproveClaim {},
+        &[&cranker],
+    )?;
+    results.insert("auto_approve_claim".into(), cu);
+
+    // With a day gone by, the wearable rolls up yesterday's readings: one
+    // every 15 minutes, proven individually at the deepest proof
+    let day = env.now().div_euclid(24 * 60 * 60) - 1;
+    let readings: Vec<(i64, i64)> = (0..96)
+        .map(|n| (day * 24 * 60 * 60 + n * 15 * 60, 60 + n % 40))
+        .collect();
+    let leaves: Vec<[u8; 32]> = readings
+        .iter()
+        .map(|&(timestamp, value)| disclosure::reading_leaf(timestamp, value))
+        .collect();
+    let device_rollup = env.pda(&[
+        b"device_rollup",
+        device_account.as_ref(),
+        &day.to_le_bytes(),
+    ]);
+    let cu = env.send(
+        accounts::RollUpDeviceReadings {
+            device_rollup,
+            device_account,
+            patient: patient.pubkey(),
+            device: device.pubkey(),
+            config,
+            system_program: system_program::ID,
+        },
+        instruction::RollUpDeviceReadings {
+            day,
+            readings_root: disclosure::merkle_root(&leaves),
+            summary: ReadingSummary {
+                reading_count: readings.len() as u32,
+                min_value: 60,
+                max_value: 99,
+                avg_value: 77,
+            },
+        },
+        &[&device],
+    )?;
+    results.insert("roll_up_device_readings".into(), cu);
+    let (timestamp, value) = readings[95];
+    let cu = env.send(
+        accounts::VerifyRollupReading { device_rollup },
+        instruction::VerifyRollupReading {
+            timestamp,
+            value,
+            index: 95,
+            proof: disclosure::merkle_proof(&leaves, 95),
+        },
+        &[&cranker],
+    )?;
+    results.insert("verify_rollup_reading".into(), cu);
+    let cu = env.send(
+        accounts::CheckEmergencyAccess {
+            emergency_access,
+            record: env.pda(&[b"health_data", emergency_record.as_ref()]),
+            requester: consultant.pubkey(),
+        },
+        instruction::CheckEmergencyAccess {},
+        &[&consultant],
+    )?;
+    results.insert("check_emergency_access".into(), cu);
+
+    let cu = env.send(
+        accounts::RevokeProvider {
+            registry_authority,
+            provider_credential,
+            provider: provider.pubkey(),
+            authority: credentialing.pubkey(),
+            config,
+        },
+        instruction::RevokeProvider {},
+        &[&credentialing],
+    )?;
+    results.insert("revoke_provider".into(), cu);
+
+    Ok(results)
+}
+
+fn create_claim(
+    env: &mut BenchEnv,
+    results: &mut Measurements,
+    patient: &solana_keypair::Keypair,
+    provider: &solana_keypair::Keypair,
+    claim_id: &str,
+    days_ago: i64,
+) -> Result<anchor_lang::prelude::Pubkey> {
+    let claim_account = env.pda(&[b"claim", claim_id.as_bytes()]);
+    let line_items = claim_lines();
+    let service_date = env.now() - days_ago * 24 * 60 * 60;
+    let fingerprint = ClaimDedup::fingerprint(
+        &patient.pubkey(),
+        &provider.pubkey(),
+        &line_items,
+        service_date,
+    );
+    let cu = env.send(
+        accounts::CreateClaim {
+            claim_account,
+            patient: patient.pubkey(),
+            filer: patient.pubkey(),
+            guardian_link: None,
+            delegate_account: None,
+            provider: provider.pubkey(),
+            insurer: None,
+            mint: None,
+            provider_stats: env.pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+            claim_dedup: env.pda(&[b"claim_dedup", &fingerprint]),
+            claim_grant: Some(env.pda(&[b"claim_grant", claim_account.as_ref()])),
+            config: env.pda(&[b"config"]),
+            system_program: system_program::ID,
+        },
+        instruction::CreateClaim {
+            claim_id: claim_id.to_string(),
+            line_items,
+            attachments: vec![record_hash(0)],
+            service_start: service_date,
+            service_end: service_date,
+            fingerprint,
+        },
+        &[patient],
+    )?;
+    results.insert("create_claim".into(), cu);
+    Ok(claim_account)
+}
+
+/// The lines of every claim `create_claim` files.
+fn claim_lines() -> Vec<LineItemInput> {
+    (0..MAX_LINE_ITEMS)
+        .map(|n| LineItemInput {
+            code: format!("{}", 99200 + n),
+            units: 1,
+            unit_price: 1_000_000,
+            modifier: String::new(),
+        })
+        .collect()
+}
+
+/// An IPFS pointer with the longest CID allowed, committing to the payload
+/// the record is named after.
+fn pointer(data_hash: [u8; 32]) -> RecordPointer {
+    RecordPointer {
+        storage: StorageKind::Ipfs,
+        cid: "b".repeat(MAX_POINTER_LEN),
+        commitment: data_hash,
+    }
+}
+
+/// Distinct record hashes, none of them zeroed.
+fn record_hash(n: usize) -> [u8; 32] {
+    let mut hash = [0xff; 32];
+    hash[..8].copy_from_slice(&(n as u64).to_le_bytes());
+    hash
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anyhow::{anyhow, Context, Result};
+use litesvm::LiteSVM;
+use primal_health_solana_program::settlement;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+/// Clock the SVM starts at, so date arguments can lie in its past.
+const START_TIMESTAMP: i64 = 1_700_000_000;
+
+/// An in-process SVM with the program loaded, used to measure instructions.
+pub struct BenchEnv {
+    svm: LiteSVM,
+}
+
+impl BenchEnv {
+    pub fn new(program_so: &Path) -> Result<Self> {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(primal_health_solana_program::ID, program_so)
+            .with_context(|| {
+                format!("loading {}; run `anchor build` first", program_so.display())
+            })?;
+        let mut env = Self { svm };
+        env.warp_to(START_TIMESTAMP);
+        Ok(env)
+    }
+
+    pub fn funded_keypair(&mut self) -> Result<Keypair> {
+        let keypair = Keypair::new();
+        self.svm
+            .airdrop(&keypair.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .map_err(|failed| anyhow!("airdrop failed: {:?}", failed.err))?;
+        Ok(keypair)
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    /// Settlement period of the SVM's current clock.
+    pub fn current_period(&self) -> u32 {
+        settlement::period_of(self.now())
+    }
+
+    /// Moves the SVM clock to `unix_timestamp`.
+    pub fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    pub fn pda(&self, seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &primal_health_solana_program::ID).0
+    }
+
+    /// Sends a single program instruction and returns the compute units it consumed.
+    pub fn send(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+        signers: &[&Keypair],
+    ) -> Result<u64> {
+        self.send_with_remaining(accounts, Vec::new(), args, signers)
+    }
+
+    /// Like [`send`](Self::send), appending `remaining` to the instruction's accounts.
+    pub fn send_with_remaining(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        remaining: Vec<AccountMeta>,
+        args: impl InstructionData,
+        signers: &[&Keypair],
+    ) -> Result<u64> {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(remaining);
+        let instruction = Instruction {
+            program_id: primal_health_solana_program::ID,
+            accounts: metas,
+            data: args.data(),
+        };
+        let payer = signers.first().context("at least one signer is required")?;
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        // Identical transactions in a row would otherwise be rejected as duplicates
+        self.svm.expire_blockhash();
+
+        match self.svm.send_transaction(tx) {
+            Ok(meta) => Ok(meta.compute_units_consumed),
+            Err(failed) => Err(anyhow!(
+                "transaction failed: {:?}\n{}",
+                failed.err,
+                failed.meta.logs.join("\n")
+            )),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Compute-unit benchmark for every program instruction.
+//!
+//! Runs each instruction against the built program in LiteSVM and compares the
+//! compute units consumed with `tools/cu-bench/baseline.json`, exiting non-zero
+//! when any instruction regresses by more than the tolerance.
+//!
+//! Usage: `cu-bench [--update] [--tolerance <percent>] [--program <path.so>]`
+//! (run from the workspace root after `anchor build`).
+
+mod cases;
+mod env;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+
+use crate::cases::Measurements;
+use crate::env::BenchEnv;
+
+const DEFAULT_PROGRAM: &str = "target/deploy/primal_health_solana_program.so";
+const DEFAULT_BASELINE: &str = "tools/cu-bench/baseline.json";
+const DEFAULT_TOLERANCE_PCT: f64 = 5.0;
+
+struct Options {
+    update: bool,
+    tolerance_pct: f64,
+    program: PathBuf,
+    baseline: PathBuf,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        update: false,
+        tolerance_pct: DEFAULT_TOLERANCE_PCT,
+        program: PathBuf::from(DEFAULT_PROGRAM),
+        baseline: PathBuf::from(DEFAULT_BASELINE),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--update" => options.update = true,
+            "--tolerance" => {
+                options.tolerance_pct = args
+                    .next()
+                    .context("--tolerance needs a value")?
+                    .parse()
+                    .context("--tolerance must be a number")?
+            }
+            "--program" => options.program = args.next().context("--program needs a path")?.into(),
+            "--baseline" => {
+                options.baseline = args.next().context("--baseline needs a path")?.into()
+            }
+            other => bail!("unknown argument {other}"),
+        }
+    }
+    Ok(options)
+}
+
+fn main() -> Result<ExitCode> {
+    let options = parse_args()?;
+    let mut env = BenchEnv::new(&options.program)?;
+    let measured = cases::run_all(&mut env)?;
+
+    if options.update {
+        std::fs::write(
+            &options.baseline,
+            serde_json::to_string_pretty(&measured)? + "\n",
+        )?;
+        println!(
+            "Wrote {} entries to {}",
+            measured.len(),
+            options.baseline.display()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let baseline: Measurements = match std::fs::read_to_string(&options.baseline) {
+        Ok(raw) => serde_json::from_str(&raw)
+            .with_context(|| format!("parsing {}", options.baseline.display()))?,
+        Err(_) => {
+            print_table(&measured, &Measurements::new(), options.tolerance_pct);
+            println!(
+                "No baseline at {}; run with --update to record one.",
+                options.baseline.display()
+            );
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+
+    let regressions = print_table(&measured, &baseline, options.tolerance_pct);
+    if regressions > 0 {
+        println!(
+            "{regressions} instruction(s) regressed by more than {}%",
+            options.tolerance_pct
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints measured vs baseline compute units and returns the number of regressions.
+fn print_table(measured: &Measurements, baseline: &Measurements, tolerance_pct: f64) -> usize {
+    let mut regressions = 0;
+    println!(
+        "{:<30} {:>10} {:>10} {:>8}",
+        "instruction", "baseline", "measured", "change"
+    );
+    for (name, &cu) in measured {
+        let Some(&base) = baseline.get(name) else {
+            println!("{name:<30} {:>10} {cu:>10} {:>8}", "-", "new");
+            continue;
+        };
+        let change_pct = (cu as f64 - base as f64) / base as f64 * 100.0;
+        let flag = if change_pct > tolerance_pct {
+            regressions += 1;
+            "  REGRESSION"
+        } else {
+            ""
+        };
+        println!("{name:<30} {base:>10} {cu:>10} {change_pct:>+7.1}%{flag}");
+    }
+    regressions
+}
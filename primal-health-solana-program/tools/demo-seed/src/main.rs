@@ -0,0 +1,53 @@
+//! Spins up a local validator with the program deployed and seeds it with demo
+//! patients, providers and claims described by a TOML scenario file.
+//!
+//! Usage: `demo-seed [scenario.toml]` (defaults to `tools/demo-seed/scenarios/default.toml`).
+//! Relative paths in the scenario are resolved from the current directory, normally
+//! the workspace root.
+
+mod scenario;
+mod seed;
+mod validator;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::scenario::Scenario;
+use crate::seed::Seeder;
+use crate::validator::LocalValidator;
+
+fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("tools/demo-seed/scenarios/default.toml"));
+    let scenario = Scenario::load(&path)?;
+
+    let validator = if scenario.validator.spawn {
+        println!("Starting solana-test-validator...");
+        Some(LocalValidator::start(&scenario.validator)?)
+    } else {
+        None
+    };
+
+    let mut seeder = Seeder::new(&scenario.validator.rpc_url)?;
+    let state = seeder.run(&scenario)?;
+    std::fs::write(&scenario.output, serde_json::to_string_pretty(&state)?)?;
+    println!(
+        "Seeded {} patients, {} providers and {} claims; wrote {}",
+        state.patients.len(),
+        state.providers.len(),
+        state.claims.len(),
+        scenario.output.display()
+    );
+
+    if let Some(validator) = validator {
+        println!(
+            "Validator running at {}; press Ctrl-C to stop.",
+            scenario.validator.rpc_url
+        );
+        validator.wait()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub validator: ValidatorConfig,
+    /// Where the generated keypairs and account addresses are written.
+    #[serde(default = "default_output")]
+    pub output: PathBuf,
+    pub patients: usize,
+    pub providers: usize,
+    #[serde(default)]
+    pub claims: ClaimMix,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorConfig {
+    /// Start a fresh `solana-test-validator`; disable to seed an already running one.
+    #[serde(default = "default_true")]
+    pub spawn: bool,
+    #[serde(default = "default_rpc_url")]
+    pub rpc_url: String,
+    #[serde(default = "default_ledger")]
+    pub ledger: PathBuf,
+    #[serde(default = "default_program_so")]
+    pub program_so: PathBuf,
+}
+
+impl Default for ValidatorConfig {
+    fn default() -> Self {
+        Self {
+            spawn: default_true(),
+            rpc_url: default_rpc_url(),
+            ledger: default_ledger(),
+            program_so: default_program_so(),
+        }
+    }
+}
+
+/// Number of claims to leave in each lifecycle status.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClaimMix {
+    #[serde(default)]
+    pub pending: usize,
+    #[serde(default)]
+    pub verified: usize,
+    #[serde(default)]
+    pub paid: usize,
+    #[serde(default)]
+    pub rejected: usize,
+    #[serde(default)]
+    pub under_review: usize,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading scenario {}", path.display()))?;
+        let scenario: Scenario =
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        anyhow::ensure!(scenario.patients > 0, "scenario needs at least one patient");
+        anyhow::ensure!(
+            scenario.providers > 0,
+            "scenario needs at least one provider"
+        );
+        Ok(scenario)
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rpc_url() -> String {
+    "http://127.0.0.1:8899".to_string()
+}
+
+fn default_ledger() -> PathBuf {
+    PathBuf::from("test-ledger")
+}
+
+fn default_program_so() -> PathBuf {
+    PathBuf::from("target/deploy/primal_health_solana_program.so")
+}
+
+fn default_output() -> PathBuf {
+    PathBuf::from("demo-state.json")
+}
@@ -0,0 +1,583 @@
+use std::rc::Rc;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anchor_client::anchor_lang::system_program;
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signer};
+use anchor_client::{Client, Cluster, Program};
+use anyhow::{bail, Result};
+use primal_health_solana_program::{
+    accounts, instruction, settlement, ClaimDedup, ClaimStatus, DataCategory, HealthRecordEntry,
+    LineDecision, LineItemInput, RecordPointer, StorageKind,
+};
+use serde::Serialize;
+
+use crate::scenario::Scenario;
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+const SAMPLE_LINES: &[(&str, u32, u64, &str)] = &[
+    ("99213", 1, 25_000_000, ""),
+    ("80053", 1, 12_000_000, ""),
+    ("71046", 2, 8_000_000, "26"),
+    ("93000", 1, 5_000_000, ""),
+];
+const SAMPLE_PROVIDERS: &[&str] = &[
+    "Northside Family Clinic",
+    "Lakeview Diagnostics",
+    "St. Brigid Medical Center",
+    "Harbor Urgent Care",
+];
+
+/// Lifecycle status a seeded claim is driven to.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetStatus {
+    Pending,
+    Verified,
+    Paid,
+    Rejected,
+    UnderReview,
+}
+
+#[derive(Serialize)]
+pub struct DemoState {
+    pub program_id: String,
+    pub rpc_url: String,
+    pub patients: Vec<DemoActor>,
+    pub providers: Vec<DemoActor>,
+    pub claims: Vec<DemoClaim>,
+}
+
+#[derive(Serialize)]
+pub struct DemoActor {
+    pub pubkey: String,
+    pub account: String,
+    /// Secret key bytes, loadable with `solana-keygen` or `Keypair::from_bytes`.
+    pub keypair: Vec<u8>,
+}
+
+#[derive(Serialize)]
+pub struct DemoClaim {
+    pub claim_id: String,
+    pub account: String,
+    pub patient: String,
+    pub provider: String,
+    pub status: TargetStatus,
+}
+
+struct Actor {
+    keypair: Keypair,
+    account: Pubkey,
+}
+
+impl Actor {
+    fn to_demo(&self) -> DemoActor {
+        DemoActor {
+            pubkey: self.keypair.pubkey().to_string(),
+            account: self.account.to_string(),
+            keypair: self.keypair.to_bytes().to_vec(),
+        }
+    }
+}
+
+pub struct Seeder {
+    program: Program<Rc<Keypair>>,
+    rpc_url: String,
+    record_counter: usize,
+    config: Pubkey,
+    protocol_treasury: Pubkey,
+}
+
+impl Seeder {
+    pub fn new(rpc_url: &str) -> Result<Self> {
+        let payer = Rc::new(Keypair::new());
+        let cluster = Cluster::Custom(rpc_url.to_string(), rpc_url.replace("http", "ws"));
+        let client =
+            Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+        let program = client.program(primal_health_solana_program::ID)?;
+        let (config, _) = Pubkey::find_program_address(&[b"config"], &program.id());
+        let (protocol_treasury, _) =
+            Pubkey::find_program_address(&[b"protocol_treasury"], &program.id());
+        let seeder = Self {
+            program,
+            rpc_url: rpc_url.to_string(),
+            record_counter: 0,
+            config,
+            protocol_treasury,
+        };
+        seeder.airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL)?;
+        Ok(seeder)
+    }
+
+    pub fn run(&mut self, scenario: &Scenario) -> Result<DemoState> {
+        self.initialize_config()?;
+        self.initialize_registry()?;
+        let patients = (0..scenario.patients)
+            .map(|_| self.create_patient())
+            .collect::<Result<Vec<_>>>()?;
+        let providers = (0..scenario.providers)
+            .map(|i| self.create_provider(SAMPLE_PROVIDERS[i % SAMPLE_PROVIDERS.len()]))
+            .collect::<Result<Vec<_>>>()?;
+
+        for patient in &patients {
+            self.import_history(patient)?;
+        }
+
+        let mix = &scenario.claims;
+        let targets = [
+            (TargetStatus::Pending, mix.pending),
+            (TargetStatus::Verified, mix.verified),
+            (TargetStatus::Paid, mix.paid),
+            (TargetStatus::Rejected, mix.rejected),
+            (TargetStatus::UnderReview, mix.under_review),
+        ]
+        .into_iter()
+        .flat_map(|(status, count)| std::iter::repeat_n(status, count));
+
+        let mut claims = Vec::new();
+        for (n, target) in targets.enumerate() {
+            let patient = &patients[n % patients.len()];
+            let provider = &providers[n % providers.len()];
+            claims.push(self.create_claim(n, patient, provider, target)?);
+        }
+
+        Ok(DemoState {
+            program_id: primal_health_solana_program::ID.to_string(),
+            rpc_url: self.rpc_url.clone(),
+            patients: patients.iter().map(Actor::to_demo).collect(),
+            providers: providers.iter().map(Actor::to_demo).collect(),
+            claims,
+        })
+    }
+
+    fn create_patient(&self) -> Result<Actor> {
+        let keypair = self.funded_keypair()?;
+        let (account, _) = Pubkey::find_program_address(
+            &[b"patient", keypair.pubkey().as_ref()],
+            &self.program.id(),
+        );
+
+        self.program
+            .request()
+            .accounts(accounts::InitializePatient {
+                patient_account: account,
+                authority: keypair.pubkey(),
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitializePatient {
+                did: format!("did:sol:{}", keypair.pubkey()),
+            })
+            .signer(&keypair)
+            .send()?;
+
+        Ok(Actor { keypair, account })
+    }
+
+    /// Makes the seeding payer the config admin. Every instruction that
+    /// writes requires the config, so this runs before anything else.
+    fn initialize_config(&self) -> Result<()> {
+        self.program
+            .request()
+            .accounts(accounts::InitializeConfig {
+                config: self.config,
+                protocol_treasury: self.protocol_treasury,
+                admin: self.program.payer(),
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitializeConfig { fee_bps: 0 })
+            .send()?;
+        Ok(())
+    }
+
+    /// Makes the seeding payer the credentialing authority, so it can verify
+    /// the providers it creates.
+    fn initialize_registry(&self) -> Result<()> {
+        let (registry_authority, _) =
+            Pubkey::find_program_address(&[b"registry_authority"], &self.program.id());
+        self.program
+            .request()
+            .accounts(accounts::InitializeRegistryAuthority {
+                registry_authority,
+                payer: self.program.payer(),
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitializeRegistryAuthority {
+                authority: self.program.payer(),
+            })
+            .send()?;
+        Ok(())
+    }
+
+    fn create_provider(&self, name: &str) -> Result<Actor> {
+        let keypair = self.funded_keypair()?;
+        let (account, _) = Pubkey::find_program_address(
+            &[b"provider", keypair.pubkey().as_ref()],
+            &self.program.id(),
+        );
+
+        self.program
+            .request()
+            .accounts(accounts::InitializeProvider {
+                provider_account: account,
+                authority: keypair.pubkey(),
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::InitializeProvider {
+                did: format!("did:sol:{}", keypair.pubkey()),
+                name: name.to_string(),
+            })
+            .signer(&keypair)
+            .send()?;
+
+        let (registry_authority, _) =
+            Pubkey::find_program_address(&[b"registry_authority"], &self.program.id());
+        let (provider_credential, _) = Pubkey::find_program_address(
+            &[b"provider_credential", keypair.pubkey().as_ref()],
+            &self.program.id(),
+        );
+        self.program
+            .request()
+            .accounts(accounts::VerifyProvider {
+                registry_authority,
+                provider_account: account,
+                provider_credential,
+                provider: keypair.pubkey(),
+                authority: self.program.payer(),
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::VerifyProvider {})
+            .send()?;
+
+        Ok(Actor { keypair, account })
+    }
+
+    /// Imports a short history for the patient in a single batch.
+    fn import_history(&mut self, patient: &Actor) -> Result<()> {
+        let owner = patient.keypair.pubkey();
+        let batch_id = "history".to_string();
+        let entries = [
+            DataCategory::LabResult,
+            DataCategory::Immunization,
+            DataCategory::VisitNote,
+        ]
+        .into_iter()
+        .map(|category| {
+            let data_hash = self.next_record_hash();
+            HealthRecordEntry {
+                pointer: format!("ipfs://demo/{}", self.record_counter),
+                data_hash,
+                category,
+            }
+        })
+        .collect::<Vec<_>>();
+        let (batch_account, _) = Pubkey::find_program_address(
+            &[b"health_data_batch", owner.as_ref(), batch_id.as_bytes()],
+            &self.program.id(),
+        );
+
+        self.program
+            .request()
+            .accounts(accounts::SubmitHealthDataBatch {
+                batch_account,
+                owner,
+                author: owner,
+                grant: None,
+                access_policy: None,
+                author_provider: None,
+                delegate_account: None,
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::SubmitHealthDataBatch { batch_id, entries })
+            .signer(&patient.keypair)
+            .send()?;
+        Ok(())
+    }
+
+    fn submit_record(&mut self, patient: &Actor) -> Result<[u8; 32]> {
+        let data_hash = self.next_record_hash();
+        let (health_data_account, _) =
+            Pubkey::find_program_address(&[b"health_data", data_hash.as_ref()], &self.program.id());
+
+        self.program
+            .request()
+            .accounts(accounts::SubmitHealthData {
+                health_data_account,
+                owner: patient.keypair.pubkey(),
+                author: patient.keypair.pubkey(),
+                grant: None,
+                access_policy: None,
+                author_provider: None,
+                delegate_account: None,
+                owner_key: None,
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::SubmitHealthData {
+                data_hash,
+                pointer: RecordPointer {
+                    storage: StorageKind::Ipfs,
+                    cid: format!("demo/{}", self.record_counter),
+                    commitment: data_hash,
+                },
+                category: DataCategory::LabResult,
+            })
+            .signer(&patient.keypair)
+            .send()?;
+        Ok(data_hash)
+    }
+
+    fn create_claim(
+        &mut self,
+        n: usize,
+        patient: &Actor,
+        provider: &Actor,
+        target: TargetStatus,
+    ) -> Result<DemoClaim> {
+        let claim_id = format!("demo-{n:04}");
+        let (claim_account, _) =
+            Pubkey::find_program_address(&[b"claim", claim_id.as_bytes()], &self.program.id());
+        let (provider_stats, _) = Pubkey::find_program_address(
+            &[b"provider_stats", provider.keypair.pubkey().as_ref()],
+            &self.program.id(),
+        );
+        let line_count = 1 + n % SAMPLE_LINES.len();
+        let line_items: Vec<LineItemInput> = SAMPLE_LINES[..line_count]
+            .iter()
+            .map(|(code, units, unit_price, modifier)| LineItemInput {
+                code: code.to_string(),
+                units: *units,
+                unit_price: *unit_price,
+                modifier: modifier.to_string(),
+            })
+            .collect();
+        // One service day per claim, going back from yesterday, so repeated
+        // line sets between the same pair are not rejected as duplicates
+        let service_date = unix_now() - (n as i64 + 1) * 24 * 60 * 60;
+        let fingerprint = ClaimDedup::fingerprint(
+            &patient.keypair.pubkey(),
+            &provider.keypair.pubkey(),
+            &line_items,
+            service_date,
+        );
+        let (claim_dedup, _) =
+            Pubkey::find_program_address(&[b"claim_dedup", &fingerprint], &self.program.id());
+        let attachment = self.submit_record(patient)?;
+        // Demo providers can read the record they are billed with
+        let (claim_grant, _) = Pubkey::find_program_address(
+            &[b"claim_grant", claim_account.as_ref()],
+            &self.program.id(),
+        );
+
+        self.program
+            .request()
+            .accounts(accounts::CreateClaim {
+                claim_account,
+                patient: patient.keypair.pubkey(),
+                filer: patient.keypair.pubkey(),
+                guardian_link: None,
+                delegate_account: None,
+                provider: provider.keypair.pubkey(),
+                insurer: None,
+                mint: None,
+                provider_stats,
+                claim_dedup,
+                claim_grant: Some(claim_grant),
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::CreateClaim {
+                claim_id: claim_id.clone(),
+                line_items,
+                attachments: vec![attachment],
+                service_start: service_date,
+                service_end: service_date,
+                fingerprint,
+            })
+            .signer(&patient.keypair)
+            .send()?;
+
+        match target {
+            TargetStatus::Pending => {}
+            TargetStatus::Verified => {
+                self.adjudicate(claim_account, provider, line_count, LineDecision::Approve)?;
+                self.verify(claim_account, provider, ClaimStatus::Verified)?;
+            }
+            TargetStatus::Paid => {
+                self.adjudicate(claim_account, provider, line_count, LineDecision::Approve)?;
+                self.verify(claim_account, provider, ClaimStatus::Verified)?;
+                let (settlement, period) = self.current_settlement(provider, patient);
+                self.program
+                    .request()
+                    .accounts(accounts::ProcessPayment {
+                        claim_account,
+                        provider: provider.keypair.pubkey(),
+                        patient: patient.keypair.pubkey(),
+                        settlement,
+                        tenant: None,
+                        treasury: None,
+                        payment_schedule: None,
+                        audit_trail: None,
+                        audit_entry: None,
+                        protocol_treasury: self.protocol_treasury,
+                        clawback_config: None,
+                        payment_hold: None,
+                        facilitator: None,
+                        facilitator_signer: None,
+                        fee_rebates: None,
+                        provider_volume: None,
+                        config: self.config,
+                        system_program: system_program::ID,
+                    })
+                    .args(instruction::ProcessPayment { period })
+                    .signer(&provider.keypair)
+                    .send()?;
+            }
+            TargetStatus::Rejected | TargetStatus::UnderReview => {
+                self.adjudicate(claim_account, provider, line_count, LineDecision::Deny)?;
+                self.verify(claim_account, provider, ClaimStatus::Rejected)?;
+                if let TargetStatus::UnderReview = target {
+                    let evidence = self.submit_record(patient)?;
+                    let (settlement, period) = self.current_settlement(provider, patient);
+                    self.program
+                        .request()
+                        .accounts(accounts::ReopenClaim {
+                            claim_account,
+                            patient: patient.keypair.pubkey(),
+                            provider: provider.keypair.pubkey(),
+                            settlement,
+                            config: self.config,
+                            system_program: system_program::ID,
+                        })
+                        .args(instruction::ReopenClaim {
+                            new_attachments: vec![evidence],
+                            period,
+                        })
+                        .signer(&patient.keypair)
+                        .send()?;
+                }
+            }
+        }
+
+        Ok(DemoClaim {
+            claim_id,
+            account: claim_account.to_string(),
+            patient: patient.keypair.pubkey().to_string(),
+            provider: provider.keypair.pubkey().to_string(),
+            status: target,
+        })
+    }
+
+    /// Accepts the claim and decides every line the same way.
+    fn adjudicate(
+        &self,
+        claim_account: Pubkey,
+        provider: &Actor,
+        line_count: usize,
+        decision: LineDecision,
+    ) -> Result<()> {
+        self.program
+            .request()
+            .accounts(accounts::ReviewClaim {
+                claim_account,
+                provider: provider.keypair.pubkey(),
+                config: self.config,
+            })
+            .args(instruction::AcceptClaim {})
+            .signer(&provider.keypair)
+            .send()?;
+        for index in 0..line_count {
+            self.program
+                .request()
+                .accounts(accounts::ReviewClaim {
+                    claim_account,
+                    provider: provider.keypair.pubkey(),
+                    config: self.config,
+                })
+                .args(instruction::AdjudicateLine {
+                    index: index as u8,
+                    decision: decision.clone(),
+                    reason_code: 0,
+                })
+                .signer(&provider.keypair)
+                .send()?;
+        }
+        Ok(())
+    }
+
+    fn verify(&self, claim_account: Pubkey, provider: &Actor, status: ClaimStatus) -> Result<()> {
+        let (provider_credential, _) = Pubkey::find_program_address(
+            &[b"provider_credential", provider.keypair.pubkey().as_ref()],
+            &self.program.id(),
+        );
+        self.program
+            .request()
+            .accounts(accounts::VerifyClaim {
+                claim_account,
+                provider: provider.keypair.pubkey(),
+                provider_credential,
+                plan_benefits: None,
+                out_of_pocket: None,
+                config: self.config,
+                system_program: system_program::ID,
+            })
+            .args(instruction::VerifyClaim { status })
+            .signer(&provider.keypair)
+            .send()?;
+        Ok(())
+    }
+
+    /// The provider and patient's settlement summary for this month.
+    fn current_settlement(&self, provider: &Actor, patient: &Actor) -> (Pubkey, u32) {
+        let period = settlement::period_of(unix_now());
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"settlement",
+                provider.keypair.pubkey().as_ref(),
+                patient.keypair.pubkey().as_ref(),
+                &period.to_le_bytes(),
+            ],
+            &self.program.id(),
+        );
+        (address, period)
+    }
+
+    /// Distinct stand-ins for the SHA-256 of each demo payload.
+    fn next_record_hash(&mut self) -> [u8; 32] {
+        self.record_counter += 1;
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&(self.record_counter as u64).to_be_bytes());
+        hash
+    }
+
+    fn funded_keypair(&self) -> Result<Keypair> {
+        let keypair = Keypair::new();
+        self.airdrop(&keypair.pubkey(), 10 * LAMPORTS_PER_SOL)?;
+        Ok(keypair)
+    }
+
+    fn airdrop(&self, to: &Pubkey, lamports: u64) -> Result<()> {
+        let rpc = self.program.rpc();
+        let signature = rpc.request_airdrop(to, lamports)?;
+        for _ in 0..60 {
+            if rpc.confirm_transaction(&signature)? {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(500));
+        }
+        bail!("airdrop to {to} was not confirmed")
+    }
+}
+
+/// The local validator runs on wall-clock time.
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
@@ -0,0 +1,65 @@
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::scenario::ValidatorConfig;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A `solana-test-validator` child process with the program preloaded.
+pub struct LocalValidator {
+    child: Child,
+}
+
+impl LocalValidator {
+    pub fn start(config: &ValidatorConfig) -> Result<Self> {
+        if !config.program_so.exists() {
+            bail!(
+                "{} not found; run `anchor build` first",
+                config.program_so.display()
+            );
+        }
+
+        let child = Command::new("solana-test-validator")
+            .arg("--reset")
+            .arg("--quiet")
+            .arg("--ledger")
+            .arg(&config.ledger)
+            .arg("--bpf-program")
+            .arg(primal_health_solana_program::ID.to_string())
+            .arg(&config.program_so)
+            .stdout(Stdio::null())
+            .spawn()
+            .context("spawning solana-test-validator (is the Solana CLI installed?)")?;
+        let mut validator = Self { child };
+
+        let rpc = RpcClient::new(config.rpc_url.clone());
+        let started = Instant::now();
+        while rpc.get_health().is_err() {
+            if let Some(status) = validator.child.try_wait()? {
+                bail!("solana-test-validator exited early with {status}");
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                bail!("solana-test-validator did not become healthy in time");
+            }
+            sleep(Duration::from_millis(500));
+        }
+
+        Ok(validator)
+    }
+
+    /// Blocks until the validator exits, keeping the seeded environment available.
+    pub fn wait(mut self) -> Result<()> {
+        self.child.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for LocalValidator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
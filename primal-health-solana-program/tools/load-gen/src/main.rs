@@ -0,0 +1,244 @@
+//! Load generator for capacity planning.
+//!
+//! Registers a pool of patients and providers, then drives a weighted mix of
+//! registrations, record submissions, claims and payments at a target rate
+//! and reports latency, compute-unit and failure-rate percentiles per
+//! operation.
+//!
+//! Usage: `load-gen [--rpc <url>] [--tps <n>] [--duration <secs>] [--mix register=1,submit=4,claim=2,pay=1]
+//! [--workers <n>] [--patients <n>] [--providers <n>] [--payer <keypair.json>] [--report <path.json>]
+//! [--max-failure-rate <percent>]`.
+//! Without `--payer` a payer is airdropped, which only works against a local
+//! validator; on devnet pass a funded keypair.
+
+mod report;
+mod runner;
+mod workload;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::instruction::Instruction;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use anchor_client::solana_sdk::transaction::Transaction;
+use anyhow::{anyhow, bail, Context, Result};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_system_interface::instruction as system_instruction;
+
+use crate::report::Report;
+use crate::runner::{fetch_compute_units, run, RunConfig};
+use crate::workload::{Mix, Workload};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+/// Each pooled wallet pays rent for the records and claims it creates.
+const ACTOR_FUNDING: u64 = 2 * LAMPORTS_PER_SOL;
+const AIRDROP: u64 = 100 * LAMPORTS_PER_SOL;
+/// Transfers packed into each funding transaction.
+const TRANSFERS_PER_TX: usize = 10;
+
+struct Options {
+    rpc_url: String,
+    tps: f64,
+    duration: Duration,
+    mix: Mix,
+    workers: usize,
+    patients: usize,
+    providers: usize,
+    payer: Option<PathBuf>,
+    report: Option<PathBuf>,
+    max_failure_rate: Option<f64>,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        rpc_url: "http://127.0.0.1:8899".to_string(),
+        tps: 10.0,
+        duration: Duration::from_secs(30),
+        mix: Mix::default(),
+        workers: 16,
+        patients: 8,
+        providers: 4,
+        payer: None,
+        report: None,
+        max_failure_rate: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--rpc" => options.rpc_url = value()?,
+            "--tps" => options.tps = value()?.parse().context("--tps must be a number")?,
+            "--duration" => {
+                options.duration = Duration::from_secs(
+                    value()?
+                        .parse()
+                        .context("--duration must be whole seconds")?,
+                )
+            }
+            "--mix" => options.mix = value()?.parse()?,
+            "--workers" => {
+                options.workers = value()?.parse().context("--workers must be a number")?
+            }
+            "--patients" => {
+                options.patients = value()?.parse().context("--patients must be a number")?
+            }
+            "--providers" => {
+                options.providers = value()?.parse().context("--providers must be a number")?
+            }
+            "--payer" => options.payer = Some(value()?.into()),
+            "--report" => options.report = Some(value()?.into()),
+            "--max-failure-rate" => {
+                options.max_failure_rate = Some(
+                    value()?
+                        .parse()
+                        .context("--max-failure-rate must be a percentage")?,
+                )
+            }
+            other => bail!("unknown argument {other}"),
+        }
+    }
+    if options.tps <= 0.0 || options.workers == 0 || options.patients == 0 || options.providers == 0
+    {
+        bail!("--tps, --workers, --patients and --providers must be positive");
+    }
+    Ok(options)
+}
+
+fn payer(rpc: &RpcClient, path: Option<&PathBuf>) -> Result<Keypair> {
+    if let Some(path) = path {
+        return read_keypair_file(path)
+            .map_err(|err| anyhow!("cannot read {}: {err}", path.display()));
+    }
+    let payer = Keypair::new();
+    let signature = rpc.request_airdrop(&payer.pubkey(), AIRDROP)?;
+    for _ in 0..60 {
+        if rpc.confirm_transaction(&signature)? {
+            return Ok(payer);
+        }
+        sleep(Duration::from_millis(500));
+    }
+    bail!(
+        "airdrop to {} was not confirmed; pass --payer",
+        payer.pubkey()
+    )
+}
+
+fn send(rpc: &RpcClient, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        rpc.get_latest_blockhash()?,
+    );
+    rpc.send_and_confirm_transaction(&tx)?;
+    Ok(())
+}
+
+/// Funds and registers the pooled patients and providers the run draws on,
+/// and credentials the providers. On a cluster without a program config or
+/// credentialing registry the payer creates them; otherwise it must be the
+/// registry authority.
+fn setup(rpc: &RpcClient, options: &Options) -> Result<Workload> {
+    let payer = payer(rpc, options.payer.as_ref())?;
+    let patients: Vec<Keypair> = (0..options.patients).map(|_| Keypair::new()).collect();
+    let providers: Vec<Keypair> = (0..options.providers).map(|_| Keypair::new()).collect();
+
+    let wallets: Vec<&Keypair> = patients.iter().chain(&providers).collect();
+    for chunk in wallets.chunks(TRANSFERS_PER_TX) {
+        let transfers: Vec<Instruction> = chunk
+            .iter()
+            .map(|wallet| {
+                system_instruction::transfer(&payer.pubkey(), &wallet.pubkey(), ACTOR_FUNDING)
+            })
+            .collect();
+        send(rpc, &transfers, &[&payer]).context("funding the pooled wallets failed")?;
+    }
+    let config = rpc
+        .get_account_with_commitment(&Workload::config(), rpc.commitment())?
+        .value;
+    if config.is_none() {
+        send(rpc, &[Workload::initialize_config(&payer)], &[&payer])
+            .context("creating the program config failed")?;
+    }
+    for patient in &patients {
+        send(rpc, &[Workload::initialize_patient(patient)], &[patient])
+            .context("registering a pooled patient failed")?;
+    }
+    let registry = rpc
+        .get_account_with_commitment(&Workload::registry_authority(), rpc.commitment())?
+        .value;
+    if registry.is_none() {
+        send(
+            rpc,
+            &[Workload::initialize_registry_authority(&payer)],
+            &[&payer],
+        )
+        .context("creating the credentialing registry failed")?;
+    }
+    for (n, provider) in providers.iter().enumerate() {
+        let name = format!("Load Test Provider {n}");
+        send(
+            rpc,
+            &[Workload::initialize_provider(provider, &name)],
+            &[provider],
+        )
+        .context("registering a pooled provider failed")?;
+        send(
+            rpc,
+            &[Workload::verify_provider(&payer, &provider.pubkey())],
+            &[&payer],
+        )
+        .context("verifying a pooled provider failed; is the payer the credentialing authority?")?;
+    }
+    Ok(Workload::new(payer, patients, providers))
+}
+
+fn main() -> Result<ExitCode> {
+    let options = parse_args()?;
+    let rpc =
+        RpcClient::new_with_commitment(options.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    println!(
+        "Registering {} patients and {} providers...",
+        options.patients, options.providers
+    );
+    let workload = setup(&rpc, &options)?;
+    println!(
+        "Payer {}; running {:.1} TPS for {}s with {} workers",
+        workload.payer().pubkey(),
+        options.tps,
+        options.duration.as_secs(),
+        options.workers
+    );
+
+    let config = RunConfig {
+        tps: options.tps,
+        duration: options.duration,
+        workers: options.workers,
+    };
+    let mut result = run(&rpc, &workload, options.mix.clone(), &config)?;
+    fetch_compute_units(&rpc, &mut result.samples, options.workers);
+
+    let report = Report::new(&result, options.tps);
+    report.print();
+    if let Some(path) = &options.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("Wrote {}", path.display());
+    }
+
+    if let Some(max) = options.max_failure_rate {
+        let worst = report
+            .ops
+            .iter()
+            .map(|op| op.failure_rate * 100.0)
+            .fold(0.0, f64::max);
+        if worst > max {
+            println!("Failure rate {worst:.1}% exceeds the {max:.1}% limit");
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
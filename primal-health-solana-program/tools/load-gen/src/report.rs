@@ -0,0 +1,149 @@
+//! Per-operation percentiles of a run.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::runner::RunResult;
+use crate::workload::Op;
+
+/// Most common failure messages kept per operation.
+const TOP_ERRORS: usize = 3;
+
+#[derive(Default, Serialize)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `values`.
+    fn of(mut values: Vec<u64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_unstable();
+        let rank = |pct: usize| values[(values.len() * pct).div_ceil(100).max(1) - 1];
+        Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct OpReport {
+    pub op: Op,
+    pub sent: u64,
+    pub failed: u64,
+    pub skipped: u64,
+    pub failure_rate: f64,
+    pub latency_ms: Percentiles,
+    pub queued_ms: Percentiles,
+    pub compute_units: Percentiles,
+    pub errors: Vec<(String, u64)>,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub target_tps: f64,
+    pub achieved_tps: f64,
+    pub elapsed_secs: f64,
+    pub ops: Vec<OpReport>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+impl Report {
+    pub fn new(result: &RunResult, target_tps: f64) -> Self {
+        let ops: Vec<OpReport> = Op::ALL
+            .into_iter()
+            .filter_map(|op| {
+                let samples: Vec<_> = result.samples.iter().filter(|s| s.op == op).collect();
+                let skipped = result.skipped.get(&op).copied().unwrap_or(0);
+                if samples.is_empty() && skipped == 0 {
+                    return None;
+                }
+                let mut errors: BTreeMap<&str, u64> = BTreeMap::new();
+                for sample in &samples {
+                    if let Err(err) = &sample.outcome {
+                        *errors.entry(err).or_default() += 1;
+                    }
+                }
+                let failed = errors.values().sum();
+                let mut errors: Vec<_> = errors
+                    .into_iter()
+                    .map(|(err, count)| (err.to_string(), count))
+                    .collect();
+                errors.sort_by(|a, b| b.1.cmp(&a.1));
+                errors.truncate(TOP_ERRORS);
+
+                let sent = samples.len() as u64;
+                Some(OpReport {
+                    op,
+                    sent,
+                    failed,
+                    skipped,
+                    failure_rate: if sent == 0 {
+                        0.0
+                    } else {
+                        failed as f64 / sent as f64
+                    },
+                    latency_ms: Percentiles::of(
+                        samples.iter().map(|s| millis(s.latency)).collect(),
+                    ),
+                    queued_ms: Percentiles::of(samples.iter().map(|s| millis(s.queued)).collect()),
+                    compute_units: Percentiles::of(
+                        samples.iter().filter_map(|s| s.compute_units).collect(),
+                    ),
+                    errors,
+                })
+            })
+            .collect();
+        let confirmed = result.samples.iter().filter(|s| s.outcome.is_ok()).count();
+        Self {
+            target_tps,
+            achieved_tps: confirmed as f64 / result.elapsed.as_secs_f64(),
+            elapsed_secs: result.elapsed.as_secs_f64(),
+            ops,
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "{:<10} {:>7} {:>7} {:>7}  {:>23}  {:>23}",
+            "op", "sent", "failed", "skipped", "latency ms p50/p90/p99", "CU p50/p90/p99"
+        );
+        for op in &self.ops {
+            println!(
+                "{:<10} {:>7} {:>6.1}% {:>7}  {:>23}  {:>23}",
+                op.op.name(),
+                op.sent,
+                op.failure_rate * 100.0,
+                op.skipped,
+                format!(
+                    "{}/{}/{}",
+                    op.latency_ms.p50, op.latency_ms.p90, op.latency_ms.p99
+                ),
+                format!(
+                    "{}/{}/{}",
+                    op.compute_units.p50, op.compute_units.p90, op.compute_units.p99
+                ),
+            );
+            for (err, count) in &op.errors {
+                println!("    {count} x {err}");
+            }
+        }
+        println!(
+            "{:.1} confirmed TPS against a target of {:.1} over {:.1}s",
+            self.achieved_tps, self.target_tps, self.elapsed_secs
+        );
+    }
+}
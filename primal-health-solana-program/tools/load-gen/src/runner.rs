@@ -0,0 +1,197 @@
+//! Sends scheduled operations from a pool of workers and times each one.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::hash::Hash;
+use anchor_client::solana_sdk::signature::{Signature, Signer};
+use anchor_client::solana_sdk::transaction::Transaction;
+use anyhow::Result;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+
+use crate::workload::{Mix, Op, Workload};
+
+/// Blockhashes stay valid for about a minute; refreshing well before that
+/// keeps workers off the RPC node's `getLatestBlockhash` path.
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(10);
+
+pub struct RunConfig {
+    pub tps: f64,
+    pub duration: Duration,
+    pub workers: usize,
+}
+
+pub struct Sample {
+    pub op: Op,
+    /// From sending the transaction until it was confirmed or failed.
+    pub latency: Duration,
+    /// How long the operation waited for a free worker after its slot in
+    /// the schedule.
+    pub queued: Duration,
+    pub outcome: Result<Signature, String>,
+    pub compute_units: Option<u64>,
+}
+
+pub struct RunResult {
+    pub samples: Vec<Sample>,
+    /// Payments scheduled before any claim was ready to pay.
+    pub skipped: BTreeMap<Op, u64>,
+    pub elapsed: Duration,
+}
+
+struct Blockhashes<'a> {
+    rpc: &'a RpcClient,
+    latest: Mutex<(Hash, Instant)>,
+}
+
+impl<'a> Blockhashes<'a> {
+    fn new(rpc: &'a RpcClient) -> Result<Self> {
+        let latest = Mutex::new((rpc.get_latest_blockhash()?, Instant::now()));
+        Ok(Self { rpc, latest })
+    }
+
+    fn get(&self) -> Result<Hash> {
+        let mut latest = self
+            .latest
+            .lock()
+            .expect("the blockhash cache is never poisoned");
+        if latest.1.elapsed() >= BLOCKHASH_REFRESH {
+            *latest = (self.rpc.get_latest_blockhash()?, Instant::now());
+        }
+        Ok(latest.0)
+    }
+}
+
+fn worker(
+    rpc: &RpcClient,
+    workload: &Workload,
+    blockhashes: &Blockhashes,
+    jobs: &Mutex<Receiver<(Op, Instant)>>,
+) -> (Vec<Sample>, BTreeMap<Op, u64>) {
+    let mut samples = Vec::new();
+    let mut skipped = BTreeMap::new();
+    loop {
+        let next = jobs.lock().expect("the job queue is never poisoned").recv();
+        let Ok((op, scheduled)) = next else {
+            return (samples, skipped);
+        };
+        let queued = scheduled.elapsed();
+        let Some(job) = workload.build(op) else {
+            *skipped.entry(op).or_default() += 1;
+            continue;
+        };
+
+        let started = Instant::now();
+        let outcome = blockhashes
+            .get()
+            .map_err(|err| err.to_string())
+            .and_then(|blockhash| {
+                let mut signers: Vec<&dyn Signer> = job
+                    .signers
+                    .iter()
+                    .map(|keypair| *keypair as &dyn Signer)
+                    .collect();
+                if let Some(wallet) = &job.new_wallet {
+                    signers.push(wallet);
+                }
+                let tx = Transaction::new_signed_with_payer(
+                    &job.instructions,
+                    Some(&job.signers[0].pubkey()),
+                    &signers,
+                    blockhash,
+                );
+                rpc.send_and_confirm_transaction(&tx)
+                    .map_err(|err| err.to_string())
+            });
+        if outcome.is_ok() {
+            workload.succeeded(job);
+        }
+        samples.push(Sample {
+            op,
+            latency: started.elapsed(),
+            queued,
+            outcome,
+            compute_units: None,
+        });
+    }
+}
+
+/// Schedules operations from `mix` at `config.tps` for `config.duration`
+/// and waits for every one to finish.
+pub fn run(
+    rpc: &RpcClient,
+    workload: &Workload,
+    mut mix: Mix,
+    config: &RunConfig,
+) -> Result<RunResult> {
+    let blockhashes = Blockhashes::new(rpc)?;
+    let (sender, receiver) = channel();
+    let jobs = Mutex::new(receiver);
+    let interval = Duration::from_secs_f64(1.0 / config.tps);
+    let total = (config.tps * config.duration.as_secs_f64()).round() as u32;
+
+    let started = Instant::now();
+    let (samples, skipped) = thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.workers)
+            .map(|_| scope.spawn(|| worker(rpc, workload, &blockhashes, &jobs)))
+            .collect();
+        for n in 0..total {
+            let due = started + interval * n;
+            sleep(due.saturating_duration_since(Instant::now()));
+            // Workers only stop once the schedule is done
+            let _ = sender.send((mix.next_op(), due));
+        }
+        drop(sender);
+
+        let mut samples = Vec::new();
+        let mut skipped: BTreeMap<Op, u64> = BTreeMap::new();
+        for handle in workers {
+            let (worker_samples, worker_skipped) = handle.join().expect("a worker panicked");
+            samples.extend(worker_samples);
+            for (op, count) in worker_skipped {
+                *skipped.entry(op).or_default() += count;
+            }
+        }
+        (samples, skipped)
+    });
+    let elapsed = started.elapsed();
+
+    Ok(RunResult {
+        samples,
+        skipped,
+        elapsed,
+    })
+}
+
+/// Looks up the compute units each confirmed transaction consumed. Done after
+/// the run so the lookups do not compete with the measured traffic.
+pub fn fetch_compute_units(rpc: &RpcClient, samples: &mut [Sample], workers: usize) {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let chunk = samples.len().div_ceil(workers.max(1)).max(1);
+    thread::scope(|scope| {
+        for chunk in samples.chunks_mut(chunk) {
+            scope.spawn(|| {
+                for sample in chunk {
+                    let Ok(signature) = &sample.outcome else {
+                        continue;
+                    };
+                    sample.compute_units = rpc
+                        .get_transaction_with_config(signature, config)
+                        .ok()
+                        .and_then(|tx| tx.transaction.meta)
+                        .and_then(|meta| meta.compute_units_consumed.map(|units| units));
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,502 @@
+//! The instructions the generator sends and the accounts they run against.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_client::anchor_lang::solana_program::instruction::Instruction;
+use anchor_client::anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signer};
+use anyhow::{bail, Context, Result};
+use primal_health_solana_program::{
+    accounts, instruction, settlement, ClaimDedup, ClaimStatus, DataCategory, LineDecision,
+    LineItemInput, RecordPointer, StorageKind, ID,
+};
+use serde::Serialize;
+use solana_system_interface::instruction as system_instruction;
+
+/// Lamports a registration hands the new patient for its rent and fees.
+const REGISTRATION_FUNDING: u64 = 10_000_000;
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+const CLAIM_LINES: &[(&str, u32, u64)] = &[("99213", 1, 20_000), ("80053", 1, 10_000)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    /// `initialize_patient` for a fresh wallet, funded in the same transaction.
+    Register,
+    /// `submit_health_data` by a pooled patient.
+    Submit,
+    /// `create_claim` by a pooled patient against a pooled provider.
+    Claim,
+    /// Adjudication, `verify_claim` and `process_payment` of a claim created
+    /// earlier in the run, in one transaction.
+    Pay,
+}
+
+impl Op {
+    pub const ALL: [Op; 4] = [Op::Register, Op::Submit, Op::Claim, Op::Pay];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Op::Register => "register",
+            Op::Submit => "submit",
+            Op::Claim => "claim",
+            Op::Pay => "pay",
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Relative weights of each operation, written `register=1,submit=4,...`.
+#[derive(Clone, Debug)]
+pub struct Mix {
+    weights: Vec<(Op, u32)>,
+    current: Vec<i64>,
+}
+
+impl Mix {
+    fn new(weights: Vec<(Op, u32)>) -> Result<Self> {
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            bail!("the mix needs at least one operation with a non-zero weight");
+        }
+        let current = vec![0; weights.len()];
+        Ok(Self { weights, current })
+    }
+
+    /// Picks the next operation with smooth weighted round-robin, so each
+    /// operation is spread evenly over the run instead of arriving in bursts.
+    pub fn next_op(&mut self) -> Op {
+        let total: i64 = self.weights.iter().map(|(_, w)| i64::from(*w)).sum();
+        for (current, (_, weight)) in self.current.iter_mut().zip(&self.weights) {
+            *current += i64::from(*weight);
+        }
+        let (index, _) = self
+            .current
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, current)| (**current, std::cmp::Reverse(*index)))
+            .expect("the mix is never empty");
+        self.current[index] -= total;
+        self.weights[index].0
+    }
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Self::new(vec![
+            (Op::Register, 1),
+            (Op::Submit, 4),
+            (Op::Claim, 2),
+            (Op::Pay, 1),
+        ])
+        .expect("the default mix has weights")
+    }
+}
+
+impl FromStr for Mix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let weights = s
+            .split(',')
+            .map(|part| {
+                let (name, weight) = part
+                    .split_once('=')
+                    .with_context(|| format!("expected op=weight, got {part}"))?;
+                let op = Op::ALL
+                    .into_iter()
+                    .find(|op| op.name() == name.trim())
+                    .with_context(|| format!("unknown operation {name}"))?;
+                let weight = weight
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("weight of {name} must be a whole number"))?;
+                Ok((op, weight))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(weights)
+    }
+}
+
+/// A transaction ready to sign: its instructions and the pooled signers,
+/// fee payer first.
+pub struct Job<'a> {
+    pub instructions: Vec<Instruction>,
+    pub signers: Vec<&'a Keypair>,
+    /// Wallet created for the job, such as a newly registered patient, which
+    /// also signs.
+    pub new_wallet: Option<Keypair>,
+    /// Claim the job creates, which becomes payable once the job succeeds.
+    created_claim: Option<PendingClaim>,
+}
+
+struct PendingClaim {
+    account: Pubkey,
+    patient: usize,
+    provider: usize,
+}
+
+/// Pooled patients and providers registered before the run, and the claims
+/// created during it that are waiting to be paid.
+pub struct Workload {
+    payer: Keypair,
+    patients: Vec<Keypair>,
+    providers: Vec<Keypair>,
+    /// Separates the records and claims of one run from those of earlier runs.
+    run_id: u32,
+    counter: AtomicU64,
+    pending: Mutex<VecDeque<PendingClaim>>,
+}
+
+fn ix(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// The validator runs on wall-clock time.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+impl Workload {
+    pub fn new(payer: Keypair, patients: Vec<Keypair>, providers: Vec<Keypair>) -> Self {
+        Self {
+            payer,
+            patients,
+            providers,
+            run_id: unix_now() as u32,
+            counter: AtomicU64::new(0),
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    pub fn initialize_patient(authority: &Keypair) -> Instruction {
+        ix(
+            accounts::InitializePatient {
+                patient_account: pda(&[b"patient", authority.pubkey().as_ref()]),
+                authority: authority.pubkey(),
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializePatient {
+                did: format!("did:sol:{}", authority.pubkey()),
+            },
+        )
+    }
+
+    pub fn initialize_provider(authority: &Keypair, name: &str) -> Instruction {
+        ix(
+            accounts::InitializeProvider {
+                provider_account: pda(&[b"provider", authority.pubkey().as_ref()]),
+                authority: authority.pubkey(),
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeProvider {
+                did: format!("did:sol:{}", authority.pubkey()),
+                name: name.to_string(),
+            },
+        )
+    }
+
+    pub fn config() -> Pubkey {
+        pda(&[b"config"])
+    }
+
+    /// Makes `payer` the config admin of a cluster without a config.
+    pub fn initialize_config(payer: &Keypair) -> Instruction {
+        ix(
+            accounts::InitializeConfig {
+                config: Self::config(),
+                protocol_treasury: pda(&[b"protocol_treasury"]),
+                admin: payer.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeConfig { fee_bps: 0 },
+        )
+    }
+
+    pub fn registry_authority() -> Pubkey {
+        pda(&[b"registry_authority"])
+    }
+
+    /// Makes `payer` the credentialing authority of a cluster without one.
+    pub fn initialize_registry_authority(payer: &Keypair) -> Instruction {
+        ix(
+            accounts::InitializeRegistryAuthority {
+                registry_authority: Self::registry_authority(),
+                payer: payer.pubkey(),
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeRegistryAuthority {
+                authority: payer.pubkey(),
+            },
+        )
+    }
+
+    /// Credentials a registered provider so it can verify claims.
+    pub fn verify_provider(authority: &Keypair, provider: &Pubkey) -> Instruction {
+        ix(
+            accounts::VerifyProvider {
+                registry_authority: Self::registry_authority(),
+                provider_account: pda(&[b"provider", provider.as_ref()]),
+                provider_credential: pda(&[b"provider_credential", provider.as_ref()]),
+                provider: *provider,
+                authority: authority.pubkey(),
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::VerifyProvider {},
+        )
+    }
+
+    fn next(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Builds the transaction for `op`, or `None` when a payment has no
+    /// created claim to pay yet.
+    pub fn build(&self, op: Op) -> Option<Job<'_>> {
+        match op {
+            Op::Register => Some(self.register()),
+            Op::Submit => Some(self.submit()),
+            Op::Claim => Some(self.claim()),
+            Op::Pay => self.pay(),
+        }
+    }
+
+    fn register(&self) -> Job<'_> {
+        let authority = Keypair::new();
+        let instructions = vec![
+            system_instruction::transfer(
+                &self.payer.pubkey(),
+                &authority.pubkey(),
+                REGISTRATION_FUNDING,
+            ),
+            Self::initialize_patient(&authority),
+        ];
+        Job {
+            instructions,
+            signers: vec![&self.payer],
+            new_wallet: Some(authority),
+            created_claim: None,
+        }
+    }
+
+    fn submit(&self) -> Job<'_> {
+        let n = self.next();
+        let patient = &self.patients[n as usize % self.patients.len()];
+        // Unique per run, so reruns against the same cluster do not collide
+        let mut data_hash = [0; 32];
+        data_hash[..4].copy_from_slice(&self.run_id.to_be_bytes());
+        data_hash[24..].copy_from_slice(&n.to_be_bytes());
+        let instruction = ix(
+            accounts::SubmitHealthData {
+                health_data_account: pda(&[b"health_data", data_hash.as_ref()]),
+                owner: patient.pubkey(),
+                author: patient.pubkey(),
+                grant: None,
+                access_policy: None,
+                author_provider: None,
+                delegate_account: None,
+                owner_key: None,
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::SubmitHealthData {
+                data_hash,
+                pointer: RecordPointer {
+                    storage: StorageKind::Ipfs,
+                    cid: "load-test-cid".to_string(),
+                    commitment: data_hash,
+                },
+                category: DataCategory::Vitals,
+            },
+        );
+        Job {
+            instructions: vec![instruction],
+            signers: vec![patient],
+            new_wallet: None,
+            created_claim: None,
+        }
+    }
+
+    fn claim(&self) -> Job<'_> {
+        let n = self.next();
+        let pairs = (self.patients.len() * self.providers.len()) as u64;
+        let patient_index = n as usize % self.patients.len();
+        let provider_index = (n as usize / self.patients.len()) % self.providers.len();
+        let (patient, provider) = (
+            &self.patients[patient_index],
+            &self.providers[provider_index],
+        );
+
+        let claim_id = format!("load-{:08x}-{n}", self.run_id);
+        let line_items: Vec<LineItemInput> = CLAIM_LINES
+            .iter()
+            .map(|(code, units, unit_price)| LineItemInput {
+                code: code.to_string(),
+                units: *units,
+                unit_price: *unit_price,
+                modifier: String::new(),
+            })
+            .collect();
+        // Each patient and provider pair bills one service day per claim,
+        // going back from yesterday, so no claim is a duplicate of another
+        let service_date = unix_now() - (n / pairs) as i64 * SECS_PER_DAY - SECS_PER_DAY;
+        let fingerprint = ClaimDedup::fingerprint(
+            &patient.pubkey(),
+            &provider.pubkey(),
+            &line_items,
+            service_date,
+        );
+        let account = pda(&[b"claim", claim_id.as_bytes()]);
+        let instruction = ix(
+            accounts::CreateClaim {
+                claim_account: account,
+                patient: patient.pubkey(),
+                filer: patient.pubkey(),
+                guardian_link: None,
+                delegate_account: None,
+                provider: provider.pubkey(),
+                insurer: None,
+                mint: None,
+                provider_stats: pda(&[b"provider_stats", provider.pubkey().as_ref()]),
+                claim_dedup: pda(&[b"claim_dedup", &fingerprint]),
+                claim_grant: None,
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::CreateClaim {
+                claim_id,
+                line_items,
+                attachments: Vec::new(),
+                service_start: service_date,
+                service_end: service_date,
+                fingerprint,
+            },
+        );
+        Job {
+            instructions: vec![instruction],
+            signers: vec![patient],
+            new_wallet: None,
+            created_claim: Some(PendingClaim {
+                account,
+                patient: patient_index,
+                provider: provider_index,
+            }),
+        }
+    }
+
+    /// Records a job that landed, queueing the claim it created for payment.
+    pub fn succeeded(&self, job: Job<'_>) {
+        if let Some(claim) = job.created_claim {
+            self.pending
+                .lock()
+                .expect("the pending queue is never poisoned")
+                .push_back(claim);
+        }
+    }
+
+    fn pay(&self) -> Option<Job<'_>> {
+        let claim = self
+            .pending
+            .lock()
+            .expect("the pending queue is never poisoned")
+            .pop_front()?;
+        let (patient, provider) = (
+            &self.patients[claim.patient],
+            &self.providers[claim.provider],
+        );
+        let review_accounts = || accounts::ReviewClaim {
+            claim_account: claim.account,
+            provider: provider.pubkey(),
+            config: Self::config(),
+        };
+
+        let mut instructions = vec![ix(review_accounts(), instruction::AcceptClaim {})];
+        instructions.extend((0..CLAIM_LINES.len()).map(|index| {
+            ix(
+                review_accounts(),
+                instruction::AdjudicateLine {
+                    index: index as u8,
+                    decision: LineDecision::Approve,
+                    reason_code: 0,
+                },
+            )
+        }));
+        instructions.push(ix(
+            accounts::VerifyClaim {
+                claim_account: claim.account,
+                provider: provider.pubkey(),
+                provider_credential: pda(&[b"provider_credential", provider.pubkey().as_ref()]),
+                plan_benefits: None,
+                out_of_pocket: None,
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::VerifyClaim {
+                status: ClaimStatus::Verified,
+            },
+        ));
+        let period = settlement::period_of(unix_now());
+        instructions.push(ix(
+            accounts::ProcessPayment {
+                claim_account: claim.account,
+                provider: provider.pubkey(),
+                patient: patient.pubkey(),
+                settlement: pda(&[
+                    b"settlement",
+                    provider.pubkey().as_ref(),
+                    patient.pubkey().as_ref(),
+                    &period.to_le_bytes(),
+                ]),
+                tenant: None,
+                treasury: None,
+                payment_schedule: None,
+                audit_trail: None,
+                audit_entry: None,
+                protocol_treasury: pda(&[b"protocol_treasury"]),
+                clawback_config: None,
+                payment_hold: None,
+                facilitator: None,
+                facilitator_signer: None,
+                fee_rebates: None,
+                provider_volume: None,
+                config: Self::config(),
+                system_program: system_program::ID,
+            },
+            instruction::ProcessPayment { period },
+        ));
+        Some(Job {
+            instructions,
+            signers: vec![provider],
+            new_wallet: None,
+            created_claim: None,
+        })
+    }
+}
@@ -0,0 +1,200 @@
+//! Finds every program account that belongs to a patient.
+
+use std::collections::BTreeMap;
+
+use anchor_client::anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use primal_health_client::archive::{Export, ExportedAccount};
+use primal_health_solana_program::{
+    AccessGrant, AccessPolicy, ClaimAccount, ClaimNotesPage, ConsentAccount, ConsentLedger,
+    EncryptionKey, HealthDataAccount, HealthDataAccountV2, HealthDataBatchAccount, HealthDataIndex,
+    KeyEnvelope, PatientAccount, Subscription, ID,
+};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+
+/// Offset of the first field, right after the 8-byte discriminator.
+const FIRST_FIELD: usize = 8;
+/// `getMultipleAccounts` accepts at most this many addresses per call.
+const MULTIPLE_ACCOUNTS_LIMIT: usize = 100;
+
+/// Accounts read from the chain, keyed by address.
+pub type Snapshot = BTreeMap<Pubkey, (String, Vec<u8>)>;
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// Program accounts of type `T` whose data starts with `filters` at the
+/// given offsets.
+fn program_accounts<T: Discriminator>(
+    rpc: &RpcClient,
+    filters: &[(usize, &[u8])],
+) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let mut memcmp = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        T::DISCRIMINATOR,
+    ))];
+    memcmp.extend(
+        filters.iter().map(|(offset, bytes)| {
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(*offset, bytes))
+        }),
+    );
+    let config = RpcProgramAccountsConfig {
+        filters: Some(memcmp),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc
+        .get_program_accounts_with_config(&ID, config)
+        .context("getProgramAccounts failed")?;
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect())
+}
+
+/// Current data of each address, or `None` for closed accounts.
+fn fetch(rpc: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>> {
+    let mut data = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_LIMIT) {
+        let accounts = rpc
+            .get_multiple_accounts(chunk)
+            .context("getMultipleAccounts failed")?;
+        data.extend(
+            accounts
+                .into_iter()
+                .map(|account| account.filter(|a| a.owner == ID).map(|a| a.data)),
+        );
+    }
+    Ok(data)
+}
+
+/// Everything owned by, granted by or filed for `patient`: the patient
+/// account, access policy, consent ledger and encryption key; health records
+/// and batches; grants with their key envelopes; consents; subscriptions; and
+/// claims with their notes.
+pub fn collect(rpc: &RpcClient, patient: &Pubkey) -> Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    let owned: &[(usize, &[u8])] = &[(FIRST_FIELD, patient.as_ref())];
+
+    let singletons = [
+        (
+            "PatientAccount",
+            pda(&[b"patient", patient.as_ref()]),
+            PatientAccount::DISCRIMINATOR,
+        ),
+        (
+            "AccessPolicy",
+            pda(&[b"access_policy", patient.as_ref()]),
+            AccessPolicy::DISCRIMINATOR,
+        ),
+        (
+            "ConsentLedger",
+            pda(&[b"consent_ledger", patient.as_ref()]),
+            ConsentLedger::DISCRIMINATOR,
+        ),
+        (
+            "EncryptionKey",
+            pda(&[b"encryption_key", patient.as_ref()]),
+            EncryptionKey::DISCRIMINATOR,
+        ),
+        (
+            "HealthDataIndex",
+            pda(&[b"health_data_index", patient.as_ref()]),
+            HealthDataIndex::DISCRIMINATOR,
+        ),
+    ];
+    let addresses: Vec<Pubkey> = singletons.iter().map(|(_, address, _)| *address).collect();
+    for ((kind, address, discriminator), data) in singletons.iter().zip(fetch(rpc, &addresses)?) {
+        if let Some(data) = data.filter(|data| data.starts_with(discriminator)) {
+            snapshot.insert(*address, (kind.to_string(), data));
+        }
+    }
+
+    for (address, data) in program_accounts::<HealthDataAccount>(rpc, owned)? {
+        snapshot.insert(address, ("HealthDataAccount".into(), data));
+    }
+    for (address, data) in program_accounts::<HealthDataAccountV2>(rpc, owned)? {
+        snapshot.insert(address, ("HealthDataAccountV2".into(), data));
+    }
+    for (address, data) in program_accounts::<HealthDataBatchAccount>(rpc, owned)? {
+        snapshot.insert(address, ("HealthDataBatchAccount".into(), data));
+    }
+    for (address, data) in program_accounts::<ConsentAccount>(rpc, owned)? {
+        snapshot.insert(address, ("ConsentAccount".into(), data));
+    }
+    for (address, data) in program_accounts::<Subscription>(rpc, owned)? {
+        snapshot.insert(address, ("Subscription".into(), data));
+    }
+
+    let grants = program_accounts::<AccessGrant>(rpc, owned)?;
+    let envelopes: Vec<Pubkey> = grants
+        .iter()
+        .map(|(grant, _)| pda(&[b"key_envelope", grant.as_ref()]))
+        .collect();
+    for (address, data) in envelopes.iter().zip(fetch(rpc, &envelopes)?) {
+        if let Some(data) = data.filter(|data| data.starts_with(KeyEnvelope::DISCRIMINATOR)) {
+            snapshot.insert(*address, ("KeyEnvelope".into(), data));
+        }
+    }
+    for (address, data) in grants {
+        snapshot.insert(address, ("AccessGrant".into(), data));
+    }
+
+    // `claim_id` is variable-length and comes before `patient`, so claims
+    // cannot be filtered by patient on the RPC side
+    for (address, data) in program_accounts::<ClaimAccount>(rpc, &[])? {
+        let claim = ClaimAccount::try_deserialize(&mut data.as_slice())
+            .with_context(|| format!("claim {address} does not decode"))?;
+        if claim.patient != *patient {
+            continue;
+        }
+        let by_claim: &[(usize, &[u8])] = &[(FIRST_FIELD, address.as_ref())];
+        for (page, data) in program_accounts::<ClaimNotesPage>(rpc, by_claim)? {
+            snapshot.insert(page, ("ClaimNotesPage".into(), data));
+        }
+        snapshot.insert(address, ("ClaimAccount".into(), data));
+    }
+    Ok(snapshot)
+}
+
+pub fn to_export(patient: &Pubkey, slot: u64, snapshot: &Snapshot) -> Export {
+    Export {
+        patient: patient.to_string(),
+        slot,
+        accounts: snapshot
+            .iter()
+            .map(|(address, (kind, data))| ExportedAccount {
+                kind: kind.clone(),
+                address: address.to_string(),
+                data: STANDARD.encode(data),
+            })
+            .collect(),
+    }
+}
+
+pub fn from_export(export: &Export) -> Result<Snapshot> {
+    export
+        .accounts
+        .iter()
+        .map(|account| {
+            let address: Pubkey = account
+                .address
+                .parse()
+                .with_context(|| format!("bad address {}", account.address))?;
+            let data = STANDARD
+                .decode(&account.data)
+                .with_context(|| format!("bad data for {address}"))?;
+            Ok((address, (account.kind.clone(), data)))
+        })
+        .collect()
+}
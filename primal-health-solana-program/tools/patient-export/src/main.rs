@@ -0,0 +1,221 @@
+//! Exports every account belonging to a patient into a signed, encrypted
+//! archive, and checks an archive against current chain state.
+//!
+//! Usage:
+//! - `patient-export export --keypair <path> [--patient <pubkey>] [--recipient <hex>] [--rpc <url>] [--out <path>]`
+//!   reads the patient's accounts (the keypair's own by default), encrypts them
+//!   to `--recipient` (the patient's registered encryption key by default) and
+//!   signs the archive with the keypair.
+//! - `patient-export verify <archive> --secret <path> [--rpc <url>]` checks the
+//!   signature, decrypts with the X25519 secret key stored as hex in `--secret`
+//!   and compares each exported account with the chain, exiting non-zero when
+//!   anything was changed, closed or added since the export.
+
+mod collect;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anchor_client::anchor_lang::AccountDeserialize;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Signature, Signer};
+use anyhow::{anyhow, bail, Context, Result};
+use primal_health_client::archive::{open_archive, seal_archive, SealedArchive};
+use primal_health_solana_program::{EncryptionKey, ID};
+use rand_core::{OsRng, UnwrapErr};
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::collect::{collect, from_export, to_export};
+
+const DEFAULT_RPC: &str = "http://127.0.0.1:8899";
+
+enum Command {
+    Export {
+        keypair: PathBuf,
+        patient: Option<Pubkey>,
+        recipient: Option<[u8; 32]>,
+        out: Option<PathBuf>,
+    },
+    Verify {
+        archive: PathBuf,
+        secret: PathBuf,
+    },
+}
+
+fn parse_key(hex: &str) -> Result<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        bail!("expected 32 bytes of hex");
+    }
+    let mut key = [0; 32];
+    for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair)?, 16)?;
+    }
+    Ok(key)
+}
+
+fn parse_args() -> Result<(Command, String)> {
+    let mut args = std::env::args().skip(1);
+    let subcommand = args.next().context("expected `export` or `verify`")?;
+    let mut rpc = DEFAULT_RPC.to_string();
+    let (mut keypair, mut patient, mut recipient, mut out) = (None, None, None, None);
+    let (mut archive, mut secret) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rpc" => rpc = args.next().context("--rpc needs a url")?,
+            "--keypair" => keypair = Some(args.next().context("--keypair needs a path")?.into()),
+            "--patient" => {
+                patient = Some(
+                    args.next()
+                        .context("--patient needs a pubkey")?
+                        .parse()
+                        .context("--patient must be a base58 pubkey")?,
+                )
+            }
+            "--recipient" => {
+                recipient = Some(
+                    parse_key(&args.next().context("--recipient needs a key")?)
+                        .context("--recipient must be an X25519 public key")?,
+                )
+            }
+            "--out" => out = Some(args.next().context("--out needs a path")?.into()),
+            "--secret" => secret = Some(args.next().context("--secret needs a path")?.into()),
+            other if subcommand == "verify" && archive.is_none() && !other.starts_with("--") => {
+                archive = Some(other.into())
+            }
+            other => bail!("unknown argument {other}"),
+        }
+    }
+    let command = match subcommand.as_str() {
+        "export" => Command::Export {
+            keypair: keypair.context("export needs --keypair")?,
+            patient,
+            recipient,
+            out,
+        },
+        "verify" => Command::Verify {
+            archive: archive.context("verify needs an archive path")?,
+            secret: secret.context("verify needs --secret")?,
+        },
+        other => bail!("unknown command {other}; expected `export` or `verify`"),
+    };
+    Ok((command, rpc))
+}
+
+fn registered_key(rpc: &RpcClient, patient: &Pubkey) -> Result<[u8; 32]> {
+    let address = Pubkey::find_program_address(&[b"encryption_key", patient.as_ref()], &ID).0;
+    let account = rpc
+        .get_account(&address)
+        .with_context(|| format!("{patient} has no registered encryption key; pass --recipient"))?;
+    Ok(EncryptionKey::try_deserialize(&mut account.data.as_slice())?.x25519_key)
+}
+
+fn export(
+    rpc: &RpcClient,
+    keypair: PathBuf,
+    patient: Option<Pubkey>,
+    recipient: Option<[u8; 32]>,
+    out: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let signer = read_keypair_file(&keypair)
+        .map_err(|err| anyhow!("cannot read {}: {err}", keypair.display()))?;
+    let patient = patient.unwrap_or_else(|| signer.pubkey());
+    let recipient = match recipient {
+        Some(recipient) => recipient,
+        None => registered_key(rpc, &patient)?,
+    };
+
+    let slot = rpc.get_slot()?;
+    let snapshot = collect(rpc, &patient)?;
+    let mut archive = seal_archive(
+        &to_export(&patient, slot, &snapshot),
+        &recipient,
+        &mut UnwrapErr(OsRng),
+    )?;
+    archive.signer = signer.pubkey().to_string();
+    archive.signature = signer.sign_message(&archive.signing_message()).to_string();
+
+    let out = out.unwrap_or_else(|| PathBuf::from(format!("{patient}.archive.json")));
+    std::fs::write(&out, serde_json::to_string_pretty(&archive)?)?;
+    println!(
+        "Exported {} accounts for {patient} at slot {slot} to {}",
+        snapshot.len(),
+        out.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(rpc: &RpcClient, archive: PathBuf, secret: PathBuf) -> Result<ExitCode> {
+    let archive: SealedArchive = serde_json::from_slice(
+        &std::fs::read(&archive).with_context(|| format!("cannot read {}", archive.display()))?,
+    )?;
+    let signer: Pubkey = archive
+        .signer
+        .parse()
+        .context("archive signer is not a pubkey")?;
+    let signature: Signature = archive
+        .signature
+        .parse()
+        .context("archive signature is malformed")?;
+    if !signature.verify(signer.as_ref(), &archive.signing_message()) {
+        bail!("archive signature does not verify for {signer}");
+    }
+
+    let secret = parse_key(&std::fs::read_to_string(&secret)?)
+        .context("--secret must hold an X25519 secret key")?;
+    let export = open_archive(&archive, &secret)?;
+    let patient: Pubkey = export
+        .patient
+        .parse()
+        .context("archive patient is not a pubkey")?;
+    let exported = from_export(&export)?;
+    let current = collect(rpc, &patient)?;
+    println!(
+        "Archive of {} accounts for {patient} at slot {}, signed by {signer}",
+        exported.len(),
+        export.slot
+    );
+
+    let mut differences = 0;
+    for (address, (kind, data)) in &exported {
+        match current.get(address) {
+            Some((_, now)) if now == data => {}
+            Some(_) => {
+                differences += 1;
+                println!("changed {kind} {address}");
+            }
+            None => {
+                differences += 1;
+                println!("closed  {kind} {address}");
+            }
+        }
+    }
+    for (address, (kind, _)) in &current {
+        if !exported.contains_key(address) {
+            differences += 1;
+            println!("new     {kind} {address}");
+        }
+    }
+
+    if differences == 0 {
+        println!("Archive matches chain state");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("{differences} accounts differ from chain state");
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn main() -> Result<ExitCode> {
+    let (command, rpc) = parse_args()?;
+    let rpc = RpcClient::new(rpc);
+    match command {
+        Command::Export {
+            keypair,
+            patient,
+            recipient,
+            out,
+        } => export(&rpc, keypair, patient, recipient, out),
+        Command::Verify { archive, secret } => verify(&rpc, archive, secret),
+    }
+}